@@ -0,0 +1,112 @@
+use std::io;
+use std::time::{Duration, Instant};
+
+use threadpool::ThreadPool;
+
+use dmmt_jpeg_core::encode_rgb8_default;
+use dmmt_jpeg_core::image::writer::jpeg::JpegTransformationOptions;
+use dmmt_jpeg_core::image::{writer::jpeg::JpegImageWriter, Image, ImageWriter};
+
+/// Standardized workload, which encodes the same synthetic image repeatedly and reports the
+/// throughput of the fastest round.
+pub struct Benchmark {
+    width: u16,
+    height: u16,
+    samples: Vec<u8>,
+    image: Image<f32>,
+    megapixels: f64,
+    rounds: usize,
+}
+
+impl Benchmark {
+    pub fn new(width: u16, height: u16, rounds: usize) -> dmmt_jpeg_core::Result<Self> {
+        let samples = Self::create_synthetic_samples(width, height);
+        let image = Image::from_rgb8(width, height, &samples)?;
+        Ok(Self {
+            width,
+            height,
+            samples,
+            image,
+            megapixels: width as f64 * height as f64 / 1_000_000_f64,
+            rounds: rounds.max(1),
+        })
+    }
+
+    /// Creates smooth gradients overlaid with a fine pattern, so the entropy coding has to deal
+    /// with both flat areas and high frequencies like in photographs.
+    fn create_synthetic_samples(width: u16, height: u16) -> Vec<u8> {
+        let (width, height) = (width as usize, height as usize);
+        (0..height)
+            .flat_map(|y| {
+                (0..width).flat_map(move |x| {
+                    let pattern = ((x ^ y) % 32) as u8;
+                    [
+                        (x * 223 / width) as u8 + pattern,
+                        (y * 223 / height) as u8 + pattern,
+                        ((x + y) * 111 / (width + height)) as u8 + pattern,
+                    ]
+                })
+            })
+            .collect()
+    }
+
+    /// Encodes the image `rounds` times and returns the throughput of the fastest round in
+    /// megapixels per second.
+    pub fn measure_throughput(
+        &self,
+        options: &JpegTransformationOptions,
+        threadpool: &ThreadPool,
+    ) -> dmmt_jpeg_core::Result<f64> {
+        self.measure_fastest_round(|| {
+            let mut image_writer =
+                JpegImageWriter::new(io::sink(), &self.image, options, threadpool);
+            image_writer.write_image()
+        })
+    }
+
+    /// Measures the throughput of [`encode_rgb8_default`] like [`Self::measure_throughput`],
+    /// including the conversion of the samples and the creation of the threadpool.
+    pub fn measure_default_throughput(&self) -> dmmt_jpeg_core::Result<f64> {
+        self.measure_fastest_round(|| {
+            encode_rgb8_default(self.width, self.height, &self.samples).map(drop)
+        })
+    }
+
+    fn measure_fastest_round(
+        &self,
+        mut encode: impl FnMut() -> dmmt_jpeg_core::Result<()>,
+    ) -> dmmt_jpeg_core::Result<f64> {
+        let mut fastest_round = Duration::MAX;
+        for _ in 0..self.rounds {
+            let start = Instant::now();
+            encode()?;
+            fastest_round = fastest_round.min(start.elapsed());
+        }
+        Ok(self.megapixels / fastest_round.as_secs_f64().max(f64::EPSILON))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use threadpool::ThreadPool;
+
+    use super::Benchmark;
+    use dmmt_jpeg_core::image::writer::jpeg::JpegTransformationOptions;
+
+    #[test]
+    fn measure_positive_throughput() {
+        let benchmark = Benchmark::new(24, 16, 1).unwrap();
+        let threadpool = ThreadPool::new(2);
+        let throughput = benchmark
+            .measure_throughput(&JpegTransformationOptions::default(), &threadpool)
+            .unwrap();
+        assert!(throughput > 0.0, "throughput was {}", throughput);
+    }
+
+    #[test]
+    fn measure_positive_default_throughput() {
+        let benchmark = Benchmark::new(24, 16, 1).unwrap();
+        let throughput = benchmark.measure_default_throughput().unwrap();
+        assert!(throughput > 0.0, "throughput was {}", throughput);
+    }
+}
@@ -1,4 +1,4 @@
-use dmmt_jpeg_encoder::binary_stream::BitWriter;
+use dmmt_jpeg_core::binary_stream::BitWriter;
 
 fn main() {
     let mut my_output: Vec<u8> = vec![];
@@ -6,13 +6,18 @@ use std::time::{Duration, Instant};
 
 use clap::builder::PossibleValue;
 use clap::{arg, value_parser, Arg, ArgMatches, Command, ValueEnum};
-use dmmt_jpeg_encoder::cosine_transform::{
-    arai::AraiDiscrete8x8CosineTransformer, separated::SeparatedDiscrete8x8CosineTransformer,
-    simple::SimpleDiscrete8x8CosineTransformer, Discrete8x8CosineTransformer,
+#[cfg(target_arch = "aarch64")]
+use dmmt_jpeg_core::cosine_transform::simd_neon::SimdNeonDiscrete8x8CosineTransformer;
+#[cfg(target_arch = "x86_64")]
+use dmmt_jpeg_core::cosine_transform::simd_x86::SimdX86Discrete8x8CosineTransformer;
+use dmmt_jpeg_core::cosine_transform::{
+    arai::AraiDiscrete8x8CosineTransformer, integer::IntegerDiscrete8x8CosineTransformer,
+    separated::SeparatedDiscrete8x8CosineTransformer, simple::SimpleDiscrete8x8CosineTransformer,
+    Discrete8x8CosineTransformer,
 };
-use dmmt_jpeg_encoder::image::subsampling::Subsampler;
-use dmmt_jpeg_encoder::image::subsampling::{SubsamplingConfig, SubsamplingMethod};
-use dmmt_jpeg_encoder::image::ColorChannel;
+use dmmt_jpeg_core::image::subsampling::Subsampler;
+use dmmt_jpeg_core::image::subsampling::{ChromaSiting, SubsamplingConfig, SubsamplingMethod};
+use dmmt_jpeg_core::image::ColorChannel;
 use threadpool::ThreadPool;
 
 const IMAGE_WIDTH: u16 = 3840;
@@ -24,11 +29,19 @@ enum DCTAlgorithm {
     Simple,
     Separated,
     Arai,
+    Simd,
+    Integer,
 }
 
 impl ValueEnum for DCTAlgorithm {
     fn value_variants<'a>() -> &'a [Self] {
-        &[Self::Simple, Self::Separated, Self::Arai]
+        &[
+            Self::Simple,
+            Self::Separated,
+            Self::Arai,
+            Self::Simd,
+            Self::Integer,
+        ]
     }
 
     fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
@@ -36,6 +49,8 @@ impl ValueEnum for DCTAlgorithm {
             Self::Simple => Some(PossibleValue::new("Simple")),
             Self::Separated => Some(PossibleValue::new("Separated")),
             Self::Arai => Some(PossibleValue::new("Arai")),
+            Self::Simd => Some(PossibleValue::new("SIMD")),
+            Self::Integer => Some(PossibleValue::new("Integer")),
         }
     }
 }
@@ -164,6 +179,7 @@ fn subsample(color_channel: &ColorChannel<f32>) -> Vec<f32> {
         vertical_rate: 1,
         horizontal_rate: 1,
         method: SubsamplingMethod::Skip,
+        siting: ChromaSiting::Centered,
     };
     let subsampler = Subsampler::new(color_channel, &subsampling_config);
     subsampler.subsample_to_square_structure(8)
@@ -186,11 +202,7 @@ fn transform_channel(
     threadpool: &ThreadPool,
 ) -> Duration {
     let start = Instant::now();
-    unsafe {
-        let channel_ptr = &raw mut channel[0];
-        transformer.transform_on_threadpool(threadpool, channel_ptr, channel.len(), 700);
-    }
-    threadpool.join();
+    transformer.transform_on_threadpool(threadpool, channel, 700);
     start.elapsed()
 }
 
@@ -269,6 +281,47 @@ fn run_arai_algorithm_measurement(channel: &[f32], rounds: usize, threadpool: &T
     print_statistics(&measurement);
 }
 
+fn run_integer_algorithm_measurement(channel: &[f32], rounds: usize, threadpool: &ThreadPool) {
+    println!("Integer Algorithm");
+    let measurement = measure_image_transformation_n_times(
+        channel,
+        rounds,
+        &IntegerDiscrete8x8CosineTransformer,
+        threadpool,
+    );
+    print_statistics(&measurement);
+}
+
+#[cfg(target_arch = "x86_64")]
+fn run_simd_algorithm_measurement(channel: &[f32], rounds: usize, threadpool: &ThreadPool) {
+    println!("SIMD Algorithm");
+    let Some(transformer) = SimdX86Discrete8x8CosineTransformer::detect() else {
+        println!("The CPU does not support AVX");
+        return;
+    };
+    let measurement =
+        measure_image_transformation_n_times(channel, rounds, transformer, threadpool);
+    print_statistics(&measurement);
+}
+
+#[cfg(target_arch = "aarch64")]
+fn run_simd_algorithm_measurement(channel: &[f32], rounds: usize, threadpool: &ThreadPool) {
+    println!("SIMD Algorithm");
+    let Some(transformer) = SimdNeonDiscrete8x8CosineTransformer::detect() else {
+        println!("The CPU does not support NEON");
+        return;
+    };
+    let measurement =
+        measure_image_transformation_n_times(channel, rounds, transformer, threadpool);
+    print_statistics(&measurement);
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn run_simd_algorithm_measurement(_channel: &[f32], _rounds: usize, _threadpool: &ThreadPool) {
+    println!("SIMD Algorithm");
+    println!("No SIMD implementation for this architecture");
+}
+
 fn get_number_of_threads() -> Result<usize> {
     Ok(thread::available_parallelism()?.get())
 }
@@ -295,5 +348,11 @@ fn main() {
         DCTAlgorithm::Arai => {
             run_arai_algorithm_measurement(&channel, number_of_rounds, &threadpool);
         }
+        DCTAlgorithm::Simd => {
+            run_simd_algorithm_measurement(&channel, number_of_rounds, &threadpool);
+        }
+        DCTAlgorithm::Integer => {
+            run_integer_algorithm_measurement(&channel, number_of_rounds, &threadpool);
+        }
     }
 }
@@ -1,11 +1,11 @@
 use std::io::Write;
 
-use dmmt_jpeg_encoder::binary_stream::BitWriter;
-use dmmt_jpeg_encoder::huffman::code::HuffmanCodeGenerator;
-use dmmt_jpeg_encoder::huffman::encoder::{HuffmanTranslator, HuffmanWriter};
-use dmmt_jpeg_encoder::huffman::length_limited::LengthLimitedHuffmanCodeGenerator;
-use dmmt_jpeg_encoder::huffman::tree::HuffmanTree;
-use dmmt_jpeg_encoder::huffman::SymbolFrequency;
+use dmmt_jpeg_core::binary_stream::{BitReader, BitWriter};
+use dmmt_jpeg_core::huffman::code::HuffmanCodeGenerator;
+use dmmt_jpeg_core::huffman::encoder::{HuffmanTranslator, HuffmanWriter};
+use dmmt_jpeg_core::huffman::length_limited::LengthLimitedHuffmanCodeGenerator;
+use dmmt_jpeg_core::huffman::tree::HuffmanTree;
+use dmmt_jpeg_core::huffman::SymbolFrequency;
 
 fn main() {
     let syms_and_freqs = [
@@ -19,9 +19,6 @@ fn main() {
     ];
 
     let mut generator = LengthLimitedHuffmanCodeGenerator::new(4);
-    let mut tree = HuffmanTree::new(&syms_and_freqs, &mut generator);
-    tree.replace_onestar();
-
     let syms_and_freqs = syms_and_freqs.map(SymbolFrequency::from);
 
     let mut output: Vec<u8> = Vec::new();
@@ -29,6 +26,7 @@ fn main() {
     let mut code_lengths = generator.generate_with_symbols(&syms_and_freqs);
     code_lengths[0].length += 1;
     let translator = HuffmanTranslator::from(&code_lengths);
+    let tree = HuffmanTree::from_code_lengths(&code_lengths);
     let mut encoder = HuffmanWriter::new(&translator, &mut writer);
 
     /* an example sequence to encode that roughly matches the relative frequencies at the beginning */
@@ -40,9 +38,12 @@ fn main() {
 
     /* have the tree decode the sequence */
     let mut decoded: Vec<u8> = Vec::new();
-    let _ = tree.decode_sequence(&mut output.as_slice(), &mut decoded);
+    let result = tree.decode_sequence(&mut BitReader::new(output.as_slice(), false), &mut decoded);
 
     println!("encoded sequence \n {:?}", output);
     println!("original sequence \n {:?}", encoding_sequence);
     println!("decoded sequence \n {:?}", decoded);
+    if let Err(error) = result {
+        println!("decoding failed \n {:?}", error);
+    }
 }
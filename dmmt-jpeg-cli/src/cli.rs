@@ -0,0 +1,2412 @@
+use crate::{Arguments, BenchmarkArguments, Task};
+use clap::{
+    arg, builder::PossibleValue, crate_authors, crate_description, crate_name, crate_version,
+    parser::ValueSource, value_parser, Arg, ArgAction, ArgMatches, Command, ValueEnum,
+};
+use dmmt_jpeg_core::cosine_transform::DCTAlgorithm;
+use dmmt_jpeg_core::image::preprocess::{Crop, Resize, Rotation};
+use dmmt_jpeg_core::image::reader::InputLimits;
+use dmmt_jpeg_core::image::subsampling::{
+    ChromaSiting, ChromaSubsamplingPreset, SubsamplingMethod,
+};
+use dmmt_jpeg_core::image::writer::jpeg::density::DensityUnit;
+use dmmt_jpeg_core::image::writer::jpeg::exif::GpsPosition;
+use dmmt_jpeg_core::image::writer::jpeg::thumbnail::ThumbnailSize;
+use dmmt_jpeg_core::image::writer::jpeg::xmp::XmpPacket;
+use dmmt_jpeg_core::image::writer::jpeg::{
+    DeadZone, HuffmanTableMode, QuantizationTableMix, QuantizationTablePreset, Tune,
+    MAX_COMMENT_LENGTH, MAX_HUFFMAN_CODE_LENGTH, MIN_HUFFMAN_CODE_LENGTH_LIMIT,
+};
+use dmmt_jpeg_core::{ColorMatrix, ColorRange, Error, RGBColorFormat};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::{io, thread};
+
+const BENCH_SUBCOMMAND_NAME: &str = "bench";
+
+pub struct CLIParser {
+    command: Command,
+}
+
+impl CLIParser {
+    pub fn new() -> Self {
+        let command = Self::create_base_command();
+        let command = Self::register_arguments(command);
+        CLIParser { command }
+    }
+
+    pub fn parse<I, T>(&mut self, itr: I) -> Task
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let matches = self
+            .command
+            .try_get_matches_from_mut(itr)
+            .unwrap_or_else(|e| e.exit());
+        match matches.subcommand() {
+            Some((BENCH_SUBCOMMAND_NAME, bench_matches)) => {
+                Task::Benchmark(Self::extract_bench_arguments(bench_matches))
+            }
+            _ => Task::Convert(Box::new(Self::extract_arguments(&matches))),
+        }
+    }
+
+    fn register_arguments(command: Command) -> Command {
+        let command = Self::register_input_file_argument(command);
+        let command = Self::register_mmap_argument(command);
+        let command = Self::register_output_file_argument(command);
+        let command = Self::register_bits_per_channel_argument(command);
+        let command = Self::register_tune_argument(command);
+        let command = Self::register_chroma_subsampling_preset_argument(command);
+        let command = Self::register_subsampling_method_argument(command);
+        let command = Self::register_chroma_siting_argument(command);
+        let command = Self::register_color_matrix_argument(command);
+        let command = Self::register_color_range_argument(command);
+        let command = Self::register_grayscale_argument(command);
+        let command = Self::register_cmyk_argument(command);
+        let command = Self::register_background_argument(command);
+        let command = Self::register_threads_argument(command);
+        let command = Self::register_quantization_table_preset_argument(command);
+        let command = Self::register_quant_mix_argument(command);
+        let command = Self::register_quality_argument(command);
+        let command = Self::register_chroma_quality_argument(command);
+        let command = Self::register_target_size_argument(command);
+        let command = Self::register_trellis_argument(command);
+        let command = Self::register_adaptive_quantization_argument(command);
+        let command = Self::register_dead_zone_argument(command);
+        let command = Self::register_chroma_red_preset_argument(command);
+        let command = Self::register_chroma_red_table_file_argument(command);
+        let command = Self::register_dct_argument(command);
+        let command = Self::register_high_precision_argument(command);
+        let command = Self::register_huffman_argument(command);
+        let command = Self::register_max_code_length_argument(command);
+        let command = Self::register_restart_interval_argument(command);
+        let command = Self::register_restart_index_argument(command);
+        let command = Self::register_dump_symbol_stats_argument(command);
+        let command = Self::register_huffman_tables_argument(command);
+        let command = Self::register_dump_huffman_argument(command);
+        let command = Self::register_invert_argument(command);
+        let command = Self::register_brightness_argument(command);
+        let command = Self::register_contrast_argument(command);
+        let command = Self::register_saturation_argument(command);
+        let command = Self::register_crop_argument(command);
+        let command = Self::register_rotate_argument(command);
+        let command = Self::register_resize_argument(command);
+        let command = Self::register_smooth_argument(command);
+        let command = Self::register_gps_argument(command);
+        let command = Self::register_gps_file_argument(command);
+        let command = Self::register_user_comment_argument(command);
+        let command = Self::register_no_timestamps_argument(command);
+        let command = Self::register_xmp_argument(command);
+        let command = Self::register_xmp_file_argument(command);
+        let command = Self::register_thumbnail_argument(command);
+        let command = Self::register_dpi_argument(command);
+        let command = Self::register_density_unit_argument(command);
+        let command = Self::register_adobe_argument(command);
+        let command = Self::register_dnl_argument(command);
+        let command = Self::register_combine_tables_argument(command);
+        let command = Self::register_comment_argument(command);
+        let command = Self::register_embed_settings_argument(command);
+        let command = Self::register_create_dirs_argument(command);
+        let command = Self::register_metrics_argument(command);
+        let command = Self::register_timings_argument(command);
+        let command = Self::register_max_pixels_argument(command);
+        let command = Self::register_verbose_argument(command);
+        Self::register_bench_subcommand(command)
+    }
+
+    fn register_bench_arguments(command: Command) -> Command {
+        let command = Self::register_bench_threads_argument(command);
+        let command = Self::register_bench_size_argument(command);
+        Self::register_bench_rounds_argument(command)
+    }
+
+    fn register_input_file_argument(command: Command) -> Command {
+        command.arg(Self::create_input_file_argument())
+    }
+
+    fn register_mmap_argument(command: Command) -> Command {
+        command.arg(Self::create_mmap_argument())
+    }
+
+    fn register_output_file_argument(command: Command) -> Command {
+        command.arg(Self::create_output_file_argument())
+    }
+
+    fn register_bits_per_channel_argument(command: Command) -> Command {
+        command.arg(Self::create_bits_per_channel_argument())
+    }
+
+    fn register_tune_argument(command: Command) -> Command {
+        command.arg(Self::create_tune_argument())
+    }
+
+    fn register_chroma_subsampling_preset_argument(command: Command) -> Command {
+        command.arg(Self::create_chroma_subsampling_preset_argument())
+    }
+
+    fn register_subsampling_method_argument(command: Command) -> Command {
+        command.arg(Self::create_subsampling_method_argument())
+    }
+
+    fn register_chroma_siting_argument(command: Command) -> Command {
+        command.arg(Self::create_chroma_siting_argument())
+    }
+
+    fn register_color_matrix_argument(command: Command) -> Command {
+        command.arg(Self::create_color_matrix_argument())
+    }
+
+    fn register_color_range_argument(command: Command) -> Command {
+        command.arg(Self::create_color_range_argument())
+    }
+
+    fn register_grayscale_argument(command: Command) -> Command {
+        command.arg(Self::create_grayscale_argument())
+    }
+
+    fn register_cmyk_argument(command: Command) -> Command {
+        command.arg(Self::create_cmyk_argument())
+    }
+
+    fn register_background_argument(command: Command) -> Command {
+        command.arg(Self::create_background_argument())
+    }
+
+    fn register_threads_argument(command: Command) -> Command {
+        command.arg(Self::create_threads_argument())
+    }
+
+    fn register_quantization_table_preset_argument(command: Command) -> Command {
+        command.arg(Self::create_quantization_table_preset_argument())
+    }
+
+    fn register_quant_mix_argument(command: Command) -> Command {
+        command.arg(Self::create_quant_mix_argument())
+    }
+
+    fn register_quality_argument(command: Command) -> Command {
+        command.arg(Self::create_quality_argument())
+    }
+
+    fn register_chroma_quality_argument(command: Command) -> Command {
+        command.arg(Self::create_chroma_quality_argument())
+    }
+
+    fn register_target_size_argument(command: Command) -> Command {
+        command.arg(Self::create_target_size_argument())
+    }
+
+    fn register_trellis_argument(command: Command) -> Command {
+        command.arg(Self::create_trellis_argument())
+    }
+
+    fn register_adaptive_quantization_argument(command: Command) -> Command {
+        command.arg(Self::create_adaptive_quantization_argument())
+    }
+
+    fn register_dead_zone_argument(command: Command) -> Command {
+        command.arg(Self::create_dead_zone_argument())
+    }
+
+    fn register_chroma_red_preset_argument(command: Command) -> Command {
+        command.arg(Self::create_chroma_red_preset_argument())
+    }
+
+    fn register_chroma_red_table_file_argument(command: Command) -> Command {
+        command.arg(Self::create_chroma_red_table_file_argument())
+    }
+
+    fn register_dct_argument(command: Command) -> Command {
+        command.arg(Self::create_dct_argument())
+    }
+
+    fn register_high_precision_argument(command: Command) -> Command {
+        command.arg(Self::create_high_precision_argument())
+    }
+
+    fn register_huffman_argument(command: Command) -> Command {
+        command.arg(Self::create_huffman_argument())
+    }
+
+    fn register_max_code_length_argument(command: Command) -> Command {
+        command.arg(Self::create_max_code_length_argument())
+    }
+
+    fn register_restart_interval_argument(command: Command) -> Command {
+        command.arg(Self::create_restart_interval_argument())
+    }
+
+    fn register_restart_index_argument(command: Command) -> Command {
+        command.arg(Self::create_restart_index_argument())
+    }
+
+    fn register_dump_symbol_stats_argument(command: Command) -> Command {
+        command.arg(Self::create_dump_symbol_stats_argument())
+    }
+
+    fn register_huffman_tables_argument(command: Command) -> Command {
+        command.arg(Self::create_huffman_tables_argument())
+    }
+
+    fn register_dump_huffman_argument(command: Command) -> Command {
+        command.arg(Self::create_dump_huffman_argument())
+    }
+
+    fn register_invert_argument(command: Command) -> Command {
+        command.arg(Self::create_invert_argument())
+    }
+
+    fn register_brightness_argument(command: Command) -> Command {
+        command.arg(Self::create_brightness_argument())
+    }
+
+    fn register_contrast_argument(command: Command) -> Command {
+        command.arg(Self::create_contrast_argument())
+    }
+
+    fn register_saturation_argument(command: Command) -> Command {
+        command.arg(Self::create_saturation_argument())
+    }
+
+    fn register_crop_argument(command: Command) -> Command {
+        command.arg(Self::create_crop_argument())
+    }
+
+    fn register_rotate_argument(command: Command) -> Command {
+        command.arg(Self::create_rotate_argument())
+    }
+
+    fn register_resize_argument(command: Command) -> Command {
+        command.arg(Self::create_resize_argument())
+    }
+
+    fn register_smooth_argument(command: Command) -> Command {
+        command.arg(Self::create_smooth_argument())
+    }
+
+    fn register_gps_argument(command: Command) -> Command {
+        command.arg(Self::create_gps_argument())
+    }
+
+    fn register_gps_file_argument(command: Command) -> Command {
+        command.arg(Self::create_gps_file_argument())
+    }
+
+    fn register_user_comment_argument(command: Command) -> Command {
+        command.arg(Self::create_user_comment_argument())
+    }
+
+    fn register_no_timestamps_argument(command: Command) -> Command {
+        command.arg(Self::create_no_timestamps_argument())
+    }
+
+    fn register_xmp_argument(command: Command) -> Command {
+        command.arg(Self::create_xmp_argument())
+    }
+
+    fn register_xmp_file_argument(command: Command) -> Command {
+        command.arg(Self::create_xmp_file_argument())
+    }
+
+    fn register_thumbnail_argument(command: Command) -> Command {
+        command.arg(Self::create_thumbnail_argument())
+    }
+
+    fn register_dpi_argument(command: Command) -> Command {
+        command.arg(Self::create_dpi_argument())
+    }
+
+    fn register_density_unit_argument(command: Command) -> Command {
+        command.arg(Self::create_density_unit_argument())
+    }
+
+    fn register_adobe_argument(command: Command) -> Command {
+        command.arg(Self::create_adobe_argument())
+    }
+
+    fn register_dnl_argument(command: Command) -> Command {
+        command.arg(Self::create_dnl_argument())
+    }
+
+    fn register_combine_tables_argument(command: Command) -> Command {
+        command.arg(Self::create_combine_tables_argument())
+    }
+
+    fn register_comment_argument(command: Command) -> Command {
+        command.arg(Self::create_comment_argument())
+    }
+
+    fn register_embed_settings_argument(command: Command) -> Command {
+        command.arg(Self::create_embed_settings_argument())
+    }
+
+    fn register_create_dirs_argument(command: Command) -> Command {
+        command.arg(Self::create_create_dirs_argument())
+    }
+
+    fn register_metrics_argument(command: Command) -> Command {
+        command.arg(Self::create_metrics_argument())
+    }
+
+    fn register_timings_argument(command: Command) -> Command {
+        command.arg(Self::create_timings_argument())
+    }
+
+    fn register_max_pixels_argument(command: Command) -> Command {
+        command.arg(Self::create_max_pixels_argument())
+    }
+
+    fn register_verbose_argument(command: Command) -> Command {
+        command.arg(Self::create_verbose_argument())
+    }
+
+    fn register_bench_subcommand(command: Command) -> Command {
+        command
+            .subcommand(Self::create_bench_subcommand())
+            .subcommand_negates_reqs(true)
+            .args_conflicts_with_subcommands(true)
+    }
+
+    fn register_bench_threads_argument(command: Command) -> Command {
+        command.arg(Self::create_bench_threads_argument())
+    }
+
+    fn register_bench_size_argument(command: Command) -> Command {
+        command.arg(Self::create_bench_size_argument())
+    }
+
+    fn register_bench_rounds_argument(command: Command) -> Command {
+        command.arg(Self::create_bench_rounds_argument())
+    }
+
+    fn create_base_command() -> Command {
+        Command::new(crate_name!())
+            .version(crate_version!())
+            .author(crate_authors!())
+            .about(crate_description!())
+    }
+
+    fn create_bench_subcommand() -> Command {
+        let command = Command::new(BENCH_SUBCOMMAND_NAME).about(
+            "Encode a synthetic image with each combination of threads, DCT algorithm and chroma subsampling preset and print the throughput",
+        );
+        Self::register_bench_arguments(command)
+    }
+
+    fn create_input_file_argument() -> Arg {
+        Arg::new("input_file")
+            .help("Path to PPM, PAM, PFM, PNG, TIFF or JPEG input file")
+            .value_parser(value_parser!(PathBuf))
+            .required(true)
+    }
+
+    fn create_mmap_argument() -> Arg {
+        arg!(--mmap "Map the input file into memory instead of reading it through a buffer")
+            .action(ArgAction::SetTrue)
+    }
+
+    fn create_output_file_argument() -> Arg {
+        Arg::new("output_file")
+            .help("Path to JPEG output file, or - to write the JPEG to stdout")
+            .value_parser(value_parser!(PathBuf))
+            .required(true)
+    }
+
+    fn create_bits_per_channel_argument() -> Arg {
+        arg!(bits_per_channel: -b --bits_per_channel <BITS> "Bits per color channel")
+            .default_value("8")
+            .value_parser([PossibleValue::new("8"), PossibleValue::new("12")])
+    }
+
+    fn create_tune_argument() -> Arg {
+        arg!(tune: --tune <TUNE> "Select the DCT algorithm, Huffman tables, chroma subsampling and quantization table preset at once. Explicitly given options take precedence")
+            .value_parser(value_parser!(Tune))
+    }
+
+    fn create_chroma_subsampling_preset_argument() -> Arg {
+        arg!(chroma_subsampling_preset: -p --chroma_subsampling_preset <PRESET> "Chroma subsampling preset, ratio like 4:2:2 or horizontal and vertical rates like 3x1")
+            .visible_alias("subsampling")
+            .default_value("P420").value_parser(value_parser!(ChromaSubsamplingPreset))
+    }
+
+    fn create_subsampling_method_argument() -> Arg {
+        arg!(subsampling_method: --"subsampling-method" <METHOD> "Filter, which subsamples the chroma channels instead of the method of the preset. Triangle and gaussian reduce the aliasing of sharp colored edges, median and nearest keep thin colored lines")
+            .value_parser(value_parser!(SubsamplingMethod))
+    }
+
+    fn create_chroma_siting_argument() -> Arg {
+        arg!(chroma_siting: --"chroma-siting" <SITING> "Position of the subsampled chroma samples. Co-sited keeps the chroma positions of content from video")
+            .default_value("centered")
+            .value_parser(value_parser!(ChromaSiting))
+    }
+
+    fn create_color_matrix_argument() -> Arg {
+        arg!(color_matrix: --"color-matrix" <MATRIX> "Luma weights of the color conversion. Frames of HD or UHD video keep their colors with bt709 or bt2020, which is noted in a comment segment")
+            .default_value("bt601")
+            .value_parser(value_parser!(ColorMatrix))
+    }
+
+    fn create_color_range_argument() -> Arg {
+        arg!(color_range: --"color-range" <RANGE> "Range of the YCbCr samples. Limited writes the studio swing of video tooling, luma from 16 to 235 and chroma from 16 to 240")
+            .default_value("full")
+            .value_parser(value_parser!(ColorRange))
+    }
+
+    fn create_grayscale_argument() -> Arg {
+        arg!(grayscale: --grayscale "Drop the chroma channels and write a JPEG with the luma component only")
+            .action(ArgAction::SetTrue)
+    }
+
+    fn create_cmyk_argument() -> Arg {
+        arg!(cmyk: --cmyk "Read a CMYK PAM or TIFF file and write a YCCK JPEG with four components. The input is neither preprocessed nor compared for metrics")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("grayscale")
+    }
+
+    fn create_background_argument() -> Arg {
+        arg!(--background <COLOR> "Hex color like #ffffff, which transparent pixels of RGBA input are composited over, since JPEG cannot carry alpha")
+            .default_value("#ffffff")
+            .value_parser(value_parser!(RGBColorFormat<f32>))
+    }
+
+    fn create_threads_argument() -> Arg {
+        arg!(-t --threads <THREADS> "Number of Threads")
+            .default_value(get_number_of_threads().unwrap_or(1).to_string())
+            .required(false)
+            .value_parser(value_parser!(usize))
+    }
+
+    fn create_quantization_table_preset_argument() -> Arg {
+        arg!(quantization_table_preset: --quantization_table <TABLE> "Quantization table preset")
+            .default_value("Specification")
+            .value_parser(value_parser!(QuantizationTablePreset))
+    }
+
+    fn create_quant_mix_argument() -> Arg {
+        arg!(quant_mix: --"quant-mix" <"PRESET:WEIGHT,PRESET:WEIGHT"> "Blend the tables of two quantization table presets with the weights, like Flat:0.3,Specification:0.7")
+            .conflicts_with("quantization_table_preset")
+            .value_parser(parse_quant_mix)
+    }
+
+    fn create_quality_argument() -> Arg {
+        arg!(quality: -q --quality <QUALITY> "Scale the quantization tables from 1 (smallest file) to 100 (best quality) like libjpeg. 50 keeps the preset unchanged")
+            .value_parser(value_parser!(u8).range(1..=100))
+    }
+
+    fn create_chroma_quality_argument() -> Arg {
+        arg!(chroma_quality: --"chroma-quality" <QUALITY> "Scale the chroma tables separately from the luma table from 1 to 100. They follow --quality, if unset")
+            .value_parser(value_parser!(u8).range(1..=100))
+    }
+
+    fn create_target_size_argument() -> Arg {
+        arg!(target_size: --"target-size" <SIZE> "Search the highest quality, whose output does not exceed the size in B, kB, MB, KiB or MiB, like 200kB")
+            .conflicts_with("quality")
+            .value_parser(parse_target_size)
+    }
+
+    fn create_trellis_argument() -> Arg {
+        arg!(--trellis "Choose the quantized coefficients by trellis quantization, which trades quality against the bits of the Huffman tables of the output like mozjpeg. Smaller files, but slower")
+            .action(ArgAction::SetTrue)
+    }
+
+    fn create_adaptive_quantization_argument() -> Arg {
+        arg!(adaptive_quantization: --"adaptive-quantization" <STRENGTH> "Quantize textured blocks coarser than flat areas and gradients with the strength (1.0 is moderate)")
+            .value_parser(parse_non_negative_factor)
+    }
+
+    fn create_dead_zone_argument() -> Arg {
+        arg!(dead_zone: --"dead-zone" <"THRESHOLD[:INDEX]"> "Zero the quantized AC coefficients, whose absolute value is below the threshold, from the zig zag index (default 1) on. Smaller files at slightly lower quality")
+            .value_parser(parse_dead_zone)
+    }
+
+    fn create_chroma_red_preset_argument() -> Arg {
+        arg!(chroma_red_preset: --"chroma-red-preset" <PRESET> "Quantization table preset, whose chroma table quantizes the Cr component separately from Cb")
+            .value_parser(value_parser!(QuantizationTablePreset))
+    }
+
+    fn create_chroma_red_table_file_argument() -> Arg {
+        arg!(chroma_red_table_file: --"chroma-red-table-file" <FILE> "File with 64 quantization steps in row major order, which quantize the Cr component separately from Cb")
+            .value_parser(value_parser!(PathBuf))
+            .conflicts_with("chroma_red_preset")
+    }
+
+    fn create_dct_argument() -> Arg {
+        arg!(dct: --dct <ALGORITHM> "Algorithm of the discrete cosine transform. Auto selects SIMD, if the CPU supports it, and Arai otherwise")
+            .default_value("Auto")
+            .value_parser(value_parser!(DCTAlgorithm))
+    }
+
+    fn create_high_precision_argument() -> Arg {
+        arg!(high_precision: --"high-precision" "Convert colors and compute the DCT in double precision. Auto uses the separated DCT, SIMD the scalar Arai DCT")
+            .action(ArgAction::SetTrue)
+    }
+
+    fn create_huffman_argument() -> Arg {
+        arg!(huffman: --huffman <MODE> "Huffman tables optimized for the image or the standard tables of the specification, which skip counting the symbols")
+            .default_value("optimized")
+            .value_parser(value_parser!(HuffmanTableMode))
+    }
+
+    fn create_max_code_length_argument() -> Arg {
+        arg!(max_code_length: --"max-code-length" <BITS> "Longest code word of the Huffman tables, which constrained hardware decoders may need below 16 bits")
+            .default_value("16")
+            .value_parser(value_parser!(u8).range(
+                MIN_HUFFMAN_CODE_LENGTH_LIMIT as i64..=MAX_HUFFMAN_CODE_LENGTH as i64,
+            ))
+    }
+
+    fn create_restart_interval_argument() -> Arg {
+        arg!(restart_interval: --"restart-interval" <MCUS> "Number of MCUs between two restart markers")
+            .value_parser(value_parser!(u16).range(1..))
+    }
+
+    fn create_restart_index_argument() -> Arg {
+        arg!(restart_index: --"restart-index" <FILE> "Sidecar file with the byte offset of each restart interval")
+            .value_parser(value_parser!(PathBuf))
+            .requires("restart_interval")
+    }
+
+    fn create_dump_symbol_stats_argument() -> Arg {
+        arg!(dump_symbol_stats: --"dump-symbol-stats" <FILE> "CSV file with the frequencies of the DC and AC Huffman symbols of each component")
+            .value_parser(value_parser!(PathBuf))
+    }
+
+    fn create_huffman_tables_argument() -> Arg {
+        arg!(huffman_tables: --"huffman-tables" <FILE> "JSON file with Huffman tables written by --dump-huffman, which replace the tables of the mode unless they lack symbols of the image")
+            .value_parser(value_parser!(PathBuf))
+    }
+
+    fn create_dump_huffman_argument() -> Arg {
+        arg!(dump_huffman: --"dump-huffman" <FILE> "JSON file with the Huffman tables of the image, which --huffman-tables reuses for similar images")
+            .value_parser(value_parser!(PathBuf))
+    }
+
+    fn create_invert_argument() -> Arg {
+        arg!(--invert "Invert the color values of the image before encoding")
+            .action(ArgAction::SetTrue)
+    }
+
+    fn create_brightness_argument() -> Arg {
+        arg!(--brightness <BRIGHTNESS> "Brightness offset relative to the full value range (-1.0 to 1.0)")
+            .default_value("0.0")
+            .allow_negative_numbers(true)
+            .value_parser(parse_brightness)
+    }
+
+    fn create_contrast_argument() -> Arg {
+        arg!(--contrast <CONTRAST> "Contrast factor applied to the luma channel (1.0 is neutral)")
+            .default_value("1.0")
+            .value_parser(parse_non_negative_factor)
+    }
+
+    fn create_saturation_argument() -> Arg {
+        arg!(--saturation <SATURATION> "Saturation factor applied to the chroma channels (1.0 is neutral)")
+            .default_value("1.0")
+            .value_parser(parse_non_negative_factor)
+    }
+
+    fn create_crop_argument() -> Arg {
+        arg!(--crop <GEOMETRY> "Crop the image to WIDTHxHEIGHT+X+Y before encoding")
+            .value_parser(parse_crop)
+    }
+
+    fn create_rotate_argument() -> Arg {
+        arg!(--rotate <DEGREES> "Rotate the image clockwise before encoding")
+            .default_value("0")
+            .value_parser(parse_rotation)
+    }
+
+    fn create_resize_argument() -> Arg {
+        arg!(--resize <SIZE> "Resize the image to WIDTHxHEIGHT before encoding")
+            .value_parser(parse_resize)
+    }
+
+    fn create_smooth_argument() -> Arg {
+        arg!(--smooth <FACTOR> "Blur the image from 0 (off) to 100 (3x3 box) before encoding, which suppresses the noise of dithered or scanned sources")
+            .default_value("0")
+            .value_parser(value_parser!(u8).range(0..=100))
+    }
+
+    fn create_gps_argument() -> Arg {
+        arg!(--gps <POSITION> "GPS position LATITUDE,LONGITUDE[,ALTITUDE] in decimal degrees and meters, written to EXIF")
+            .allow_hyphen_values(true)
+            .value_parser(value_parser!(GpsPosition))
+    }
+
+    fn create_gps_file_argument() -> Arg {
+        arg!(gps_file: --"gps-file" <FILE> "Sidecar file with latitude, longitude and altitude lines, written to EXIF")
+            .value_parser(value_parser!(PathBuf))
+            .conflicts_with("gps")
+    }
+
+    fn create_user_comment_argument() -> Arg {
+        arg!(user_comment: --"user-comment" <TEXT> "User comment, written to EXIF")
+            .value_parser(value_parser!(String))
+    }
+
+    fn create_no_timestamps_argument() -> Arg {
+        arg!(no_timestamps: --"no-timestamps" "Do not write the EXIF DateTime. SOURCE_DATE_EPOCH overrides the current time otherwise")
+            .action(ArgAction::SetTrue)
+    }
+
+    fn create_xmp_argument() -> Arg {
+        arg!(--xmp <PACKET> "XMP packet, embedded as APP1 segment")
+            .allow_hyphen_values(true)
+            .value_parser(parse_xmp_packet)
+    }
+
+    fn create_xmp_file_argument() -> Arg {
+        arg!(xmp_file: --"xmp-file" <FILE> "File with an XMP packet, embedded as APP1 segment")
+            .value_parser(value_parser!(PathBuf))
+            .conflicts_with("xmp")
+    }
+
+    fn create_thumbnail_argument() -> Arg {
+        arg!(--thumbnail <SIZE> "Embed an uncompressed RGB thumbnail of WIDTHxHEIGHT pixels into the JFIF header")
+            .value_parser(parse_thumbnail_size)
+    }
+
+    fn create_dpi_argument() -> Arg {
+        arg!(--dpi <N> "Pixel density of the JFIF header in the density unit")
+            .value_parser(value_parser!(u16).range(1..))
+    }
+
+    fn create_density_unit_argument() -> Arg {
+        arg!(density_unit: --"density-unit" <UNIT> "Unit of the pixel density given with --dpi")
+            .default_value("dpi")
+            .requires("dpi")
+            .value_parser(value_parser!(DensityUnit))
+    }
+
+    fn create_adobe_argument() -> Arg {
+        arg!(--adobe "Write the Adobe APP14 segment with the YCbCr color transform flag")
+            .action(ArgAction::SetTrue)
+    }
+
+    fn create_dnl_argument() -> Arg {
+        arg!(--dnl "Write a height of 0 into the frame header and the actual height into a DNL segment after the scan")
+            .action(ArgAction::SetTrue)
+    }
+
+    fn create_combine_tables_argument() -> Arg {
+        arg!(combine_tables: --"combine-tables" "Write all quantization tables into a single DQT segment and all Huffman tables into a single DHT segment")
+            .action(ArgAction::SetTrue)
+    }
+
+    fn create_comment_argument() -> Arg {
+        arg!(--comment <TEXT> "Text, written as COM segment after the application segments. Can be repeated")
+            .action(ArgAction::Append)
+            .allow_hyphen_values(true)
+            .value_parser(parse_comment)
+    }
+
+    fn create_embed_settings_argument() -> Arg {
+        arg!(embed_settings: --"embed-settings" "Write the encoder version and the normalized options as JSON comment segment")
+            .action(ArgAction::SetTrue)
+    }
+
+    fn create_metrics_argument() -> Arg {
+        arg!(--metrics "Decode the written JPEG and print its PSNR and SSIM against the source")
+            .action(ArgAction::SetTrue)
+    }
+
+    fn create_timings_argument() -> Arg {
+        arg!(--timings "Print the CPU time and the processed bytes of each stage of the conversion")
+            .action(ArgAction::SetTrue)
+    }
+
+    fn create_create_dirs_argument() -> Arg {
+        arg!(create_dirs: --"create-dirs" "Create missing parent directories of the output file")
+            .action(ArgAction::SetTrue)
+    }
+
+    fn create_verbose_argument() -> Arg {
+        arg!(verbose: -v --verbose "Print the sampling factors and MCU layout of the output file")
+            .action(ArgAction::SetTrue)
+    }
+
+    fn create_max_pixels_argument() -> Arg {
+        arg!(max_pixels: --"max-pixels" <COUNT> "Reject input images, which declare more pixels")
+            .default_value(InputLimits::default().max_pixel_count.to_string())
+            .value_parser(value_parser!(u64).range(1..))
+    }
+
+    fn create_bench_threads_argument() -> Arg {
+        let available_threads = get_number_of_threads().unwrap_or(1);
+        let default_threads = if available_threads > 1 {
+            format!("1,{}", available_threads)
+        } else {
+            "1".to_string()
+        };
+        arg!(-t --threads <THREADS> "Comma separated numbers of threads to measure")
+            .default_value(default_threads)
+            .value_delimiter(',')
+            .value_parser(parse_positive_count)
+    }
+
+    fn create_bench_size_argument() -> Arg {
+        arg!(--size <SIZE> "Size WIDTHxHEIGHT of the synthetic image")
+            .default_value("1920x1080")
+            .value_parser(parse_size)
+    }
+
+    fn create_bench_rounds_argument() -> Arg {
+        arg!(--rounds <ROUNDS> "Number of encodings per configuration, the fastest one is reported")
+            .default_value("3")
+            .value_parser(parse_positive_count)
+    }
+
+    fn extract_arguments(matches: &ArgMatches) -> Arguments {
+        Arguments {
+            input_file: Self::extract_input_file_argument(matches),
+            memory_map: Self::extract_mmap_argument(matches),
+            output_file: Self::extract_output_file_argument(matches),
+            chroma_subsampling_preset: Self::extract_chroma_subsampling_preset_argument(matches),
+            chroma_subsampling_method: Self::extract_subsampling_method_argument(matches),
+            chroma_siting: Self::extract_chroma_siting_argument(matches),
+            color_matrix: Self::extract_color_matrix_argument(matches),
+            color_range: Self::extract_color_range_argument(matches),
+            grayscale: Self::extract_grayscale_argument(matches),
+            cmyk: Self::extract_cmyk_argument(matches),
+            background: Self::extract_background_argument(matches),
+            bits_per_channel: Self::extract_bits_per_channel_argument(matches),
+            number_of_threads: Self::extract_threads_argument(matches),
+            quantization_table_preset: Self::extract_quantization_table_preset_argument(matches),
+            quantization_table_mix: Self::extract_quant_mix_argument(matches),
+            quality: Self::extract_quality_argument(matches),
+            chroma_quality: Self::extract_chroma_quality_argument(matches),
+            target_size: Self::extract_target_size_argument(matches),
+            trellis_quantization: Self::extract_trellis_argument(matches),
+            adaptive_quantization: Self::extract_adaptive_quantization_argument(matches),
+            dead_zone: Self::extract_dead_zone_argument(matches),
+            chroma_red_preset: Self::extract_chroma_red_preset_argument(matches),
+            chroma_red_table_file: Self::extract_chroma_red_table_file_argument(matches),
+            dct_algorithm: Self::extract_dct_argument(matches),
+            high_precision: Self::extract_high_precision_argument(matches),
+            huffman_table_mode: Self::extract_huffman_argument(matches),
+            max_huffman_code_length: Self::extract_max_code_length_argument(matches),
+            restart_interval: Self::extract_restart_interval_argument(matches),
+            restart_index_file: Self::extract_restart_index_argument(matches),
+            symbol_stats_file: Self::extract_dump_symbol_stats_argument(matches),
+            huffman_tables_file: Self::extract_huffman_tables_argument(matches),
+            dump_huffman_file: Self::extract_dump_huffman_argument(matches),
+            invert: Self::extract_invert_argument(matches),
+            brightness: Self::extract_brightness_argument(matches),
+            contrast: Self::extract_contrast_argument(matches),
+            saturation: Self::extract_saturation_argument(matches),
+            crop: Self::extract_crop_argument(matches),
+            rotation: Self::extract_rotate_argument(matches),
+            resize: Self::extract_resize_argument(matches),
+            smoothing: Self::extract_smooth_argument(matches),
+            gps_position: Self::extract_gps_argument(matches),
+            gps_sidecar_file: Self::extract_gps_file_argument(matches),
+            user_comment: Self::extract_user_comment_argument(matches),
+            timestamps: Self::extract_timestamps_argument(matches),
+            xmp_packet: Self::extract_xmp_argument(matches),
+            xmp_file: Self::extract_xmp_file_argument(matches),
+            thumbnail_size: Self::extract_thumbnail_argument(matches),
+            dpi: Self::extract_dpi_argument(matches),
+            density_unit: Self::extract_density_unit_argument(matches),
+            adobe_marker: Self::extract_adobe_argument(matches),
+            define_number_of_lines: Self::extract_dnl_argument(matches),
+            combine_table_segments: Self::extract_combine_tables_argument(matches),
+            comments: Self::extract_comment_argument(matches),
+            embed_settings: Self::extract_embed_settings_argument(matches),
+            create_dirs: Self::extract_create_dirs_argument(matches),
+            metrics: Self::extract_metrics_argument(matches),
+            timings: Self::extract_timings_argument(matches),
+            max_pixel_count: Self::extract_max_pixels_argument(matches),
+            verbose: Self::extract_verbose_argument(matches),
+        }
+    }
+
+    fn extract_input_file_argument(matches: &ArgMatches) -> PathBuf {
+        matches
+            .get_one::<PathBuf>("input_file")
+            .expect("Required argument input_file not provided")
+            .clone()
+    }
+
+    fn extract_mmap_argument(matches: &ArgMatches) -> bool {
+        matches.get_flag("mmap")
+    }
+
+    fn extract_output_file_argument(matches: &ArgMatches) -> PathBuf {
+        matches
+            .get_one::<PathBuf>("output_file")
+            .expect("Required argument output_file not provided")
+            .clone()
+    }
+
+    fn extract_bits_per_channel_argument(matches: &ArgMatches) -> u8 {
+        matches
+            .get_one::<String>("bits_per_channel")
+            .expect("Bits per channel must be provided, but was unset.")
+            .parse::<u8>()
+            .expect("Argument value for bits per channel must be in range of u8")
+    }
+
+    fn extract_tune_argument(matches: &ArgMatches) -> Option<Tune> {
+        matches.try_get_one::<Tune>("tune").ok().flatten().copied()
+    }
+
+    /// Value of the tune for the argument, if a tune is given and the argument keeps its
+    /// default value.
+    fn extract_tuned_argument<T>(
+        matches: &ArgMatches,
+        id: &str,
+        tuned_value: fn(&Tune) -> T,
+    ) -> Option<T> {
+        if matches.value_source(id) != Some(ValueSource::DefaultValue) {
+            return None;
+        }
+        Self::extract_tune_argument(matches).map(|tune| tuned_value(&tune))
+    }
+
+    fn extract_chroma_subsampling_preset_argument(matches: &ArgMatches) -> ChromaSubsamplingPreset {
+        Self::extract_tuned_argument(
+            matches,
+            "chroma_subsampling_preset",
+            Tune::chroma_subsampling_preset,
+        )
+        .unwrap_or_else(|| {
+            matches
+                .get_one::<ChromaSubsamplingPreset>("chroma_subsampling_preset")
+                .expect("Chroma subsampling preset must be provided, but was unset.")
+                .to_owned()
+        })
+    }
+
+    fn extract_subsampling_method_argument(matches: &ArgMatches) -> Option<SubsamplingMethod> {
+        matches
+            .get_one::<SubsamplingMethod>("subsampling_method")
+            .copied()
+    }
+
+    fn extract_chroma_siting_argument(matches: &ArgMatches) -> ChromaSiting {
+        matches
+            .get_one::<ChromaSiting>("chroma_siting")
+            .copied()
+            .expect("Chroma siting has a default value")
+    }
+
+    fn extract_color_matrix_argument(matches: &ArgMatches) -> ColorMatrix {
+        matches
+            .get_one::<ColorMatrix>("color_matrix")
+            .copied()
+            .expect("Color matrix has a default value")
+    }
+
+    fn extract_color_range_argument(matches: &ArgMatches) -> ColorRange {
+        matches
+            .get_one::<ColorRange>("color_range")
+            .copied()
+            .expect("Color range has a default value")
+    }
+
+    fn extract_grayscale_argument(matches: &ArgMatches) -> bool {
+        matches.get_flag("grayscale")
+    }
+
+    fn extract_cmyk_argument(matches: &ArgMatches) -> bool {
+        matches.get_flag("cmyk")
+    }
+
+    fn extract_background_argument(matches: &ArgMatches) -> RGBColorFormat<f32> {
+        matches
+            .get_one::<RGBColorFormat<f32>>("background")
+            .copied()
+            .expect("Background has a default value")
+    }
+
+    fn extract_threads_argument(matches: &ArgMatches) -> usize {
+        matches
+            .get_one::<usize>("threads")
+            .expect("Required argument threads not provided")
+            .to_owned()
+    }
+
+    fn extract_quantization_table_preset_argument(matches: &ArgMatches) -> QuantizationTablePreset {
+        Self::extract_tuned_argument(
+            matches,
+            "quantization_table_preset",
+            Tune::quantization_table_preset,
+        )
+        .unwrap_or_else(|| {
+            matches
+                .get_one::<QuantizationTablePreset>("quantization_table_preset")
+                .expect("Quantization table preset must be provided, but was unset")
+                .to_owned()
+        })
+    }
+
+    fn extract_quant_mix_argument(matches: &ArgMatches) -> Option<QuantizationTableMix> {
+        matches
+            .get_one::<QuantizationTableMix>("quant_mix")
+            .copied()
+    }
+
+    fn extract_quality_argument(matches: &ArgMatches) -> Option<u8> {
+        matches.get_one::<u8>("quality").copied()
+    }
+
+    fn extract_chroma_quality_argument(matches: &ArgMatches) -> Option<u8> {
+        matches.get_one::<u8>("chroma_quality").copied()
+    }
+
+    fn extract_target_size_argument(matches: &ArgMatches) -> Option<u64> {
+        matches.get_one::<u64>("target_size").copied()
+    }
+
+    fn extract_trellis_argument(matches: &ArgMatches) -> bool {
+        matches.get_flag("trellis")
+    }
+
+    fn extract_adaptive_quantization_argument(matches: &ArgMatches) -> Option<f32> {
+        matches.get_one::<f32>("adaptive_quantization").copied()
+    }
+
+    fn extract_dead_zone_argument(matches: &ArgMatches) -> Option<DeadZone> {
+        matches.get_one::<DeadZone>("dead_zone").copied()
+    }
+
+    fn extract_chroma_red_preset_argument(matches: &ArgMatches) -> Option<QuantizationTablePreset> {
+        matches
+            .get_one::<QuantizationTablePreset>("chroma_red_preset")
+            .copied()
+    }
+
+    fn extract_chroma_red_table_file_argument(matches: &ArgMatches) -> Option<PathBuf> {
+        matches.get_one::<PathBuf>("chroma_red_table_file").cloned()
+    }
+
+    fn extract_dct_argument(matches: &ArgMatches) -> DCTAlgorithm {
+        Self::extract_tuned_argument(matches, "dct", Tune::dct_algorithm).unwrap_or_else(|| {
+            matches
+                .get_one::<DCTAlgorithm>("dct")
+                .expect("DCT algorithm must be provided, but was unset")
+                .to_owned()
+        })
+    }
+
+    fn extract_high_precision_argument(matches: &ArgMatches) -> bool {
+        matches.get_flag("high_precision")
+    }
+
+    fn extract_huffman_argument(matches: &ArgMatches) -> HuffmanTableMode {
+        Self::extract_tuned_argument(matches, "huffman", Tune::huffman_table_mode).unwrap_or_else(
+            || {
+                matches
+                    .get_one::<HuffmanTableMode>("huffman")
+                    .expect("Huffman table mode must be provided, but was unset")
+                    .to_owned()
+            },
+        )
+    }
+
+    fn extract_max_code_length_argument(matches: &ArgMatches) -> u8 {
+        *matches
+            .get_one::<u8>("max_code_length")
+            .expect("Maximum code length must be provided, but was unset")
+    }
+
+    fn extract_restart_interval_argument(matches: &ArgMatches) -> Option<u16> {
+        matches.get_one::<u16>("restart_interval").copied()
+    }
+
+    fn extract_restart_index_argument(matches: &ArgMatches) -> Option<PathBuf> {
+        matches.get_one::<PathBuf>("restart_index").cloned()
+    }
+
+    fn extract_dump_symbol_stats_argument(matches: &ArgMatches) -> Option<PathBuf> {
+        matches.get_one::<PathBuf>("dump_symbol_stats").cloned()
+    }
+
+    fn extract_huffman_tables_argument(matches: &ArgMatches) -> Option<PathBuf> {
+        matches.get_one::<PathBuf>("huffman_tables").cloned()
+    }
+
+    fn extract_dump_huffman_argument(matches: &ArgMatches) -> Option<PathBuf> {
+        matches.get_one::<PathBuf>("dump_huffman").cloned()
+    }
+
+    fn extract_invert_argument(matches: &ArgMatches) -> bool {
+        matches.get_flag("invert")
+    }
+
+    fn extract_brightness_argument(matches: &ArgMatches) -> f32 {
+        matches
+            .get_one::<f32>("brightness")
+            .expect("Brightness must be provided, but was unset")
+            .to_owned()
+    }
+
+    fn extract_contrast_argument(matches: &ArgMatches) -> f32 {
+        matches
+            .get_one::<f32>("contrast")
+            .expect("Contrast must be provided, but was unset")
+            .to_owned()
+    }
+
+    fn extract_saturation_argument(matches: &ArgMatches) -> f32 {
+        matches
+            .get_one::<f32>("saturation")
+            .expect("Saturation must be provided, but was unset")
+            .to_owned()
+    }
+
+    fn extract_crop_argument(matches: &ArgMatches) -> Option<Crop> {
+        matches.get_one::<Crop>("crop").copied()
+    }
+
+    fn extract_rotate_argument(matches: &ArgMatches) -> Rotation {
+        matches
+            .get_one::<Rotation>("rotate")
+            .expect("Rotation must be provided, but was unset")
+            .to_owned()
+    }
+
+    fn extract_resize_argument(matches: &ArgMatches) -> Option<Resize> {
+        matches.get_one::<Resize>("resize").copied()
+    }
+
+    fn extract_smooth_argument(matches: &ArgMatches) -> u8 {
+        matches
+            .get_one::<u8>("smooth")
+            .copied()
+            .expect("Smoothing factor must be provided, but was unset")
+    }
+
+    fn extract_gps_argument(matches: &ArgMatches) -> Option<GpsPosition> {
+        matches.get_one::<GpsPosition>("gps").copied()
+    }
+
+    fn extract_gps_file_argument(matches: &ArgMatches) -> Option<PathBuf> {
+        matches.get_one::<PathBuf>("gps_file").cloned()
+    }
+
+    fn extract_user_comment_argument(matches: &ArgMatches) -> Option<String> {
+        matches.get_one::<String>("user_comment").cloned()
+    }
+
+    fn extract_timestamps_argument(matches: &ArgMatches) -> bool {
+        !matches.get_flag("no_timestamps")
+    }
+
+    fn extract_xmp_argument(matches: &ArgMatches) -> Option<XmpPacket> {
+        matches.get_one::<XmpPacket>("xmp").cloned()
+    }
+
+    fn extract_xmp_file_argument(matches: &ArgMatches) -> Option<PathBuf> {
+        matches.get_one::<PathBuf>("xmp_file").cloned()
+    }
+
+    fn extract_thumbnail_argument(matches: &ArgMatches) -> Option<ThumbnailSize> {
+        matches.get_one::<ThumbnailSize>("thumbnail").copied()
+    }
+
+    fn extract_dpi_argument(matches: &ArgMatches) -> Option<u16> {
+        matches.get_one::<u16>("dpi").copied()
+    }
+
+    fn extract_density_unit_argument(matches: &ArgMatches) -> DensityUnit {
+        *matches
+            .get_one::<DensityUnit>("density_unit")
+            .expect("Density unit must be provided, but was unset")
+    }
+
+    fn extract_adobe_argument(matches: &ArgMatches) -> bool {
+        matches.get_flag("adobe")
+    }
+
+    fn extract_dnl_argument(matches: &ArgMatches) -> bool {
+        matches.get_flag("dnl")
+    }
+
+    fn extract_combine_tables_argument(matches: &ArgMatches) -> bool {
+        matches.get_flag("combine_tables")
+    }
+
+    fn extract_comment_argument(matches: &ArgMatches) -> Vec<String> {
+        matches
+            .get_many::<String>("comment")
+            .map_or_else(Vec::new, |comments| comments.cloned().collect())
+    }
+
+    fn extract_embed_settings_argument(matches: &ArgMatches) -> bool {
+        matches.get_flag("embed_settings")
+    }
+
+    fn extract_create_dirs_argument(matches: &ArgMatches) -> bool {
+        matches.get_flag("create_dirs")
+    }
+
+    fn extract_metrics_argument(matches: &ArgMatches) -> bool {
+        matches.get_flag("metrics")
+    }
+
+    fn extract_timings_argument(matches: &ArgMatches) -> bool {
+        matches.get_flag("timings")
+    }
+
+    fn extract_verbose_argument(matches: &ArgMatches) -> bool {
+        matches.get_flag("verbose")
+    }
+
+    fn extract_max_pixels_argument(matches: &ArgMatches) -> u64 {
+        matches
+            .get_one::<u64>("max_pixels")
+            .expect("Max pixels must be provided, but was unset")
+            .to_owned()
+    }
+
+    fn extract_bench_arguments(matches: &ArgMatches) -> BenchmarkArguments {
+        let (width, height) = Self::extract_bench_size_argument(matches);
+        BenchmarkArguments {
+            thread_counts: Self::extract_bench_threads_argument(matches),
+            width,
+            height,
+            rounds: Self::extract_bench_rounds_argument(matches),
+        }
+    }
+
+    fn extract_bench_threads_argument(matches: &ArgMatches) -> Vec<usize> {
+        matches
+            .get_many::<usize>("threads")
+            .expect("Threads must be provided, but were unset")
+            .copied()
+            .collect()
+    }
+
+    fn extract_bench_size_argument(matches: &ArgMatches) -> (u16, u16) {
+        matches
+            .get_one::<(u16, u16)>("size")
+            .expect("Size must be provided, but was unset")
+            .to_owned()
+    }
+
+    fn extract_bench_rounds_argument(matches: &ArgMatches) -> usize {
+        matches
+            .get_one::<usize>("rounds")
+            .expect("Rounds must be provided, but were unset")
+            .to_owned()
+    }
+}
+
+impl Default for CLIParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn get_number_of_threads() -> io::Result<usize> {
+    Ok(thread::available_parallelism()?.get())
+}
+
+fn parse_brightness(value: &str) -> Result<f32, String> {
+    let brightness: f32 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if !(-1.0..=1.0).contains(&brightness) {
+        return Err(format!("{} is not in range -1.0 to 1.0", brightness));
+    }
+    Ok(brightness)
+}
+
+fn parse_non_negative_factor(value: &str) -> Result<f32, String> {
+    let factor: f32 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if !factor.is_finite() || factor < 0.0 {
+        return Err(format!("{} must be a finite, non negative number", factor));
+    }
+    Ok(factor)
+}
+
+fn parse_positive_count(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) | Err(_) => Err(format!("'{}' is not a positive number", value)),
+        Ok(count) => Ok(count),
+    }
+}
+
+fn parse_target_size(value: &str) -> Result<u64, String> {
+    let unit_start = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(unit_start);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{}' is not a size like 200kB", value))?;
+    let unit_size = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "mb" => 1000 * 1000,
+        "kib" => 1024,
+        "mib" => 1024 * 1024,
+        _ => return Err(format!("'{}' is not a unit of B, kB, MB, KiB or MiB", unit)),
+    };
+    let size = (number * unit_size as f64).round();
+    if size < 1.0 || size > u64::MAX as f64 {
+        return Err(format!("'{}' is not a size of at least 1 byte", value));
+    }
+    Ok(size as u64)
+}
+
+fn parse_quant_mix(value: &str) -> Result<QuantizationTableMix, String> {
+    let parse_component = |component: &str| {
+        let (name, weight) = component
+            .split_once(':')
+            .ok_or_else(|| format!("'{}' is not of the form PRESET:WEIGHT", component))?;
+        let preset = QuantizationTablePreset::from_str(name, true)
+            .map_err(|_| format!("'{}' is not a quantization table preset", name))?;
+        Ok::<_, String>((preset, parse_non_negative_factor(weight)?))
+    };
+    let (first, second) = value
+        .split_once(',')
+        .ok_or_else(|| format!("'{}' is not of the form PRESET:WEIGHT,PRESET:WEIGHT", value))?;
+    let (first, second) = (parse_component(first)?, parse_component(second)?);
+    if first.1 + second.1 <= 0.0 {
+        return Err("weights must not both be zero".to_string());
+    }
+    Ok(QuantizationTableMix::new(first, second))
+}
+
+fn parse_dead_zone(value: &str) -> Result<DeadZone, String> {
+    let (threshold, start_index) = value.split_once(':').unwrap_or((value, "1"));
+    let threshold = threshold.parse::<u16>().map_err(|_| {
+        format!(
+            "'{}' is not a threshold in range 0 to {}",
+            threshold,
+            u16::MAX
+        )
+    })?;
+    match start_index.parse::<u8>() {
+        Ok(start_index @ 1..=63) => Ok(DeadZone::new(threshold, start_index)),
+        _ => Err(format!(
+            "'{}' is not an AC index in range 1 to 63",
+            start_index
+        )),
+    }
+}
+
+fn parse_size(value: &str) -> Result<(u16, u16), String> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| format!("'{}' is not of the form WIDTHxHEIGHT", value))?;
+    let parse_dimension = |dimension: &str| match dimension.parse::<u16>() {
+        Ok(0) | Err(_) => Err(format!(
+            "'{}' is not a dimension in range 1 to {}",
+            dimension,
+            u16::MAX
+        )),
+        Ok(dimension) => Ok(dimension),
+    };
+    Ok((parse_dimension(width)?, parse_dimension(height)?))
+}
+
+fn parse_resize(value: &str) -> Result<Resize, String> {
+    let (width, height) = parse_size(value)?;
+    Ok(Resize::new(width, height))
+}
+
+fn parse_thumbnail_size(value: &str) -> Result<ThumbnailSize, String> {
+    let (width, height) = parse_size(value)?;
+    ThumbnailSize::new(width, height).map_err(|e| e.to_string())
+}
+
+fn parse_crop(value: &str) -> Result<Crop, String> {
+    let mut parts = value.split('+');
+    let size = parts.next().unwrap_or_default();
+    let (width, height) = parse_size(size)?;
+    let mut parse_offset = || {
+        let offset = parts
+            .next()
+            .ok_or_else(|| format!("'{}' is not of the form WIDTHxHEIGHT+X+Y", value))?;
+        offset
+            .parse::<u16>()
+            .map_err(|_| format!("'{}' is not an offset in range 0 to {}", offset, u16::MAX))
+    };
+    let x = parse_offset()?;
+    let y = parse_offset()?;
+    if parts.next().is_some() {
+        return Err(format!("'{}' is not of the form WIDTHxHEIGHT+X+Y", value));
+    }
+    Ok(Crop::new(x, y, width, height))
+}
+
+fn parse_xmp_packet(value: &str) -> Result<XmpPacket, String> {
+    XmpPacket::new(value.to_string()).map_err(|e| e.to_string())
+}
+
+fn parse_comment(value: &str) -> Result<String, String> {
+    if value.len() > MAX_COMMENT_LENGTH {
+        return Err(Error::CommentTooLong(value.len()).to_string());
+    }
+    Ok(value.to_string())
+}
+
+fn parse_rotation(value: &str) -> Result<Rotation, String> {
+    value
+        .parse()
+        .ok()
+        .and_then(Rotation::from_degrees)
+        .ok_or_else(|| format!("'{}' is not one of 0, 90, 180 or 270", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{error::ErrorKind, Command};
+
+    use super::{
+        CLIParser, ChromaSiting, ChromaSubsamplingPreset, ColorMatrix, ColorRange, Crop,
+        DCTAlgorithm, DeadZone, DensityUnit, GpsPosition, HuffmanTableMode, QuantizationTableMix,
+        QuantizationTablePreset, Resize, Rotation, SubsamplingMethod, ThumbnailSize, XmpPacket,
+    };
+    use crate::Task;
+
+    const PROGRAM_NAME_ARGUMENT: &str = "test_program_name";
+
+    #[test]
+    fn parse_input_file_argument() {
+        let input_file_name = "testfile.ppm";
+        let command = Command::new("test");
+        let command = CLIParser::register_input_file_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, input_file_name]);
+        let input_file = CLIParser::extract_input_file_argument(&matches);
+        assert_eq!(input_file.file_name().unwrap(), input_file_name);
+    }
+
+    #[test]
+    fn parse_mmap_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_mmap_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--mmap"]);
+        assert!(CLIParser::extract_mmap_argument(&matches));
+    }
+
+    #[test]
+    fn parse_output_file_argument() {
+        let output_file_name = "testfile.ppm";
+        let command = Command::new("test");
+        let command = CLIParser::register_output_file_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, output_file_name]);
+        let output_file = CLIParser::extract_output_file_argument(&matches);
+        assert_eq!(output_file.file_name().unwrap(), output_file_name);
+    }
+
+    #[test]
+    fn parse_bits_per_channel_argument() {
+        let expected_bits_per_channel = 12;
+        let command = Command::new("test");
+        let command = CLIParser::register_bits_per_channel_argument(command);
+        let matches =
+            command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--bits_per_channel", "12"]);
+        let bits_per_channel = CLIParser::extract_bits_per_channel_argument(&matches);
+        assert_eq!(bits_per_channel, expected_bits_per_channel);
+    }
+
+    #[test]
+    fn parse_bits_per_channel_illegal_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_bits_per_channel_argument(command);
+        let result =
+            command.try_get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--bits_per_channel", "16"]);
+        if let Err(error) = result {
+            assert_eq!(error.kind(), ErrorKind::InvalidValue);
+        } else {
+            panic!("Illegal value for bits_per_channel not detected");
+        }
+    }
+
+    #[test]
+    fn parse_chroma_subsampling_preset_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_chroma_subsampling_preset_argument(command);
+        let matches = command.get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--chroma_subsampling_preset",
+            "P444",
+        ]);
+        let actual_preset = CLIParser::extract_chroma_subsampling_preset_argument(&matches);
+        let expected_preset = ChromaSubsamplingPreset::P444;
+        assert_eq!(actual_preset, expected_preset);
+    }
+
+    #[test]
+    fn parse_chroma_subsampling_rates_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_chroma_subsampling_preset_argument(command);
+        let parse = |value: &str| {
+            command
+                .clone()
+                .try_get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--subsampling", value])
+                .map(|matches| CLIParser::extract_chroma_subsampling_preset_argument(&matches))
+        };
+        assert_eq!(parse("4:2:2").unwrap(), ChromaSubsamplingPreset::P422);
+        assert_eq!(parse("2x2").unwrap(), ChromaSubsamplingPreset::P420);
+        assert_eq!(
+            parse("3x1").unwrap(),
+            ChromaSubsamplingPreset::Custom {
+                horizontal_rate: 3,
+                vertical_rate: 1
+            }
+        );
+        assert_eq!(
+            parse("4:1:0").unwrap(),
+            ChromaSubsamplingPreset::Custom {
+                horizontal_rate: 4,
+                vertical_rate: 2
+            }
+        );
+        // 9 luma and 2 chroma blocks exceed the 10 blocks of an MCU
+        assert_eq!(parse("3x3").unwrap_err().kind(), ErrorKind::ValueValidation);
+        assert_eq!(
+            parse("4:2:1").unwrap_err().kind(),
+            ErrorKind::ValueValidation
+        );
+    }
+
+    #[test]
+    fn parse_subsampling_method_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_subsampling_method_argument(command);
+        let matches = command.clone().get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--subsampling-method",
+            "gaussian",
+        ]);
+        assert_eq!(
+            CLIParser::extract_subsampling_method_argument(&matches),
+            Some(SubsamplingMethod::Gaussian)
+        );
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT]);
+        assert_eq!(
+            CLIParser::extract_subsampling_method_argument(&matches),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_chroma_siting_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_chroma_siting_argument(command);
+        let matches = command.clone().get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--chroma-siting",
+            "co-sited",
+        ]);
+        assert_eq!(
+            CLIParser::extract_chroma_siting_argument(&matches),
+            ChromaSiting::CoSited
+        );
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT]);
+        assert_eq!(
+            CLIParser::extract_chroma_siting_argument(&matches),
+            ChromaSiting::Centered
+        );
+    }
+
+    #[test]
+    fn parse_color_matrix_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_color_matrix_argument(command);
+        let matches = command.clone().get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--color-matrix",
+            "bt709",
+        ]);
+        assert_eq!(
+            CLIParser::extract_color_matrix_argument(&matches),
+            ColorMatrix::Bt709
+        );
+        let matches =
+            command
+                .clone()
+                .get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--color-matrix", "2020"]);
+        assert_eq!(
+            CLIParser::extract_color_matrix_argument(&matches),
+            ColorMatrix::Bt2020
+        );
+        let matches = command
+            .clone()
+            .get_matches_from(vec![PROGRAM_NAME_ARGUMENT]);
+        assert_eq!(
+            CLIParser::extract_color_matrix_argument(&matches),
+            ColorMatrix::Bt601
+        );
+        let result =
+            command.try_get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--color-matrix", "bt470"]);
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidValue);
+    }
+
+    #[test]
+    fn parse_color_range_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_color_range_argument(command);
+        let matches = command.clone().get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--color-range",
+            "limited",
+        ]);
+        assert_eq!(
+            CLIParser::extract_color_range_argument(&matches),
+            ColorRange::Limited
+        );
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT]);
+        assert_eq!(
+            CLIParser::extract_color_range_argument(&matches),
+            ColorRange::Full
+        );
+    }
+
+    #[test]
+    fn parse_grayscale_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_grayscale_argument(command);
+        let matches = command
+            .clone()
+            .get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--grayscale"]);
+        assert!(CLIParser::extract_grayscale_argument(&matches));
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT]);
+        assert!(!CLIParser::extract_grayscale_argument(&matches));
+    }
+
+    #[test]
+    fn parse_cmyk_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_grayscale_argument(command);
+        let command = CLIParser::register_cmyk_argument(command);
+        let matches = command
+            .clone()
+            .get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--cmyk"]);
+        assert!(CLIParser::extract_cmyk_argument(&matches));
+        let result =
+            command.try_get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--cmyk", "--grayscale"]);
+        assert!(result.is_err(), "CMYK images have no grayscale encoding");
+    }
+
+    #[test]
+    fn parse_background_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_background_argument(command);
+        let matches = command
+            .clone()
+            .get_matches_from(vec![PROGRAM_NAME_ARGUMENT]);
+        assert_eq!(
+            CLIParser::extract_background_argument(&matches).to_rgb8(),
+            [255, 255, 255]
+        );
+        let matches = command.clone().get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--background",
+            "#00ff80",
+        ]);
+        assert_eq!(
+            CLIParser::extract_background_argument(&matches).to_rgb8(),
+            [0, 255, 128]
+        );
+        let result =
+            command.try_get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--background", "white"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_number_of_threads_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_threads_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--threads", "5"]);
+        let actual = CLIParser::extract_threads_argument(&matches);
+        let expected = 5;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn parse_quality_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_quality_argument(command);
+        let matches = command
+            .clone()
+            .get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "-q", "85"]);
+        assert_eq!(CLIParser::extract_quality_argument(&matches), Some(85));
+        let matches = command
+            .clone()
+            .get_matches_from(vec![PROGRAM_NAME_ARGUMENT]);
+        assert_eq!(CLIParser::extract_quality_argument(&matches), None);
+        for illegal in ["0", "101"] {
+            let result = command.clone().try_get_matches_from(vec![
+                PROGRAM_NAME_ARGUMENT,
+                "--quality",
+                illegal,
+            ]);
+            assert_eq!(result.unwrap_err().kind(), ErrorKind::ValueValidation);
+        }
+    }
+
+    #[test]
+    fn parse_chroma_quality_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_quality_argument(command);
+        let command = CLIParser::register_chroma_quality_argument(command);
+        let matches = command.clone().get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--quality",
+            "85",
+            "--chroma-quality",
+            "70",
+        ]);
+        assert_eq!(CLIParser::extract_quality_argument(&matches), Some(85));
+        assert_eq!(
+            CLIParser::extract_chroma_quality_argument(&matches),
+            Some(70)
+        );
+        let result =
+            command.try_get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--chroma-quality", "0"]);
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn parse_target_size_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_quality_argument(command);
+        let command = CLIParser::register_target_size_argument(command);
+        for (size, bytes) in [
+            ("5000", 5000),
+            ("200kB", 200_000),
+            ("1.5MB", 1_500_000),
+            ("64KiB", 65_536),
+            ("2mib", 2_097_152),
+        ] {
+            let matches = command.clone().get_matches_from(vec![
+                PROGRAM_NAME_ARGUMENT,
+                "--target-size",
+                size,
+            ]);
+            assert_eq!(
+                CLIParser::extract_target_size_argument(&matches),
+                Some(bytes)
+            );
+        }
+        let matches = command
+            .clone()
+            .get_matches_from(vec![PROGRAM_NAME_ARGUMENT]);
+        assert_eq!(CLIParser::extract_target_size_argument(&matches), None);
+        for illegal in ["0", "kB", "200GB", "0.1B"] {
+            let result = command.clone().try_get_matches_from(vec![
+                PROGRAM_NAME_ARGUMENT,
+                "--target-size",
+                illegal,
+            ]);
+            assert_eq!(result.unwrap_err().kind(), ErrorKind::ValueValidation);
+        }
+        let result = command.clone().try_get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--target-size",
+            "200kB",
+            "-q",
+            "85",
+        ]);
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn parse_trellis_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_trellis_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--trellis"]);
+        assert!(CLIParser::extract_trellis_argument(&matches));
+    }
+
+    #[test]
+    fn parse_adaptive_quantization_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_adaptive_quantization_argument(command);
+        let matches = command.clone().get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--adaptive-quantization",
+            "1.5",
+        ]);
+        assert_eq!(
+            CLIParser::extract_adaptive_quantization_argument(&matches),
+            Some(1.5)
+        );
+        let matches = command
+            .clone()
+            .get_matches_from(vec![PROGRAM_NAME_ARGUMENT]);
+        assert_eq!(
+            CLIParser::extract_adaptive_quantization_argument(&matches),
+            None
+        );
+        let result = command.try_get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--adaptive-quantization",
+            "-1",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_quant_mix_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_quantization_table_preset_argument(command);
+        let command = CLIParser::register_quant_mix_argument(command);
+        let parse = |arguments: &[&str]| {
+            command
+                .clone()
+                .try_get_matches_from([&[PROGRAM_NAME_ARGUMENT], arguments].concat())
+                .map(|matches| CLIParser::extract_quant_mix_argument(&matches))
+        };
+        assert_eq!(
+            parse(&["--quant-mix", "Flat:0.3,spec:0.7"]).unwrap(),
+            Some(QuantizationTableMix::new(
+                (QuantizationTablePreset::Flat, 0.3),
+                (QuantizationTablePreset::Specification, 0.7)
+            ))
+        );
+        assert_eq!(parse(&[]).unwrap(), None);
+        assert!(parse(&["--quant-mix", "Flat:0.3"]).is_err());
+        assert!(parse(&["--quant-mix", "Flat:0,Specification:0"]).is_err());
+        assert!(parse(&["--quant-mix", "Flat:1,Unknown:1"]).is_err());
+        assert!(parse(&[
+            "--quant-mix",
+            "Flat:1,Specification:1",
+            "--quantization_table",
+            "Flat"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn parse_dead_zone_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_dead_zone_argument(command);
+        let parse = |value: &str| {
+            command
+                .clone()
+                .try_get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--dead-zone", value])
+                .map(|matches| CLIParser::extract_dead_zone_argument(&matches))
+        };
+        assert_eq!(parse("2").unwrap(), Some(DeadZone::new(2, 1)));
+        assert_eq!(parse("3:10").unwrap(), Some(DeadZone::new(3, 10)));
+        assert!(parse("3:0").is_err());
+        assert!(parse("3:64").is_err());
+        assert!(parse("-1").is_err());
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT]);
+        assert_eq!(CLIParser::extract_dead_zone_argument(&matches), None);
+    }
+
+    #[test]
+    fn parse_chroma_red_arguments() {
+        let command = Command::new("test");
+        let command = CLIParser::register_chroma_red_preset_argument(command);
+        let command = CLIParser::register_chroma_red_table_file_argument(command);
+        let matches = command.clone().get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--chroma-red-preset",
+            "Flat",
+        ]);
+        assert_eq!(
+            CLIParser::extract_chroma_red_preset_argument(&matches),
+            Some(QuantizationTablePreset::Flat)
+        );
+        assert_eq!(
+            CLIParser::extract_chroma_red_table_file_argument(&matches),
+            None
+        );
+        let result = command.try_get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--chroma-red-preset",
+            "Flat",
+            "--chroma-red-table-file",
+            "cr.txt",
+        ]);
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn parse_dct_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_dct_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--dct", "Separated"]);
+        let actual = CLIParser::extract_dct_argument(&matches);
+        assert_eq!(actual, DCTAlgorithm::Separated);
+    }
+
+    #[test]
+    fn parse_tune_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_tune_argument(command);
+        let command = CLIParser::register_dct_argument(command);
+        let command = CLIParser::register_huffman_argument(command);
+        let command = CLIParser::register_chroma_subsampling_preset_argument(command);
+        let command = CLIParser::register_quantization_table_preset_argument(command);
+        let matches =
+            command
+                .clone()
+                .get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--tune", "best"]);
+        assert_eq!(
+            CLIParser::extract_dct_argument(&matches),
+            DCTAlgorithm::Separated
+        );
+        assert_eq!(
+            CLIParser::extract_chroma_subsampling_preset_argument(&matches),
+            ChromaSubsamplingPreset::P444
+        );
+        assert_eq!(
+            CLIParser::extract_quantization_table_preset_argument(&matches),
+            QuantizationTablePreset::ImageMagick
+        );
+        let matches = command.get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--tune",
+            "fastest",
+            "--huffman",
+            "optimized",
+            "--dct",
+            "Simple",
+        ]);
+        assert_eq!(
+            CLIParser::extract_huffman_argument(&matches),
+            HuffmanTableMode::Optimized
+        );
+        assert_eq!(
+            CLIParser::extract_dct_argument(&matches),
+            DCTAlgorithm::Simple
+        );
+        assert_eq!(
+            CLIParser::extract_chroma_subsampling_preset_argument(&matches),
+            ChromaSubsamplingPreset::P420
+        );
+    }
+
+    #[test]
+    fn parse_high_precision_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_high_precision_argument(command);
+        let matches = command
+            .clone()
+            .get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--high-precision"]);
+        assert!(CLIParser::extract_high_precision_argument(&matches));
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT]);
+        assert!(!CLIParser::extract_high_precision_argument(&matches));
+    }
+
+    #[test]
+    fn parse_huffman_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_huffman_argument(command);
+        let matches =
+            command
+                .clone()
+                .get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--huffman", "standard"]);
+        assert_eq!(
+            CLIParser::extract_huffman_argument(&matches),
+            HuffmanTableMode::Standard
+        );
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT]);
+        assert_eq!(
+            CLIParser::extract_huffman_argument(&matches),
+            HuffmanTableMode::Optimized
+        );
+    }
+
+    #[test]
+    fn parse_dump_symbol_stats_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_dump_symbol_stats_argument(command);
+        let matches = command.get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--dump-symbol-stats",
+            "stats.csv",
+        ]);
+        assert_eq!(
+            CLIParser::extract_dump_symbol_stats_argument(&matches),
+            Some("stats.csv".into())
+        );
+    }
+
+    #[test]
+    fn parse_huffman_tables_arguments() {
+        let command = Command::new("test");
+        let command = CLIParser::register_huffman_tables_argument(command);
+        let command = CLIParser::register_dump_huffman_argument(command);
+        let matches = command.get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--huffman-tables",
+            "reference.json",
+            "--dump-huffman",
+            "tables.json",
+        ]);
+        assert_eq!(
+            CLIParser::extract_huffman_tables_argument(&matches),
+            Some("reference.json".into())
+        );
+        assert_eq!(
+            CLIParser::extract_dump_huffman_argument(&matches),
+            Some("tables.json".into())
+        );
+    }
+
+    #[test]
+    fn parse_max_code_length_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_max_code_length_argument(command);
+        let matches = command
+            .clone()
+            .get_matches_from(vec![PROGRAM_NAME_ARGUMENT]);
+        assert_eq!(CLIParser::extract_max_code_length_argument(&matches), 16);
+        let matches = command.clone().get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--max-code-length",
+            "12",
+        ]);
+        assert_eq!(CLIParser::extract_max_code_length_argument(&matches), 12);
+        for length in ["7", "17"] {
+            let result = command.clone().try_get_matches_from(vec![
+                PROGRAM_NAME_ARGUMENT,
+                "--max-code-length",
+                length,
+            ]);
+            assert_eq!(result.unwrap_err().kind(), ErrorKind::ValueValidation);
+        }
+    }
+
+    #[test]
+    fn parse_restart_arguments() {
+        let command = Command::new("test");
+        let command = CLIParser::register_restart_interval_argument(command);
+        let command = CLIParser::register_restart_index_argument(command);
+        let matches = command.clone().get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--restart-interval",
+            "4",
+            "--restart-index",
+            "index.txt",
+        ]);
+        assert_eq!(
+            CLIParser::extract_restart_interval_argument(&matches),
+            Some(4)
+        );
+        assert_eq!(
+            CLIParser::extract_restart_index_argument(&matches),
+            Some("index.txt".into())
+        );
+        let result = command.clone().try_get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--restart-index",
+            "index.txt",
+        ]);
+        assert_eq!(
+            result.unwrap_err().kind(),
+            ErrorKind::MissingRequiredArgument
+        );
+        let result =
+            command.try_get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--restart-interval", "0"]);
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn parse_bench_subcommand() {
+        let mut cli_parser = CLIParser::new();
+        let task = cli_parser.parse(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "bench",
+            "--threads",
+            "1,4",
+            "--size",
+            "64x32",
+            "--rounds",
+            "2",
+        ]);
+        let Task::Benchmark(arguments) = task else {
+            panic!("bench subcommand was not detected");
+        };
+        assert_eq!(arguments.thread_counts, [1, 4]);
+        assert_eq!((arguments.width, arguments.height), (64, 32));
+        assert_eq!(arguments.rounds, 2);
+    }
+
+    #[test]
+    fn parse_invert_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_invert_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--invert"]);
+        assert!(CLIParser::extract_invert_argument(&matches));
+    }
+
+    #[test]
+    fn parse_color_adjustment_arguments() {
+        let command = Command::new("test");
+        let command = CLIParser::register_brightness_argument(command);
+        let command = CLIParser::register_contrast_argument(command);
+        let command = CLIParser::register_saturation_argument(command);
+        let matches = command.get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--brightness",
+            "-0.25",
+            "--contrast",
+            "1.5",
+            "--saturation",
+            "0",
+        ]);
+        assert_eq!(CLIParser::extract_brightness_argument(&matches), -0.25);
+        assert_eq!(CLIParser::extract_contrast_argument(&matches), 1.5);
+        assert_eq!(CLIParser::extract_saturation_argument(&matches), 0.0);
+    }
+
+    #[test]
+    fn parse_illegal_brightness_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_brightness_argument(command);
+        let result = command.try_get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--brightness", "2"]);
+        if let Err(error) = result {
+            assert_eq!(error.kind(), ErrorKind::ValueValidation);
+        } else {
+            panic!("Illegal value for brightness not detected");
+        }
+    }
+
+    #[test]
+    fn parse_geometry_arguments() {
+        let command = Command::new("test");
+        let command = CLIParser::register_crop_argument(command);
+        let command = CLIParser::register_rotate_argument(command);
+        let command = CLIParser::register_resize_argument(command);
+        let matches = command.get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--crop",
+            "640x480+10+20",
+            "--rotate",
+            "270",
+            "--resize",
+            "320x240",
+        ]);
+        assert_eq!(
+            CLIParser::extract_crop_argument(&matches),
+            Some(Crop::new(10, 20, 640, 480))
+        );
+        assert_eq!(
+            CLIParser::extract_rotate_argument(&matches),
+            Rotation::Clockwise270
+        );
+        assert_eq!(
+            CLIParser::extract_resize_argument(&matches),
+            Some(Resize::new(320, 240))
+        );
+    }
+
+    #[test]
+    fn parse_smooth_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_smooth_argument(command);
+        let matches =
+            command
+                .clone()
+                .get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--smooth", "30"]);
+        assert_eq!(CLIParser::extract_smooth_argument(&matches), 30);
+        let result = command.try_get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--smooth", "101"]);
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn parse_illegal_geometry_arguments() {
+        for (name, value) in [
+            ("--crop", "640x480+10"),
+            ("--crop", "0x480+0+0"),
+            ("--rotate", "45"),
+            ("--resize", "320"),
+        ] {
+            let command = Command::new("test");
+            let command = CLIParser::register_crop_argument(command);
+            let command = CLIParser::register_rotate_argument(command);
+            let command = CLIParser::register_resize_argument(command);
+            let result = command.try_get_matches_from(vec![PROGRAM_NAME_ARGUMENT, name, value]);
+            if let Err(error) = result {
+                assert_eq!(error.kind(), ErrorKind::ValueValidation);
+            } else {
+                panic!("Illegal value '{}' for {} not detected", value, name);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_exif_arguments() {
+        let command = Command::new("test");
+        let command = CLIParser::register_gps_argument(command);
+        let command = CLIParser::register_gps_file_argument(command);
+        let command = CLIParser::register_user_comment_argument(command);
+        let matches = command.get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--gps",
+            "-33.5,151.25,12",
+            "--user-comment",
+            "frame 17",
+        ]);
+        assert_eq!(
+            CLIParser::extract_gps_argument(&matches),
+            Some(GpsPosition::new(-33.5, 151.25, Some(12.0)).unwrap())
+        );
+        assert_eq!(CLIParser::extract_gps_file_argument(&matches), None);
+        assert_eq!(
+            CLIParser::extract_user_comment_argument(&matches).as_deref(),
+            Some("frame 17")
+        );
+    }
+
+    #[test]
+    fn parse_conflicting_gps_arguments() {
+        let command = Command::new("test");
+        let command = CLIParser::register_gps_argument(command);
+        let command = CLIParser::register_gps_file_argument(command);
+        let result = command.try_get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--gps",
+            "1,2",
+            "--gps-file",
+            "position.txt",
+        ]);
+        if let Err(error) = result {
+            assert_eq!(error.kind(), ErrorKind::ArgumentConflict);
+        } else {
+            panic!("Conflicting GPS arguments not detected");
+        }
+    }
+
+    #[test]
+    fn parse_no_timestamps_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_no_timestamps_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--no-timestamps"]);
+        assert!(!CLIParser::extract_timestamps_argument(&matches));
+    }
+
+    #[test]
+    fn parse_xmp_arguments() {
+        let command = Command::new("test");
+        let command = CLIParser::register_xmp_argument(command);
+        let command = CLIParser::register_xmp_file_argument(command);
+        let matches =
+            command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--xmp", "<x:xmpmeta/>"]);
+        assert_eq!(
+            CLIParser::extract_xmp_argument(&matches)
+                .as_ref()
+                .map(XmpPacket::as_str),
+            Some("<x:xmpmeta/>")
+        );
+        assert_eq!(CLIParser::extract_xmp_file_argument(&matches), None);
+    }
+
+    #[test]
+    fn parse_thumbnail_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_thumbnail_argument(command);
+        let matches =
+            command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--thumbnail", "160x120"]);
+        assert_eq!(
+            CLIParser::extract_thumbnail_argument(&matches),
+            Some(ThumbnailSize::new(160, 120).unwrap())
+        );
+        for value in ["160", "256x16", "200x200"] {
+            let command = Command::new("test");
+            let command = CLIParser::register_thumbnail_argument(command);
+            let result =
+                command.try_get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--thumbnail", value]);
+            if let Err(error) = result {
+                assert_eq!(error.kind(), ErrorKind::ValueValidation);
+            } else {
+                panic!("Illegal thumbnail size '{}' not detected", value);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_dpi_and_density_unit_arguments() {
+        let command = || {
+            let command = Command::new("test");
+            let command = CLIParser::register_dpi_argument(command);
+            CLIParser::register_density_unit_argument(command)
+        };
+        let matches = command().get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--dpi", "300"]);
+        assert_eq!(CLIParser::extract_dpi_argument(&matches), Some(300));
+        assert_eq!(
+            CLIParser::extract_density_unit_argument(&matches),
+            DensityUnit::DotsPerInch
+        );
+        let matches = command().get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--dpi",
+            "118",
+            "--density-unit",
+            "dpcm",
+        ]);
+        assert_eq!(
+            CLIParser::extract_density_unit_argument(&matches),
+            DensityUnit::DotsPerCentimeter
+        );
+        let result =
+            command().try_get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--density-unit", "dpcm"]);
+        assert!(result.is_err(), "the unit requires a density");
+        let result = command().try_get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--dpi", "0"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_adobe_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_adobe_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--adobe"]);
+        assert!(CLIParser::extract_adobe_argument(&matches));
+    }
+
+    #[test]
+    fn parse_dnl_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_dnl_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--dnl"]);
+        assert!(CLIParser::extract_dnl_argument(&matches));
+    }
+
+    #[test]
+    fn parse_combine_tables_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_combine_tables_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--combine-tables"]);
+        assert!(CLIParser::extract_combine_tables_argument(&matches));
+    }
+
+    #[test]
+    fn parse_repeated_comment_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_comment_argument(command);
+        let matches = command.get_matches_from(vec![
+            PROGRAM_NAME_ARGUMENT,
+            "--comment",
+            "batch 7",
+            "--comment",
+            "-- scanned",
+        ]);
+        assert_eq!(
+            CLIParser::extract_comment_argument(&matches),
+            ["batch 7", "-- scanned"]
+        );
+    }
+
+    #[test]
+    fn parse_too_long_comment_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_comment_argument(command);
+        let comment = "x".repeat(u16::MAX as usize);
+        let result =
+            command.try_get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--comment", &comment]);
+        if let Err(error) = result {
+            assert_eq!(error.kind(), ErrorKind::ValueValidation);
+        } else {
+            panic!("Too long comment not detected");
+        }
+    }
+
+    #[test]
+    fn parse_embed_settings_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_embed_settings_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--embed-settings"]);
+        assert!(CLIParser::extract_embed_settings_argument(&matches));
+    }
+
+    #[test]
+    fn parse_create_dirs_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_create_dirs_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--create-dirs"]);
+        assert!(CLIParser::extract_create_dirs_argument(&matches));
+    }
+
+    #[test]
+    fn parse_metrics_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_metrics_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--metrics"]);
+        assert!(CLIParser::extract_metrics_argument(&matches));
+    }
+
+    #[test]
+    fn parse_timings_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_timings_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT]);
+        assert!(!CLIParser::extract_timings_argument(&matches));
+        let command = Command::new("test");
+        let command = CLIParser::register_timings_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--timings"]);
+        assert!(CLIParser::extract_timings_argument(&matches));
+    }
+
+    #[test]
+    fn parse_verbose_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_verbose_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "-v"]);
+        assert!(CLIParser::extract_verbose_argument(&matches));
+    }
+
+    #[test]
+    fn parse_max_pixels_argument() {
+        let command = Command::new("test");
+        let command = CLIParser::register_max_pixels_argument(command);
+        let matches = command.get_matches_from(vec![PROGRAM_NAME_ARGUMENT, "--max-pixels", "1000"]);
+        assert_eq!(CLIParser::extract_max_pixels_argument(&matches), 1000);
+    }
+
+    #[test]
+    fn parse_required_arguments_only() {
+        let input_file_name = "inputfile.ppm";
+        let input_file_path = format!("/input_directory/{}", input_file_name);
+        let output_file_name = "outputfile.jpg";
+        let output_file_path = format!("/output_directory/{}", output_file_name);
+        let mut cli_parser = CLIParser::default();
+        let Task::Convert(arguments) = cli_parser.parse(vec![
+            PROGRAM_NAME_ARGUMENT,
+            &input_file_path,
+            &output_file_path,
+            "-t",
+            "8",
+        ]) else {
+            panic!("Conversion task expected");
+        };
+        assert_eq!(
+            arguments.input_file.file_name().unwrap(),
+            input_file_name,
+            "input file does not match"
+        );
+        assert_eq!(
+            arguments.output_file.file_name().unwrap(),
+            output_file_name,
+            "output file does not match"
+        );
+        assert_eq!(
+            arguments.bits_per_channel, 8,
+            "bits_per_channel does not match"
+        );
+        assert_eq!(
+            arguments.chroma_subsampling_preset,
+            ChromaSubsamplingPreset::P420,
+            "chroma_subsampling_preset does not match"
+        );
+        assert_eq!(
+            arguments.number_of_threads, 8,
+            "number_of_threads does not match"
+        );
+        assert!(!arguments.invert, "invert does not match");
+        assert_eq!(arguments.crop, None, "crop does not match");
+        assert_eq!(
+            arguments.rotation,
+            Rotation::None,
+            "rotation does not match"
+        );
+        assert_eq!(arguments.resize, None, "resize does not match");
+        assert_eq!(arguments.smoothing, 0, "smoothing does not match");
+        assert!(arguments.timestamps, "timestamps does not match");
+    }
+}
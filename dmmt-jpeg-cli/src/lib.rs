@@ -0,0 +1,755 @@
+use std::{
+    fmt::Display,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use benchmark::Benchmark;
+pub use cli::CLIParser;
+use dmmt_jpeg_core::{
+    cosine_transform::DCTAlgorithm,
+    image::{
+        metrics,
+        preprocess::{
+            ColorAdjustment, Crop, Invert, PreprocessingPipeline, Resize, Rotate, Rotation, Smooth,
+        },
+        reader::{
+            jpeg::JPEGImageReader, pam::PAMImageReader, tiff::TIFFImageReader, ImageReaderRegistry,
+            InputLimits,
+        },
+        subsampling::{ChromaSiting, ChromaSubsamplingPreset, SubsamplingMethod},
+        writer::jpeg::{
+            density::{DensityUnit, PixelDensity},
+            exif::{ExifDateTime, ExifMetadata, GpsPosition},
+            parse_huffman_tables, parse_quantization_table,
+            thumbnail::ThumbnailSize,
+            timings::Stage,
+            xmp::XmpPacket,
+            DeadZone, HuffmanTableMode, HuffmanTables, JpegImageWriter, JpegTransformationOptions,
+            QuantizationTableMix, QuantizationTablePreset, ENCODER_NAME,
+        },
+        CMYKImage, Image, ImageReader, ImageWriter, RGBAImage,
+    },
+    ColorMatrix, ColorRange, Error, RGBColorFormat, Result, Warnings,
+};
+use threadpool::ThreadPool;
+
+mod benchmark;
+mod cli;
+mod logger;
+
+/// Output path, which writes the JPEG to stdout instead of a file.
+const STDOUT_OUTPUT_PATH: &str = "-";
+
+pub struct Arguments {
+    input_file: PathBuf,
+    memory_map: bool,
+    output_file: PathBuf,
+    bits_per_channel: u8,
+    chroma_subsampling_preset: ChromaSubsamplingPreset,
+    chroma_subsampling_method: Option<SubsamplingMethod>,
+    chroma_siting: ChromaSiting,
+    color_matrix: ColorMatrix,
+    color_range: ColorRange,
+    grayscale: bool,
+    cmyk: bool,
+    background: RGBColorFormat<f32>,
+    number_of_threads: usize,
+    quantization_table_preset: QuantizationTablePreset,
+    quantization_table_mix: Option<QuantizationTableMix>,
+    quality: Option<u8>,
+    chroma_quality: Option<u8>,
+    target_size: Option<u64>,
+    trellis_quantization: bool,
+    adaptive_quantization: Option<f32>,
+    dead_zone: Option<DeadZone>,
+    chroma_red_preset: Option<QuantizationTablePreset>,
+    chroma_red_table_file: Option<PathBuf>,
+    dct_algorithm: DCTAlgorithm,
+    high_precision: bool,
+    huffman_table_mode: HuffmanTableMode,
+    max_huffman_code_length: u8,
+    restart_interval: Option<u16>,
+    restart_index_file: Option<PathBuf>,
+    symbol_stats_file: Option<PathBuf>,
+    huffman_tables_file: Option<PathBuf>,
+    dump_huffman_file: Option<PathBuf>,
+    invert: bool,
+    brightness: f32,
+    contrast: f32,
+    saturation: f32,
+    crop: Option<Crop>,
+    rotation: Rotation,
+    resize: Option<Resize>,
+    smoothing: u8,
+    gps_position: Option<GpsPosition>,
+    gps_sidecar_file: Option<PathBuf>,
+    user_comment: Option<String>,
+    timestamps: bool,
+    xmp_packet: Option<XmpPacket>,
+    xmp_file: Option<PathBuf>,
+    thumbnail_size: Option<ThumbnailSize>,
+    dpi: Option<u16>,
+    density_unit: DensityUnit,
+    adobe_marker: bool,
+    define_number_of_lines: bool,
+    combine_table_segments: bool,
+    comments: Vec<String>,
+    embed_settings: bool,
+    create_dirs: bool,
+    metrics: bool,
+    timings: bool,
+    max_pixel_count: u64,
+    verbose: bool,
+}
+
+impl Arguments {
+    /// Whether the JPEG is written to stdout, so messages have to go to stderr.
+    pub fn writes_to_stdout(&self) -> bool {
+        self.output_file.as_os_str() == STDOUT_OUTPUT_PATH
+    }
+
+    /// Prints the message to stdout or to stderr, when the JPEG is written to stdout.
+    pub fn print_message(&self, message: impl Display) {
+        if self.writes_to_stdout() {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+}
+
+pub struct BenchmarkArguments {
+    thread_counts: Vec<usize>,
+    width: u16,
+    height: u16,
+    rounds: usize,
+}
+
+/// The task selected on the command line.
+pub enum Task {
+    Convert(Box<Arguments>),
+    Benchmark(BenchmarkArguments),
+}
+
+impl From<&Arguments> for JpegTransformationOptions {
+    fn from(value: &Arguments) -> Self {
+        Self {
+            chroma_subsampling_preset: value.chroma_subsampling_preset,
+            chroma_subsampling_method: value.chroma_subsampling_method,
+            chroma_siting: value.chroma_siting,
+            color_matrix: value.color_matrix,
+            color_range: value.color_range,
+            grayscale: value.grayscale,
+            bits_per_channel: value.bits_per_channel,
+            quantization_table_preset: value.quantization_table_preset,
+            quantization_table_mix: value.quantization_table_mix,
+            quality: value.quality,
+            chroma_quality: value.chroma_quality,
+            target_size: value.target_size,
+            trellis_quantization: value.trellis_quantization,
+            adaptive_quantization: value.adaptive_quantization,
+            dead_zone: value.dead_zone,
+            chroma_red_quantization_table: value
+                .chroma_red_preset
+                .map(QuantizationTablePreset::chroma_table),
+            dct_algorithm: value.dct_algorithm,
+            high_precision: value.high_precision,
+            huffman_table_mode: value.huffman_table_mode,
+            huffman_tables: None,
+            max_huffman_code_length: value.max_huffman_code_length,
+            restart_interval: value.restart_interval,
+            exif_metadata: ExifMetadata {
+                software: Some(format!("{} {}", ENCODER_NAME, env!("CARGO_PKG_VERSION"))),
+                date_time: value.timestamps.then(ExifDateTime::from_environment),
+                gps_position: value.gps_position,
+                user_comment: value.user_comment.clone(),
+            },
+            xmp_packet: value.xmp_packet.clone(),
+            thumbnail_size: value.thumbnail_size,
+            pixel_density: value.dpi.map_or_else(PixelDensity::default, |dpi| {
+                PixelDensity::uniform(value.density_unit, dpi)
+            }),
+            adobe_marker: value.adobe_marker,
+            define_number_of_lines: value.define_number_of_lines,
+            combine_table_segments: value.combine_table_segments,
+            comments: value.comments.clone(),
+            embed_settings: value.embed_settings,
+            collect_symbol_stats: value.symbol_stats_file.is_some(),
+        }
+    }
+}
+
+impl From<&Arguments> for PreprocessingPipeline {
+    fn from(value: &Arguments) -> Self {
+        let mut pipeline = Self::new();
+        if let Some(crop) = value.crop {
+            pipeline.push(crop);
+        }
+        if value.rotation != Rotation::None {
+            pipeline.push(Rotate(value.rotation));
+        }
+        if value.smoothing > 0 {
+            pipeline.push(Smooth::new(value.smoothing));
+        }
+        if let Some(resize) = value.resize {
+            pipeline.push(resize);
+        }
+        if value.invert {
+            pipeline.push(Invert);
+        }
+        let color_adjustment = ColorAdjustment {
+            brightness: value.brightness,
+            contrast: value.contrast,
+            saturation: value.saturation,
+        };
+        if !color_adjustment.is_neutral() {
+            pipeline.push(color_adjustment);
+        }
+        pipeline
+    }
+}
+
+fn open_input_file(file_path: &Path) -> Result<File> {
+    File::open(file_path).map_err(|e| {
+        Error::UnableToOpenInputFileForReading(file_path.to_str().unwrap().to_owned(), e)
+    })
+}
+
+/// Opens the output file for writing. Missing parent directories are created, if requested,
+/// otherwise they are reported as dedicated error, just like missing write permissions.
+fn open_output_file(file_path: &Path, create_dirs: bool) -> Result<File> {
+    let parent = file_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty());
+    if let (true, Some(parent)) = (create_dirs, parent) {
+        fs::create_dir_all(parent).map_err(|e| {
+            Error::UnableToCreateOutputDirectory(parent.to_string_lossy().into_owned(), e)
+        })?;
+    }
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(file_path)
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound if parent.is_some_and(|parent| !parent.is_dir()) => {
+                Error::OutputDirectoryNotFound(parent.unwrap().to_string_lossy().into_owned())
+            }
+            ErrorKind::PermissionDenied => {
+                Error::NoWritePermissionForOutputFile(file_path.to_string_lossy().into_owned())
+            }
+            _ => Error::UnableToOpenOutputFileForWriting(file_path.to_str().unwrap().to_owned(), e),
+        })
+}
+
+fn read_gps_sidecar_file(file_path: &Path) -> Result<GpsPosition> {
+    let path = file_path.to_str().unwrap().to_owned();
+    let content = fs::read_to_string(file_path)
+        .map_err(|e| Error::UnableToReadGpsSidecarFile(path.clone(), e))?;
+    GpsPosition::from_sidecar(&content).map_err(|reason| Error::InvalidGpsSidecarFile(path, reason))
+}
+
+fn read_xmp_file(file_path: &Path) -> Result<XmpPacket> {
+    let packet = fs::read_to_string(file_path)
+        .map_err(|e| Error::UnableToReadXmpFile(file_path.to_str().unwrap().to_owned(), e))?;
+    XmpPacket::new(packet)
+}
+
+fn read_quantization_table_file(file_path: &Path) -> Result<[u16; 64]> {
+    let path = file_path.to_str().unwrap().to_owned();
+    let content = fs::read_to_string(file_path)
+        .map_err(|e| Error::UnableToReadQuantizationTableFile(path.clone(), e))?;
+    parse_quantization_table(&content)
+        .map_err(|reason| Error::InvalidQuantizationTableFile(path, reason))
+}
+
+fn read_huffman_tables_file(file_path: &Path) -> Result<HuffmanTables> {
+    let path = file_path.to_str().unwrap().to_owned();
+    let content = fs::read_to_string(file_path)
+        .map_err(|e| Error::UnableToReadHuffmanTablesFile(path.clone(), e))?;
+    parse_huffman_tables(&content).map_err(|reason| Error::InvalidHuffmanTablesFile(path, reason))
+}
+
+/// Reads the input image and composites an alpha channel over the background, since JPEG
+/// cannot carry alpha.
+fn read_input_image(
+    file_path: &Path,
+    memory_map: bool,
+    readers: &ImageReaderRegistry,
+    limits: &InputLimits,
+    background: &RGBColorFormat<f32>,
+) -> Result<Image<f32>> {
+    let input_file = open_input_file(file_path)?;
+    let image = if memory_map {
+        read_memory_mapped_input_image(&input_file, file_path, readers, limits)?
+    } else {
+        readers.read_rgba_image(file_path, BufReader::new(input_file), limits)?
+    };
+    if image.has_alpha() {
+        log::info!("Compositing the alpha channel over the background");
+    }
+    Ok(image.composite(background))
+}
+
+/// Reads the input file as CMYK image with the PAM or TIFF reader, which is selected by the file
+/// extension.
+fn read_cmyk_input_image(file_path: &Path, limits: &InputLimits) -> Result<CMYKImage> {
+    let input_file = BufReader::new(open_input_file(file_path)?);
+    let extension = file_path
+        .extension()
+        .map_or(String::new(), |e| e.to_string_lossy().to_ascii_lowercase());
+    match extension.as_str() {
+        "pam" => PAMImageReader::with_limits(input_file, *limits).read_cmyk_image(),
+        "tif" | "tiff" => TIFFImageReader::with_limits(input_file, *limits).read_cmyk_image(),
+        _ => Err(Error::UnsupportedInputFormat(extension)),
+    }
+}
+
+/// Maps the input file into memory and feeds the image reader from the mapping, which avoids
+/// copying the file content through an intermediate read buffer.
+#[cfg(feature = "mmap")]
+fn read_memory_mapped_input_image(
+    input_file: &File,
+    file_path: &Path,
+    readers: &ImageReaderRegistry,
+    limits: &InputLimits,
+) -> Result<RGBAImage> {
+    // SAFETY: The mapping is only read while the image is decoded. Modifying the file from
+    // another process at the same time is not supported and may produce a corrupted image.
+    let mapping = unsafe { memmap2::Mmap::map(input_file) }
+        .map_err(|e| Error::UnableToMapInputFile(file_path.to_str().unwrap().to_owned(), e))?;
+    readers.read_rgba_image(file_path, &mapping[..], limits)
+}
+
+#[cfg(not(feature = "mmap"))]
+fn read_memory_mapped_input_image(
+    _input_file: &File,
+    _file_path: &Path,
+    _readers: &ImageReaderRegistry,
+    _limits: &InputLimits,
+) -> Result<RGBAImage> {
+    Err(Error::FeatureNotEnabled("mmap"))
+}
+
+/// Writes one line per restart interval with the index of the interval, the index of its first
+/// MCU and the byte offset of its entropy coded data in the JPEG file.
+fn write_restart_index(
+    file_path: &Path,
+    restart_interval: u16,
+    restart_interval_offsets: &[u64],
+) -> Result<()> {
+    let mut content = String::from("# interval first_mcu byte_offset\n");
+    for (interval_index, offset) in restart_interval_offsets.iter().enumerate() {
+        let first_mcu = interval_index * restart_interval as usize;
+        content.push_str(&format!("{} {} {}\n", interval_index, first_mcu, offset));
+    }
+    fs::write(file_path, content).map_err(|e| {
+        Error::UnableToWriteRestartIndexFile(file_path.to_str().unwrap().to_owned(), e)
+    })
+}
+
+/// Image of the input file, which is converted.
+enum InputImage {
+    Rgb(Image<f32>),
+    Cmyk(CMYKImage),
+}
+
+/// Passes the JPEG on to the output and keeps a copy, if it is decoded again for the metrics.
+struct RecordingWriter<W: Write> {
+    writer: W,
+    recording: Option<Vec<u8>>,
+}
+
+impl<W: Write> RecordingWriter<W> {
+    fn new(writer: W, record: bool) -> Self {
+        Self {
+            writer,
+            recording: record.then(Vec::new),
+        }
+    }
+
+    fn recording(&self) -> Option<&[u8]> {
+        self.recording.as_deref()
+    }
+}
+
+impl<W: Write> Write for RecordingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.writer.write(buf)?;
+        if let Some(recording) = &mut self.recording {
+            recording.extend_from_slice(&buf[..n]);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+fn write_symbol_stats(file_path: &Path, symbol_stats: &str) -> Result<()> {
+    fs::write(file_path, symbol_stats)
+        .map_err(|e| Error::UnableToWriteSymbolStatsFile(file_path.to_str().unwrap().to_owned(), e))
+}
+
+fn write_huffman_tables(file_path: &Path, huffman_tables: &HuffmanTables) -> Result<()> {
+    fs::write(file_path, huffman_tables.to_json()).map_err(|e| {
+        Error::UnableToWriteHuffmanTablesFile(file_path.to_str().unwrap().to_owned(), e)
+    })
+}
+
+/// Converts the input file to JPEG. The reader for the input file is selected from the
+/// registry, so formats of other crates can be converted by registering their readers. Returns
+/// the warnings of the encode.
+pub fn convert_to_jpeg(arguments: &Arguments, readers: &ImageReaderRegistry) -> Result<Warnings> {
+    let limits = InputLimits {
+        max_pixel_count: arguments.max_pixel_count,
+        ..Default::default()
+    };
+    let read_start = Instant::now();
+    let input_image = if arguments.cmyk {
+        InputImage::Cmyk(read_cmyk_input_image(&arguments.input_file, &limits)?)
+    } else {
+        InputImage::Rgb(read_input_image(
+            &arguments.input_file,
+            arguments.memory_map,
+            readers,
+            &limits,
+            &arguments.background,
+        )?)
+    };
+    let read_time = read_start.elapsed();
+    let threadpool = ThreadPool::new(arguments.number_of_threads);
+
+    let mut transformation_options = JpegTransformationOptions::from(arguments);
+    let sampling_layout = transformation_options
+        .frame_subsampling_preset()
+        .sampling_layout();
+    let sampling_description = if transformation_options.grayscale {
+        format!(
+            "Y {}x{} (0x{:02X}) only, MCU 8x8 pixels with 1 block: Y0",
+            sampling_layout.luma_factors.horizontal,
+            sampling_layout.luma_factors.vertical,
+            sampling_layout.luma_factors.to_byte()
+        )
+    } else {
+        sampling_layout.to_string()
+    };
+    log::info!("Sampling layout: {}", sampling_description);
+    if arguments.verbose {
+        arguments.print_message(format_args!("Sampling layout: {}", sampling_description));
+    }
+    let mcu_height = sampling_layout.mcu_height();
+    let input_image = match input_image {
+        InputImage::Rgb(image) => {
+            InputImage::Rgb(PreprocessingPipeline::from(arguments).run_on_threadpool(
+                image,
+                &threadpool,
+                mcu_height,
+            )?)
+        }
+        cmyk_image => cmyk_image,
+    };
+
+    if let Some(gps_sidecar_file) = &arguments.gps_sidecar_file {
+        transformation_options.exif_metadata.gps_position =
+            Some(read_gps_sidecar_file(gps_sidecar_file)?);
+    }
+    if let Some(xmp_file) = &arguments.xmp_file {
+        transformation_options.xmp_packet = Some(read_xmp_file(xmp_file)?);
+    }
+    if let Some(table_file) = &arguments.chroma_red_table_file {
+        transformation_options.chroma_red_quantization_table =
+            Some(read_quantization_table_file(table_file)?);
+    }
+    if let Some(huffman_tables_file) = &arguments.huffman_tables_file {
+        transformation_options.huffman_tables =
+            Some(read_huffman_tables_file(huffman_tables_file)?);
+    }
+    // the output is truncated only after all inputs are read, so they can't destroy it
+    let output: Box<dyn Write> = if arguments.writes_to_stdout() {
+        Box::new(io::stdout().lock())
+    } else {
+        Box::new(open_output_file(
+            &arguments.output_file,
+            arguments.create_dirs,
+        )?)
+    };
+    let output_writer = RecordingWriter::new(BufWriter::new(output), arguments.metrics);
+    let mut image_writer = match &input_image {
+        InputImage::Rgb(image) => {
+            JpegImageWriter::new(output_writer, image, &transformation_options, &threadpool)
+        }
+        InputImage::Cmyk(image) => JpegImageWriter::from_cmyk_image(
+            output_writer,
+            image,
+            &transformation_options,
+            &threadpool,
+        ),
+    };
+    image_writer.write_image()?;
+    if arguments.timings {
+        let input_file_size = fs::metadata(&arguments.input_file)
+            .map(|metadata| metadata.len())
+            .unwrap_or_default();
+        let stage_timings = image_writer.stage_timings();
+        stage_timings.record(Stage::Read, read_time, input_file_size);
+        arguments.print_message(stage_timings);
+    }
+    if let Some(quality) = image_writer.target_size_quality() {
+        log::info!("Quality for the target size: {}", quality);
+        if arguments.verbose {
+            arguments.print_message(format_args!("Quality for the target size: {}", quality));
+        }
+    }
+    if let (Some(restart_index_file), Some(restart_interval)) =
+        (&arguments.restart_index_file, arguments.restart_interval)
+    {
+        write_restart_index(
+            restart_index_file,
+            restart_interval,
+            image_writer.restart_interval_offsets(),
+        )?;
+    }
+    if let (Some(symbol_stats_file), Some(symbol_stats)) =
+        (&arguments.symbol_stats_file, image_writer.symbol_stats())
+    {
+        write_symbol_stats(symbol_stats_file, symbol_stats)?;
+    }
+    if let (Some(dump_huffman_file), Some(huffman_tables)) =
+        (&arguments.dump_huffman_file, image_writer.huffman_tables())
+    {
+        write_huffman_tables(dump_huffman_file, huffman_tables)?;
+    }
+    if let (Some(encoded), InputImage::Rgb(image)) =
+        (image_writer.get_ref().recording(), &input_image)
+    {
+        // decodes the written data, so the metrics cover every stage of the encoder
+        let decoded = JPEGImageReader::new(encoded).read_image()?;
+        // a grayscale JPEG holds the luma of the matrix, not the colors of the source
+        let metrics = if transformation_options.grayscale {
+            metrics::compare_luma(image, &decoded, transformation_options.color_matrix)?
+        } else {
+            metrics::compare(image, &decoded)?
+        };
+        arguments.print_message(format_args!(
+            "PSNR: {:.2} dB, SSIM: {:.4}",
+            metrics.psnr, metrics.ssim
+        ));
+    }
+    Ok(image_writer.warnings().clone())
+}
+
+/// Encodes a synthetic image with every combination of thread count, DCT algorithm and chroma
+/// subsampling preset and writes the throughput of each combination as table into the writer.
+/// The last row holds the throughput of the one-shot fast path with all defaults.
+pub fn run_benchmark(arguments: &BenchmarkArguments, writer: &mut impl Write) -> Result<()> {
+    let benchmark = Benchmark::new(arguments.width, arguments.height, arguments.rounds)?;
+    let write_line = |writer: &mut dyn Write, line: String| {
+        writeln!(writer, "{}", line).map_err(Error::FailedToWriteBenchmarkReport)
+    };
+    write_line(
+        writer,
+        format!(
+            "{:>7}  {:<9}  {:<6}  {:>8}",
+            "threads", "dct", "preset", "MPix/s"
+        ),
+    )?;
+    for &number_of_threads in &arguments.thread_counts {
+        let threadpool = ThreadPool::new(number_of_threads);
+        // Auto measures the same transformer as one of the other algorithms
+        let dct_algorithms = DCTAlgorithm::variants()
+            .iter()
+            .filter(|&&dct_algorithm| dct_algorithm != DCTAlgorithm::Auto);
+        for &dct_algorithm in dct_algorithms {
+            for &chroma_subsampling_preset in ChromaSubsamplingPreset::variants() {
+                let options = JpegTransformationOptions {
+                    chroma_subsampling_preset,
+                    dct_algorithm,
+                    ..Default::default()
+                };
+                let throughput = benchmark.measure_throughput(&options, &threadpool)?;
+                write_line(
+                    writer,
+                    format!(
+                        "{:>7}  {:<9}  {:<6}  {:>8.2}",
+                        number_of_threads,
+                        format!("{:?}", dct_algorithm),
+                        format!("{:?}", chroma_subsampling_preset),
+                        throughput
+                    ),
+                )?;
+            }
+        }
+    }
+    write_line(
+        writer,
+        format!(
+            "{:>7}  {:<9}  {:<6}  {:>8.2}",
+            "auto",
+            "default",
+            "P420",
+            benchmark.measure_default_throughput()?
+        ),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, path::PathBuf, process};
+
+    use super::{
+        convert_to_jpeg, open_output_file, read_cmyk_input_image, read_input_image, CLIParser,
+        ImageReaderRegistry, InputLimits, RGBColorFormat, Task,
+    };
+    use dmmt_jpeg_core::Error;
+
+    fn get_test_image_path() -> PathBuf {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("tests/8x8.ppm");
+        path
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn read_memory_mapped_input_image() {
+        let path = get_test_image_path();
+        let readers = ImageReaderRegistry::default();
+        let limits = InputLimits::default();
+        let background = RGBColorFormat::white();
+        let buffered = read_input_image(&path, false, &readers, &limits, &background).unwrap();
+        let mapped = read_input_image(&path, true, &readers, &limits, &background).unwrap();
+        assert_eq!(mapped, buffered);
+    }
+
+    #[cfg(not(feature = "mmap"))]
+    #[test]
+    fn read_memory_mapped_input_image_without_feature() {
+        let result = read_input_image(
+            &get_test_image_path(),
+            true,
+            &ImageReaderRegistry::default(),
+            &InputLimits::default(),
+            &RGBColorFormat::white(),
+        );
+        assert!(matches!(result, Err(Error::FeatureNotEnabled("mmap"))));
+    }
+
+    /// Returns a directory below the temporary directory, which does not exist yet.
+    fn get_missing_directory_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("dmmt-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&path);
+        path
+    }
+
+    #[test]
+    fn open_output_file_in_missing_directory() {
+        let directory = get_missing_directory_path("missing");
+        let result = open_output_file(&directory.join("image.jpg"), false);
+        assert!(matches!(
+            result,
+            Err(Error::OutputDirectoryNotFound(path)) if path == directory.to_string_lossy()
+        ));
+    }
+
+    #[test]
+    fn open_output_file_and_create_directories() {
+        let directory = get_missing_directory_path("create");
+        let file_path = directory.join("nested").join("image.jpg");
+        open_output_file(&file_path, true).unwrap();
+        assert!(file_path.is_file());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    /// Little endian CMYK TIFF of 2x1 pixels with 8 bits per sample.
+    fn cmyk_tiff() -> Vec<u8> {
+        let entries: [(u16, u16, u32); 9] = [
+            (256, 3, 2),
+            (257, 3, 1),
+            (258, 3, 8),
+            (259, 3, 1),
+            (262, 3, 5),
+            (273, 4, 8 + 2 + 9 * 12 + 4),
+            (277, 3, 4),
+            (278, 3, 1),
+            (279, 4, 8),
+        ];
+        let mut bytes = b"II".to_vec();
+        bytes.extend(42_u16.to_le_bytes());
+        bytes.extend(8_u32.to_le_bytes());
+        bytes.extend((entries.len() as u16).to_le_bytes());
+        for (tag, field_type, value) in entries {
+            bytes.extend(tag.to_le_bytes());
+            bytes.extend(field_type.to_le_bytes());
+            bytes.extend(1_u32.to_le_bytes());
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(0_u32.to_le_bytes());
+        bytes.extend([0; 8]);
+        bytes
+    }
+
+    #[test]
+    fn apply_limits_to_cmyk_input_images() {
+        let directory = get_missing_directory_path("cmyk");
+        fs::create_dir_all(&directory).unwrap();
+        let pam_file = directory.join("image.pam");
+        fs::write(
+            &pam_file,
+            b"P7\nWIDTH 2\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE CMYK\nENDHDR\n\0\0\0\0\0\0\0\0",
+        )
+        .unwrap();
+        let tiff_file = directory.join("image.tif");
+        fs::write(&tiff_file, cmyk_tiff()).unwrap();
+        let limits = InputLimits {
+            max_pixel_count: 1,
+            ..Default::default()
+        };
+        for file_path in [&pam_file, &tiff_file] {
+            assert!(read_cmyk_input_image(file_path, &InputLimits::default()).is_ok());
+            let result = read_cmyk_input_image(file_path, &limits);
+            assert!(
+                matches!(result, Err(Error::PixelCountExceedsLimit(2, 1))),
+                "{:?} ignores the limits",
+                file_path
+            );
+        }
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn keep_output_file_of_invalid_sidecar_files() {
+        let directory = get_missing_directory_path("sidecar");
+        fs::create_dir_all(&directory).unwrap();
+        let output_file = directory.join("image.jpg");
+        let invalid_file = directory.join("invalid.txt");
+        fs::write(&invalid_file, "no valid content").unwrap();
+        // any text is an XMP packet, so only a missing file fails
+        let missing_file = directory.join("missing.xmp");
+        for (option, sidecar_file) in [
+            ("--chroma-red-table-file", &invalid_file),
+            ("--huffman-tables", &invalid_file),
+            ("--gps-file", &invalid_file),
+            ("--xmp-file", &missing_file),
+        ] {
+            fs::write(&output_file, "previous image").unwrap();
+            let Task::Convert(arguments) = CLIParser::new().parse(vec![
+                "test",
+                get_test_image_path().to_str().unwrap(),
+                output_file.to_str().unwrap(),
+                option,
+                sidecar_file.to_str().unwrap(),
+            ]) else {
+                panic!("Conversion task expected");
+            };
+            let result = convert_to_jpeg(&arguments, &ImageReaderRegistry::default());
+            assert!(result.is_err(), "{} accepts an invalid file", option);
+            assert_eq!(fs::read(&output_file).unwrap(), b"previous image");
+        }
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
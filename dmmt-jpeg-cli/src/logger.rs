@@ -0,0 +1,18 @@
+use std::path::Path;
+
+const CONFIG_FILE_NAME: &str = "log4rs.yaml";
+
+/// The configuration is read from the working directory. Tests run in the directory of their
+/// package, so they fall back to the configuration in the root of the workspace.
+#[ctor::ctor]
+fn init() {
+    use log4rs;
+    let config_file = if Path::new(CONFIG_FILE_NAME).is_file() {
+        Path::new(CONFIG_FILE_NAME).to_path_buf()
+    } else {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("..")
+            .join(CONFIG_FILE_NAME)
+    };
+    log4rs::init_file(config_file, Default::default()).unwrap();
+}
@@ -0,0 +1,28 @@
+use std::env::args_os;
+use std::io::stdout;
+
+use dmmt_jpeg_cli::{convert_to_jpeg, run_benchmark, CLIParser, Task};
+use dmmt_jpeg_core::image::reader::ImageReaderRegistry;
+
+fn main() {
+    let mut cli_parser = CLIParser::default();
+    match cli_parser.parse(args_os()) {
+        Task::Convert(arguments) => {
+            match convert_to_jpeg(&arguments, &ImageReaderRegistry::default()) {
+                Ok(warnings) => {
+                    for warning in &warnings {
+                        eprintln!("Warning: {}", warning);
+                    }
+                    // The JPEG itself may be written to stdout
+                    arguments.print_message("Conversion successful");
+                }
+                Err(e) => eprintln!("Conversion failed because of: {}", e),
+            }
+        }
+        Task::Benchmark(arguments) => {
+            if let Err(e) = run_benchmark(&arguments, &mut stdout()) {
+                eprintln!("Benchmark failed because of: {}", e);
+            }
+        }
+    }
+}
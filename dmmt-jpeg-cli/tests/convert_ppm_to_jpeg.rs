@@ -0,0 +1,107 @@
+use dmmt_jpeg_cli::{convert_to_jpeg, CLIParser, Task};
+use dmmt_jpeg_core::image::reader::ImageReaderRegistry;
+use std::path::PathBuf;
+use std::process::Command;
+use std::{env, fs};
+
+const INPUT_IMAGE_PATH: &str = "tests/image.ppm";
+const RESULT_IMAGE_PATH: &str = "tests/result.jpg";
+
+fn get_project_root_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn get_input_image_path() -> PathBuf {
+    let mut root_path = get_project_root_path();
+    root_path.push(INPUT_IMAGE_PATH);
+    root_path
+}
+
+fn get_result_image_path() -> PathBuf {
+    let mut root_path = get_project_root_path();
+    root_path.push(RESULT_IMAGE_PATH);
+    root_path
+}
+
+fn cleanup() {
+    let result_image_path = get_result_image_path();
+    if result_image_path.exists() && result_image_path.is_file() {
+        fs::remove_file(result_image_path).expect("Deletion of output file failed");
+    }
+}
+
+#[test]
+fn test_convert_ppm_to_jpeg() {
+    cleanup();
+    let result_image_path = get_result_image_path();
+    let mut cli_parser = CLIParser::new();
+    let Task::Convert(arguments) = cli_parser.parse(vec![
+        "test",
+        get_input_image_path().to_str().unwrap(),
+        result_image_path.to_str().unwrap(),
+    ]) else {
+        panic!("Conversion task expected");
+    };
+    convert_to_jpeg(&arguments, &ImageReaderRegistry::default()).expect("Conversion failed");
+    assert!(result_image_path.exists(), "Output file was not created");
+}
+
+#[test]
+fn test_convert_ppm_to_jpeg_on_stdout() {
+    let output = Command::new(env!("CARGO_BIN_EXE_dmmt-jpeg-encoder"))
+        .arg(get_input_image_path())
+        .arg("-")
+        .current_dir(env::temp_dir())
+        .output()
+        .expect("Encoder could not be run");
+    assert_eq!(
+        output.stdout[..2],
+        [0xFF, 0xD8],
+        "Start of image marker missing"
+    );
+    assert_eq!(output.stdout[output.stdout.len() - 2..], [0xFF, 0xD9]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Conversion successful"));
+}
+
+#[test]
+fn test_convert_ppm_to_jpeg_with_metrics() {
+    let output = Command::new(env!("CARGO_BIN_EXE_dmmt-jpeg-encoder"))
+        .arg(get_input_image_path())
+        .arg("-")
+        .arg("--metrics")
+        .current_dir(env::temp_dir())
+        .output()
+        .expect("Encoder could not be run");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let line = stderr
+        .lines()
+        .find(|line| line.starts_with("PSNR: "))
+        .expect("Metrics missing");
+    let (psnr, ssim) = line
+        .strip_prefix("PSNR: ")
+        .and_then(|metrics| metrics.split_once(" dB, SSIM: "))
+        .expect("Metrics malformed");
+    assert!(psnr.parse::<f64>().unwrap() > 25.0);
+    assert!(ssim.parse::<f64>().unwrap() > 0.8);
+}
+
+#[test]
+fn test_convert_ppm_to_grayscale_jpeg_with_metrics() {
+    let output = Command::new(env!("CARGO_BIN_EXE_dmmt-jpeg-encoder"))
+        .arg(get_input_image_path())
+        .arg("-")
+        .args(["--metrics", "--grayscale", "--color-matrix", "bt709"])
+        .current_dir(env::temp_dir())
+        .output()
+        .expect("Encoder could not be run");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let psnr = stderr
+        .lines()
+        .find_map(|line| line.strip_prefix("PSNR: "))
+        .and_then(|metrics| metrics.split_once(" dB"))
+        .expect("Metrics missing")
+        .0;
+    // the luma of the source is weighted with the matrix of the encoder
+    assert!(psnr.parse::<f64>().unwrap() > 40.0);
+}
@@ -0,0 +1,420 @@
+use std::io;
+use std::io::{Read, Write};
+
+use crate::BitPattern;
+
+/// Bits, the accumulator collects before it writes them as a word. Together with the at most 32
+/// bits of a single write, they fit into the 64 bits of the accumulator.
+const WORD_BITS: u32 = 32;
+
+/// Writes bits to the inner writer, the most significant bit of each byte first. The bits are
+/// collected in a 64 bit accumulator and written as whole words of 4 bytes.
+pub struct BitWriter<'a, T: Write> {
+    /// the underlying output stream
+    writer: &'a mut T,
+    /// bits not yet written in the lowest `bit_count` bits, the oldest bit is the most
+    /// significant one
+    accumulator: u64,
+    /// how many bits are waiting to be written, always less than a word between the writes
+    bit_count: u32,
+    /// whether the padding up to the byte border consists of ones
+    flush_with_ones: bool,
+}
+
+impl<'a, T: Write> BitWriter<'a, T> {
+    /// flush_with_ones: if true, pad with 1's until byte border on flush (0 otherwise)
+    pub fn new(writer: &'a mut T, flush_with_ones: bool) -> BitWriter<'a, T> {
+        BitWriter {
+            writer,
+            accumulator: 0,
+            bit_count: 0,
+            flush_with_ones,
+        }
+    }
+
+    /// Writes the lowest `count` bits of the value, the most significant one first. At most 32
+    /// bits can be written at once.
+    ///
+    /// Returns the number of bytes written to the underlying stream, which holds whole words
+    /// only. The remaining bits are written by later writes or the padding.
+    pub fn write_bits_u32(&mut self, value: u32, count: u32) -> Result<usize, io::Error> {
+        assert!(count <= u32::BITS, "At most 32 bits are written at once");
+        if count == 0 {
+            return Ok(0);
+        }
+        let mask = u64::MAX >> (u64::BITS - count);
+        self.accumulator = (self.accumulator << count) | (value as u64 & mask);
+        self.bit_count += count;
+        if self.bit_count < WORD_BITS {
+            return Ok(0);
+        }
+        self.bit_count -= WORD_BITS;
+        let word = (self.accumulator >> self.bit_count) as u32;
+        self.writer.write_all(&word.to_be_bytes())?;
+        Ok(4)
+    }
+
+    /// write a non-byte-aligned number of bits
+    ///
+    /// buf: a byte array containing a contigous block
+    /// count: how many bits of buf to write
+    ///
+    /// returns the number of byte writes incurred onto
+    /// the underlying stream, but does not guarantee that
+    /// all bits have been written, use flush to write
+    /// any remaining bits.
+    pub fn write_bits(&mut self, buf: &[u8], count: usize) -> Result<usize, io::Error> {
+        let number_of_whole_bytes = count / 8;
+        let whole_bytes = &buf[..number_of_whole_bytes];
+        let mut bytes_written = 0;
+        if self.bit_count.is_multiple_of(8) {
+            // this is efficient for large blocks of byte writes
+            bytes_written += self.write_whole_bytes()?;
+            self.writer.write_all(whole_bytes)?;
+            bytes_written += whole_bytes.len();
+        } else {
+            for word in whole_bytes.chunks(4) {
+                let value = word
+                    .iter()
+                    .fold(0, |value, &byte| (value << 8) | byte as u32);
+                bytes_written += self.write_bits_u32(value, word.len() as u32 * 8)?;
+            }
+        }
+        let remaining_bits = (count % 8) as u32;
+        if remaining_bits > 0 {
+            let value = buf[number_of_whole_bytes] >> (8 - remaining_bits);
+            bytes_written += self.write_bits_u32(value as u32, remaining_bits)?;
+        }
+        Ok(bytes_written)
+    }
+
+    /// Writes the pattern without allocating, if its bits fit into a word.
+    pub fn write_bit_pattern(&mut self, pattern: &impl BitPattern) -> Result<usize, io::Error> {
+        match pattern.to_u32() {
+            Some(value) => self.write_bits_u32(value, pattern.bit_len() as u32),
+            None => self.write_bits(&pattern.to_bytes(), pattern.bit_len()),
+        }
+    }
+
+    /// Writes the whole bytes of the buffered bits, which are fewer than a word.
+    fn write_whole_bytes(&mut self) -> Result<usize, io::Error> {
+        let number_of_bytes = self.bit_count / 8;
+        self.bit_count %= 8;
+        let bytes = (self.accumulator >> self.bit_count).to_be_bytes();
+        self.writer
+            .write_all(&bytes[bytes.len() - number_of_bytes as usize..])?;
+        Ok(number_of_bytes as usize)
+    }
+
+    /// Gets a mutable reference to the underlying writer. Bits, which are still buffered, are
+    /// written after the bytes written to it, so it is used after the padding only.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.writer
+    }
+
+    /// Pads the buffered bits up to the next byte border and writes them, without flushing the
+    /// underlying writer.
+    pub fn write_padding(&mut self) -> Result<(), io::Error> {
+        let padding_bits = (8 - self.bit_count % 8) % 8;
+        let padding = if self.flush_with_ones { u32::MAX } else { 0 };
+        self.write_bits_u32(padding, padding_bits)?;
+        self.write_whole_bytes()?;
+        Ok(())
+    }
+}
+
+impl<T: Write> Write for BitWriter<'_, T> {
+    /// Writing of byte arrays into the bit writer (for performance)
+    ///
+    /// Warning: Even when the returned number in the result equals
+    ///          the length of the input buffer, not all bits of the
+    ///          input may have been written (because of possible
+    ///          bits in BitWriters accumulator)
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        self.write_bits(buf, buf.len() * 8)?;
+        Ok(buf.len())
+    }
+
+    /// Flush all bits and the underlying writer;
+    ///
+    /// If there are non-byte-aligned bits still
+    /// in the buffer, they will be written to the output
+    /// with padding to the next byte;
+    fn flush(&mut self) -> Result<(), io::Error> {
+        self.write_padding()?;
+        self.writer.flush()
+    }
+}
+
+/// Reads the bits of the inner reader one by one, the most significant bit of each byte first.
+pub struct BitReader<T: Read> {
+    /// the underlying input stream
+    reader: T,
+    /// the byte, whose bits are being read
+    byte: u8,
+    /// how many bits of the byte are not read yet
+    remaining_bits: u8,
+    /// whether each 0xFF byte is followed by a stuffed 0x00 byte, which is skipped
+    remove_byte_stuffing: bool,
+    /// marker, which ended the data
+    marker: Option<u8>,
+}
+
+impl<T: Read> BitReader<T> {
+    /// remove_byte_stuffing: if true, skip the 0x00 byte after each 0xFF byte, as in the entropy
+    /// coded data of JPEG, and end the data at the first marker
+    pub fn new(reader: T, remove_byte_stuffing: bool) -> Self {
+        Self {
+            reader,
+            byte: 0,
+            remaining_bits: 0,
+            remove_byte_stuffing,
+            marker: None,
+        }
+    }
+
+    /// The next bit or `None` at the end of the data.
+    pub fn read_bit(&mut self) -> Result<Option<bool>, io::Error> {
+        if self.remaining_bits == 0 {
+            let Some(byte) = self.read_byte()? else {
+                return Ok(None);
+            };
+            self.byte = byte;
+            self.remaining_bits = 8;
+        }
+        self.remaining_bits -= 1;
+        Ok(Some(self.byte >> self.remaining_bits & 1 == 1))
+    }
+
+    /// Marker behind the data, if the byte stuffing is removed and a marker ended the data.
+    pub fn marker(&self) -> Option<u8> {
+        self.marker
+    }
+
+    fn read_byte(&mut self) -> Result<Option<u8>, io::Error> {
+        if self.marker.is_some() {
+            return Ok(None);
+        }
+        let byte = self.read_raw_byte()?;
+        if !self.remove_byte_stuffing || byte != Some(0xFF) {
+            return Ok(byte);
+        }
+        match self.read_raw_byte()? {
+            Some(0x00) => Ok(byte),
+            Some(marker) => {
+                self.marker = Some(marker);
+                Ok(None)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "data ends within a marker",
+            )),
+        }
+    }
+
+    fn read_raw_byte(&mut self) -> Result<Option<u8>, io::Error> {
+        let mut buffer = [0; 1];
+        loop {
+            match self.reader.read(&mut buffer) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(buffer[0])),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Passes all bytes on to the inner writer and counts them.
+pub struct ByteCountingWriter<T: Write> {
+    writer: T,
+    bytes_written: u64,
+}
+
+impl<T: Write> ByteCountingWriter<T> {
+    pub fn new(writer: T) -> Self {
+        Self {
+            writer,
+            bytes_written: 0,
+        }
+    }
+
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    pub fn into_inner(self) -> T {
+        self.writer
+    }
+}
+
+impl<T: Write> Write for ByteCountingWriter<T> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        let n = self.writer.write(buf)?;
+        self.bytes_written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BitReader, BitWriter, ByteCountingWriter};
+    use crate::BitPattern;
+    use std::io::Write;
+
+    /// Pattern of bytes, whose words are taken from the bytes by the default implementation.
+    struct BytePattern(Vec<u8>, usize);
+
+    impl BitPattern for BytePattern {
+        fn to_bytes(&self) -> Box<[u8]> {
+            self.0.clone().into_boxed_slice()
+        }
+
+        fn bit_len(&self) -> usize {
+            self.1
+        }
+    }
+
+    fn read_all_bits(reader: &mut BitReader<&[u8]>) -> Vec<bool> {
+        std::iter::from_fn(|| reader.read_bit().unwrap()).collect()
+    }
+
+    #[test]
+    fn read_bits_most_significant_first() {
+        let data = [0b1010_0000, 0xFF];
+        let mut reader = BitReader::new(&data[..], false);
+        let bits = read_all_bits(&mut reader);
+        assert_eq!(bits.len(), 16);
+        assert_eq!(bits[..4], [true, false, true, false]);
+        assert!(bits[8..].iter().all(|&bit| bit));
+        assert_eq!(reader.marker(), None);
+    }
+
+    #[test]
+    fn read_bits_without_byte_stuffing_up_to_marker() {
+        let data = [0xFF, 0x00, 0x12, 0xFF, 0xD9, 0x34];
+        let mut reader = BitReader::new(&data[..], true);
+        let bits = read_all_bits(&mut reader);
+        assert_eq!(bits.len(), 16);
+        assert!(bits[..8].iter().all(|&bit| bit));
+        assert_eq!(reader.marker(), Some(0xD9));
+        let mut reader = BitReader::new(&[0x12, 0xFF][..], true);
+        assert!(std::iter::from_fn(|| reader.read_bit().transpose()).any(|bit| bit.is_err()));
+    }
+
+    #[test]
+    fn byte_mode_test() {
+        let mut my_output: Vec<u8> = vec![];
+        let mut writer = BitWriter::new(&mut my_output, false);
+        let input = &[72, 65, 76, 76, 79];
+        writer.write_all(input).expect("should not fail");
+        writer.flush().expect("flushing should not fail");
+        assert_eq!(my_output[0], 72);
+        assert_eq!(my_output[1], 65);
+        assert_eq!(my_output[2], 76);
+        assert_eq!(my_output[3], 76);
+        assert_eq!(my_output[4], 79);
+        assert_eq!(my_output.len(), 5);
+    }
+
+    #[test]
+    fn bit_mode_test() {
+        let mut my_output: Vec<u8> = vec![];
+        let mut writer = BitWriter::new(&mut my_output, false);
+        // write 0x11000011 0x11110000 (in MSb notation)
+        writer.write_bits(&[0xFF], 2).expect("ERR");
+        writer.write_bits(&[0x00], 4).expect("ERR");
+        writer.write_bits(&[0xFF], 2).expect("ERR");
+        writer.write_bits(&[0xFF], 4).expect("ERR");
+        writer.flush().expect("ERR");
+        assert_eq!(my_output.len(), 2);
+        assert_eq!(my_output[0], 195);
+        assert_eq!(my_output[1], 15 << 4);
+    }
+
+    #[test]
+    fn mixed_mode_test() {
+        let mut my_output: Vec<u8> = vec![];
+        let mut writer = BitWriter::new(&mut my_output, false);
+        // 0b111
+        writer.write_bits(&[0xFF], 3).expect("ERR");
+        // 0b11100000 00100000 01010000 100
+        writer.write_all(&[1, 2, 4 | 128]).expect("ERR");
+        writer.flush().expect("ERR");
+        assert_eq!(my_output.len(), 4);
+        assert_eq!(my_output[0], 224);
+        assert_eq!(my_output[1], 32);
+        assert_eq!(my_output[2], 80);
+        assert_eq!(my_output[3], 128);
+    }
+
+    #[test]
+    fn one_padding_test() {
+        let mut my_output: Vec<u8> = vec![];
+        let mut writer = BitWriter::new(&mut my_output, true);
+        writer.write_bits(&[0x00], 3).expect("ERR");
+        writer.flush().expect("ERR");
+        assert_eq!(my_output.len(), 1);
+        assert_eq!(my_output[0], 31);
+    }
+
+    #[test]
+    fn write_words_test() {
+        let mut my_output: Vec<u8> = vec![];
+        let mut writer = BitWriter::new(&mut my_output, false);
+        assert_eq!(writer.write_bits_u32(0b101, 3).expect("ERR"), 0);
+        assert_eq!(writer.write_bits_u32(0xFFFF_FFFF, 0).expect("ERR"), 0);
+        assert_eq!(writer.write_bits_u32(0xDEAD_BEEF, 32).expect("ERR"), 4);
+        assert_eq!(writer.write_bits_u32(0x1_0001, 17).expect("ERR"), 0);
+        writer.flush().expect("ERR");
+        // 101 + 0xDEADBEEF + 1 0000 0000 0000 0001, padded with zeros
+        assert_eq!(my_output, [0xBB, 0xD5, 0xB7, 0xDD, 0xF0, 0x00, 0x10]);
+    }
+
+    #[test]
+    fn match_bitwise_reference_test() {
+        let mut reference_bits = Vec::new();
+        let mut my_output: Vec<u8> = vec![];
+        let mut writer = BitWriter::new(&mut my_output, true);
+        for index in 0..500_u32 {
+            let count = index * 7 % 33;
+            let value = index.wrapping_mul(0x9E37_79B9);
+            reference_bits.extend((0..count).rev().map(|bit| value >> bit & 1 == 1));
+            if index % 5 == 0 {
+                let bytes = value.to_be_bytes();
+                let pattern = BytePattern(bytes.to_vec(), count as usize);
+                let bit_index = reference_bits.len() - count as usize;
+                reference_bits.truncate(bit_index);
+                reference_bits
+                    .extend((0..count).map(|bit| bytes[bit as usize / 8] << (bit % 8) & 0x80 != 0));
+                writer.write_bit_pattern(&pattern).expect("ERR");
+            } else {
+                writer.write_bits_u32(value, count).expect("ERR");
+            }
+        }
+        writer.flush().expect("ERR");
+        while reference_bits.len() % 8 != 0 {
+            reference_bits.push(true);
+        }
+        let reference: Vec<u8> = reference_bits
+            .chunks(8)
+            .map(|bits| bits.iter().fold(0, |byte, &bit| (byte << 1) | bit as u8))
+            .collect();
+        assert_eq!(my_output, reference);
+    }
+
+    #[test]
+    fn count_written_bytes_test() {
+        let mut my_output: Vec<u8> = vec![];
+        let mut counting_writer = ByteCountingWriter::new(&mut my_output);
+        let mut writer = BitWriter::new(&mut counting_writer, true);
+        writer.write_bits(&[0x00, 0x00], 11).expect("ERR");
+        writer.write_padding().expect("ERR");
+        assert_eq!(counting_writer.bytes_written(), 2);
+        assert_eq!(my_output, [0x00, 0x1F]);
+    }
+}
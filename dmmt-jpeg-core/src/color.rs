@@ -0,0 +1,961 @@
+use core::panic;
+use std::fmt::Display;
+use std::str::FromStr;
+
+#[cfg(feature = "clap")]
+use clap::{builder::PossibleValue, ValueEnum};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RGBColorFormat<T> {
+    red: T,
+    green: T,
+    blue: T,
+}
+
+pub struct RangeColorFormat<T> {
+    max: T,
+    red: T,
+    green: T,
+    blue: T,
+}
+
+/// Sample precision of baseline JPEG images in bits.
+pub const BASELINE_SAMPLE_PRECISION: u8 = 8;
+
+/// Sample precision of extended sequential JPEG images in bits.
+pub const EXTENDED_SAMPLE_PRECISION: u8 = 12;
+
+/// Half of the range of samples of `sample_precision` bits, which is subtracted from the
+/// unsigned samples to center them around zero before the DCT, 128 for 8 bit samples.
+pub const fn level_shift(sample_precision: u8) -> u32 {
+    1 << (sample_precision - 1)
+}
+
+/// Matrix of the conversion from RGB to YCbCr, which weights the colors in the luma like the
+/// standard of the source. JFIF assumes BT.601, so the other matrices are annotated in a comment
+/// segment of the written image.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorMatrix {
+    /// Standard definition television, the matrix of JFIF.
+    #[default]
+    Bt601,
+    /// High definition television.
+    Bt709,
+    /// Ultra high definition television.
+    Bt2020,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for ColorMatrix {
+    fn value_variants<'a>() -> &'a [Self] {
+        Self::variants()
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()).alias(self.number()))
+    }
+}
+
+impl ColorMatrix {
+    pub fn variants() -> &'static [Self] {
+        &[Self::Bt601, Self::Bt709, Self::Bt2020]
+    }
+
+    /// Name of the matrix, as accepted on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Bt601 => "bt601",
+            Self::Bt709 => "bt709",
+            Self::Bt2020 => "bt2020",
+        }
+    }
+
+    /// Number of the recommendation, which is accepted on the command line as well.
+    #[cfg(feature = "clap")]
+    fn number(&self) -> &'static str {
+        &self.name()[2..]
+    }
+
+    /// Weights of red and blue in the luma. Green has the remaining weight.
+    fn luma_weights(&self) -> (f64, f64) {
+        match self {
+            Self::Bt601 => (0.299, 0.114),
+            Self::Bt709 => (0.2126, 0.0722),
+            Self::Bt2020 => (0.2627, 0.0593),
+        }
+    }
+
+    /// Rows of the matrix, which compute the luma, the chroma blue and the chroma red of the
+    /// normalized red, green and blue. The chroma ranges from -0.5 to 0.5.
+    pub fn coefficients(&self) -> [[f64; 3]; 3] {
+        let (red_weight, blue_weight) = self.luma_weights();
+        let green_weight = 1.0 - red_weight - blue_weight;
+        let blue_scale = 2.0 * (1.0 - blue_weight);
+        let red_scale = 2.0 * (1.0 - red_weight);
+        [
+            [red_weight, green_weight, blue_weight],
+            [-red_weight / blue_scale, -green_weight / blue_scale, 0.5],
+            [0.5, -green_weight / red_scale, -blue_weight / red_scale],
+        ]
+    }
+
+    /// Text of the comment segment, which annotates the matrix of the image data, or `None`
+    /// for BT.601, which decoders assume anyway.
+    pub fn annotation(&self) -> Option<String> {
+        (*self != Self::Bt601).then(|| format!("YCbCr color matrix: {}", self.name()))
+    }
+}
+
+/// Range of the YCbCr samples. JFIF uses the full range, video the limited range of studio
+/// swing, where the luma of 8 bit samples ranges from 16 to 235 and the chroma from 16 to 240.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorRange {
+    #[default]
+    Full,
+    Limited,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for ColorRange {
+    fn value_variants<'a>() -> &'a [Self] {
+        Self::variants()
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()))
+    }
+}
+
+impl ColorRange {
+    pub fn variants() -> &'static [Self] {
+        &[Self::Full, Self::Limited]
+    }
+
+    /// Name of the range, as accepted on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::Limited => "limited",
+        }
+    }
+
+    /// Scale and offset of the luma and of the chroma, which map unsigned full range samples of
+    /// `sample_precision` bits into the range.
+    pub(crate) fn scales_and_offsets(&self, sample_precision: u8) -> [(f64, f64); 2] {
+        match self {
+            Self::Full => [(1.0, 0.0); 2],
+            Self::Limited => {
+                let max_value = ((1_u32 << sample_precision) - 1) as f64;
+                let level_shift = level_shift(sample_precision) as f64;
+                let step = (1_u32 << (sample_precision - BASELINE_SAMPLE_PRECISION)) as f64;
+                let luma_scale = 219.0 * step / max_value;
+                let chroma_scale = 224.0 * step / max_value;
+                // the black of the full range is mapped to the black of 16 steps and the neutral
+                // chroma stays in the middle of the range
+                [
+                    (luma_scale, 16.0 * step),
+                    (chroma_scale, level_shift * (1.0 - chroma_scale)),
+                ]
+            }
+        }
+    }
+
+    /// Text of the comment segment, which annotates the range of the image data, or `None` for
+    /// the full range of JFIF.
+    pub fn annotation(&self) -> Option<String> {
+        (*self != Self::Full).then(|| format!("YCbCr range: {}", self.name()))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct YCbCrColorFormat<T> {
+    pub luma: T,
+    pub chroma_blue: T,
+    pub chroma_red: T,
+}
+
+/// Ink coverage of a dot of a print image. Each sample ranges from 0.0 for no ink to 1.0 for
+/// full ink.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CMYKColorFormat<T> {
+    pub cyan: T,
+    pub magenta: T,
+    pub yellow: T,
+    pub black: T,
+}
+
+impl CMYKColorFormat<f32> {
+    /// Creates a color from samples from 0 to `max`, where `max` is full ink.
+    pub fn from_samples(max: u16, [cyan, magenta, yellow, black]: [u16; 4]) -> Self {
+        let normalize = |sample: u16| sample as f32 / max as f32;
+        CMYKColorFormat {
+            cyan: normalize(cyan),
+            magenta: normalize(magenta),
+            yellow: normalize(yellow),
+            black: normalize(black),
+        }
+    }
+
+    /// Cyan, magenta and yellow in place of red, green and blue, which Adobe converts into the
+    /// YCC components of YCCK. Decoders subtract the decoded red, green and blue from the
+    /// maximum, which yields the inverted inks, as Adobe stores CMYK.
+    pub fn ycc_source(&self) -> RGBColorFormat<f32> {
+        RGBColorFormat {
+            red: self.cyan,
+            green: self.magenta,
+            blue: self.yellow,
+        }
+    }
+}
+
+/// Approximates the color of the inks without a color profile, which is good enough for
+/// previews like the thumbnail.
+impl From<&CMYKColorFormat<f32>> for RGBColorFormat<f32> {
+    fn from(value: &CMYKColorFormat<f32>) -> Self {
+        let white = 1.0 - value.black;
+        RGBColorFormat {
+            red: (1.0 - value.cyan) * white,
+            green: (1.0 - value.magenta) * white,
+            blue: (1.0 - value.yellow) * white,
+        }
+    }
+}
+
+#[cfg(test)]
+impl RGBColorFormat<f32> {
+    pub fn red() -> Self {
+        RGBColorFormat {
+            red: 1.0,
+            green: 0.0,
+            blue: 0.0,
+        }
+    }
+}
+
+impl Default for RGBColorFormat<f32> {
+    fn default() -> Self {
+        RGBColorFormat {
+            red: 0.0,
+            green: 0.0,
+            blue: 0.0,
+        }
+    }
+}
+
+impl RGBColorFormat<f32> {
+    pub fn from_samples([red, green, blue]: [f32; 3]) -> Self {
+        RGBColorFormat { red, green, blue }
+    }
+
+    pub fn white() -> Self {
+        RGBColorFormat {
+            red: 1.0,
+            green: 1.0,
+            blue: 1.0,
+        }
+    }
+
+    /// Creates a color from normalized samples. Samples outside of the range of 0.0 to 1.0 are
+    /// clamped, NaN samples are mapped to 0.0.
+    pub fn clamped(red: f32, green: f32, blue: f32) -> Self {
+        let clamp = |sample: f32| {
+            if sample.is_nan() {
+                0.0
+            } else {
+                sample.clamp(0.0, 1.0)
+            }
+        };
+        RGBColorFormat {
+            red: clamp(red),
+            green: clamp(green),
+            blue: clamp(blue),
+        }
+    }
+
+    pub fn samples(&self) -> [f32; 3] {
+        [self.red, self.green, self.blue]
+    }
+
+    /// Quantizes the normalized samples to 8 bit, rounding to the nearest value.
+    pub fn to_rgb8(self) -> [u8; 3] {
+        let quantize = |sample: f32| (sample.clamp(0.0, 1.0) * u8::MAX as f32).round() as u8;
+        [
+            quantize(self.red),
+            quantize(self.green),
+            quantize(self.blue),
+        ]
+    }
+
+    /// The 8 bit samples of the color, if it was read from a source of 8 bit samples, or `None`,
+    /// if a sample lies between two 8 bit values, like those of 16 bit sources.
+    pub fn exact_rgb8(&self) -> Option<[u8; 3]> {
+        let mut samples = [0; 3];
+        for (sample, normalized) in samples.iter_mut().zip(self.samples()) {
+            let scaled = normalized * u8::MAX as f32;
+            let rounded = scaled.round();
+            if !(0.0..=u8::MAX as f32).contains(&rounded) || (scaled - rounded).abs() > 1e-4 {
+                return None;
+            }
+            *sample = rounded as u8;
+        }
+        Some(samples)
+    }
+
+    pub fn inverted(&self) -> Self {
+        RGBColorFormat {
+            red: 1.0 - self.red,
+            green: 1.0 - self.green,
+            blue: 1.0 - self.blue,
+        }
+    }
+
+    pub fn interpolate(&self, other: &Self, weight: f32) -> Self {
+        RGBColorFormat {
+            red: self.red + (other.red - self.red) * weight,
+            green: self.green + (other.green - self.green) * weight,
+            blue: self.blue + (other.blue - self.blue) * weight,
+        }
+    }
+
+    /// Composites the color with straight alpha over the opaque background. An alpha of 1.0 keeps
+    /// the color, an alpha of 0.0 yields the background.
+    pub fn composite(&self, alpha: f32, background: &Self) -> Self {
+        let blend = |sample: f32, background: f32| sample * alpha + background * (1.0 - alpha);
+        RGBColorFormat {
+            red: blend(self.red, background.red),
+            green: blend(self.green, background.green),
+            blue: blend(self.blue, background.blue),
+        }
+    }
+}
+
+impl FromStr for RGBColorFormat<f32> {
+    type Err = String;
+
+    /// Parses a hex color `#rrggbb` like in CSS. The `#` is optional.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let digits = value.strip_prefix('#').unwrap_or(value);
+        if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("'{}' is not a hex color like #ffffff", value));
+        }
+        let sample = |index: usize| {
+            let sample = u8::from_str_radix(&digits[index..index + 2], 16).unwrap();
+            sample as f32 / u8::MAX as f32
+        };
+        Ok(Self::from_samples([sample(0), sample(2), sample(4)]))
+    }
+}
+
+impl From<&RangeColorFormat<u16>> for RGBColorFormat<f32> {
+    fn from(value: &RangeColorFormat<u16>) -> Self {
+        RGBColorFormat {
+            red: value.red as f32 / value.max as f32,
+            green: value.green as f32 / value.max as f32,
+            blue: value.blue as f32 / value.max as f32,
+        }
+    }
+}
+
+impl From<RangeColorFormat<u16>> for RGBColorFormat<f32> {
+    fn from(value: RangeColorFormat<u16>) -> Self {
+        RGBColorFormat::from(&value)
+    }
+}
+
+impl From<&RangeColorFormat<u16>> for RGBColorFormat<u16> {
+    /// Rescales the samples to the full 16 bit range in integer arithmetic, so no precision of
+    /// high max value images is lost.
+    fn from(value: &RangeColorFormat<u16>) -> Self {
+        let max = value.max.max(1) as u32;
+        let rescale = |sample: u16| ((sample as u32 * u16::MAX as u32 + max / 2) / max) as u16;
+        RGBColorFormat {
+            red: rescale(value.red),
+            green: rescale(value.green),
+            blue: rescale(value.blue),
+        }
+    }
+}
+
+impl From<RangeColorFormat<u16>> for RGBColorFormat<u16> {
+    fn from(value: RangeColorFormat<u16>) -> Self {
+        RGBColorFormat::from(&value)
+    }
+}
+
+impl From<&RGBColorFormat<u16>> for RGBColorFormat<f32> {
+    fn from(value: &RGBColorFormat<u16>) -> Self {
+        let max = u16::MAX as f32;
+        RGBColorFormat {
+            red: value.red as f32 / max,
+            green: value.green as f32 / max,
+            blue: value.blue as f32 / max,
+        }
+    }
+}
+
+impl<T: PartialOrd<T> + Display> RangeColorFormat<T> {
+    pub fn new(max: T, red: T, green: T, blue: T) -> Self {
+        if red > max || green > max || blue > max {
+            panic!("Color value must not be greater than max value of {}", max);
+        }
+        RangeColorFormat {
+            max,
+            red,
+            green,
+            blue,
+        }
+    }
+}
+
+impl From<&RGBColorFormat<f32>> for YCbCrColorFormat<f32> {
+    fn from(value: &RGBColorFormat<f32>) -> Self {
+        YCbCrColorFormat::with_sample_precision(value, BASELINE_SAMPLE_PRECISION)
+    }
+}
+
+impl YCbCrColorFormat<f32> {
+    /// Converts the color to samples of `sample_precision` bits, level shifted to be centered
+    /// around zero. A luma of 8 bit precision ranges from -128 to 127, one of 12 bit precision
+    /// from -2048 to 2047.
+    pub fn with_sample_precision(value: &RGBColorFormat<f32>, sample_precision: u8) -> Self {
+        YCbCrColorFormat::with_color_matrix(value, sample_precision, ColorMatrix::Bt601)
+    }
+
+    /// Converts the color like [`YCbCrColorFormat::with_sample_precision`] with the luma
+    /// weights of the matrix.
+    pub fn with_color_matrix(
+        value: &RGBColorFormat<f32>,
+        sample_precision: u8,
+        color_matrix: ColorMatrix,
+    ) -> Self {
+        YCbCrColorFormat::unsigned_with_color_matrix(value, sample_precision, color_matrix)
+            .level_shifted(sample_precision)
+    }
+
+    /// Converts the color to unsigned samples of `sample_precision` bits like JFIF, where the
+    /// luma ranges from 0 to the maximum and the neutral chroma lies in the middle of the range.
+    pub fn unsigned_with_color_matrix(
+        value: &RGBColorFormat<f32>,
+        sample_precision: u8,
+        color_matrix: ColorMatrix,
+    ) -> Self {
+        let [luma_row, chroma_blue_row, chroma_red_row] = color_matrix
+            .coefficients()
+            .map(|row| row.map(|weight| weight as f32));
+        let max_value = ((1_u32 << sample_precision) - 1) as f32;
+        let level_shift = level_shift(sample_precision) as f32;
+        let red = value.red;
+        let green = value.green;
+        let blue = value.blue;
+
+        let weighted_red = red * luma_row[0];
+        let weighted_green = green * luma_row[1];
+        let weighted_blue = blue * luma_row[2];
+        let luma = (weighted_red + weighted_green + weighted_blue) * max_value;
+        let weighted_red = red * chroma_blue_row[0];
+        let weighted_green = green * chroma_blue_row[1];
+        let weighted_blue = blue * chroma_blue_row[2];
+        let chroma_blue = (weighted_red + weighted_green + weighted_blue) * max_value + level_shift;
+        let weighted_red = red * chroma_red_row[0];
+        let weighted_green = green * chroma_red_row[1];
+        let weighted_blue = blue * chroma_red_row[2];
+        let chroma_red = (weighted_red + weighted_green + weighted_blue) * max_value + level_shift;
+
+        YCbCrColorFormat {
+            luma,
+            chroma_blue,
+            chroma_red,
+        }
+    }
+
+    /// Centers the unsigned samples of `sample_precision` bits around zero.
+    pub fn level_shifted(self, sample_precision: u8) -> Self {
+        let level_shift = level_shift(sample_precision) as f32;
+        YCbCrColorFormat {
+            luma: self.luma - level_shift,
+            chroma_blue: self.chroma_blue - level_shift,
+            chroma_red: self.chroma_red - level_shift,
+        }
+    }
+
+    /// Maps the unsigned full range samples of `sample_precision` bits into the range.
+    pub fn in_range(self, color_range: ColorRange, sample_precision: u8) -> Self {
+        let [(luma_scale, luma_offset), (chroma_scale, chroma_offset)] =
+            color_range.scales_and_offsets(sample_precision);
+        let chroma = |sample: f32| sample * chroma_scale as f32 + chroma_offset as f32;
+        YCbCrColorFormat {
+            luma: self.luma * luma_scale as f32 + luma_offset as f32,
+            chroma_blue: chroma(self.chroma_blue),
+            chroma_red: chroma(self.chroma_red),
+        }
+    }
+}
+
+impl YCbCrColorFormat<f64> {
+    /// Centers the unsigned samples of `sample_precision` bits around zero.
+    pub fn level_shifted(self, sample_precision: u8) -> Self {
+        let level_shift = level_shift(sample_precision) as f64;
+        YCbCrColorFormat {
+            luma: self.luma - level_shift,
+            chroma_blue: self.chroma_blue - level_shift,
+            chroma_red: self.chroma_red - level_shift,
+        }
+    }
+
+    /// Maps the unsigned full range samples of `sample_precision` bits into the range.
+    pub fn in_range(self, color_range: ColorRange, sample_precision: u8) -> Self {
+        let [(luma_scale, luma_offset), (chroma_scale, chroma_offset)] =
+            color_range.scales_and_offsets(sample_precision);
+        let chroma = |sample: f64| sample * chroma_scale + chroma_offset;
+        YCbCrColorFormat {
+            luma: self.luma * luma_scale + luma_offset,
+            chroma_blue: chroma(self.chroma_blue),
+            chroma_red: chroma(self.chroma_red),
+        }
+    }
+
+    /// Converts the color like [`YCbCrColorFormat::with_sample_precision`], but computes in
+    /// double precision with the exact coefficients of the JFIF specification.
+    pub fn with_sample_precision_f64(value: &RGBColorFormat<f32>, sample_precision: u8) -> Self {
+        YCbCrColorFormat::with_color_matrix_f64(value, sample_precision, ColorMatrix::Bt601)
+    }
+
+    /// Converts the color like [`YCbCrColorFormat::with_sample_precision_f64`] with the luma
+    /// weights of the matrix.
+    pub fn with_color_matrix_f64(
+        value: &RGBColorFormat<f32>,
+        sample_precision: u8,
+        color_matrix: ColorMatrix,
+    ) -> Self {
+        YCbCrColorFormat::unsigned_with_color_matrix_f64(value, sample_precision, color_matrix)
+            .level_shifted(sample_precision)
+    }
+
+    /// Converts the color like [`YCbCrColorFormat::unsigned_with_color_matrix`] in double
+    /// precision.
+    pub fn unsigned_with_color_matrix_f64(
+        value: &RGBColorFormat<f32>,
+        sample_precision: u8,
+        color_matrix: ColorMatrix,
+    ) -> Self {
+        let [luma_row, chroma_blue_row, chroma_red_row] = color_matrix.coefficients();
+        let max_value = ((1_u32 << sample_precision) - 1) as f64;
+        let level_shift = level_shift(sample_precision) as f64;
+        let red = value.red as f64;
+        let green = value.green as f64;
+        let blue = value.blue as f64;
+
+        let weigh = |[red_weight, green_weight, blue_weight]: [f64; 3]| {
+            red_weight * red + green_weight * green + blue_weight * blue
+        };
+        let luma = weigh(luma_row) * max_value;
+        let chroma_blue = weigh(chroma_blue_row) * max_value + level_shift;
+        let chroma_red = weigh(chroma_red_row) * max_value + level_shift;
+
+        YCbCrColorFormat {
+            luma,
+            chroma_blue,
+            chroma_red,
+        }
+    }
+}
+
+impl From<&YCbCrColorFormat<f32>> for RGBColorFormat<f32> {
+    fn from(value: &YCbCrColorFormat<f32>) -> Self {
+        let luma = value.luma + 128_f32;
+        let chroma_blue = value.chroma_blue;
+        let chroma_red = value.chroma_red;
+
+        let red = luma + 1.402_f32 * chroma_red;
+        let green = luma - 0.344136_f32 * chroma_blue - 0.714136_f32 * chroma_red;
+        let blue = luma + 1.772_f32 * chroma_blue;
+
+        RGBColorFormat {
+            red: (red / 255_f32).clamp(0_f32, 1_f32),
+            green: (green / 255_f32).clamp(0_f32, 1_f32),
+            blue: (blue / 255_f32).clamp(0_f32, 1_f32),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        level_shift, CMYKColorFormat, ColorMatrix, ColorRange, RGBColorFormat, RangeColorFormat,
+        YCbCrColorFormat,
+    };
+
+    #[test]
+    fn convert_rgb_to_ycbcr() {
+        let rgb = RGBColorFormat {
+            red: 0.25_f32,
+            green: 0.75_f32,
+            blue: 0.333_f32,
+        };
+        let result = YCbCrColorFormat::from(&rgb);
+        assert!(
+            result.luma >= 12.95_f32 && result.luma < 13.05_f32,
+            "luma is wrong, was {}",
+            result.luma
+        );
+        assert!(
+            result.chroma_blue >= -31.68 && result.chroma_blue < -31.58,
+            "chroma blue is wrong, was {}",
+            result.chroma_blue
+        );
+        assert!(
+            result.chroma_red >= -55.13 && result.chroma_red < -55.03,
+            "chroma red is wrong, was {}",
+            result.chroma_red
+        );
+    }
+
+    #[test]
+    fn convert_rgb_white_to_ycbcr() {
+        let rgb = RGBColorFormat {
+            red: 1_f32,
+            green: 1_f32,
+            blue: 1_f32,
+        };
+        let result = YCbCrColorFormat::from(&rgb);
+        assert!(
+            result.luma >= 126.99999_f32 && result.luma <= 127.00001_f32,
+            "luma is wrong, was {}",
+            result.luma
+        );
+        assert!(
+            result.chroma_blue >= -0.5_f32 && result.chroma_blue <= 0.5_f32,
+            "chroma blue is wrong, was {}",
+            result.chroma_blue
+        );
+        assert!(
+            result.chroma_red >= -0.5_f32 && result.chroma_red <= 0.5_f32,
+            "chroma red is wrong, was {}",
+            result.chroma_red
+        );
+    }
+
+    #[test]
+    fn convert_rgb_black_to_ycbcr() {
+        let rgb = RGBColorFormat {
+            red: 0_f32,
+            green: 0_f32,
+            blue: 0_f32,
+        };
+        let result = YCbCrColorFormat::from(&rgb);
+        assert_eq!(result.luma, -128_f32, "luma is wrong");
+        assert_eq!(result.chroma_blue, 0_f32, "chroma blue is wrong");
+        assert_eq!(result.chroma_red, 0_f32, "chroma red is wrong");
+    }
+
+    #[test]
+    fn convert_ycbcr_to_rgb() {
+        let rgb = RGBColorFormat {
+            red: 0.25_f32,
+            green: 0.75_f32,
+            blue: 0.333_f32,
+        };
+        let result = RGBColorFormat::from(&YCbCrColorFormat::from(&rgb));
+        assert!((result.red - rgb.red).abs() < 1e-3, "red is wrong");
+        assert!((result.green - rgb.green).abs() < 1e-3, "green is wrong");
+        assert!((result.blue - rgb.blue).abs() < 1e-3, "blue is wrong");
+    }
+
+    #[test]
+    fn convert_rgb_to_ycbcr_in_double_precision() {
+        let rgb = RGBColorFormat {
+            red: 0.25_f32,
+            green: 0.75_f32,
+            blue: 0.333_f32,
+        };
+        let single = YCbCrColorFormat::from(&rgb);
+        let double = YCbCrColorFormat::with_sample_precision_f64(&rgb, 8);
+        assert!(
+            (double.luma - single.luma as f64).abs() < 1e-3,
+            "luma is wrong"
+        );
+        assert!(
+            (double.chroma_blue - single.chroma_blue as f64).abs() < 0.1,
+            "chroma blue is wrong"
+        );
+        assert!(
+            (double.chroma_red - single.chroma_red as f64).abs() < 0.1,
+            "chroma red is wrong"
+        );
+        let white =
+            YCbCrColorFormat::with_sample_precision_f64(&RGBColorFormat::default().inverted(), 8);
+        assert!((white.luma - 127.0).abs() < 1e-9, "white luma is wrong");
+        assert!(white.chroma_blue.abs() < 1e-9, "white chroma blue is wrong");
+        assert!(white.chroma_red.abs() < 1e-9, "white chroma red is wrong");
+    }
+
+    #[test]
+    fn convert_rgb_with_color_matrices() {
+        let rgb = RGBColorFormat {
+            red: 0.25_f32,
+            green: 0.75_f32,
+            blue: 0.333_f32,
+        };
+        let bt601 = YCbCrColorFormat::with_color_matrix(&rgb, 8, ColorMatrix::Bt601);
+        assert_eq!(bt601, YCbCrColorFormat::from(&rgb));
+        let bt709 = YCbCrColorFormat::with_color_matrix_f64(&rgb, 8, ColorMatrix::Bt709);
+        let luma = (0.2126 * 0.25 + 0.7152 * 0.75 + 0.0722 * 0.333_f32 as f64) * 255.0 - 128.0;
+        assert!((bt709.luma - luma).abs() < 1e-6, "BT.709 luma is wrong");
+        for &color_matrix in ColorMatrix::variants() {
+            let white = YCbCrColorFormat::with_color_matrix_f64(
+                &RGBColorFormat::default().inverted(),
+                8,
+                color_matrix,
+            );
+            assert!((white.luma - 127.0).abs() < 1e-9, "white luma is wrong");
+            assert!(white.chroma_blue.abs() < 1e-9, "white chroma blue is wrong");
+            assert!(white.chroma_red.abs() < 1e-9, "white chroma red is wrong");
+            let blue = YCbCrColorFormat::with_color_matrix_f64(
+                &RGBColorFormat::from_samples([0.0, 0.0, 1.0]),
+                8,
+                color_matrix,
+            );
+            assert!(
+                (blue.chroma_blue - 127.5).abs() < 1e-9,
+                "blue chroma is wrong"
+            );
+            let single = YCbCrColorFormat::with_color_matrix(&rgb, 8, color_matrix);
+            let double = YCbCrColorFormat::with_color_matrix_f64(&rgb, 8, color_matrix);
+            assert!(
+                (double.luma - single.luma as f64).abs() < 1e-3,
+                "luma differs"
+            );
+        }
+        assert_eq!(ColorMatrix::Bt601.annotation(), None);
+        assert_eq!(
+            ColorMatrix::Bt2020.annotation().as_deref(),
+            Some("YCbCr color matrix: bt2020")
+        );
+    }
+
+    #[test]
+    fn convert_rgb_to_unsigned_ycbcr() {
+        let black = RGBColorFormat::default();
+        for sample_precision in [8, 12] {
+            let level_shift = level_shift(sample_precision) as f32;
+            let unsigned = YCbCrColorFormat::unsigned_with_color_matrix(
+                &black,
+                sample_precision,
+                ColorMatrix::Bt601,
+            );
+            assert_eq!(unsigned.luma, 0.0);
+            assert!((unsigned.chroma_blue - level_shift).abs() < 1e-3);
+            assert!((unsigned.chroma_red - level_shift).abs() < 1e-3);
+            let shifted = unsigned.level_shifted(sample_precision);
+            assert_eq!(shifted.luma, -level_shift);
+            assert_eq!(
+                shifted,
+                YCbCrColorFormat::with_sample_precision(&black, sample_precision)
+            );
+        }
+    }
+
+    #[test]
+    fn convert_rgb_to_limited_range() {
+        let white = RGBColorFormat::default().inverted();
+        let black = RGBColorFormat::default();
+        let blue = RGBColorFormat::from_samples([0.0, 0.0, 1.0]);
+        for sample_precision in [8, 12] {
+            let step = (1 << (sample_precision - 8)) as f64;
+            let level_shift = (1 << (sample_precision - 1)) as f64;
+            let convert = |rgb| {
+                YCbCrColorFormat::unsigned_with_color_matrix_f64(
+                    &rgb,
+                    sample_precision,
+                    ColorMatrix::Bt601,
+                )
+                .in_range(ColorRange::Limited, sample_precision)
+            };
+            let white = convert(white);
+            assert!((white.luma - 235.0 * step).abs() < 1e-9);
+            let black = convert(black);
+            assert!((black.luma - 16.0 * step).abs() < 1e-9);
+            assert!((black.chroma_blue - level_shift).abs() < 1e-9);
+            let blue = convert(blue);
+            assert!((blue.chroma_blue - 240.0 * step).abs() < 1e-9);
+        }
+        let rgb = RGBColorFormat::from_samples([0.25, 0.75, 0.333]);
+        let full = YCbCrColorFormat::unsigned_with_color_matrix(&rgb, 8, ColorMatrix::Bt601);
+        assert_eq!(full.in_range(ColorRange::Full, 8), full);
+        let limited = full.in_range(ColorRange::Limited, 8);
+        assert!((limited.luma - (full.luma * 219.0 / 255.0 + 16.0)).abs() < 1e-3);
+        assert_eq!(ColorRange::Full.annotation(), None);
+    }
+
+    #[test]
+    fn detect_exact_rgb8() {
+        let rgb8 = [0, 1, 255];
+        let rgb = RGBColorFormat::from(RangeColorFormat::new(255, 0, 1, 255));
+        assert_eq!(rgb.exact_rgb8(), Some(rgb8));
+        for value in 0..=u8::MAX {
+            let rgb = RGBColorFormat::from_samples([value as f32 / 255.0; 3]);
+            assert_eq!(rgb.exact_rgb8(), Some([value; 3]));
+        }
+        let rgb = RGBColorFormat::<f32>::from(&RGBColorFormat {
+            red: 0_u16,
+            green: 257,
+            blue: 258,
+        });
+        assert_eq!(rgb.exact_rgb8(), None);
+        assert_eq!(
+            RGBColorFormat::from_samples([0.0, 1.5, 0.0]).exact_rgb8(),
+            None
+        );
+    }
+
+    #[test]
+    fn composite_over_background() {
+        let background = RGBColorFormat::white();
+        let red = RGBColorFormat::red();
+        assert_eq!(red.composite(1.0, &background), red);
+        assert_eq!(red.composite(0.0, &background), background);
+        let result = red.composite(0.25, &background);
+        assert_eq!(result.samples(), [1.0, 0.75, 0.75]);
+    }
+
+    #[test]
+    fn parse_hex_color() {
+        assert_eq!("#ffffff".parse(), Ok(RGBColorFormat::white()));
+        assert_eq!("FF0000".parse(), Ok(RGBColorFormat::red()));
+        assert_eq!(
+            "#336699".parse::<RGBColorFormat<f32>>().unwrap().to_rgb8(),
+            [0x33, 0x66, 0x99]
+        );
+        for value in ["#fff", "#fffffff", "#gggggg", "", "#ff ff f"] {
+            assert!(value.parse::<RGBColorFormat<f32>>().is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn interpolate_rgb() {
+        let black = RGBColorFormat::default();
+        let result = black.interpolate(&RGBColorFormat::red(), 0.25);
+        assert_eq!(result.red, 0.25_f32, "red is wrong");
+        assert_eq!(result.green, 0_f32, "green is wrong");
+        assert_eq!(result.blue, 0_f32, "blue is wrong");
+    }
+
+    #[test]
+    fn invert_rgb() {
+        let rgb = RGBColorFormat {
+            red: 1_f32,
+            green: 0.25_f32,
+            blue: 0_f32,
+        };
+        let result = rgb.inverted();
+        assert_eq!(result.red, 0_f32, "red is wrong");
+        assert_eq!(result.green, 0.75_f32, "green is wrong");
+        assert_eq!(result.blue, 1_f32, "blue is wrong");
+    }
+
+    #[test]
+    fn convert_range_color_to_rgb() {
+        let range_color = RangeColorFormat::new(17734_u16, 128_u16, 14355_u16, 9_u16);
+        let result: RGBColorFormat<f32> = RGBColorFormat::from(&range_color);
+        assert!(
+            result.red >= 7.209e-3_f32 && result.red <= 7.219e-3_f32,
+            "red is wrong"
+        );
+        assert!(
+            result.green >= 0.809459_f32 && result.red <= 0.809469_f32,
+            "green is wrong"
+        );
+        assert!(
+            result.blue >= 4.99e-4_f32 && result.blue <= 5.09e-4_f32,
+            "blue is wrong"
+        );
+    }
+
+    #[test]
+    fn convert_range_color_white_to_rgb() {
+        let range_color = RangeColorFormat::new(u16::MAX, u16::MAX, u16::MAX, u16::MAX);
+        let result: RGBColorFormat<f32> = RGBColorFormat::from(&range_color);
+        assert_eq!(result.red, 1_f32, "red is wrong");
+        assert_eq!(result.green, 1_f32, "green is wrong");
+        assert_eq!(result.blue, 1_f32, "blue is wrong");
+    }
+
+    #[test]
+    fn convert_range_color_4bit_to_rgb() {
+        let range_color = RangeColorFormat::new(0b1111_u16, 0b0010_u16, 0b0101_u16, 0b1111_u16);
+        let result: RGBColorFormat<f32> = RGBColorFormat::from(&range_color);
+        assert!(
+            result.red >= 0.133333 && result.red <= 0.133334,
+            "red is wrong"
+        );
+        assert!(
+            result.green >= 0.333333 && result.green <= 0.333334,
+            "green is wrong"
+        );
+        assert!(result.blue == 1_f32, "blue is wrong");
+    }
+
+    #[test]
+    fn convert_range_color_to_16bit_rgb() {
+        let range_color = RangeColorFormat::new(1023_u16, 0_u16, 1_u16, 1023_u16);
+        let result: RGBColorFormat<u16> = RGBColorFormat::from(&range_color);
+        assert_eq!(result.red, 0, "red is wrong");
+        assert_eq!(result.green, 64, "green is wrong");
+        assert_eq!(result.blue, u16::MAX, "blue is wrong");
+    }
+
+    #[test]
+    fn convert_rgb_to_12bit_ycbcr_keeps_precision() {
+        let low = RGBColorFormat::<f32>::from(&RGBColorFormat {
+            red: 0x1000_u16,
+            green: 0x1000_u16,
+            blue: 0x1000_u16,
+        });
+        let high = RGBColorFormat::<f32>::from(&RGBColorFormat {
+            red: 0x1010_u16,
+            green: 0x1010_u16,
+            blue: 0x1010_u16,
+        });
+        let low = YCbCrColorFormat::with_sample_precision(&low, 12);
+        let high = YCbCrColorFormat::with_sample_precision(&high, 12);
+        let difference = high.luma - low.luma;
+        assert!(
+            (difference - 1_f32).abs() < 1e-3,
+            "luma difference is wrong, was {}",
+            difference
+        );
+    }
+
+    #[test]
+    fn convert_rgb_white_and_black_to_12bit_ycbcr() {
+        let white = RGBColorFormat {
+            red: 1_f32,
+            green: 1_f32,
+            blue: 1_f32,
+        };
+        let white = YCbCrColorFormat::with_sample_precision(&white, 12);
+        assert!((white.luma - 2047_f32).abs() < 1e-2, "white luma is wrong");
+        let black = YCbCrColorFormat::with_sample_precision(&RGBColorFormat::default(), 12);
+        assert_eq!(black.luma, -2048_f32, "black luma is wrong");
+    }
+
+    #[test]
+    #[should_panic]
+    fn create_range_color_out_of_range() {
+        RangeColorFormat::new(144, 12, 144, 145);
+    }
+
+    #[test]
+    fn create_range_color() {
+        RangeColorFormat::new(u16::MAX, 0, 5325, u16::MAX);
+    }
+
+    #[test]
+    fn convert_cmyk_inks() {
+        let cyan = CMYKColorFormat::from_samples(u8::MAX as u16, [255, 0, 0, 0]);
+        assert_eq!(cyan.ycc_source().samples(), [1.0, 0.0, 0.0]);
+        assert_eq!(RGBColorFormat::from(&cyan).samples(), [0.0, 1.0, 1.0]);
+        let black = CMYKColorFormat::from_samples(u8::MAX as u16, [0, 0, 0, 255]);
+        assert_eq!(RGBColorFormat::from(&black), RGBColorFormat::default());
+    }
+}
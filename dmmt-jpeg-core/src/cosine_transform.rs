@@ -0,0 +1,328 @@
+use std::marker::{Send, Sync};
+#[cfg(not(feature = "rayon"))]
+use std::sync::mpsc;
+
+use arai::AraiDiscrete8x8CosineTransformer;
+#[cfg(feature = "clap")]
+use clap::{builder::PossibleValue, ValueEnum};
+use integer::IntegerDiscrete8x8CosineTransformer;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use separated::SeparatedDiscrete8x8CosineTransformer;
+use simple::SimpleDiscrete8x8CosineTransformer;
+use threadpool::ThreadPool;
+
+pub mod arai;
+pub mod integer;
+pub mod separated;
+#[cfg(target_arch = "aarch64")]
+pub mod simd_neon;
+#[cfg(target_arch = "x86_64")]
+pub mod simd_x86;
+pub mod simple;
+
+/// Selects the implementation of the forward DCT, used by the encoder.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DCTAlgorithm {
+    /// Fastest algorithm of the CPU, see [`DCTAlgorithm::resolve`].
+    #[default]
+    Auto,
+    Simple,
+    Separated,
+    Arai,
+    /// Arai algorithm vectorized for the CPU, which falls back to the scalar Arai algorithm,
+    /// if the CPU lacks the needed instructions.
+    Simd,
+    /// Fixed point algorithm of libjpeg, whose result does not depend on the floating point
+    /// rounding of the platform.
+    Integer,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for DCTAlgorithm {
+    fn value_variants<'a>() -> &'a [Self] {
+        Self::variants()
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()))
+    }
+}
+
+impl DCTAlgorithm {
+    pub fn variants() -> &'static [Self] {
+        &[
+            Self::Auto,
+            Self::Simple,
+            Self::Separated,
+            Self::Arai,
+            Self::Simd,
+            Self::Integer,
+        ]
+    }
+
+    /// Name of the algorithm, as accepted on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Auto => "Auto",
+            Self::Simple => "Simple",
+            Self::Separated => "Separated",
+            Self::Arai => "Arai",
+            Self::Simd => "SIMD",
+            Self::Integer => "Integer",
+        }
+    }
+
+    /// Algorithm, which is actually used. Auto selects the vectorized Arai algorithm, if the
+    /// CPU supports it, and the scalar Arai algorithm otherwise. The other algorithms are kept.
+    pub fn resolve(self) -> Self {
+        match self {
+            Self::Auto if simd_transformer().is_some() => Self::Simd,
+            Self::Auto => Self::Arai,
+            algorithm => algorithm,
+        }
+    }
+
+    /// Algorithm, which is actually used in double precision. Auto selects the separated
+    /// algorithm, which is the most precise of the fast algorithms. There is no vectorized
+    /// variant in double precision, so SIMD is replaced by the scalar Arai algorithm.
+    pub fn resolve_double_precision(self) -> Self {
+        match self {
+            Self::Auto => Self::Separated,
+            Self::Simd => Self::Arai,
+            algorithm => algorithm,
+        }
+    }
+
+    /// Double precision variant of the transformer, see [`DCTAlgorithm::resolve_double_precision`].
+    pub fn double_precision_transformer(
+        &self,
+    ) -> &'static dyn DoublePrecisionDiscrete8x8CosineTransformer {
+        match self.resolve_double_precision() {
+            Self::Simple => &SimpleDiscrete8x8CosineTransformer,
+            Self::Arai => &AraiDiscrete8x8CosineTransformer,
+            Self::Integer => &IntegerDiscrete8x8CosineTransformer,
+            Self::Separated | Self::Auto | Self::Simd => &SeparatedDiscrete8x8CosineTransformer,
+        }
+    }
+
+    pub fn transformer(&self) -> &'static dyn Discrete8x8CosineTransformer {
+        match self {
+            Self::Auto => self.resolve().transformer(),
+            Self::Simple => &SimpleDiscrete8x8CosineTransformer,
+            Self::Separated => &SeparatedDiscrete8x8CosineTransformer,
+            Self::Arai => &AraiDiscrete8x8CosineTransformer,
+            Self::Simd => simd_transformer().unwrap_or(&AraiDiscrete8x8CosineTransformer),
+            Self::Integer => &IntegerDiscrete8x8CosineTransformer,
+        }
+    }
+}
+
+/// Vectorized transformer of the architecture, if the CPU supports the needed instructions.
+#[cfg(target_arch = "x86_64")]
+fn simd_transformer() -> Option<&'static dyn Discrete8x8CosineTransformer> {
+    simd_x86::SimdX86Discrete8x8CosineTransformer::detect()
+        .map(|transformer| transformer as &dyn Discrete8x8CosineTransformer)
+}
+
+#[cfg(target_arch = "aarch64")]
+fn simd_transformer() -> Option<&'static dyn Discrete8x8CosineTransformer> {
+    simd_neon::SimdNeonDiscrete8x8CosineTransformer::detect()
+        .map(|transformer| transformer as &dyn Discrete8x8CosineTransformer)
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn simd_transformer() -> Option<&'static dyn Discrete8x8CosineTransformer> {
+    None
+}
+
+/// Copies the values in chunks of `chunk_length` to the threadpool, applies `transform_chunk` on
+/// each chunk on a thread of the pool and copies the transformed chunks back. The function blocks
+/// until all chunks have been transformed.
+///
+/// With the `rayon` feature, the chunks are transformed on the current rayon pool instead and the
+/// threadpool is ignored. Cargo enables the feature for the whole build, as soon as any crate of
+/// the build enables it.
+#[cfg(not(feature = "rayon"))]
+pub fn transform_chunks_on_threadpool<T, F>(
+    threadpool: &ThreadPool,
+    values: &mut [T],
+    chunk_length: usize,
+    transform_chunk: F,
+) where
+    T: Copy + Send + 'static,
+    F: Fn(&mut [T]) + Clone + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    let mut number_of_chunks = 0;
+    for (chunk_index, chunk) in values.chunks(chunk_length).enumerate() {
+        let mut chunk = chunk.to_vec();
+        let sender = sender.clone();
+        let transform_chunk = transform_chunk.clone();
+        threadpool.execute(move || {
+            transform_chunk(&mut chunk);
+            sender
+                .send((chunk_index, chunk))
+                .expect("Receiver of transformed chunks was dropped");
+        });
+        number_of_chunks += 1;
+    }
+    drop(sender);
+    let mut number_of_received_chunks = 0;
+    for (chunk_index, chunk) in receiver.iter() {
+        let start = chunk_index * chunk_length;
+        values[start..start + chunk.len()].copy_from_slice(&chunk);
+        number_of_received_chunks += 1;
+    }
+    assert_eq!(
+        number_of_received_chunks, number_of_chunks,
+        "Transformation of a chunk failed"
+    );
+}
+
+/// Applies `transform_chunk` on each chunk of `chunk_length` values in place with the parallel
+/// iterators of the current rayon pool, so the threadpool is ignored. The `rayon` feature selects
+/// this variant for the whole build, see the variant without the feature. Each job of rayon
+/// transforms with its own clone of `transform_chunk`. The function blocks until all chunks have
+/// been transformed.
+#[cfg(feature = "rayon")]
+pub fn transform_chunks_on_threadpool<T, F>(
+    _threadpool: &ThreadPool,
+    values: &mut [T],
+    chunk_length: usize,
+    transform_chunk: F,
+) where
+    T: Copy + Send + 'static,
+    F: Fn(&mut [T]) + Clone + Send + 'static,
+{
+    values
+        .par_chunks_mut(chunk_length)
+        .for_each_with(transform_chunk, |transform_chunk, chunk| {
+            transform_chunk(chunk)
+        });
+}
+
+pub trait Discrete8x8CosineTransformer
+where
+    Self: 'static + Send + Sync,
+{
+    /// Applies the 8x8 discrete cosine transform (DCT) on the block of 64 values in place.
+    fn transform(&self, block: &mut [f32; 64]);
+
+    /// Applies the 8x8 discrete cosine transform (DCT) on each 64-value-block of the contiguous
+    /// values in one call. The default implementation transforms block by block, implementations
+    /// may process the blocks in a more cache friendly order or unroll the loop over the blocks.
+    ///
+    /// # Panics
+    ///
+    /// If the number of values is not a multiple of 64.
+    fn transform_blocks(&self, blocks: &mut [f32]) {
+        assert_eq!(blocks.len() % 64, 0, "Blocks consist of 64 values");
+        for block in blocks.chunks_exact_mut(64) {
+            let block: &mut [f32; 64] = block
+                .try_into()
+                .expect("Chunks of exactly 64 values are blocks");
+            self.transform(block);
+        }
+    }
+
+    /// Applies the 8x8 discrete cosine transform (DCT) for each 64-value-block of the channel on
+    /// a threadpool. The size of the jobs, executed on the pool, can be controled by the
+    /// `jobs_chunk_size` parameter. If the parameter is set to 100, a single thread will transform
+    /// 100 blocks in sequence. The function returns, when all blocks have been transformed.
+    fn transform_on_threadpool(
+        &'static self,
+        threadpool: &ThreadPool,
+        channel: &mut [f32],
+        jobs_chunk_size: usize,
+    ) {
+        transform_chunks_on_threadpool(threadpool, channel, jobs_chunk_size * 64, move |chunk| {
+            self.transform_blocks(chunk)
+        });
+    }
+}
+
+/// Forward DCT, which computes in double precision. It is used to create reference encodes, which
+/// show how much the single precision transformers deviate.
+pub trait DoublePrecisionDiscrete8x8CosineTransformer
+where
+    Self: 'static + Send + Sync,
+{
+    /// Applies the 8x8 discrete cosine transform (DCT) on the block of 64 values in place.
+    fn transform_double_precision(&self, block: &mut [f64; 64]);
+}
+
+#[cfg(test)]
+mod test {
+    use super::{simd_transformer, DCTAlgorithm};
+
+    #[test]
+    fn double_precision_transformers_match_single_precision() {
+        let values: [f32; 64] =
+            std::array::from_fn(|index| ((index * 37 + index / 8 * 11) % 255) as f32 - 128.0);
+        for &algorithm in DCTAlgorithm::variants() {
+            let mut single_precision_block = values;
+            algorithm
+                .resolve_double_precision()
+                .transformer()
+                .transform(&mut single_precision_block);
+            let mut double_precision_block = values.map(f64::from);
+            algorithm
+                .double_precision_transformer()
+                .transform_double_precision(&mut double_precision_block);
+            for (index, (actual, expected)) in double_precision_block
+                .into_iter()
+                .zip(single_precision_block)
+                .enumerate()
+            {
+                assert!(
+                    (actual as f32 - expected).abs() <= 1e-3,
+                    "Value {} at index {} of {} is different than {}",
+                    actual,
+                    index,
+                    algorithm.name(),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn transform_blocks_like_single_blocks() {
+        let values: Vec<f32> = (0..64 * 5)
+            .map(|index| ((index * 37 + index / 8 * 11) % 255) as f32 - 128.0)
+            .collect();
+        for &algorithm in DCTAlgorithm::variants() {
+            let transformer = algorithm.transformer();
+            let mut batched = values.clone();
+            transformer.transform_blocks(&mut batched);
+            for (block_index, block) in values.chunks_exact(64).enumerate() {
+                let mut block: [f32; 64] = block.try_into().unwrap();
+                transformer.transform(&mut block);
+                let batched_block = &batched[block_index * 64..(block_index + 1) * 64];
+                assert_eq!(
+                    batched_block,
+                    block.as_slice(),
+                    "Block {} of {} differs",
+                    block_index,
+                    algorithm.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn resolve_auto_to_fastest_supported_algorithm() {
+        let expected = if simd_transformer().is_some() {
+            DCTAlgorithm::Simd
+        } else {
+            DCTAlgorithm::Arai
+        };
+        assert_eq!(DCTAlgorithm::Auto.resolve(), expected);
+        for &algorithm in DCTAlgorithm::variants() {
+            if algorithm != DCTAlgorithm::Auto {
+                assert_eq!(algorithm.resolve(), algorithm);
+            }
+        }
+    }
+}
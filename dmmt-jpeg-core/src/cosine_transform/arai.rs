@@ -0,0 +1,379 @@
+use std::f32::consts::{FRAC_1_SQRT_2, SQRT_2};
+use std::ops::{Add, Mul, Neg, Sub};
+
+use super::{Discrete8x8CosineTransformer, DoublePrecisionDiscrete8x8CosineTransformer};
+
+pub struct AraiDiscrete8x8CosineTransformer;
+
+/// Inverse of [`AraiDiscrete8x8CosineTransformer`] after the floating point IDCT of libjpeg
+/// (`jpeg_idct_float`), which reverses the butterflies of the forward algorithm.
+pub struct InverseAraiDiscrete8x8CosineTransformer;
+
+pub(super) const A1: f32 = FRAC_1_SQRT_2;
+// sqrt(1 - FRAC_1_SQRT_2)
+pub(super) const A2: f32 = 0.5411961;
+pub(super) const A3: f32 = A1;
+// sqrt(1 + FRAC_1_SQRT_2)
+pub(super) const A4: f32 = 1.3065629;
+// 1/2 * sqrt(2 - √2)
+pub(super) const A5: f32 = 0.3826834;
+
+// 1 / 2 * sqrt(2)
+pub(super) const S0: f32 = 0.3535533;
+// From here Sk = 1 / 4*Ck
+// Ck = cos(PI*k/16)
+pub(super) const S1: f32 = 0.254_897_8;
+pub(super) const S2: f32 = 0.270_598_05;
+pub(super) const S3: f32 = 0.300_672_44;
+pub(super) const S4: f32 = 0.353_553_38;
+pub(super) const S5: f32 = 0.449_988_1;
+pub(super) const S6: f32 = 0.653_281_5;
+pub(super) const S7: f32 = 1.281_457_7;
+
+/// Constants `A1` to `A5` and `S0` to `S7` of the algorithm in the precision of the values.
+struct Constants<T> {
+    a: [T; 5],
+    s: [T; 8],
+}
+
+const SINGLE_PRECISION_CONSTANTS: Constants<f32> = Constants {
+    a: [A1, A2, A3, A4, A5],
+    s: [S0, S1, S2, S3, S4, S5, S6, S7],
+};
+
+const DOUBLE_PRECISION_CONSTANTS: Constants<f64> = Constants {
+    a: [
+        std::f64::consts::FRAC_1_SQRT_2,
+        0.541_196_100_146_197,
+        std::f64::consts::FRAC_1_SQRT_2,
+        1.306_562_964_876_376_6,
+        0.382_683_432_365_089_7,
+    ],
+    s: [
+        0.353_553_390_593_273_73,
+        0.254_897_789_552_079_6,
+        0.270_598_050_073_098_5,
+        0.300_672_443_467_522_64,
+        0.353_553_390_593_273_73,
+        0.449_988_111_568_207_8,
+        0.653_281_482_438_188_2,
+        1.281_457_723_870_752_7,
+    ],
+};
+
+impl AraiDiscrete8x8CosineTransformer {
+    /// Transforms the eight values, which begin at the offset and are the stride apart from each
+    /// other.
+    #[inline(always)]
+    fn fast_arai<T>(block: &mut [T], offset: usize, stride: usize, constants: &Constants<T>)
+    where
+        T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Neg<Output = T>,
+    {
+        let [a1, a2, a3, a4, a5] = constants.a;
+        let [s0, s1, s2, s3, s4, s5, s6, s7] = constants.s;
+        let p = |index: usize| offset + index * stride;
+
+        let v00 = block[p(0)];
+        let v01 = block[p(1)];
+        let v02 = block[p(2)];
+        let v03 = block[p(3)];
+        let v04 = block[p(4)];
+        let v05 = block[p(5)];
+        let v06 = block[p(6)];
+        let v07 = block[p(7)];
+
+        let v10 = v00 + v07;
+        let v11 = v01 + v06;
+        let v12 = v02 + v05;
+        let v13 = v03 + v04;
+        let v14 = v03 - v04;
+        let v15 = v02 - v05;
+        let v16 = v01 - v06;
+        let v17 = v00 - v07;
+
+        let v20 = v10 + v13;
+        let v21 = v11 + v12;
+        let v22 = v11 - v12;
+        let v23 = v10 - v13;
+        let v24 = -v14 - v15;
+        let v25 = v15 + v16;
+        let v26 = v16 + v17;
+
+        let v30 = v20 + v21;
+        let v31 = v20 - v21;
+        let v32 = v22 + v23;
+
+        let v42 = v32 * a1;
+        let v44 = -v24 * a2 - (v24 + v26) * a5;
+        let v45 = v25 * a3;
+        let v46 = v26 * a4 - (v26 + v24) * a5;
+
+        let v52 = v42 + v23;
+        let v53 = v23 - v42;
+        let v55 = v45 + v17;
+        let v57 = v17 - v45;
+
+        let v64 = v44 + v57;
+        let v65 = v55 + v46;
+        let v66 = v55 - v46;
+        let v67 = v57 - v44;
+
+        block[p(0)] = v30 * s0;
+        block[p(4)] = v31 * s4;
+        block[p(2)] = v52 * s2;
+        block[p(6)] = v53 * s6;
+        block[p(5)] = v64 * s5;
+        block[p(1)] = v65 * s1;
+        block[p(7)] = v66 * s7;
+        block[p(3)] = v67 * s3;
+    }
+}
+
+/// Factors of the coefficients before the inverse transform of each row and column, which undo the
+/// scaling of the forward transform by `S0` to `S7`.
+const INVERSE_SCALE: [f32; 8] = [
+    1.0 / (8.0 * S0),
+    1.0 / (8.0 * S1),
+    1.0 / (8.0 * S2),
+    1.0 / (8.0 * S3),
+    1.0 / (8.0 * S4),
+    1.0 / (8.0 * S5),
+    1.0 / (8.0 * S6),
+    1.0 / (8.0 * S7),
+];
+
+const B1: f32 = SQRT_2;
+// 2 * cos(PI / 8)
+const B2: f32 = 1.847_759;
+// 2 * (cos(PI / 8) - sin(PI / 8))
+const B3: f32 = 1.082_392_2;
+// 2 * (cos(PI / 8) + sin(PI / 8))
+const B4: f32 = 2.613_126;
+
+impl InverseAraiDiscrete8x8CosineTransformer {
+    /// Transforms the eight coefficients, which begin at the offset and are the stride apart from
+    /// each other, back to values.
+    #[inline(always)]
+    fn fast_inverse_arai(block: &mut [f32], offset: usize, stride: usize) {
+        let p = |index: usize| offset + index * stride;
+        let c = |index: usize| block[p(index)] * INVERSE_SCALE[index];
+
+        // even part
+        let v10 = c(0) + c(4);
+        let v11 = c(0) - c(4);
+        let v13 = c(2) + c(6);
+        let v12 = (c(2) - c(6)) * B1 - v13;
+        let v20 = v10 + v13;
+        let v23 = v10 - v13;
+        let v21 = v11 + v12;
+        let v22 = v11 - v12;
+
+        // odd part
+        let z13 = c(5) + c(3);
+        let z10 = c(5) - c(3);
+        let z11 = c(1) + c(7);
+        let z12 = c(1) - c(7);
+        let v27 = z11 + z13;
+        let v31 = (z11 - z13) * B1;
+        let z5 = (z10 + z12) * B2;
+        let v30 = z5 - z12 * B3;
+        let v32 = z5 - z10 * B4;
+        let v26 = v32 - v27;
+        let v25 = v31 - v26;
+        let v24 = v30 - v25;
+
+        block[p(0)] = v20 + v27;
+        block[p(7)] = v20 - v27;
+        block[p(1)] = v21 + v26;
+        block[p(6)] = v21 - v26;
+        block[p(2)] = v22 + v25;
+        block[p(5)] = v22 - v25;
+        block[p(3)] = v23 + v24;
+        block[p(4)] = v23 - v24;
+    }
+}
+
+impl Discrete8x8CosineTransformer for AraiDiscrete8x8CosineTransformer {
+    fn transform(&self, block: &mut [f32; 64]) {
+        self.transform_blocks(block);
+    }
+
+    /// Transforms the rows of all blocks in one pass over the contiguous values, before the
+    /// columns are transformed block by block.
+    fn transform_blocks(&self, blocks: &mut [f32]) {
+        assert_eq!(blocks.len() % 64, 0, "Blocks consist of 64 values");
+        for row in blocks.chunks_exact_mut(8) {
+            Self::fast_arai(row, 0, 1, &SINGLE_PRECISION_CONSTANTS);
+        }
+        for block in blocks.chunks_exact_mut(64) {
+            for i in 0..8 {
+                Self::fast_arai(block, i, 8, &SINGLE_PRECISION_CONSTANTS);
+            }
+        }
+    }
+}
+
+impl DoublePrecisionDiscrete8x8CosineTransformer for AraiDiscrete8x8CosineTransformer {
+    fn transform_double_precision(&self, block: &mut [f64; 64]) {
+        for i in 0..8 {
+            Self::fast_arai(block, i * 8, 1, &DOUBLE_PRECISION_CONSTANTS);
+        }
+        for i in 0..8 {
+            Self::fast_arai(block, i, 8, &DOUBLE_PRECISION_CONSTANTS);
+        }
+    }
+}
+
+impl Discrete8x8CosineTransformer for InverseAraiDiscrete8x8CosineTransformer {
+    fn transform(&self, block: &mut [f32; 64]) {
+        self.transform_blocks(block);
+    }
+
+    fn transform_blocks(&self, blocks: &mut [f32]) {
+        assert_eq!(blocks.len() % 64, 0, "Blocks consist of 64 values");
+        for row in blocks.chunks_exact_mut(8) {
+            Self::fast_inverse_arai(row, 0, 1);
+        }
+        for block in blocks.chunks_exact_mut(64) {
+            for i in 0..8 {
+                Self::fast_inverse_arai(block, i, 8);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::super::simple::{
+        InverseSimpleDiscrete8x8CosineTransformer, SimpleDiscrete8x8CosineTransformer,
+    };
+    use super::super::Discrete8x8CosineTransformer;
+    use super::{
+        AraiDiscrete8x8CosineTransformer, InverseAraiDiscrete8x8CosineTransformer, A1, A2, A3, A4,
+        A5, S0, S1, S2, S3, S4, S5, S6, S7, SINGLE_PRECISION_CONSTANTS,
+    };
+
+    type Row = [f32; 8];
+
+    fn y0(input: &Row) -> f32 {
+        let s: f32 = input.iter().sum();
+        s * S0
+    }
+
+    fn y4(i: &Row) -> f32 {
+        (i[0] + i[7] + i[3] + i[4] - i[1] - i[6] - i[2] - i[5]) * S4
+    }
+
+    fn y2(i: &Row) -> f32 {
+        ((i[0] + i[1] - i[2] - i[3] - i[4] - i[5] + i[6] + i[7]) * A1 + i[0] + i[7] - i[3] - i[4])
+            * S2
+    }
+
+    fn y6(i: &Row) -> f32 {
+        ((i[0] + i[1] - i[2] - i[3] - i[4] - i[5] + i[6] + i[7]) * -A1 + i[0] + i[7] - i[3] - i[4])
+            * S6
+    }
+
+    fn y5(i: &Row) -> f32 {
+        (A2 * (i[3] - i[4] + i[2] - i[5])
+            + A5 * (i[3] - i[4] + i[2] - i[5] - i[1] + i[6] - i[0] + i[7])
+            + i[0]
+            - i[7]
+            - A3 * (i[2] - i[5] + i[1] - i[6]))
+            * S5
+    }
+
+    fn y1(i: &Row) -> f32 {
+        let temp = i[1] - i[6] + i[0] - i[7];
+        (i[0] - i[7] + A3 * (i[2] - i[5] + i[1] - i[6]) + A4 * (temp)
+            - A5 * (temp - i[3] + i[4] - i[0] + i[7]))
+            * S1
+    }
+
+    fn y7(i: &Row) -> f32 {
+        let temp = i[1] - i[6] + i[0] - i[7];
+        (i[0] - i[7] + A3 * (i[2] - i[5] + i[1] - i[6]) - A4 * (temp)
+            + A5 * (temp - i[3] + i[4] - i[0] + i[7]))
+            * S7
+    }
+
+    fn y3(i: &Row) -> f32 {
+        let temp = i[3] - i[4] + i[2] - i[5];
+        (-A2 * temp - A5 * (temp - i[1] + i[6] - i[0] + i[7]) + i[0]
+            - i[7]
+            - A3 * (i[2] - i[5] + i[1] - i[6]))
+            * S3
+    }
+
+    #[rustfmt::skip]
+    const TEST_VALUES: [f32; 64] = [
+        1.0, 2.0, 1.0, 2.0, 3.0, 2.0, 3.0, 2.0,
+        3.0, 2.0, 1.0, 2.0, 3.0, 4.0, 3.0, 2.0,
+        3.0, 4.0, 3.0, 2.0, 3.0, 4.0, 5.0, 6.0,
+        7.0, 6.0, 5.0, 4.0, 3.0, 2.0, 3.0, 2.0,
+        3.0, 4.0, 5.0, 5.0, 6.0, 5.0, 2.0, 3.0,
+        4.0, 3.0, 2.0, 3.0, 4.0, 5.0, 4.0, 3.0,
+        2.0, 3.0, 4.0, 5.0, 6.0, 5.0, 4.0, 3.0,
+        2.0, 3.0, 4.0, 5.0, 3.0, 4.0, 3.0, 4.0,
+    ];
+
+    fn assert_almost_eq(actual: f32, expected: f32, deviation: f32, index: usize) {
+        assert!(
+            (expected - actual).abs() <= deviation,
+            "Value {} at index {} is different than {} with deviation of {}",
+            actual,
+            index,
+            expected,
+            deviation
+        );
+    }
+
+    #[test]
+    fn test_fast_simple() {
+        let mut test_values = TEST_VALUES;
+        let mut simple_test_values = TEST_VALUES;
+
+        AraiDiscrete8x8CosineTransformer.transform(&mut test_values);
+        SimpleDiscrete8x8CosineTransformer.transform(&mut simple_test_values);
+        for i in 0..64 {
+            assert_almost_eq(test_values[i], simple_test_values[i], 1e-4, i)
+        }
+    }
+
+    #[test]
+    fn compare_fast_own() {
+        let mut input = TEST_VALUES;
+        AraiDiscrete8x8CosineTransformer::fast_arai(&mut input, 0, 1, &SINGLE_PRECISION_CONSTANTS);
+        let input2 = TEST_VALUES[0..8].try_into().unwrap();
+        assert_eq!(input[0], y0(input2), "Wrong Y0 calculated");
+        assert_eq!(input[4], y4(input2), "Wrong Y4 calculated");
+        assert_eq!(input[2], y2(input2), "Wrong Y2 calculated");
+        assert_eq!(input[6], y6(input2), "Wrong Y6 calculated");
+        assert_eq!(input[5], y5(input2), "Wrong Y5 calculated");
+        assert_eq!(input[1], y1(input2), "Wrong Y1 calculated");
+        assert_eq!(input[7], y7(input2), "Wrong Y7 calculated");
+        assert_eq!(input[3], y3(input2), "Wrong Y3 calculated");
+    }
+
+    #[test]
+    fn inverse_matches_simple_inverse() {
+        let mut test_values = TEST_VALUES;
+        let mut simple_test_values = TEST_VALUES;
+        InverseAraiDiscrete8x8CosineTransformer.transform(&mut test_values);
+        InverseSimpleDiscrete8x8CosineTransformer.transform(&mut simple_test_values);
+        for i in 0..64 {
+            assert_almost_eq(test_values[i], simple_test_values[i], 1e-4, i)
+        }
+    }
+
+    #[test]
+    fn transform_to_frequency_domain_and_back() {
+        let mut test_values = TEST_VALUES;
+        AraiDiscrete8x8CosineTransformer.transform(&mut test_values);
+        InverseAraiDiscrete8x8CosineTransformer.transform(&mut test_values);
+        for i in 0..64 {
+            assert_almost_eq(test_values[i], TEST_VALUES[i], 1e-4, i)
+        }
+    }
+}
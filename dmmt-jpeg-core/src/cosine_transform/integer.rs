@@ -0,0 +1,175 @@
+use super::{Discrete8x8CosineTransformer, DoublePrecisionDiscrete8x8CosineTransformer};
+
+/// Fixed point DCT of libjpeg (`jpeg_fdct_islow`), which computes in 32 bit integers after the
+/// Loeffler, Ligtenberg and Moschytz algorithm. The samples are rounded to integers, so the
+/// result does not depend on the floating point rounding of the platform, and machines without
+/// a strong floating point unit transform faster.
+pub struct IntegerDiscrete8x8CosineTransformer;
+
+/// Bits of the fraction of the constants.
+const CONST_BITS: u32 = 13;
+/// Additional bits of precision, which the intermediate values of the first pass keep.
+const PASS1_BITS: u32 = 2;
+/// Factor of the output of libjpeg compared with the orthonormal DCT of the other transformers.
+const OUTPUT_SCALE: f32 = 8.0;
+
+// constants of the algorithm, round(x * 2^CONST_BITS)
+const FIX_0_298631336: i32 = 2446;
+const FIX_0_390180644: i32 = 3196;
+const FIX_0_541196100: i32 = 4433;
+const FIX_0_765366865: i32 = 6270;
+const FIX_0_899976223: i32 = 7373;
+const FIX_1_175875602: i32 = 9633;
+const FIX_1_501321110: i32 = 12299;
+const FIX_1_847759065: i32 = 15137;
+const FIX_1_961570560: i32 = 16069;
+const FIX_2_053119869: i32 = 16819;
+const FIX_2_562915447: i32 = 20995;
+const FIX_3_072711026: i32 = 25172;
+
+/// Divides by 2^bits and rounds to the nearest integer.
+fn descale(value: i32, bits: u32) -> i32 {
+    (value + (1 << (bits - 1))) >> bits
+}
+
+impl IntegerDiscrete8x8CosineTransformer {
+    /// Transforms the eight values at the offset with the stride. `even_scale` scales the
+    /// coefficients 0 and 4, which need no multiplication, and the products of the other
+    /// coefficients are descaled by `product_descale_bits`.
+    fn transform_values(
+        data: &mut [i32; 64],
+        offset: usize,
+        stride: usize,
+        even_scale: impl Fn(i32) -> i32,
+        product_descale_bits: u32,
+    ) {
+        let d = |index: usize| data[offset + index * stride];
+        let tmp0 = d(0) + d(7);
+        let tmp7 = d(0) - d(7);
+        let tmp1 = d(1) + d(6);
+        let tmp6 = d(1) - d(6);
+        let tmp2 = d(2) + d(5);
+        let tmp5 = d(2) - d(5);
+        let tmp3 = d(3) + d(4);
+        let tmp4 = d(3) - d(4);
+
+        // even part
+        let tmp10 = tmp0 + tmp3;
+        let tmp13 = tmp0 - tmp3;
+        let tmp11 = tmp1 + tmp2;
+        let tmp12 = tmp1 - tmp2;
+        let z1 = (tmp12 + tmp13) * FIX_0_541196100;
+        let out0 = even_scale(tmp10 + tmp11);
+        let out4 = even_scale(tmp10 - tmp11);
+        let out2 = descale(z1 + tmp13 * FIX_0_765366865, product_descale_bits);
+        let out6 = descale(z1 - tmp12 * FIX_1_847759065, product_descale_bits);
+
+        // odd part
+        let z1 = tmp4 + tmp7;
+        let z2 = tmp5 + tmp6;
+        let z3 = tmp4 + tmp6;
+        let z4 = tmp5 + tmp7;
+        let z5 = (z3 + z4) * FIX_1_175875602;
+        let tmp4 = tmp4 * FIX_0_298631336;
+        let tmp5 = tmp5 * FIX_2_053119869;
+        let tmp6 = tmp6 * FIX_3_072711026;
+        let tmp7 = tmp7 * FIX_1_501321110;
+        let z1 = z1 * -FIX_0_899976223;
+        let z2 = z2 * -FIX_2_562915447;
+        let z3 = z3 * -FIX_1_961570560 + z5;
+        let z4 = z4 * -FIX_0_390180644 + z5;
+        let out7 = descale(tmp4 + z1 + z3, product_descale_bits);
+        let out5 = descale(tmp5 + z2 + z4, product_descale_bits);
+        let out3 = descale(tmp6 + z2 + z3, product_descale_bits);
+        let out1 = descale(tmp7 + z1 + z4, product_descale_bits);
+
+        for (index, value) in [out0, out1, out2, out3, out4, out5, out6, out7]
+            .into_iter()
+            .enumerate()
+        {
+            data[offset + index * stride] = value;
+        }
+    }
+
+    /// Transforms the block of integer samples in place. The result is scaled up by 8 like
+    /// the output of libjpeg.
+    pub fn transform_integer(data: &mut [i32; 64]) {
+        // the rows keep PASS1_BITS more bits of precision, which the columns remove
+        for row in 0..8 {
+            Self::transform_values(
+                data,
+                row * 8,
+                1,
+                |value| value << PASS1_BITS,
+                CONST_BITS - PASS1_BITS,
+            );
+        }
+        for column in 0..8 {
+            Self::transform_values(
+                data,
+                column,
+                8,
+                |value| descale(value, PASS1_BITS),
+                CONST_BITS + PASS1_BITS,
+            );
+        }
+    }
+}
+
+impl Discrete8x8CosineTransformer for IntegerDiscrete8x8CosineTransformer {
+    fn transform(&self, block: &mut [f32; 64]) {
+        let mut data = block.map(|value| value.round() as i32);
+        Self::transform_integer(&mut data);
+        for (value, coefficient) in block.iter_mut().zip(data) {
+            *value = coefficient as f32 / OUTPUT_SCALE;
+        }
+    }
+}
+
+/// The samples are rounded to integers in double precision as well, so only the division of the
+/// result is more precise.
+impl DoublePrecisionDiscrete8x8CosineTransformer for IntegerDiscrete8x8CosineTransformer {
+    fn transform_double_precision(&self, block: &mut [f64; 64]) {
+        let mut data = block.map(|value| value.round() as i32);
+        Self::transform_integer(&mut data);
+        for (value, coefficient) in block.iter_mut().zip(data) {
+            *value = coefficient as f64 / OUTPUT_SCALE as f64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::simple::SimpleDiscrete8x8CosineTransformer;
+    use super::super::Discrete8x8CosineTransformer;
+    use super::IntegerDiscrete8x8CosineTransformer;
+
+    #[test]
+    fn match_floating_point_transform() {
+        let samples: [f32; 64] =
+            std::array::from_fn(|index| ((index * 37 + index / 8 * 11) % 255) as f32 - 128.0);
+        let mut integer_block = samples;
+        let mut float_block = samples;
+        IntegerDiscrete8x8CosineTransformer.transform(&mut integer_block);
+        SimpleDiscrete8x8CosineTransformer.transform(&mut float_block);
+        for (index, (actual, expected)) in integer_block.into_iter().zip(float_block).enumerate() {
+            // libjpeg rounds each coefficient to an integer before dividing it by 8
+            assert!(
+                (actual - expected).abs() <= 0.25,
+                "Value {} at index {} is different than {}",
+                actual,
+                index,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn transform_flat_block_to_dc_only() {
+        let mut data = [100; 64];
+        IntegerDiscrete8x8CosineTransformer::transform_integer(&mut data);
+        // DC of libjpeg is 8 times the orthonormal DC of 8 * 100
+        assert_eq!(data[0], 6400);
+        assert!(data[1..].iter().all(|&coefficient| coefficient == 0));
+    }
+}
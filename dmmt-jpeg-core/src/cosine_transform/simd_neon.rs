@@ -0,0 +1,241 @@
+use std::arch::aarch64::{
+    float32x4_t, vaddq_f32, vdupq_n_f32, vld1q_f32, vmulq_n_f32, vnegq_f32, vreinterpretq_f32_f64,
+    vreinterpretq_f64_f32, vst1q_f32, vsubq_f32, vtrn1q_f32, vtrn1q_f64, vtrn2q_f32, vtrn2q_f64,
+};
+
+use super::arai::{A1, A2, A3, A4, A5, S0, S1, S2, S3, S4, S5, S6, S7};
+use super::Discrete8x8CosineTransformer;
+
+/// Left and right half of each row of a block, the columns 0 to 3 and 4 to 7.
+type Halves = [[float32x4_t; 8]; 2];
+
+/// Arai DCT with NEON, which holds each row of the block in two vector registers of four
+/// values. The butterflies of the algorithm combine whole rows, so they transform four columns
+/// at once. The block is transposed between the column and the row pass.
+///
+/// The transformer can only be obtained by [`Self::detect`], which makes sure, that the CPU
+/// supports NEON.
+pub struct SimdNeonDiscrete8x8CosineTransformer {
+    _neon: (),
+}
+
+impl SimdNeonDiscrete8x8CosineTransformer {
+    /// Whether the CPU supports NEON, which the transformer needs. All CPUs of the
+    /// architecture, this crate is built for, are expected to support it.
+    pub fn is_supported() -> bool {
+        std::arch::is_aarch64_feature_detected!("neon")
+    }
+
+    /// The transformer, if the CPU supports NEON.
+    pub fn detect() -> Option<&'static Self> {
+        Self::is_supported().then_some(&Self { _neon: () })
+    }
+
+    /// Transforms the eight vectors in the same way, as
+    /// [`super::arai::AraiDiscrete8x8CosineTransformer`] transforms eight values.
+    #[target_feature(enable = "neon")]
+    unsafe fn fast_arai(v: [float32x4_t; 8]) -> [float32x4_t; 8] {
+        let v10 = vaddq_f32(v[0], v[7]);
+        let v11 = vaddq_f32(v[1], v[6]);
+        let v12 = vaddq_f32(v[2], v[5]);
+        let v13 = vaddq_f32(v[3], v[4]);
+        let v14 = vsubq_f32(v[3], v[4]);
+        let v15 = vsubq_f32(v[2], v[5]);
+        let v16 = vsubq_f32(v[1], v[6]);
+        let v17 = vsubq_f32(v[0], v[7]);
+
+        let v20 = vaddq_f32(v10, v13);
+        let v21 = vaddq_f32(v11, v12);
+        let v22 = vsubq_f32(v11, v12);
+        let v23 = vsubq_f32(v10, v13);
+        let v24 = vnegq_f32(vaddq_f32(v14, v15));
+        let v25 = vaddq_f32(v15, v16);
+        let v26 = vaddq_f32(v16, v17);
+
+        let v30 = vaddq_f32(v20, v21);
+        let v31 = vsubq_f32(v20, v21);
+        let v32 = vaddq_f32(v22, v23);
+
+        let v42 = vmulq_n_f32(v32, A1);
+        let v44 = vsubq_f32(vmulq_n_f32(v24, -A2), vmulq_n_f32(vaddq_f32(v24, v26), A5));
+        let v45 = vmulq_n_f32(v25, A3);
+        let v46 = vsubq_f32(vmulq_n_f32(v26, A4), vmulq_n_f32(vaddq_f32(v26, v24), A5));
+
+        let v52 = vaddq_f32(v42, v23);
+        let v53 = vsubq_f32(v23, v42);
+        let v55 = vaddq_f32(v45, v17);
+        let v57 = vsubq_f32(v17, v45);
+
+        let v64 = vaddq_f32(v44, v57);
+        let v65 = vaddq_f32(v55, v46);
+        let v66 = vsubq_f32(v55, v46);
+        let v67 = vsubq_f32(v57, v44);
+
+        [
+            vmulq_n_f32(v30, S0),
+            vmulq_n_f32(v65, S1),
+            vmulq_n_f32(v52, S2),
+            vmulq_n_f32(v67, S3),
+            vmulq_n_f32(v31, S4),
+            vmulq_n_f32(v64, S5),
+            vmulq_n_f32(v53, S6),
+            vmulq_n_f32(v66, S7),
+        ]
+    }
+
+    /// Transposes the 4x4 values of the four vectors.
+    #[target_feature(enable = "neon")]
+    unsafe fn transpose_4x4(r: &[float32x4_t]) -> [float32x4_t; 4] {
+        let t0 = vreinterpretq_f64_f32(vtrn1q_f32(r[0], r[1]));
+        let t1 = vreinterpretq_f64_f32(vtrn2q_f32(r[0], r[1]));
+        let t2 = vreinterpretq_f64_f32(vtrn1q_f32(r[2], r[3]));
+        let t3 = vreinterpretq_f64_f32(vtrn2q_f32(r[2], r[3]));
+        [
+            vreinterpretq_f32_f64(vtrn1q_f64(t0, t2)),
+            vreinterpretq_f32_f64(vtrn1q_f64(t1, t3)),
+            vreinterpretq_f32_f64(vtrn2q_f64(t0, t2)),
+            vreinterpretq_f32_f64(vtrn2q_f64(t1, t3)),
+        ]
+    }
+
+    /// Transposes the block by transposing its 4x4 quadrants and swapping the upper right and
+    /// lower left quadrant.
+    #[target_feature(enable = "neon")]
+    unsafe fn transpose(halves: Halves) -> Halves {
+        let [left, right] = halves;
+        let upper_left = Self::transpose_4x4(&left[..4]);
+        let lower_left = Self::transpose_4x4(&left[4..]);
+        let upper_right = Self::transpose_4x4(&right[..4]);
+        let lower_right = Self::transpose_4x4(&right[4..]);
+        [
+            [
+                upper_left[0],
+                upper_left[1],
+                upper_left[2],
+                upper_left[3],
+                upper_right[0],
+                upper_right[1],
+                upper_right[2],
+                upper_right[3],
+            ],
+            [
+                lower_left[0],
+                lower_left[1],
+                lower_left[2],
+                lower_left[3],
+                lower_right[0],
+                lower_right[1],
+                lower_right[2],
+                lower_right[3],
+            ],
+        ]
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support NEON.
+    #[target_feature(enable = "neon")]
+    #[inline]
+    unsafe fn transform_neon(block: &mut [f32; 64]) {
+        let block_start = block.as_mut_ptr();
+        let mut halves: Halves = [[vdupq_n_f32(0.0); 8]; 2];
+        for row in 0..8 {
+            halves[0][row] = vld1q_f32(block_start.add(row * 8));
+            halves[1][row] = vld1q_f32(block_start.add(row * 8 + 4));
+        }
+        let columns_transformed =
+            Self::transpose([Self::fast_arai(halves[0]), Self::fast_arai(halves[1])]);
+        let rows_transformed = Self::transpose([
+            Self::fast_arai(columns_transformed[0]),
+            Self::fast_arai(columns_transformed[1]),
+        ]);
+        for row in 0..8 {
+            vst1q_f32(block_start.add(row * 8), rows_transformed[0][row]);
+            vst1q_f32(block_start.add(row * 8 + 4), rows_transformed[1][row]);
+        }
+    }
+
+    /// Transforms the blocks in a loop, which is compiled with NEON as a whole, so the transform
+    /// of each block is inlined instead of called.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support NEON.
+    #[target_feature(enable = "neon")]
+    unsafe fn transform_blocks_neon(blocks: &mut [f32]) {
+        for block in blocks.chunks_exact_mut(64) {
+            let block: &mut [f32; 64] = block
+                .try_into()
+                .expect("Chunks of exactly 64 values are blocks");
+            Self::transform_neon(block);
+        }
+    }
+}
+
+impl Discrete8x8CosineTransformer for SimdNeonDiscrete8x8CosineTransformer {
+    fn transform(&self, block: &mut [f32; 64]) {
+        // SAFETY: the transformer only exists, if the CPU supports NEON, see `Self::detect`
+        unsafe { Self::transform_neon(block) }
+    }
+
+    fn transform_blocks(&self, blocks: &mut [f32]) {
+        assert_eq!(blocks.len() % 64, 0, "Blocks consist of 64 values");
+        // SAFETY: the transformer only exists, if the CPU supports NEON, see `Self::detect`
+        unsafe { Self::transform_blocks_neon(blocks) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::arai::AraiDiscrete8x8CosineTransformer;
+    use super::super::simple::InverseSimpleDiscrete8x8CosineTransformer;
+    use super::super::Discrete8x8CosineTransformer;
+    use super::SimdNeonDiscrete8x8CosineTransformer;
+
+    const TEST_BLOCK: [f32; 64] = [
+        0.736259, 0.2606891, 0.5610827, 0.8214362, 0.9691457, 0.8678548, 0.6238593, 0.5084994,
+        0.8050782, 0.7121189, 0.5455183, 0.9727164, 0.5572985, 0.2453382, 0.8806421, 0.1258583,
+        0.8396557, 0.3285012, 0.348796, 0.7314371, 0.3823053, 0.5750602, 0.5600756, 0.7767876,
+        0.3731192, 0.0588091, 0.6840113, 0.3082369, 0.1330607, 0.4003418, 0.9928281, 0.6752525,
+        0.2386547, 0.1788079, 0.2037415, 0.320719, 0.0138248, 0.8993194, 0.5502792, 0.8301034,
+        0.461806, 0.2384105, 0.3627735, 0.582995, 0.2926725, 0.9669484, 0.4517349, 0.7738883,
+        0.2172307, 0.6634418, 0.6780297, 0.3852351, 0.2001098, 0.6359752, 0.8304086, 0.3636585,
+        0.3370769, 0.1292153, 0.7361369, 0.9847407, 0.7540513, 0.5663624, 0.7456282, 0.474166,
+    ];
+
+    fn assert_eq_with_deviation(actual: f32, expected: f32, deviation: f32, index: usize) {
+        assert!(
+            (actual - expected).abs() < deviation,
+            "Value {} at index {} is different than {} with deviation of {}",
+            actual,
+            index,
+            expected,
+            deviation
+        );
+    }
+
+    #[test]
+    fn test_transform_to_frequency_domain_and_back() {
+        let transformer =
+            SimdNeonDiscrete8x8CosineTransformer::detect().expect("NEON is not supported");
+        let mut test_block = TEST_BLOCK;
+        transformer.transform(&mut test_block);
+        InverseSimpleDiscrete8x8CosineTransformer.transform(&mut test_block);
+        for (index, (actual, expected)) in test_block.into_iter().zip(TEST_BLOCK).enumerate() {
+            assert_eq_with_deviation(actual, expected, 1e-4, index);
+        }
+    }
+
+    #[test]
+    fn match_scalar_arai() {
+        let transformer =
+            SimdNeonDiscrete8x8CosineTransformer::detect().expect("NEON is not supported");
+        let mut simd_block = TEST_BLOCK;
+        let mut scalar_block = TEST_BLOCK;
+        transformer.transform(&mut simd_block);
+        AraiDiscrete8x8CosineTransformer.transform(&mut scalar_block);
+        for (index, (actual, expected)) in simd_block.into_iter().zip(scalar_block).enumerate() {
+            assert_eq_with_deviation(actual, expected, 1e-4, index);
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::arch::x86_64::{
+    __m256, _mm256_add_ps, _mm256_loadu_ps, _mm256_mul_ps, _mm256_permute2f128_ps, _mm256_set1_ps,
+    _mm256_shuffle_ps, _mm256_storeu_ps, _mm256_sub_ps, _mm256_unpackhi_ps, _mm256_unpacklo_ps,
+};
+
+use super::arai::{A1, A2, A3, A4, A5, S0, S1, S2, S3, S4, S5, S6, S7};
+use super::Discrete8x8CosineTransformer;
+
+/// Arai DCT with AVX, which holds a row of the block in each of eight vector registers. The
+/// butterflies of the algorithm combine whole rows, so they transform all eight columns at
+/// once. The block is transposed between the column and the row pass.
+///
+/// The transformer can only be obtained by [`Self::detect`], which makes sure, that the CPU
+/// supports AVX.
+pub struct SimdX86Discrete8x8CosineTransformer {
+    _avx: (),
+}
+
+impl SimdX86Discrete8x8CosineTransformer {
+    /// Whether the CPU supports AVX, which the transformer needs.
+    pub fn is_supported() -> bool {
+        is_x86_feature_detected!("avx")
+    }
+
+    /// The transformer, if the CPU supports AVX.
+    pub fn detect() -> Option<&'static Self> {
+        Self::is_supported().then_some(&Self { _avx: () })
+    }
+
+    /// Transforms the eight vectors of the registers in the same way, as
+    /// [`super::arai::AraiDiscrete8x8CosineTransformer`] transforms eight values.
+    #[target_feature(enable = "avx")]
+    fn fast_arai(v: [__m256; 8]) -> [__m256; 8] {
+        let add = |a, b| _mm256_add_ps(a, b);
+        let sub = |a, b| _mm256_sub_ps(a, b);
+        let mul = |a, factor: f32| _mm256_mul_ps(a, _mm256_set1_ps(factor));
+
+        let v10 = add(v[0], v[7]);
+        let v11 = add(v[1], v[6]);
+        let v12 = add(v[2], v[5]);
+        let v13 = add(v[3], v[4]);
+        let v14 = sub(v[3], v[4]);
+        let v15 = sub(v[2], v[5]);
+        let v16 = sub(v[1], v[6]);
+        let v17 = sub(v[0], v[7]);
+
+        let v20 = add(v10, v13);
+        let v21 = add(v11, v12);
+        let v22 = sub(v11, v12);
+        let v23 = sub(v10, v13);
+        let v24 = mul(add(v14, v15), -1.0);
+        let v25 = add(v15, v16);
+        let v26 = add(v16, v17);
+
+        let v30 = add(v20, v21);
+        let v31 = sub(v20, v21);
+        let v32 = add(v22, v23);
+
+        let v42 = mul(v32, A1);
+        let v44 = sub(mul(v24, -A2), mul(add(v24, v26), A5));
+        let v45 = mul(v25, A3);
+        let v46 = sub(mul(v26, A4), mul(add(v26, v24), A5));
+
+        let v52 = add(v42, v23);
+        let v53 = sub(v23, v42);
+        let v55 = add(v45, v17);
+        let v57 = sub(v17, v45);
+
+        let v64 = add(v44, v57);
+        let v65 = add(v55, v46);
+        let v66 = sub(v55, v46);
+        let v67 = sub(v57, v44);
+
+        [
+            mul(v30, S0),
+            mul(v65, S1),
+            mul(v52, S2),
+            mul(v67, S3),
+            mul(v31, S4),
+            mul(v64, S5),
+            mul(v53, S6),
+            mul(v66, S7),
+        ]
+    }
+
+    #[target_feature(enable = "avx")]
+    fn transpose(r: [__m256; 8]) -> [__m256; 8] {
+        let t0 = _mm256_unpacklo_ps(r[0], r[1]);
+        let t1 = _mm256_unpackhi_ps(r[0], r[1]);
+        let t2 = _mm256_unpacklo_ps(r[2], r[3]);
+        let t3 = _mm256_unpackhi_ps(r[2], r[3]);
+        let t4 = _mm256_unpacklo_ps(r[4], r[5]);
+        let t5 = _mm256_unpackhi_ps(r[4], r[5]);
+        let t6 = _mm256_unpacklo_ps(r[6], r[7]);
+        let t7 = _mm256_unpackhi_ps(r[6], r[7]);
+
+        let s0 = _mm256_shuffle_ps::<0x44>(t0, t2);
+        let s1 = _mm256_shuffle_ps::<0xEE>(t0, t2);
+        let s2 = _mm256_shuffle_ps::<0x44>(t1, t3);
+        let s3 = _mm256_shuffle_ps::<0xEE>(t1, t3);
+        let s4 = _mm256_shuffle_ps::<0x44>(t4, t6);
+        let s5 = _mm256_shuffle_ps::<0xEE>(t4, t6);
+        let s6 = _mm256_shuffle_ps::<0x44>(t5, t7);
+        let s7 = _mm256_shuffle_ps::<0xEE>(t5, t7);
+
+        [
+            _mm256_permute2f128_ps::<0x20>(s0, s4),
+            _mm256_permute2f128_ps::<0x20>(s1, s5),
+            _mm256_permute2f128_ps::<0x20>(s2, s6),
+            _mm256_permute2f128_ps::<0x20>(s3, s7),
+            _mm256_permute2f128_ps::<0x31>(s0, s4),
+            _mm256_permute2f128_ps::<0x31>(s1, s5),
+            _mm256_permute2f128_ps::<0x31>(s2, s6),
+            _mm256_permute2f128_ps::<0x31>(s3, s7),
+        ]
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX.
+    #[target_feature(enable = "avx")]
+    #[inline]
+    unsafe fn transform_avx(block: &mut [f32; 64]) {
+        let block_start = block.as_mut_ptr();
+        let rows = [
+            _mm256_loadu_ps(block_start),
+            _mm256_loadu_ps(block_start.add(8)),
+            _mm256_loadu_ps(block_start.add(16)),
+            _mm256_loadu_ps(block_start.add(24)),
+            _mm256_loadu_ps(block_start.add(32)),
+            _mm256_loadu_ps(block_start.add(40)),
+            _mm256_loadu_ps(block_start.add(48)),
+            _mm256_loadu_ps(block_start.add(56)),
+        ];
+        let columns_transformed = Self::transpose(Self::fast_arai(rows));
+        let rows_transformed = Self::transpose(Self::fast_arai(columns_transformed));
+        for (index, row) in rows_transformed.into_iter().enumerate() {
+            _mm256_storeu_ps(block_start.add(index * 8), row);
+        }
+    }
+
+    /// Transforms the blocks in a loop, which is compiled with AVX as a whole, so the transform
+    /// of each block is inlined instead of called.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX.
+    #[target_feature(enable = "avx")]
+    unsafe fn transform_blocks_avx(blocks: &mut [f32]) {
+        for block in blocks.chunks_exact_mut(64) {
+            let block: &mut [f32; 64] = block
+                .try_into()
+                .expect("Chunks of exactly 64 values are blocks");
+            Self::transform_avx(block);
+        }
+    }
+}
+
+impl Discrete8x8CosineTransformer for SimdX86Discrete8x8CosineTransformer {
+    fn transform(&self, block: &mut [f32; 64]) {
+        // SAFETY: the transformer only exists, if the CPU supports AVX, see `Self::detect`
+        unsafe { Self::transform_avx(block) }
+    }
+
+    fn transform_blocks(&self, blocks: &mut [f32]) {
+        assert_eq!(blocks.len() % 64, 0, "Blocks consist of 64 values");
+        // SAFETY: the transformer only exists, if the CPU supports AVX, see `Self::detect`
+        unsafe { Self::transform_blocks_avx(blocks) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::arai::AraiDiscrete8x8CosineTransformer;
+    use super::super::Discrete8x8CosineTransformer;
+    use super::SimdX86Discrete8x8CosineTransformer;
+
+    #[test]
+    fn match_scalar_arai() {
+        let Some(transformer) = SimdX86Discrete8x8CosineTransformer::detect() else {
+            return;
+        };
+        let values: [f32; 64] =
+            std::array::from_fn(|index| ((index * 37 + index / 8 * 11) % 255) as f32 - 128.0);
+        let mut simd_values = values;
+        let mut scalar_values = values;
+        transformer.transform(&mut simd_values);
+        AraiDiscrete8x8CosineTransformer.transform(&mut scalar_values);
+        for (index, (simd, scalar)) in simd_values.iter().zip(scalar_values).enumerate() {
+            assert!(
+                (simd - scalar).abs() <= 1e-3,
+                "Value {} at index {} differs from {}",
+                simd,
+                index,
+                scalar
+            );
+        }
+    }
+}
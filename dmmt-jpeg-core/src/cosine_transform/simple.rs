@@ -1,6 +1,6 @@
-use core::{f32, slice};
+use core::f32;
 
-use super::Discrete8x8CosineTransformer;
+use super::{Discrete8x8CosineTransformer, DoublePrecisionDiscrete8x8CosineTransformer};
 
 const SQUARE_SIZE: usize = 8;
 const NUMBER_OF_VALUES: usize = SQUARE_SIZE * SQUARE_SIZE;
@@ -43,8 +43,7 @@ impl SimpleDiscrete8x8CosineTransformer {
 }
 
 impl Discrete8x8CosineTransformer for SimpleDiscrete8x8CosineTransformer {
-    unsafe fn transform(&self, block_start: *mut f32) {
-        let values = slice::from_raw_parts_mut(block_start, NUMBER_OF_VALUES);
+    fn transform(&self, values: &mut [f32; 64]) {
         let transformed_values = (0..NUMBER_OF_VALUES)
             .map(|index| {
                 let i = index % SQUARE_SIZE;
@@ -52,12 +51,42 @@ impl Discrete8x8CosineTransformer for SimpleDiscrete8x8CosineTransformer {
                 Self::calculate_value(i, j, values)
             })
             .collect::<Vec<f32>>();
-        for (value, t_value) in values.iter_mut().zip(transformed_values.into_iter()) {
+        for (value, t_value) in values.iter_mut().zip(transformed_values) {
             *value = t_value;
         }
     }
 }
 
+impl DoublePrecisionDiscrete8x8CosineTransformer for SimpleDiscrete8x8CosineTransformer {
+    fn transform_double_precision(&self, values: &mut [f64; 64]) {
+        let cosine = |a: usize, b: usize| {
+            ((2 * a + 1) as f64 * b as f64 * std::f64::consts::PI / (2 * SQUARE_SIZE) as f64).cos()
+        };
+        let factor_c = |value: usize| {
+            if value == 0 {
+                std::f64::consts::FRAC_1_SQRT_2
+            } else {
+                1.0
+            }
+        };
+        let transformed_values: [f64; NUMBER_OF_VALUES] = std::array::from_fn(|index| {
+            let i = index % SQUARE_SIZE;
+            let j = index / SQUARE_SIZE;
+            let sum: f64 = values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| {
+                    let x = index % SQUARE_SIZE;
+                    let y = index / SQUARE_SIZE;
+                    value * cosine(x, i) * cosine(y, j)
+                })
+                .sum();
+            2.0 / SQUARE_SIZE as f64 * factor_c(i) * factor_c(j) * sum
+        });
+        *values = transformed_values;
+    }
+}
+
 pub struct InverseSimpleDiscrete8x8CosineTransformer;
 
 impl InverseSimpleDiscrete8x8CosineTransformer {
@@ -83,8 +112,7 @@ impl InverseSimpleDiscrete8x8CosineTransformer {
 }
 
 impl Discrete8x8CosineTransformer for InverseSimpleDiscrete8x8CosineTransformer {
-    unsafe fn transform(&self, block_start: *mut f32) {
-        let values = slice::from_raw_parts_mut(block_start, NUMBER_OF_VALUES);
+    fn transform(&self, values: &mut [f32; 64]) {
         let transformed_values = (0..NUMBER_OF_VALUES)
             .map(|index| {
                 let x = index % SQUARE_SIZE;
@@ -92,7 +120,7 @@ impl Discrete8x8CosineTransformer for InverseSimpleDiscrete8x8CosineTransformer
                 Self::calculate_value(x, y, values)
             })
             .collect::<Vec<f32>>();
-        for (value, t_value) in values.iter_mut().zip(transformed_values.into_iter()) {
+        for (value, t_value) in values.iter_mut().zip(transformed_values) {
             *value = t_value;
         }
     }
@@ -144,11 +172,9 @@ mod test {
     fn test_transform_to_frequency_domain_and_back() {
         let deviation = 1e-6_f32;
         let mut test_block = TEST_BLOCK;
-        unsafe {
-            SimpleDiscrete8x8CosineTransformer.transform(&raw mut test_block[0]);
-            assert_values_not_zero(&test_block);
-            InverseSimpleDiscrete8x8CosineTransformer.transform(&raw mut test_block[0]);
-        }
+        SimpleDiscrete8x8CosineTransformer.transform(&mut test_block);
+        assert_values_not_zero(&test_block);
+        InverseSimpleDiscrete8x8CosineTransformer.transform(&mut test_block);
         for (index, (actual, expected)) in test_block.into_iter().zip(TEST_BLOCK).enumerate() {
             assert_eq_with_deviation(actual, expected, deviation, index);
         }
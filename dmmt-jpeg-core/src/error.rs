@@ -0,0 +1,490 @@
+use std::fmt::Display;
+
+/// Position of a token in a text based image file. Lines and columns start at 1, the byte
+/// offset at 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextPosition {
+    pub byte: u64,
+    pub line: u64,
+    pub column: u64,
+}
+
+impl Display for TextPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {} (byte {})",
+            self.line, self.column, self.byte
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    PPMFileDoesNotContainRequiredToken(&'static str),
+    PAMFileDoesNotContainRequiredToken(&'static str),
+    UnsupportedPAMTupleType(String),
+    PAMDepthDoesNotMatchTupleType(u8),
+    PFMFileDoesNotContainRequiredToken(&'static str),
+    Y4MStreamDoesNotContainRequiredToken(&'static str),
+    UnsupportedY4MColorSpace(String),
+    UnableToDecodePNGImage(String),
+    ImageDimensionsNotSupported(u32, u32),
+    EmptyImage(u16, u16),
+    ImageDimensionsDiffer((u16, u16), (u16, u16)),
+    UnsupportedInputFormat(String),
+    CMYKImageNotExpected,
+    CMYKImageExpected,
+    InvalidTIFFFile(&'static str),
+    TIFFFileDoesNotContainRequiredTag(u16),
+    UnsupportedTIFFFeature(&'static str, u32),
+    InvalidJPEGFile(&'static str),
+    UnsupportedJPEGFeature(&'static str, u32),
+    CropRegionOutsideOfImage(String, u16, u16),
+    InvalidSamplingFactors(u8, u8),
+    TooManyBlocksPerMCU(usize),
+    ParsingOfTokenFailed(&'static str, Option<TextPosition>),
+    TokenExceedsMaximumLength(usize, Option<TextPosition>),
+    PixelCountExceedsLimit(u64, u64),
+    IncompletePixelParsed(usize, TextPosition),
+    MismatchOfSizeBetweenHeaderAndValues,
+    MismatchOfSizeBetweenDimensionsAndBuffer(usize, usize),
+    InputFileNotFound(String),
+    NoReadPermissionForInputFile(String),
+    UnableToOpenInputFileForReading(String, std::io::Error),
+    UnableToOpenOutputFileForWriting(String, std::io::Error),
+    OutputDirectoryNotFound(String),
+    NoWritePermissionForOutputFile(String),
+    UnableToCreateOutputDirectory(String, std::io::Error),
+    UnableToMapInputFile(String, std::io::Error),
+    UnableToWriteRestartIndexFile(String, std::io::Error),
+    UnableToWriteSymbolStatsFile(String, std::io::Error),
+    UnableToWriteHuffmanTablesFile(String, std::io::Error),
+    FeatureNotEnabled(&'static str),
+    FailedToWriteStartOfFile,
+    FailedToWriteHuffmanTables,
+    FailedToWriteEndOfFile,
+    FailedToWriteJfifApplicationHeader,
+    FailedToWriteExifApplicationHeader,
+    FailedToWriteXmpApplicationHeader,
+    FailedToWriteAdobeApplicationHeader,
+    FailedToWriteApplicationSegment,
+    FailedToWriteComment,
+    XmpPacketTooLarge(usize),
+    CommentTooLong(usize),
+    InvalidApplicationMarkerNumber(u8),
+    ApplicationSegmentTooLarge(usize),
+    InvalidThumbnailSize(u16, u16),
+    UnsupportedSamplePrecision(u8),
+    GrayscaleCMYKImage,
+    InvalidMaxHuffmanCodeLength(u8),
+    UnableToReadXmpFile(String, std::io::Error),
+    UnableToReadGpsSidecarFile(String, std::io::Error),
+    InvalidGpsSidecarFile(String, String),
+    UnableToReadQuantizationTableFile(String, std::io::Error),
+    InvalidQuantizationTableFile(String, String),
+    UnableToReadHuffmanTablesFile(String, std::io::Error),
+    InvalidHuffmanTablesFile(String, String),
+    FailedToWriteQuantizationTable,
+    FailedToWriteRestartInterval,
+    FailedToWriteNumberOfLines,
+    FailedToWriteStartOfFrame,
+    FailedToWriteStartOfScan,
+    FailedToWriteImageData,
+    HuffmanSymbolNotPresentInTranslator(u8, &'static str),
+    HuffmanTableVerificationFailed(&'static str, String),
+    FailedToWriteBlock,
+    FailedToFlushImage(std::io::Error),
+    FailedToWriteBenchmarkReport(std::io::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PPMFileDoesNotContainRequiredToken(token_name) => {
+                write!(f, "Expected token '{}' not found in PPM file", token_name)
+            }
+            Self::PAMFileDoesNotContainRequiredToken(token_name) => {
+                write!(f, "Expected token '{}' not found in PAM file", token_name)
+            }
+            Self::UnsupportedPAMTupleType(tuple_type) => {
+                write!(
+                    f,
+                    "PAM tuple type '{}' is not supported. Expected RGB or GRAYSCALE.",
+                    tuple_type
+                )
+            }
+            Self::PAMDepthDoesNotMatchTupleType(depth) => {
+                write!(f, "PAM depth {} does not match the tuple type", depth)
+            }
+            Self::PFMFileDoesNotContainRequiredToken(token_name) => {
+                write!(f, "Expected token '{}' not found in PFM file", token_name)
+            }
+            Self::Y4MStreamDoesNotContainRequiredToken(token_name) => {
+                write!(f, "Expected token '{}' not found in Y4M stream", token_name)
+            }
+            Self::UnsupportedY4MColorSpace(color_space) => {
+                write!(
+                    f,
+                    "Y4M color space '{}' is not supported. Expected 8 bit 420, 422, 444 or mono.",
+                    color_space
+                )
+            }
+            Self::UnableToDecodePNGImage(reason) => {
+                write!(f, "Unable to decode PNG image: {}", reason)
+            }
+            Self::ImageDimensionsNotSupported(width, height) => {
+                write!(
+                    f,
+                    "Image dimensions {}x{} are not supported. Width and height must not exceed {}.",
+                    width,
+                    height,
+                    u16::MAX
+                )
+            }
+            Self::EmptyImage(width, height) => {
+                write!(
+                    f,
+                    "Image of {}x{} pixels is empty. Width and height must be at least 1.",
+                    width, height
+                )
+            }
+            Self::ImageDimensionsDiffer((width, height), (other_width, other_height)) => {
+                write!(
+                    f,
+                    "Images of {}x{} and {}x{} pixels cannot be compared",
+                    width, height, other_width, other_height
+                )
+            }
+            Self::UnsupportedInputFormat(extension) => {
+                write!(
+                    f,
+                    "Input format '{}' is not supported by this build",
+                    extension
+                )
+            }
+            Self::CMYKImageNotExpected => {
+                write!(
+                    f,
+                    "Input image has CMYK samples, which must be read as CMYK image"
+                )
+            }
+            Self::CMYKImageExpected => {
+                write!(f, "Input image has no CMYK samples")
+            }
+            Self::InvalidTIFFFile(reason) => {
+                write!(f, "Invalid TIFF file: {}", reason)
+            }
+            Self::TIFFFileDoesNotContainRequiredTag(tag) => {
+                write!(f, "Required tag {} not found in TIFF file", tag)
+            }
+            Self::UnsupportedTIFFFeature(feature, value) => {
+                write!(f, "TIFF {} {} is not supported", feature, value)
+            }
+            Self::InvalidJPEGFile(reason) => {
+                write!(f, "Invalid JPEG file: {}", reason)
+            }
+            Self::UnsupportedJPEGFeature(feature, value) => {
+                write!(f, "JPEG {} {} is not supported", feature, value)
+            }
+            Self::CropRegionOutsideOfImage(region, width, height) => {
+                write!(
+                    f,
+                    "Crop region '{}' is outside of the {}x{} image",
+                    region, width, height
+                )
+            }
+            Self::InvalidSamplingFactors(horizontal, vertical) => {
+                write!(
+                    f,
+                    "Sampling factors {}x{} are invalid. Factors must be in range 1 to 4 and divide the luma factors.",
+                    horizontal, vertical
+                )
+            }
+            Self::TooManyBlocksPerMCU(blocks) => {
+                write!(
+                    f,
+                    "Sampling factors require {} blocks per MCU, but at most 10 are allowed",
+                    blocks
+                )
+            }
+            Self::ParsingOfTokenFailed(token_name, None) => {
+                write!(f, "Parsing of token '{}' failed", token_name)
+            }
+            Self::ParsingOfTokenFailed(token_name, Some(position)) => {
+                write!(
+                    f,
+                    "Parsing of token '{}' at {} failed",
+                    token_name, position
+                )
+            }
+            Self::TokenExceedsMaximumLength(max_length, None) => {
+                write!(
+                    f,
+                    "Token exceeds the maximum length of {} bytes",
+                    max_length
+                )
+            }
+            Self::TokenExceedsMaximumLength(max_length, Some(position)) => {
+                write!(
+                    f,
+                    "Token at {} exceeds the maximum length of {} bytes",
+                    position, max_length
+                )
+            }
+            Self::PixelCountExceedsLimit(pixel_count, max_pixel_count) => {
+                write!(
+                    f,
+                    "Image declares {} pixels, which exceeds the limit of {} pixels",
+                    pixel_count, max_pixel_count
+                )
+            }
+            Self::IncompletePixelParsed(number_of_tokens_parsed, position) => {
+                write!(
+                    f,
+                    "Incomplete pixel parsed at {}. Expected 3 components, but got {}.",
+                    position, number_of_tokens_parsed
+                )
+            }
+            Self::MismatchOfSizeBetweenHeaderAndValues => {
+                write!(
+                    f,
+                    "Nubmer of pixels do not match the size, provided in header"
+                )
+            }
+            Self::MismatchOfSizeBetweenDimensionsAndBuffer(expected, actual) => {
+                write!(
+                    f,
+                    "Buffer holds {} samples, but the image dimensions require {}",
+                    actual, expected
+                )
+            }
+            Self::InputFileNotFound(path) => {
+                write!(f, "Input file '{}' not found", path)
+            }
+            Self::NoReadPermissionForInputFile(path) => {
+                write!(
+                    f,
+                    "Unable open file '{}' for reading. Permission denied.",
+                    path
+                )
+            }
+            Self::UnableToOpenInputFileForReading(path, error) => {
+                write!(
+                    f,
+                    "Unable to open input file '{}' for reading: {}",
+                    path, error
+                )
+            }
+            Self::UnableToOpenOutputFileForWriting(path, error) => {
+                write!(
+                    f,
+                    "Unable to open output file '{}' for writing: {}",
+                    path, error
+                )
+            }
+            Self::OutputDirectoryNotFound(path) => {
+                write!(
+                    f,
+                    "Directory '{}' of the output file does not exist. Create it or pass --create-dirs.",
+                    path
+                )
+            }
+            Self::NoWritePermissionForOutputFile(path) => {
+                write!(
+                    f,
+                    "Unable to open file '{}' for writing. Permission denied.",
+                    path
+                )
+            }
+            Self::UnableToCreateOutputDirectory(path, error) => {
+                write!(f, "Unable to create output directory '{}': {}", path, error)
+            }
+            Self::UnableToMapInputFile(path, error) => {
+                write!(
+                    f,
+                    "Unable to map input file '{}' into memory: {}",
+                    path, error
+                )
+            }
+            Self::UnableToWriteRestartIndexFile(path, error) => {
+                write!(
+                    f,
+                    "Unable to write restart index file '{}': {}",
+                    path, error
+                )
+            }
+            Self::UnableToWriteSymbolStatsFile(path, error) => {
+                write!(
+                    f,
+                    "Unable to write symbol statistics file '{}': {}",
+                    path, error
+                )
+            }
+            Self::UnableToWriteHuffmanTablesFile(path, error) => {
+                write!(
+                    f,
+                    "Unable to write Huffman tables file '{}': {}",
+                    path, error
+                )
+            }
+            Self::FeatureNotEnabled(feature) => {
+                write!(
+                    f,
+                    "Support for '{}' is not enabled. Build with the cargo feature '{}'",
+                    feature, feature
+                )
+            }
+            Error::FailedToWriteStartOfFile => {
+                write!(f, "Failed to write start of file control marker")
+            }
+            Error::FailedToWriteEndOfFile => {
+                write!(f, "Failed to write end of file control marker")
+            }
+            Error::FailedToWriteHuffmanTables => {
+                write!(f, "Failed to write huffmann tables")
+            }
+            Error::FailedToWriteJfifApplicationHeader => {
+                write!(f, "Failed to write JFIF application header")
+            }
+            Error::FailedToWriteExifApplicationHeader => {
+                write!(f, "Failed to write EXIF application header")
+            }
+            Error::FailedToWriteXmpApplicationHeader => {
+                write!(f, "Failed to write XMP application header")
+            }
+            Error::FailedToWriteAdobeApplicationHeader => {
+                write!(f, "Failed to write Adobe application header")
+            }
+            Error::FailedToWriteApplicationSegment => {
+                write!(f, "Failed to write application segment")
+            }
+            Error::FailedToWriteComment => {
+                write!(f, "Failed to write comment")
+            }
+            Error::XmpPacketTooLarge(length) => {
+                write!(
+                    f,
+                    "XMP packet of {} bytes does not fit into a single segment of at most {} bytes",
+                    length,
+                    crate::image::writer::jpeg::xmp::MAX_XMP_PACKET_LENGTH
+                )
+            }
+            Error::CommentTooLong(length) => {
+                write!(
+                    f,
+                    "Comment of {} bytes does not fit into a single segment of at most {} bytes",
+                    length,
+                    crate::image::writer::jpeg::MAX_COMMENT_LENGTH
+                )
+            }
+            Error::InvalidApplicationMarkerNumber(number) => {
+                write!(
+                    f,
+                    "Application marker number {} is out of the range from 0 to 15",
+                    number
+                )
+            }
+            Error::ApplicationSegmentTooLarge(length) => {
+                write!(
+                    f,
+                    "Application segment of {} bytes does not fit into a single segment of at most {} bytes",
+                    length,
+                    crate::image::writer::jpeg::MAX_APPLICATION_SEGMENT_LENGTH
+                )
+            }
+            Error::InvalidThumbnailSize(width, height) => {
+                write!(
+                    f,
+                    "Thumbnail of {}x{} pixels does not fit into the JFIF header, which holds at most {} pixels of at most 255x255",
+                    width,
+                    height,
+                    crate::image::writer::jpeg::thumbnail::MAX_THUMBNAIL_PIXELS
+                )
+            }
+            Error::UnsupportedSamplePrecision(precision) => {
+                write!(
+                    f,
+                    "Sample precision of {} bits is not supported, only 8 and 12 bits are",
+                    precision
+                )
+            }
+            Error::GrayscaleCMYKImage => {
+                write!(f, "CMYK images can't be encoded in grayscale")
+            }
+            Error::InvalidMaxHuffmanCodeLength(length) => {
+                write!(
+                    f,
+                    "Maximum Huffman code length of {} bits is not between {} and {} bits",
+                    length,
+                    crate::image::writer::jpeg::MIN_HUFFMAN_CODE_LENGTH_LIMIT,
+                    crate::image::writer::jpeg::MAX_HUFFMAN_CODE_LENGTH
+                )
+            }
+            Error::UnableToReadXmpFile(path, error) => {
+                write!(f, "Unable to read XMP file '{}': {}", path, error)
+            }
+            Error::UnableToReadGpsSidecarFile(path, error) => {
+                write!(f, "Unable to read GPS sidecar file '{}': {}", path, error)
+            }
+            Error::InvalidGpsSidecarFile(path, reason) => {
+                write!(f, "Invalid GPS sidecar file '{}': {}", path, reason)
+            }
+            Error::UnableToReadQuantizationTableFile(path, error) => {
+                write!(
+                    f,
+                    "Unable to read quantization table file '{}': {}",
+                    path, error
+                )
+            }
+            Error::InvalidQuantizationTableFile(path, reason) => {
+                write!(f, "Invalid quantization table file '{}': {}", path, reason)
+            }
+            Error::UnableToReadHuffmanTablesFile(path, error) => {
+                write!(
+                    f,
+                    "Unable to read Huffman tables file '{}': {}",
+                    path, error
+                )
+            }
+            Error::InvalidHuffmanTablesFile(path, reason) => {
+                write!(f, "Invalid Huffman tables file '{}': {}", path, reason)
+            }
+            Error::FailedToWriteQuantizationTable => {
+                write!(f, "Failed to write quantization table")
+            }
+            Error::FailedToWriteRestartInterval => {
+                write!(f, "Failed to write restart interval definition")
+            }
+            Error::FailedToWriteNumberOfLines => {
+                write!(f, "Failed to write number of lines segment")
+            }
+            Error::FailedToWriteStartOfFrame => write!(f, "Failed to write start of frame segment"),
+            Error::FailedToWriteStartOfScan => write!(f, "Failed to write start of scan segment"),
+            Error::FailedToWriteImageData => write!(f, "Failed to write image data"),
+            Error::HuffmanSymbolNotPresentInTranslator(symbol, translator) => {
+                write!(
+                    f,
+                    "Huffman symbol '{}' not present in {} translator",
+                    symbol, translator
+                )
+            }
+            Error::HuffmanTableVerificationFailed(table, reason) => {
+                write!(
+                    f,
+                    "Huffman table of {} does not decode the coded symbols: {}",
+                    table, reason
+                )
+            }
+            Error::FailedToWriteBlock => write!(f, "Failed to write image block"),
+            Error::FailedToFlushImage(e) => {
+                write!(f, "Failed to flush the written image: {}", e)
+            }
+            Error::FailedToWriteBenchmarkReport(e) => {
+                write!(f, "Failed to write benchmark report: {}", e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,85 @@
+pub mod code;
+pub mod coding_error;
+pub mod decoder;
+pub mod encoder;
+pub mod length_limited;
+pub mod standard_tables;
+pub mod tree;
+
+pub type Symbol = u8;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SymbolCodeLength {
+    pub symbol: Symbol,
+    pub length: usize,
+}
+
+impl SymbolCodeLength {
+    pub fn new(symbol: Symbol, length: usize) -> Self {
+        Self { symbol, length }
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+}
+
+impl From<(Symbol, usize)> for SymbolCodeLength {
+    fn from(value: (Symbol, usize)) -> Self {
+        Self {
+            symbol: value.0,
+            length: value.1,
+        }
+    }
+}
+
+/// Number of codes of each length from 1 to 16, as the DHT segment lists them.
+pub fn code_length_counts(code_lengths: &[SymbolCodeLength]) -> [u8; 16] {
+    let mut counts = [0; 16];
+    for code_length in code_lengths {
+        counts[code_length.length - 1] += 1;
+    }
+    counts
+}
+
+/// Converts the code length counts and symbols of a DHT segment into code lengths sorted by
+/// descending length, as the encoder expects them. Symbols of the same length keep their order,
+/// so the assigned code words match those of the segment.
+pub fn code_lengths_of_counts(
+    code_length_counts: &[u8; 16],
+    symbols: &[Symbol],
+) -> Vec<SymbolCodeLength> {
+    let mut symbols = symbols.iter();
+    let mut code_lengths: Vec<SymbolCodeLength> = code_length_counts
+        .iter()
+        .enumerate()
+        .flat_map(|(index, &count)| (0..count).map(move |_| index + 1))
+        .map(|length| SymbolCodeLength::new(*symbols.next().unwrap(), length))
+        .collect();
+    code_lengths.reverse();
+    code_lengths
+}
+
+pub struct SymbolFrequency {
+    pub symbol: Symbol,
+    pub frequency: usize,
+}
+
+impl SymbolFrequency {
+    pub fn new(symbol: Symbol, frequency: usize) -> Self {
+        Self { symbol, frequency }
+    }
+}
+
+impl From<(Symbol, usize)> for SymbolFrequency {
+    fn from(value: (Symbol, usize)) -> Self {
+        Self {
+            symbol: value.0,
+            frequency: value.1,
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use std::io;
+
+#[derive(Debug)]
+pub enum CodingError {
+    /// The bits could not be read from the inner reader.
+    ReadFailed(io::Error),
+    /// The data ends within a code word, which is not the padding of the last byte.
+    UnexpectedEndOfData,
+    /// The bits are no code word of the tree, like the unused code word of only ones.
+    InvalidCodeWord,
+    /// The tree consists of a single symbol without code word, so nothing can be decoded.
+    DegenerateTree,
+}
+
+impl From<io::Error> for CodingError {
+    fn from(error: io::Error) -> Self {
+        Self::ReadFailed(error)
+    }
+}
@@ -0,0 +1,132 @@
+use super::{code_length_counts, SymbolCodeLength};
+use crate::Error;
+
+/// Huffman decoder built from the code length counts and symbols of a DHT segment, prepared for
+/// decoding as described in F.2.2.3.
+pub struct HuffmanDecoder {
+    /// Largest code of each length, or -1 if there is no code of the length.
+    max_codes: [i32; 17],
+    /// Difference between the index of the first symbol and the first code of each length.
+    value_offsets: [i32; 17],
+    symbols: Vec<u8>,
+}
+
+impl HuffmanDecoder {
+    /// Decoder of the table, which has `code_length_counts[i]` codes of length `i + 1` and lists
+    /// the symbols in the order of ascending code words, as a DHT segment does.
+    pub fn new(code_length_counts: &[u8; 16], symbols: &[u8]) -> crate::Result<Self> {
+        let number_of_codes: usize = code_length_counts.iter().map(|&c| c as usize).sum();
+        if number_of_codes != symbols.len() || number_of_codes > 256 {
+            return Err(Error::InvalidJPEGFile(
+                "huffman table symbol count mismatch",
+            ));
+        }
+        let mut max_codes = [-1; 17];
+        let mut value_offsets = [0; 17];
+        let mut code = 0_i32;
+        let mut symbol_index = 0_i32;
+        for (length_index, &count) in code_length_counts.iter().enumerate() {
+            let length = length_index + 1;
+            if count > 0 {
+                value_offsets[length] = symbol_index - code;
+                code += count as i32;
+                symbol_index += count as i32;
+                max_codes[length] = code - 1;
+            }
+            if code > 1 << length {
+                return Err(Error::InvalidJPEGFile("huffman code lengths overflow"));
+            }
+            code <<= 1;
+        }
+        Ok(Self {
+            max_codes,
+            value_offsets,
+            symbols: symbols.to_vec(),
+        })
+    }
+
+    /// Decoder of the code lengths sorted by descending length, as the encoder takes them. The
+    /// table is the same, which the encoder writes into the DHT segment.
+    pub fn from_code_lengths(code_lengths: &[SymbolCodeLength]) -> crate::Result<Self> {
+        let symbols: Vec<u8> = code_lengths
+            .iter()
+            .rev()
+            .map(|code_length| code_length.symbol)
+            .collect();
+        Self::new(&code_length_counts(code_lengths), &symbols)
+    }
+
+    /// Decodes the next symbol from the bits returned by `read_bit`.
+    pub fn decode(&self, mut read_bit: impl FnMut() -> crate::Result<u16>) -> crate::Result<u8> {
+        let mut code = 0_i32;
+        for length in 1..=16 {
+            code = code << 1 | read_bit()? as i32;
+            if code <= self.max_codes[length] {
+                let index = (code + self.value_offsets[length]) as usize;
+                return Ok(self.symbols[index]);
+            }
+        }
+        Err(Error::InvalidJPEGFile("invalid huffman code"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use super::HuffmanDecoder;
+    use crate::{
+        binary_stream::BitWriter,
+        huffman::{
+            encoder::{HuffmanTranslator, HuffmanWriter},
+            standard_tables,
+        },
+        image::writer::jpeg::consts::ANNEX_K_LUMINANCE_DC_HUFFMAN_TABLE,
+    };
+
+    fn decode_bits(decoder: &HuffmanDecoder, bits: &[u16]) -> u8 {
+        let mut bits = bits.iter();
+        decoder.decode(|| Ok(*bits.next().unwrap())).unwrap()
+    }
+
+    #[test]
+    fn decode_annex_k_luminance_dc_codes() {
+        let specification = ANNEX_K_LUMINANCE_DC_HUFFMAN_TABLE;
+        let decoder =
+            HuffmanDecoder::new(&specification.code_length_counts, specification.symbols).unwrap();
+        assert_eq!(decode_bits(&decoder, &[0, 0]), 0);
+        assert_eq!(decode_bits(&decoder, &[0, 1, 0]), 1);
+        assert_eq!(decode_bits(&decoder, &[1, 1, 0]), 5);
+        assert_eq!(decode_bits(&decoder, &[1, 1, 1, 1, 1, 1, 1, 1, 0]), 11);
+    }
+
+    #[test]
+    fn reject_overflowing_code_lengths() {
+        let mut counts = [0; 16];
+        counts[0] = 3;
+        assert!(HuffmanDecoder::new(&counts, &[0, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn decode_symbols_of_the_encoder() {
+        let code_lengths = standard_tables::luminance_ac();
+        let symbols: Vec<u8> = code_lengths
+            .iter()
+            .map(|code_length| code_length.symbol)
+            .collect();
+        let translator = HuffmanTranslator::from(&code_lengths);
+        let mut output = Vec::new();
+        let mut bit_writer = BitWriter::new(&mut output, true);
+        let mut writer = HuffmanWriter::new(&translator, &mut bit_writer);
+        writer.write_all(&symbols).unwrap();
+        writer.flush().unwrap();
+
+        let decoder = HuffmanDecoder::from_code_lengths(&code_lengths).unwrap();
+        let mut bits = output
+            .iter()
+            .flat_map(|&byte| (0..8).rev().map(move |shift| (byte >> shift & 1) as u16));
+        for &symbol in &symbols {
+            assert_eq!(decoder.decode(|| Ok(bits.next().unwrap())).unwrap(), symbol);
+        }
+    }
+}
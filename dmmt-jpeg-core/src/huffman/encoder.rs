@@ -27,6 +27,10 @@ impl BitPattern for CodeWord {
     fn bit_len(&self) -> usize {
         self.length
     }
+
+    fn to_u32(&self) -> Option<u32> {
+        Some(self.bit_pattern as u32 >> (CodeBitPattern::BITS as usize - self.length))
+    }
 }
 
 pub struct HuffmanTranslator {
@@ -90,6 +94,10 @@ impl<'a> HuffmanTranslator {
                 CodeBitPattern::BITS
             );
         }
+
+        if code_lengths.clone().last().unwrap().length == 0 {
+            panic!("code words must be at least 1 bit long");
+        }
     }
 }
 
@@ -126,6 +134,26 @@ impl HuffmanTranslator {
         &self.code_word_lookup_table[symbol as usize]
     }
 
+    /// Length in bits of the code word of the symbol or `None`, if the symbol is not present.
+    pub fn code_word_length(&self, symbol: Symbol) -> Option<usize> {
+        self.get_code_word_for_symbol(symbol)
+            .as_ref()
+            .map(|code_word| code_word.length)
+    }
+
+    /// Code word lengths of all present symbols in ascending order of the symbols.
+    pub fn code_word_lengths(&self) -> Vec<SymbolCodeLength> {
+        self.code_word_lookup_table
+            .iter()
+            .enumerate()
+            .filter_map(|(symbol, code_word)| {
+                code_word
+                    .as_ref()
+                    .map(|code_word| SymbolCodeLength::new(symbol as Symbol, code_word.length))
+            })
+            .collect()
+    }
+
     fn ensure_symbol_was_not_inserted_before(&self, symbol: Symbol) {
         if self.symbol_exists(symbol) {
             panic!(
@@ -140,6 +168,15 @@ impl HuffmanTranslator {
     }
 }
 
+/// Translator without any symbols, for tables which are not written.
+impl Default for HuffmanTranslator {
+    fn default() -> Self {
+        Self {
+            code_word_lookup_table: [const { None }; Symbol::MAX as usize],
+        }
+    }
+}
+
 impl<'a, T, I> From<T> for HuffmanTranslator
 where
     T: IntoIterator<Item = &'a SymbolCodeLength, IntoIter = I>,
@@ -148,9 +185,7 @@ where
     fn from(code_lengths: T) -> Self {
         let code_lengths_iterator = code_lengths.into_iter();
         Self::validate_input_code_lengths(&code_lengths_iterator);
-        let mut encoder = HuffmanTranslator {
-            code_word_lookup_table: [const { None }; Symbol::MAX as usize],
-        };
+        let mut encoder = HuffmanTranslator::default();
         encoder.fill_lookup_table(&code_lengths_iterator);
         encoder
     }
@@ -210,6 +245,27 @@ mod test {
         let _ = HuffmanTranslator::from(&symbols);
     }
 
+    #[test]
+    #[should_panic]
+    fn test_empty_code_word() {
+        let symbols = [(0, 0)].map(SymbolCodeLength::from);
+        let _ = HuffmanTranslator::from(&symbols);
+    }
+
+    #[test]
+    fn test_single_symbol() -> io::Result<()> {
+        let symbols = [(7, 1)].map(SymbolCodeLength::from);
+        let translator = HuffmanTranslator::from(&symbols);
+        let mut output: Vec<u8> = Vec::new();
+        let mut writer = BitWriter::new(&mut output, false);
+        let mut writer = HuffmanWriter::new(&translator, &mut writer);
+        writer.write_all(&[7, 7, 7])?;
+        writer.flush()?;
+        // one zero bit for each symbol
+        assert_eq!(output, [0b0000_0000]);
+        Ok(())
+    }
+
     const TEST_SYMBOL_SEQUENCE: &[u8] = &[
         27, 17, 7, 31, 22, 12, 2, 29, 21, 19, 11, 9, 1, 30, 25, 15, 5, 24, 14, 4, 20, 10, 26, 23,
         16, 13, 6, 3, 32, 28, 18, 8,
@@ -268,6 +324,32 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_code_word_lengths() {
+        let code_lengths = [(0, 3), (1, 3), (2, 2), (3, 1)].map(SymbolCodeLength::from);
+        let translator = HuffmanTranslator::from(&code_lengths);
+        assert_eq!(translator.code_word_length(2), Some(2));
+        assert_eq!(translator.code_word_length(4), None);
+        let lengths: Vec<(u8, usize)> = translator
+            .code_word_lengths()
+            .iter()
+            .map(|code_length| (code_length.symbol, code_length.length))
+            .collect();
+        assert_eq!(lengths, [(0, 3), (1, 3), (2, 2), (3, 1)]);
+    }
+
+    #[test]
+    fn test_code_word_lengths_match_written_bits() {
+        let mut sorted_syms = SYMBOLS_AND_FREQUENCIES_ODD_LEN.map(SymbolFrequency::from);
+        sorted_syms.sort_by_key(|x| x.frequency);
+        let translator = create_test_translator(&sorted_syms, 6);
+        let bits: usize = TEST_SYMBOL_SEQUENCE
+            .iter()
+            .map(|&symbol| translator.code_word_length(symbol).unwrap())
+            .sum();
+        assert_eq!(bits.div_ceil(8), TEST_BYTE_SEQUENCE.len());
+    }
+
     #[test]
     fn test_calculate_bit_pattern_one() {
         let previous_code_word = CodeWord {
@@ -0,0 +1,288 @@
+use super::code::HuffmanCode;
+use super::code::HuffmanCodeGenerator;
+
+/// Node of a lookahead chain of the boundary package-merge algorithm. The chains of the lower
+/// lists, which the node refers to by its tail, are the packages it was merged from.
+#[derive(Clone, Copy, Debug)]
+struct ChainNode {
+    /// sum of the frequencies of the leaves and packages in the chain
+    weight: usize,
+    /// number of leaves in the list of the node, the least frequent leaves first
+    count: usize,
+    /// index of the node of the list below, which the chain continues with
+    tail: Option<usize>,
+}
+
+/// Generator of optimal codes, whose code words are at most `limit` bits long, with the
+/// boundary package-merge algorithm of Katajainen, Moffat and Turpin. Instead of merging whole
+/// lists of packages for each length, it keeps only the last two chains of each list and
+/// creates the chains of the lower lists lazily, when a package of them is needed. It takes
+/// O(n * limit) time and the buffers of the nodes and lists are reused between the codes.
+pub struct LengthLimitedHuffmanCodeGenerator {
+    limit: usize,
+    nodes: Vec<ChainNode>,
+    lists: Vec<[usize; 2]>,
+}
+
+/// A single symbol gets a code word of one bit, because a code word must not be empty, and no
+/// symbols give an empty code.
+impl HuffmanCodeGenerator for LengthLimitedHuffmanCodeGenerator {
+    fn generate(&mut self, sorted_frequencies: &[usize]) -> HuffmanCode {
+        assert!(
+            sorted_frequencies.is_sorted(),
+            "Frequencies must be sorted in ascending order"
+        );
+        let code_length = sorted_frequencies.len();
+        if code_length <= 2 {
+            return vec![1; code_length];
+        }
+        assert!(
+            code_length <= 2_usize.pow(self.limit as u32),
+            "Tree of depth limit {} can not hold {} code words",
+            self.limit,
+            code_length
+        );
+        // a code of n symbols has no code words longer than n - 1 bits
+        let limit = self.limit.min(code_length - 1);
+        self.initialize_lists(sorted_frequencies, limit);
+        // the last list needs 2n - 2 chains, two of them exist after the initialization
+        for _ in 0..2 * code_length - 5 {
+            self.boundary_package_merge(sorted_frequencies, limit - 1);
+        }
+        self.finish_last_list(sorted_frequencies, limit - 1);
+        self.code_word_lengths(code_length, limit - 1)
+    }
+}
+
+impl LengthLimitedHuffmanCodeGenerator {
+    pub fn new(limit: usize) -> LengthLimitedHuffmanCodeGenerator {
+        LengthLimitedHuffmanCodeGenerator {
+            limit,
+            nodes: Vec::new(),
+            lists: Vec::new(),
+        }
+    }
+
+    /// Starts each list with the chains of the two least frequent leaves.
+    fn initialize_lists(&mut self, sorted_frequencies: &[usize], limit: usize) {
+        self.nodes.clear();
+        self.nodes.reserve(2 * limit * sorted_frequencies.len());
+        self.lists.clear();
+        self.nodes.push(ChainNode {
+            weight: sorted_frequencies[0],
+            count: 1,
+            tail: None,
+        });
+        self.nodes.push(ChainNode {
+            weight: sorted_frequencies[1],
+            count: 2,
+            tail: None,
+        });
+        self.lists.resize(limit, [0, 1]);
+    }
+
+    fn push_node(&mut self, node: ChainNode) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    /// Appends the next chain to the list at the index, which either takes the next leaf or
+    /// the package of the last two chains of the list below, the leaf if both weigh the same.
+    /// A taken package is replaced by two new chains in the list below.
+    fn boundary_package_merge(&mut self, sorted_frequencies: &[usize], index: usize) {
+        let [_, last] = self.lists[index];
+        let last_count = self.nodes[last].count;
+        if index == 0 && last_count >= sorted_frequencies.len() {
+            return;
+        }
+        let new_node = if index == 0 {
+            ChainNode {
+                weight: sorted_frequencies[last_count],
+                count: last_count + 1,
+                tail: None,
+            }
+        } else {
+            let [lower_first, lower_last] = self.lists[index - 1];
+            let package_weight = self.nodes[lower_first].weight + self.nodes[lower_last].weight;
+            if last_count < sorted_frequencies.len()
+                && package_weight >= sorted_frequencies[last_count]
+            {
+                ChainNode {
+                    weight: sorted_frequencies[last_count],
+                    count: last_count + 1,
+                    tail: self.nodes[last].tail,
+                }
+            } else {
+                ChainNode {
+                    weight: package_weight,
+                    count: last_count,
+                    tail: Some(lower_last),
+                }
+            }
+        };
+        let takes_package = index > 0 && new_node.count == last_count;
+        let new_node = self.push_node(new_node);
+        self.lists[index] = [last, new_node];
+        if takes_package {
+            self.boundary_package_merge(sorted_frequencies, index - 1);
+            self.boundary_package_merge(sorted_frequencies, index - 1);
+        }
+    }
+
+    /// Creates the last chain of the last list, which is the only one of the solution, so the
+    /// lists below need no new chains.
+    fn finish_last_list(&mut self, sorted_frequencies: &[usize], index: usize) {
+        let [_, last] = self.lists[index];
+        let last_count = self.nodes[last].count;
+        let [lower_first, lower_last] = self.lists[index - 1];
+        let package_weight = self.nodes[lower_first].weight + self.nodes[lower_last].weight;
+        if last_count < sorted_frequencies.len() && package_weight >= sorted_frequencies[last_count]
+        {
+            let new_node = self.push_node(ChainNode {
+                weight: sorted_frequencies[last_count],
+                count: last_count + 1,
+                tail: self.nodes[last].tail,
+            });
+            self.lists[index][1] = new_node;
+        } else {
+            self.nodes[last].tail = Some(lower_last);
+        }
+    }
+
+    /// Each node of the solution chain holds the number of leaves of its list. A leaf is in as
+    /// many lists as its code word has bits.
+    fn code_word_lengths(&self, code_length: usize, index: usize) -> HuffmanCode {
+        let mut code = vec![0; code_length];
+        let mut node = Some(self.lists[index][1]);
+        while let Some(index) = node {
+            for length in &mut code[..self.nodes[index].count] {
+                *length += 1;
+            }
+            node = self.nodes[index].tail;
+        }
+        code
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cmp::Reverse, collections::BinaryHeap};
+
+    use super::HuffmanCodeGenerator;
+
+    use super::LengthLimitedHuffmanCodeGenerator;
+
+    /// Cost of the unlimited Huffman code of the frequencies.
+    fn huffman_cost(frequencies: &[usize]) -> usize {
+        let mut heap: BinaryHeap<Reverse<usize>> =
+            frequencies.iter().copied().map(Reverse).collect();
+        let mut cost = 0;
+        while heap.len() > 1 {
+            let Reverse(first) = heap.pop().unwrap();
+            let Reverse(second) = heap.pop().unwrap();
+            cost += first + second;
+            heap.push(Reverse(first + second));
+        }
+        cost
+    }
+
+    fn cost(frequencies: &[usize], code: &[usize]) -> usize {
+        frequencies
+            .iter()
+            .zip(code)
+            .map(|(frequency, length)| frequency * length)
+            .sum()
+    }
+
+    #[test]
+    fn test_generate_one() {
+        let limit = 4;
+        let sorted_frequencies: [usize; 11] = [1, 2, 5, 8, 10, 11, 14, 14, 15, 18, 20];
+        let mut generator = LengthLimitedHuffmanCodeGenerator::new(limit);
+        let expected_code = [4, 4, 4, 4, 4, 4, 3, 3, 3, 3, 3];
+        let code = generator.generate(&sorted_frequencies);
+        for (index, (actual_len, expected_len)) in code.into_iter().zip(expected_code).enumerate() {
+            assert_eq!(
+                actual_len, expected_len,
+                "Codeword lengths do not equal at index {}",
+                index
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_two() {
+        let limit = 5;
+        let sorted_frequencies: [usize; 10] = [1, 1, 1, 2, 2, 2, 3, 6, 17, 20];
+        let mut generator = LengthLimitedHuffmanCodeGenerator::new(limit);
+        let expected_code = [5, 5, 4, 4, 4, 4, 4, 3, 2, 2];
+        let code = generator.generate(&sorted_frequencies);
+        for (index, (acutal_len, expected_len)) in code.into_iter().zip(expected_code).enumerate() {
+            assert_eq!(
+                acutal_len, expected_len,
+                "Codeword lengths do not equal at index {}",
+                index
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_three() {
+        let limit = 4;
+        let sorted_frequencies: [usize; 10] = [1, 1, 1, 2, 2, 2, 3, 6, 17, 20];
+        let mut generator = LengthLimitedHuffmanCodeGenerator::new(limit);
+        let expected_code = [4, 4, 4, 4, 4, 4, 4, 4, 2, 2];
+        let code = generator.generate(&sorted_frequencies);
+        for (index, (acutal_len, expected_len)) in code.into_iter().zip(expected_code).enumerate() {
+            assert_eq!(
+                acutal_len, expected_len,
+                "Codeword lengths do not equal at index {}",
+                index
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_degenerate() {
+        let mut generator = LengthLimitedHuffmanCodeGenerator::new(4);
+        assert_eq!(generator.generate(&[]), Vec::<usize>::new());
+        assert_eq!(generator.generate(&[42]), [1]);
+        assert_eq!(generator.generate(&[0, 42]), [1, 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_generate_too_long_input_array() {
+        let limit = 3;
+        let sorted_frequencies: [usize; 10] = [1, 1, 1, 2, 2, 2, 3, 6, 17, 20];
+        let mut generator = LengthLimitedHuffmanCodeGenerator::new(limit);
+        let _ = generator.generate(&sorted_frequencies);
+    }
+
+    #[test]
+    fn test_generate_optimal_code_without_binding_limit() {
+        for size in 3..=40 {
+            let mut frequencies: Vec<usize> = (0..size)
+                .map(|index: usize| (index * index * 31 + index * 7) % 97 + 1)
+                .collect();
+            frequencies.sort();
+            let mut generator = LengthLimitedHuffmanCodeGenerator::new(16);
+            let code = generator.generate(&frequencies);
+            assert_eq!(cost(&frequencies, &code), huffman_cost(&frequencies));
+        }
+    }
+
+    #[test]
+    fn test_generate_complete_code_within_limit() {
+        // exponential frequencies make the unlimited code as deep as possible
+        let frequencies: Vec<usize> = (0..30).map(|index| 1 << (index / 2)).collect();
+        let mut generator = LengthLimitedHuffmanCodeGenerator::new(8);
+        for _ in 0..2 {
+            let code = generator.generate(&frequencies);
+            assert!(code.iter().all(|&length| (1..=8).contains(&length)));
+            assert!(code.is_sorted_by(|shorter, longer| shorter >= longer));
+            let kraft_sum: usize = code.iter().map(|length| 1 << (8 - length)).sum();
+            assert_eq!(kraft_sum, 1 << 8, "Code of {:?} is not complete", code);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Default Huffman tables of the JPEG specification (Annex K.3) as code lengths. They allow to
+//! encode without counting the symbols first and serve as reference for the size of optimized
+//! tables. They cover the symbols of 8 bit samples only.
+
+use super::{code_lengths_of_counts, SymbolCodeLength};
+use crate::image::writer::jpeg::consts::{
+    HuffmanTableSpecification, ANNEX_K_CHROMINANCE_AC_HUFFMAN_TABLE,
+    ANNEX_K_CHROMINANCE_DC_HUFFMAN_TABLE, ANNEX_K_LUMINANCE_AC_HUFFMAN_TABLE,
+    ANNEX_K_LUMINANCE_DC_HUFFMAN_TABLE,
+};
+
+/// Code lengths of the luminance DC table (Table K.3).
+pub fn luminance_dc() -> Vec<SymbolCodeLength> {
+    code_lengths(&ANNEX_K_LUMINANCE_DC_HUFFMAN_TABLE)
+}
+
+/// Code lengths of the luminance AC table (Table K.5).
+pub fn luminance_ac() -> Vec<SymbolCodeLength> {
+    code_lengths(&ANNEX_K_LUMINANCE_AC_HUFFMAN_TABLE)
+}
+
+/// Code lengths of the chrominance DC table (Table K.4).
+pub fn chrominance_dc() -> Vec<SymbolCodeLength> {
+    code_lengths(&ANNEX_K_CHROMINANCE_DC_HUFFMAN_TABLE)
+}
+
+/// Code lengths of the chrominance AC table (Table K.6).
+pub fn chrominance_ac() -> Vec<SymbolCodeLength> {
+    code_lengths(&ANNEX_K_CHROMINANCE_AC_HUFFMAN_TABLE)
+}
+
+/// Converts the table into code lengths sorted by descending length, as the encoder expects
+/// them. The assigned code words match those of the specification.
+pub fn code_lengths(table: &HuffmanTableSpecification) -> Vec<SymbolCodeLength> {
+    code_lengths_of_counts(&table.code_length_counts, table.symbols)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{chrominance_ac, chrominance_dc, code_lengths, luminance_ac, luminance_dc};
+    use crate::{
+        huffman::{encoder::HuffmanTranslator, SymbolCodeLength},
+        image::writer::jpeg::consts::{
+            HuffmanTableSpecification, ANNEX_K_CHROMINANCE_AC_HUFFMAN_TABLE,
+            ANNEX_K_CHROMINANCE_DC_HUFFMAN_TABLE, ANNEX_K_LUMINANCE_AC_HUFFMAN_TABLE,
+            ANNEX_K_LUMINANCE_DC_HUFFMAN_TABLE,
+        },
+        BitPattern,
+    };
+
+    /// Code words of the table in the order of its symbols, generated like in Annex C.2.
+    fn specification_code_words(table: &HuffmanTableSpecification) -> Vec<(u16, usize)> {
+        let mut code_words = Vec::new();
+        let mut code = 0u16;
+        for (index, &count) in table.code_length_counts.iter().enumerate() {
+            for _ in 0..count {
+                code_words.push((code, index + 1));
+                code += 1;
+            }
+            code = code.wrapping_shl(1);
+        }
+        code_words
+    }
+
+    fn assert_round_trip(code_lengths: Vec<SymbolCodeLength>, table: &HuffmanTableSpecification) {
+        assert_eq!(code_lengths.len(), table.symbols.len());
+        assert!(code_lengths.is_sorted_by(|a, b| a.length >= b.length));
+        let translator = HuffmanTranslator::from(&code_lengths);
+        for (&symbol, (code, length)) in table.symbols.iter().zip(specification_code_words(table)) {
+            let code_word = translator
+                .get_code_word_for_symbol(symbol)
+                .as_ref()
+                .unwrap_or_else(|| panic!("Symbol {:#04x} has no code word", symbol));
+            assert_eq!(
+                code_word.bit_len(),
+                length,
+                "Length of symbol {:#04x}",
+                symbol
+            );
+            let bytes = code_word.to_bytes();
+            let bit_pattern = u16::from_be_bytes([bytes[0], bytes[1]]) >> (16 - length);
+            assert_eq!(bit_pattern, code, "Code word of symbol {:#04x}", symbol);
+        }
+        let mut expected_lengths: Vec<(u8, usize)> = code_lengths
+            .iter()
+            .map(|code_length| (code_length.symbol, code_length.length))
+            .collect();
+        expected_lengths.sort();
+        let translated_lengths: Vec<(u8, usize)> = translator
+            .code_word_lengths()
+            .iter()
+            .map(|code_length| (code_length.symbol, code_length.length))
+            .collect();
+        assert_eq!(translated_lengths, expected_lengths);
+    }
+
+    #[test]
+    fn luminance_dc_round_trips() {
+        assert_round_trip(luminance_dc(), &ANNEX_K_LUMINANCE_DC_HUFFMAN_TABLE);
+    }
+
+    #[test]
+    fn luminance_ac_round_trips() {
+        assert_round_trip(luminance_ac(), &ANNEX_K_LUMINANCE_AC_HUFFMAN_TABLE);
+    }
+
+    #[test]
+    fn chrominance_dc_round_trips() {
+        assert_round_trip(chrominance_dc(), &ANNEX_K_CHROMINANCE_DC_HUFFMAN_TABLE);
+    }
+
+    #[test]
+    fn chrominance_ac_round_trips() {
+        assert_round_trip(chrominance_ac(), &ANNEX_K_CHROMINANCE_AC_HUFFMAN_TABLE);
+    }
+
+    #[test]
+    fn code_lengths_reproduce_specification_code_words() {
+        let code_lengths = code_lengths(&ANNEX_K_LUMINANCE_DC_HUFFMAN_TABLE);
+        assert_eq!(code_lengths.len(), 12);
+        let translator = HuffmanTranslator::from(&code_lengths);
+        // Table K.3: category 0 is coded as 00, category 5 as 110, category 11 as 111111110
+        let code_word = |symbol| {
+            translator
+                .get_code_word_for_symbol(symbol)
+                .as_ref()
+                .unwrap()
+        };
+        assert_eq!(code_word(0).bit_len(), 2);
+        assert_eq!(*code_word(0).to_bytes(), [0b0000_0000, 0]);
+        assert_eq!(code_word(5).bit_len(), 3);
+        assert_eq!(*code_word(5).to_bytes(), [0b1100_0000, 0]);
+        assert_eq!(code_word(11).bit_len(), 9);
+        assert_eq!(*code_word(11).to_bytes(), [0b1111_1111, 0b0000_0000]);
+    }
+}
@@ -1,5 +1,9 @@
 use super::code::HuffmanCodeGenerator;
 use super::coding_error::CodingError;
+use super::encoder::HuffmanTranslator;
+use super::{Symbol, SymbolCodeLength};
+use crate::binary_stream::BitReader;
+use crate::BitPattern;
 use std::cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd};
 use std::collections::VecDeque;
 use std::fmt;
@@ -83,7 +87,7 @@ impl HuffmanTree {
         generator: &mut impl HuffmanCodeGenerator,
     ) -> HuffmanTree {
         let mut symbols_and_frequencies: Vec<(u8, usize)> = symbols_and_frequencies.to_vec();
-        symbols_and_frequencies.sort_by(|a, b| a.1.cmp(&b.1));
+        symbols_and_frequencies.sort_by_key(|a| a.1);
         let frequencies: Vec<usize> = symbols_and_frequencies.iter().map(|a| a.1).collect();
         let code = generator.generate(&frequencies);
 
@@ -119,6 +123,82 @@ impl HuffmanTree {
         tree
     }
 
+    /// Builds the tree of the code words, which [`HuffmanTranslator`] assigns to the code
+    /// lengths, so the tree decodes exactly what the encoder writes. The frequencies of the
+    /// nodes are unknown and 0. Only the code word of only ones may be left unused, which
+    /// becomes a [`NodeKind::OneStar`] node.
+    pub fn from_code_lengths(code_lengths: &[SymbolCodeLength]) -> HuffmanTree {
+        let translator = HuffmanTranslator::from(code_lengths);
+        let mut code_words: Vec<(u16, usize, Symbol)> = code_lengths
+            .iter()
+            .map(|code_length| {
+                let code_word = translator
+                    .get_code_word_for_symbol(code_length.symbol)
+                    .as_ref()
+                    .expect("Translator has a code word for each symbol");
+                let bytes = code_word.to_bytes();
+                let bit_pattern = u16::from_be_bytes([bytes[0], bytes[1]]);
+                (bit_pattern, code_word.bit_len(), code_length.symbol)
+            })
+            .collect();
+        code_words.sort_unstable_by_key(|&(bit_pattern, _, _)| bit_pattern);
+        let mut tree = HuffmanTree {
+            nodes: Vec::new(),
+            root_index: 0,
+            least_frequent_symbol_node_index: 0,
+            leaf_count: code_words.len(),
+        };
+        tree.root_index = tree
+            .build_subtree(&code_words, 0)
+            .expect("code_lengths must not be empty");
+        tree
+    }
+
+    /// Builds the subtree of the code words sorted by their bit patterns, which share the
+    /// first `depth` bits, and returns the index of its root.
+    fn build_subtree(
+        &mut self,
+        code_words: &[(u16, usize, Symbol)],
+        depth: usize,
+    ) -> Option<usize> {
+        let &(_, length, symbol) = code_words.first()?;
+        if length == depth {
+            assert_eq!(
+                code_words.len(),
+                1,
+                "code word is the prefix of another one"
+            );
+            return Some(self.push_node(NodeKind::Leaf { symbol }));
+        }
+        let split = code_words.partition_point(|&(bit_pattern, _, _)| {
+            bit_pattern & (1 << (u16::BITS as usize - 1 - depth)) == 0
+        });
+        let left = self.build_subtree(&code_words[..split], depth + 1);
+        let right = self.build_subtree(&code_words[split..], depth + 1);
+        match (left, right) {
+            (Some(left), Some(right)) => Some(self.push_node(NodeKind::Inner { left, right })),
+            (Some(left), None) => match self.nodes[left].kind {
+                // the unused code word of only ones
+                NodeKind::Leaf { symbol } => {
+                    self.nodes[left].kind = NodeKind::OneStar { symbol };
+                    Some(left)
+                }
+                _ => panic!("code words leave more than the code word of only ones unused"),
+            },
+            _ => panic!("code words leave more than the code word of only ones unused"),
+        }
+    }
+
+    fn push_node(&mut self, kind: NodeKind) -> usize {
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            frequency: 0,
+            index,
+            kind,
+        });
+        index
+    }
+
     fn build_structure(&mut self, layers: Vec<Vec<usize>>) {
         // list of leafs with depths
         self.nodes.truncate(self.leaf_count);
@@ -152,55 +232,68 @@ impl HuffmanTree {
         replace_one_star_pattern(self, self.root_index, true);
     }
 
-    pub fn decode_sequence<I: Read>(
+    /// Decodes the symbols up to the end of the data. The last byte may be padded with ones.
+    pub fn decode_sequence<R: Read>(
         &self,
-        seq: &mut I,
+        reader: &mut BitReader<R>,
         out: &mut Vec<u8>,
     ) -> Result<(), CodingError> {
-        // tree traversal decode -> this is here for debugging not for speed
-        let mut current_index = self.root_index;
-        let mut buffer = [0; 1];
-        let mut atbit = 0;
-        let mut s = seq
-            .read(&mut buffer)
-            .map_err(|_| CodingError::DecoderError)?;
-        while s == 1 && atbit < 8 {
-            let take_right = buffer[0] & ((1 << 7) >> atbit) > 0;
-            let mut node = self.nodes[current_index];
-            match node.kind {
+        while let Some(symbol) = self.decode_symbol(reader)? {
+            out.push(symbol);
+        }
+        Ok(())
+    }
+
+    /// Decodes the next symbol by walking the tree along the bits. Returns `None` at the end of
+    /// the data, if the bits after the last symbol are less than 8 ones, which pad the last byte.
+    pub fn decode_symbol<R: Read>(
+        &self,
+        reader: &mut BitReader<R>,
+    ) -> Result<Option<Symbol>, CodingError> {
+        if let NodeKind::Leaf { .. } = self.nodes[self.root_index].kind {
+            return Err(CodingError::DegenerateTree);
+        }
+        let mut index = self.root_index;
+        let mut bits_read = 0;
+        let mut only_ones = true;
+        loop {
+            let bit = match reader.read_bit()? {
+                Some(bit) => bit,
+                None if only_ones && bits_read < 8 => return Ok(None),
+                None => return Err(CodingError::UnexpectedEndOfData),
+            };
+            bits_read += 1;
+            only_ones &= bit;
+            match self.nodes[index].kind {
                 NodeKind::Inner { left, right } => {
-                    if take_right {
-                        current_index = right;
-                    } else {
-                        current_index = left;
+                    index = if bit { right } else { left };
+                    if let NodeKind::Leaf { symbol } = self.nodes[index].kind {
+                        return Ok(Some(symbol));
                     }
                 }
-                _ => {
-                    panic!("unreachable, only one symbol in tree")
-                }
-            };
-            node = self.nodes[current_index];
-            match node.kind {
-                NodeKind::Leaf { symbol } => {
-                    out.push(symbol);
-                    current_index = self.root_index;
-                }
-                NodeKind::OneStar { symbol } => {
-                    out.push(symbol);
-                    atbit += 1;
-                    current_index = self.root_index;
+                NodeKind::OneStar { symbol } if !bit => return Ok(Some(symbol)),
+                NodeKind::OneStar { .. } if only_ones => {
+                    return Self::skip_padding(reader, bits_read);
                 }
-                _ => {}
-            };
-            atbit += 1;
-            if atbit >= 8 {
-                s = seq
-                    .read(&mut buffer)
-                    .map_err(|_| CodingError::DecoderError)?;
-                atbit %= 8;
+                NodeKind::OneStar { .. } => return Err(CodingError::InvalidCodeWord),
+                NodeKind::Leaf { .. } => unreachable!("a leaf ends the decoding of a symbol"),
+            }
+        }
+    }
+
+    /// Reads the rest of the bits after the unused code word of only ones, which is valid as
+    /// padding of less than 8 ones at the end of the data only.
+    fn skip_padding<R: Read>(
+        reader: &mut BitReader<R>,
+        mut bits_read: usize,
+    ) -> Result<Option<Symbol>, CodingError> {
+        loop {
+            match reader.read_bit()? {
+                None if bits_read < 8 => return Ok(None),
+                Some(true) if bits_read < 7 => bits_read += 1,
+                _ => return Err(CodingError::InvalidCodeWord),
             }
         }
-        Ok(())
     }
 }
 
@@ -285,7 +378,14 @@ impl fmt::Display for HuffmanTree {
 
 #[cfg(test)]
 mod test {
+    use std::io::Write;
+
+    use crate::binary_stream::{BitReader, BitWriter};
+    use crate::huffman::code::HuffmanCodeGenerator;
+    use crate::huffman::coding_error::CodingError;
+    use crate::huffman::encoder::{HuffmanTranslator, HuffmanWriter};
     use crate::huffman::length_limited::LengthLimitedHuffmanCodeGenerator;
+    use crate::huffman::{standard_tables, SymbolFrequency};
 
     use super::{HuffmanTree, NodeKind};
 
@@ -524,10 +624,11 @@ mod test {
         let mut code_generator = LengthLimitedHuffmanCodeGenerator::new(10);
         let mut tree = HuffmanTree::new(SYMBOLS_AND_FREQUENCIES_ODD_LEN, &mut code_generator);
         tree.replace_onestar();
-        let sequence = Vec::from(TEST_BYTE_SEQUENCE);
+        let mut reader = BitReader::new(TEST_BYTE_SEQUENCE, false);
         let mut symbol_sequence = Vec::new();
-        tree.decode_sequence(&mut sequence.as_slice(), &mut symbol_sequence)
-            .unwrap();
+        for _ in TEST_SYMBOL_SEQUENCE {
+            symbol_sequence.push(tree.decode_symbol(&mut reader).unwrap().unwrap());
+        }
         for (index, &symbol) in TEST_SYMBOL_SEQUENCE.iter().enumerate() {
             assert_eq!(
                 symbol_sequence[index], symbol,
@@ -537,6 +638,119 @@ mod test {
         }
     }
 
+    #[test]
+    fn decode_code_words_of_the_encoder() {
+        let code_lengths = standard_tables::luminance_dc();
+        let tree = HuffmanTree::from_code_lengths(&code_lengths);
+        assert_eq!(tree.leaf_count, 12);
+        // Table K.3: 0 is coded as 00, 5 as 110, 11 as 111111110 and 3 as 100
+        let symbols = [0, 5, 11, 3, 11, 0];
+        let translator = HuffmanTranslator::from(&code_lengths);
+        let mut output = Vec::new();
+        let mut bit_writer = BitWriter::new(&mut output, false);
+        let mut writer = HuffmanWriter::new(&translator, &mut bit_writer);
+        writer.write_all(&symbols).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(output, [0b00110111, 0b11111010, 0b01111111, 0b10000000]);
+        let mut symbol_sequence = Vec::new();
+        let mut reader = BitReader::new(output.as_slice(), false);
+        for _ in symbols {
+            symbol_sequence.push(tree.decode_symbol(&mut reader).unwrap().unwrap());
+        }
+        assert_eq!(symbol_sequence, symbols);
+    }
+
+    fn decode(tree: &HuffmanTree, data: &[u8]) -> Result<Vec<u8>, CodingError> {
+        let mut symbols = Vec::new();
+        tree.decode_sequence(&mut BitReader::new(data, true), &mut symbols)?;
+        Ok(symbols)
+    }
+
+    #[test]
+    fn decode_sequence_with_padding_and_byte_stuffing() {
+        let code_lengths = standard_tables::luminance_ac();
+        let tree = HuffmanTree::from_code_lengths(&code_lengths);
+        let symbols: Vec<u8> = code_lengths
+            .iter()
+            .map(|code_length| code_length.symbol)
+            .collect();
+        let translator = HuffmanTranslator::from(&code_lengths);
+        let mut output = Vec::new();
+        let mut bit_writer = BitWriter::new(&mut output, true);
+        let mut writer = HuffmanWriter::new(&translator, &mut bit_writer);
+        writer.write_all(&symbols).unwrap();
+        writer.flush().unwrap();
+        let stuffed: Vec<u8> = output
+            .iter()
+            .flat_map(|&byte| {
+                if byte == 0xFF {
+                    vec![0xFF, 0x00]
+                } else {
+                    vec![byte]
+                }
+            })
+            .chain([0xFF, 0xD9])
+            .collect();
+        assert!(stuffed.len() > output.len() + 2);
+        assert_eq!(decode(&tree, &stuffed).unwrap(), symbols);
+    }
+
+    #[test]
+    fn reject_invalid_data() {
+        let tree = HuffmanTree::from_code_lengths(&standard_tables::luminance_dc());
+        // 00 codes 0, 011 codes 2 and 111 pads the byte
+        assert_eq!(decode(&tree, &[0b0001_1111]).unwrap(), [0, 2]);
+        // 010 codes 1 and 00 codes 0 twice, but the data ends within the next code word
+        assert!(matches!(
+            decode(&tree, &[0b0100_0000]),
+            Err(CodingError::UnexpectedEndOfData)
+        ));
+        assert!(matches!(
+            decode(&tree, &[0xFF, 0x00]),
+            Err(CodingError::UnexpectedEndOfData)
+        ));
+        // 111111111 is the unused code word of only ones, which is followed by further data
+        assert!(matches!(
+            decode(&tree, &[0xFF, 0x00, 0b1000_0000]),
+            Err(CodingError::InvalidCodeWord)
+        ));
+        // a whole byte of ones is no padding
+        assert!(matches!(
+            decode(&tree, &[0b0011_1111, 0xFF, 0x00]),
+            Err(CodingError::InvalidCodeWord)
+        ));
+    }
+
+    #[test]
+    fn tree_of_code_lengths_has_their_depths() {
+        let mut code_generator = LengthLimitedHuffmanCodeGenerator::new(10);
+        let mut symbols_and_frequencies =
+            SYMBOLS_AND_FREQUENCIES_ODD_LEN.map(SymbolFrequency::from);
+        symbols_and_frequencies.sort_by_key(|symbol| symbol.frequency);
+        let mut code_lengths = code_generator.generate_with_symbols(&symbols_and_frequencies);
+        code_lengths[0].length += 1;
+        let tree = HuffmanTree::from_code_lengths(&code_lengths);
+        let depths = calculate_depth_for_each_node(&tree);
+        for code_length in &code_lengths {
+            let node = tree
+                .nodes
+                .iter()
+                .find(|node| match node.kind {
+                    NodeKind::Leaf { symbol } | NodeKind::OneStar { symbol } => {
+                        symbol == code_length.symbol
+                    }
+                    NodeKind::Inner { .. } => false,
+                })
+                .unwrap();
+            assert_eq!(
+                depths[node.index] - 1,
+                code_length.length,
+                "Depth of symbol {} does not match",
+                code_length.symbol
+            );
+        }
+    }
+
     #[test]
     fn test_shortest_right_subtree_is_longer_eq_the_longest_left_subtree() {
         let symbols_and_frequencies = &[(1, 4), (2, 4), (3, 6), (4, 6), (5, 7), (6, 9)];
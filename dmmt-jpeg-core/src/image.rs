@@ -0,0 +1,285 @@
+use crate::color::{CMYKColorFormat, RGBColorFormat, RangeColorFormat, YCbCrColorFormat};
+use crate::error::Error;
+use crate::warning::{Warning, Warnings};
+
+pub mod metrics;
+pub mod preprocess;
+pub mod reader;
+pub mod subsampling;
+pub mod writer;
+
+#[derive(Debug, PartialEq)]
+pub struct Image<T> {
+    width: u16,
+    height: u16,
+    dots: Vec<RGBColorFormat<T>>,
+}
+
+impl Image<f32> {
+    /// Creates an image from interleaved 8 bit RGB samples in row major order.
+    pub fn from_rgb8(width: u16, height: u16, samples: &[u8]) -> crate::Result<Self> {
+        let expected_length = width as usize * height as usize * 3;
+        if samples.len() != expected_length {
+            return Err(Error::MismatchOfSizeBetweenDimensionsAndBuffer(
+                expected_length,
+                samples.len(),
+            ));
+        }
+        let dots = samples
+            .chunks_exact(3)
+            .map(|dot| {
+                RangeColorFormat::new(u8::MAX as u16, dot[0] as u16, dot[1] as u16, dot[2] as u16)
+            })
+            .map(RGBColorFormat::from)
+            .collect();
+        Ok(Self {
+            width,
+            height,
+            dots,
+        })
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Returns a copy with NaN samples replaced by 0.0 and the other samples clamped to the
+    /// range of 0.0 to 1.0, with a warning for each kind of replaced sample. Returns `None`, if
+    /// all samples are valid, so the image is encoded without a copy.
+    pub(crate) fn sanitized(&self, warnings: &mut Warnings) -> Option<Self> {
+        let samples = self.dots.iter().flat_map(|dot| dot.samples());
+        let nan_samples = samples.clone().filter(|sample| sample.is_nan()).count();
+        let clamped_samples = samples
+            .filter(|sample| !(0.0..=1.0).contains(sample) && !sample.is_nan())
+            .count();
+        if nan_samples == 0 && clamped_samples == 0 {
+            return None;
+        }
+        if nan_samples > 0 {
+            warnings.push(Warning::ReplacedNaNSamples(nan_samples));
+        }
+        if clamped_samples > 0 {
+            warnings.push(Warning::ClampedSamples(clamped_samples));
+        }
+        let dots = self
+            .dots
+            .iter()
+            .map(|dot| {
+                let [red, green, blue] = dot.samples();
+                RGBColorFormat::clamped(red, green, blue)
+            })
+            .collect();
+        Some(Self {
+            width: self.width,
+            height: self.height,
+            dots,
+        })
+    }
+
+    /// Returns interleaved 8 bit RGB samples in row major order, the inverse of `from_rgb8`.
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        self.dots.iter().flat_map(|dot| dot.to_rgb8()).collect()
+    }
+}
+
+/// Image with straight alpha, as read from PNG and PAM sources with an alpha channel. JPEG
+/// cannot carry alpha, so the image is composited over a background before it is encoded.
+#[derive(Debug, PartialEq)]
+pub struct RGBAImage {
+    width: u16,
+    height: u16,
+    dots: Vec<RGBColorFormat<f32>>,
+    /// Normalized alpha of each dot or `None`, if the source has no alpha channel.
+    alpha: Option<Vec<f32>>,
+}
+
+impl RGBAImage {
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.alpha.is_some()
+    }
+
+    /// Composites the dots over the opaque background. An image without alpha is returned as is.
+    pub fn composite(self, background: &RGBColorFormat<f32>) -> Image<f32> {
+        let dots = match self.alpha {
+            Some(alpha) => self
+                .dots
+                .iter()
+                .zip(alpha)
+                .map(|(dot, alpha)| dot.composite(alpha, background))
+                .collect(),
+            None => self.dots,
+        };
+        Image {
+            width: self.width,
+            height: self.height,
+            dots,
+        }
+    }
+}
+
+impl From<Image<f32>> for RGBAImage {
+    fn from(value: Image<f32>) -> Self {
+        Self {
+            width: value.width,
+            height: value.height,
+            dots: value.dots,
+            alpha: None,
+        }
+    }
+}
+
+/// Image, which is already in YCbCr with level shifted samples of baseline precision, as
+/// produced by video sources. Such images skip the color conversion when encoded.
+#[derive(Debug, PartialEq)]
+pub struct YCbCrImage {
+    width: u16,
+    height: u16,
+    dots: Vec<YCbCrColorFormat<f32>>,
+}
+
+/// Image of the inks of a print, which is encoded as YCCK with four components.
+#[derive(Debug, PartialEq)]
+pub struct CMYKImage {
+    width: u16,
+    height: u16,
+    dots: Vec<CMYKColorFormat<f32>>,
+}
+
+impl CMYKImage {
+    /// Creates an image from interleaved 8 bit CMYK samples in row major order, where 255 is
+    /// full ink.
+    pub fn from_cmyk8(width: u16, height: u16, samples: &[u8]) -> crate::Result<Self> {
+        let expected_length = width as usize * height as usize * 4;
+        if samples.len() != expected_length {
+            return Err(Error::MismatchOfSizeBetweenDimensionsAndBuffer(
+                expected_length,
+                samples.len(),
+            ));
+        }
+        let dots = samples
+            .chunks_exact(4)
+            .map(|dot| {
+                CMYKColorFormat::from_samples(
+                    u8::MAX as u16,
+                    [dot[0], dot[1], dot[2], dot[3]].map(u16::from),
+                )
+            })
+            .collect();
+        Ok(Self {
+            width,
+            height,
+            dots,
+        })
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+}
+
+/// Images with 16 bit samples use the full range from 0 to 65535, independent of the max value of
+/// the source file.
+impl From<&Image<u16>> for Image<f32> {
+    fn from(value: &Image<u16>) -> Self {
+        Self {
+            width: value.width,
+            height: value.height,
+            dots: value.dots.iter().map(RGBColorFormat::from).collect(),
+        }
+    }
+}
+
+pub trait ImageReader<T> {
+    fn read_image(&mut self) -> crate::Result<Image<T>>;
+}
+
+pub trait ImageWriter {
+    fn write_image(&mut self) -> crate::Result<()>;
+}
+
+pub struct ColorChannel<T> {
+    width: u16,
+    height: u16,
+    dots: Vec<T>,
+}
+
+impl<T> ColorChannel<T> {
+    pub fn new(width: u16, height: u16, dots: Vec<T>) -> Self {
+        Self {
+            width,
+            height,
+            dots,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Image, RGBAImage};
+    use crate::color::RGBColorFormat;
+    use crate::warning::{Warning, Warnings};
+
+    #[test]
+    fn sanitize_valid_image() {
+        let image = Image::from_rgb8(1, 2, &[0, 128, 255, 1, 2, 3]).unwrap();
+        let mut warnings = Warnings::new();
+        assert_eq!(image.sanitized(&mut warnings), None);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn sanitize_nan_and_out_of_range_samples() {
+        let image = Image {
+            width: 2,
+            height: 1,
+            dots: vec![
+                RGBColorFormat::from_samples([f32::NAN, 0.5, 1.5]),
+                RGBColorFormat::from_samples([-0.5, f32::NAN, 1.0]),
+            ],
+        };
+        let mut warnings = Warnings::new();
+        let sanitized = image.sanitized(&mut warnings).unwrap();
+        assert_eq!(sanitized.dots[0].samples(), [0.0, 0.5, 1.0]);
+        assert_eq!(sanitized.dots[1].samples(), [0.0, 0.0, 1.0]);
+        assert_eq!(
+            warnings.into_iter().collect::<Vec<_>>(),
+            [Warning::ReplacedNaNSamples(2), Warning::ClampedSamples(2)]
+        );
+    }
+
+    #[test]
+    fn composite_rgba_image() {
+        let red = RGBColorFormat::red();
+        let background = RGBColorFormat::white();
+        let image = RGBAImage {
+            width: 3,
+            height: 1,
+            dots: vec![red; 3],
+            alpha: Some(vec![1.0, 0.5, 0.0]),
+        };
+        let composited = image.composite(&background);
+        assert_eq!(composited.dots[0], red);
+        assert_eq!(composited.dots[1].samples(), [1.0, 0.5, 0.5]);
+        assert_eq!(composited.dots[2], background);
+        let opaque = Image::from_rgb8(1, 1, &[255, 0, 0]).unwrap();
+        let image = RGBAImage::from(opaque);
+        assert!(!image.has_alpha());
+        assert_eq!(image.composite(&background).dots[0], red);
+    }
+}
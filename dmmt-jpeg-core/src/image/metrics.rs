@@ -0,0 +1,196 @@
+use super::Image;
+use crate::{color::ColorMatrix, Error};
+
+/// Largest value of a luma sample, which the metrics are computed on.
+const PEAK_VALUE: f64 = 255.0;
+/// Side length of the square windows of the SSIM, which overlap by half of their size.
+const SSIM_WINDOW_SIZE: usize = 8;
+const SSIM_WINDOW_STEP: usize = SSIM_WINDOW_SIZE / 2;
+const SSIM_C1: f64 = (0.01 * PEAK_VALUE) * (0.01 * PEAK_VALUE);
+const SSIM_C2: f64 = (0.03 * PEAK_VALUE) * (0.03 * PEAK_VALUE);
+
+/// Quality of an encoded image compared with its source. Both metrics are computed on the luma,
+/// so they also apply to grayscale output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImageMetrics {
+    /// Peak signal to noise ratio in dB, infinite for identical images.
+    pub psnr: f64,
+    /// Mean structural similarity from -1 to 1, 1 for identical images.
+    pub ssim: f64,
+}
+
+/// Luma samples from 0 to 255 in row major order with the weights of the color matrix.
+fn luma_samples(image: &Image<f32>, color_matrix: ColorMatrix) -> Vec<f64> {
+    let [red_weight, green_weight, blue_weight] = color_matrix.coefficients()[0];
+    image
+        .dots
+        .iter()
+        .map(|dot| {
+            let [red, green, blue] = dot.samples().map(|sample| sample.clamp(0.0, 1.0) as f64);
+            (red_weight * red + green_weight * green + blue_weight * blue) * PEAK_VALUE
+        })
+        .collect()
+}
+
+fn psnr(reference: &[f64], distorted: &[f64]) -> f64 {
+    let squared_error: f64 = reference
+        .iter()
+        .zip(distorted)
+        .map(|(reference, distorted)| (reference - distorted).powi(2))
+        .sum();
+    let mean_squared_error = squared_error / reference.len() as f64;
+    10.0 * (PEAK_VALUE * PEAK_VALUE / mean_squared_error).log10()
+}
+
+/// Structural similarity of the window at the position.
+fn window_ssim(
+    reference: &[f64],
+    distorted: &[f64],
+    width: usize,
+    (x, y): (usize, usize),
+    (window_width, window_height): (usize, usize),
+) -> f64 {
+    let window = |samples: &'_ [f64]| -> Vec<f64> {
+        (y..y + window_height)
+            .flat_map(|row| samples[row * width + x..row * width + x + window_width].to_vec())
+            .collect()
+    };
+    let (reference, distorted) = (window(reference), window(distorted));
+    let count = reference.len() as f64;
+    let mean = |samples: &[f64]| samples.iter().sum::<f64>() / count;
+    let (reference_mean, distorted_mean) = (mean(&reference), mean(&distorted));
+    let mut reference_variance = 0.0;
+    let mut distorted_variance = 0.0;
+    let mut covariance = 0.0;
+    for (reference, distorted) in reference.iter().zip(&distorted) {
+        let reference = reference - reference_mean;
+        let distorted = distorted - distorted_mean;
+        reference_variance += reference * reference;
+        distorted_variance += distorted * distorted;
+        covariance += reference * distorted;
+    }
+    let (reference_variance, distorted_variance, covariance) = (
+        reference_variance / count,
+        distorted_variance / count,
+        covariance / count,
+    );
+    ((2.0 * reference_mean * distorted_mean + SSIM_C1) * (2.0 * covariance + SSIM_C2))
+        / ((reference_mean.powi(2) + distorted_mean.powi(2) + SSIM_C1)
+            * (reference_variance + distorted_variance + SSIM_C2))
+}
+
+/// Mean SSIM over windows of 8x8 samples. Images smaller than a window are compared as a whole.
+fn ssim(reference: &[f64], distorted: &[f64], width: usize, height: usize) -> f64 {
+    let window_size = (width.min(SSIM_WINDOW_SIZE), height.min(SSIM_WINDOW_SIZE));
+    let positions = |length: usize, window_length: usize| {
+        (0..=length - window_length).step_by(SSIM_WINDOW_STEP)
+    };
+    let mut sum = 0.0;
+    let mut count = 0;
+    for y in positions(height, window_size.1) {
+        for x in positions(width, window_size.0) {
+            sum += window_ssim(reference, distorted, width, (x, y), window_size);
+            count += 1;
+        }
+    }
+    sum / count as f64
+}
+
+/// Compares the decoded image with the source, it was encoded from. The luma has the weights
+/// of BT.601, which decoders convert the colors with.
+pub fn compare(reference: &Image<f32>, distorted: &Image<f32>) -> crate::Result<ImageMetrics> {
+    compare_luma(reference, distorted, ColorMatrix::Bt601)
+}
+
+/// Compares the decoded image of a grayscale JPEG with the luma of the source, which the encoder
+/// computed with the color matrix. The gray dots of the decoded image are their luma in every
+/// matrix.
+pub fn compare_luma(
+    reference: &Image<f32>,
+    distorted: &Image<f32>,
+    color_matrix: ColorMatrix,
+) -> crate::Result<ImageMetrics> {
+    if (reference.width, reference.height) != (distorted.width, distorted.height) {
+        return Err(Error::ImageDimensionsDiffer(
+            (reference.width, reference.height),
+            (distorted.width, distorted.height),
+        ));
+    }
+    if reference.dots.is_empty() {
+        return Err(Error::EmptyImage(reference.width, reference.height));
+    }
+    let width = reference.width as usize;
+    let height = reference.height as usize;
+    let reference = luma_samples(reference, color_matrix);
+    let distorted = luma_samples(distorted, color_matrix);
+    Ok(ImageMetrics {
+        psnr: psnr(&reference, &distorted),
+        ssim: ssim(&reference, &distorted, width, height),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{compare, compare_luma};
+    use crate::{color::ColorMatrix, image::Image, Error};
+
+    fn gradient(width: u16, height: u16, offset: u8) -> Image<f32> {
+        let samples: Vec<u8> = (0..width as usize * height as usize * 3)
+            .map(|index| (index / 3 % 200) as u8 + offset)
+            .collect();
+        Image::from_rgb8(width, height, &samples).unwrap()
+    }
+
+    #[test]
+    fn identical_images_are_perfect() {
+        let image = gradient(19, 11, 0);
+        let metrics = compare(&image, &image).unwrap();
+        assert!(metrics.psnr.is_infinite());
+        assert!((metrics.ssim - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn offset_lowers_psnr() {
+        let metrics = compare(&gradient(19, 11, 0), &gradient(19, 11, 5)).unwrap();
+        // an error of 5 in every sample: 10 * log10(255^2 / 25)
+        assert!((metrics.psnr - 34.15).abs() < 0.01);
+        assert!(metrics.ssim < 1.0 && metrics.ssim > 0.9);
+    }
+
+    #[test]
+    fn compare_small_images_as_a_whole() {
+        let metrics = compare(&gradient(3, 2, 0), &gradient(3, 2, 0)).unwrap();
+        assert!((metrics.ssim - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn compare_gray_image_with_luma_of_color_matrix() {
+        let samples: Vec<u8> = (0..16 * 16)
+            .flat_map(|index| [(index % 256) as u8, (index * 7 % 256) as u8, 40])
+            .collect();
+        let image = Image::from_rgb8(16, 16, &samples).unwrap();
+        let [red_weight, green_weight, blue_weight] = ColorMatrix::Bt709.coefficients()[0];
+        let gray_samples: Vec<u8> = samples
+            .chunks_exact(3)
+            .flat_map(|dot| {
+                let luma = red_weight * dot[0] as f64
+                    + green_weight * dot[1] as f64
+                    + blue_weight * dot[2] as f64;
+                [luma.round() as u8; 3]
+            })
+            .collect();
+        let gray_image = Image::from_rgb8(16, 16, &gray_samples).unwrap();
+        let metrics = compare_luma(&image, &gray_image, ColorMatrix::Bt709).unwrap();
+        // only the rounding to 8 bit differs
+        assert!(metrics.psnr > 50.0);
+        assert!(compare(&image, &gray_image).unwrap().psnr < metrics.psnr);
+    }
+
+    #[test]
+    fn reject_differing_dimensions() {
+        assert!(matches!(
+            compare(&gradient(8, 8, 0), &gradient(8, 9, 0)),
+            Err(Error::ImageDimensionsDiffer((8, 8), (8, 9)))
+        ));
+    }
+}
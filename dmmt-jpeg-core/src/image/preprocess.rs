@@ -0,0 +1,236 @@
+use std::sync::{mpsc, Arc};
+
+use threadpool::ThreadPool;
+
+use super::Image;
+use crate::color::RGBColorFormat;
+
+mod color_adjustment;
+mod crop;
+mod invert;
+mod resize;
+mod rotate;
+mod smooth;
+
+pub use color_adjustment::ColorAdjustment;
+pub use crop::Crop;
+pub use invert::Invert;
+pub use resize::Resize;
+pub use rotate::{Rotate, Rotation};
+pub use smooth::Smooth;
+
+/// A single preprocessing step, applied to the decoded image before it is transformed.
+pub trait ImageOp: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn apply(&self, image: Image<f32>) -> crate::Result<Image<f32>>;
+
+    /// Returns the operation as dot operation, if each dot is mapped independently of its
+    /// neighbours. Those operations can be applied on stripes of the image in parallel.
+    fn as_dot_op(&self) -> Option<&dyn DotOp> {
+        None
+    }
+}
+
+/// A preprocessing step, which maps each dot independently of its position and neighbours.
+pub trait DotOp: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn map_dot(&self, dot: &RGBColorFormat<f32>) -> RGBColorFormat<f32>;
+}
+
+impl<T: DotOp> ImageOp for T {
+    fn name(&self) -> &'static str {
+        DotOp::name(self)
+    }
+
+    fn apply(&self, mut image: Image<f32>) -> crate::Result<Image<f32>> {
+        for dot in image.dots.iter_mut() {
+            *dot = self.map_dot(dot);
+        }
+        Ok(image)
+    }
+
+    fn as_dot_op(&self) -> Option<&dyn DotOp> {
+        Some(self)
+    }
+}
+
+/// Ordered list of preprocessing steps. Steps are applied in the order they were pushed.
+#[derive(Default)]
+pub struct PreprocessingPipeline {
+    operations: Vec<Arc<dyn ImageOp>>,
+}
+
+impl PreprocessingPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, operation: impl ImageOp + 'static) {
+        self.operations.push(Arc::new(operation));
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    pub fn run(&self, image: Image<f32>) -> crate::Result<Image<f32>> {
+        self.operations.iter().try_fold(image, |image, operation| {
+            log::info!("Applying preprocessing step {}", operation.name());
+            operation.apply(image)
+        })
+    }
+
+    /// Runs the pipeline like [`Self::run`], but fuses consecutive dot operations and applies
+    /// them on stripes of `stripe_height` rows on the threadpool. The stripe height should be the
+    /// height of an MCU, so each stripe covers whole MCU rows of the padded image.
+    pub fn run_on_threadpool(
+        &self,
+        image: Image<f32>,
+        threadpool: &ThreadPool,
+        stripe_height: u16,
+    ) -> crate::Result<Image<f32>> {
+        let mut image = image;
+        let mut remaining_operations = self.operations.as_slice();
+        while let Some(operation) = remaining_operations.first() {
+            let number_of_dot_operations = remaining_operations
+                .iter()
+                .take_while(|operation| operation.as_dot_op().is_some())
+                .count();
+            if number_of_dot_operations == 0 {
+                log::info!("Applying preprocessing step {}", operation.name());
+                image = operation.apply(image)?;
+                remaining_operations = &remaining_operations[1..];
+                continue;
+            }
+            let (dot_operations, rest) = remaining_operations.split_at(number_of_dot_operations);
+            image = Self::apply_on_stripes(image, dot_operations, threadpool, stripe_height);
+            remaining_operations = rest;
+        }
+        Ok(image)
+    }
+
+    fn apply_on_stripes(
+        mut image: Image<f32>,
+        operations: &[Arc<dyn ImageOp>],
+        threadpool: &ThreadPool,
+        stripe_height: u16,
+    ) -> Image<f32> {
+        for operation in operations {
+            log::info!(
+                "Applying preprocessing step {} on stripes",
+                operation.name()
+            );
+        }
+        let operations: Arc<[Arc<dyn ImageOp>]> = Arc::from(operations);
+        let stripe_length = (image.width as usize * stripe_height.max(1) as usize).max(1);
+        let (sender, receiver) = mpsc::channel();
+        let mut number_of_stripes = 0;
+        while !image.dots.is_empty() {
+            let rest = image.dots.split_off(stripe_length.min(image.dots.len()));
+            let mut stripe = std::mem::replace(&mut image.dots, rest);
+            let operations = Arc::clone(&operations);
+            let sender = sender.clone();
+            let stripe_index = number_of_stripes;
+            threadpool.execute(move || {
+                for operation in operations.iter() {
+                    let dot_operation = operation
+                        .as_dot_op()
+                        .expect("Only dot operations are applied on stripes");
+                    for dot in stripe.iter_mut() {
+                        *dot = dot_operation.map_dot(dot);
+                    }
+                }
+                sender
+                    .send((stripe_index, stripe))
+                    .expect("Receiver of preprocessed stripes was dropped");
+            });
+            number_of_stripes += 1;
+        }
+        drop(sender);
+        let mut stripes: Vec<_> = receiver.iter().collect();
+        assert_eq!(
+            stripes.len(),
+            number_of_stripes,
+            "Preprocessing of a stripe failed"
+        );
+        stripes.sort_unstable_by_key(|(index, _)| *index);
+        image.dots = stripes.into_iter().flat_map(|(_, stripe)| stripe).collect();
+        image
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use threadpool::ThreadPool;
+
+    use crate::{color::RGBColorFormat, image::Image};
+
+    use super::{ColorAdjustment, Crop, Invert, PreprocessingPipeline, Rotate, Rotation};
+
+    #[test]
+    fn run_operations_in_order() {
+        let image: Image<f32> = Image {
+            width: 2,
+            height: 1,
+            dots: Vec::from([RGBColorFormat::red(), RGBColorFormat::default()]),
+        };
+        let mut pipeline = PreprocessingPipeline::new();
+        pipeline.push(Rotate(Rotation::Clockwise90));
+        pipeline.push(Crop::new(0, 1, 1, 1));
+        pipeline.push(Invert);
+        assert_eq!(pipeline.len(), 3);
+        let result = pipeline.run(image).unwrap();
+        assert_eq!(result.width, 1);
+        assert_eq!(result.height, 1);
+        assert_eq!(result.dots[0], RGBColorFormat::default().inverted());
+    }
+
+    #[test]
+    fn empty_pipeline_keeps_image() {
+        let image: Image<f32> = Image {
+            width: 1,
+            height: 1,
+            dots: Vec::from([RGBColorFormat::red()]),
+        };
+        let pipeline = PreprocessingPipeline::new();
+        assert!(pipeline.is_empty());
+        let result = pipeline.run(image).unwrap();
+        assert_eq!(result.dots[0], RGBColorFormat::red());
+    }
+
+    #[test]
+    fn run_on_threadpool_matches_sequential_run() {
+        let create_image = || {
+            let dots = (0..35)
+                .map(|i| {
+                    RGBColorFormat::default().interpolate(&RGBColorFormat::red(), i as f32 / 35.0)
+                })
+                .collect();
+            Image {
+                width: 5,
+                height: 7,
+                dots,
+            }
+        };
+        let mut pipeline = PreprocessingPipeline::new();
+        pipeline.push(Invert);
+        pipeline.push(ColorAdjustment {
+            saturation: 0.5,
+            ..Default::default()
+        });
+        pipeline.push(Crop::new(1, 1, 4, 5));
+        pipeline.push(Invert);
+        let threadpool = ThreadPool::new(3);
+        let expected = pipeline.run(create_image()).unwrap();
+        let result = pipeline
+            .run_on_threadpool(create_image(), &threadpool, 2)
+            .unwrap();
+        assert_eq!(result.width, expected.width);
+        assert_eq!(result.height, expected.height);
+        assert_eq!(result.dots, expected.dots);
+    }
+}
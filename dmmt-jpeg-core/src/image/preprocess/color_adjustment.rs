@@ -0,0 +1,122 @@
+use super::DotOp;
+use crate::color::{RGBColorFormat, YCbCrColorFormat};
+
+const MIN_SAMPLE_VALUE: f32 = -128.0;
+const MAX_SAMPLE_VALUE: f32 = 127.0;
+
+/// Brightness, contrast and saturation adjustment, applied in the level shifted YCbCr space.
+///
+/// `brightness` is an offset relative to the full value range (-1.0 to 1.0), `contrast` scales
+/// the luma around mid gray and `saturation` scales both chroma components. The neutral values
+/// are 0.0, 1.0 and 1.0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorAdjustment {
+    pub brightness: f32,
+    pub contrast: f32,
+    pub saturation: f32,
+}
+
+impl Default for ColorAdjustment {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+        }
+    }
+}
+
+impl ColorAdjustment {
+    pub fn is_neutral(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn adjust(&self, value: YCbCrColorFormat<f32>) -> YCbCrColorFormat<f32> {
+        let luma = value.luma * self.contrast + self.brightness * 255.0;
+        YCbCrColorFormat {
+            luma: luma.clamp(MIN_SAMPLE_VALUE, MAX_SAMPLE_VALUE),
+            chroma_blue: (value.chroma_blue * self.saturation)
+                .clamp(MIN_SAMPLE_VALUE, MAX_SAMPLE_VALUE),
+            chroma_red: (value.chroma_red * self.saturation)
+                .clamp(MIN_SAMPLE_VALUE, MAX_SAMPLE_VALUE),
+        }
+    }
+}
+
+impl DotOp for ColorAdjustment {
+    fn name(&self) -> &'static str {
+        "color adjustment"
+    }
+
+    fn map_dot(&self, dot: &RGBColorFormat<f32>) -> RGBColorFormat<f32> {
+        let adjusted = self.adjust(YCbCrColorFormat::from(dot));
+        RGBColorFormat::from(&adjusted)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color::{RGBColorFormat, YCbCrColorFormat};
+    use crate::image::Image;
+
+    use super::super::ImageOp;
+    use super::ColorAdjustment;
+
+    fn create_test_color() -> YCbCrColorFormat<f32> {
+        YCbCrColorFormat {
+            luma: 20.0,
+            chroma_blue: -40.0,
+            chroma_red: 10.0,
+        }
+    }
+
+    #[test]
+    fn neutral_color_adjustment_keeps_values() {
+        let adjustment = ColorAdjustment::default();
+        assert!(adjustment.is_neutral());
+        let result = adjustment.adjust(create_test_color());
+        assert_eq!(result.luma, 20.0, "luma is wrong");
+        assert_eq!(result.chroma_blue, -40.0, "chroma blue is wrong");
+        assert_eq!(result.chroma_red, 10.0, "chroma red is wrong");
+    }
+
+    #[test]
+    fn brightness_and_contrast_change_luma_only() {
+        let adjustment = ColorAdjustment {
+            brightness: 0.1,
+            contrast: 2.0,
+            ..Default::default()
+        };
+        let result = adjustment.adjust(create_test_color());
+        assert_eq!(result.luma, 65.5, "luma is wrong");
+        assert_eq!(result.chroma_blue, -40.0, "chroma blue is wrong");
+        assert_eq!(result.chroma_red, 10.0, "chroma red is wrong");
+    }
+
+    #[test]
+    fn saturation_scales_chroma_and_clamps() {
+        let adjustment = ColorAdjustment {
+            saturation: 4.0,
+            ..Default::default()
+        };
+        let result = adjustment.adjust(create_test_color());
+        assert_eq!(result.luma, 20.0, "luma is wrong");
+        assert_eq!(result.chroma_blue, -128.0, "chroma blue is wrong");
+        assert_eq!(result.chroma_red, 40.0, "chroma red is wrong");
+    }
+
+    #[test]
+    fn full_brightness_turns_image_white() {
+        let image: Image<f32> = Image {
+            width: 1,
+            height: 1,
+            dots: Vec::from([RGBColorFormat::default()]),
+        };
+        let adjustment = ColorAdjustment {
+            brightness: 1.0,
+            ..Default::default()
+        };
+        let result = adjustment.apply(image).unwrap();
+        assert_eq!(result.dots[0], RGBColorFormat::default().inverted());
+    }
+}
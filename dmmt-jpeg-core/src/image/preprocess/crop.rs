@@ -0,0 +1,103 @@
+use std::fmt::Display;
+
+use super::super::Image;
+use super::ImageOp;
+use crate::Error;
+
+/// Cuts the rectangle of `width` x `height` dots starting at (`x`, `y`) out of the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Crop {
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+}
+
+impl Crop {
+    pub fn new(x: u16, y: u16, width: u16, height: u16) -> Self {
+        if width == 0 || height == 0 {
+            panic!("Crop region must not be empty");
+        }
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn fits_into(&self, image: &Image<f32>) -> bool {
+        self.x as u32 + self.width as u32 <= image.width as u32
+            && self.y as u32 + self.height as u32 <= image.height as u32
+    }
+}
+
+impl Display for Crop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}+{}+{}", self.width, self.height, self.x, self.y)
+    }
+}
+
+impl ImageOp for Crop {
+    fn name(&self) -> &'static str {
+        "crop"
+    }
+
+    fn apply(&self, image: Image<f32>) -> crate::Result<Image<f32>> {
+        if !self.fits_into(&image) {
+            return Err(Error::CropRegionOutsideOfImage(
+                self.to_string(),
+                image.width,
+                image.height,
+            ));
+        }
+        let image_width = image.width as usize;
+        let dots = image
+            .dots
+            .chunks_exact(image_width)
+            .skip(self.y as usize)
+            .take(self.height as usize)
+            .flat_map(|row| &row[self.x as usize..(self.x + self.width) as usize])
+            .copied()
+            .collect();
+        Ok(Image {
+            width: self.width,
+            height: self.height,
+            dots,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{color::RGBColorFormat, error::Error, image::Image};
+
+    use super::{Crop, ImageOp};
+
+    fn create_test_image() -> Image<f32> {
+        let black = RGBColorFormat::default();
+        let red = RGBColorFormat::red();
+        Image {
+            width: 3,
+            height: 2,
+            dots: Vec::from([black, black, black, black, red, red]),
+        }
+    }
+
+    #[test]
+    fn crop_region() {
+        let result = Crop::new(1, 1, 2, 1).apply(create_test_image()).unwrap();
+        assert_eq!(result.width, 2);
+        assert_eq!(result.height, 1);
+        assert_eq!(result.dots, [RGBColorFormat::red(), RGBColorFormat::red()]);
+    }
+
+    #[test]
+    fn crop_region_outside_of_image() {
+        let result = Crop::new(2, 0, 2, 1).apply(create_test_image());
+        assert!(matches!(
+            result,
+            Err(Error::CropRegionOutsideOfImage(_, 3, 2))
+        ));
+    }
+}
@@ -0,0 +1,42 @@
+use super::DotOp;
+use crate::color::RGBColorFormat;
+
+/// Inverts every color channel of the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Invert;
+
+impl DotOp for Invert {
+    fn name(&self) -> &'static str {
+        "invert"
+    }
+
+    fn map_dot(&self, dot: &RGBColorFormat<f32>) -> RGBColorFormat<f32> {
+        dot.inverted()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{color::RGBColorFormat, image::Image};
+
+    use super::super::ImageOp;
+    use super::Invert;
+
+    #[test]
+    fn invert_image() {
+        let image: Image<f32> = Image {
+            width: 2,
+            height: 1,
+            dots: Vec::from([RGBColorFormat::red(), RGBColorFormat::default()]),
+        };
+        let image = Invert.apply(image).unwrap();
+        assert_eq!(image.dots[0], RGBColorFormat::red().inverted());
+        assert_eq!(image.dots[1], RGBColorFormat::default().inverted());
+        let image = Invert.apply(image).unwrap();
+        assert_eq!(
+            image.dots[0],
+            RGBColorFormat::red(),
+            "double inversion must be identity"
+        );
+    }
+}
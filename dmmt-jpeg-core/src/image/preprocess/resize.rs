@@ -0,0 +1,99 @@
+use super::super::Image;
+use super::ImageOp;
+use crate::color::RGBColorFormat;
+
+/// Scales the image to `width` x `height` dots using bilinear interpolation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Resize {
+    width: u16,
+    height: u16,
+}
+
+impl Resize {
+    pub fn new(width: u16, height: u16) -> Self {
+        if width == 0 || height == 0 {
+            panic!("Target size of resize must not be empty");
+        }
+        Self { width, height }
+    }
+
+    /// Maps the center of the target dot at `index` onto the source axis and returns the two
+    /// neighbouring source indices together with the weight of the second one.
+    fn sample_position(index: u16, target_len: u16, source_len: u16) -> (usize, usize, f32) {
+        let scale = source_len as f32 / target_len as f32;
+        let position = ((index as f32 + 0.5) * scale - 0.5).max(0.0);
+        let lower = (position.floor() as usize).min(source_len as usize - 1);
+        let upper = (lower + 1).min(source_len as usize - 1);
+        (lower, upper, position - lower as f32)
+    }
+
+    fn sample(image: &Image<f32>, x: u16, y: u16, width: u16, height: u16) -> RGBColorFormat<f32> {
+        let (left, right, x_weight) = Self::sample_position(x, width, image.width);
+        let (top, bottom, y_weight) = Self::sample_position(y, height, image.height);
+        let image_width = image.width as usize;
+        let dot = |x: usize, y: usize| &image.dots[y * image_width + x];
+        let top_row = dot(left, top).interpolate(dot(right, top), x_weight);
+        let bottom_row = dot(left, bottom).interpolate(dot(right, bottom), x_weight);
+        top_row.interpolate(&bottom_row, y_weight)
+    }
+}
+
+impl ImageOp for Resize {
+    fn name(&self) -> &'static str {
+        "resize"
+    }
+
+    fn apply(&self, image: Image<f32>) -> crate::Result<Image<f32>> {
+        if image.width == self.width && image.height == self.height {
+            return Ok(image);
+        }
+        let dots = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .map(|(x, y)| Self::sample(&image, x, y, self.width, self.height))
+            .collect();
+        Ok(Image {
+            width: self.width,
+            height: self.height,
+            dots,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{color::RGBColorFormat, image::Image};
+
+    use super::{ImageOp, Resize};
+
+    #[test]
+    fn upscale_interpolates_between_dots() {
+        let image: Image<f32> = Image {
+            width: 2,
+            height: 1,
+            dots: Vec::from([RGBColorFormat::default(), RGBColorFormat::red()]),
+        };
+        let result = Resize::new(4, 2).apply(image).unwrap();
+        assert_eq!(result.width, 4);
+        assert_eq!(result.height, 2);
+        let black = RGBColorFormat::default();
+        let red = RGBColorFormat::red();
+        assert_eq!(result.dots[0], black);
+        assert_eq!(result.dots[1], black.interpolate(&red, 0.25));
+        assert_eq!(result.dots[2], black.interpolate(&red, 0.75));
+        assert_eq!(result.dots[3], red);
+        assert_eq!(result.dots[4..], result.dots[..4]);
+    }
+
+    #[test]
+    fn downscale_uniform_image() {
+        let image: Image<f32> = Image {
+            width: 4,
+            height: 4,
+            dots: vec![RGBColorFormat::red(); 16],
+        };
+        let result = Resize::new(2, 1).apply(image).unwrap();
+        assert_eq!(result.width, 2);
+        assert_eq!(result.height, 1);
+        assert!(result.dots.iter().all(|dot| *dot == RGBColorFormat::red()));
+    }
+}
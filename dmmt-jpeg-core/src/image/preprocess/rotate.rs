@@ -0,0 +1,135 @@
+use super::super::Image;
+use super::ImageOp;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rotation {
+    None,
+    Clockwise90,
+    Clockwise180,
+    Clockwise270,
+}
+
+/// Rotates the image clockwise by a multiple of 90 degrees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rotate(pub Rotation);
+
+impl Rotation {
+    pub fn degrees(&self) -> u16 {
+        match self {
+            Self::None => 0,
+            Self::Clockwise90 => 90,
+            Self::Clockwise180 => 180,
+            Self::Clockwise270 => 270,
+        }
+    }
+
+    pub fn from_degrees(degrees: u16) -> Option<Self> {
+        match degrees {
+            0 => Some(Self::None),
+            90 => Some(Self::Clockwise90),
+            180 => Some(Self::Clockwise180),
+            270 => Some(Self::Clockwise270),
+            _ => None,
+        }
+    }
+}
+
+impl ImageOp for Rotate {
+    fn name(&self) -> &'static str {
+        "rotate"
+    }
+
+    fn apply(&self, image: Image<f32>) -> crate::Result<Image<f32>> {
+        let width = image.width as usize;
+        let height = image.height as usize;
+        let rotated = match self.0 {
+            Rotation::None => image,
+            Rotation::Clockwise180 => Image {
+                width: image.width,
+                height: image.height,
+                dots: image.dots.into_iter().rev().collect(),
+            },
+            Rotation::Clockwise90 => Image {
+                width: image.height,
+                height: image.width,
+                dots: (0..width)
+                    .flat_map(|y| (0..height).map(move |x| (height - 1 - x) * width + y))
+                    .map(|index| image.dots[index])
+                    .collect(),
+            },
+            Rotation::Clockwise270 => Image {
+                width: image.height,
+                height: image.width,
+                dots: (0..width)
+                    .flat_map(|y| (0..height).map(move |x| x * width + (width - 1 - y)))
+                    .map(|index| image.dots[index])
+                    .collect(),
+            },
+        };
+        Ok(rotated)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{color::RGBColorFormat, image::Image};
+
+    use super::{ImageOp, Rotate, Rotation};
+
+    fn create_test_image() -> Image<f32> {
+        let black = RGBColorFormat::default();
+        let red = RGBColorFormat::red();
+        let white = black.inverted();
+        Image {
+            width: 3,
+            height: 2,
+            dots: Vec::from([red, black, black, black, black, white]),
+        }
+    }
+
+    #[test]
+    fn rotate_clockwise_90() {
+        let result = Rotate(Rotation::Clockwise90)
+            .apply(create_test_image())
+            .unwrap();
+        assert_eq!(result.width, 2);
+        assert_eq!(result.height, 3);
+        assert_eq!(result.dots[1], RGBColorFormat::red());
+        assert_eq!(result.dots[4], RGBColorFormat::default().inverted());
+    }
+
+    #[test]
+    fn rotate_clockwise_180() {
+        let result = Rotate(Rotation::Clockwise180)
+            .apply(create_test_image())
+            .unwrap();
+        assert_eq!(result.width, 3);
+        assert_eq!(result.height, 2);
+        assert_eq!(result.dots[0], RGBColorFormat::default().inverted());
+        assert_eq!(result.dots[5], RGBColorFormat::red());
+    }
+
+    #[test]
+    fn rotate_clockwise_270() {
+        let result = Rotate(Rotation::Clockwise270)
+            .apply(create_test_image())
+            .unwrap();
+        assert_eq!(result.width, 2);
+        assert_eq!(result.height, 3);
+        assert_eq!(result.dots[4], RGBColorFormat::red());
+        assert_eq!(result.dots[1], RGBColorFormat::default().inverted());
+    }
+
+    #[test]
+    fn rotation_from_degrees() {
+        for rotation in [
+            Rotation::None,
+            Rotation::Clockwise90,
+            Rotation::Clockwise180,
+            Rotation::Clockwise270,
+        ] {
+            assert_eq!(Rotation::from_degrees(rotation.degrees()), Some(rotation));
+        }
+        assert_eq!(Rotation::from_degrees(45), None);
+    }
+}
@@ -0,0 +1,131 @@
+use super::super::Image;
+use super::ImageOp;
+use crate::color::RGBColorFormat;
+
+/// Blurs the image with a separable filter of three taps in each direction, which suppresses
+/// the dithering noise of scanned or dithered sources before it costs bits in the DCT. The
+/// factor ranges from 0 (no smoothing) to 100 (3x3 box blur). Dots beyond the border are
+/// replaced by the nearest dot of the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Smooth {
+    factor: u8,
+}
+
+impl Smooth {
+    pub fn new(factor: u8) -> Self {
+        if factor > 100 {
+            panic!("Smoothing factor must not exceed 100");
+        }
+        Self { factor }
+    }
+
+    /// Weight of each neighbour of the filter. The center keeps the remaining weight.
+    fn neighbour_weight(&self) -> f32 {
+        self.factor as f32 / 300.0
+    }
+
+    /// Filters the dots along one axis. `neighbour` returns the index of the dot before or after
+    /// the dot at the index, clamped to the image.
+    fn filter(
+        &self,
+        dots: &[RGBColorFormat<f32>],
+        neighbour: impl Fn(usize, bool) -> usize,
+    ) -> Vec<RGBColorFormat<f32>> {
+        let neighbour_weight = self.neighbour_weight();
+        let center_weight = 1.0 - 2.0 * neighbour_weight;
+        (0..dots.len())
+            .map(|index| {
+                let before = dots[neighbour(index, false)].samples();
+                let center = dots[index].samples();
+                let after = dots[neighbour(index, true)].samples();
+                RGBColorFormat::from_samples(std::array::from_fn(|channel| {
+                    center[channel] * center_weight
+                        + (before[channel] + after[channel]) * neighbour_weight
+                }))
+            })
+            .collect()
+    }
+}
+
+impl ImageOp for Smooth {
+    fn name(&self) -> &'static str {
+        "smooth"
+    }
+
+    fn apply(&self, image: Image<f32>) -> crate::Result<Image<f32>> {
+        if self.factor == 0 {
+            return Ok(image);
+        }
+        let width = image.width as usize;
+        let height = image.height as usize;
+        let horizontal = self.filter(&image.dots, |index, after| {
+            let x = index % width;
+            let neighbour_x = if after {
+                (x + 1).min(width - 1)
+            } else {
+                x.saturating_sub(1)
+            };
+            index - x + neighbour_x
+        });
+        let dots = self.filter(&horizontal, |index, after| {
+            let y = index / width;
+            let neighbour_y = if after {
+                (y + 1).min(height - 1)
+            } else {
+                y.saturating_sub(1)
+            };
+            index + neighbour_y * width - y * width
+        });
+        Ok(Image { dots, ..image })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{color::RGBColorFormat, image::Image};
+
+    use super::{ImageOp, Smooth};
+
+    fn checkerboard() -> Image<f32> {
+        let dots = (0..16)
+            .map(|index| {
+                let value = ((index % 4 + index / 4) % 2) as f32;
+                RGBColorFormat::from_samples([value; 3])
+            })
+            .collect();
+        Image {
+            width: 4,
+            height: 4,
+            dots,
+        }
+    }
+
+    #[test]
+    fn smooth_checkerboard() {
+        let result = Smooth::new(100).apply(checkerboard()).unwrap();
+        // the 3x3 box around an inner dot holds 4 or 5 white dots of 9
+        let inner = |x: usize, y: usize| result.dots[y * 4 + x].samples()[0];
+        assert!((inner(1, 1) - 4.0 / 9.0).abs() < 1e-6);
+        assert!((inner(2, 1) - 5.0 / 9.0).abs() < 1e-6);
+        let sum: f32 = result.dots.iter().map(|dot| dot.samples()[0]).sum();
+        assert!((sum - 8.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn keep_uniform_image_and_zero_factor() {
+        let image: Image<f32> = Image {
+            width: 3,
+            height: 2,
+            dots: vec![RGBColorFormat::red(); 6],
+        };
+        let result = Smooth::new(60).apply(image).unwrap();
+        for dot in &result.dots {
+            let [red, green, blue] = dot.samples();
+            assert!((red - 1.0).abs() < 1e-6 && green.abs() < 1e-6 && blue.abs() < 1e-6);
+        }
+        assert_eq!(
+            Smooth::new(0).apply(checkerboard()).unwrap(),
+            checkerboard()
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use std::io::{BufRead, Read};
+use std::path::Path;
+
+use super::{Image, ImageReader, RGBAImage};
+use crate::Error;
+
+pub mod jpeg;
+pub mod pam;
+pub mod pfm;
+#[cfg(feature = "png")]
+pub mod png;
+pub mod ppm;
+pub mod tiff;
+pub mod y4m;
+
+/// Reads a whole image from the reader within the limits. The reader is positioned at the
+/// start of the file.
+pub type ReadImageFn = fn(&mut dyn Read, &InputLimits) -> crate::Result<Image<f32>>;
+
+/// Reads a whole image with its alpha channel, like `ReadImageFn`.
+pub type ReadRGBAImageFn = fn(&mut dyn Read, &InputLimits) -> crate::Result<RGBAImage>;
+
+/// Limits enforced while the header of an input file is parsed, before memory for the samples
+/// is allocated. They protect against malicious files, which declare absurd dimensions or
+/// consist of a single endless token.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InputLimits {
+    /// Maximum length of a token of a text based header in bytes. Comments are not limited.
+    pub max_token_length: usize,
+    /// Maximum number of pixels, an image may declare.
+    pub max_pixel_count: u64,
+}
+
+impl InputLimits {
+    pub fn check_pixel_count(&self, width: u16, height: u16) -> crate::Result<()> {
+        let pixel_count = width as u64 * height as u64;
+        if pixel_count > self.max_pixel_count {
+            return Err(Error::PixelCountExceedsLimit(
+                pixel_count,
+                self.max_pixel_count,
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for InputLimits {
+    /// Allows tokens of 64 bytes, which is plenty for numbers and keywords, and images of up to
+    /// 2^28 pixels, which already take 3 GiB as float samples.
+    fn default() -> Self {
+        Self {
+            max_token_length: 64,
+            max_pixel_count: 1 << 28,
+        }
+    }
+}
+
+/// An image reader together with the file extensions and magic numbers, it is selected by.
+pub struct ImageReaderRegistration {
+    pub name: &'static str,
+    pub extensions: &'static [&'static str],
+    pub magic_numbers: &'static [&'static [u8]],
+    pub read_image: ReadImageFn,
+    /// Reader, which keeps the alpha channel, or `None`, if the format has no alpha.
+    pub read_rgba_image: Option<ReadRGBAImageFn>,
+}
+
+impl ImageReaderRegistration {
+    fn matches_extension(&self, extension: &str) -> bool {
+        self.extensions
+            .iter()
+            .any(|e| e.eq_ignore_ascii_case(extension))
+    }
+
+    fn matches_magic_number(&self, header: &[u8]) -> bool {
+        self.magic_numbers
+            .iter()
+            .any(|magic_number| header.starts_with(magic_number))
+    }
+}
+
+/// Selects the image reader for an input file. A reader is selected by the file extension
+/// first and by the magic number at the start of the file, if no extension matches. Readers
+/// registered later take precedence, so the built-in readers can be replaced.
+pub struct ImageReaderRegistry {
+    registrations: Vec<ImageReaderRegistration>,
+}
+
+impl ImageReaderRegistry {
+    /// Creates a registry without any readers.
+    pub fn empty() -> Self {
+        Self {
+            registrations: Vec::new(),
+        }
+    }
+
+    pub fn register(&mut self, registration: ImageReaderRegistration) {
+        self.registrations.push(registration);
+    }
+
+    pub fn find(&self, file_path: &Path, header: &[u8]) -> Option<&ImageReaderRegistration> {
+        let extension = file_path
+            .extension()
+            .and_then(|extension| extension.to_str());
+        let registrations = || self.registrations.iter().rev();
+        extension
+            .and_then(|extension| registrations().find(|r| r.matches_extension(extension)))
+            .or_else(|| registrations().find(|r| r.matches_magic_number(header)))
+    }
+
+    fn select(
+        &self,
+        file_path: &Path,
+        reader: &mut impl BufRead,
+    ) -> crate::Result<&ImageReaderRegistration> {
+        let header = reader.fill_buf().unwrap_or_default();
+        let registration = self.find(file_path, header).ok_or_else(|| {
+            let extension = file_path
+                .extension()
+                .map_or(String::new(), |e| e.to_string_lossy().into_owned());
+            Error::UnsupportedInputFormat(extension)
+        })?;
+        log::info!("Reading input image as {}", registration.name);
+        Ok(registration)
+    }
+
+    /// Reads the image with the reader selected for the file path and the first bytes, which
+    /// are available in the buffer of the reader.
+    pub fn read_image(
+        &self,
+        file_path: &Path,
+        mut reader: impl BufRead,
+        limits: &InputLimits,
+    ) -> crate::Result<Image<f32>> {
+        let registration = self.select(file_path, &mut reader)?;
+        (registration.read_image)(&mut reader, limits)
+    }
+
+    /// Reads the image like `read_image`, but keeps the alpha channel, if the selected reader
+    /// supports it. Images of other readers are opaque.
+    pub fn read_rgba_image(
+        &self,
+        file_path: &Path,
+        mut reader: impl BufRead,
+        limits: &InputLimits,
+    ) -> crate::Result<RGBAImage> {
+        let registration = self.select(file_path, &mut reader)?;
+        match registration.read_rgba_image {
+            Some(read_rgba_image) => read_rgba_image(&mut reader, limits),
+            None => (registration.read_image)(&mut reader, limits).map(RGBAImage::from),
+        }
+    }
+}
+
+impl Default for ImageReaderRegistry {
+    /// Creates a registry with the built-in readers of this crate.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(ImageReaderRegistration {
+            name: "PPM",
+            extensions: &["ppm"],
+            magic_numbers: &[b"P3"],
+            read_image: |reader, limits| {
+                ppm::PPMImageReader::with_limits(reader, *limits).read_image()
+            },
+            read_rgba_image: None,
+        });
+        registry.register(ImageReaderRegistration {
+            name: "PAM",
+            extensions: &["pam"],
+            magic_numbers: &[b"P7"],
+            read_image: |reader, limits| {
+                pam::PAMImageReader::with_limits(reader, *limits).read_image()
+            },
+            read_rgba_image: Some(|reader, limits| {
+                pam::PAMImageReader::with_limits(reader, *limits).read_rgba_image()
+            }),
+        });
+        registry.register(ImageReaderRegistration {
+            name: "PFM",
+            extensions: &["pfm"],
+            magic_numbers: &[b"PF", b"Pf"],
+            read_image: |reader, limits| {
+                pfm::PFMImageReader::with_limits(reader, *limits).read_image()
+            },
+            read_rgba_image: None,
+        });
+        #[cfg(feature = "png")]
+        registry.register(ImageReaderRegistration {
+            name: "PNG",
+            extensions: &["png"],
+            magic_numbers: &[b"\x89PNG"],
+            read_image: |reader, limits| {
+                png::PNGImageReader::with_limits(reader, *limits).read_image()
+            },
+            read_rgba_image: Some(|reader, limits| {
+                png::PNGImageReader::with_limits(reader, *limits).read_rgba_image()
+            }),
+        });
+        registry.register(ImageReaderRegistration {
+            name: "JPEG",
+            extensions: &["jpg", "jpeg"],
+            magic_numbers: &[b"\xFF\xD8\xFF"],
+            read_image: |reader, limits| {
+                jpeg::JPEGImageReader::with_limits(reader, *limits).read_image()
+            },
+            read_rgba_image: None,
+        });
+        registry.register(ImageReaderRegistration {
+            name: "TIFF",
+            extensions: &["tif", "tiff"],
+            magic_numbers: &[b"II*\0", b"MM\0*"],
+            read_image: |reader, limits| {
+                tiff::TIFFImageReader::with_limits(reader, *limits).read_image()
+            },
+            read_rgba_image: None,
+        });
+        registry
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{color::RGBColorFormat, error::Error, image::Image};
+
+    use super::{ImageReaderRegistration, ImageReaderRegistry, InputLimits};
+
+    const PPM: &[u8] = b"P3\n1 1\n255\n255 0 0\n";
+
+    #[test]
+    fn select_reader_by_extension() {
+        let registry = ImageReaderRegistry::default();
+        let registration = registry.find(Path::new("image.TIFF"), b"").unwrap();
+        assert_eq!(registration.name, "TIFF");
+    }
+
+    #[test]
+    fn select_reader_by_magic_number_for_unknown_extension() {
+        let registry = ImageReaderRegistry::default();
+        let image = registry
+            .read_image(Path::new("image.txt"), PPM, &InputLimits::default())
+            .unwrap();
+        assert_eq!(image.dots[0], RGBColorFormat::red());
+    }
+
+    #[test]
+    fn unknown_format() {
+        let registry = ImageReaderRegistry::default();
+        let result = registry.read_image(
+            Path::new("image.xyz"),
+            &b"unknown"[..],
+            &InputLimits::default(),
+        );
+        assert!(matches!(result, Err(Error::UnsupportedInputFormat(e)) if e == "xyz"));
+    }
+
+    #[test]
+    fn read_rgba_image_with_and_without_alpha() {
+        let registry = ImageReaderRegistry::default();
+        let pam =
+            b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n\xFF\0\0\0";
+        let image = registry
+            .read_rgba_image(Path::new("image.pam"), &pam[..], &InputLimits::default())
+            .unwrap();
+        assert!(image.has_alpha());
+        let image = registry
+            .read_rgba_image(Path::new("image.ppm"), PPM, &InputLimits::default())
+            .unwrap();
+        assert!(!image.has_alpha());
+        let image = image.composite(&RGBColorFormat::default());
+        assert_eq!(image.dots[0], RGBColorFormat::red());
+    }
+
+    #[test]
+    fn reject_declared_pixel_count_above_limit() {
+        let registry = ImageReaderRegistry::default();
+        let limits = InputLimits {
+            max_pixel_count: 1000,
+            ..Default::default()
+        };
+        for header in [
+            &b"P3\n100 11\n255\n"[..],
+            b"P7\nWIDTH 100\nHEIGHT 11\nDEPTH 3\nMAXVAL 255\nTUPLTYPE RGB\nENDHDR\n",
+            b"PF\n100 11\n-1.0\n",
+            // frame header of three components without any tables or scan
+            b"\xFF\xD8\xFF\xC0\x00\x11\x08\x00\x0B\x00\x64\x03\x01\x11\x00\x02\x11\x00\x03\x11\x00",
+        ] {
+            let result = registry.read_image(Path::new("image"), header, &limits);
+            assert!(
+                matches!(result, Err(Error::PixelCountExceedsLimit(1100, 1000))),
+                "{:?}",
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn registered_reader_replaces_built_in_reader() {
+        let mut registry = ImageReaderRegistry::default();
+        registry.register(ImageReaderRegistration {
+            name: "Custom",
+            extensions: &["ppm"],
+            magic_numbers: &[],
+            read_image: |_, _| {
+                Ok(Image {
+                    width: 1,
+                    height: 1,
+                    dots: vec![RGBColorFormat::default()],
+                })
+            },
+            read_rgba_image: None,
+        });
+        let image = registry
+            .read_image(Path::new("image.ppm"), PPM, &InputLimits::default())
+            .unwrap();
+        assert_eq!(image.dots[0], RGBColorFormat::default());
+    }
+}
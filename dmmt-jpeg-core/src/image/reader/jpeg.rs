@@ -0,0 +1,766 @@
+use std::io::Read;
+
+use scan_reader::ScanReader;
+
+use super::super::Image;
+use super::super::ImageReader;
+use super::InputLimits;
+use crate::color::{
+    CMYKColorFormat, RGBColorFormat, YCbCrColorFormat, BASELINE_SAMPLE_PRECISION,
+    EXTENDED_SAMPLE_PRECISION,
+};
+use crate::cosine_transform::arai::InverseAraiDiscrete8x8CosineTransformer;
+use crate::cosine_transform::Discrete8x8CosineTransformer;
+use crate::huffman::decoder::HuffmanDecoder;
+use crate::image::writer::jpeg::consts;
+use crate::Error;
+
+mod scan_reader;
+
+const FIRST_APPLICATION_MARKER: u8 = consts::APP0;
+const LAST_APPLICATION_MARKER: u8 = consts::APP0 + 15;
+
+struct Component {
+    id: u8,
+    horizontal_factor: usize,
+    vertical_factor: usize,
+    quantization_table: usize,
+    /// Number of blocks in a row of the coefficients, padded to whole MCUs.
+    blocks_per_line: usize,
+    /// Dequantized coefficients of each block in natural order.
+    blocks: Vec<[f32; 64]>,
+}
+
+struct Frame {
+    sample_precision: u8,
+    width: u16,
+    height: u16,
+    max_horizontal_factor: usize,
+    max_vertical_factor: usize,
+    components: Vec<Component>,
+}
+
+impl Frame {
+    fn mcus_per_line(&self) -> usize {
+        (self.width as usize).div_ceil(8 * self.max_horizontal_factor)
+    }
+
+    fn mcus_per_column(&self) -> usize {
+        (self.height as usize).div_ceil(8 * self.max_vertical_factor)
+    }
+
+    /// Allocates the blocks of each component, padded to whole MCUs. The height must be known.
+    fn allocate_blocks(&mut self) {
+        let (mcus_per_line, mcus_per_column) = (self.mcus_per_line(), self.mcus_per_column());
+        for component in self.components.iter_mut() {
+            component.blocks_per_line = mcus_per_line * component.horizontal_factor;
+            let blocks_per_column = mcus_per_column * component.vertical_factor;
+            component.blocks = vec![[0.0; 64]; component.blocks_per_line * blocks_per_column];
+        }
+    }
+
+    /// Number of blocks of the component, which cover the image without MCU padding.
+    fn component_blocks(&self, component: &Component) -> (usize, usize) {
+        let width = (self.width as usize * component.horizontal_factor)
+            .div_ceil(self.max_horizontal_factor);
+        let height =
+            (self.height as usize * component.vertical_factor).div_ceil(self.max_vertical_factor);
+        (width.div_ceil(8), height.div_ceil(8))
+    }
+}
+
+struct ScanComponent {
+    index: usize,
+    dc_table: usize,
+    ac_table: usize,
+}
+
+/// Decodes baseline JPEG files with huffman coding and 8 bit samples, which contain one
+/// grayscale, three YCbCr or four YCCK components. Subsampled chroma is upsampled by
+/// replication. The inks of YCCK are approximated in RGB.
+pub struct JPEGImageReader<T: Read> {
+    reader: T,
+    limits: InputLimits,
+}
+
+impl<T: Read> JPEGImageReader<T> {
+    pub fn new(reader: T) -> Self {
+        Self::with_limits(reader, InputLimits::default())
+    }
+
+    pub fn with_limits(reader: T, limits: InputLimits) -> Self {
+        Self { reader, limits }
+    }
+}
+
+impl<T: Read> ImageReader<f32> for JPEGImageReader<T> {
+    fn read_image(&mut self) -> crate::Result<Image<f32>> {
+        let mut bytes = Vec::new();
+        self.reader
+            .read_to_end(&mut bytes)
+            .map_err(|_| Error::InvalidJPEGFile("file could not be read"))?;
+        Decoder::new(&bytes, self.limits).decode()
+    }
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    position: usize,
+    quantization_tables: [Option<[u16; 64]>; 4],
+    dc_tables: [Option<HuffmanDecoder>; 4],
+    ac_tables: [Option<HuffmanDecoder>; 4],
+    restart_interval: usize,
+    frame: Option<Frame>,
+    limits: InputLimits,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(data: &'a [u8], limits: InputLimits) -> Self {
+        Self {
+            data,
+            position: 0,
+            quantization_tables: [None; 4],
+            dc_tables: [None, None, None, None],
+            ac_tables: [None, None, None, None],
+            restart_interval: 0,
+            frame: None,
+            limits,
+        }
+    }
+
+    fn read_u8(&mut self) -> crate::Result<u8> {
+        let byte = *self
+            .data
+            .get(self.position)
+            .ok_or(Error::InvalidJPEGFile("file is truncated"))?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn read_u16(&mut self) -> crate::Result<u16> {
+        Ok(u16::from_be_bytes([self.read_u8()?, self.read_u8()?]))
+    }
+
+    fn read_marker(&mut self) -> crate::Result<u8> {
+        if self.read_u8()? != consts::MARKER_PREFIX {
+            return Err(Error::InvalidJPEGFile("marker expected"));
+        }
+        let mut code = self.read_u8()?;
+        // Any number of fill bytes may precede a marker
+        while code == consts::MARKER_PREFIX {
+            code = self.read_u8()?;
+        }
+        Ok(code)
+    }
+
+    /// Returns the content of the segment at the current position and skips it.
+    fn read_segment(&mut self) -> crate::Result<&'a [u8]> {
+        let length = self.read_u16()? as usize;
+        let content = length
+            .checked_sub(2)
+            .and_then(|content_length| self.data.get(self.position..self.position + content_length))
+            .ok_or(Error::InvalidJPEGFile("segment length out of range"))?;
+        self.position += content.len();
+        Ok(content)
+    }
+
+    fn decode(mut self) -> crate::Result<Image<f32>> {
+        if self.read_marker()? != consts::SOI {
+            return Err(Error::InvalidJPEGFile("start of image marker missing"));
+        }
+        loop {
+            let marker = self.read_marker()?;
+            match marker {
+                consts::EOI => break,
+                consts::DQT => {
+                    let content = self.read_segment()?;
+                    self.define_quantization_tables(content)?;
+                }
+                consts::DHT => {
+                    let content = self.read_segment()?;
+                    self.define_huffman_tables(content)?;
+                }
+                consts::DRI => {
+                    let content = self.read_segment()?;
+                    let interval = content
+                        .get(..2)
+                        .ok_or(Error::InvalidJPEGFile("restart interval segment too short"))?;
+                    self.restart_interval = u16::from_be_bytes([interval[0], interval[1]]) as usize;
+                }
+                consts::SOF0 | consts::SOF1 => {
+                    let content = self.read_segment()?;
+                    self.frame = Some(Self::read_frame_header(content, &self.limits)?);
+                }
+                consts::SOS => {
+                    let content = self.read_segment()?;
+                    self.decode_scan(content)?;
+                }
+                FIRST_APPLICATION_MARKER..=LAST_APPLICATION_MARKER | consts::COM => {
+                    self.read_segment()?;
+                }
+                0xC2..=0xCF if marker != consts::DHT => {
+                    return Err(Error::UnsupportedJPEGFeature(
+                        "start of frame type",
+                        marker as u32,
+                    ));
+                }
+                _ => {
+                    self.read_segment()?;
+                }
+            }
+        }
+        let frame = self
+            .frame
+            .ok_or(Error::InvalidJPEGFile("frame header missing"))?;
+        Ok(Self::convert_frame_to_image(&frame))
+    }
+
+    fn define_quantization_tables(&mut self, mut content: &[u8]) -> crate::Result<()> {
+        while let Some((&precision_and_id, rest)) = content.split_first() {
+            let id = (precision_and_id & 0x0F) as usize;
+            let value_size = if precision_and_id >> 4 == 0 { 1 } else { 2 };
+            let values = rest
+                .get(..64 * value_size)
+                .ok_or(Error::InvalidJPEGFile("quantization table too short"))?;
+            let table = self
+                .quantization_tables
+                .get_mut(id)
+                .ok_or(Error::InvalidJPEGFile("quantization table id out of range"))?;
+            let mut natural_order = [0; 64];
+            for (zig_zag_index, value) in values.chunks_exact(value_size).enumerate() {
+                natural_order[consts::ZIG_ZAG_ORDER[zig_zag_index]] = match value {
+                    [byte] => *byte as u16,
+                    _ => u16::from_be_bytes([value[0], value[1]]),
+                };
+            }
+            *table = Some(natural_order);
+            content = &rest[64 * value_size..];
+        }
+        Ok(())
+    }
+
+    fn define_huffman_tables(&mut self, mut content: &[u8]) -> crate::Result<()> {
+        while let Some((&class_and_id, rest)) = content.split_first() {
+            let counts: [u8; 16] = rest
+                .get(..16)
+                .and_then(|counts| counts.try_into().ok())
+                .ok_or(Error::InvalidJPEGFile("huffman table too short"))?;
+            let number_of_symbols: usize = counts.iter().map(|&c| c as usize).sum();
+            let symbols = rest
+                .get(16..16 + number_of_symbols)
+                .ok_or(Error::InvalidJPEGFile("huffman table too short"))?;
+            let table = HuffmanDecoder::new(&counts, symbols)?;
+            let tables = match class_and_id >> 4 {
+                0 => &mut self.dc_tables,
+                1 => &mut self.ac_tables,
+                _ => return Err(Error::InvalidJPEGFile("unknown huffman table class")),
+            };
+            *tables
+                .get_mut((class_and_id & 0x0F) as usize)
+                .ok_or(Error::InvalidJPEGFile("huffman table id out of range"))? = Some(table);
+            content = &rest[16 + number_of_symbols..];
+        }
+        Ok(())
+    }
+
+    /// Reads the frame header and allocates the blocks of the components, once the declared
+    /// dimensions are within the limits.
+    fn read_frame_header(content: &[u8], limits: &InputLimits) -> crate::Result<Frame> {
+        let header = content
+            .get(..6)
+            .ok_or(Error::InvalidJPEGFile("frame header too short"))?;
+        let sample_precision = header[0];
+        if sample_precision != BASELINE_SAMPLE_PRECISION
+            && sample_precision != EXTENDED_SAMPLE_PRECISION
+        {
+            return Err(Error::UnsupportedJPEGFeature(
+                "sample precision",
+                header[0] as u32,
+            ));
+        }
+        let height = u16::from_be_bytes([header[1], header[2]]);
+        let width = u16::from_be_bytes([header[3], header[4]]);
+        limits.check_pixel_count(width, height)?;
+        let number_of_components = header[5] as usize;
+        if !matches!(number_of_components, 1 | 3 | 4) {
+            return Err(Error::UnsupportedJPEGFeature(
+                "number of components",
+                number_of_components as u32,
+            ));
+        }
+        let specifications = content
+            .get(6..6 + 3 * number_of_components)
+            .ok_or(Error::InvalidJPEGFile("frame header too short"))?;
+        let components: Vec<Component> = specifications
+            .chunks_exact(3)
+            .map(|specification| Component {
+                id: specification[0],
+                horizontal_factor: (specification[1] >> 4) as usize,
+                vertical_factor: (specification[1] & 0x0F) as usize,
+                quantization_table: specification[2] as usize,
+                blocks_per_line: 0,
+                blocks: Vec::new(),
+            })
+            .collect();
+        if components.iter().any(|c| {
+            !(1..=4).contains(&c.horizontal_factor)
+                || !(1..=4).contains(&c.vertical_factor)
+                || c.quantization_table > 3
+        }) {
+            return Err(Error::InvalidJPEGFile("invalid component specification"));
+        }
+        let mut frame = Frame {
+            sample_precision,
+            width,
+            height,
+            max_horizontal_factor: components
+                .iter()
+                .map(|c| c.horizontal_factor)
+                .max()
+                .unwrap(),
+            max_vertical_factor: components.iter().map(|c| c.vertical_factor).max().unwrap(),
+            components,
+        };
+        // A height of 0 is defined by the DNL segment after the first scan
+        if height > 0 {
+            frame.allocate_blocks();
+        }
+        Ok(frame)
+    }
+
+    /// Reads the height from the DNL segment, which follows the entropy coded data of the scan
+    /// at the current position.
+    fn read_number_of_lines(&self) -> crate::Result<u16> {
+        let scan_data = &self.data[self.position..];
+        // Within the entropy coded data 0xFF is followed by a stuffed zero or a restart marker
+        let marker_position = scan_data
+            .windows(2)
+            .position(|bytes| {
+                bytes[0] == consts::MARKER_PREFIX
+                    && !matches!(bytes[1], 0x00 | consts::MARKER_PREFIX)
+                    && !(consts::RST0..consts::RST0 + 8).contains(&bytes[1])
+            })
+            .ok_or(Error::InvalidJPEGFile("file is truncated"))?;
+        if scan_data[marker_position + 1] != consts::DNL {
+            return Err(Error::InvalidJPEGFile("number of lines segment missing"));
+        }
+        let height = scan_data
+            .get(marker_position + 4..marker_position + 6)
+            .map(|height| u16::from_be_bytes([height[0], height[1]]))
+            .ok_or(Error::InvalidJPEGFile("number of lines segment too short"))?;
+        if height == 0 {
+            return Err(Error::InvalidJPEGFile("number of lines is zero"));
+        }
+        Ok(height)
+    }
+
+    fn read_scan_header(&self, frame: &Frame, content: &[u8]) -> crate::Result<Vec<ScanComponent>> {
+        let number_of_components = *content
+            .first()
+            .ok_or(Error::InvalidJPEGFile("scan header too short"))?
+            as usize;
+        let specifications = content
+            .get(1..1 + 2 * number_of_components)
+            .ok_or(Error::InvalidJPEGFile("scan header too short"))?;
+        specifications
+            .chunks_exact(2)
+            .map(|specification| {
+                let index = frame
+                    .components
+                    .iter()
+                    .position(|c| c.id == specification[0])
+                    .ok_or(Error::InvalidJPEGFile("scan references unknown component"))?;
+                let dc_table = (specification[1] >> 4) as usize;
+                let ac_table = (specification[1] & 0x0F) as usize;
+                if dc_table > 3 || ac_table > 3 {
+                    return Err(Error::InvalidJPEGFile("huffman table id out of range"));
+                }
+                Ok(ScanComponent {
+                    index,
+                    dc_table,
+                    ac_table,
+                })
+            })
+            .collect()
+    }
+
+    fn decode_scan(&mut self, content: &[u8]) -> crate::Result<()> {
+        let mut frame = self
+            .frame
+            .take()
+            .ok_or(Error::InvalidJPEGFile("scan in front of the frame header"))?;
+        let scan_components = self.read_scan_header(&frame, content)?;
+        if frame.height == 0 {
+            frame.height = self.read_number_of_lines()?;
+            self.limits.check_pixel_count(frame.width, frame.height)?;
+            frame.allocate_blocks();
+        }
+        let mut reader = ScanReader::new(self.data, self.position);
+        let result = self.decode_scan_data(&mut frame, &scan_components, &mut reader);
+        self.frame = Some(frame);
+        result?;
+        self.position = reader.position();
+        Ok(())
+    }
+
+    fn decode_scan_data(
+        &self,
+        frame: &mut Frame,
+        scan_components: &[ScanComponent],
+        reader: &mut ScanReader,
+    ) -> crate::Result<()> {
+        // A scan of a single component is not interleaved. Each of its MCUs is a single block.
+        let (mcus_per_line, number_of_mcus) = match scan_components {
+            [scan_component] => {
+                let (blocks_per_line, blocks_per_column) =
+                    frame.component_blocks(&frame.components[scan_component.index]);
+                (blocks_per_line, blocks_per_line * blocks_per_column)
+            }
+            _ => (
+                frame.mcus_per_line(),
+                frame.mcus_per_line() * frame.mcus_per_column(),
+            ),
+        };
+        let mut predictions = vec![0; scan_components.len()];
+        for mcu in 0..number_of_mcus {
+            if self.restart_interval > 0 && mcu > 0 && mcu % self.restart_interval == 0 {
+                reader.read_restart_marker(mcu / self.restart_interval - 1)?;
+                predictions.fill(0);
+            }
+            let (mcu_x, mcu_y) = (mcu % mcus_per_line, mcu / mcus_per_line);
+            for (scan_component, prediction) in scan_components.iter().zip(predictions.iter_mut()) {
+                let component = &mut frame.components[scan_component.index];
+                let (horizontal_blocks, vertical_blocks) = if scan_components.len() == 1 {
+                    (1, 1)
+                } else {
+                    (component.horizontal_factor, component.vertical_factor)
+                };
+                for block_y in 0..vertical_blocks {
+                    for block_x in 0..horizontal_blocks {
+                        let x = mcu_x * horizontal_blocks + block_x;
+                        let y = mcu_y * vertical_blocks + block_y;
+                        let block = self.decode_block(reader, scan_component, prediction)?;
+                        let quantization_table = self.quantization_tables
+                            [component.quantization_table]
+                            .as_ref()
+                            .ok_or(Error::InvalidJPEGFile("quantization table missing"))?;
+                        let target = &mut component.blocks[y * component.blocks_per_line + x];
+                        for ((target, coefficient), quantization_value) in
+                            target.iter_mut().zip(block).zip(quantization_table)
+                        {
+                            *target = (coefficient * *quantization_value as i32) as f32;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Decodes the quantized coefficients of a block in natural order (F.2.2).
+    fn decode_block(
+        &self,
+        reader: &mut ScanReader,
+        scan_component: &ScanComponent,
+        prediction: &mut i32,
+    ) -> crate::Result<[i32; 64]> {
+        let dc_table = self.dc_tables[scan_component.dc_table]
+            .as_ref()
+            .ok_or(Error::InvalidJPEGFile("dc huffman table missing"))?;
+        let ac_table = self.ac_tables[scan_component.ac_table]
+            .as_ref()
+            .ok_or(Error::InvalidJPEGFile("ac huffman table missing"))?;
+        let mut block = [0; 64];
+        let category = reader.decode(dc_table)?;
+        *prediction += reader.receive_extend(category)?;
+        block[0] = *prediction;
+        let mut index = 1;
+        while index < 64 {
+            let symbol = reader.decode(ac_table)?;
+            let (run, category) = ((symbol >> 4) as usize, symbol & 0x0F);
+            if category == 0 {
+                if run != 15 {
+                    break;
+                }
+                index += 16;
+                continue;
+            }
+            index += run;
+            if index > 63 {
+                return Err(Error::InvalidJPEGFile("block exceeds 64 coefficients"));
+            }
+            block[consts::ZIG_ZAG_ORDER[index]] = reader.receive_extend(category)?;
+            index += 1;
+        }
+        Ok(block)
+    }
+
+    /// Transforms the blocks of each component back to level shifted samples, upsamples them
+    /// to the image resolution and converts the colors to RGB. Samples of extended precision are
+    /// scaled down to the range of baseline samples before the color conversion.
+    fn convert_frame_to_image(frame: &Frame) -> Image<f32> {
+        let precision_scale =
+            1.0 / (1 << (frame.sample_precision - BASELINE_SAMPLE_PRECISION)) as f32;
+        let planes: Vec<(usize, Vec<f32>)> = frame
+            .components
+            .iter()
+            .map(|component| {
+                let stride = component.blocks_per_line * 8;
+                let mut plane = vec![0.0; component.blocks.len() * 64];
+                for (block_index, block) in component.blocks.iter().enumerate() {
+                    let samples = inverse_cosine_transform(block);
+                    let block_x = block_index % component.blocks_per_line * 8;
+                    let block_y = block_index / component.blocks_per_line * 8;
+                    for (row, row_samples) in samples.chunks_exact(8).enumerate() {
+                        let start = (block_y + row) * stride + block_x;
+                        plane[start..start + 8].copy_from_slice(row_samples);
+                    }
+                }
+                (stride, plane)
+            })
+            .collect();
+        let sample = |component_index: usize, x: usize, y: usize| {
+            let component = &frame.components[component_index];
+            let (stride, plane) = &planes[component_index];
+            let x = x * component.horizontal_factor / frame.max_horizontal_factor;
+            let y = y * component.vertical_factor / frame.max_vertical_factor;
+            plane[y * stride + x] * precision_scale
+        };
+        let (width, height) = (frame.width as usize, frame.height as usize);
+        let dots = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let ycbcr = if frame.components.len() >= 3 {
+                    YCbCrColorFormat {
+                        luma: sample(0, x, y),
+                        chroma_blue: sample(1, x, y),
+                        chroma_red: sample(2, x, y),
+                    }
+                } else {
+                    YCbCrColorFormat {
+                        luma: sample(0, x, y),
+                        chroma_blue: 0.0,
+                        chroma_red: 0.0,
+                    }
+                };
+                if frame.components.len() < 4 {
+                    return RGBColorFormat::from(&ycbcr);
+                }
+                // YCCK holds the inks as red, green and blue and the black ink inverted
+                let [cyan, magenta, yellow] = RGBColorFormat::from(&ycbcr).samples();
+                let key = (sample(3, x, y) + 128.0) / 255.0;
+                RGBColorFormat::from(&CMYKColorFormat {
+                    cyan,
+                    magenta,
+                    yellow,
+                    black: 1.0 - key.clamp(0.0, 1.0),
+                })
+            })
+            .collect();
+        Image {
+            width: frame.width,
+            height: frame.height,
+            dots,
+        }
+    }
+}
+
+/// Inverse DCT of a block of coefficients in natural order (A.3.3) by the Arai algorithm.
+fn inverse_cosine_transform(block: &[f32; 64]) -> [f32; 64] {
+    let mut samples = *block;
+    InverseAraiDiscrete8x8CosineTransformer.transform(&mut samples);
+    samples
+}
+
+#[cfg(test)]
+mod test {
+    use threadpool::ThreadPool;
+
+    use crate::{
+        color::RGBColorFormat,
+        error::Error,
+        image::{
+            subsampling::ChromaSubsamplingPreset,
+            writer::jpeg::{JpegImageWriter, JpegTransformationOptions},
+            CMYKImage, Image, ImageReader, ImageWriter,
+        },
+    };
+
+    use super::{inverse_cosine_transform, JPEGImageReader};
+
+    fn encode(image: &Image<f32>, options: &JpegTransformationOptions) -> Vec<u8> {
+        let threadpool = ThreadPool::new(1);
+        let mut writer = JpegImageWriter::new(Vec::new(), image, options, &threadpool);
+        writer.write_image().unwrap();
+        writer.into_inner()
+    }
+
+    #[test]
+    fn inverse_transform_of_dc_only_block_is_flat() {
+        let mut block = [0.0; 64];
+        block[0] = 80.0;
+        let samples = inverse_cosine_transform(&block);
+        assert!(samples.iter().all(|sample| (sample - 10.0).abs() < 1e-4));
+    }
+
+    #[test]
+    fn decode_encoded_flat_image() {
+        // Whole MCUs of every preset, so no black padding bleeds into the chroma blocks
+        let samples: Vec<u8> = [200, 40, 90].repeat(32 * 16);
+        let image = Image::from_rgb8(32, 16, &samples).unwrap();
+        for &preset in ChromaSubsamplingPreset::variants() {
+            for restart_interval in [None, Some(1)] {
+                let options = JpegTransformationOptions {
+                    chroma_subsampling_preset: preset,
+                    restart_interval,
+                    ..Default::default()
+                };
+                let bytes = encode(&image, &options);
+                let decoded = JPEGImageReader::new(&bytes[..]).read_image().unwrap();
+                for (decoded, expected) in decoded.to_rgb8().iter().zip(&samples) {
+                    assert!(
+                        decoded.abs_diff(*expected) <= 3,
+                        "{} differs from {} with {:?}",
+                        decoded,
+                        expected,
+                        preset
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decode_blocks_in_place_for_all_presets() {
+        // Gray image with a different brightness for each of the 4x2 blocks
+        let (width, height) = (32, 16);
+        let samples: Vec<u8> = (0..width * height)
+            .map(|index| {
+                let block = (index / width / 8) * 4 + index % width / 8;
+                30 + 25 * block as u8
+            })
+            .flat_map(|value| [value; 3])
+            .collect();
+        let image = Image::from_rgb8(width as u16, height as u16, &samples).unwrap();
+        for &preset in ChromaSubsamplingPreset::variants() {
+            let options = JpegTransformationOptions {
+                chroma_subsampling_preset: preset,
+                ..Default::default()
+            };
+            let bytes = encode(&image, &options);
+            let decoded = JPEGImageReader::new(&bytes[..]).read_image().unwrap();
+            for (decoded, expected) in decoded.to_rgb8().iter().zip(&samples) {
+                assert!(
+                    decoded.abs_diff(*expected) <= 4,
+                    "{} differs from {} with {:?}",
+                    decoded,
+                    expected,
+                    preset
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn decode_height_of_number_of_lines_segment() {
+        let samples: Vec<u8> = [200, 40, 90].repeat(16 * 16);
+        let image = Image::from_rgb8(16, 16, &samples).unwrap();
+        let options = JpegTransformationOptions {
+            define_number_of_lines: true,
+            restart_interval: Some(1),
+            ..Default::default()
+        };
+        let bytes = encode(&image, &options);
+        let decoded = JPEGImageReader::new(&bytes[..]).read_image().unwrap();
+        assert_eq!((decoded.width, decoded.height), (16, 16));
+        for (decoded, expected) in decoded.to_rgb8().iter().zip(&samples) {
+            assert!(decoded.abs_diff(*expected) <= 3);
+        }
+    }
+
+    #[test]
+    fn decode_encoded_twelve_bit_image() {
+        let samples: Vec<u8> = [200, 40, 90].repeat(16 * 16);
+        let image = Image::from_rgb8(16, 16, &samples).unwrap();
+        let options = JpegTransformationOptions {
+            bits_per_channel: 12,
+            ..Default::default()
+        };
+        let bytes = encode(&image, &options);
+        let decoded = JPEGImageReader::new(&bytes[..]).read_image().unwrap();
+        for (decoded, expected) in decoded.to_rgb8().iter().zip(&samples) {
+            assert!(decoded.abs_diff(*expected) <= 1);
+        }
+    }
+
+    #[test]
+    fn decode_encoded_cmyk_image() {
+        // Different inks in each of the 4x2 blocks, the black ink in the lower row only
+        let (width, height) = (32, 16);
+        let samples: Vec<u8> = (0..width * height)
+            .flat_map(|index| {
+                let block = (index / width / 8) * 4 + index % width / 8;
+                let ink = 20 + 20 * block as u8;
+                [ink, 200 - ink, 90, if block < 4 { 0 } else { ink }]
+            })
+            .collect();
+        let image = CMYKImage::from_cmyk8(width as u16, height as u16, &samples).unwrap();
+        let expected: Vec<u8> = image
+            .dots
+            .iter()
+            .flat_map(|dot| RGBColorFormat::from(dot).to_rgb8())
+            .collect();
+        let threadpool = ThreadPool::new(2);
+        for &preset in ChromaSubsamplingPreset::variants() {
+            for restart_interval in [None, Some(1)] {
+                let options = JpegTransformationOptions {
+                    chroma_subsampling_preset: preset,
+                    restart_interval,
+                    quality: Some(95),
+                    ..Default::default()
+                };
+                let mut writer =
+                    JpegImageWriter::from_cmyk_image(Vec::new(), &image, &options, &threadpool);
+                writer.write_image().unwrap();
+                let bytes = writer.into_inner();
+                let decoded = JPEGImageReader::new(&bytes[..]).read_image().unwrap();
+                for (decoded, expected) in decoded.to_rgb8().iter().zip(&expected) {
+                    assert!(
+                        decoded.abs_diff(*expected) <= 4,
+                        "{} differs from {} with {:?}",
+                        decoded,
+                        expected,
+                        preset
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn crop_padding_of_partial_blocks() {
+        let image = Image::from_rgb8(13, 11, &[128; 13 * 11 * 3]).unwrap();
+        let bytes = encode(&image, &JpegTransformationOptions::default());
+        let decoded = JPEGImageReader::new(&bytes[..]).read_image().unwrap();
+        assert_eq!(decoded.width, 13);
+        assert_eq!(decoded.height, 11);
+        assert_eq!(decoded.dots.len(), 13 * 11);
+    }
+
+    #[test]
+    fn reject_progressive_frames() {
+        let bytes = [0xFF, 0xD8, 0xFF, 0xC2, 0x00, 0x02];
+        let result = JPEGImageReader::new(&bytes[..]).read_image();
+        assert!(matches!(
+            result,
+            Err(Error::UnsupportedJPEGFeature(_, 0xC2))
+        ));
+    }
+
+    #[test]
+    fn reject_truncated_file() {
+        let bytes = [0xFF, 0xD8, 0xFF, 0xDB, 0x00, 0x43, 0x00];
+        let result = JPEGImageReader::new(&bytes[..]).read_image();
+        assert!(matches!(result, Err(Error::InvalidJPEGFile(_))));
+    }
+}
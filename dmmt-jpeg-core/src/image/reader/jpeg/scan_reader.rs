@@ -0,0 +1,131 @@
+use crate::huffman::decoder::HuffmanDecoder;
+use crate::image::writer::jpeg::consts;
+use crate::Error;
+
+/// Reads the bits of the entropy coded data of a scan. Stuffed zero bytes are skipped, restart
+/// markers are only consumed on request.
+pub struct ScanReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    current_byte: u8,
+    remaining_bits: u8,
+}
+
+impl<'a> ScanReader<'a> {
+    pub fn new(data: &'a [u8], position: usize) -> Self {
+        Self {
+            data,
+            position,
+            current_byte: 0,
+            remaining_bits: 0,
+        }
+    }
+
+    /// Position of the first byte behind the bits read so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    fn read_byte(&mut self) -> crate::Result<u8> {
+        let byte = *self
+            .data
+            .get(self.position)
+            .ok_or(Error::InvalidJPEGFile("entropy coded data is truncated"))?;
+        if byte == consts::MARKER_PREFIX {
+            match self.data.get(self.position + 1) {
+                Some(0x00) => self.position += 1,
+                _ => {
+                    return Err(Error::InvalidJPEGFile(
+                        "unexpected marker in entropy coded data",
+                    ))
+                }
+            }
+        }
+        self.position += 1;
+        Ok(byte)
+    }
+
+    pub fn read_bit(&mut self) -> crate::Result<u16> {
+        if self.remaining_bits == 0 {
+            self.current_byte = self.read_byte()?;
+            self.remaining_bits = 8;
+        }
+        self.remaining_bits -= 1;
+        Ok(((self.current_byte >> self.remaining_bits) & 1) as u16)
+    }
+
+    pub fn read_bits(&mut self, count: u8) -> crate::Result<u16> {
+        (0..count).try_fold(0, |value, _| Ok(value << 1 | self.read_bit()?))
+    }
+
+    pub fn decode(&mut self, table: &HuffmanDecoder) -> crate::Result<u8> {
+        table.decode(|| self.read_bit())
+    }
+
+    /// Reads the additional bits of a coefficient of the category and extends them to the
+    /// signed value (F.2.2.1).
+    pub fn receive_extend(&mut self, category: u8) -> crate::Result<i32> {
+        if category == 0 {
+            return Ok(0);
+        }
+        if category > 15 {
+            return Err(Error::InvalidJPEGFile("coefficient category out of range"));
+        }
+        let value = self.read_bits(category)? as i32;
+        if value < 1 << (category - 1) {
+            Ok(value - (1 << category) + 1)
+        } else {
+            Ok(value)
+        }
+    }
+
+    /// Drops the padding bits of the current byte and consumes the expected restart marker.
+    pub fn read_restart_marker(&mut self, index: usize) -> crate::Result<()> {
+        self.remaining_bits = 0;
+        let expected = consts::marker(consts::RST0 + (index % 8) as u8);
+        if self.data.get(self.position..self.position + 2) != Some(&expected[..]) {
+            return Err(Error::InvalidJPEGFile("restart marker expected"));
+        }
+        self.position += 2;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ScanReader;
+    use crate::error::Error;
+
+    #[test]
+    fn skip_stuffed_zero_bytes() {
+        let data = [0xFF, 0x00, 0x80];
+        let mut reader = ScanReader::new(&data, 0);
+        assert_eq!(reader.read_bits(8).unwrap(), 0xFF);
+        assert_eq!(reader.read_bit().unwrap(), 1);
+        assert_eq!(reader.position(), 3);
+    }
+
+    #[test]
+    fn extend_negative_values() {
+        let data = [0b0111_0000];
+        let mut reader = ScanReader::new(&data, 0);
+        assert_eq!(reader.receive_extend(2).unwrap(), -2);
+        assert_eq!(reader.receive_extend(3).unwrap(), 6);
+    }
+
+    #[test]
+    fn consume_restart_marker() {
+        let data = [0b1011_1111, 0xFF, 0xD1, 0x80];
+        let mut reader = ScanReader::new(&data, 0);
+        reader.read_bits(2).unwrap();
+        reader.read_restart_marker(1).unwrap();
+        assert_eq!(reader.read_bit().unwrap(), 1);
+    }
+
+    #[test]
+    fn fail_on_marker_in_data() {
+        let data = [0xFF, 0xD9];
+        let mut reader = ScanReader::new(&data, 0);
+        assert!(matches!(reader.read_bit(), Err(Error::InvalidJPEGFile(_))));
+    }
+}
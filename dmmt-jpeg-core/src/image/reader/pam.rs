@@ -0,0 +1,412 @@
+use std::io::Read;
+use std::str;
+
+use super::super::ImageReader;
+use super::super::{CMYKImage, Image, RGBAImage};
+use super::InputLimits;
+use crate::color::{CMYKColorFormat, RGBColorFormat, RangeColorFormat};
+use crate::Error;
+
+const P7_HEADER_TOKEN_NAME: &str = "P7 Header";
+const WIDTH_HEADER_TOKEN_NAME: &str = "WIDTH";
+const HEIGHT_HEADER_TOKEN_NAME: &str = "HEIGHT";
+const DEPTH_HEADER_TOKEN_NAME: &str = "DEPTH";
+const MAX_VALUE_HEADER_TOKEN_NAME: &str = "MAXVAL";
+const TUPLE_TYPE_HEADER_TOKEN_NAME: &str = "TUPLTYPE";
+const END_OF_HEADER_TOKEN_NAME: &str = "ENDHDR";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TupleType {
+    Rgb,
+    Grayscale,
+    Cmyk,
+    RgbAlpha,
+    GrayscaleAlpha,
+}
+
+impl TupleType {
+    fn depth(&self) -> u8 {
+        match self {
+            Self::Rgb => 3,
+            Self::Grayscale => 1,
+            Self::Cmyk => 4,
+            Self::RgbAlpha => 4,
+            Self::GrayscaleAlpha => 2,
+        }
+    }
+
+    fn has_alpha(&self) -> bool {
+        matches!(self, Self::RgbAlpha | Self::GrayscaleAlpha)
+    }
+}
+
+struct PAMHeader {
+    width: u16,
+    height: u16,
+    depth: u8,
+    max_value: u16,
+    tuple_type: TupleType,
+}
+
+impl PAMHeader {
+    fn bytes_per_sample(&self) -> usize {
+        if self.max_value > u8::MAX as u16 {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+pub struct PAMImageReader<T: Read> {
+    reader: T,
+    limits: InputLimits,
+}
+
+impl<T: Read> PAMImageReader<T> {
+    pub fn new(reader: T) -> Self {
+        Self::with_limits(reader, InputLimits::default())
+    }
+
+    pub fn with_limits(reader: T, limits: InputLimits) -> Self {
+        Self { reader, limits }
+    }
+
+    /// Reads a header line. Comment lines are truncated to the maximum token length, other
+    /// lines must not exceed it.
+    fn read_line(&mut self) -> crate::Result<Option<String>> {
+        let mut buffer = Vec::new();
+        let mut byte = [0; 1];
+        loop {
+            let n = self
+                .reader
+                .read(&mut byte)
+                .map_err(|_| Error::PAMFileDoesNotContainRequiredToken(END_OF_HEADER_TOKEN_NAME))?;
+            if n == 0 {
+                if buffer.is_empty() {
+                    return Ok(None);
+                }
+                break;
+            }
+            if byte[0] == b'\n' {
+                break;
+            }
+            if buffer.len() == self.limits.max_token_length {
+                if buffer.first() == Some(&b'#') {
+                    continue;
+                }
+                return Err(Error::TokenExceedsMaximumLength(
+                    self.limits.max_token_length,
+                    None,
+                ));
+            }
+            buffer.push(byte[0]);
+        }
+        let line = str::from_utf8(&buffer)
+            .map_err(|_| Error::ParsingOfTokenFailed(END_OF_HEADER_TOKEN_NAME, None))?
+            .trim()
+            .to_string();
+        Ok(Some(line))
+    }
+
+    fn read_header(&mut self) -> crate::Result<PAMHeader> {
+        let magic = self
+            .read_line()?
+            .ok_or(Error::PAMFileDoesNotContainRequiredToken(
+                P7_HEADER_TOKEN_NAME,
+            ))?;
+        if magic != "P7" {
+            return Err(Error::PAMFileDoesNotContainRequiredToken(
+                P7_HEADER_TOKEN_NAME,
+            ));
+        }
+        let mut width = None;
+        let mut height = None;
+        let mut depth = None;
+        let mut max_value = None;
+        let mut tuple_type = None;
+        loop {
+            let line = self
+                .read_line()?
+                .ok_or(Error::PAMFileDoesNotContainRequiredToken(
+                    END_OF_HEADER_TOKEN_NAME,
+                ))?;
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once(char::is_whitespace).unwrap_or((&line, ""));
+            let value = value.trim();
+            match key {
+                WIDTH_HEADER_TOKEN_NAME => {
+                    width = Some(Self::parse_value(value, WIDTH_HEADER_TOKEN_NAME)?)
+                }
+                HEIGHT_HEADER_TOKEN_NAME => {
+                    height = Some(Self::parse_value(value, HEIGHT_HEADER_TOKEN_NAME)?)
+                }
+                DEPTH_HEADER_TOKEN_NAME => {
+                    depth = Some(Self::parse_value(value, DEPTH_HEADER_TOKEN_NAME)?)
+                }
+                MAX_VALUE_HEADER_TOKEN_NAME => {
+                    max_value = Some(Self::parse_value(value, MAX_VALUE_HEADER_TOKEN_NAME)?)
+                }
+                TUPLE_TYPE_HEADER_TOKEN_NAME => tuple_type = Some(Self::parse_tuple_type(value)?),
+                END_OF_HEADER_TOKEN_NAME => break,
+                _ => continue,
+            }
+        }
+        let header = PAMHeader {
+            width: width.ok_or(Error::PAMFileDoesNotContainRequiredToken(
+                WIDTH_HEADER_TOKEN_NAME,
+            ))?,
+            height: height.ok_or(Error::PAMFileDoesNotContainRequiredToken(
+                HEIGHT_HEADER_TOKEN_NAME,
+            ))?,
+            depth: depth.ok_or(Error::PAMFileDoesNotContainRequiredToken(
+                DEPTH_HEADER_TOKEN_NAME,
+            ))?,
+            max_value: max_value.ok_or(Error::PAMFileDoesNotContainRequiredToken(
+                MAX_VALUE_HEADER_TOKEN_NAME,
+            ))?,
+            tuple_type: tuple_type.ok_or(Error::PAMFileDoesNotContainRequiredToken(
+                TUPLE_TYPE_HEADER_TOKEN_NAME,
+            ))?,
+        };
+        if header.depth != header.tuple_type.depth() {
+            return Err(Error::PAMDepthDoesNotMatchTupleType(header.depth));
+        }
+        self.limits.check_pixel_count(header.width, header.height)?;
+        Ok(header)
+    }
+
+    fn parse_value<V: str::FromStr>(value: &str, token_name: &'static str) -> crate::Result<V> {
+        value
+            .parse()
+            .map_err(|_| Error::ParsingOfTokenFailed(token_name, None))
+    }
+
+    fn parse_tuple_type(value: &str) -> crate::Result<TupleType> {
+        match value {
+            "RGB" => Ok(TupleType::Rgb),
+            "GRAYSCALE" => Ok(TupleType::Grayscale),
+            "CMYK" => Ok(TupleType::Cmyk),
+            "RGB_ALPHA" => Ok(TupleType::RgbAlpha),
+            "GRAYSCALE_ALPHA" => Ok(TupleType::GrayscaleAlpha),
+            _ => Err(Error::UnsupportedPAMTupleType(value.to_string())),
+        }
+    }
+
+    fn read_samples(&mut self, header: &PAMHeader) -> crate::Result<Vec<u16>> {
+        let number_of_samples =
+            header.width as usize * header.height as usize * header.depth as usize;
+        let bytes_per_sample = header.bytes_per_sample();
+        let mut buffer = vec![0; number_of_samples * bytes_per_sample];
+        self.reader
+            .read_exact(&mut buffer)
+            .map_err(|_| Error::MismatchOfSizeBetweenHeaderAndValues)?;
+        let samples: Vec<u16> = if bytes_per_sample == 2 {
+            buffer
+                .chunks_exact(2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                .collect()
+        } else {
+            buffer.into_iter().map(u16::from).collect()
+        };
+        if samples.iter().any(|&s| s > header.max_value) {
+            return Err(Error::ParsingOfTokenFailed(
+                MAX_VALUE_HEADER_TOKEN_NAME,
+                None,
+            ));
+        }
+        Ok(samples)
+    }
+
+    fn convert_samples_to_dots(
+        header: &PAMHeader,
+        samples: &[u16],
+    ) -> crate::Result<Vec<RGBColorFormat<f32>>> {
+        let max_value = header.max_value;
+        let dots = samples
+            .chunks_exact(header.depth as usize)
+            .map(|tuple| match header.tuple_type {
+                TupleType::Rgb | TupleType::RgbAlpha => {
+                    RangeColorFormat::new(max_value, tuple[0], tuple[1], tuple[2])
+                }
+                TupleType::Grayscale | TupleType::GrayscaleAlpha => {
+                    RangeColorFormat::new(max_value, tuple[0], tuple[0], tuple[0])
+                }
+                TupleType::Cmyk => unreachable!("CMYK images are rejected before"),
+            })
+            .map(RGBColorFormat::from)
+            .collect();
+        Ok(dots)
+    }
+
+    /// The alpha is the last sample of each tuple, where 0 is fully transparent.
+    fn convert_samples_to_alpha(header: &PAMHeader, samples: &[u16]) -> Option<Vec<f32>> {
+        if !header.tuple_type.has_alpha() {
+            return None;
+        }
+        let alpha = samples
+            .chunks_exact(header.depth as usize)
+            .map(|tuple| tuple[tuple.len() - 1] as f32 / header.max_value as f32)
+            .collect();
+        Some(alpha)
+    }
+
+    /// Reads an image of the tuple types RGB and GRAYSCALE with or without alpha, which is kept
+    /// straight, so it can be composited over any background.
+    pub fn read_rgba_image(&mut self) -> crate::Result<RGBAImage> {
+        let header = self.read_header()?;
+        if header.tuple_type == TupleType::Cmyk {
+            return Err(Error::CMYKImageNotExpected);
+        }
+        let samples = self.read_samples(&header)?;
+        let dots = Self::convert_samples_to_dots(&header, &samples)?;
+        let alpha = Self::convert_samples_to_alpha(&header, &samples);
+        Ok(RGBAImage {
+            width: header.width,
+            height: header.height,
+            dots,
+            alpha,
+        })
+    }
+
+    /// Reads an image of the tuple type CMYK, whose samples are the amount of ink.
+    pub fn read_cmyk_image(&mut self) -> crate::Result<CMYKImage> {
+        let header = self.read_header()?;
+        if header.tuple_type != TupleType::Cmyk {
+            return Err(Error::CMYKImageExpected);
+        }
+        let samples = self.read_samples(&header)?;
+        let dots = samples
+            .chunks_exact(4)
+            .map(|tuple| {
+                CMYKColorFormat::from_samples(
+                    header.max_value,
+                    [tuple[0], tuple[1], tuple[2], tuple[3]],
+                )
+            })
+            .collect();
+        Ok(CMYKImage {
+            width: header.width,
+            height: header.height,
+            dots,
+        })
+    }
+}
+
+/// Images with alpha are composited over white.
+impl<T: Read> ImageReader<f32> for PAMImageReader<T> {
+    fn read_image(&mut self) -> crate::Result<Image<f32>> {
+        Ok(self.read_rgba_image()?.composite(&RGBColorFormat::white()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::RGBColorFormat,
+        error::Error,
+        image::{Image, ImageReader},
+        Result,
+    };
+
+    use super::PAMImageReader;
+
+    fn read_pam(bytes: &[u8]) -> Result<Image<f32>> {
+        let mut reader = PAMImageReader::new(bytes);
+        reader.read_image()
+    }
+
+    #[test]
+    fn read_rgb() {
+        let mut bytes =
+            b"P7\nWIDTH 2\nHEIGHT 1\nDEPTH 3\nMAXVAL 255\nTUPLTYPE RGB\nENDHDR\n".to_vec();
+        bytes.extend([255, 0, 0, 0, 0, 0]);
+        let image = read_pam(&bytes).unwrap();
+        assert_eq!(image.width, 2);
+        assert_eq!(image.height, 1);
+        assert_eq!(image.dots[0], RGBColorFormat::red());
+        assert_eq!(image.dots[1], RGBColorFormat::default());
+    }
+
+    #[test]
+    fn read_grayscale_with_comment_and_16_bit_samples() {
+        let mut bytes =
+            b"P7\n# comment\nWIDTH 1\nHEIGHT 1\nDEPTH 1\nMAXVAL 65535\nTUPLTYPE GRAYSCALE\nENDHDR\n"
+                .to_vec();
+        bytes.extend([0xFF, 0xFF]);
+        let image = read_pam(&bytes).unwrap();
+        let white = RGBColorFormat::default().inverted();
+        assert_eq!(image.dots[0], white);
+    }
+
+    #[test]
+    fn unsupported_tuple_type() {
+        let bytes = b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 1\nMAXVAL 1\nTUPLTYPE BLACKANDWHITE\nENDHDR\n\0";
+        let result = read_pam(bytes);
+        assert!(matches!(result, Err(Error::UnsupportedPAMTupleType(_))));
+    }
+
+    #[test]
+    fn read_rgb_alpha() {
+        let mut bytes =
+            b"P7\nWIDTH 2\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n".to_vec();
+        bytes.extend([255, 0, 0, 255, 255, 0, 0, 0]);
+        let image = PAMImageReader::new(bytes.as_slice())
+            .read_rgba_image()
+            .unwrap();
+        assert!(image.has_alpha());
+        let image = image.composite(&RGBColorFormat::default());
+        assert_eq!(image.dots[0], RGBColorFormat::red());
+        assert_eq!(image.dots[1], RGBColorFormat::default());
+        let image = read_pam(&bytes).unwrap();
+        assert_eq!(image.dots[1], RGBColorFormat::white());
+    }
+
+    #[test]
+    fn read_grayscale_alpha() {
+        let bytes =
+            b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 2\nMAXVAL 255\nTUPLTYPE GRAYSCALE_ALPHA\nENDHDR\n\0\x80";
+        let image = read_pam(bytes).unwrap();
+        let expected = 1.0 - 128.0 / 255.0;
+        assert!(image.dots[0]
+            .samples()
+            .iter()
+            .all(|sample| (sample - expected).abs() < 1e-6));
+    }
+
+    #[test]
+    fn read_cmyk() {
+        let mut bytes =
+            b"P7\nWIDTH 2\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE CMYK\nENDHDR\n".to_vec();
+        bytes.extend([255, 0, 0, 0, 0, 0, 0, 255]);
+        let image = PAMImageReader::new(bytes.as_slice())
+            .read_cmyk_image()
+            .unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.dots[0].cyan, 1.0);
+        assert_eq!(image.dots[1].black, 1.0);
+        assert!(matches!(read_pam(&bytes), Err(Error::CMYKImageNotExpected)));
+    }
+
+    #[test]
+    fn depth_mismatch() {
+        let bytes = b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 1\nMAXVAL 255\nTUPLTYPE RGB\nENDHDR\n\0";
+        let result = read_pam(bytes);
+        assert!(matches!(
+            result,
+            Err(Error::PAMDepthDoesNotMatchTupleType(1))
+        ));
+    }
+
+    #[test]
+    fn too_few_samples() {
+        let bytes = b"P7\nWIDTH 2\nHEIGHT 1\nDEPTH 3\nMAXVAL 255\nTUPLTYPE RGB\nENDHDR\n\0\0\0";
+        let result = read_pam(bytes);
+        assert!(matches!(
+            result,
+            Err(Error::MismatchOfSizeBetweenHeaderAndValues)
+        ));
+    }
+}
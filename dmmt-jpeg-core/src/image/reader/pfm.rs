@@ -0,0 +1,216 @@
+use std::io::Read;
+use std::str;
+
+use super::super::Image;
+use super::super::ImageReader;
+use super::InputLimits;
+use crate::color::RGBColorFormat;
+use crate::Error;
+
+const PF_HEADER_TOKEN_NAME: &str = "PF Header";
+const WIDTH_HEADER_TOKEN_NAME: &str = "Width";
+const HEIGHT_HEADER_TOKEN_NAME: &str = "Height";
+const SCALE_HEADER_TOKEN_NAME: &str = "Scale";
+
+struct PFMHeader {
+    width: u16,
+    height: u16,
+    channels: usize,
+    little_endian: bool,
+}
+
+/// Reads portable float maps. The float samples are taken over directly without quantization
+/// and clamped to the range of 0.0 to 1.0, so HDR content must be tone mapped beforehand.
+pub struct PFMImageReader<T: Read> {
+    reader: T,
+    limits: InputLimits,
+}
+
+impl<T: Read> PFMImageReader<T> {
+    pub fn new(reader: T) -> Self {
+        Self::with_limits(reader, InputLimits::default())
+    }
+
+    pub fn with_limits(reader: T, limits: InputLimits) -> Self {
+        Self { reader, limits }
+    }
+
+    /// Reads the next whitespace separated token. Exactly one whitespace character behind the
+    /// token is consumed, which is the single separator in front of the raster.
+    fn read_token(&mut self, token_name: &'static str) -> crate::Result<String> {
+        let mut buffer = Vec::new();
+        let mut byte = [0; 1];
+        loop {
+            self.reader
+                .read_exact(&mut byte)
+                .map_err(|_| Error::PFMFileDoesNotContainRequiredToken(token_name))?;
+            if !byte[0].is_ascii_whitespace() {
+                if buffer.len() == self.limits.max_token_length {
+                    return Err(Error::TokenExceedsMaximumLength(
+                        self.limits.max_token_length,
+                        None,
+                    ));
+                }
+                buffer.push(byte[0]);
+            } else if !buffer.is_empty() {
+                break;
+            }
+        }
+        str::from_utf8(&buffer)
+            .map(str::to_string)
+            .map_err(|_| Error::ParsingOfTokenFailed(token_name, None))
+    }
+
+    fn read_value<V: str::FromStr>(&mut self, token_name: &'static str) -> crate::Result<V> {
+        self.read_token(token_name)?
+            .parse()
+            .map_err(|_| Error::ParsingOfTokenFailed(token_name, None))
+    }
+
+    fn read_header(&mut self) -> crate::Result<PFMHeader> {
+        let channels = match self.read_token(PF_HEADER_TOKEN_NAME)?.as_str() {
+            "PF" => 3,
+            "Pf" => 1,
+            _ => {
+                return Err(Error::PFMFileDoesNotContainRequiredToken(
+                    PF_HEADER_TOKEN_NAME,
+                ))
+            }
+        };
+        let width = self.read_value(WIDTH_HEADER_TOKEN_NAME)?;
+        let height = self.read_value(HEIGHT_HEADER_TOKEN_NAME)?;
+        self.limits.check_pixel_count(width, height)?;
+        let scale: f32 = self.read_value(SCALE_HEADER_TOKEN_NAME)?;
+        if scale == 0.0 || !scale.is_finite() {
+            return Err(Error::ParsingOfTokenFailed(SCALE_HEADER_TOKEN_NAME, None));
+        }
+        Ok(PFMHeader {
+            width,
+            height,
+            channels,
+            little_endian: scale < 0.0,
+        })
+    }
+
+    fn read_samples(&mut self, header: &PFMHeader) -> crate::Result<Vec<f32>> {
+        let number_of_samples = header.width as usize * header.height as usize * header.channels;
+        let mut buffer = vec![0; number_of_samples * 4];
+        self.reader
+            .read_exact(&mut buffer)
+            .map_err(|_| Error::MismatchOfSizeBetweenHeaderAndValues)?;
+        let samples = buffer
+            .chunks_exact(4)
+            .map(|b| {
+                let bytes = [b[0], b[1], b[2], b[3]];
+                if header.little_endian {
+                    f32::from_le_bytes(bytes)
+                } else {
+                    f32::from_be_bytes(bytes)
+                }
+            })
+            .collect();
+        Ok(samples)
+    }
+
+    /// The raster of a PFM file starts with the bottom row, so the rows are reversed.
+    fn convert_samples_to_dots(header: &PFMHeader, samples: &[f32]) -> Vec<RGBColorFormat<f32>> {
+        let row_length = (header.width as usize * header.channels).max(1);
+        samples
+            .rchunks_exact(row_length)
+            .flat_map(|row| row.chunks_exact(header.channels))
+            .map(|tuple| match header.channels {
+                3 => RGBColorFormat::clamped(tuple[0], tuple[1], tuple[2]),
+                _ => RGBColorFormat::clamped(tuple[0], tuple[0], tuple[0]),
+            })
+            .collect()
+    }
+}
+
+impl<T: Read> ImageReader<f32> for PFMImageReader<T> {
+    fn read_image(&mut self) -> crate::Result<Image<f32>> {
+        let header = self.read_header()?;
+        let samples = self.read_samples(&header)?;
+        let dots = Self::convert_samples_to_dots(&header, &samples);
+        Ok(Image {
+            width: header.width,
+            height: header.height,
+            dots,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::RGBColorFormat,
+        error::Error,
+        image::{Image, ImageReader},
+        Result,
+    };
+
+    use super::PFMImageReader;
+
+    fn read_pfm(bytes: &[u8]) -> Result<Image<f32>> {
+        let mut reader = PFMImageReader::new(bytes);
+        reader.read_image()
+    }
+
+    #[test]
+    fn read_little_endian_rgb_bottom_to_top() {
+        let mut bytes = b"PF\n1 2\n-1.0\n".to_vec();
+        for sample in [0.0_f32, 0.0, 0.0, 1.0, 0.0, 0.0] {
+            bytes.extend(sample.to_le_bytes());
+        }
+        let image = read_pfm(&bytes).unwrap();
+        assert_eq!(image.width, 1);
+        assert_eq!(image.height, 2);
+        assert_eq!(image.dots[0], RGBColorFormat::red());
+        assert_eq!(image.dots[1], RGBColorFormat::default());
+    }
+
+    #[test]
+    fn read_big_endian_grayscale_and_clamp_samples() {
+        let mut bytes = b"Pf 2 1 1.0\n".to_vec();
+        for sample in [4.5_f32, -0.25] {
+            bytes.extend(sample.to_be_bytes());
+        }
+        let image = read_pfm(&bytes).unwrap();
+        assert_eq!(image.dots[0], RGBColorFormat::default().inverted());
+        assert_eq!(image.dots[1], RGBColorFormat::default());
+    }
+
+    #[test]
+    fn read_samples_without_quantization() {
+        let mut bytes = b"Pf\n1 1\n-1.0\n".to_vec();
+        bytes.extend(0.123_456_7_f32.to_le_bytes());
+        let image = read_pfm(&bytes).unwrap();
+        assert_eq!(
+            image.dots[0],
+            RGBColorFormat::clamped(0.123_456_7, 0.123_456_7, 0.123_456_7)
+        );
+    }
+
+    #[test]
+    fn invalid_magic() {
+        let result = read_pfm(b"P6\n1 1\n255\n\0\0\0");
+        assert!(matches!(
+            result,
+            Err(Error::PFMFileDoesNotContainRequiredToken(_))
+        ));
+    }
+
+    #[test]
+    fn zero_scale() {
+        let result = read_pfm(b"PF\n1 1\n0.0\n");
+        assert!(matches!(result, Err(Error::ParsingOfTokenFailed(_, None))));
+    }
+
+    #[test]
+    fn too_few_samples() {
+        let result = read_pfm(b"PF\n1 1\n-1.0\n\0\0\0\0");
+        assert!(matches!(
+            result,
+            Err(Error::MismatchOfSizeBetweenHeaderAndValues)
+        ));
+    }
+}
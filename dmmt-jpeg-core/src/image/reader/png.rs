@@ -0,0 +1,187 @@
+use std::io::Read;
+
+use png::{BitDepth, ColorType, Decoder, Transformations};
+
+use super::super::ImageReader;
+use super::super::{Image, RGBAImage};
+use super::InputLimits;
+use crate::color::{RGBColorFormat, RangeColorFormat};
+use crate::Error;
+
+/// Reads PNG images of any color type. Palette and low bit depth images are expanded to 8 bit
+/// samples, an alpha channel is kept by `read_rgba_image` and composited over white by
+/// `read_image`.
+pub struct PNGImageReader<T: Read> {
+    reader: Option<T>,
+    limits: InputLimits,
+}
+
+impl<T: Read> PNGImageReader<T> {
+    pub fn new(reader: T) -> Self {
+        Self::with_limits(reader, InputLimits::default())
+    }
+
+    pub fn with_limits(reader: T, limits: InputLimits) -> Self {
+        Self {
+            reader: Some(reader),
+            limits,
+        }
+    }
+
+    fn read_samples(sample_bytes: &[u8], bit_depth: BitDepth) -> Vec<u16> {
+        match bit_depth {
+            BitDepth::Sixteen => sample_bytes
+                .chunks_exact(2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                .collect(),
+            _ => sample_bytes.iter().copied().map(u16::from).collect(),
+        }
+    }
+
+    fn convert_samples_to_dots(
+        samples: &[u16],
+        color_type: ColorType,
+        max_value: u16,
+    ) -> Vec<RGBColorFormat<f32>> {
+        let samples_per_pixel = color_type.samples();
+        samples
+            .chunks_exact(samples_per_pixel)
+            .map(|pixel| match color_type {
+                ColorType::Grayscale | ColorType::GrayscaleAlpha => {
+                    RangeColorFormat::new(max_value, pixel[0], pixel[0], pixel[0])
+                }
+                _ => RangeColorFormat::new(max_value, pixel[0], pixel[1], pixel[2]),
+            })
+            .map(RGBColorFormat::from)
+            .collect()
+    }
+
+    /// The alpha is the last sample of each pixel, where 0 is fully transparent.
+    fn convert_samples_to_alpha(
+        samples: &[u16],
+        color_type: ColorType,
+        max_value: u16,
+    ) -> Option<Vec<f32>> {
+        if !matches!(color_type, ColorType::GrayscaleAlpha | ColorType::Rgba) {
+            return None;
+        }
+        let alpha = samples
+            .chunks_exact(color_type.samples())
+            .map(|pixel| pixel[pixel.len() - 1] as f32 / max_value as f32)
+            .collect();
+        Some(alpha)
+    }
+
+    /// Reads the image with its alpha channel, which includes the transparency of palette
+    /// images.
+    pub fn read_rgba_image(&mut self) -> crate::Result<RGBAImage> {
+        let reader = self
+            .reader
+            .take()
+            .expect("PNG image reader can only read a single image");
+        let mut decoder = Decoder::new(reader);
+        decoder.set_transformations(Transformations::EXPAND);
+        let mut png_reader = decoder
+            .read_info()
+            .map_err(|e| Error::UnableToDecodePNGImage(e.to_string()))?;
+        // the dimensions of the IHDR chunk are checked, before the samples are allocated
+        let (png_width, png_height) = (png_reader.info().width, png_reader.info().height);
+        let width = u16::try_from(png_width)
+            .map_err(|_| Error::ImageDimensionsNotSupported(png_width, png_height))?;
+        let height = u16::try_from(png_height)
+            .map_err(|_| Error::ImageDimensionsNotSupported(png_width, png_height))?;
+        self.limits.check_pixel_count(width, height)?;
+        let mut buffer = vec![0; png_reader.output_buffer_size()];
+        let frame_info = png_reader
+            .next_frame(&mut buffer)
+            .map_err(|e| Error::UnableToDecodePNGImage(e.to_string()))?;
+        let max_value = match frame_info.bit_depth {
+            BitDepth::Sixteen => u16::MAX,
+            _ => u8::MAX as u16,
+        };
+        let samples = Self::read_samples(&buffer[..frame_info.buffer_size()], frame_info.bit_depth);
+        let dots = Self::convert_samples_to_dots(&samples, frame_info.color_type, max_value);
+        let alpha = Self::convert_samples_to_alpha(&samples, frame_info.color_type, max_value);
+        Ok(RGBAImage {
+            width,
+            height,
+            dots,
+            alpha,
+        })
+    }
+}
+
+impl<T: Read> ImageReader<f32> for PNGImageReader<T> {
+    fn read_image(&mut self) -> crate::Result<Image<f32>> {
+        Ok(self.read_rgba_image()?.composite(&RGBColorFormat::white()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use png::{BitDepth, ColorType, Encoder};
+
+    use crate::{
+        color::RGBColorFormat,
+        error::Error,
+        image::{Image, ImageReader},
+    };
+
+    use super::{InputLimits, PNGImageReader};
+
+    fn encode_png(width: u32, height: u32, color_type: ColorType, data: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        let mut encoder = Encoder::new(&mut output, width, height);
+        encoder.set_color(color_type);
+        encoder.set_depth(BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(data).unwrap();
+        writer.finish().unwrap();
+        output
+    }
+
+    fn read_png(bytes: &[u8]) -> Image<f32> {
+        PNGImageReader::new(bytes).read_image().unwrap()
+    }
+
+    #[test]
+    fn read_rgb() {
+        let bytes = encode_png(2, 1, ColorType::Rgb, &[255, 0, 0, 0, 0, 0]);
+        let image = read_png(&bytes);
+        assert_eq!(image.width, 2);
+        assert_eq!(image.height, 1);
+        assert_eq!(image.dots[0], RGBColorFormat::red());
+        assert_eq!(image.dots[1], RGBColorFormat::default());
+    }
+
+    #[test]
+    fn read_grayscale_alpha() {
+        let bytes = encode_png(1, 1, ColorType::GrayscaleAlpha, &[255, 0]);
+        let image = read_png(&bytes);
+        assert_eq!(image.dots[0], RGBColorFormat::default().inverted());
+    }
+
+    #[test]
+    fn read_rgba() {
+        let bytes = encode_png(2, 1, ColorType::Rgba, &[255, 0, 0, 255, 255, 0, 0, 0]);
+        let image = PNGImageReader::new(bytes.as_slice())
+            .read_rgba_image()
+            .unwrap();
+        assert!(image.has_alpha());
+        let image = image.composite(&RGBColorFormat::default());
+        assert_eq!(image.dots[0], RGBColorFormat::red());
+        assert_eq!(image.dots[1], RGBColorFormat::default());
+        assert_eq!(read_png(&bytes).dots[1], RGBColorFormat::white());
+    }
+
+    #[test]
+    fn reject_pixel_count_above_limit() {
+        let bytes = encode_png(2, 1, ColorType::Rgb, &[0; 6]);
+        let limits = InputLimits {
+            max_pixel_count: 1,
+            ..Default::default()
+        };
+        let result = PNGImageReader::with_limits(bytes.as_slice(), limits).read_image();
+        assert!(matches!(result, Err(Error::PixelCountExceedsLimit(2, 1))));
+    }
+}
@@ -0,0 +1,447 @@
+use std::io::Read;
+
+use super::super::Image;
+use super::super::ImageReader;
+use super::InputLimits;
+use crate::color::{RGBColorFormat, RangeColorFormat};
+use crate::{Error, TextPosition};
+
+pub struct PPMImageReader<T: Read> {
+    reader: T,
+    limits: InputLimits,
+}
+
+impl<T: Read> PPMImageReader<T> {
+    pub fn new(reader: T) -> Self {
+        Self::with_limits(reader, InputLimits::default())
+    }
+
+    pub fn with_limits(reader: T, limits: InputLimits) -> Self {
+        Self { reader, limits }
+    }
+}
+
+impl<T: Read> ImageReader<f32> for PPMImageReader<T> {
+    fn read_image(&mut self) -> crate::Result<Image<f32>> {
+        let mut tokenizer = PPMTokenizer::new(&mut self.reader, self.limits.max_token_length);
+        let mut parser = PPMParser::new(&mut tokenizer, self.limits);
+        parser.parse_tokens()
+    }
+}
+
+/// Reads the samples rescaled to the full 16 bit range, which keeps the precision of images with
+/// a max value above 255.
+impl<T: Read> ImageReader<u16> for PPMImageReader<T> {
+    fn read_image(&mut self) -> crate::Result<Image<u16>> {
+        let mut tokenizer = PPMTokenizer::new(&mut self.reader, self.limits.max_token_length);
+        let mut parser = PPMParser::new(&mut tokenizer, self.limits);
+        parser.parse_tokens()
+    }
+}
+
+/// A whitespace separated token together with the position of its first byte.
+struct Token {
+    text: String,
+    position: TextPosition,
+}
+
+struct PPMTokenizer<'a, R: Read> {
+    reader: &'a mut R,
+    buffer: Vec<u8>,
+    max_token_length: usize,
+    position: TextPosition,
+}
+
+impl<'a, R: Read> PPMTokenizer<'a, R> {
+    pub fn new(reader: &'a mut R, max_token_length: usize) -> Self {
+        PPMTokenizer {
+            reader,
+            buffer: Vec::new(),
+            max_token_length,
+            position: TextPosition {
+                byte: 0,
+                line: 1,
+                column: 1,
+            },
+        }
+    }
+
+    fn read_byte(&mut self) -> Option<u8> {
+        let mut byte = [0; 1];
+        if self.reader.read(&mut byte).unwrap_or(0) == 0 {
+            return None;
+        }
+        self.position.byte += 1;
+        if byte[0] == b'\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+        Some(byte[0])
+    }
+}
+
+impl<R: Read> Iterator for PPMTokenizer<'_, R> {
+    type Item = crate::Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.buffer.clear();
+        let mut in_comment = false;
+        let mut token_position = self.position;
+
+        loop {
+            let byte_position = self.position;
+            let Some(byte) = self.read_byte() else {
+                break;
+            };
+            if in_comment {
+                if byte == b'\n' {
+                    in_comment = false;
+                }
+                continue;
+            }
+            if byte == b'#' {
+                in_comment = true;
+                continue;
+            }
+            if byte.is_ascii_whitespace() {
+                if !self.buffer.is_empty() {
+                    break;
+                }
+            } else {
+                if self.buffer.is_empty() {
+                    token_position = byte_position;
+                }
+                if self.buffer.len() == self.max_token_length {
+                    return Some(Err(Error::TokenExceedsMaximumLength(
+                        self.max_token_length,
+                        Some(token_position),
+                    )));
+                }
+                self.buffer.push(byte);
+            }
+        }
+
+        if self.buffer.is_empty() {
+            return None;
+        }
+
+        // Invalid UTF-8 sequences are replaced, so they fail to parse with the position
+        let text = String::from_utf8_lossy(&self.buffer).into_owned();
+        Some(Ok(Token {
+            text,
+            position: token_position,
+        }))
+    }
+}
+
+const P3_HEADER_TOKEN_NAME: &str = "P3 Header";
+const WIDTH_HEADER_TOKEN_NAME: &str = "Width Header";
+const HEIGHT_HEADER_TOKEN_NAME: &str = "Height Header";
+const MAX_VALUE_HEADER_TOKEN_NAME: &str = "Max Value Header";
+const COLOR_COMPONENT_VALUE_TOKEN_NAME: &str = "Color Component Value";
+
+#[derive(Clone, Copy)]
+struct Dot {
+    buffer: [u16; 3],
+    index: usize,
+    /// Position of the first color component.
+    position: TextPosition,
+}
+
+impl Dot {
+    fn new() -> Self {
+        Self {
+            buffer: [u16::default(); 3],
+            index: 0,
+            position: TextPosition::default(),
+        }
+    }
+
+    fn red(&self) -> u16 {
+        self.buffer[0]
+    }
+
+    fn green(&self) -> u16 {
+        self.buffer[1]
+    }
+
+    fn blue(&self) -> u16 {
+        self.buffer[2]
+    }
+
+    fn push_color_component(&mut self, component: u16, position: TextPosition) {
+        if self.is_complete() {
+            return;
+        }
+        if self.is_empty() {
+            self.position = position;
+        }
+        self.buffer[self.index] = component;
+        self.index += 1;
+    }
+
+    fn is_complete(&self) -> bool {
+        self.index == 3
+    }
+
+    fn reset(&mut self) {
+        self.index = 0;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.index == 0
+    }
+}
+
+struct PPMParser<'a, T> {
+    tokenizer: &'a mut T,
+    limits: InputLimits,
+}
+
+impl<'a, T> PPMParser<'a, T>
+where
+    T: Iterator<Item = crate::Result<Token>>,
+{
+    fn new(tokenizer: &'a mut T, limits: InputLimits) -> Self {
+        Self { tokenizer, limits }
+    }
+
+    fn parse_tokens<D>(&mut self) -> crate::Result<Image<D>>
+    where
+        RGBColorFormat<D>: From<RangeColorFormat<u16>>,
+    {
+        let header = self.parse_header()?;
+        Self::check_header_version(&header)?;
+        let width = self.parse_width()?;
+        let height = self.parse_height()?;
+        self.limits.check_pixel_count(width, height)?;
+        let max_value = self.parse_max_value()?;
+        let dots = self.parse_all_dots(width as usize * height as usize)?;
+        Self::check_parsed_dots_length_match_header_information(&dots, width, height)?;
+        let dots = dots
+            .into_iter()
+            .map(|d| RangeColorFormat::new(max_value, d.red(), d.green(), d.blue()))
+            .map(RGBColorFormat::from)
+            .collect::<Vec<RGBColorFormat<D>>>();
+        Ok(Image {
+            width,
+            height,
+            dots,
+        })
+    }
+
+    fn check_parsed_dots_length_match_header_information(
+        dots: &[Dot],
+        width: u16,
+        height: u16,
+    ) -> crate::Result<()> {
+        let expected_number_of_dots = width as usize * height as usize;
+        if dots.len() != expected_number_of_dots {
+            return Err(Error::MismatchOfSizeBetweenHeaderAndValues);
+        }
+        Ok(())
+    }
+
+    fn check_header_version(header: &str) -> crate::Result<()> {
+        if header != "P3" {
+            return Err(Error::PPMFileDoesNotContainRequiredToken(
+                P3_HEADER_TOKEN_NAME,
+            ));
+        }
+        Ok(())
+    }
+
+    fn parse_header(&mut self) -> crate::Result<String> {
+        Ok(self.next_token(P3_HEADER_TOKEN_NAME)?.text)
+    }
+
+    fn parse_width(&mut self) -> crate::Result<u16> {
+        let token = self.next_token(WIDTH_HEADER_TOKEN_NAME)?;
+        Self::parse_token(&token, WIDTH_HEADER_TOKEN_NAME)
+    }
+
+    fn parse_height(&mut self) -> crate::Result<u16> {
+        let token = self.next_token(HEIGHT_HEADER_TOKEN_NAME)?;
+        Self::parse_token(&token, HEIGHT_HEADER_TOKEN_NAME)
+    }
+
+    fn parse_max_value(&mut self) -> crate::Result<u16> {
+        let token = self.next_token(MAX_VALUE_HEADER_TOKEN_NAME)?;
+        Self::parse_token(&token, MAX_VALUE_HEADER_TOKEN_NAME)
+    }
+
+    fn next_token(&mut self, token_name: &'static str) -> crate::Result<Token> {
+        self.tokenizer
+            .next()
+            .ok_or(Error::PPMFileDoesNotContainRequiredToken(token_name))?
+    }
+
+    fn parse_token(token: &Token, token_name: &'static str) -> crate::Result<u16> {
+        token
+            .text
+            .parse()
+            .map_err(|_| Error::ParsingOfTokenFailed(token_name, Some(token.position)))
+    }
+
+    /// Parses the dots up to one more than expected, so a file with too many values does not
+    /// grow the buffer any further.
+    fn parse_all_dots(&mut self, expected_number_of_dots: usize) -> crate::Result<Vec<Dot>> {
+        let mut current_dot = Dot::new();
+        let mut dots = Vec::with_capacity(expected_number_of_dots);
+        for token in self.tokenizer.by_ref() {
+            let token = token?;
+            if dots.len() > expected_number_of_dots {
+                break;
+            }
+            let component = Self::parse_token(&token, COLOR_COMPONENT_VALUE_TOKEN_NAME)?;
+            current_dot.push_color_component(component, token.position);
+            if current_dot.is_complete() {
+                dots.push(current_dot);
+                current_dot.reset();
+            }
+        }
+        Self::check_pixel_was_complete(&current_dot)?;
+        Ok(dots)
+    }
+
+    fn check_pixel_was_complete(dot: &Dot) -> crate::Result<()> {
+        if !dot.is_empty() {
+            return Err(Error::IncompletePixelParsed(dot.index, dot.position));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::{RGBColorFormat, RangeColorFormat},
+        error::Error,
+        image::Image,
+        Result, TextPosition,
+    };
+
+    use super::{InputLimits, PPMParser, PPMTokenizer};
+
+    fn parse_ppm_tokens(token_string: &str) -> Result<Image<f32>> {
+        let mut bytes = token_string.as_bytes();
+        let mut tokenizer = PPMTokenizer::new(&mut bytes, InputLimits::default().max_token_length);
+        let mut parser = PPMParser::new(&mut tokenizer, InputLimits::default());
+        parser.parse_tokens()
+    }
+
+    #[test]
+    fn read_string() {
+        let string = "P3\n# Example PPM image string\n3 2\n255\n255 0 0   0 255 0   0 0 255\n255 255 0  255 0 255  0 255 255";
+        let image = parse_ppm_tokens(string).unwrap();
+        assert!(image.height == 2);
+    }
+
+    #[test]
+    fn read_continuous_string() {
+        let string = "P3 3 2 255 255 0 0   0 255 0   0 0 255 255 255 0  255 0 255  0 255 255";
+        let image = parse_ppm_tokens(string).unwrap();
+        assert!(image.height == 2);
+    }
+
+    #[test]
+    fn read_newline_string() {
+        let string = "P3\n# Example PPM image newlines\n3\n2\n255\n255\n0\n0\n0\n255\n0\n0\n0\n255\n255\n255\n0\n255\n0\n255\n0\n255\n255";
+        let image = parse_ppm_tokens(string).unwrap();
+        assert!(image.height == 2);
+    }
+
+    #[test]
+    fn read_16bit_samples_without_loss() {
+        let string = "P3 2 1 65535 0 1 2 65533 65534 65535";
+        let mut bytes = string.as_bytes();
+        let mut tokenizer = PPMTokenizer::new(&mut bytes, InputLimits::default().max_token_length);
+        let mut parser = PPMParser::new(&mut tokenizer, InputLimits::default());
+        let image: Image<u16> = parser.parse_tokens().unwrap();
+        let expected: [RGBColorFormat<u16>; 2] = [
+            RangeColorFormat::new(u16::MAX, 0, 1, 2).into(),
+            RangeColorFormat::new(u16::MAX, 65533, 65534, 65535).into(),
+        ];
+        assert_eq!(image.dots, expected);
+    }
+
+    #[test]
+    fn incomplete_pixel() {
+        let string = "P3\n3 2 255 0 0 255 0 0";
+        if let Err(Error::IncompletePixelParsed(n, position)) = parse_ppm_tokens(string) {
+            assert_eq!(position.line, 2);
+            assert_eq!(position.column, 17);
+            if n != 2 {
+                panic!("Number of parsed pixels should be 2, but was {}", n);
+            }
+            return;
+        };
+        panic!("Incomplete pixel not detected");
+    }
+
+    #[test]
+    fn report_position_of_malformed_value() {
+        let string = "P3\n# comment\n2 1\n255\n0 0 0\n  0 x 0";
+        let result = parse_ppm_tokens(string);
+        let Err(Error::ParsingOfTokenFailed(token_name, Some(position))) = result else {
+            panic!("Malformed value not detected");
+        };
+        assert_eq!(token_name, "Color Component Value");
+        assert_eq!(
+            position,
+            TextPosition {
+                byte: 31,
+                line: 6,
+                column: 5
+            }
+        );
+    }
+
+    #[test]
+    fn report_position_of_malformed_width() {
+        let string = "P3 1O 1 255 0 0 0";
+        let result = parse_ppm_tokens(string);
+        assert!(matches!(
+            result,
+            Err(Error::ParsingOfTokenFailed(
+                "Width Header",
+                Some(TextPosition {
+                    byte: 3,
+                    line: 1,
+                    column: 4
+                })
+            ))
+        ));
+    }
+
+    #[test]
+    fn reject_endless_token() {
+        let string = format!("P3 1 1 255 0 0 {}", "1".repeat(1000));
+        let result = parse_ppm_tokens(&string);
+        assert!(matches!(
+            result,
+            Err(Error::TokenExceedsMaximumLength(64, Some(position))) if position.byte == 15
+        ));
+    }
+
+    #[test]
+    fn reject_more_values_than_declared() {
+        let string = format!("P3 1 1 255 {}", "0 ".repeat(1000));
+        let result = parse_ppm_tokens(&string);
+        assert!(matches!(
+            result,
+            Err(Error::MismatchOfSizeBetweenHeaderAndValues)
+        ));
+    }
+
+    #[test]
+    fn wrong_size() {
+        let string = "P3\n3 2 255 0 0 255";
+        if let Err(Error::MismatchOfSizeBetweenHeaderAndValues) = parse_ppm_tokens(string) {
+            return;
+        };
+        panic!("Mismatch of size in header and actual pixels was not detected!");
+    }
+}
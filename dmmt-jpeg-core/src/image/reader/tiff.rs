@@ -0,0 +1,505 @@
+use std::io::Read;
+
+use super::super::ImageReader;
+use super::super::{CMYKImage, Image};
+use super::InputLimits;
+use crate::color::{CMYKColorFormat, RGBColorFormat, RangeColorFormat};
+use crate::Error;
+
+const IMAGE_WIDTH_TAG: u16 = 256;
+const IMAGE_LENGTH_TAG: u16 = 257;
+const BITS_PER_SAMPLE_TAG: u16 = 258;
+const COMPRESSION_TAG: u16 = 259;
+const PHOTOMETRIC_INTERPRETATION_TAG: u16 = 262;
+const STRIP_OFFSETS_TAG: u16 = 273;
+const SAMPLES_PER_PIXEL_TAG: u16 = 277;
+const ROWS_PER_STRIP_TAG: u16 = 278;
+const STRIP_BYTE_COUNTS_TAG: u16 = 279;
+const PLANAR_CONFIGURATION_TAG: u16 = 284;
+
+const SHORT_FIELD_TYPE: u16 = 3;
+const LONG_FIELD_TYPE: u16 = 4;
+
+const NO_COMPRESSION: u32 = 1;
+const PHOTOMETRIC_RGB: u32 = 2;
+/// Separated inks, which are CMYK without an ink set tag.
+const PHOTOMETRIC_SEPARATED: u32 = 5;
+const PLANAR_CONFIGURATION_CHUNKY: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ByteOrder {
+    LittleEndian,
+    BigEndian,
+}
+
+/// The raw file content together with its byte order. Offsets inside of a TIFF file are absolute,
+/// so the whole file is kept in memory.
+struct TIFFData {
+    bytes: Vec<u8>,
+    byte_order: ByteOrder,
+}
+
+impl TIFFData {
+    fn slice(&self, offset: usize, length: usize) -> crate::Result<&[u8]> {
+        offset
+            .checked_add(length)
+            .and_then(|end| self.bytes.get(offset..end))
+            .ok_or(Error::InvalidTIFFFile("offset points outside of the file"))
+    }
+
+    fn read_u16(&self, offset: usize) -> crate::Result<u16> {
+        let bytes = self.slice(offset, 2)?;
+        let bytes = [bytes[0], bytes[1]];
+        Ok(match self.byte_order {
+            ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+        })
+    }
+
+    fn read_u32(&self, offset: usize) -> crate::Result<u32> {
+        let bytes = self.slice(offset, 4)?;
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        Ok(match self.byte_order {
+            ByteOrder::LittleEndian => u32::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u32::from_be_bytes(bytes),
+        })
+    }
+}
+
+struct IFDEntry {
+    tag: u16,
+    field_type: u16,
+    count: u32,
+    value_offset: usize,
+}
+
+impl IFDEntry {
+    fn values(&self, data: &TIFFData) -> crate::Result<Vec<u32>> {
+        let value_size = match self.field_type {
+            SHORT_FIELD_TYPE => 2,
+            LONG_FIELD_TYPE => 4,
+            _ => return Err(Error::InvalidTIFFFile("unexpected field type")),
+        };
+        let count = self.count as usize;
+        let offset = if count * value_size <= 4 {
+            self.value_offset
+        } else {
+            data.read_u32(self.value_offset)? as usize
+        };
+        (0..count)
+            .map(|index| match self.field_type {
+                SHORT_FIELD_TYPE => data.read_u16(offset + index * 2).map(u32::from),
+                _ => data.read_u32(offset + index * 4),
+            })
+            .collect()
+    }
+}
+
+struct TIFFHeader {
+    width: u16,
+    height: u16,
+    photometric_interpretation: u32,
+    bits_per_sample: u32,
+    samples_per_pixel: usize,
+    rows_per_strip: usize,
+    strip_offsets: Vec<u32>,
+    strip_byte_counts: Vec<u32>,
+}
+
+impl TIFFHeader {
+    fn bytes_per_sample(&self) -> usize {
+        self.bits_per_sample as usize / 8
+    }
+
+    fn bytes_per_row(&self) -> usize {
+        self.width as usize * self.samples_per_pixel * self.bytes_per_sample()
+    }
+}
+
+/// Reads uncompressed, stripped RGB or CMYK TIFF images with 8 or 16 bits per sample. Extra
+/// samples, like an alpha channel, are ignored.
+pub struct TIFFImageReader<T: Read> {
+    reader: T,
+    limits: InputLimits,
+}
+
+impl<T: Read> TIFFImageReader<T> {
+    pub fn new(reader: T) -> Self {
+        Self::with_limits(reader, InputLimits::default())
+    }
+
+    pub fn with_limits(reader: T, limits: InputLimits) -> Self {
+        Self { reader, limits }
+    }
+
+    fn read_data(&mut self) -> crate::Result<TIFFData> {
+        let mut bytes = Vec::new();
+        self.reader
+            .read_to_end(&mut bytes)
+            .map_err(|_| Error::InvalidTIFFFile("file could not be read"))?;
+        let byte_order = match bytes.get(0..2) {
+            Some(b"II") => ByteOrder::LittleEndian,
+            Some(b"MM") => ByteOrder::BigEndian,
+            _ => return Err(Error::InvalidTIFFFile("unknown byte order")),
+        };
+        let data = TIFFData { bytes, byte_order };
+        if data.read_u16(2)? != 42 {
+            return Err(Error::InvalidTIFFFile("magic number 42 is missing"));
+        }
+        Ok(data)
+    }
+
+    fn read_ifd_entries(data: &TIFFData) -> crate::Result<Vec<IFDEntry>> {
+        let ifd_offset = data.read_u32(4)? as usize;
+        let number_of_entries = data.read_u16(ifd_offset)? as usize;
+        (0..number_of_entries)
+            .map(|index| {
+                let entry_offset = ifd_offset + 2 + index * 12;
+                Ok(IFDEntry {
+                    tag: data.read_u16(entry_offset)?,
+                    field_type: data.read_u16(entry_offset + 2)?,
+                    count: data.read_u32(entry_offset + 4)?,
+                    value_offset: entry_offset + 8,
+                })
+            })
+            .collect()
+    }
+
+    fn find_values(
+        data: &TIFFData,
+        entries: &[IFDEntry],
+        tag: u16,
+    ) -> crate::Result<Option<Vec<u32>>> {
+        entries
+            .iter()
+            .find(|entry| entry.tag == tag)
+            .map(|entry| entry.values(data))
+            .transpose()
+    }
+
+    fn find_required_values(
+        data: &TIFFData,
+        entries: &[IFDEntry],
+        tag: u16,
+    ) -> crate::Result<Vec<u32>> {
+        Self::find_values(data, entries, tag)?
+            .filter(|values| !values.is_empty())
+            .ok_or(Error::TIFFFileDoesNotContainRequiredTag(tag))
+    }
+
+    fn find_value_or(
+        data: &TIFFData,
+        entries: &[IFDEntry],
+        tag: u16,
+        default: u32,
+    ) -> crate::Result<u32> {
+        Ok(Self::find_values(data, entries, tag)?
+            .and_then(|values| values.first().copied())
+            .unwrap_or(default))
+    }
+
+    fn read_dimension(data: &TIFFData, entries: &[IFDEntry], tag: u16) -> crate::Result<u32> {
+        Ok(Self::find_required_values(data, entries, tag)?[0])
+    }
+
+    /// Reads the header of the first IFD. Its dimensions are checked against the limits, before
+    /// the strips are read.
+    fn read_header(data: &TIFFData, limits: &InputLimits) -> crate::Result<TIFFHeader> {
+        let entries = Self::read_ifd_entries(data)?;
+        let compression = Self::find_value_or(data, &entries, COMPRESSION_TAG, NO_COMPRESSION)?;
+        if compression != NO_COMPRESSION {
+            return Err(Error::UnsupportedTIFFFeature("compression", compression));
+        }
+        let photometric_interpretation =
+            Self::find_required_values(data, &entries, PHOTOMETRIC_INTERPRETATION_TAG)?[0];
+        if !matches!(
+            photometric_interpretation,
+            PHOTOMETRIC_RGB | PHOTOMETRIC_SEPARATED
+        ) {
+            return Err(Error::UnsupportedTIFFFeature(
+                "photometric interpretation",
+                photometric_interpretation,
+            ));
+        }
+        let planar_configuration = Self::find_value_or(
+            data,
+            &entries,
+            PLANAR_CONFIGURATION_TAG,
+            PLANAR_CONFIGURATION_CHUNKY,
+        )?;
+        if planar_configuration != PLANAR_CONFIGURATION_CHUNKY {
+            return Err(Error::UnsupportedTIFFFeature(
+                "planar configuration",
+                planar_configuration,
+            ));
+        }
+        let samples_per_pixel = Self::find_value_or(data, &entries, SAMPLES_PER_PIXEL_TAG, 1)?;
+        let color_samples = if photometric_interpretation == PHOTOMETRIC_SEPARATED {
+            4
+        } else {
+            3
+        };
+        if samples_per_pixel < color_samples {
+            return Err(Error::UnsupportedTIFFFeature(
+                "samples per pixel",
+                samples_per_pixel,
+            ));
+        }
+        let bits_per_sample = Self::find_required_values(data, &entries, BITS_PER_SAMPLE_TAG)?;
+        let first_bits_per_sample = bits_per_sample[0];
+        if !matches!(first_bits_per_sample, 8 | 16)
+            || bits_per_sample.iter().any(|&b| b != first_bits_per_sample)
+        {
+            return Err(Error::UnsupportedTIFFFeature(
+                "bits per sample",
+                first_bits_per_sample,
+            ));
+        }
+        let width = Self::read_dimension(data, &entries, IMAGE_WIDTH_TAG)?;
+        let height = Self::read_dimension(data, &entries, IMAGE_LENGTH_TAG)?;
+        let to_u16 = |value: u32| {
+            u16::try_from(value).map_err(|_| Error::ImageDimensionsNotSupported(width, height))
+        };
+        let (width, height) = (to_u16(width)?, to_u16(height)?);
+        limits.check_pixel_count(width, height)?;
+        let rows_per_strip =
+            Self::find_value_or(data, &entries, ROWS_PER_STRIP_TAG, u32::from(height))?;
+        let strip_offsets = Self::find_required_values(data, &entries, STRIP_OFFSETS_TAG)?;
+        let strip_byte_counts = Self::find_required_values(data, &entries, STRIP_BYTE_COUNTS_TAG)?;
+        if strip_offsets.len() != strip_byte_counts.len() {
+            return Err(Error::InvalidTIFFFile(
+                "number of strip offsets and byte counts differ",
+            ));
+        }
+        Ok(TIFFHeader {
+            width,
+            height,
+            photometric_interpretation,
+            bits_per_sample: first_bits_per_sample,
+            samples_per_pixel: samples_per_pixel as usize,
+            rows_per_strip: rows_per_strip.max(1) as usize,
+            strip_offsets,
+            strip_byte_counts,
+        })
+    }
+
+    fn read_strips(data: &TIFFData, header: &TIFFHeader) -> crate::Result<Vec<u8>> {
+        let bytes_per_row = header.bytes_per_row();
+        let mut rows_left = header.height as usize;
+        // the samples per pixel aren't limited, but the strips can't exceed the file
+        let mut image_bytes = Vec::with_capacity((bytes_per_row * rows_left).min(data.bytes.len()));
+        for (&offset, &byte_count) in header.strip_offsets.iter().zip(&header.strip_byte_counts) {
+            if rows_left == 0 {
+                break;
+            }
+            let rows_in_strip = header.rows_per_strip.min(rows_left);
+            let strip_length = rows_in_strip * bytes_per_row;
+            if (byte_count as usize) < strip_length {
+                return Err(Error::MismatchOfSizeBetweenHeaderAndValues);
+            }
+            image_bytes.extend_from_slice(data.slice(offset as usize, strip_length)?);
+            rows_left -= rows_in_strip;
+        }
+        if rows_left != 0 {
+            return Err(Error::MismatchOfSizeBetweenHeaderAndValues);
+        }
+        Ok(image_bytes)
+    }
+
+    fn convert_bytes_to_samples(
+        data: &TIFFData,
+        header: &TIFFHeader,
+        image_bytes: &[u8],
+    ) -> Vec<u16> {
+        let bytes_per_sample = header.bytes_per_sample();
+        if bytes_per_sample == 2 {
+            image_bytes
+                .chunks_exact(2)
+                .map(|b| match data.byte_order {
+                    ByteOrder::LittleEndian => u16::from_le_bytes([b[0], b[1]]),
+                    ByteOrder::BigEndian => u16::from_be_bytes([b[0], b[1]]),
+                })
+                .collect()
+        } else {
+            image_bytes.iter().copied().map(u16::from).collect()
+        }
+    }
+
+    /// Reads an image of separated inks, whose samples are the amount of ink.
+    pub fn read_cmyk_image(&mut self) -> crate::Result<CMYKImage> {
+        let data = self.read_data()?;
+        let header = Self::read_header(&data, &self.limits)?;
+        if header.photometric_interpretation != PHOTOMETRIC_SEPARATED {
+            return Err(Error::CMYKImageExpected);
+        }
+        let image_bytes = Self::read_strips(&data, &header)?;
+        let samples = Self::convert_bytes_to_samples(&data, &header, &image_bytes);
+        let max_value = ((1_u32 << header.bits_per_sample) - 1) as u16;
+        let dots = samples
+            .chunks_exact(header.samples_per_pixel)
+            .map(|pixel| {
+                CMYKColorFormat::from_samples(max_value, [pixel[0], pixel[1], pixel[2], pixel[3]])
+            })
+            .collect();
+        Ok(CMYKImage {
+            width: header.width,
+            height: header.height,
+            dots,
+        })
+    }
+}
+
+impl<T: Read> ImageReader<f32> for TIFFImageReader<T> {
+    fn read_image(&mut self) -> crate::Result<Image<f32>> {
+        let data = self.read_data()?;
+        let header = Self::read_header(&data, &self.limits)?;
+        if header.photometric_interpretation == PHOTOMETRIC_SEPARATED {
+            return Err(Error::CMYKImageNotExpected);
+        }
+        let image_bytes = Self::read_strips(&data, &header)?;
+        let samples = Self::convert_bytes_to_samples(&data, &header, &image_bytes);
+        let max_value = ((1_u32 << header.bits_per_sample) - 1) as u16;
+        let dots = samples
+            .chunks_exact(header.samples_per_pixel)
+            .map(|pixel| RangeColorFormat::new(max_value, pixel[0], pixel[1], pixel[2]))
+            .map(RGBColorFormat::from)
+            .collect();
+        Ok(Image {
+            width: header.width,
+            height: header.height,
+            dots,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::RGBColorFormat,
+        error::Error,
+        image::{Image, ImageReader},
+        Result,
+    };
+
+    use super::{InputLimits, TIFFImageReader};
+
+    enum Value {
+        Inline(u32),
+        ExtraOffset,
+        DataOffset,
+    }
+
+    /// Builds a little endian TIFF file with a single IFD. The IFD is followed by the `extra`
+    /// bytes, which hold values not fitting into an entry, and by the image data.
+    fn create_tiff(entries: &[(u16, u16, u32, Value)], extra: &[u8], image_data: &[u8]) -> Vec<u8> {
+        let ifd_offset = 8_u32;
+        let extra_offset = ifd_offset + 2 + entries.len() as u32 * 12 + 4;
+        let data_offset = extra_offset + extra.len() as u32;
+        let mut bytes = b"II".to_vec();
+        bytes.extend(42_u16.to_le_bytes());
+        bytes.extend(ifd_offset.to_le_bytes());
+        bytes.extend((entries.len() as u16).to_le_bytes());
+        for (tag, field_type, count, value) in entries {
+            let value = match value {
+                Value::Inline(value) => *value,
+                Value::ExtraOffset => extra_offset,
+                Value::DataOffset => data_offset,
+            };
+            bytes.extend(tag.to_le_bytes());
+            bytes.extend(field_type.to_le_bytes());
+            bytes.extend(count.to_le_bytes());
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(0_u32.to_le_bytes());
+        bytes.extend(extra);
+        bytes.extend(image_data);
+        bytes
+    }
+
+    fn read_tiff(bytes: &[u8]) -> Result<Image<f32>> {
+        TIFFImageReader::new(bytes).read_image()
+    }
+
+    fn rgb_entries(compression: u32) -> Vec<(u16, u16, u32, Value)> {
+        Vec::from([
+            (256, 3, 1, Value::Inline(2)),
+            (257, 3, 1, Value::Inline(1)),
+            (258, 3, 3, Value::ExtraOffset),
+            (259, 3, 1, Value::Inline(compression)),
+            (262, 3, 1, Value::Inline(2)),
+            (273, 4, 1, Value::DataOffset),
+            (277, 3, 1, Value::Inline(3)),
+            (278, 3, 1, Value::Inline(1)),
+            (279, 4, 1, Value::Inline(6)),
+        ])
+    }
+
+    fn bits_per_sample() -> Vec<u8> {
+        [8_u16, 8, 8].iter().flat_map(|b| b.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn read_rgb() {
+        let bytes = create_tiff(&rgb_entries(1), &bits_per_sample(), &[255, 0, 0, 0, 0, 0]);
+        let image = read_tiff(&bytes).unwrap();
+        assert_eq!(image.width, 2);
+        assert_eq!(image.height, 1);
+        assert_eq!(image.dots[0], RGBColorFormat::red());
+        assert_eq!(image.dots[1], RGBColorFormat::default());
+    }
+
+    #[test]
+    fn read_cmyk() {
+        let mut entries = rgb_entries(1);
+        entries[2] = (258, 3, 4, Value::ExtraOffset);
+        entries[4] = (262, 3, 1, Value::Inline(5));
+        entries[6] = (277, 3, 1, Value::Inline(4));
+        entries[8] = (279, 4, 1, Value::Inline(8));
+        let bits_per_sample: Vec<u8> = [8_u16; 4].iter().flat_map(|b| b.to_le_bytes()).collect();
+        let bytes = create_tiff(&entries, &bits_per_sample, &[0, 255, 0, 0, 0, 0, 0, 255]);
+        let image = TIFFImageReader::new(bytes.as_slice())
+            .read_cmyk_image()
+            .unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.dots[0].magenta, 1.0);
+        assert_eq!(image.dots[1].black, 1.0);
+        assert!(matches!(
+            read_tiff(&bytes),
+            Err(Error::CMYKImageNotExpected)
+        ));
+    }
+
+    #[test]
+    fn reject_pixel_count_above_limit() {
+        let bytes = create_tiff(&rgb_entries(1), &bits_per_sample(), &[0; 6]);
+        let limits = InputLimits {
+            max_pixel_count: 1,
+            ..Default::default()
+        };
+        let result = TIFFImageReader::with_limits(bytes.as_slice(), limits).read_image();
+        assert!(matches!(result, Err(Error::PixelCountExceedsLimit(2, 1))));
+    }
+
+    #[test]
+    fn compressed_image_is_not_supported() {
+        let bytes = create_tiff(&rgb_entries(5), &bits_per_sample(), &[0; 6]);
+        let result = read_tiff(&bytes);
+        assert!(matches!(
+            result,
+            Err(Error::UnsupportedTIFFFeature("compression", 5))
+        ));
+    }
+
+    #[test]
+    fn too_few_bytes_in_strip() {
+        let bytes = create_tiff(&rgb_entries(1), &bits_per_sample(), &[0; 3]);
+        let result = read_tiff(&bytes);
+        assert!(matches!(result, Err(Error::InvalidTIFFFile(_))));
+    }
+
+    #[test]
+    fn missing_byte_order() {
+        let result = read_tiff(b"XX*\0");
+        assert!(matches!(result, Err(Error::InvalidTIFFFile(_))));
+    }
+}
@@ -0,0 +1,323 @@
+use std::io::Read;
+use std::str;
+
+use super::super::YCbCrImage;
+use crate::color::YCbCrColorFormat;
+use crate::Error;
+
+const STREAM_HEADER_TOKEN_NAME: &str = "YUV4MPEG2";
+const FRAME_HEADER_TOKEN_NAME: &str = "FRAME";
+const WIDTH_HEADER_TOKEN_NAME: &str = "W";
+const HEIGHT_HEADER_TOKEN_NAME: &str = "H";
+const COLOR_RANGE_PARAMETER: &str = "COLORRANGE=";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ColorSpace {
+    C420,
+    C422,
+    C444,
+    Mono,
+}
+
+impl ColorSpace {
+    fn parse(value: &str) -> crate::Result<Self> {
+        match value {
+            "420jpeg" | "420paldv" | "420mpeg2" | "420" => Ok(Self::C420),
+            "422" => Ok(Self::C422),
+            "444" => Ok(Self::C444),
+            "mono" => Ok(Self::Mono),
+            _ => Err(Error::UnsupportedY4MColorSpace(value.to_string())),
+        }
+    }
+
+    /// Horizontal and vertical ratio between the luma and the chroma planes.
+    fn chroma_ratios(&self) -> (usize, usize) {
+        match self {
+            Self::C420 => (2, 2),
+            Self::C422 => (2, 1),
+            Self::C444 | Self::Mono => (1, 1),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Y4MHeader {
+    width: u16,
+    height: u16,
+    color_space: ColorSpace,
+    full_range: bool,
+}
+
+impl Y4MHeader {
+    fn chroma_plane_dimensions(&self) -> (usize, usize) {
+        let (horizontal_ratio, vertical_ratio) = self.color_space.chroma_ratios();
+        (
+            (self.width as usize).div_ceil(horizontal_ratio),
+            (self.height as usize).div_ceil(vertical_ratio),
+        )
+    }
+
+    fn chroma_plane_length(&self) -> usize {
+        if self.color_space == ColorSpace::Mono {
+            return 0;
+        }
+        let (width, height) = self.chroma_plane_dimensions();
+        width * height
+    }
+
+    /// Maps a luma sample to the full range, level shifted to be centered around zero.
+    fn luma(&self, sample: u8) -> f32 {
+        if self.full_range {
+            sample as f32 - 128.0
+        } else {
+            (sample as f32 - 16.0) * 255.0 / 219.0 - 128.0
+        }
+    }
+
+    /// Maps a chroma sample to the full range, centered around zero.
+    fn chroma(&self, sample: u8) -> f32 {
+        if self.full_range {
+            sample as f32 - 128.0
+        } else {
+            (sample as f32 - 128.0) * 255.0 / 224.0
+        }
+    }
+}
+
+/// Reads the frames of a YUV4MPEG2 stream with 8 bit samples. The frames are returned as YCbCr
+/// images, so they skip the color conversion when encoded. The chroma planes are upsampled to
+/// the luma resolution, limited range samples are expanded to the full range of JFIF.
+pub struct Y4MStreamReader<T: Read> {
+    reader: T,
+    header: Option<Y4MHeader>,
+}
+
+impl<T: Read> Y4MStreamReader<T> {
+    pub fn new(reader: T) -> Self {
+        Self {
+            reader,
+            header: None,
+        }
+    }
+
+    /// Reads a line without the line feed. Returns `None` at the end of the stream.
+    fn read_line(&mut self, token_name: &'static str) -> crate::Result<Option<String>> {
+        let mut buffer = Vec::new();
+        let mut byte = [0; 1];
+        loop {
+            let n = self
+                .reader
+                .read(&mut byte)
+                .map_err(|_| Error::Y4MStreamDoesNotContainRequiredToken(token_name))?;
+            if n == 0 {
+                if buffer.is_empty() {
+                    return Ok(None);
+                }
+                return Err(Error::Y4MStreamDoesNotContainRequiredToken(token_name));
+            }
+            if byte[0] == b'\n' {
+                break;
+            }
+            buffer.push(byte[0]);
+        }
+        str::from_utf8(&buffer)
+            .map(|line| Some(line.to_string()))
+            .map_err(|_| Error::ParsingOfTokenFailed(token_name, None))
+    }
+
+    fn read_header(&mut self) -> crate::Result<Y4MHeader> {
+        let line = self.read_line(STREAM_HEADER_TOKEN_NAME)?.ok_or(
+            Error::Y4MStreamDoesNotContainRequiredToken(STREAM_HEADER_TOKEN_NAME),
+        )?;
+        let mut tokens = line.split(' ');
+        if tokens.next() != Some(STREAM_HEADER_TOKEN_NAME) {
+            return Err(Error::Y4MStreamDoesNotContainRequiredToken(
+                STREAM_HEADER_TOKEN_NAME,
+            ));
+        }
+        let mut width = None;
+        let mut height = None;
+        let mut color_space = ColorSpace::C420;
+        let mut full_range = false;
+        for token in tokens.filter(|token| !token.is_empty()) {
+            let mut characters = token.chars();
+            let key = characters.next();
+            let value = characters.as_str();
+            match key {
+                Some('W') => width = Some(Self::parse_value(value, WIDTH_HEADER_TOKEN_NAME)?),
+                Some('H') => height = Some(Self::parse_value(value, HEIGHT_HEADER_TOKEN_NAME)?),
+                Some('C') => color_space = ColorSpace::parse(value)?,
+                Some('X') => {
+                    if let Some(range) = value.strip_prefix(COLOR_RANGE_PARAMETER) {
+                        full_range = range == "FULL";
+                    }
+                }
+                _ => continue,
+            }
+        }
+        Ok(Y4MHeader {
+            width: width.ok_or(Error::Y4MStreamDoesNotContainRequiredToken(
+                WIDTH_HEADER_TOKEN_NAME,
+            ))?,
+            height: height.ok_or(Error::Y4MStreamDoesNotContainRequiredToken(
+                HEIGHT_HEADER_TOKEN_NAME,
+            ))?,
+            color_space,
+            full_range,
+        })
+    }
+
+    fn parse_value<V: str::FromStr>(value: &str, token_name: &'static str) -> crate::Result<V> {
+        value
+            .parse()
+            .map_err(|_| Error::ParsingOfTokenFailed(token_name, None))
+    }
+
+    fn read_plane(&mut self, length: usize) -> crate::Result<Vec<u8>> {
+        let mut plane = vec![0; length];
+        self.reader
+            .read_exact(&mut plane)
+            .map_err(|_| Error::MismatchOfSizeBetweenHeaderAndValues)?;
+        Ok(plane)
+    }
+
+    fn convert_planes_to_dots(
+        header: &Y4MHeader,
+        luma_plane: &[u8],
+        chroma_blue_plane: &[u8],
+        chroma_red_plane: &[u8],
+    ) -> Vec<YCbCrColorFormat<f32>> {
+        let (horizontal_ratio, vertical_ratio) = header.color_space.chroma_ratios();
+        let (chroma_width, _) = header.chroma_plane_dimensions();
+        let width = header.width as usize;
+        luma_plane
+            .iter()
+            .enumerate()
+            .map(|(index, &luma)| {
+                let (x, y) = (index % width, index / width);
+                let chroma_index = y / vertical_ratio * chroma_width + x / horizontal_ratio;
+                let chroma =
+                    |plane: &[u8]| plane.get(chroma_index).map_or(0.0, |&c| header.chroma(c));
+                YCbCrColorFormat {
+                    luma: header.luma(luma),
+                    chroma_blue: chroma(chroma_blue_plane),
+                    chroma_red: chroma(chroma_red_plane),
+                }
+            })
+            .collect()
+    }
+
+    /// Reads the next frame of the stream. Returns `None` after the last frame.
+    pub fn read_frame(&mut self) -> crate::Result<Option<YCbCrImage>> {
+        let header = match self.header {
+            Some(header) => header,
+            None => {
+                let header = self.read_header()?;
+                *self.header.insert(header)
+            }
+        };
+        let Some(frame_line) = self.read_line(FRAME_HEADER_TOKEN_NAME)? else {
+            return Ok(None);
+        };
+        if frame_line.split(' ').next() != Some(FRAME_HEADER_TOKEN_NAME) {
+            return Err(Error::Y4MStreamDoesNotContainRequiredToken(
+                FRAME_HEADER_TOKEN_NAME,
+            ));
+        }
+        let luma_plane = self.read_plane(header.width as usize * header.height as usize)?;
+        let chroma_blue_plane = self.read_plane(header.chroma_plane_length())?;
+        let chroma_red_plane = self.read_plane(header.chroma_plane_length())?;
+        let dots = Self::convert_planes_to_dots(
+            &header,
+            &luma_plane,
+            &chroma_blue_plane,
+            &chroma_red_plane,
+        );
+        Ok(Some(YCbCrImage {
+            width: header.width,
+            height: header.height,
+            dots,
+        }))
+    }
+}
+
+impl<T: Read> Iterator for Y4MStreamReader<T> {
+    type Item = crate::Result<YCbCrImage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{color::YCbCrColorFormat, error::Error};
+
+    use super::Y4MStreamReader;
+
+    fn ycbcr(luma: f32, chroma_blue: f32, chroma_red: f32) -> YCbCrColorFormat<f32> {
+        YCbCrColorFormat {
+            luma,
+            chroma_blue,
+            chroma_red,
+        }
+    }
+
+    #[test]
+    fn read_420_frames_and_upsample_chroma() {
+        let mut bytes = b"YUV4MPEG2 W2 H2 F25:1 Ip A1:1 C420jpeg XCOLORRANGE=FULL\n".to_vec();
+        bytes.extend(b"FRAME\n");
+        bytes.extend([0, 128, 255, 128, 0, 255]);
+        bytes.extend(b"FRAME Ixyz\n");
+        bytes.extend([128, 128, 128, 128, 128, 128]);
+        let frames: Vec<_> = Y4MStreamReader::new(&bytes[..])
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].width, 2);
+        assert_eq!(frames[0].height, 2);
+        assert_eq!(frames[0].dots[0], ycbcr(-128.0, -128.0, 127.0));
+        assert_eq!(frames[0].dots[2], ycbcr(127.0, -128.0, 127.0));
+        assert_eq!(frames[1].dots[3], ycbcr(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn expand_limited_range() {
+        let mut bytes = b"YUV4MPEG2 W1 H1 C444\nFRAME\n".to_vec();
+        bytes.extend([16, 16, 240]);
+        let frame = Y4MStreamReader::new(&bytes[..])
+            .read_frame()
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame.dots[0], ycbcr(-128.0, -127.5, 127.5));
+    }
+
+    #[test]
+    fn read_mono_frame() {
+        let mut bytes = b"YUV4MPEG2 W2 H1 Cmono XCOLORRANGE=FULL\nFRAME\n".to_vec();
+        bytes.extend([255, 0]);
+        let frame = Y4MStreamReader::new(&bytes[..])
+            .read_frame()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            frame.dots,
+            [ycbcr(127.0, 0.0, 0.0), ycbcr(-128.0, 0.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn unsupported_color_space() {
+        let result = Y4MStreamReader::new(&b"YUV4MPEG2 W1 H1 C420p10\nFRAME\n"[..]).read_frame();
+        assert!(matches!(result, Err(Error::UnsupportedY4MColorSpace(c)) if c == "420p10"));
+    }
+
+    #[test]
+    fn truncated_frame() {
+        let result = Y4MStreamReader::new(&b"YUV4MPEG2 W2 H2\nFRAME\n\0\0\0\0\0"[..]).read_frame();
+        assert!(matches!(
+            result,
+            Err(Error::MismatchOfSizeBetweenHeaderAndValues)
+        ));
+    }
+}
@@ -0,0 +1,1441 @@
+use std::{
+    cmp,
+    fmt::Display,
+    iter::Sum,
+    ops::{AddAssign, Div, DivAssign, Mul, Range},
+    str::FromStr,
+    sync::{mpsc, Arc},
+};
+
+#[cfg(feature = "clap")]
+use clap::{builder::PossibleValue, ValueEnum};
+use threadpool::ThreadPool;
+
+use super::ColorChannel;
+use crate::Error;
+
+/// Maximum number of blocks in an MCU of an interleaved scan (B.2.3).
+const MAX_BLOCKS_PER_MCU: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChromaSubsamplingPreset {
+    P444,
+    P422,
+    P420,
+    /// Chroma of a quarter of the horizontal resolution, as used by DV.
+    P411,
+    /// Chroma of half the vertical resolution, typical for rotated 4:2:2 images.
+    P440,
+    /// Other rates of the luma resolution to the chroma resolution, see [`Self::custom`].
+    Custom {
+        horizontal_rate: u8,
+        vertical_rate: u8,
+    },
+}
+
+impl ChromaSubsamplingPreset {
+    /// The named presets, without the custom rates.
+    pub fn variants() -> &'static [Self] {
+        &[Self::P444, Self::P422, Self::P420, Self::P411, Self::P440]
+    }
+
+    /// Subsampling of the rates, which are the sampling factors of the luma component, as the
+    /// chroma components have factors of 1. The rates must be in range 1 to 4 and an MCU must
+    /// not hold more than 10 blocks. Rates of a named preset yield the preset.
+    pub fn custom(horizontal_rate: u8, vertical_rate: u8) -> crate::Result<Self> {
+        SamplingLayout::new(
+            SamplingFactors::new(horizontal_rate, vertical_rate)?,
+            SamplingFactors::new(1, 1)?,
+        )?;
+        let custom = Self::Custom {
+            horizontal_rate,
+            vertical_rate,
+        };
+        Ok(Self::variants()
+            .iter()
+            .copied()
+            .find(|preset| preset.sampling_layout() == custom.sampling_layout())
+            .unwrap_or(custom))
+    }
+
+    /// Name of the preset, as accepted on the command line. Custom rates are named by the
+    /// horizontal and vertical rate like `3x1`.
+    pub fn name(&self) -> String {
+        match self {
+            Self::P444 => "P444".to_string(),
+            Self::P422 => "P422".to_string(),
+            Self::P420 => "P420".to_string(),
+            Self::P411 => "P411".to_string(),
+            Self::P440 => "P440".to_string(),
+            Self::Custom {
+                horizontal_rate,
+                vertical_rate,
+            } => format!("{}x{}", horizontal_rate, vertical_rate),
+        }
+    }
+
+    pub fn horizontal_rate(&self) -> u8 {
+        match self {
+            ChromaSubsamplingPreset::P444 => 1,
+            ChromaSubsamplingPreset::P422 => 2,
+            ChromaSubsamplingPreset::P420 => 2,
+            ChromaSubsamplingPreset::P411 => 4,
+            ChromaSubsamplingPreset::P440 => 1,
+            ChromaSubsamplingPreset::Custom {
+                horizontal_rate, ..
+            } => *horizontal_rate,
+        }
+    }
+
+    /// Number of blocks in one MCU, the luma blocks followed by one block of each chroma channel.
+    pub fn blocks_per_mcu(&self) -> usize {
+        self.sampling_layout().blocks_per_mcu()
+    }
+
+    pub fn luma_blocks_per_mcu(&self) -> usize {
+        self.sampling_layout().luma_factors.blocks()
+    }
+
+    pub fn sampling_layout(&self) -> SamplingLayout {
+        SamplingLayout {
+            luma_factors: SamplingFactors {
+                horizontal: self.horizontal_rate(),
+                vertical: self.vertical_rate(),
+            },
+            chroma_factors: SamplingFactors {
+                horizontal: 1,
+                vertical: 1,
+            },
+        }
+    }
+
+    pub fn vertical_rate(&self) -> u8 {
+        match self {
+            ChromaSubsamplingPreset::P444 => 1,
+            ChromaSubsamplingPreset::P422 => 1,
+            ChromaSubsamplingPreset::P420 => 2,
+            ChromaSubsamplingPreset::P411 => 1,
+            ChromaSubsamplingPreset::P440 => 2,
+            ChromaSubsamplingPreset::Custom { vertical_rate, .. } => *vertical_rate,
+        }
+    }
+
+    pub fn method(&self) -> SubsamplingMethod {
+        match self {
+            ChromaSubsamplingPreset::P444 => SubsamplingMethod::Skip,
+            _ => SubsamplingMethod::Average,
+        }
+    }
+}
+
+impl FromStr for ChromaSubsamplingPreset {
+    type Err = String;
+
+    /// Parses the name of a preset, a ratio `J:a:b` like `4:2:2` or the horizontal and vertical
+    /// rate like `3x1`. The ratio has `a` chroma samples per `J` luma samples in the first row
+    /// and `b` additional chroma samples in the second row, so `b` is either `a` or 0.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Some(&preset) = Self::variants()
+            .iter()
+            .find(|preset| preset.name().eq_ignore_ascii_case(value))
+        {
+            return Ok(preset);
+        }
+        let parse_number = |number: &str| {
+            number
+                .parse::<u8>()
+                .map_err(|_| format!("'{}' is not a number in range 0 to {}", number, u8::MAX))
+        };
+        let (horizontal_rate, vertical_rate) =
+            if let Some((horizontal, vertical)) = value.split_once('x') {
+                (parse_number(horizontal)?, parse_number(vertical)?)
+            } else {
+                let parts = value
+                    .split(':')
+                    .map(parse_number)
+                    .collect::<Result<Vec<u8>, String>>()?;
+                match parts[..] {
+                    [luma, chroma, second_row] if chroma != 0 && luma % chroma == 0 => {
+                        let vertical_rate = match second_row {
+                            0 => 2,
+                            _ if second_row == chroma => 1,
+                            _ => {
+                                return Err(format!(
+                                    "'{}' has neither {} nor 0 chroma samples in the second row",
+                                    value, chroma
+                                ))
+                            }
+                        };
+                        (luma / chroma, vertical_rate)
+                    }
+                    _ => {
+                        return Err(format!(
+                            "'{}' is neither a preset, a ratio J:a:b nor rates HxV",
+                            value
+                        ))
+                    }
+                }
+            };
+        Self::custom(horizontal_rate, vertical_rate).map_err(|e| e.to_string())
+    }
+}
+
+/// Horizontal and vertical sampling factors Hi and Vi of a component (A.1.1).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SamplingFactors {
+    pub horizontal: u8,
+    pub vertical: u8,
+}
+
+impl SamplingFactors {
+    /// Creates sampling factors, which must be in range 1 to 4.
+    pub fn new(horizontal: u8, vertical: u8) -> crate::Result<Self> {
+        if !(1..=4).contains(&horizontal) || !(1..=4).contains(&vertical) {
+            return Err(Error::InvalidSamplingFactors(horizontal, vertical));
+        }
+        Ok(Self {
+            horizontal,
+            vertical,
+        })
+    }
+
+    /// The factors as written into the frame header, Hi in the high and Vi in the low nibble.
+    pub fn to_byte(self) -> u8 {
+        self.horizontal << 4 | self.vertical
+    }
+
+    /// Number of blocks of the component in one MCU.
+    pub fn blocks(self) -> usize {
+        self.horizontal as usize * self.vertical as usize
+    }
+}
+
+/// Sampling factors of the luma and both chroma components and the resulting layout of the
+/// interleaved MCUs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SamplingLayout {
+    pub luma_factors: SamplingFactors,
+    pub chroma_factors: SamplingFactors,
+}
+
+impl SamplingLayout {
+    /// Creates a layout of custom factors. The luma factors must be multiples of the chroma
+    /// factors and an MCU must not hold more than 10 blocks.
+    pub fn new(
+        luma_factors: SamplingFactors,
+        chroma_factors: SamplingFactors,
+    ) -> crate::Result<Self> {
+        if !luma_factors
+            .horizontal
+            .is_multiple_of(chroma_factors.horizontal)
+            || !luma_factors
+                .vertical
+                .is_multiple_of(chroma_factors.vertical)
+        {
+            return Err(Error::InvalidSamplingFactors(
+                chroma_factors.horizontal,
+                chroma_factors.vertical,
+            ));
+        }
+        let layout = Self {
+            luma_factors,
+            chroma_factors,
+        };
+        if layout.blocks_per_mcu() > MAX_BLOCKS_PER_MCU {
+            return Err(Error::TooManyBlocksPerMCU(layout.blocks_per_mcu()));
+        }
+        Ok(layout)
+    }
+
+    /// Width of an MCU in pixels.
+    pub fn mcu_width(&self) -> u16 {
+        self.luma_factors.horizontal as u16 * 8
+    }
+
+    /// Height of an MCU in pixels.
+    pub fn mcu_height(&self) -> u16 {
+        self.luma_factors.vertical as u16 * 8
+    }
+
+    pub fn blocks_per_mcu(&self) -> usize {
+        self.luma_factors.blocks() + 2 * self.chroma_factors.blocks()
+    }
+
+    /// Names of the blocks of one MCU in the order of the interleaved scan. The blocks of a
+    /// component are numbered row by row (A.2.3).
+    pub fn block_order(&self) -> Vec<String> {
+        let component_blocks = |name: &str, factors: SamplingFactors| {
+            (0..factors.blocks())
+                .map(|index| format!("{}{}", name, index))
+                .collect::<Vec<_>>()
+        };
+        let mut order = component_blocks("Y", self.luma_factors);
+        order.extend(component_blocks("Cb", self.chroma_factors));
+        order.extend(component_blocks("Cr", self.chroma_factors));
+        order
+    }
+}
+
+impl Display for SamplingLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let component = |factors: SamplingFactors| {
+            format!(
+                "{}x{} (0x{:02X})",
+                factors.horizontal,
+                factors.vertical,
+                factors.to_byte()
+            )
+        };
+        write!(
+            f,
+            "Y {}, Cb {}, Cr {}, MCU {}x{} pixels with {} blocks: {}",
+            component(self.luma_factors),
+            component(self.chroma_factors),
+            component(self.chroma_factors),
+            self.mcu_width(),
+            self.mcu_height(),
+            self.blocks_per_mcu(),
+            self.block_order().join(" ")
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SubsamplingMethod {
+    /// Takes the top left sample of each area.
+    Skip,
+    /// Takes the sample nearest to the position of the chroma sample, which is the center of
+    /// the area or its top left sample, depending on the siting.
+    Nearest,
+    /// Box filter, the mean of the samples of each area.
+    #[default]
+    Average,
+    /// Tent filter of twice the width of the area, which weights the samples by their distance
+    /// to its center and overlaps the neighbouring areas. Less aliasing than the box filter on
+    /// sharp colored edges.
+    Triangle,
+    /// Gaussian filter with a standard deviation of half the subsampling rate, which is the
+    /// smoothest and suppresses aliasing best.
+    Gaussian,
+    /// Median of the samples of each area, which ignores speckle noise and keeps thin colored
+    /// lines of UI screenshots instead of smearing them into their surroundings.
+    Median,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for SubsamplingMethod {
+    fn value_variants<'a>() -> &'a [Self] {
+        Self::variants()
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()))
+    }
+}
+
+impl SubsamplingMethod {
+    pub fn variants() -> &'static [Self] {
+        &[
+            Self::Skip,
+            Self::Nearest,
+            Self::Average,
+            Self::Triangle,
+            Self::Gaussian,
+            Self::Median,
+        ]
+    }
+
+    /// Name of the method, as accepted on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::Nearest => "nearest",
+            Self::Average => "average",
+            Self::Triangle => "triangle",
+            Self::Gaussian => "gaussian",
+            Self::Median => "median",
+        }
+    }
+}
+
+/// Position of the subsampled chroma samples relative to the luma samples of their area.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ChromaSiting {
+    /// In the center of the area, as JFIF specifies.
+    #[default]
+    Centered,
+    /// On the top left luma sample of the area, as in most video formats. The filters are
+    /// centered on that sample, so images from video content keep their chroma positions.
+    CoSited,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for ChromaSiting {
+    fn value_variants<'a>() -> &'a [Self] {
+        Self::variants()
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()))
+    }
+}
+
+impl ChromaSiting {
+    pub fn variants() -> &'static [Self] {
+        &[Self::Centered, Self::CoSited]
+    }
+
+    /// Name of the siting, as accepted on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Centered => "centered",
+            Self::CoSited => "co-sited",
+        }
+    }
+
+    /// Offset of the sample nearest to the chroma sample from the first sample of the area. The
+    /// center of an area of an even rate lies between two samples, the latter is taken.
+    fn nearest_offset(&self, rate: u16) -> i32 {
+        match self {
+            Self::Centered => rate as i32 / 2,
+            Self::CoSited => 0,
+        }
+    }
+
+    /// Offset of the averaged window from the first sample of the area. Windows of an even
+    /// rate cannot be centered on a sample, so co-sited windows start half a sample early.
+    fn window_offset(&self, rate: u16) -> i32 {
+        match self {
+            Self::Centered => 0,
+            Self::CoSited => -(rate as i32 / 2),
+        }
+    }
+}
+
+/// Weights of a filter along one dimension. The first weight belongs to the sample `offset`
+/// samples from the first sample of the subsampled area. The weights of the triangle and Gaussian
+/// filters are normalized, the box filter of the average weights each sample with 1 and the sum
+/// is divided by the number of samples.
+struct Kernel {
+    offset: i32,
+    weights: Vec<f32>,
+}
+
+impl Kernel {
+    /// Kernel of the filter method for the rate, `None` for the methods, which pick single
+    /// samples. A rate of 1 keeps the samples unchanged.
+    fn new(method: SubsamplingMethod, rate: u16, siting: ChromaSiting) -> Option<Self> {
+        match method {
+            SubsamplingMethod::Skip | SubsamplingMethod::Nearest | SubsamplingMethod::Median => {
+                return None
+            }
+            SubsamplingMethod::Average => {
+                return Some(Self {
+                    offset: siting.window_offset(rate),
+                    weights: vec![1.0; rate as usize],
+                })
+            }
+            _ => {}
+        }
+        // twice the distance of the samples to the position of the chroma sample, which is a
+        // whole number
+        let doubled_center = match siting {
+            ChromaSiting::Centered => rate as i32 - 1,
+            ChromaSiting::CoSited => 0,
+        };
+        let doubled_distance = move |index: i32| (2 * index - doubled_center).abs() as f32;
+        let rate = rate as i32;
+        let weight: Box<dyn Fn(i32) -> f32> = match method {
+            SubsamplingMethod::Skip
+            | SubsamplingMethod::Nearest
+            | SubsamplingMethod::Average
+            | SubsamplingMethod::Median => unreachable!(),
+            _ if rate == 1 => Box::new(|index| if index == 0 { 1.0 } else { 0.0 }),
+            SubsamplingMethod::Triangle => {
+                Box::new(move |index| (2.0 * rate as f32 - doubled_distance(index)).max(0.0))
+            }
+            SubsamplingMethod::Gaussian => Box::new(move |index| {
+                let distance = doubled_distance(index) / 2.0;
+                let sigma = rate as f32 / 2.0;
+                if distance < 3.0 * sigma {
+                    (-distance * distance / (2.0 * sigma * sigma)).exp()
+                } else {
+                    0.0
+                }
+            }),
+        };
+        let taps: Vec<(i32, f32)> = (-2 * rate..2 * rate)
+            .map(|index| (index, weight(index)))
+            .filter(|&(_, weight)| weight > 0.0)
+            .collect();
+        let sum: f32 = taps.iter().map(|&(_, weight)| weight).sum();
+        Some(Self {
+            offset: taps[0].0,
+            weights: taps.iter().map(|&(_, weight)| weight / sum).collect(),
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SubsamplingConfig {
+    /// vertical subsampling rate
+    pub vertical_rate: u16,
+    /// horizontal subsampling rate
+    pub horizontal_rate: u16,
+    /// how to sample the image
+    pub method: SubsamplingMethod,
+    /// position of the samples in their area
+    pub siting: ChromaSiting,
+}
+
+impl From<ChromaSubsamplingPreset> for SubsamplingConfig {
+    fn from(value: ChromaSubsamplingPreset) -> Self {
+        Self {
+            vertical_rate: value.vertical_rate() as u16,
+            horizontal_rate: value.horizontal_rate() as u16,
+            method: value.method(),
+            siting: ChromaSiting::default(),
+        }
+    }
+}
+
+pub struct Subsampler<'a, T> {
+    color_channel: &'a ColorChannel<T>,
+    subsampling_config: &'a SubsamplingConfig,
+    /// horizontal and vertical kernel of the filters and the average
+    kernels: Option<(Kernel, Kernel)>,
+    /// number of samples, which the box filter of the average sums up, 1 for the other methods
+    divisor: u16,
+}
+
+impl<'a, T> Subsampler<'a, T> {
+    pub fn new(
+        color_channel: &'a ColorChannel<T>,
+        subsampling_config: &'a SubsamplingConfig,
+    ) -> Self {
+        let method = subsampling_config.method;
+        let siting = subsampling_config.siting;
+        let kernels = Kernel::new(method, subsampling_config.horizontal_rate, siting).zip(
+            Kernel::new(method, subsampling_config.vertical_rate, siting),
+        );
+        let divisor = match method {
+            SubsamplingMethod::Average => {
+                subsampling_config.horizontal_rate * subsampling_config.vertical_rate
+            }
+            _ => 1,
+        };
+        Self {
+            color_channel,
+            subsampling_config,
+            kernels,
+            divisor,
+        }
+    }
+}
+
+impl<'a, T> Subsampler<'a, T>
+where
+    T: Sized
+        + Copy
+        + AddAssign
+        + DivAssign
+        + Sum
+        + From<u16>
+        + From<f32>
+        + Div
+        + Div<Output = T>
+        + Mul<Output = T>
+        + PartialOrd,
+{
+    /// Dots of the row, which is clamped to the rows of the channel, so the rows beyond the
+    /// borders repeat the border rows.
+    fn clamped_row(&self, row_index: i32) -> &[T] {
+        let width = self.color_channel.width as usize;
+        let row_index = row_index.clamp(0, self.color_channel.height as i32 - 1) as usize;
+        &self.color_channel.dots[row_index * width..(row_index + 1) * width]
+    }
+
+    /// Offsets of the picked sample from the first sample of the area.
+    fn picked_offsets(&self) -> (i32, i32) {
+        let config = self.subsampling_config;
+        match config.method {
+            SubsamplingMethod::Nearest => (
+                config.siting.nearest_offset(config.horizontal_rate),
+                config.siting.nearest_offset(config.vertical_rate),
+            ),
+            _ => (0, 0),
+        }
+    }
+
+    /// Sample of the area at the column and row, which the skipping or nearest method picks.
+    fn picked(&self, column_index: u16, row_index: u16) -> T {
+        let (column_offset, row_offset) = self.picked_offsets();
+        let row = self.clamped_row(row_index as i32 + row_offset);
+        let last_column_index = self.color_channel.width as i32 - 1;
+        row[(column_index as i32 + column_offset).clamp(0, last_column_index) as usize]
+    }
+
+    /// Median of the samples of the window of the area at the column and row, the mean of the
+    /// two middle samples for windows of an even number of samples. `samples` is a buffer,
+    /// which is reused for all windows.
+    fn median(&self, column_index: u16, row_index: u16, samples: &mut Vec<T>) -> T {
+        let config = self.subsampling_config;
+        let first_column_index =
+            column_index as i32 + config.siting.window_offset(config.horizontal_rate);
+        let first_row_index = row_index as i32 + config.siting.window_offset(config.vertical_rate);
+        let last_column_index = self.color_channel.width as i32 - 1;
+        samples.clear();
+        for y in 0..config.vertical_rate as i32 {
+            let row = self.clamped_row(first_row_index + y);
+            for x in 0..config.horizontal_rate as i32 {
+                samples.push(row[(first_column_index + x).clamp(0, last_column_index) as usize]);
+            }
+        }
+        samples.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(cmp::Ordering::Equal));
+        let middle = samples.len() / 2;
+        if samples.len() % 2 == 1 {
+            return samples[middle];
+        }
+        let mut sum = samples[middle - 1];
+        sum += samples[middle];
+        sum / T::from(2)
+    }
+
+    /// Sum of the samples around the area at the column and row, weighted by the kernels. The
+    /// samples beyond the borders repeat the border samples.
+    fn filtered(&self, column_index: u16, row_index: u16) -> T {
+        let (horizontal_kernel, vertical_kernel) =
+            self.kernels.as_ref().expect("Filter methods have kernels");
+        let last_column_index = self.color_channel.width as i32 - 1;
+        let mut sum = T::from(0.0);
+        for (y, &vertical_weight) in vertical_kernel.weights.iter().enumerate() {
+            let row = self.clamped_row(row_index as i32 + vertical_kernel.offset + y as i32);
+            let mut row_sum = T::from(0.0);
+            for (x, &horizontal_weight) in horizontal_kernel.weights.iter().enumerate() {
+                let column = column_index as i32 + horizontal_kernel.offset + x as i32;
+                row_sum +=
+                    row[column.clamp(0, last_column_index) as usize] * T::from(horizontal_weight);
+            }
+            sum += row_sum * T::from(vertical_weight);
+        }
+        sum / T::from(self.divisor)
+    }
+
+    /// Width and height of the subsampled channel.
+    fn subsampled_size(&self) -> (usize, usize) {
+        (
+            (self.color_channel.width / self.subsampling_config.horizontal_rate) as usize,
+            (self.color_channel.height / self.subsampling_config.vertical_rate) as usize,
+        )
+    }
+
+    /// Subsamples a whole row of the subsampled channel into `output`. The kernel is applied to
+    /// the rows of the channel first, which accumulates them into `column_sums` element by
+    /// element, so the loop is vectorized, and to the columns of the sums afterwards.
+    /// `column_sums` holds a value per column of the channel, `samples` is the buffer of the
+    /// median.
+    fn subsample_row(
+        &self,
+        row_index: usize,
+        column_sums: &mut [T],
+        samples: &mut Vec<T>,
+        output: &mut [T],
+    ) {
+        let horizontal_rate = self.subsampling_config.horizontal_rate as usize;
+        let vertical_rate = self.subsampling_config.vertical_rate as usize;
+        match self.subsampling_config.method {
+            SubsamplingMethod::Skip | SubsamplingMethod::Nearest => {
+                let (column_offset, row_offset) = self.picked_offsets();
+                let row = self.clamped_row((row_index * vertical_rate) as i32 + row_offset);
+                let picked_dots = row
+                    .iter()
+                    .skip(column_offset as usize)
+                    .step_by(horizontal_rate);
+                for (value, &dot) in output.iter_mut().zip(picked_dots) {
+                    *value = dot;
+                }
+                return;
+            }
+            SubsamplingMethod::Median => {
+                for (column_index, value) in output.iter_mut().enumerate() {
+                    *value = self.median(
+                        (column_index * horizontal_rate) as u16,
+                        (row_index * vertical_rate) as u16,
+                        samples,
+                    );
+                }
+                return;
+            }
+            _ => {}
+        }
+        let (horizontal_kernel, vertical_kernel) =
+            self.kernels.as_ref().expect("Filter methods have kernels");
+        column_sums.fill(T::from(0.0));
+        let first_row_index = (row_index * vertical_rate) as i32 + vertical_kernel.offset;
+        for (y, &weight) in vertical_kernel.weights.iter().enumerate() {
+            let weight = T::from(weight);
+            let row = self.clamped_row(first_row_index + y as i32);
+            for (sum, &dot) in column_sums.iter_mut().zip(row) {
+                *sum += dot * weight;
+            }
+        }
+        let last_column_index = column_sums.len() as i32 - 1;
+        let divisor = T::from(self.divisor);
+        for (column_index, value) in output.iter_mut().enumerate() {
+            let first_column_index =
+                (column_index * horizontal_rate) as i32 + horizontal_kernel.offset;
+            let mut sum = T::from(0.0);
+            for (x, &weight) in horizontal_kernel.weights.iter().enumerate() {
+                let column = (first_column_index + x as i32).clamp(0, last_column_index);
+                sum += column_sums[column as usize] * T::from(weight);
+            }
+            *value = sum / divisor;
+        }
+    }
+
+    /// Number of bands of `square_size` rows of the subsampled channel.
+    fn number_of_bands(&self, square_size: usize) -> usize {
+        self.subsampled_size().1.div_ceil(square_size)
+    }
+
+    pub fn subsampling_iter(&'a self) -> ChannelRowView<'a, T> {
+        ChannelRowView {
+            subsampler: self,
+            subsampling_config: self.subsampling_config,
+            row_index: 0,
+        }
+    }
+
+    /// Subsamples the channel and resorts the samples into squares of `square_size` samples in
+    /// each dimension, which follow each other from left to right and top to bottom.
+    pub fn subsample_to_square_structure(&self, square_size: usize) -> Vec<T> {
+        let mut samples = Vec::new();
+        self.subsample_rows_into_square_structure(
+            0..self.subsampled_size().1,
+            square_size,
+            &mut samples,
+        );
+        samples
+    }
+
+    /// Subsamples the rows of the subsampled channel in the range, which holds whole squares
+    /// unless it ends with the channel, resorts them into squares like
+    /// [`Subsampler::subsample_to_square_structure`] and appends them to the samples, so the
+    /// caller can reuse their buffer. Each band of `square_size` rows holds squares, which
+    /// follow each other from left to right. The rows beyond the range are only read by the
+    /// filters, so a band of a larger image with enough rows around it is subsampled like the
+    /// same rows of the whole image.
+    pub fn subsample_rows_into_square_structure(
+        &self,
+        rows: Range<usize>,
+        square_size: usize,
+        samples: &mut Vec<T>,
+    ) {
+        let (width, height) = self.subsampled_size();
+        let rows = rows.start..cmp::min(rows.end, height);
+        let mut column_sums = vec![T::from(0.0); self.color_channel.width as usize];
+        let mut area_samples = Vec::new();
+        let mut row = vec![T::from(0.0); width];
+        let first_sample_index = samples.len();
+        samples.resize(first_sample_index + width * rows.len(), T::from(0.0));
+        for first_row_index in rows.clone().step_by(square_size) {
+            let number_of_rows = cmp::min(square_size, rows.end - first_row_index);
+            let band_start = first_sample_index + (first_row_index - rows.start) * width;
+            let band = &mut samples[band_start..band_start + width * number_of_rows];
+            for y in 0..number_of_rows {
+                self.subsample_row(
+                    first_row_index + y,
+                    &mut column_sums,
+                    &mut area_samples,
+                    &mut row,
+                );
+                for (column_index, &value) in row.iter().enumerate() {
+                    let square_index = column_index / square_size;
+                    let x = column_index % square_size;
+                    band[square_index * square_size * number_of_rows + y * square_size + x] = value;
+                }
+            }
+        }
+    }
+}
+
+/// Subsamples the channel like [`Subsampler::subsample_to_square_structure`], but splits the
+/// bands of rows into a range per thread of the pool. The function blocks until all bands have
+/// been subsampled.
+pub fn subsample_to_square_structure_on_threadpool<T>(
+    color_channel: Arc<ColorChannel<T>>,
+    subsampling_config: SubsamplingConfig,
+    square_size: usize,
+    threadpool: &ThreadPool,
+) -> Vec<T>
+where
+    T: Sized
+        + Copy
+        + AddAssign
+        + DivAssign
+        + Sum
+        + From<u16>
+        + From<f32>
+        + Div
+        + Div<Output = T>
+        + Mul<Output = T>
+        + PartialOrd
+        + Send
+        + Sync
+        + 'static,
+{
+    let number_of_bands =
+        Subsampler::new(&color_channel, &subsampling_config).number_of_bands(square_size);
+    let bands_per_job = number_of_bands.div_ceil(threadpool.max_count()).max(1);
+    let (sender, receiver) = mpsc::channel();
+    let mut number_of_jobs = 0;
+    for (job_index, first_band_index) in (0..number_of_bands).step_by(bands_per_job).enumerate() {
+        let bands = first_band_index..cmp::min(first_band_index + bands_per_job, number_of_bands);
+        let color_channel = Arc::clone(&color_channel);
+        let sender = sender.clone();
+        threadpool.execute(move || {
+            let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+            let mut samples = Vec::new();
+            subsampler.subsample_rows_into_square_structure(
+                bands.start * square_size..bands.end * square_size,
+                square_size,
+                &mut samples,
+            );
+            drop(color_channel);
+            sender
+                .send((job_index, samples))
+                .expect("Receiver of subsampled bands was dropped");
+        });
+        number_of_jobs += 1;
+    }
+    drop(sender);
+    let mut jobs: Vec<Option<Vec<T>>> = (0..number_of_jobs).map(|_| None).collect();
+    for (job_index, samples) in receiver.iter() {
+        jobs[job_index] = Some(samples);
+    }
+    jobs.into_iter()
+        .flat_map(|samples| samples.expect("Subsampling of bands failed"))
+        .collect()
+}
+
+/// a potentially subsampled image iterator
+pub struct ChannelRowView<'a, T> {
+    subsampling_config: &'a SubsamplingConfig,
+    row_index: u16,
+    subsampler: &'a Subsampler<'a, T>,
+}
+
+impl<'a, T> Iterator for ChannelRowView<'a, T> {
+    type Item = ChannelColumnView<'a, T>;
+
+    fn nth(&mut self, n: usize) -> Option<ChannelColumnView<'a, T>> {
+        self.row_index += self.subsampling_config.vertical_rate * n as u16;
+        if self.row_index >= self.subsampler.color_channel.height {
+            return None;
+        }
+        let return_value = ChannelColumnView {
+            subsampling_config: self.subsampling_config,
+            column_index: 0,
+            row_index: self.row_index,
+            subsampler: self.subsampler,
+            samples: Vec::new(),
+        };
+        self.row_index += self.subsampling_config.vertical_rate;
+        Some(return_value)
+    }
+
+    fn next(&mut self) -> Option<ChannelColumnView<'a, T>> {
+        self.nth(0)
+    }
+}
+
+pub struct ChannelColumnView<'a, T> {
+    subsampling_config: &'a SubsamplingConfig,
+    column_index: u16,
+    row_index: u16,
+    subsampler: &'a Subsampler<'a, T>,
+    /// buffer of the median
+    samples: Vec<T>,
+}
+
+impl<T> Iterator for ChannelColumnView<'_, T>
+where
+    T: Sized
+        + Copy
+        + AddAssign
+        + DivAssign
+        + Sum
+        + From<u16>
+        + From<f32>
+        + Div
+        + Div<Output = T>
+        + Mul<Output = T>
+        + PartialOrd,
+{
+    type Item = T;
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.column_index += self.subsampling_config.horizontal_rate * n as u16;
+        if self.column_index >= self.subsampler.color_channel.width {
+            return None;
+        }
+        let return_value = match self.subsampling_config.method {
+            SubsamplingMethod::Skip | SubsamplingMethod::Nearest => {
+                self.subsampler.picked(self.column_index, self.row_index)
+            }
+            SubsamplingMethod::Median => {
+                self.subsampler
+                    .median(self.column_index, self.row_index, &mut self.samples)
+            }
+            _ => self.subsampler.filtered(self.column_index, self.row_index),
+        };
+        self.column_index += self.subsampling_config.horizontal_rate;
+        Some(return_value)
+    }
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.nth(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use threadpool::ThreadPool;
+
+    use super::{
+        subsample_to_square_structure_on_threadpool, ChromaSiting, ChromaSubsamplingPreset,
+        ColorChannel, Kernel, SamplingFactors, SamplingLayout, Subsampler, SubsamplingConfig,
+        SubsamplingMethod,
+    };
+    use crate::Error;
+
+    #[rustfmt::skip]
+    const TEST_CHANNEL_ONE: &[f32] = &[
+         1.0,  2.0,  3.0,  4.0,
+         5.0,  6.0,  7.0,  8.0,
+         9.0, 10.0, 11.0, 12.0,
+        13.0, 14.0, 15.0, 16.0,
+    ];
+
+    const TEST_CHANNEL_TWO: &[f32; 64] = &[
+        1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+        17.0, 18.0, 19.0, 20.0, 21.0, 22.0, 23.0, 24.0, 25.0, 26.0, 27.0, 28.0, 29.0, 30.0, 31.0,
+        32.0, 33.0, 34.0, 35.0, 36.0, 37.0, 38.0, 39.0, 40.0, 41.0, 42.0, 43.0, 44.0, 45.0, 46.0,
+        47.0, 48.0, 49.0, 50.0, 51.0, 52.0, 53.0, 54.0, 55.0, 56.0, 57.0, 58.0, 59.0, 60.0, 61.0,
+        62.0, 63.0, 64.0,
+    ];
+
+    #[test]
+    fn sampling_layout_of_presets() {
+        let layout = ChromaSubsamplingPreset::P420.sampling_layout();
+        assert_eq!(layout.luma_factors.to_byte(), 0x22);
+        assert_eq!(layout.chroma_factors.to_byte(), 0x11);
+        assert_eq!((layout.mcu_width(), layout.mcu_height()), (16, 16));
+        assert_eq!(layout.blocks_per_mcu(), 6);
+        assert_eq!(
+            layout.to_string(),
+            "Y 2x2 (0x22), Cb 1x1 (0x11), Cr 1x1 (0x11), MCU 16x16 pixels with 6 blocks: Y0 Y1 Y2 Y3 Cb0 Cr0"
+        );
+        let layout = ChromaSubsamplingPreset::P422.sampling_layout();
+        assert_eq!(layout.luma_factors.to_byte(), 0x21);
+        assert_eq!((layout.mcu_width(), layout.mcu_height()), (16, 8));
+        assert_eq!(ChromaSubsamplingPreset::P444.blocks_per_mcu(), 3);
+        let layout = ChromaSubsamplingPreset::P411.sampling_layout();
+        assert_eq!(layout.luma_factors.to_byte(), 0x41);
+        assert_eq!((layout.mcu_width(), layout.mcu_height()), (32, 8));
+        let layout = ChromaSubsamplingPreset::P440.sampling_layout();
+        assert_eq!(layout.luma_factors.to_byte(), 0x12);
+        assert_eq!((layout.mcu_width(), layout.mcu_height()), (8, 16));
+        assert_eq!(layout.blocks_per_mcu(), 4);
+    }
+
+    #[test]
+    fn validate_custom_sampling_factors() {
+        let factors = |h, v| SamplingFactors::new(h, v).unwrap();
+        assert!(matches!(
+            SamplingFactors::new(5, 1),
+            Err(Error::InvalidSamplingFactors(5, 1))
+        ));
+        assert!(matches!(
+            SamplingLayout::new(factors(3, 1), factors(2, 1)),
+            Err(Error::InvalidSamplingFactors(2, 1))
+        ));
+        assert!(SamplingLayout::new(factors(4, 2), factors(1, 1)).is_ok());
+        assert!(matches!(
+            SamplingLayout::new(factors(4, 4), factors(2, 2)),
+            Err(Error::TooManyBlocksPerMCU(24))
+        ));
+        let layout = SamplingLayout::new(factors(4, 1), factors(1, 1)).unwrap();
+        assert_eq!(layout.block_order().len(), 6);
+        assert_eq!((layout.mcu_width(), layout.mcu_height()), (32, 8));
+    }
+
+    #[test]
+    fn parse_subsampling_presets_and_rates() {
+        let parse = |value: &str| value.parse::<ChromaSubsamplingPreset>();
+        assert_eq!(parse("p411"), Ok(ChromaSubsamplingPreset::P411));
+        assert_eq!(parse("4:4:4"), Ok(ChromaSubsamplingPreset::P444));
+        assert_eq!(parse("4:2:0"), Ok(ChromaSubsamplingPreset::P420));
+        assert_eq!(parse("4:4:0"), Ok(ChromaSubsamplingPreset::P440));
+        assert_eq!(parse("1x2"), Ok(ChromaSubsamplingPreset::P440));
+        let custom = parse("1x3").unwrap();
+        assert_eq!(custom.name(), "1x3");
+        let layout = custom.sampling_layout();
+        assert_eq!(layout.luma_factors.to_byte(), 0x13);
+        assert_eq!(layout.chroma_factors.to_byte(), 0x11);
+        assert_eq!(
+            parse("3:1:1").ok(),
+            ChromaSubsamplingPreset::custom(3, 1).ok()
+        );
+        assert!(parse("5x1").is_err());
+        assert!(parse("4x3").is_err());
+        assert!(parse("4:3:3").is_err());
+        assert!(parse("4:2:1").is_err());
+        assert!(parse("P421").is_err());
+    }
+
+    #[test]
+    fn no_subsampling_test() {
+        let color_channel = ColorChannel {
+            dots: Vec::from(TEST_CHANNEL_ONE),
+            width: 4,
+            height: 4,
+        };
+
+        let subsampling_config = SubsamplingConfig {
+            horizontal_rate: 1,
+            vertical_rate: 1,
+            method: SubsamplingMethod::Skip,
+            siting: ChromaSiting::Centered,
+        };
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        let mut my_itr = subsampler.subsampling_iter();
+
+        let val = my_itr
+            .nth(1)
+            .expect("image should have 4 rows")
+            .nth(2)
+            .expect("image should have 4 columns");
+        assert_eq!(val, 7.0);
+    }
+
+    #[test]
+    fn skip_subsampling_test() {
+        let color_channel = ColorChannel {
+            dots: Vec::from(TEST_CHANNEL_ONE),
+            width: 4,
+            height: 4,
+        };
+        let subsampling_config = SubsamplingConfig {
+            horizontal_rate: 2,
+            vertical_rate: 1,
+            method: SubsamplingMethod::Skip,
+            siting: ChromaSiting::Centered,
+        };
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        let mut my_itr = subsampler.subsampling_iter();
+
+        let val = my_itr
+            .nth(1)
+            .expect("image should have 4 rows")
+            .nth(1)
+            .expect("image should have 4 columns");
+        assert_eq!(val, 7.0);
+    }
+
+    #[test]
+    fn average_subsampling_test() {
+        let color_channel = ColorChannel {
+            dots: Vec::from(TEST_CHANNEL_ONE),
+            width: 4,
+            height: 4,
+        };
+        let subsampling_config = SubsamplingConfig {
+            horizontal_rate: 1,
+            vertical_rate: 2,
+            method: SubsamplingMethod::Average,
+            siting: ChromaSiting::Centered,
+        };
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        let mut my_itr = subsampler.subsampling_iter();
+
+        let val = my_itr
+            .nth(1)
+            .expect("image should have 4 rows")
+            .nth(1)
+            .expect("image should have 4 columns");
+        assert_eq!(val, 12.0);
+    }
+
+    #[test]
+    fn filter_kernels() {
+        let kernel = Kernel::new(SubsamplingMethod::Triangle, 2, ChromaSiting::Centered)
+            .expect("Triangle has a kernel");
+        assert_eq!(kernel.offset, -1);
+        assert_eq!(kernel.weights, vec![0.125, 0.375, 0.375, 0.125]);
+        for method in [SubsamplingMethod::Triangle, SubsamplingMethod::Gaussian] {
+            let kernel =
+                Kernel::new(method, 1, ChromaSiting::Centered).expect("Filters have kernels");
+            assert_eq!((kernel.offset, kernel.weights), (0, vec![1.0]));
+            let kernel =
+                Kernel::new(method, 4, ChromaSiting::Centered).expect("Filters have kernels");
+            let sum: f32 = kernel.weights.iter().sum();
+            assert!(
+                (sum - 1.0).abs() < 1e-6,
+                "Weights of {:?} sum to {}",
+                method,
+                sum
+            );
+            assert!(
+                kernel.weights.iter().eq(kernel.weights.iter().rev()),
+                "Kernel of {:?} is not symmetric around the area",
+                method
+            );
+        }
+        assert!(Kernel::new(SubsamplingMethod::Skip, 2, ChromaSiting::Centered).is_none());
+    }
+
+    #[test]
+    fn triangle_subsampling_test() {
+        let color_channel = ColorChannel {
+            dots: Vec::from(TEST_CHANNEL_ONE),
+            width: 4,
+            height: 4,
+        };
+        let subsampling_config = SubsamplingConfig {
+            horizontal_rate: 2,
+            vertical_rate: 1,
+            method: SubsamplingMethod::Triangle,
+            siting: ChromaSiting::Centered,
+        };
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        let mut my_itr = subsampler.subsampling_iter();
+
+        let val = my_itr
+            .nth(1)
+            .expect("image should have 4 rows")
+            .nth(1)
+            .expect("image should have 2 columns");
+        // 6, 7, 8 and the repeated border 8
+        assert_eq!(val, (6.0 + 3.0 * 7.0 + 3.0 * 8.0 + 8.0) / 8.0);
+    }
+
+    #[test]
+    fn co_sited_subsampling_test() {
+        let kernel = Kernel::new(SubsamplingMethod::Triangle, 2, ChromaSiting::CoSited)
+            .expect("Triangle has a kernel");
+        assert_eq!((kernel.offset, kernel.weights), (-1, vec![0.25, 0.5, 0.25]));
+
+        let color_channel = ColorChannel {
+            dots: Vec::from(TEST_CHANNEL_ONE),
+            width: 4,
+            height: 4,
+        };
+        let subsampling_config = SubsamplingConfig {
+            horizontal_rate: 2,
+            vertical_rate: 1,
+            method: SubsamplingMethod::Average,
+            siting: ChromaSiting::CoSited,
+        };
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        let row: Vec<f32> = subsampler
+            .subsampling_iter()
+            .nth(1)
+            .expect("image should have 4 rows")
+            .collect();
+        // the window of the first column repeats the left border
+        assert_eq!(row, vec![5.0, 6.5]);
+    }
+
+    #[test]
+    fn nearest_subsampling_test() {
+        let color_channel = ColorChannel {
+            dots: Vec::from(TEST_CHANNEL_ONE),
+            width: 4,
+            height: 4,
+        };
+        let mut subsampling_config = SubsamplingConfig {
+            horizontal_rate: 2,
+            vertical_rate: 2,
+            method: SubsamplingMethod::Nearest,
+            siting: ChromaSiting::Centered,
+        };
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        assert_eq!(
+            subsampler.subsample_to_square_structure(2),
+            vec![6.0, 8.0, 14.0, 16.0]
+        );
+        subsampling_config.siting = ChromaSiting::CoSited;
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        assert_eq!(
+            subsampler.subsample_to_square_structure(2),
+            vec![1.0, 3.0, 9.0, 11.0]
+        );
+    }
+
+    #[test]
+    fn median_subsampling_test() {
+        // a thin line and a speckle within a flat area
+        #[rustfmt::skip]
+        let dots = vec![
+            10.0, 10.0, 10.0, 90.0,
+            90.0, 90.0, 10.0, 10.0,
+            10.0, 10.0, 10.0, 10.0,
+            10.0, 10.0, 10.0, 10.0,
+        ];
+        let color_channel = ColorChannel {
+            dots,
+            width: 4,
+            height: 4,
+        };
+        let subsampling_config = SubsamplingConfig {
+            horizontal_rate: 2,
+            vertical_rate: 2,
+            method: SubsamplingMethod::Median,
+            siting: ChromaSiting::Centered,
+        };
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        // the line fills half of the first area, so the middle samples are 10 and 90
+        assert_eq!(
+            subsampler.subsample_to_square_structure(2),
+            vec![50.0, 10.0, 10.0, 10.0]
+        );
+        let subsampling_config = SubsamplingConfig {
+            horizontal_rate: 3,
+            vertical_rate: 1,
+            ..subsampling_config
+        };
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        let first_row: Vec<f32> = subsampler
+            .subsampling_iter()
+            .next()
+            .expect("image should have rows")
+            .collect();
+        assert_eq!(first_row, vec![10.0, 90.0]);
+    }
+
+    #[test]
+    fn gaussian_subsampling_keeps_flat_channel() {
+        let color_channel = ColorChannel {
+            dots: vec![42.0f32; 64],
+            width: 8,
+            height: 8,
+        };
+        let subsampling_config = SubsamplingConfig {
+            horizontal_rate: 2,
+            vertical_rate: 2,
+            method: SubsamplingMethod::Gaussian,
+            siting: ChromaSiting::Centered,
+        };
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        for value in subsampler.subsample_to_square_structure(4) {
+            assert!((value - 42.0).abs() < 1e-4, "Value {} is not flat", value);
+        }
+    }
+
+    #[test]
+    fn subsample_rows_like_single_samples() {
+        let dots: Vec<f32> = (0..32 * 16)
+            .map(|index| ((index * 37) % 251) as f32)
+            .collect();
+        let color_channel = Arc::new(ColorChannel::new(32, 16, dots));
+        let threadpool = ThreadPool::new(3);
+        for &method in SubsamplingMethod::variants() {
+            for siting in [ChromaSiting::Centered, ChromaSiting::CoSited] {
+                let subsampling_config = SubsamplingConfig {
+                    horizontal_rate: 2,
+                    vertical_rate: 2,
+                    method,
+                    siting,
+                };
+                let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+                let squares = subsampler.subsample_to_square_structure(4);
+                // 4 squares per band of 4 rows
+                for (row_index, row) in subsampler.subsampling_iter().enumerate() {
+                    for (column_index, expected) in row.enumerate() {
+                        let index = row_index / 4 * 64
+                            + column_index / 4 * 16
+                            + row_index % 4 * 4
+                            + column_index % 4;
+                        let actual = squares[index];
+                        assert!(
+                            (actual - expected).abs() < 1e-3,
+                            "{:?} sample {} differs from {} with {:?}",
+                            method,
+                            actual,
+                            expected,
+                            siting
+                        );
+                    }
+                }
+                assert_eq!(
+                    subsample_to_square_structure_on_threadpool(
+                        Arc::clone(&color_channel),
+                        subsampling_config,
+                        4,
+                        &threadpool
+                    ),
+                    squares
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_bounds_high() {
+        let color_channel = ColorChannel {
+            dots: Vec::from(TEST_CHANNEL_ONE),
+            width: 4,
+            height: 4,
+        };
+        let subsampling_config = SubsamplingConfig {
+            horizontal_rate: 2,
+            vertical_rate: 1,
+            method: SubsamplingMethod::Average,
+            siting: ChromaSiting::Centered,
+        };
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        let mut my_itr = subsampler.subsampling_iter();
+
+        let option = my_itr.nth(2).expect("image should have 4 rows").nth(2);
+        assert!(option.is_none(), "Read out of bounds should return none");
+    }
+
+    #[test]
+    fn repeat_border_test() {
+        let color_channel = ColorChannel {
+            dots: Vec::from(TEST_CHANNEL_ONE),
+            width: 4,
+            height: 4,
+        };
+        let subsampling_config = SubsamplingConfig {
+            horizontal_rate: 2,
+            vertical_rate: 3,
+            method: SubsamplingMethod::Average,
+            siting: ChromaSiting::Centered,
+        };
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        let mut my_itr = subsampler.subsampling_iter();
+
+        let val = my_itr
+            .nth(1)
+            .expect("image should have 4 rows")
+            .nth(1)
+            .expect("image should have 4 columns");
+        assert_eq!(val, 15.5);
+    }
+
+    #[test]
+    fn test_block_iter_with_single_fit_image() {
+        let color_channel = ColorChannel {
+            dots: Vec::from(TEST_CHANNEL_ONE),
+            width: 4,
+            height: 4,
+        };
+        let subsampling_config = SubsamplingConfig {
+            horizontal_rate: 1,
+            vertical_rate: 1,
+            method: SubsamplingMethod::Skip,
+            siting: ChromaSiting::Centered,
+        };
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        let block = subsampler.subsample_to_square_structure(4);
+        assert_eq!(block.len(), 16, "Block must have 4x4 fields");
+        for (&actual, &expected) in block.iter().zip(TEST_CHANNEL_ONE) {
+            assert_eq!(actual, expected, "Value does not match");
+        }
+    }
+
+    #[test]
+    fn test_square_resorter_with_1x1_subsampling() {
+        let color_channel = ColorChannel {
+            dots: Vec::from(TEST_CHANNEL_TWO),
+            width: 8,
+            height: 8,
+        };
+        let subsampling_config = SubsamplingConfig {
+            horizontal_rate: 1,
+            vertical_rate: 1,
+            method: SubsamplingMethod::Skip,
+            siting: ChromaSiting::Centered,
+        };
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        let resorted_channel = subsampler.subsample_to_square_structure(4);
+        let expected: &[f32; 64] = &[
+            1.0, 2.0, 3.0, 4.0, 9.0, 10.0, 11.0, 12.0, 17.0, 18.0, 19.0, 20.0, 25.0, 26.0, 27.0,
+            28.0, 5.0, 6.0, 7.0, 8.0, 13.0, 14.0, 15.0, 16.0, 21.0, 22.0, 23.0, 24.0, 29.0, 30.0,
+            31.0, 32.0, 33.0, 34.0, 35.0, 36.0, 41.0, 42.0, 43.0, 44.0, 49.0, 50.0, 51.0, 52.0,
+            57.0, 58.0, 59.0, 60.0, 37.0, 38.0, 39.0, 40.0, 45.0, 46.0, 47.0, 48.0, 53.0, 54.0,
+            55.0, 56.0, 61.0, 62.0, 63.0, 64.0,
+        ];
+        assert_eq!(
+            resorted_channel.len(),
+            expected.len(),
+            "Length of resorted channel does not match"
+        );
+        for (&actual, &expected) in resorted_channel.iter().zip(expected) {
+            assert_eq!(actual, expected, "Value does not match");
+        }
+    }
+
+    #[test]
+    fn test_square_resorter_with_2x2_subsampling() {
+        let color_channel = ColorChannel {
+            dots: Vec::from(TEST_CHANNEL_TWO),
+            width: 8,
+            height: 8,
+        };
+        let subsampling_config = SubsamplingConfig {
+            horizontal_rate: 2,
+            vertical_rate: 2,
+            method: SubsamplingMethod::Skip,
+            siting: ChromaSiting::Centered,
+        };
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        let resorted_channel = subsampler.subsample_to_square_structure(4);
+        let expected: &[f32; 16] = &[
+            1.0, 3.0, 5.0, 7.0, 17.0, 19.0, 21.0, 23.0, 33.0, 35.0, 37.0, 39.0, 49.0, 51.0, 53.0,
+            55.0,
+        ];
+        assert_eq!(
+            resorted_channel.len(),
+            expected.len(),
+            "Length of resorted channel does not match"
+        );
+        for (&actual, &expected) in resorted_channel.iter().zip(expected) {
+            assert_eq!(actual, expected, "Value does not match");
+        }
+    }
+
+    #[test]
+    fn test_square_resorter_with_1x2_subsampling() {
+        let color_channel = ColorChannel {
+            dots: Vec::from(TEST_CHANNEL_TWO),
+            width: 8,
+            height: 8,
+        };
+        let subsampling_config = SubsamplingConfig {
+            horizontal_rate: 1,
+            vertical_rate: 2,
+            method: SubsamplingMethod::Skip,
+            siting: ChromaSiting::Centered,
+        };
+        let subsampler = Subsampler::new(&color_channel, &subsampling_config);
+        let resorted_channel = subsampler.subsample_to_square_structure(4);
+        let expected: &[f32; 32] = &[
+            1.0, 2.0, 3.0, 4.0, 17.0, 18.0, 19.0, 20.0, 33.0, 34.0, 35.0, 36.0, 49.0, 50.0, 51.0,
+            52.0, 5.0, 6.0, 7.0, 8.0, 21.0, 22.0, 23.0, 24.0, 37.0, 38.0, 39.0, 40.0, 53.0, 54.0,
+            55.0, 56.0,
+        ];
+        assert_eq!(
+            resorted_channel.len(),
+            expected.len(),
+            "Length of resorted channel does not match"
+        );
+        for (&actual, &expected) in resorted_channel.iter().zip(expected) {
+            assert_eq!(actual, expected, "Value does not match");
+        }
+    }
+}
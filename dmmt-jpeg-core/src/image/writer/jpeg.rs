@@ -0,0 +1,1290 @@
+use std::{
+    io::{self, Write},
+    iter,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+pub mod consts;
+pub mod density;
+mod encoder;
+pub mod exif;
+mod huffman_tables;
+mod padder;
+mod quantization_tables;
+mod segment_marker_injector;
+pub mod thumbnail;
+pub mod timings;
+mod transformer;
+mod tune;
+pub mod xmp;
+
+use density::PixelDensity;
+use encoder::Encoder;
+use exif::ExifMetadata;
+pub use huffman_tables::{parse_huffman_tables, HuffmanTableMode, HuffmanTables};
+pub use quantization_tables::{
+    parse_quantization_table, QuantizationTableMix, QuantizationTablePreset,
+};
+use threadpool::ThreadPool;
+use thumbnail::{Thumbnail, ThumbnailSize};
+use timings::{Stage, StageTimings};
+use transformer::{
+    categorize::CategorizedBlock, sample::SourceDot, CombinedColorChannels, StreamedOutputImage,
+    Transformer,
+};
+pub use transformer::{dead_zone::DeadZone, release_buffers};
+pub use tune::Tune;
+use xmp::XmpPacket;
+
+use crate::{
+    binary_stream::ByteCountingWriter,
+    color::{ColorMatrix, ColorRange, RGBColorFormat, BASELINE_SAMPLE_PRECISION},
+    cosine_transform::DCTAlgorithm,
+    image::{
+        subsampling::{ChromaSiting, ChromaSubsamplingPreset, SubsamplingMethod},
+        CMYKImage, Image, ImageWriter, YCbCrImage,
+    },
+    warning::{Warning, Warnings},
+    Error,
+};
+
+/// Name of the encoder, as embedded into the written files. It is independent of the names of
+/// the packages, so files of the library and of the command line interface are identical.
+pub const ENCODER_NAME: &str = "dmmt-jpeg-encoder";
+
+/// Largest comment, which fits into a single COM segment.
+pub const MAX_COMMENT_LENGTH: usize = u16::MAX as usize - 2;
+
+/// Longest code word of the Huffman tables of baseline JPEG, which the DHT segment can describe.
+pub const MAX_HUFFMAN_CODE_LENGTH: u8 = 16;
+
+/// Shortest limit of the code word length, which still leaves a code word for each of the 162
+/// AC symbols and the reserved code word of only ones.
+pub const MIN_HUFFMAN_CODE_LENGTH_LIMIT: u8 = 8;
+
+/// Largest content of a custom APPn segment.
+pub const MAX_APPLICATION_SEGMENT_LENGTH: usize = u16::MAX as usize - 2;
+
+/// Number of pixels, above which the options, that transform the whole image at once, are
+/// warned about. The coefficients of such an image take at least 96 MiB.
+const MAX_WHOLE_IMAGE_PIXELS: u64 = 1 << 24;
+
+/// Custom APPn segment, which is written after the application segments of the encoder.
+#[derive(Clone)]
+struct ApplicationSegment {
+    marker_number: u8,
+    content: Vec<u8>,
+}
+
+/// Largest step of a table with 8 bit precision, which baseline frames require.
+const MAX_BASELINE_QUANTIZATION_STEP: u32 = u8::MAX as u32;
+/// Largest step of a table with 16 bit precision, as limited by libjpeg.
+const MAX_QUANTIZATION_STEP: u32 = i16::MAX as u32;
+
+/// Whether all steps of the table fit into 8 bit, as required for baseline frames.
+fn is_baseline_table(table: &[u16; 64]) -> bool {
+    table
+        .iter()
+        .all(|&step| step as u32 <= MAX_BASELINE_QUANTIZATION_STEP)
+}
+
+fn scale_table(table: [u16; 64], quality: u8) -> [u16; 64] {
+    let quality = quality.clamp(1, 100) as u32;
+    let scale_factor = if quality < 50 {
+        5000 / quality
+    } else {
+        200 - 2 * quality
+    };
+    let max_step = if is_baseline_table(&table) {
+        MAX_BASELINE_QUANTIZATION_STEP
+    } else {
+        MAX_QUANTIZATION_STEP
+    };
+    table.map(|step| ((step as u32 * scale_factor + 50) / 100).clamp(1, max_step) as u16)
+}
+
+#[derive(Clone, Copy)]
+pub struct QuantizationTablePair {
+    luma_table: [u16; 64],
+    chroma_table: [u16; 64],
+    /// Separate table of the Cr component. Cb and Cr share the chroma table, if unset.
+    chroma_red_table: Option<[u16; 64]>,
+}
+
+impl QuantizationTablePair {
+    /// Table of the Cr component, which is the chroma table, unless a separate one is set.
+    pub fn chroma_red_table(&self) -> &[u16; 64] {
+        self.chroma_red_table.as_ref().unwrap_or(&self.chroma_table)
+    }
+
+    /// Whether all tables fit into 8 bit, as required for baseline frames.
+    pub fn is_baseline(&self) -> bool {
+        is_baseline_table(&self.luma_table)
+            && is_baseline_table(&self.chroma_table)
+            && self.chroma_red_table.as_ref().is_none_or(is_baseline_table)
+    }
+
+    /// Scales all tables with the formula of libjpeg. Quality 50 keeps the tables, lower values
+    /// enlarge and higher values shrink the quantization steps. The steps of baseline tables are
+    /// clamped to the range of baseline tables, those of 16 bit tables to the range of libjpeg.
+    pub fn scaled(self, quality: u8) -> Self {
+        self.scaled_separately(Some(quality), Some(quality))
+    }
+
+    /// Scales the luma table and the chroma tables with separate qualities like
+    /// [`Self::scaled`]. The tables without a quality are kept.
+    pub fn scaled_separately(self, luma_quality: Option<u8>, chroma_quality: Option<u8>) -> Self {
+        let scale = |table: [u16; 64], quality: Option<u8>| match quality {
+            Some(quality) => scale_table(table, quality),
+            None => table,
+        };
+        Self {
+            luma_table: scale(self.luma_table, luma_quality),
+            chroma_table: scale(self.chroma_table, chroma_quality),
+            chroma_red_table: self
+                .chroma_red_table
+                .map(|table| scale(table, chroma_quality)),
+        }
+    }
+}
+
+pub struct JpegTransformationOptions {
+    pub chroma_subsampling_preset: ChromaSubsamplingPreset,
+    /// Filter, which subsamples the chroma channels, instead of the method of the preset. The
+    /// triangle and Gaussian filters reduce the aliasing of sharp colored edges, the median and
+    /// the nearest sample keep thin colored lines sharp.
+    pub chroma_subsampling_method: Option<SubsamplingMethod>,
+    /// Position of the subsampled chroma samples, which the filters are centered on. Co-sited
+    /// chroma keeps the positions of content from video, which is decoded to co-sited chroma.
+    pub chroma_siting: ChromaSiting,
+    /// Luma weights of the color conversion. Frames of HD or UHD video keep their colors with
+    /// the matrix of their standard, which is annotated in a comment segment.
+    pub color_matrix: ColorMatrix,
+    /// Range of the YCbCr samples. The limited range of studio swing suits pipelines, which
+    /// feed the output into video tooling, and is annotated in a comment segment, as JFIF
+    /// decoders assume the full range.
+    pub color_range: ColorRange,
+    /// Drops the chroma channels after the color conversion and writes a frame with the luma
+    /// component only. The chroma subsampling preset is ignored.
+    pub grayscale: bool,
+    pub bits_per_channel: u8,
+    pub quantization_table_preset: QuantizationTablePreset,
+    /// Blend of the tables of two presets, which replaces the tables of the preset, if set.
+    pub quantization_table_mix: Option<QuantizationTableMix>,
+    /// Scales the quantization tables of the preset from 1 (coarsest) to 100 (finest). The
+    /// tables of the preset are used unchanged, if unset.
+    pub quality: Option<u8>,
+    /// Scales the chroma tables, including a separate Cr table, with another quality than the
+    /// luma table, as chroma of photographic content often tolerates a coarser quantization.
+    /// The chroma tables follow the quality, if unset.
+    pub chroma_quality: Option<u8>,
+    /// Separate quantization table of the Cr component in row major order, which is scaled to
+    /// the quality like the tables of the preset. Red chroma often tolerates a coarser
+    /// quantization than blue chroma. Cb and Cr share the chroma table, if unset.
+    pub chroma_red_quantization_table: Option<[u16; 64]>,
+    /// Maximum size of the encoded image in bytes. The highest quality, whose output fits, is
+    /// searched and the set quality is ignored. Quality 1 is used, if no quality fits.
+    pub target_size: Option<u64>,
+    /// Chooses the quantized AC coefficients by trellis quantization, which trades quality
+    /// against the bits of each coefficient like mozjpeg, instead of rounding them. The bits are
+    /// estimated with the Huffman tables of the output, optimized tables need a second pass of
+    /// the quantization. Smaller files at about the same quality, but the quantization is much
+    /// slower.
+    pub trellis_quantization: bool,
+    /// Strength of the adaptive quantization, which coarsens the quantization steps of blocks
+    /// with more texture than the average block, so flat areas and gradients are quantized
+    /// finer than textured areas. The tables stay baseline, the coefficients are rounded to
+    /// multiples of the coarsened steps. 1.0 is a moderate strength.
+    pub adaptive_quantization: Option<f32>,
+    /// Zeroes the quantized AC coefficients below a threshold, which trades slightly lower
+    /// quality for noticeably smaller files. No coefficients are zeroed, if unset.
+    pub dead_zone: Option<DeadZone>,
+    pub dct_algorithm: DCTAlgorithm,
+    /// Converts colors and computes the DCT in double precision instead of single precision,
+    /// see [`DCTAlgorithm::resolve_double_precision`] for the used algorithm.
+    pub high_precision: bool,
+    /// Standard Huffman tables skip the counting of the symbols, which is faster and makes
+    /// the output comparable to other encoders. They are replaced by optimized tables for
+    /// samples of more than 8 bit.
+    pub huffman_table_mode: HuffmanTableMode,
+    /// Tables, which replace those of the Huffman table mode, like the tables of a similar image,
+    /// so the symbols need not be counted. Optimized tables are generated instead, if the
+    /// tables lack a symbol of the image.
+    pub huffman_tables: Option<HuffmanTables>,
+    /// Length limit of the code words of the Huffman tables in bits, from
+    /// [`MIN_HUFFMAN_CODE_LENGTH_LIMIT`] to [`MAX_HUFFMAN_CODE_LENGTH`]. Hardware decoders with
+    /// small lookup tables may need shorter code words at the cost of slightly larger files.
+    /// Optimized tables replace the standard tables below the maximum, as they have code words
+    /// of 16 bits.
+    pub max_huffman_code_length: u8,
+    /// Number of MCUs between two restart markers. No restart markers are written, if unset.
+    pub restart_interval: Option<u16>,
+    pub exif_metadata: ExifMetadata,
+    pub xmp_packet: Option<XmpPacket>,
+    /// Embeds an uncompressed RGB thumbnail of the image into the JFIF APP0 segment.
+    pub thumbnail_size: Option<ThumbnailSize>,
+    /// Pixel density of the JFIF APP0 segment.
+    pub pixel_density: PixelDensity,
+    /// Writes the APP14 segment of Adobe with the color transform flag, which some decoders,
+    /// notably those of PDF toolchains, need to interpret the components as YCbCr.
+    pub adobe_marker: bool,
+    /// Writes a height of 0 into the frame header and the actual height into a DNL segment
+    /// after the scan, as encoders do, which stream images of unknown height.
+    pub define_number_of_lines: bool,
+    /// Packs all quantization tables into a single DQT segment and all Huffman tables into a
+    /// single DHT segment, which saves the marker and length of every further segment.
+    pub combine_table_segments: bool,
+    /// Texts, which are written as COM segments after the application segments, one segment
+    /// per text. Each text must not exceed [`MAX_COMMENT_LENGTH`] bytes.
+    pub comments: Vec<String>,
+    /// Writes the crate version and the encoding options as JSON into a comment segment, so
+    /// the output file tells, how it was produced.
+    pub embed_settings: bool,
+    /// Collects the frequencies of the Huffman symbols of each component as CSV, which is
+    /// available from the [`JpegImageWriter`] after the image was written.
+    pub collect_symbol_stats: bool,
+}
+
+impl Default for JpegTransformationOptions {
+    fn default() -> Self {
+        Self {
+            chroma_subsampling_preset: ChromaSubsamplingPreset::P420,
+            chroma_subsampling_method: None,
+            chroma_siting: ChromaSiting::default(),
+            color_matrix: ColorMatrix::default(),
+            color_range: ColorRange::default(),
+            grayscale: false,
+            bits_per_channel: 8,
+            quantization_table_preset: QuantizationTablePreset::Specification,
+            quantization_table_mix: None,
+            quality: None,
+            chroma_quality: None,
+            chroma_red_quantization_table: None,
+            target_size: None,
+            trellis_quantization: false,
+            adaptive_quantization: None,
+            dead_zone: None,
+            dct_algorithm: DCTAlgorithm::default(),
+            high_precision: false,
+            huffman_table_mode: HuffmanTableMode::default(),
+            huffman_tables: None,
+            max_huffman_code_length: MAX_HUFFMAN_CODE_LENGTH,
+            restart_interval: None,
+            exif_metadata: ExifMetadata::default(),
+            xmp_packet: None,
+            thumbnail_size: None,
+            pixel_density: PixelDensity::default(),
+            adobe_marker: false,
+            define_number_of_lines: false,
+            combine_table_segments: false,
+            comments: Vec::new(),
+            embed_settings: false,
+            collect_symbol_stats: false,
+        }
+    }
+}
+
+impl JpegTransformationOptions {
+    /// Subsampling preset of the written frame. A grayscale frame has a single block per MCU.
+    pub fn frame_subsampling_preset(&self) -> ChromaSubsamplingPreset {
+        if self.grayscale {
+            ChromaSubsamplingPreset::P444
+        } else {
+            self.chroma_subsampling_preset
+        }
+    }
+
+    /// Quantization tables of the preset, scaled to the quality and chroma quality if set.
+    pub fn quantization_table_pair(&self) -> QuantizationTablePair {
+        self.unscaled_quantization_table_pair()
+            .scaled_separately(self.quality, self.chroma_quality.or(self.quality))
+    }
+
+    /// Quantization tables of the preset, scaled to the given quality instead of the set one.
+    /// The chroma tables keep the chroma quality, if set.
+    pub fn quantization_table_pair_of_quality(&self, quality: u8) -> QuantizationTablePair {
+        self.unscaled_quantization_table_pair()
+            .scaled_separately(Some(quality), self.chroma_quality.or(Some(quality)))
+    }
+
+    fn unscaled_quantization_table_pair(&self) -> QuantizationTablePair {
+        QuantizationTablePair {
+            chroma_red_table: self.chroma_red_quantization_table,
+            ..self.quantization_table_mix.map_or_else(
+                || self.quantization_table_preset.to_pair(),
+                QuantizationTableMix::to_pair,
+            )
+        }
+    }
+
+    /// Method, which subsamples the chroma channels, the method of the preset, if unset.
+    pub fn used_chroma_subsampling_method(&self) -> SubsamplingMethod {
+        self.chroma_subsampling_method
+            .unwrap_or_else(|| self.chroma_subsampling_preset.method())
+    }
+
+    /// Comments of the options followed by the annotations of the color matrix and the range, if
+    /// they differ from the BT.601 matrix and the full range of JFIF.
+    pub fn written_comments(&self) -> Vec<String> {
+        self.comments
+            .iter()
+            .cloned()
+            .chain(self.color_matrix.annotation())
+            .chain(self.color_range.annotation())
+            .collect()
+    }
+
+    /// Huffman table mode, which is actually used. The standard tables lack the symbols of
+    /// samples with extended precision and exceed code length limits below 16 bits.
+    pub fn used_huffman_table_mode(&self) -> HuffmanTableMode {
+        if self.bits_per_channel == BASELINE_SAMPLE_PRECISION
+            && self.max_huffman_code_length == MAX_HUFFMAN_CODE_LENGTH
+        {
+            self.huffman_table_mode
+        } else {
+            HuffmanTableMode::Optimized
+        }
+    }
+
+    /// Describes the options, which affect the output, as JSON object. The values are
+    /// normalized to the names accepted on the command line and to the DCT algorithm, which is
+    /// actually used, so the output can be reproduced with them. The EXIF metadata, the XMP
+    /// packet and the comments are left out, as they are written unchanged into segments of
+    /// their own and would not fit into the comment of the settings.
+    pub fn settings_json(&self) -> String {
+        self.settings_json_of_quality(self.quality)
+    }
+
+    /// Settings as JSON with the quality, the image was actually encoded with, which differs
+    /// from the set quality for a target size.
+    fn settings_json_of_quality(&self, quality: Option<u8>) -> String {
+        let dct_algorithm = if self.high_precision {
+            self.dct_algorithm.resolve_double_precision()
+        } else {
+            self.dct_algorithm.resolve()
+        };
+        let quality = quality.map_or("null".to_string(), |quality| quality.to_string());
+        let quantization_table_mix = self
+            .quantization_table_mix
+            .map_or("null".to_string(), |mix| format!("\"{mix}\""));
+        let chroma_quality = self
+            .chroma_quality
+            .map_or("null".to_string(), |quality| quality.to_string());
+        let adaptive_quantization = self
+            .adaptive_quantization
+            .map_or("null".to_string(), |strength| strength.to_string());
+        let dead_zone = self
+            .dead_zone
+            .map_or("null".to_string(), |dead_zone| format!("\"{dead_zone}\""));
+        let restart_interval = self
+            .restart_interval
+            .map_or("null".to_string(), |interval| interval.to_string());
+        let chroma_red_table =
+            self.chroma_red_quantization_table
+                .map_or("null".to_string(), |table| {
+                    let steps: Vec<String> = table.iter().map(u16::to_string).collect();
+                    format!("[{}]", steps.join(","))
+                });
+        let target_size = self
+            .target_size
+            .map_or("null".to_string(), |size| size.to_string());
+        let huffman_tables = self
+            .huffman_tables
+            .as_ref()
+            .map_or("null".to_string(), |tables| {
+                tables.to_json().trim_end().to_string()
+            });
+        let thumbnail = self.thumbnail_size.map_or("null".to_string(), |size| {
+            format!("\"{}x{}\"", size.width(), size.height())
+        });
+        format!(
+            concat!(
+                "{{\"encoder\":\"{}\",\"version\":\"{}\",",
+                "\"chroma_subsampling_preset\":\"{}\",\"subsampling_method\":\"{}\",",
+                "\"chroma_siting\":\"{}\",\"color_matrix\":\"{}\",\"color_range\":\"{}\",",
+                "\"grayscale\":{},\"bits_per_channel\":{},",
+                "\"quantization_table\":\"{}\",\"quant_mix\":{},\"quality\":{},\"chroma_quality\":{},",
+                "\"chroma_red_table\":{},\"target_size\":{},\"trellis\":{},",
+                "\"adaptive_quantization\":{},\"dead_zone\":{},\"dct\":\"{}\",",
+                "\"high_precision\":{},\"huffman\":\"{}\",\"huffman_tables\":{},",
+                "\"max_huffman_code_length\":{},\"restart_interval\":{},",
+                "\"thumbnail\":{},\"density_unit\":\"{}\",\"density\":[{},{}],",
+                "\"adobe\":{},\"dnl\":{},\"combine_tables\":{}}}"
+            ),
+            ENCODER_NAME,
+            env!("CARGO_PKG_VERSION"),
+            self.chroma_subsampling_preset.name(),
+            self.used_chroma_subsampling_method().name(),
+            self.chroma_siting.name(),
+            self.color_matrix.name(),
+            self.color_range.name(),
+            self.grayscale,
+            self.bits_per_channel,
+            self.quantization_table_preset.name(),
+            quantization_table_mix,
+            quality,
+            chroma_quality,
+            chroma_red_table,
+            target_size,
+            self.trellis_quantization,
+            adaptive_quantization,
+            dead_zone,
+            dct_algorithm.name(),
+            self.high_precision,
+            self.used_huffman_table_mode().name(),
+            huffman_tables,
+            self.max_huffman_code_length,
+            restart_interval,
+            thumbnail,
+            self.pixel_density.unit.name(),
+            self.pixel_density.horizontal,
+            self.pixel_density.vertical,
+            self.adobe_marker,
+            self.define_number_of_lines,
+            self.combine_table_segments,
+        )
+    }
+}
+
+enum SourceImage<'a> {
+    Rgb(&'a Image<f32>),
+    YCbCr(&'a YCbCrImage),
+    Cmyk(&'a CMYKImage),
+}
+
+/// Writer of JPEG files, whose stages run on the threadpool of the writer.
+///
+/// The bands of MCU rows of the source image are streamed through the stages into the scan, so
+/// besides the source image, which the caller holds, only the bands in flight and the counted
+/// bands of small images are kept. The adaptive quantization and the search for a target size
+/// transform the whole image at once, which is warned about for large images.
+///
+/// With the `rayon` feature, the DCT, the band jobs and the counting of the Huffman symbols run
+/// on the current rayon pool instead and only the preprocessing uses the threadpool. Cargo
+/// enables the feature for the whole build, as soon as any crate of the build enables it.
+pub struct JpegImageWriter<'a, T: Write> {
+    writer: T,
+    image: SourceImage<'a>,
+    options: &'a JpegTransformationOptions,
+    threadpool: &'a ThreadPool,
+    application_segments: Vec<ApplicationSegment>,
+    restart_interval_offsets: Vec<u64>,
+    symbol_stats: Option<String>,
+    huffman_tables: Option<HuffmanTables>,
+    target_size_quality: Option<u8>,
+    stage_timings: Arc<StageTimings>,
+    warnings: Warnings,
+}
+
+impl<'a, T: Write> JpegImageWriter<'a, T> {
+    pub fn new(
+        writer: T,
+        image: &'a Image<f32>,
+        options: &'a JpegTransformationOptions,
+        threadpool: &'a ThreadPool,
+    ) -> Self {
+        Self::with_source_image(writer, SourceImage::Rgb(image), options, threadpool)
+    }
+
+    /// Creates a writer for an image, which is already in YCbCr, like the frames of a video.
+    pub fn from_ycbcr_image(
+        writer: T,
+        image: &'a YCbCrImage,
+        options: &'a JpegTransformationOptions,
+        threadpool: &'a ThreadPool,
+    ) -> Self {
+        Self::with_source_image(writer, SourceImage::YCbCr(image), options, threadpool)
+    }
+
+    /// Creates a writer for a CMYK image, which is encoded as YCCK with an Adobe segment. Such
+    /// images have no JFIF segment, so no thumbnail is written.
+    pub fn from_cmyk_image(
+        writer: T,
+        image: &'a CMYKImage,
+        options: &'a JpegTransformationOptions,
+        threadpool: &'a ThreadPool,
+    ) -> Self {
+        Self::with_source_image(writer, SourceImage::Cmyk(image), options, threadpool)
+    }
+
+    fn with_source_image(
+        writer: T,
+        image: SourceImage<'a>,
+        options: &'a JpegTransformationOptions,
+        threadpool: &'a ThreadPool,
+    ) -> Self {
+        Self {
+            writer,
+            image,
+            options,
+            threadpool,
+            application_segments: Vec::new(),
+            restart_interval_offsets: Vec::new(),
+            symbol_stats: None,
+            huffman_tables: None,
+            target_size_quality: None,
+            stage_timings: Arc::new(StageTimings::new()),
+            warnings: Warnings::new(),
+        }
+    }
+
+    /// Adds an APPn segment with the marker number from 0 to 15, so applications can attach
+    /// their own metadata. The segments are written in the order they were added, after the
+    /// JFIF, EXIF, XMP and Adobe segments.
+    pub fn add_app_segment(&mut self, marker_number: u8, content: Vec<u8>) -> crate::Result<()> {
+        if marker_number > 15 {
+            return Err(Error::InvalidApplicationMarkerNumber(marker_number));
+        }
+        if content.len() > MAX_APPLICATION_SEGMENT_LENGTH {
+            return Err(Error::ApplicationSegmentTooLarge(content.len()));
+        }
+        self.application_segments.push(ApplicationSegment {
+            marker_number,
+            content,
+        });
+        Ok(())
+    }
+
+    /// Byte offsets of the entropy coded data of each restart interval, relative to the start
+    /// of the written image. Only available after the image was written with a restart interval.
+    pub fn restart_interval_offsets(&self) -> &[u64] {
+        &self.restart_interval_offsets
+    }
+
+    /// Frequencies of the Huffman symbols of each component as CSV. Only available after the
+    /// image was written with [`JpegTransformationOptions::collect_symbol_stats`] set.
+    pub fn symbol_stats(&self) -> Option<&str> {
+        self.symbol_stats.as_deref()
+    }
+
+    /// Huffman tables of the last written image, which can be reused for similar images with
+    /// [`JpegTransformationOptions::huffman_tables`].
+    pub fn huffman_tables(&self) -> Option<&HuffmanTables> {
+        self.huffman_tables.as_ref()
+    }
+
+    /// Quality found by the search for [`JpegTransformationOptions::target_size`]. Only
+    /// available after the image was written with a target size.
+    pub fn target_size_quality(&self) -> Option<u8> {
+        self.target_size_quality
+    }
+
+    /// Time and processed bytes of the stages of the last written image. The read of the input
+    /// is not part of the writer, the caller may record it with [`StageTimings::record`].
+    pub fn stage_timings(&self) -> &StageTimings {
+        &self.stage_timings
+    }
+
+    /// Non-fatal issues of the last written image, like adjusted options or replaced samples.
+    pub fn warnings(&self) -> &Warnings {
+        &self.warnings
+    }
+
+    /// Collects warnings about options, which are adjusted for the encode or transform a large
+    /// image at once, and about the padding of the image to whole MCUs.
+    fn collect_option_warnings(&mut self) {
+        let options = self.options;
+        for quality in [options.quality, options.chroma_quality]
+            .into_iter()
+            .flatten()
+        {
+            let used = quality.clamp(1, 100);
+            if used != quality {
+                self.warnings.push(Warning::QualityClamped {
+                    requested: quality,
+                    used,
+                });
+            }
+        }
+        let double_precision_algorithm = options.dct_algorithm.resolve_double_precision();
+        if options.high_precision
+            && options.dct_algorithm != DCTAlgorithm::Auto
+            && double_precision_algorithm != options.dct_algorithm
+        {
+            self.warnings.push(Warning::DCTAlgorithmReplaced {
+                requested: options.dct_algorithm,
+                used: double_precision_algorithm,
+            });
+        }
+        let huffman_table_mode = options.used_huffman_table_mode();
+        if huffman_table_mode != options.huffman_table_mode {
+            self.warnings.push(Warning::HuffmanTableModeReplaced {
+                requested: options.huffman_table_mode,
+                used: huffman_table_mode,
+            });
+        }
+        let (width, height) = match self.image {
+            SourceImage::Rgb(image) => (image.width, image.height),
+            SourceImage::YCbCr(image) => (image.width, image.height),
+            SourceImage::Cmyk(image) => (image.width, image.height),
+        };
+        let layout = options.frame_subsampling_preset().sampling_layout();
+        // lengths near the maximum of u16 are padded beyond it
+        let pad = |length: u16, multiple: u16| {
+            u32::from(length).div_ceil(u32::from(multiple)) * u32::from(multiple)
+        };
+        let padded_width = pad(width, layout.mcu_width());
+        let padded_height = pad(height, layout.mcu_height());
+        if width > 0
+            && height > 0
+            && (padded_width != u32::from(width) || padded_height != u32::from(height))
+        {
+            self.warnings.push(Warning::PaddedDimensions {
+                width,
+                height,
+                padded_width,
+                padded_height,
+            });
+        }
+        let pixels = u64::from(width) * u64::from(height);
+        if pixels > MAX_WHOLE_IMAGE_PIXELS {
+            let whole_image_options = [
+                (
+                    options.adaptive_quantization.is_some(),
+                    "Adaptive quantization",
+                ),
+                (options.target_size.is_some(), "Search for the target size"),
+            ];
+            for (_, option) in whole_image_options.into_iter().filter(|(used, _)| *used) {
+                self.warnings
+                    .push(Warning::WholeImageTransformed { pixels, option });
+            }
+        }
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &T {
+        &self.writer
+    }
+
+    /// Gets a mutable reference to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.writer
+    }
+
+    /// Unwraps this `JpegImageWriter`, returning the underlying writer. The writer was already
+    /// flushed by [`ImageWriter::write_image`], so the encoded image is complete.
+    pub fn into_inner(self) -> T {
+        self.writer
+    }
+}
+
+impl<T: Write> ImageWriter for JpegImageWriter<'_, T> {
+    fn write_image(&mut self) -> crate::Result<()> {
+        self.warnings = Warnings::new();
+        self.target_size_quality = None;
+        self.stage_timings = Arc::new(StageTimings::new());
+        self.collect_option_warnings();
+        let thumbnail_size = self.options.thumbnail_size;
+        // the bands of the scan read the source image, while they are written
+        let sanitized_image = match self.image {
+            SourceImage::Rgb(image) => image.sanitized(&mut self.warnings),
+            _ => None,
+        };
+        let streamed_output_image = match self.image {
+            SourceImage::Rgb(image) => {
+                let image = sanitized_image.as_ref().unwrap_or(image);
+                let thumbnail = thumbnail_size.map(|size| {
+                    let dot = |x, y| image.dots[y * image.width as usize + x];
+                    Thumbnail::downsample(image.width, image.height, dot, size)
+                });
+                let transformer = Transformer::new(image, self.options, self.threadpool)
+                    .with_stage_timings(Arc::clone(&self.stage_timings));
+                self.transform(transformer, thumbnail)?
+            }
+            SourceImage::YCbCr(image) => {
+                let thumbnail = thumbnail_size.map(|size| {
+                    let dot =
+                        |x, y| RGBColorFormat::from(&image.dots[y * image.width as usize + x]);
+                    Thumbnail::downsample(image.width, image.height, dot, size)
+                });
+                let transformer =
+                    Transformer::from_ycbcr_image(image, self.options, self.threadpool)
+                        .with_stage_timings(Arc::clone(&self.stage_timings));
+                self.transform(transformer, thumbnail)?
+            }
+            SourceImage::Cmyk(image) => {
+                let transformer =
+                    Transformer::from_cmyk_image(image, self.options, self.threadpool)
+                        .with_stage_timings(Arc::clone(&self.stage_timings));
+                self.transform(transformer, None)?
+            }
+        };
+        let StreamedOutputImage {
+            mut output_image,
+            scan_bands,
+        } = streamed_output_image;
+        if output_image.incomplete_huffman_tables {
+            self.warnings.push(Warning::HuffmanTablesIncomplete);
+        }
+        let mut encoder = Encoder::new(&mut self.writer, &output_image);
+        let start = Instant::now();
+        // the bands are transformed, while they are taken, which isn't part of the writing
+        let mut transform_time = Duration::ZERO;
+        match scan_bands {
+            Some(mut scan_bands) => encoder.encode_bands(iter::from_fn(|| {
+                let start = Instant::now();
+                let band = scan_bands.next();
+                transform_time += start.elapsed();
+                band
+            }))?,
+            None => encoder.encode()?,
+        }
+        self.stage_timings.record(
+            Stage::EntropyWriting,
+            start.elapsed().saturating_sub(transform_time),
+            encoder.bytes_written(),
+        );
+        self.restart_interval_offsets = encoder.take_restart_interval_offsets();
+        self.symbol_stats = output_image.symbol_stats.take();
+        self.huffman_tables = Some(std::mem::take(&mut output_image.huffman_tables));
+        // a closed pipe of the output fails here at the latest
+        self.writer.flush().map_err(Error::FailedToFlushImage)
+    }
+}
+
+impl<T: Write> JpegImageWriter<'_, T> {
+    /// Transforms the image into the output image with the thumbnail and the custom application
+    /// segments. For a target size, each probed quality is encoded into a counting sink.
+    fn transform<'b, P: SourceDot>(
+        &mut self,
+        transformer: Transformer<'b, P>,
+        thumbnail: Option<Thumbnail>,
+    ) -> crate::Result<StreamedOutputImage<'b>> {
+        let application_segments = &self.application_segments;
+        let stage_timings = Arc::clone(&self.stage_timings);
+        let complete = |output_image: &mut OutputImage| {
+            output_image.thumbnail = thumbnail.clone();
+            output_image.application_segments = application_segments.clone();
+        };
+        let Some(target_size) = self.options.target_size else {
+            let mut streamed_output_image = transformer.transform()?;
+            complete(&mut streamed_output_image.output_image);
+            return Ok(streamed_output_image);
+        };
+        let sized_output_image =
+            transformer.transform_to_target_size(target_size, |output_image| {
+                complete(output_image);
+                let mut sink = ByteCountingWriter::new(io::sink());
+                let start = Instant::now();
+                Encoder::new(&mut sink, output_image).encode()?;
+                stage_timings.record(Stage::EntropyWriting, start.elapsed(), sink.bytes_written());
+                Ok(sink.bytes_written())
+            })?;
+        if sized_output_image.size > target_size {
+            self.warnings.push(Warning::TargetSizeExceeded {
+                target_size,
+                size: sized_output_image.size,
+            });
+        }
+        self.target_size_quality = Some(sized_output_image.quality);
+        Ok(StreamedOutputImage {
+            output_image: sized_output_image.output_image,
+            scan_bands: None,
+        })
+    }
+}
+
+struct OutputImage {
+    width: u16,
+    height: u16,
+    chroma_subsampling_preset: ChromaSubsamplingPreset,
+    grayscale: bool,
+    /// Whether the image is YCCK with the black ink as fourth component.
+    cmyk: bool,
+    bits_per_channel: u8,
+    huffman_tables: HuffmanTables,
+    /// Whether the tables of the options lack symbols of the image and were replaced.
+    incomplete_huffman_tables: bool,
+    blockwise_image_data: CombinedColorChannels<Vec<CategorizedBlock>>,
+    quantization_table_pair: QuantizationTablePair,
+    exif_metadata: ExifMetadata,
+    xmp_packet: Option<XmpPacket>,
+    thumbnail: Option<Thumbnail>,
+    pixel_density: PixelDensity,
+    adobe_marker: bool,
+    application_segments: Vec<ApplicationSegment>,
+    define_number_of_lines: bool,
+    combine_table_segments: bool,
+    comments: Vec<String>,
+    restart_interval: Option<u16>,
+    settings_comment: Option<String>,
+    symbol_stats: Option<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{self, Write};
+
+    use threadpool::ThreadPool;
+
+    use super::{
+        density::{DensityUnit, PixelDensity},
+        thumbnail::ThumbnailSize,
+        DeadZone, HuffmanTableMode, HuffmanTables, JpegImageWriter, JpegTransformationOptions,
+        QuantizationTableMix, QuantizationTablePair, MAX_APPLICATION_SEGMENT_LENGTH,
+    };
+    use crate::{
+        color::{ColorMatrix, ColorRange},
+        cosine_transform::DCTAlgorithm,
+        image::{
+            subsampling::{ChromaSiting, ChromaSubsamplingPreset, SubsamplingMethod},
+            writer::jpeg::QuantizationTablePreset,
+            CMYKImage, Image, ImageWriter,
+        },
+        warning::Warning,
+        Error,
+    };
+
+    #[test]
+    fn write_custom_application_segments() {
+        let image = Image::from_rgb8(8, 8, &[128; 8 * 8 * 3]).unwrap();
+        let options = JpegTransformationOptions::default();
+        let threadpool = ThreadPool::new(1);
+        let mut writer = JpegImageWriter::new(Vec::new(), &image, &options, &threadpool);
+        assert!(matches!(
+            writer.add_app_segment(16, Vec::new()),
+            Err(Error::InvalidApplicationMarkerNumber(16))
+        ));
+        assert!(matches!(
+            writer.add_app_segment(3, vec![0; MAX_APPLICATION_SEGMENT_LENGTH + 1]),
+            Err(Error::ApplicationSegmentTooLarge(_))
+        ));
+        writer.add_app_segment(3, b"first".to_vec()).unwrap();
+        writer.add_app_segment(15, b"second".to_vec()).unwrap();
+        writer.write_image().unwrap();
+        let output = writer.into_inner();
+        let position = |segment: &[u8]| {
+            output
+                .windows(segment.len())
+                .position(|window| window == segment)
+                .expect("Application segment missing")
+        };
+        let first = position(&[0xFF, 0xE3, 0x00, 0x07, b'f', b'i', b'r', b's', b't']);
+        let second = position(&[0xFF, 0xEF, 0x00, 0x08, b's', b'e', b'c', b'o', b'n', b'd']);
+        // Written after SOI and the JFIF header of 20 bytes, in the order they were added
+        assert!(20 <= first && first < second);
+    }
+
+    #[test]
+    fn warn_about_padding_beyond_maximum_length() {
+        let threadpool = ThreadPool::new(1);
+        let image = Image::from_rgb8(u16::MAX, 1, &vec![0; u16::MAX as usize * 3]).unwrap();
+        let options = JpegTransformationOptions::default();
+        let mut writer = JpegImageWriter::new(Vec::new(), &image, &options, &threadpool);
+        writer.collect_option_warnings();
+        let expected = Warning::PaddedDimensions {
+            width: u16::MAX,
+            height: 1,
+            padded_width: 65536,
+            padded_height: 16,
+        };
+        assert!(writer.warnings().iter().any(|warning| *warning == expected));
+    }
+
+    #[test]
+    fn warn_about_whole_image_options_of_large_images() {
+        let threadpool = ThreadPool::new(1);
+        // the warnings only depend on the dimensions
+        let image = Image {
+            width: 4096,
+            height: 4097,
+            dots: Vec::new(),
+        };
+        let options = JpegTransformationOptions {
+            adaptive_quantization: Some(1.0),
+            target_size: Some(1 << 20),
+            ..Default::default()
+        };
+        let mut writer = JpegImageWriter::new(Vec::new(), &image, &options, &threadpool);
+        writer.collect_option_warnings();
+        let warned_options: Vec<&str> = writer
+            .warnings()
+            .iter()
+            .filter_map(|warning| match warning {
+                Warning::WholeImageTransformed { pixels, option } => {
+                    assert_eq!(*pixels, 4096 * 4097);
+                    Some(*option)
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            warned_options,
+            ["Adaptive quantization", "Search for the target size"]
+        );
+
+        let image = Image {
+            width: 4096,
+            height: 4096,
+            dots: Vec::new(),
+        };
+        let mut writer = JpegImageWriter::new(Vec::new(), &image, &options, &threadpool);
+        writer.collect_option_warnings();
+        assert!(writer.warnings().is_empty());
+    }
+
+    /// Writer, which takes the data, but fails to flush it like a closed pipe.
+    struct FailingFlushWriter(Vec<u8>);
+
+    impl Write for FailingFlushWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::Error::from(io::ErrorKind::BrokenPipe))
+        }
+    }
+
+    #[test]
+    fn return_error_of_failed_flush() {
+        let threadpool = ThreadPool::new(1);
+        let image = Image::from_rgb8(8, 8, &[128; 8 * 8 * 3]).unwrap();
+        let options = JpegTransformationOptions::default();
+        let mut writer = JpegImageWriter::new(
+            FailingFlushWriter(Vec::new()),
+            &image,
+            &options,
+            &threadpool,
+        );
+        let result = writer.write_image();
+        assert!(
+            matches!(result, Err(Error::FailedToFlushImage(ref e)) if e.kind() == io::ErrorKind::BrokenPipe)
+        );
+    }
+
+    #[test]
+    fn reject_grayscale_cmyk_image() {
+        let image = CMYKImage::from_cmyk8(8, 8, &[64; 8 * 8 * 4]).unwrap();
+        let options = JpegTransformationOptions {
+            grayscale: true,
+            ..Default::default()
+        };
+        let threadpool = ThreadPool::new(1);
+        let mut writer =
+            JpegImageWriter::from_cmyk_image(Vec::new(), &image, &options, &threadpool);
+        assert!(matches!(
+            writer.write_image(),
+            Err(Error::GrayscaleCMYKImage)
+        ));
+    }
+
+    #[test]
+    fn search_highest_quality_within_target_size() {
+        let samples: Vec<u8> = (0..64 * 64 * 3)
+            .map(|index: usize| (index * 37 % 251) as u8)
+            .collect();
+        let image = Image::from_rgb8(64, 64, &samples).unwrap();
+        let threadpool = ThreadPool::new(1);
+        let encode = |options: &JpegTransformationOptions| {
+            let mut writer = JpegImageWriter::new(Vec::new(), &image, options, &threadpool);
+            writer.write_image().unwrap();
+            let quality = writer.target_size_quality();
+            let warnings = writer.warnings().clone();
+            (writer.into_inner(), quality, warnings)
+        };
+        let (reference, quality, _) = encode(&JpegTransformationOptions {
+            quality: Some(60),
+            ..Default::default()
+        });
+        assert_eq!(quality, None);
+
+        let target_size = reference.len() as u64;
+        let (output, quality, warnings) = encode(&JpegTransformationOptions {
+            quality: Some(100),
+            target_size: Some(target_size),
+            ..Default::default()
+        });
+        assert!(quality.unwrap() >= 60);
+        assert!(output.len() as u64 <= target_size);
+        assert!(warnings.is_empty());
+
+        let (_, quality, warnings) = encode(&JpegTransformationOptions {
+            target_size: Some(100),
+            ..Default::default()
+        });
+        assert_eq!(quality, Some(1));
+        assert!(matches!(
+            warnings.iter().next(),
+            Some(Warning::TargetSizeExceeded {
+                target_size: 100,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn reuse_huffman_tables_of_written_image() {
+        let samples: Vec<u8> = (0..32 * 32 * 3)
+            .map(|index: usize| (index * 37 % 251) as u8)
+            .collect();
+        let image = Image::from_rgb8(32, 32, &samples).unwrap();
+        let threadpool = ThreadPool::new(1);
+        let encode = |options: &JpegTransformationOptions| {
+            let mut writer = JpegImageWriter::new(Vec::new(), &image, options, &threadpool);
+            writer.write_image().unwrap();
+            let huffman_tables = writer.huffman_tables().cloned().unwrap();
+            let warnings = writer.warnings().clone();
+            (writer.into_inner(), huffman_tables, warnings)
+        };
+        let (reference, huffman_tables, _) = encode(&JpegTransformationOptions::default());
+        let (output, reused_tables, warnings) = encode(&JpegTransformationOptions {
+            huffman_tables: Some(huffman_tables.clone()),
+            ..Default::default()
+        });
+        assert_eq!(output, reference);
+        assert_eq!(reused_tables, huffman_tables);
+        assert!(warnings.is_empty());
+
+        // the tables of a flat image lack most symbols of the textured image
+        let flat_image = Image::from_rgb8(32, 32, &[128; 32 * 32 * 3]).unwrap();
+        let options = JpegTransformationOptions::default();
+        let mut writer = JpegImageWriter::new(Vec::new(), &flat_image, &options, &threadpool);
+        writer.write_image().unwrap();
+        let flat_tables = writer.huffman_tables().cloned().unwrap();
+        let (output, replaced_tables, warnings) = encode(&JpegTransformationOptions {
+            huffman_tables: Some(flat_tables),
+            ..Default::default()
+        });
+        assert_eq!(output, reference);
+        assert_eq!(replaced_tables, huffman_tables);
+        assert_eq!(
+            warnings.iter().collect::<Vec<_>>(),
+            [&Warning::HuffmanTablesIncomplete]
+        );
+    }
+
+    #[test]
+    fn scale_quantization_tables_with_quality() {
+        let pair = QuantizationTablePreset::Specification.to_pair();
+        assert_eq!(pair.scaled(50).luma_table, pair.luma_table);
+        // quality 75 halves the steps, the first luma step of the specification is 16
+        assert_eq!(pair.scaled(75).luma_table[0], 8);
+        assert_eq!(pair.scaled(25).luma_table[0], 32);
+        assert!(pair.scaled(100).luma_table.iter().all(|&step| step == 1));
+        assert!(pair.scaled(1).chroma_table.iter().all(|&step| step == 255));
+    }
+
+    #[test]
+    fn scale_sixteen_bit_tables_beyond_baseline_range() {
+        let pair = QuantizationTablePreset::ImageMagick.to_pair();
+        assert!(!pair.is_baseline());
+        assert_eq!(pair.scaled(25).luma_table[63], 836);
+        assert!(QuantizationTablePreset::Specification
+            .to_pair()
+            .scaled(1)
+            .is_baseline());
+    }
+
+    #[test]
+    fn quantization_table_pair_uses_quality() {
+        let options = JpegTransformationOptions {
+            quality: Some(90),
+            ..Default::default()
+        };
+        let expected: QuantizationTablePair =
+            QuantizationTablePreset::Specification.to_pair().scaled(90);
+        assert_eq!(
+            options.quantization_table_pair().chroma_table,
+            expected.chroma_table
+        );
+        let options = JpegTransformationOptions::default();
+        assert_eq!(
+            options.quantization_table_pair().luma_table,
+            QuantizationTablePreset::Specification.to_pair().luma_table
+        );
+    }
+
+    #[test]
+    fn quantization_table_pair_uses_chroma_quality() {
+        let specification = QuantizationTablePreset::Specification.to_pair();
+        let options = JpegTransformationOptions {
+            quality: Some(85),
+            chroma_quality: Some(70),
+            chroma_red_quantization_table: Some([20; 64]),
+            ..Default::default()
+        };
+        let pair = options.quantization_table_pair();
+        assert_eq!(pair.luma_table, specification.scaled(85).luma_table);
+        assert_eq!(pair.chroma_table, specification.scaled(70).chroma_table);
+        // quality 70 scales the steps by 0.6
+        assert_eq!(pair.chroma_red_table(), &[12; 64]);
+        let pair = options.quantization_table_pair_of_quality(40);
+        assert_eq!(pair.luma_table, specification.scaled(40).luma_table);
+        assert_eq!(pair.chroma_table, specification.scaled(70).chroma_table);
+
+        let options = JpegTransformationOptions {
+            chroma_quality: Some(70),
+            ..Default::default()
+        };
+        let pair = options.quantization_table_pair();
+        assert_eq!(pair.luma_table, specification.luma_table);
+        assert_eq!(pair.chroma_table, specification.scaled(70).chroma_table);
+    }
+
+    #[test]
+    fn quantization_table_pair_scales_chroma_red_table() {
+        let pair = JpegTransformationOptions::default().quantization_table_pair();
+        assert_eq!(pair.chroma_red_table(), &pair.chroma_table);
+        let options = JpegTransformationOptions {
+            quality: Some(25),
+            chroma_red_quantization_table: Some([20; 64]),
+            ..Default::default()
+        };
+        let pair = options.quantization_table_pair();
+        assert_eq!(pair.chroma_red_table(), &[40; 64]);
+        assert_ne!(pair.chroma_table, [40; 64]);
+    }
+
+    #[test]
+    fn annotate_color_matrix_in_comment() {
+        let image = Image::from_rgb8(8, 8, &[128; 8 * 8 * 3]).unwrap();
+        let annotation = b"YCbCr color matrix: bt709";
+        let threadpool = ThreadPool::new(1);
+        let encode = |color_matrix| {
+            let options = JpegTransformationOptions {
+                color_matrix,
+                ..Default::default()
+            };
+            let mut writer = JpegImageWriter::new(Vec::new(), &image, &options, &threadpool);
+            writer.write_image().unwrap();
+            writer.into_inner()
+        };
+        let contains_annotation = |output: &[u8]| {
+            output
+                .windows(annotation.len())
+                .any(|window| window == annotation)
+        };
+        assert!(contains_annotation(&encode(ColorMatrix::Bt709)));
+        assert!(!contains_annotation(&encode(ColorMatrix::Bt601)));
+    }
+
+    #[test]
+    fn settings_json_uses_command_line_names() {
+        let options = JpegTransformationOptions {
+            chroma_subsampling_preset: ChromaSubsamplingPreset::P444,
+            quantization_table_preset: QuantizationTablePreset::MSSIMKodakTuned,
+            dct_algorithm: DCTAlgorithm::Arai,
+            restart_interval: Some(4),
+            ..Default::default()
+        };
+        assert_eq!(
+            options.settings_json(),
+            concat!(
+                "{\"encoder\":\"dmmt-jpeg-encoder\",\"version\":\"",
+                env!("CARGO_PKG_VERSION"),
+                "\",\"chroma_subsampling_preset\":\"P444\",\"subsampling_method\":\"skip\",",
+                "\"chroma_siting\":\"centered\",\"color_matrix\":\"bt601\",\"color_range\":\"full\",",
+                "\"grayscale\":false,\"bits_per_channel\":8,",
+                "\"quantization_table\":\"MSSIM-Kodak-Tuned\",\"quant_mix\":null,\"quality\":null,\"chroma_quality\":null,",
+                "\"chroma_red_table\":null,\"target_size\":null,\"trellis\":false,",
+                "\"adaptive_quantization\":null,\"dead_zone\":null,\"dct\":\"Arai\",",
+                "\"high_precision\":false,\"huffman\":\"optimized\",\"huffman_tables\":null,",
+                "\"max_huffman_code_length\":16,\"restart_interval\":4,",
+                "\"thumbnail\":null,\"density_unit\":\"aspect-ratio\",\"density\":[72,72],",
+                "\"adobe\":false,\"dnl\":false,\"combine_tables\":false}"
+            )
+        );
+    }
+
+    #[test]
+    fn settings_json_reports_dct_used_with_high_precision() {
+        let options = JpegTransformationOptions {
+            dct_algorithm: DCTAlgorithm::Simd,
+            high_precision: true,
+            ..Default::default()
+        };
+        let settings = options.settings_json();
+        assert!(settings.contains("\"dct\":\"Arai\""));
+        assert!(settings.contains("\"restart_interval\":null,"));
+    }
+
+    #[test]
+    fn settings_json_describes_every_option() {
+        // a new option fails to compile here, until it is described or left out on purpose
+        let JpegTransformationOptions {
+            chroma_subsampling_preset: _,
+            chroma_subsampling_method: _,
+            chroma_siting: _,
+            color_matrix: _,
+            color_range: _,
+            grayscale: _,
+            bits_per_channel: _,
+            quantization_table_preset: _,
+            quantization_table_mix: _,
+            quality: _,
+            chroma_quality: _,
+            chroma_red_quantization_table: _,
+            target_size: _,
+            trellis_quantization: _,
+            adaptive_quantization: _,
+            dead_zone: _,
+            dct_algorithm: _,
+            high_precision: _,
+            huffman_table_mode: _,
+            huffman_tables: _,
+            max_huffman_code_length: _,
+            restart_interval: _,
+            thumbnail_size: _,
+            pixel_density: _,
+            adobe_marker: _,
+            define_number_of_lines: _,
+            combine_table_segments: _,
+            // written unchanged into segments of their own
+            exif_metadata: _,
+            xmp_packet: _,
+            comments: _,
+            // don't change the encoded image
+            embed_settings: _,
+            collect_symbol_stats: _,
+        } = JpegTransformationOptions::default();
+        type Change = fn(&mut JpegTransformationOptions);
+        let changes: [Change; 27] = [
+            |options| options.chroma_subsampling_preset = ChromaSubsamplingPreset::P444,
+            |options| options.chroma_subsampling_method = Some(SubsamplingMethod::Median),
+            |options| options.chroma_siting = ChromaSiting::CoSited,
+            |options| options.color_matrix = ColorMatrix::Bt709,
+            |options| options.color_range = ColorRange::Limited,
+            |options| options.grayscale = true,
+            |options| options.bits_per_channel = 12,
+            |options| options.quantization_table_preset = QuantizationTablePreset::Flat,
+            |options| {
+                options.quantization_table_mix = Some(QuantizationTableMix::new(
+                    (QuantizationTablePreset::Flat, 1.0),
+                    (QuantizationTablePreset::Specification, 1.0),
+                ))
+            },
+            |options| options.quality = Some(80),
+            |options| options.chroma_quality = Some(60),
+            |options| options.chroma_red_quantization_table = Some([7; 64]),
+            |options| options.target_size = Some(20_000),
+            |options| options.trellis_quantization = true,
+            |options| options.adaptive_quantization = Some(1.0),
+            |options| options.dead_zone = Some(DeadZone::new(2, 1)),
+            |options| options.dct_algorithm = DCTAlgorithm::Simple,
+            |options| options.high_precision = true,
+            |options| options.huffman_table_mode = HuffmanTableMode::Standard,
+            |options| options.huffman_tables = Some(HuffmanTables::default()),
+            |options| options.max_huffman_code_length = 12,
+            |options| options.restart_interval = Some(2),
+            |options| options.thumbnail_size = Some(ThumbnailSize::new(16, 16).unwrap()),
+            |options| options.pixel_density = PixelDensity::uniform(DensityUnit::DotsPerInch, 300),
+            |options| options.adobe_marker = true,
+            |options| options.define_number_of_lines = true,
+            |options| options.combine_table_segments = true,
+        ];
+        let default_settings = JpegTransformationOptions::default().settings_json();
+        for (index, change) in changes.iter().enumerate() {
+            let mut options = JpegTransformationOptions::default();
+            change(&mut options);
+            assert_ne!(
+                options.settings_json(),
+                default_settings,
+                "change {} is missing from the settings",
+                index
+            );
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Constant data of the JPEG standard (ITU-T T.81), shared by the encoder and external tools.
+
+/// Indexes of the coefficients of a row major 8x8 block in zig-zag order (Figure A.6).
+pub const ZIG_ZAG_ORDER: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// Luminance quantization table of Annex K.1 in row major order.
+#[rustfmt::skip]
+pub const ANNEX_K_LUMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+    16,  11,  10,  16,  24,  40,  51,  61,
+    12,  12,  14,  19,  26,  58,  60,  55,
+    14,  13,  16,  24,  40,  57,  69,  56,
+    14,  17,  22,  29,  51,  87,  80,  62,
+    18,  22,  37,  56,  68, 109, 103,  77,
+    24,  35,  55,  64,  81, 104, 113,  92,
+    49,  64,  78,  87, 103, 121, 120, 101,
+    72,  92,  95,  98, 112, 100, 103,  99,
+];
+
+/// Chrominance quantization table of Annex K.1 in row major order.
+#[rustfmt::skip]
+pub const ANNEX_K_CHROMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+    17,  18,  24,  47,  99,  99,  99,  99,
+    18,  21,  26,  66,  99,  99,  99,  99,
+    24,  26,  56,  99,  99,  99,  99,  99,
+    47,  66,  99,  99,  99,  99,  99,  99,
+    99,  99,  99,  99,  99,  99,  99,  99,
+    99,  99,  99,  99,  99,  99,  99,  99,
+    99,  99,  99,  99,  99,  99,  99,  99,
+    99,  99,  99,  99,  99,  99,  99,  99,
+];
+
+/// Huffman table in the form of a DHT segment: the number of codes of each length from 1 to 16
+/// bits and the symbols ordered by code length.
+pub struct HuffmanTableSpecification {
+    pub code_length_counts: [u8; 16],
+    pub symbols: &'static [u8],
+}
+
+/// Luminance DC table of Annex K.3 (Table K.3).
+pub const ANNEX_K_LUMINANCE_DC_HUFFMAN_TABLE: HuffmanTableSpecification =
+    HuffmanTableSpecification {
+        code_length_counts: [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0],
+        symbols: &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+    };
+
+/// Chrominance DC table of Annex K.3 (Table K.4).
+pub const ANNEX_K_CHROMINANCE_DC_HUFFMAN_TABLE: HuffmanTableSpecification =
+    HuffmanTableSpecification {
+        code_length_counts: [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0],
+        symbols: &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+    };
+
+/// Luminance AC table of Annex K.3 (Table K.5).
+#[rustfmt::skip]
+pub const ANNEX_K_LUMINANCE_AC_HUFFMAN_TABLE: HuffmanTableSpecification =
+    HuffmanTableSpecification {
+        code_length_counts: [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7D],
+        symbols: &[
+            0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12,
+            0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07,
+            0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xA1, 0x08,
+            0x23, 0x42, 0xB1, 0xC1, 0x15, 0x52, 0xD1, 0xF0,
+            0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0A, 0x16,
+            0x17, 0x18, 0x19, 0x1A, 0x25, 0x26, 0x27, 0x28,
+            0x29, 0x2A, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39,
+            0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49,
+            0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59,
+            0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+            0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79,
+            0x7A, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+            0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98,
+            0x99, 0x9A, 0xA2, 0xA3, 0xA4, 0xA5, 0xA6, 0xA7,
+            0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6,
+            0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3, 0xC4, 0xC5,
+            0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2, 0xD3, 0xD4,
+            0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA, 0xE1, 0xE2,
+            0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA,
+            0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8,
+            0xF9, 0xFA,
+        ],
+    };
+
+/// Chrominance AC table of Annex K.3 (Table K.6).
+#[rustfmt::skip]
+pub const ANNEX_K_CHROMINANCE_AC_HUFFMAN_TABLE: HuffmanTableSpecification =
+    HuffmanTableSpecification {
+        code_length_counts: [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77],
+        symbols: &[
+            0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21,
+            0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71,
+            0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91,
+            0xA1, 0xB1, 0xC1, 0x09, 0x23, 0x33, 0x52, 0xF0,
+            0x15, 0x62, 0x72, 0xD1, 0x0A, 0x16, 0x24, 0x34,
+            0xE1, 0x25, 0xF1, 0x17, 0x18, 0x19, 0x1A, 0x26,
+            0x27, 0x28, 0x29, 0x2A, 0x35, 0x36, 0x37, 0x38,
+            0x39, 0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48,
+            0x49, 0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58,
+            0x59, 0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68,
+            0x69, 0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78,
+            0x79, 0x7A, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87,
+            0x88, 0x89, 0x8A, 0x92, 0x93, 0x94, 0x95, 0x96,
+            0x97, 0x98, 0x99, 0x9A, 0xA2, 0xA3, 0xA4, 0xA5,
+            0xA6, 0xA7, 0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4,
+            0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3,
+            0xC4, 0xC5, 0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2,
+            0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA,
+            0xE2, 0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9,
+            0xEA, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8,
+            0xF9, 0xFA,
+        ],
+    };
+
+/// Byte in front of every marker code.
+pub const MARKER_PREFIX: u8 = 0xFF;
+/// Baseline DCT start of frame.
+pub const SOF0: u8 = 0xC0;
+/// Extended sequential DCT start of frame, which allows a sample precision of 12 bit.
+pub const SOF1: u8 = 0xC1;
+/// Define Huffman tables.
+pub const DHT: u8 = 0xC4;
+/// First of the eight restart markers RST0 to RST7.
+pub const RST0: u8 = 0xD0;
+/// Start of image.
+pub const SOI: u8 = 0xD8;
+/// End of image.
+pub const EOI: u8 = 0xD9;
+/// Start of scan.
+pub const SOS: u8 = 0xDA;
+/// Define quantization tables.
+pub const DQT: u8 = 0xDB;
+/// Define number of lines.
+pub const DNL: u8 = 0xDC;
+/// Define restart interval.
+pub const DRI: u8 = 0xDD;
+/// First of the sixteen application segments APP0 to APP15.
+pub const APP0: u8 = 0xE0;
+/// Comment.
+pub const COM: u8 = 0xFE;
+
+/// Returns the two byte marker for the marker code.
+pub const fn marker(code: u8) -> [u8; 2] {
+    [MARKER_PREFIX, code]
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn zig_zag_order_is_permutation_following_anti_diagonals() {
+        let indexes: HashSet<usize> = ZIG_ZAG_ORDER.iter().copied().collect();
+        assert_eq!(indexes.len(), 64);
+        let diagonals: Vec<usize> = ZIG_ZAG_ORDER.iter().map(|i| i / 8 + i % 8).collect();
+        assert!(diagonals.windows(2).all(|pair| pair[1] - pair[0] <= 1));
+        assert_eq!(ZIG_ZAG_ORDER[..6], [0, 1, 8, 16, 9, 2]);
+        assert_eq!(ZIG_ZAG_ORDER[63], 63);
+    }
+
+    #[test]
+    fn annex_k_quantization_tables() {
+        assert_eq!(ANNEX_K_LUMINANCE_QUANTIZATION_TABLE[..4], [16, 11, 10, 16]);
+        assert_eq!(ANNEX_K_LUMINANCE_QUANTIZATION_TABLE[63], 99);
+        assert_eq!(
+            ANNEX_K_CHROMINANCE_QUANTIZATION_TABLE[..4],
+            [17, 18, 24, 47]
+        );
+        assert!(ANNEX_K_CHROMINANCE_QUANTIZATION_TABLE[36..]
+            .iter()
+            .all(|&value| value == 99));
+    }
+
+    fn assert_valid_table(table: &HuffmanTableSpecification, expected_symbols: HashSet<u8>) {
+        let number_of_codes: usize = table.code_length_counts.iter().map(|&c| c as usize).sum();
+        assert_eq!(number_of_codes, table.symbols.len());
+        let symbols: HashSet<u8> = table.symbols.iter().copied().collect();
+        assert_eq!(symbols, expected_symbols);
+        // The code of all ones is reserved, so the kraft sum must stay below one.
+        let kraft_sum: u32 = table
+            .code_length_counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| (count as u32) << (15 - i))
+            .sum();
+        assert!(kraft_sum < 1 << 16);
+    }
+
+    #[test]
+    fn annex_k_dc_huffman_tables() {
+        let categories: HashSet<u8> = (0..=11).collect();
+        assert_valid_table(&ANNEX_K_LUMINANCE_DC_HUFFMAN_TABLE, categories.clone());
+        assert_valid_table(&ANNEX_K_CHROMINANCE_DC_HUFFMAN_TABLE, categories);
+    }
+
+    #[test]
+    fn annex_k_ac_huffman_tables() {
+        let symbols: HashSet<u8> = (0..16)
+            .flat_map(|run| (1..=10).map(move |size| run << 4 | size))
+            .chain([0x00, 0xF0])
+            .collect();
+        assert_eq!(symbols.len(), 162);
+        assert_valid_table(&ANNEX_K_LUMINANCE_AC_HUFFMAN_TABLE, symbols.clone());
+        assert_valid_table(&ANNEX_K_CHROMINANCE_AC_HUFFMAN_TABLE, symbols);
+    }
+
+    #[test]
+    fn marker_codes() {
+        assert_eq!(marker(SOI), [0xFF, 0xD8]);
+        assert_eq!(marker(EOI), [0xFF, 0xD9]);
+        assert_eq!(marker(RST0 + 7), [0xFF, 0xD7]);
+        assert_eq!(marker(APP0 + 1), [0xFF, 0xE1]);
+    }
+}
@@ -0,0 +1,77 @@
+#[cfg(feature = "clap")]
+use clap::{builder::PossibleValue, ValueEnum};
+
+/// Unit of the pixel density in the JFIF APP0 segment.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DensityUnit {
+    /// No absolute unit, the densities only define the aspect ratio of the pixels.
+    #[default]
+    AspectRatio,
+    DotsPerInch,
+    DotsPerCentimeter,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for DensityUnit {
+    fn value_variants<'a>() -> &'a [Self] {
+        Self::variants()
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()))
+    }
+}
+
+impl DensityUnit {
+    pub fn variants() -> &'static [Self] {
+        &[
+            Self::AspectRatio,
+            Self::DotsPerInch,
+            Self::DotsPerCentimeter,
+        ]
+    }
+
+    /// Name of the unit, as accepted on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AspectRatio => "aspect-ratio",
+            Self::DotsPerInch => "dpi",
+            Self::DotsPerCentimeter => "dpcm",
+        }
+    }
+
+    /// Value of the unit in the JFIF header.
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Self::AspectRatio => 0,
+            Self::DotsPerInch => 1,
+            Self::DotsPerCentimeter => 2,
+        }
+    }
+}
+
+/// Pixel density of the JFIF APP0 segment, which print workflows use to size the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelDensity {
+    pub unit: DensityUnit,
+    pub horizontal: u16,
+    pub vertical: u16,
+}
+
+impl PixelDensity {
+    /// Density of square pixels with the same value in both directions.
+    pub fn uniform(unit: DensityUnit, density: u16) -> Self {
+        Self {
+            unit,
+            horizontal: density,
+            vertical: density,
+        }
+    }
+}
+
+impl Default for PixelDensity {
+    /// Square pixels without an absolute unit, written as 72:72 like most encoders do.
+    fn default() -> Self {
+        Self::uniform(DensityUnit::AspectRatio, 72)
+    }
+}
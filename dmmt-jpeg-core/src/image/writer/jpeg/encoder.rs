@@ -0,0 +1,1554 @@
+use block_fold_iterator::{BlockFoldIterator, ColorInformation};
+
+use crate::binary_stream::{BitReader, BitWriter, ByteCountingWriter};
+use crate::color::BASELINE_SAMPLE_PRECISION;
+use crate::error::Error;
+use crate::huffman::decoder::HuffmanDecoder;
+use crate::huffman::encoder::{HuffmanTranslator, HuffmanWriter};
+use crate::huffman::{code_length_counts, SymbolCodeLength};
+use crate::image::subsampling::SamplingFactors;
+use crate::{BitPattern, Result};
+use std::borrow::Borrow;
+use std::fmt::Display;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::iter;
+
+use super::segment_marker_injector::SegmentMarkerInjector;
+use super::transformer::categorize::{channel_bit_cost, CategorizedBlock};
+use super::transformer::frequency_block::ZigZagIterator;
+use super::transformer::{CategorizedBand, CombinedColorChannels};
+use super::OutputImage;
+use super::{consts, is_baseline_table};
+use crate::logger;
+use segment_buffer::SegmentBuffer;
+
+mod block_fold_iterator;
+mod segment_buffer;
+
+const START_OF_FILE_MARKER: [u8; 2] = consts::marker(consts::SOI);
+const END_OF_FILE_MARKER: [u8; 2] = consts::marker(consts::EOI);
+const HUFFMAN_TABLE_MARKER: [u8; 2] = consts::marker(consts::DHT);
+const QUANTIZATION_TABLE_MARKER: [u8; 2] = consts::marker(consts::DQT);
+const START_OF_FRAME_MARKER: [u8; 2] = consts::marker(consts::SOF0);
+const EXTENDED_START_OF_FRAME_MARKER: [u8; 2] = consts::marker(consts::SOF1);
+const START_OF_SCAN_MARKER: [u8; 2] = consts::marker(consts::SOS);
+const JFIF_APPLICATION_MARKER: [u8; 2] = consts::marker(consts::APP0);
+const EXIF_APPLICATION_MARKER: [u8; 2] = consts::marker(consts::APP0 + 1);
+const XMP_APPLICATION_MARKER: [u8; 2] = consts::marker(consts::APP0 + 1);
+const ADOBE_APPLICATION_MARKER: [u8; 2] = consts::marker(consts::APP0 + 14);
+const APPLICATION_MARKERS: [[u8; 2]; 16] = {
+    let mut markers = [[0; 2]; 16];
+    let mut number = 0;
+    while number < markers.len() {
+        markers[number] = consts::marker(consts::APP0 + number as u8);
+        number += 1;
+    }
+    markers
+};
+const COMMENT_MARKER: [u8; 2] = consts::marker(consts::COM);
+const DEFINE_RESTART_INTERVAL_MARKER: [u8; 2] = consts::marker(consts::DRI);
+const DEFINE_NUMBER_OF_LINES_MARKER: [u8; 2] = consts::marker(consts::DNL);
+const RESTART_MARKERS: [[u8; 2]; 8] = [
+    consts::marker(consts::RST0),
+    consts::marker(consts::RST0 + 1),
+    consts::marker(consts::RST0 + 2),
+    consts::marker(consts::RST0 + 3),
+    consts::marker(consts::RST0 + 4),
+    consts::marker(consts::RST0 + 5),
+    consts::marker(consts::RST0 + 6),
+    consts::marker(consts::RST0 + 7),
+];
+
+/// Maximum content length of a huffman table segment: table class and id, 16 code length
+/// counts and at most 256 symbols.
+const MAX_HUFFMAN_TABLE_SEGMENT_LENGTH: usize = 1 + 16 + 256;
+/// Maximum content length of a quantization table segment: precision and id, 64 values of
+/// at most 16 bit.
+const MAX_QUANTIZATION_TABLE_SEGMENT_LENGTH: usize = 1 + 2 * 64;
+/// Maximum content length of a start of frame segment: precision, height, width, number of
+/// components and three bytes for each of at most four components.
+const MAX_START_OF_FRAME_SEGMENT_LENGTH: usize = 6 + 3 * 4;
+/// Maximum content length of a start of scan segment: number of components, two bytes for each
+/// of at most four components and the spectral selection and successive approximation.
+const MAX_START_OF_SCAN_SEGMENT_LENGTH: usize = 1 + 2 * 4 + 3;
+
+/// Number of blocks at the start of each channel, whose symbols verify the Huffman tables.
+const VERIFIED_BLOCKS_PER_CHANNEL: usize = 64;
+
+/// Size of the chunks, in which the entropy coded data is passed on to the writer.
+const IMAGE_DATA_CHUNK_SIZE: usize = 64 * 1024;
+
+enum ControlMarker {
+    StartOfFile,
+    EndOfFile,
+    Restart(usize),
+}
+
+enum SegmentMarker {
+    HuffmanTable,
+    QuantizationTable,
+    JfifApplication,
+    ExifApplication,
+    XmpApplication,
+    AdobeApplication,
+    Application(u8),
+    Comment,
+    DefineRestartInterval,
+    DefineNumberOfLines,
+    StartOfFrame,
+    ExtendedStartOfFrame,
+    StartOfScan,
+}
+
+trait AsBinaryRef {
+    fn as_binary_ref(&self) -> &'static [u8];
+}
+
+impl AsBinaryRef for ControlMarker {
+    fn as_binary_ref(&self) -> &'static [u8] {
+        match self {
+            Self::StartOfFile => &START_OF_FILE_MARKER,
+            Self::EndOfFile => &END_OF_FILE_MARKER,
+            Self::Restart(index) => &RESTART_MARKERS[index % RESTART_MARKERS.len()],
+        }
+    }
+}
+
+impl AsBinaryRef for SegmentMarker {
+    fn as_binary_ref(&self) -> &'static [u8] {
+        match self {
+            Self::HuffmanTable => &HUFFMAN_TABLE_MARKER,
+            Self::QuantizationTable => &QUANTIZATION_TABLE_MARKER,
+            Self::JfifApplication => &JFIF_APPLICATION_MARKER,
+            Self::ExifApplication => &EXIF_APPLICATION_MARKER,
+            Self::XmpApplication => &XMP_APPLICATION_MARKER,
+            Self::AdobeApplication => &ADOBE_APPLICATION_MARKER,
+            Self::Application(number) => &APPLICATION_MARKERS[*number as usize],
+            Self::Comment => &COMMENT_MARKER,
+            Self::DefineRestartInterval => &DEFINE_RESTART_INTERVAL_MARKER,
+            Self::DefineNumberOfLines => &DEFINE_NUMBER_OF_LINES_MARKER,
+            Self::StartOfFrame => &START_OF_FRAME_MARKER,
+            Self::ExtendedStartOfFrame => &EXTENDED_START_OF_FRAME_MARKER,
+            Self::StartOfScan => &START_OF_SCAN_MARKER,
+        }
+    }
+}
+
+impl Display for SegmentMarker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HuffmanTable => write!(f, "Huffman Table"),
+            Self::QuantizationTable => write!(f, "Quantization Table"),
+            Self::JfifApplication => write!(f, "Jfif Application"),
+            Self::ExifApplication => write!(f, "Exif Application"),
+            Self::XmpApplication => write!(f, "Xmp Application"),
+            Self::AdobeApplication => write!(f, "Adobe Application"),
+            Self::Application(number) => write!(f, "Application {}", number),
+            Self::Comment => write!(f, "Comment"),
+            Self::DefineRestartInterval => write!(f, "Define Restart Interval"),
+            Self::DefineNumberOfLines => write!(f, "Define Number of Lines"),
+            Self::StartOfFrame => write!(f, "Start of Frame"),
+            Self::ExtendedStartOfFrame => write!(f, "Extended Start of Frame"),
+            Self::StartOfScan => write!(f, "Start of Scan"),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+enum TableKind {
+    LumaDC = 0b0000_0000,
+    LumaAC = 0b0001_0001,
+    ChromaDC = 0b0000_0010,
+    ChromaAC = 0b0001_0011,
+}
+
+impl TableKind {
+    fn value(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Identifier of the table within its class.
+    fn id(&self) -> u8 {
+        self.value() & 0x0F
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::LumaDC => "luma DC",
+            Self::LumaAC => "luma AC",
+            Self::ChromaDC => "chroma DC",
+            Self::ChromaAC => "chroma AC",
+        }
+    }
+}
+
+/// Component of the frame with the tables it selects, from which the frame and scan headers
+/// are built.
+#[derive(Clone, Copy)]
+struct FrameComponent {
+    id: u8,
+    sampling_factors: SamplingFactors,
+    quantization_table: u8,
+    dc_huffman_table: u8,
+    ac_huffman_table: u8,
+}
+
+pub struct Encoder<'a, T: Write> {
+    writer: ByteCountingWriter<&'a mut T>,
+    image: &'a OutputImage,
+    huffman_translators: HuffmanTranslators,
+    restart_interval_offsets: Vec<u64>,
+}
+
+struct HuffmanTranslators {
+    luma_ac_huffman_translator: HuffmanTranslator,
+    luma_dc_huffman_translator: HuffmanTranslator,
+    chroma_ac_huffman_translator: HuffmanTranslator,
+    chroma_dc_huffman_translator: HuffmanTranslator,
+}
+
+impl<'a, T: Write> Encoder<'a, T> {
+    pub fn new(writer: &'a mut T, image: &'a OutputImage) -> Encoder<'a, T> {
+        let luma_ac_huffman_translator = HuffmanTranslator::from(&image.huffman_tables.luma_ac);
+        let luma_dc_huffman_translator = HuffmanTranslator::from(&image.huffman_tables.luma_dc);
+        // A grayscale image has no chroma tables
+        let chroma_translator = |code_lengths: &Vec<SymbolCodeLength>| {
+            if code_lengths.is_empty() {
+                HuffmanTranslator::default()
+            } else {
+                HuffmanTranslator::from(code_lengths)
+            }
+        };
+        let chroma_ac_huffman_translator = chroma_translator(&image.huffman_tables.chroma_ac);
+        let chroma_dc_huffman_translator = chroma_translator(&image.huffman_tables.chroma_dc);
+        Encoder {
+            writer: ByteCountingWriter::new(writer),
+            image,
+            huffman_translators: HuffmanTranslators {
+                luma_ac_huffman_translator,
+                luma_dc_huffman_translator,
+                chroma_ac_huffman_translator,
+                chroma_dc_huffman_translator,
+            },
+            restart_interval_offsets: Vec::new(),
+        }
+    }
+
+    pub fn encode(&mut self) -> Result<()> {
+        let image = self.image;
+        self.encode_bands(iter::once(&image.blockwise_image_data))
+    }
+
+    /// Encodes the image with the scan written from the bands, which are taken one after
+    /// another in the order of the image. The blocks of the output image are not used, so a band
+    /// can be dropped, as soon as it is written.
+    pub fn encode_bands<B: Borrow<CategorizedBand>>(
+        &mut self,
+        bands: impl Iterator<Item = B>,
+    ) -> Result<()> {
+        let mut bands = bands.peekable();
+        self.write_start_of_file()?;
+        self.write_jfif_application_header()?;
+        self.write_exif_application_header()?;
+        self.write_xmp_application_header()?;
+        self.write_adobe_application_header()?;
+        self.write_application_segments()?;
+        self.write_comments()?;
+        self.write_settings_comment()?;
+        self.write_all_quantization_tables()?;
+        self.write_start_of_frame()?;
+        match bands.peek() {
+            Some(first_band) => self.verify_huffman_tables(first_band.borrow())?,
+            None => self.verify_huffman_tables(&self.image.blockwise_image_data)?,
+        }
+        self.write_all_huffman_tables()?;
+        self.write_restart_interval()?;
+        self.write_start_of_scan()?;
+        self.write_bands(bands)?;
+        self.write_number_of_lines()?;
+        self.write_end_of_file()?;
+        Ok(())
+    }
+
+    /// Number of bytes written so far.
+    pub fn bytes_written(&self) -> u64 {
+        self.writer.bytes_written()
+    }
+
+    /// Returns the byte offsets of the restart intervals, collected while the image data was
+    /// written.
+    pub fn take_restart_interval_offsets(&mut self) -> Vec<u64> {
+        std::mem::take(&mut self.restart_interval_offsets)
+    }
+
+    fn write_segment(&mut self, marker: SegmentMarker, content: &[u8]) -> io::Result<()> {
+        log::info!("Writing {}", marker);
+        let marker_binary_ref = marker.as_binary_ref();
+        let segment_len = marker_binary_ref.len() + content.len();
+        if segment_len > u16::MAX as usize {
+            panic!(
+                "The length of the segment '{}' is greater than u16::MAX",
+                marker
+            );
+        }
+        let segment_length = (segment_len as u16).to_be_bytes();
+        logger::log_segment(marker_binary_ref, content, &segment_length);
+        self.writer.write_all(marker_binary_ref)?;
+        self.writer.write_all(&segment_length)?;
+        self.writer.write_all(content)?;
+        Ok(())
+    }
+
+    fn write_control_marker(&mut self, marker: ControlMarker) -> io::Result<()> {
+        self.writer.write_all(marker.as_binary_ref())
+    }
+
+    fn write_start_of_file(&mut self) -> Result<()> {
+        self.write_control_marker(ControlMarker::StartOfFile)
+            .map_err(|_| Error::FailedToWriteStartOfFile)
+    }
+
+    fn write_end_of_file(&mut self) -> Result<()> {
+        self.write_control_marker(ControlMarker::EndOfFile)
+            .map_err(|_| Error::FailedToWriteEndOfFile)
+    }
+
+    fn huffman_table_content(
+        table_kind: TableKind,
+        symdepths: &[SymbolCodeLength],
+    ) -> SegmentBuffer<MAX_HUFFMAN_TABLE_SEGMENT_LENGTH> {
+        let mut header = SegmentBuffer::<MAX_HUFFMAN_TABLE_SEGMENT_LENGTH>::new();
+        header.push(table_kind.value());
+        header.extend(code_length_counts(symdepths));
+        header.extend(symdepths.iter().rev().map(|i| i.symbol));
+        header
+    }
+
+    fn write_huffman_table(
+        &mut self,
+        table_kind: TableKind,
+        symdepths: &[SymbolCodeLength],
+    ) -> Result<()> {
+        let header = Self::huffman_table_content(table_kind, symdepths);
+        self.write_segment(SegmentMarker::HuffmanTable, header.as_slice())
+            .map_err(|_| Error::FailedToWriteHuffmanTables)
+    }
+
+    /// Huffman tables of the frame. A grayscale frame has no chroma tables.
+    fn huffman_tables(&self) -> Vec<(TableKind, &'a [SymbolCodeLength])> {
+        let image = self.image;
+        let mut tables = vec![
+            (TableKind::LumaAC, image.huffman_tables.luma_ac.as_slice()),
+            (TableKind::LumaDC, image.huffman_tables.luma_dc.as_slice()),
+        ];
+        if !image.grayscale {
+            tables.push((
+                TableKind::ChromaAC,
+                image.huffman_tables.chroma_ac.as_slice(),
+            ));
+            tables.push((
+                TableKind::ChromaDC,
+                image.huffman_tables.chroma_dc.as_slice(),
+            ));
+        }
+        tables
+    }
+
+    /// Writes each table into its own segment or all tables into a single segment.
+    fn write_all_huffman_tables(&mut self) -> Result<()> {
+        let tables = self.huffman_tables();
+        if !self.image.combine_table_segments {
+            for (table_kind, symdepths) in tables {
+                self.write_huffman_table(table_kind, symdepths)?;
+            }
+            return Ok(());
+        }
+        let content: Vec<u8> = tables
+            .into_iter()
+            .flat_map(|(table_kind, symdepths)| {
+                Self::huffman_table_content(table_kind, symdepths)
+                    .as_slice()
+                    .to_vec()
+            })
+            .collect();
+        self.write_segment(SegmentMarker::HuffmanTable, &content)
+            .map_err(|_| Error::FailedToWriteHuffmanTables)
+    }
+
+    /// Codes the symbols of the first blocks of each channel of the first band and decodes them
+    /// with the tables of the DHT segments, which are about to be written. Code length counts or
+    /// a symbol order, which don't describe the code words of the translators, fail the
+    /// encoding, before a broken image is written.
+    fn verify_huffman_tables(&self, channels: &CategorizedBand) -> Result<()> {
+        let luma = || {
+            let key = channels.key.iter();
+            channels
+                .luma
+                .iter()
+                .take(VERIFIED_BLOCKS_PER_CHANNEL)
+                .chain(key.take(VERIFIED_BLOCKS_PER_CHANNEL))
+        };
+        let chroma = || {
+            let chroma_blue = channels.chroma_blue.iter();
+            let chroma_red = channels.chroma_red.iter();
+            chroma_blue
+                .take(VERIFIED_BLOCKS_PER_CHANNEL)
+                .chain(chroma_red.take(VERIFIED_BLOCKS_PER_CHANNEL))
+        };
+        for (table_kind, symdepths) in self.huffman_tables() {
+            let symbols: Vec<u8> = match table_kind {
+                TableKind::LumaDC => luma().map(CategorizedBlock::dc_symbol).collect(),
+                TableKind::LumaAC => luma().flat_map(|block| block.iter_ac_symbols()).collect(),
+                TableKind::ChromaDC => chroma().map(CategorizedBlock::dc_symbol).collect(),
+                TableKind::ChromaAC => chroma().flat_map(|block| block.iter_ac_symbols()).collect(),
+            };
+            verify_huffman_table(
+                table_kind,
+                Self::huffman_table_content(table_kind, symdepths).as_slice(),
+                self.huffman_translators.translator(table_kind),
+                &symbols,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Quantization tables of the frame with their numbers. The separate table of the Cr
+    /// component takes the third slot, if there is one.
+    fn quantization_tables(&self) -> Vec<(u8, &'a [u16; 64])> {
+        let pair = &self.image.quantization_table_pair;
+        let mut tables = vec![(0, &pair.luma_table)];
+        if self.image.grayscale {
+            return tables;
+        }
+        tables.push((1, &pair.chroma_table));
+        if let Some(chroma_red_table) = &pair.chroma_red_table {
+            tables.push((2, chroma_red_table));
+        }
+        tables
+    }
+
+    /// Writes each table into its own segment or all tables into a single segment.
+    fn write_all_quantization_tables(&mut self) -> Result<()> {
+        let tables = self.quantization_tables();
+        if !self.image.combine_table_segments {
+            for (number, table) in tables {
+                self.write_quantization_table(number, table)?;
+            }
+            return Ok(());
+        }
+        let content: Vec<u8> = tables
+            .into_iter()
+            .flat_map(|(number, table)| {
+                self.quantization_table_content(number, table)
+                    .as_slice()
+                    .to_vec()
+            })
+            .collect();
+        self.write_segment(SegmentMarker::QuantizationTable, &content)
+            .map_err(|_| Error::FailedToWriteQuantizationTable)
+    }
+
+    /// Baseline frames require 8 bit table values. Frames of extended sample precision and
+    /// tables with larger steps get 16 bit table values.
+    fn quantization_table_content(
+        &self,
+        number: u8,
+        table: &[u16; 64],
+    ) -> SegmentBuffer<MAX_QUANTIZATION_TABLE_SEGMENT_LENGTH> {
+        let mut header = SegmentBuffer::<MAX_QUANTIZATION_TABLE_SEGMENT_LENGTH>::new();
+        if self.image.bits_per_channel == BASELINE_SAMPLE_PRECISION && is_baseline_table(table) {
+            header.push(number);
+            header.extend(ZigZagIterator::from(table).map(|&value| value as u8));
+        } else {
+            header.push(0x10 | number);
+            header.extend(ZigZagIterator::from(table).flat_map(|value| value.to_be_bytes()));
+        }
+        header
+    }
+
+    /// Baseline frames are limited to 8 bit samples and 8 bit quantization tables.
+    fn is_baseline(&self) -> bool {
+        self.image.bits_per_channel == BASELINE_SAMPLE_PRECISION
+            && self
+                .quantization_tables()
+                .into_iter()
+                .all(|(_, table)| is_baseline_table(table))
+    }
+
+    fn write_quantization_table(&mut self, number: u8, table: &[u16; 64]) -> Result<()> {
+        let header = self.quantization_table_content(number, table);
+        self.write_segment(SegmentMarker::QuantizationTable, header.as_slice())
+            .map_err(|_| Error::FailedToWriteQuantizationTable)
+    }
+
+    /// Writes the APP0 segment of JFIF, which is defined for one or three components only. CMYK
+    /// images are described by the Adobe segment instead.
+    fn write_jfif_application_header(&mut self) -> Result<()> {
+        if self.image.cmyk {
+            return Ok(());
+        }
+        let (thumbnail_width, thumbnail_height, thumbnail_samples) = match &self.image.thumbnail {
+            Some(thumbnail) => (
+                thumbnail.size.width(),
+                thumbnail.size.height(),
+                thumbnail.samples.as_slice(),
+            ),
+            None => (0, 0, [].as_slice()),
+        };
+        let density = &self.image.pixel_density;
+        let [horizontal_high, horizontal_low] = density.horizontal.to_be_bytes();
+        let [vertical_high, vertical_low] = density.vertical.to_be_bytes();
+        #[rustfmt::skip]
+        let header = [
+            b'J', b'F', b'I', b'F', b'\0',     // Identifier
+            0x01, 0x02,                        // Version
+            density.unit.to_byte(),            // Density unit
+            horizontal_high, horizontal_low,   // X Density
+            vertical_high, vertical_low,       // Y Density
+            thumbnail_width,                   // X Thumbnail
+            thumbnail_height                   // Y Thumbnail
+        ];
+        let content = [header.as_slice(), thumbnail_samples].concat();
+        self.write_segment(SegmentMarker::JfifApplication, &content)
+            .map_err(|_| Error::FailedToWriteJfifApplicationHeader)
+    }
+
+    fn write_exif_application_header(&mut self) -> Result<()> {
+        if self.image.exif_metadata.is_empty() {
+            return Ok(());
+        }
+        let content = self.image.exif_metadata.to_segment_content();
+        self.write_segment(SegmentMarker::ExifApplication, &content)
+            .map_err(|_| Error::FailedToWriteExifApplicationHeader)
+    }
+
+    fn write_xmp_application_header(&mut self) -> Result<()> {
+        let Some(xmp_packet) = &self.image.xmp_packet else {
+            return Ok(());
+        };
+        let content = xmp_packet.to_segment_content();
+        self.write_segment(SegmentMarker::XmpApplication, &content)
+            .map_err(|_| Error::FailedToWriteXmpApplicationHeader)
+    }
+
+    /// Writes the APP14 segment of Adobe. Its transform flag tells decoders, which do not rely on
+    /// JFIF, that three components are YCbCr instead of RGB. CMYK images always carry it, as
+    /// only the flag tells decoders, that the four components are YCCK.
+    fn write_adobe_application_header(&mut self) -> Result<()> {
+        if !self.image.adobe_marker && !self.image.cmyk {
+            return Ok(());
+        }
+        let transform = if self.image.cmyk {
+            2
+        } else if self.image.grayscale {
+            0
+        } else {
+            1
+        };
+        #[rustfmt::skip]
+        let content = &[
+            b'A', b'd', b'o', b'b', b'e', // Identifier
+            0x00, 0x64,                   // Version 100
+            0x00, 0x00,                   // Flags 0
+            0x00, 0x00,                   // Flags 1
+            transform,                    // Color transform (0 unknown, 1 YCbCr, 2 YCCK)
+        ];
+        self.write_segment(SegmentMarker::AdobeApplication, content)
+            .map_err(|_| Error::FailedToWriteAdobeApplicationHeader)
+    }
+
+    fn write_application_segments(&mut self) -> Result<()> {
+        for segment in &self.image.application_segments {
+            self.write_segment(
+                SegmentMarker::Application(segment.marker_number),
+                &segment.content,
+            )
+            .map_err(|_| Error::FailedToWriteApplicationSegment)?;
+        }
+        Ok(())
+    }
+
+    fn write_comments(&mut self) -> Result<()> {
+        for comment in &self.image.comments {
+            self.write_segment(SegmentMarker::Comment, comment.as_bytes())
+                .map_err(|_| Error::FailedToWriteComment)?;
+        }
+        Ok(())
+    }
+
+    fn write_settings_comment(&mut self) -> Result<()> {
+        let Some(settings_comment) = &self.image.settings_comment else {
+            return Ok(());
+        };
+        self.write_segment(SegmentMarker::Comment, settings_comment.as_bytes())
+            .map_err(|_| Error::FailedToWriteComment)
+    }
+
+    fn write_restart_interval(&mut self) -> Result<()> {
+        let Some(restart_interval) = self.image.restart_interval else {
+            return Ok(());
+        };
+        self.write_segment(
+            SegmentMarker::DefineRestartInterval,
+            &restart_interval.to_be_bytes(),
+        )
+        .map_err(|_| Error::FailedToWriteRestartInterval)
+    }
+
+    /// Components of the frame in the order of the frame and scan headers. The sampling factors
+    /// are taken from the layout of the subsampling preset.
+    fn frame_components(&self) -> Vec<FrameComponent> {
+        let layout = self.image.chroma_subsampling_preset.sampling_layout();
+        let luma = FrameComponent {
+            id: 0x01,
+            sampling_factors: layout.luma_factors,
+            quantization_table: 0,
+            dc_huffman_table: TableKind::LumaDC.id(),
+            ac_huffman_table: TableKind::LumaAC.id(),
+        };
+        if self.image.grayscale {
+            return vec![luma];
+        }
+        let chroma = |id, quantization_table| FrameComponent {
+            id,
+            sampling_factors: layout.chroma_factors,
+            quantization_table,
+            dc_huffman_table: TableKind::ChromaDC.id(),
+            ac_huffman_table: TableKind::ChromaAC.id(),
+        };
+        let chroma_red_table = match self.image.quantization_table_pair.chroma_red_table {
+            Some(_) => 2,
+            None => 1,
+        };
+        let mut components = vec![luma, chroma(0x02, 1), chroma(0x03, chroma_red_table)];
+        if self.image.cmyk {
+            // The black ink of YCCK is sampled and coded like the luma
+            components.push(FrameComponent { id: 0x04, ..luma });
+        }
+        components
+    }
+
+    fn write_start_of_frame(&mut self) -> Result<()> {
+        let components = self.frame_components();
+        let mut content = SegmentBuffer::<MAX_START_OF_FRAME_SEGMENT_LENGTH>::new();
+        content.push(self.image.bits_per_channel);
+        // A height of 0 defers the height to the DNL segment after the scan
+        let height = if self.image.define_number_of_lines {
+            0
+        } else {
+            self.image.height
+        };
+        content.extend(height.to_be_bytes());
+        content.extend(self.image.width.to_be_bytes());
+        content.push(components.len() as u8);
+        for component in &components {
+            content.extend([
+                component.id,
+                component.sampling_factors.to_byte(),
+                component.quantization_table,
+            ]);
+        }
+        let marker = if self.is_baseline() {
+            SegmentMarker::StartOfFrame
+        } else {
+            SegmentMarker::ExtendedStartOfFrame
+        };
+        self.write_segment(marker, content.as_slice())
+            .map_err(|_| Error::FailedToWriteStartOfFrame)
+    }
+
+    fn write_number_of_lines(&mut self) -> Result<()> {
+        if !self.image.define_number_of_lines {
+            return Ok(());
+        }
+        self.write_segment(
+            SegmentMarker::DefineNumberOfLines,
+            &self.image.height.to_be_bytes(),
+        )
+        .map_err(|_| Error::FailedToWriteNumberOfLines)
+    }
+
+    fn write_start_of_scan(&mut self) -> Result<()> {
+        let components = self.frame_components();
+        let mut content = SegmentBuffer::<MAX_START_OF_SCAN_SEGMENT_LENGTH>::new();
+        content.push(components.len() as u8);
+        for component in &components {
+            content.extend([
+                component.id,
+                component.dc_huffman_table << 4 | component.ac_huffman_table,
+            ]);
+        }
+        // Sequential scans cover the whole spectrum without successive approximation
+        content.extend([0x00, 0x3F, 0x00]);
+        self.write_segment(SegmentMarker::StartOfScan, content.as_slice())
+            .map_err(|_| Error::FailedToWriteStartOfScan)
+    }
+
+    /// Streams the entropy coded data of the bands through the byte stuffing to the writer. The
+    /// data is collected in chunks of bounded size, so the scan is never buffered as a whole.
+    /// Each band holds whole MCUs, the bit writer carries the bits over to the next band.
+    ///
+    /// With a restart interval, the data of each interval is padded to a byte border and followed
+    /// by a restart marker. The offset of each interval is recorded.
+    fn write_bands<B: Borrow<CategorizedBand>>(
+        &mut self,
+        bands: impl Iterator<Item = B>,
+    ) -> Result<()> {
+        let subsampling_preset = self.image.chroma_subsampling_preset;
+        // A scan of the luma component only is not interleaved, each block is an MCU
+        let blocks_per_mcu = if self.image.grayscale {
+            1
+        } else if self.image.cmyk {
+            subsampling_preset.blocks_per_mcu() + subsampling_preset.luma_blocks_per_mcu()
+        } else {
+            subsampling_preset.blocks_per_mcu()
+        };
+        let mcus_per_interval = self
+            .image
+            .restart_interval
+            .map(|restart_interval| usize::from(restart_interval).max(1));
+
+        let translators = &self.huffman_translators;
+        let log_bit_cost = log::log_enabled!(log::Level::Debug);
+        let mut scan_bit_cost = 0;
+        let scan_offset = self.writer.bytes_written();
+        let chunk_writer = BufWriter::with_capacity(IMAGE_DATA_CHUNK_SIZE, &mut self.writer);
+        let mut scan_writer = ByteCountingWriter::new(chunk_writer);
+        let mut segment_marker_injector = SegmentMarkerInjector::new(&mut scan_writer);
+        let mut bit_writer = BitWriter::new(&mut segment_marker_injector, true);
+        let mut interval_index = 0;
+        let mut interval_mcus = 0;
+        if mcus_per_interval.is_some() {
+            self.restart_interval_offsets.push(scan_offset);
+            log::debug!("Writing restart interval 1");
+        }
+        for band in bands {
+            let channels = band.borrow();
+            let number_of_blocks = channels.luma.len()
+                + channels.chroma_blue.len()
+                + channels.chroma_red.len()
+                + channels.key.len();
+            let number_of_mcus = number_of_blocks / blocks_per_mcu;
+            debug_assert!(
+                self.image.grayscale
+                    || channels.chroma_blue.len() == number_of_mcus
+                        && channels.chroma_red.len() == number_of_mcus,
+                "each MCU needs one block of each chroma channel"
+            );
+            if log_bit_cost {
+                scan_bit_cost += translators.scan_bit_cost(channels)?;
+            }
+            let block_fold_iterator = if self.image.grayscale {
+                BlockFoldIterator::luma_only(channels)
+            } else {
+                BlockFoldIterator::new(channels, subsampling_preset)
+            };
+            for (index, (color_info, block)) in block_fold_iterator.enumerate() {
+                let starts_mcu = index % blocks_per_mcu == 0;
+                if starts_mcu && mcus_per_interval == Some(interval_mcus) {
+                    bit_writer
+                        .write_padding()
+                        .map_err(|_| Error::FailedToWriteBlock)?;
+                    let scan_writer = bit_writer.get_mut().get_mut();
+                    scan_writer
+                        .write_all(ControlMarker::Restart(interval_index).as_binary_ref())
+                        .map_err(|_| Error::FailedToWriteBlock)?;
+                    interval_index += 1;
+                    interval_mcus = 0;
+                    self.restart_interval_offsets
+                        .push(scan_offset + scan_writer.bytes_written());
+                    log::debug!("Writing restart interval {}", interval_index + 1);
+                }
+                if starts_mcu {
+                    interval_mcus += 1;
+                }
+                match color_info {
+                    ColorInformation::Luma => {
+                        translators.write_luma_block(&mut bit_writer, block)?
+                    }
+                    ColorInformation::Chroma => {
+                        translators.write_chroma_block(&mut bit_writer, block)?
+                    }
+                }
+            }
+        }
+        bit_writer
+            .write_padding()
+            .map_err(|_| Error::FailedToWriteBlock)?;
+        if log_bit_cost {
+            log::debug!("Entropy coded data holds {} bits", scan_bit_cost);
+        }
+        // passes the buffered chunk on, the writer of the image flushes the output at the end
+        scan_writer
+            .into_inner()
+            .into_inner()
+            .map(|_| ())
+            .map_err(|_| Error::FailedToWriteBlock)
+    }
+}
+
+/// Codes the symbols with the translator and decodes them with the table of the DHT segment
+/// content, which must yield the same symbols.
+fn verify_huffman_table(
+    table_kind: TableKind,
+    content: &[u8],
+    translator: &HuffmanTranslator,
+    symbols: &[u8],
+) -> Result<()> {
+    let failure = |reason: String| Error::HuffmanTableVerificationFailed(table_kind.name(), reason);
+    let code_length_counts: &[u8; 16] = content[1..17]
+        .try_into()
+        .expect("DHT segment content holds 16 code length counts");
+    let decoder = HuffmanDecoder::new(code_length_counts, &content[17..])
+        .map_err(|error| failure(error.to_string()))?;
+    let mut coded = Vec::new();
+    {
+        let mut bit_writer = BitWriter::new(&mut coded, true);
+        let mut huffman_writer = HuffmanWriter::new(translator, &mut bit_writer);
+        huffman_writer
+            .write_all(symbols)
+            .and_then(|_| huffman_writer.flush())
+            .map_err(|_| failure("a symbol has no code word".to_string()))?;
+    }
+    let mut bit_reader = BitReader::new(coded.as_slice(), false);
+    for (position, &symbol) in symbols.iter().enumerate() {
+        let decoded = decoder
+            .decode(|| match bit_reader.read_bit() {
+                Ok(Some(bit)) => Ok(bit as u16),
+                _ => Err(Error::InvalidJPEGFile(
+                    "coded symbols end within a code word",
+                )),
+            })
+            .map_err(|error| {
+                failure(format!(
+                    "symbol {} at position {} is not decoded: {}",
+                    symbol, position, error
+                ))
+            })?;
+        if decoded != symbol {
+            return Err(failure(format!(
+                "symbol {} at position {} is decoded as {}",
+                symbol, position, decoded
+            )));
+        }
+    }
+    Ok(())
+}
+
+impl HuffmanTranslators {
+    fn translator(&self, table_kind: TableKind) -> &HuffmanTranslator {
+        match table_kind {
+            TableKind::LumaDC => &self.luma_dc_huffman_translator,
+            TableKind::LumaAC => &self.luma_ac_huffman_translator,
+            TableKind::ChromaDC => &self.chroma_dc_huffman_translator,
+            TableKind::ChromaAC => &self.chroma_ac_huffman_translator,
+        }
+    }
+
+    /// Number of bits of the entropy coded data of all channels, without padding, byte
+    /// stuffing and restart markers.
+    fn scan_bit_cost(
+        &self,
+        channels: &CombinedColorChannels<Vec<CategorizedBlock>>,
+    ) -> Result<u64> {
+        let luma_cost = channel_bit_cost(
+            &channels.luma,
+            &self.luma_dc_huffman_translator,
+            &self.luma_ac_huffman_translator,
+        )? + channel_bit_cost(
+            &channels.key,
+            &self.luma_dc_huffman_translator,
+            &self.luma_ac_huffman_translator,
+        )?;
+        let chroma_cost = channel_bit_cost(
+            &channels.chroma_blue,
+            &self.chroma_dc_huffman_translator,
+            &self.chroma_ac_huffman_translator,
+        )? + channel_bit_cost(
+            &channels.chroma_red,
+            &self.chroma_dc_huffman_translator,
+            &self.chroma_ac_huffman_translator,
+        )?;
+        Ok(luma_cost + chroma_cost)
+    }
+
+    fn write_luma_block<W: Write>(
+        &self,
+        bit_writer: &mut BitWriter<'_, W>,
+        block: &CategorizedBlock,
+    ) -> Result<()> {
+        self.write_luma_dc_from_block(bit_writer, block)?;
+        self.write_luma_ac_from_block(bit_writer, block)?;
+        Ok(())
+    }
+
+    fn write_chroma_block<W: Write>(
+        &self,
+        bit_writer: &mut BitWriter<'_, W>,
+        block: &CategorizedBlock,
+    ) -> Result<()> {
+        self.write_chroma_dc_from_block(bit_writer, block)?;
+        self.write_chroma_ac_from_block(bit_writer, block)?;
+        Ok(())
+    }
+
+    fn write_luma_dc_from_block<W: Write>(
+        &self,
+        bit_writer: &mut BitWriter<'_, W>,
+        block: &CategorizedBlock,
+    ) -> Result<()> {
+        Self::write_dc_from_block(
+            bit_writer,
+            block,
+            &self.luma_dc_huffman_translator,
+            "luma dc",
+        )
+    }
+
+    fn write_chroma_dc_from_block<W: Write>(
+        &self,
+        bit_writer: &mut BitWriter<'_, W>,
+        block: &CategorizedBlock,
+    ) -> Result<()> {
+        Self::write_dc_from_block(
+            bit_writer,
+            block,
+            &self.chroma_dc_huffman_translator,
+            "chroma dc",
+        )
+    }
+
+    fn write_luma_ac_from_block<W: Write>(
+        &self,
+        bit_writer: &mut BitWriter<'_, W>,
+        block: &CategorizedBlock,
+    ) -> Result<()> {
+        Self::write_ac_from_block(
+            bit_writer,
+            block,
+            &self.luma_ac_huffman_translator,
+            "luma ac",
+        )
+    }
+
+    fn write_chroma_ac_from_block<W: Write>(
+        &self,
+        bit_writer: &mut BitWriter<'_, W>,
+        block: &CategorizedBlock,
+    ) -> Result<()> {
+        Self::write_ac_from_block(
+            bit_writer,
+            block,
+            &self.chroma_ac_huffman_translator,
+            "chroma ac",
+        )
+    }
+
+    fn write_dc_from_block<W: Write>(
+        bit_writer: &mut BitWriter<'_, W>,
+        block: &CategorizedBlock,
+        huffman_translator: &HuffmanTranslator,
+        component_name: &'static str,
+    ) -> Result<()> {
+        let symbol = block.dc_symbol();
+        let symbol = huffman_translator
+            .get_code_word_for_symbol(symbol)
+            .as_ref()
+            .ok_or(Error::HuffmanSymbolNotPresentInTranslator(
+                symbol,
+                component_name,
+            ))?;
+        let category = block.dc_category();
+        Self::write_symbol_and_category(bit_writer, symbol, category)
+            .map_err(|_| Error::FailedToWriteBlock)?;
+        Ok(())
+    }
+
+    fn write_symbol_and_category<W: Write>(
+        bit_writer: &mut BitWriter<'_, W>,
+        symbol: &impl BitPattern,
+        category: &impl BitPattern,
+    ) -> io::Result<()> {
+        bit_writer.write_bit_pattern(symbol)?;
+        bit_writer.write_bit_pattern(category)?;
+        Ok(())
+    }
+
+    fn write_ac_from_block<W: Write>(
+        bit_writer: &mut BitWriter<'_, W>,
+        block: &CategorizedBlock,
+        huffman_tranlator: &HuffmanTranslator,
+        component_name: &'static str,
+    ) -> Result<()> {
+        for (symbol, category) in block.iter_ac_symbols().zip(block.iter_ac_categories()) {
+            let symbol = huffman_tranlator
+                .get_code_word_for_symbol(symbol)
+                .as_ref()
+                .ok_or(Error::HuffmanSymbolNotPresentInTranslator(
+                    symbol,
+                    component_name,
+                ))?;
+            Self::write_symbol_and_category(bit_writer, symbol, category)
+                .map_err(|_| Error::FailedToWriteBlock)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+
+    use crate::{
+        huffman::SymbolCodeLength,
+        image::{
+            subsampling::ChromaSubsamplingPreset,
+            writer::jpeg::{
+                density::{DensityUnit, PixelDensity},
+                exif::ExifMetadata,
+                thumbnail::{Thumbnail, ThumbnailSize},
+                transformer::{
+                    categorize::{CategorizedBlock, CategoryEncodedInteger, LeadingZerosToken},
+                    CombinedColorChannels,
+                },
+                xmp::XmpPacket,
+                HuffmanTables, QuantizationTablePreset,
+            },
+        },
+    };
+
+    use super::{super::OutputImage, verify_huffman_table, Encoder, TableKind};
+    use crate::{huffman::encoder::HuffmanTranslator, Error};
+
+    const HUFFMAN_CODES: &[SymbolCodeLength; 2] = &[
+        SymbolCodeLength {
+            symbol: 3,
+            length: 5,
+        },
+        SymbolCodeLength {
+            symbol: 1,
+            length: 1,
+        },
+    ];
+
+    fn create_test_image() -> OutputImage {
+        OutputImage {
+            width: 3,
+            height: 2,
+            chroma_subsampling_preset: ChromaSubsamplingPreset::P444,
+            grayscale: false,
+            cmyk: false,
+            bits_per_channel: 8,
+            huffman_tables: HuffmanTables {
+                luma_ac: Vec::from(HUFFMAN_CODES),
+                luma_dc: Vec::from(HUFFMAN_CODES),
+                chroma_ac: Vec::from(HUFFMAN_CODES),
+                chroma_dc: Vec::from(HUFFMAN_CODES),
+            },
+            incomplete_huffman_tables: false,
+            blockwise_image_data: CombinedColorChannels {
+                luma: Vec::new(),
+                chroma_red: Vec::new(),
+                chroma_blue: Vec::new(),
+                key: Vec::new(),
+            },
+            quantization_table_pair: QuantizationTablePreset::Specification.to_pair(),
+            exif_metadata: ExifMetadata::default(),
+            xmp_packet: None,
+            thumbnail: None,
+            pixel_density: PixelDensity::default(),
+            adobe_marker: false,
+            application_segments: Vec::new(),
+            define_number_of_lines: false,
+            combine_table_segments: false,
+            comments: Vec::new(),
+            restart_interval: None,
+            settings_comment: None,
+            symbol_stats: None,
+        }
+    }
+
+    #[test]
+    fn test_write_jfif() {
+        let mut output = Vec::new();
+        let image = create_test_image();
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_jfif_application_header().unwrap();
+        assert_eq!(
+            output,
+            [
+                0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F', b'\0', 0x01, 0x02, 0x00, 0x00,
+                0x48, 0x00, 0x48, 0, 0
+            ]
+        )
+    }
+
+    #[test]
+    fn test_write_jfif_with_pixel_density() {
+        let mut output = Vec::new();
+        let mut image = create_test_image();
+        image.pixel_density = PixelDensity {
+            unit: DensityUnit::DotsPerInch,
+            horizontal: 300,
+            vertical: 600,
+        };
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_jfif_application_header().unwrap();
+        assert_eq!(output[11..16], [0x01, 0x01, 0x2C, 0x02, 0x58]);
+    }
+
+    #[test]
+    fn test_write_jfif_with_thumbnail() {
+        let mut output = Vec::new();
+        let mut image = create_test_image();
+        image.thumbnail = Some(Thumbnail {
+            size: ThumbnailSize::new(2, 1).unwrap(),
+            samples: vec![255, 0, 0, 0, 0, 255],
+        });
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_jfif_application_header().unwrap();
+        assert_eq!(output[2..4], [0x00, 0x16]);
+        assert_eq!(output[16..18], [2, 1]);
+        assert_eq!(output[18..], [255, 0, 0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_write_exif() {
+        let mut output = Vec::new();
+        let mut image = create_test_image();
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_exif_application_header().unwrap();
+        assert!(output.is_empty(), "empty EXIF metadata must not be written");
+
+        image.exif_metadata.user_comment = Some("comment".to_string());
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_exif_application_header().unwrap();
+        let content = image.exif_metadata.to_segment_content();
+        assert_eq!(output[..2], [0xFF, 0xE1]);
+        assert_eq!(
+            u16::from_be_bytes([output[2], output[3]]) as usize,
+            content.len() + 2
+        );
+        assert_eq!(output[4..], content);
+    }
+
+    #[test]
+    fn test_write_xmp() {
+        let mut output = Vec::new();
+        let mut image = create_test_image();
+        image.xmp_packet = Some(XmpPacket::new("<x:xmpmeta/>".to_string()).unwrap());
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_xmp_application_header().unwrap();
+        assert_eq!(output[..4], [0xFF, 0xE1, 0x00, 0x2B]);
+        assert_eq!(&output[4..33], b"http://ns.adobe.com/xap/1.0/\0");
+        assert_eq!(&output[33..], b"<x:xmpmeta/>");
+    }
+
+    #[test]
+    fn test_write_adobe() {
+        let mut output = Vec::new();
+        let mut image = create_test_image();
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_adobe_application_header().unwrap();
+        assert!(output.is_empty(), "APP14 must only be written on request");
+
+        image.adobe_marker = true;
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_adobe_application_header().unwrap();
+        assert_eq!(
+            output,
+            [
+                0xFF, 0xEE, 0x00, 0x0E, b'A', b'd', b'o', b'b', b'e', 0x00, 0x64, 0x00, 0x00, 0x00,
+                0x00, 0x01
+            ]
+        );
+
+        output.clear();
+        image.grayscale = true;
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_adobe_application_header().unwrap();
+        assert_eq!(output.last(), Some(&0x00));
+    }
+
+    #[test]
+    fn test_write_cmyk_headers() {
+        let mut output = Vec::new();
+        let mut image = create_test_image();
+        image.cmyk = true;
+        image.chroma_subsampling_preset = ChromaSubsamplingPreset::P420;
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_jfif_application_header().unwrap();
+        assert!(output.is_empty(), "JFIF does not describe four components");
+
+        // YCCK is announced without the option of the Adobe segment
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_adobe_application_header().unwrap();
+        assert_eq!(output.len(), 16);
+        assert_eq!(output.last(), Some(&0x02));
+
+        output.clear();
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_start_of_frame().unwrap();
+        encoder.write_start_of_scan().unwrap();
+        assert_eq!(
+            output,
+            [
+                0xFF, 0xC0, 0x00, 0x14, 0x08, 0x00, 0x02, 0x00, 0x03, 0x04, 0x01, 0x22, 0x00, 0x02,
+                0x11, 0x01, 0x03, 0x11, 0x01, 0x04, 0x22, 0x00, 0xFF, 0xDA, 0x00, 0x0E, 0x04, 0x01,
+                0x01, 0x02, 0x23, 0x03, 0x23, 0x04, 0x01, 0x00, 0x3F, 0x00,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_settings_comment() {
+        let mut output = Vec::new();
+        let mut image = create_test_image();
+        image.settings_comment = Some("{}".to_string());
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_settings_comment().unwrap();
+        assert_eq!(output, [0xFF, 0xFE, 0x00, 0x04, b'{', b'}']);
+    }
+
+    #[test]
+    fn test_write_huffman_header() {
+        let mut output = Vec::new();
+        let image = create_test_image();
+        let mut encoder = Encoder::new(&mut output, &image);
+        let symdepths =
+            [(3, 2), (4, 2), (8, 4), (2, 4), (5, 4), (1, 4)].map(SymbolCodeLength::from);
+
+        encoder
+            .write_huffman_table(TableKind::LumaDC, &symdepths)
+            .unwrap();
+
+        let mut count = 0;
+        while count < output.len() {
+            assert_eq!(output[count], 0xFF);
+            assert_eq!(output[count + 1], 0xC4);
+            let skip = [output[count + 2], output[count + 3]];
+            count += u16::from_le_bytes(skip) as usize;
+        }
+    }
+
+    #[test]
+    fn test_verify_huffman_table() {
+        let symdepths = [(0, 3), (1, 3), (2, 2), (3, 1)].map(SymbolCodeLength::from);
+        let translator = HuffmanTranslator::from(&symdepths);
+        let content = Encoder::<Vec<u8>>::huffman_table_content(TableKind::LumaAC, &symdepths);
+        let symbols = [3, 0, 2, 1, 3, 3, 1];
+        verify_huffman_table(TableKind::LumaAC, content.as_slice(), &translator, &symbols).unwrap();
+
+        let mut reversed_symbols = content.as_slice().to_vec();
+        reversed_symbols[17..].reverse();
+        let result =
+            verify_huffman_table(TableKind::LumaAC, &reversed_symbols, &translator, &symbols);
+        assert!(matches!(
+            result,
+            Err(Error::HuffmanTableVerificationFailed("luma AC", _))
+        ));
+
+        let mut shifted_counts = content.as_slice().to_vec();
+        shifted_counts[1..17].rotate_right(1);
+        let result =
+            verify_huffman_table(TableKind::LumaAC, &shifted_counts, &translator, &symbols);
+        assert!(matches!(
+            result,
+            Err(Error::HuffmanTableVerificationFailed("luma AC", _))
+        ));
+    }
+
+    #[test]
+    fn test_write_start_of_frame() {
+        let mut output = Vec::new();
+        let image = create_test_image();
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_start_of_frame().unwrap();
+
+        let width_bytes = (image.width).to_be_bytes();
+        let height_bytes = (image.height).to_be_bytes();
+        assert_eq!(
+            output,
+            [
+                0xFF,
+                0xC0,
+                0x00,
+                0x11,
+                0x08,
+                height_bytes[0],
+                height_bytes[1],
+                width_bytes[0],
+                width_bytes[1],
+                0x03,
+                0x01,
+                0x11,
+                0x00,
+                0x02,
+                0x11,
+                0x01,
+                0x03,
+                0x11,
+                0x01,
+            ]
+        )
+    }
+
+    #[test]
+    fn test_write_height_into_number_of_lines_segment() {
+        let mut output = Vec::new();
+        let mut image = create_test_image();
+        image.define_number_of_lines = true;
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_start_of_frame().unwrap();
+        assert_eq!(output[5..7], [0x00, 0x00]);
+
+        let mut output = Vec::new();
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_number_of_lines().unwrap();
+        let height_bytes = image.height.to_be_bytes();
+        assert_eq!(
+            output,
+            [0xFF, 0xDC, 0x00, 0x04, height_bytes[0], height_bytes[1]]
+        );
+    }
+
+    #[test]
+    fn test_write_start_of_frame_factors_of_all_presets() {
+        let expected_factors = |preset| match preset {
+            ChromaSubsamplingPreset::P444 => [0x11, 0x11, 0x11],
+            ChromaSubsamplingPreset::P422 => [0x21, 0x11, 0x11],
+            ChromaSubsamplingPreset::P420 => [0x22, 0x11, 0x11],
+            ChromaSubsamplingPreset::P411 => [0x41, 0x11, 0x11],
+            ChromaSubsamplingPreset::P440 => [0x12, 0x11, 0x11],
+            // the custom rates of the test are 3x1
+            ChromaSubsamplingPreset::Custom { .. } => [0x31, 0x11, 0x11],
+        };
+        let custom = ChromaSubsamplingPreset::custom(3, 1).unwrap();
+        for &preset in ChromaSubsamplingPreset::variants().iter().chain([&custom]) {
+            let mut output = Vec::new();
+            let mut image = create_test_image();
+            image.chroma_subsampling_preset = preset;
+            let mut encoder = Encoder::new(&mut output, &image);
+            encoder.write_start_of_frame().unwrap();
+            let factors: Vec<u8> = output[10..].chunks(3).map(|c| c[1]).collect();
+            assert_eq!(factors, expected_factors(preset), "{:?}", preset);
+        }
+    }
+
+    #[test]
+    fn test_write_grayscale_frame_and_scan_headers() {
+        let mut output = Vec::new();
+        let mut image = create_test_image();
+        image.grayscale = true;
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_start_of_frame().unwrap();
+        encoder.write_start_of_scan().unwrap();
+        assert_eq!(
+            output,
+            [
+                0xFF, 0xC0, 0x00, 0x0B, 0x08, 0x00, 0x02, 0x00, 0x03, 0x01, 0x01, 0x11, 0x00, 0xFF,
+                0xDA, 0x00, 0x08, 0x01, 0x01, 0x01, 0x00, 0x3F, 0x00,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_quantization() {
+        let mut output = Vec::new();
+        let image = create_test_image();
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder
+            .write_quantization_table(2, &image.quantization_table_pair.luma_table)
+            .unwrap();
+
+        assert_eq!(
+            output,
+            [
+                0xFF, 0xDB, 0x00, 0x43, 0x02, 16, 11, 12, 14, 12, 10, 16, 14, 13, 14, 18, 17, 16,
+                19, 24, 40, 26, 24, 22, 22, 24, 49, 35, 37, 29, 40, 58, 51, 61, 60, 57, 51, 56, 55,
+                64, 72, 92, 78, 64, 68, 87, 69, 55, 56, 80, 109, 81, 87, 95, 98, 103, 104, 103, 62,
+                77, 113, 121, 112, 100, 120, 92, 101, 103, 99
+            ]
+        )
+    }
+
+    #[test]
+    fn test_write_twelve_bit_frame_and_quantization_headers() {
+        let mut output = Vec::new();
+        let mut image = create_test_image();
+        image.grayscale = true;
+        image.bits_per_channel = 12;
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_start_of_frame().unwrap();
+        encoder
+            .write_quantization_table(0, &image.quantization_table_pair.luma_table)
+            .unwrap();
+
+        assert_eq!(
+            output[..13],
+            [0xFF, 0xC1, 0x00, 0x0B, 0x0C, 0x00, 0x02, 0x00, 0x03, 0x01, 0x01, 0x11, 0x00]
+        );
+        assert_eq!(output[13..20], [0xFF, 0xDB, 0x00, 0x83, 0x10, 0x00, 16]);
+        assert_eq!(output.len(), 13 + 4 + 1 + 128);
+    }
+
+    #[test]
+    fn test_write_sixteen_bit_quantization_table_into_extended_frame() {
+        let mut output = Vec::new();
+        let mut image = create_test_image();
+        image.grayscale = true;
+        image.quantization_table_pair.luma_table[63] = 418;
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_all_quantization_tables().unwrap();
+        encoder.write_start_of_frame().unwrap();
+
+        assert_eq!(output[..7], [0xFF, 0xDB, 0x00, 0x83, 0x10, 0x00, 16]);
+        // The last step in zig zag order is the last one in row major order
+        assert_eq!(output[4 + 1 + 126..4 + 1 + 128], [0x01, 0xA2]);
+        assert_eq!(output[4 + 1 + 128..4 + 1 + 128 + 2], [0xFF, 0xC1]);
+    }
+
+    #[test]
+    fn test_write_separate_chroma_red_quantization_table() {
+        let mut output = Vec::new();
+        let mut image = create_test_image();
+        image.quantization_table_pair.chroma_red_table = Some([99; 64]);
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_all_quantization_tables().unwrap();
+        encoder.write_start_of_frame().unwrap();
+
+        let table_ids: Vec<u8> = output
+            .chunks(4 + 1 + 64)
+            .take(3)
+            .map(|segment| segment[4])
+            .collect();
+        assert_eq!(table_ids, [0, 1, 2]);
+        assert!(output[3 * 69 - 64..3 * 69].iter().all(|&step| step == 99));
+        // Cb keeps the chroma table, Cr selects the third one
+        assert_eq!(
+            output[output.len() - 6..],
+            [0x02, 0x11, 0x01, 0x03, 0x11, 0x02]
+        );
+    }
+
+    #[test]
+    fn test_write_combined_table_segments() {
+        let mut output = Vec::new();
+        let mut image = create_test_image();
+        image.combine_table_segments = true;
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_all_quantization_tables().unwrap();
+        assert_eq!(output[..5], [0xFF, 0xDB, 0x00, 0x84, 0x00]);
+        assert_eq!(output[4 + 65], 0x01);
+        assert_eq!(output.len(), 4 + 2 * 65);
+
+        output.clear();
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_all_huffman_tables().unwrap();
+        let length = u16::from_be_bytes([output[2], output[3]]) as usize;
+        assert_eq!(output[..2], [0xFF, 0xC4]);
+        assert_eq!(output.len(), 2 + length);
+        // Each of the four tables holds the class and id, 16 counts and two symbols
+        assert_eq!(length, 2 + 4 * (1 + 16 + 2));
+    }
+
+    #[test]
+    fn test_write_start_of_scan() {
+        let mut output = Vec::new();
+        let image = create_test_image();
+        let mut encoder = Encoder::new(&mut output, &image);
+        encoder.write_start_of_scan().unwrap();
+
+        assert_eq!(
+            output,
+            [0xFF, 0xDA, 0x00, 0x0C, 0x03, 0x01, 0x01, 0x02, 0x23, 0x03, 0x23, 0x00, 0x3F, 0x00,]
+        )
+    }
+
+    #[test]
+    fn test_scan_bit_cost_matches_image_data() {
+        let block = || {
+            CategorizedBlock::new(
+                CategoryEncodedInteger::from(4),
+                vec![LeadingZerosToken::new(0, 1)],
+            )
+        };
+        let mut image = create_test_image();
+        image.blockwise_image_data = CombinedColorChannels {
+            luma: vec![block()],
+            chroma_blue: vec![block()],
+            chroma_red: vec![block()],
+            key: Vec::new(),
+        };
+        let mut output = Vec::new();
+        let mut encoder = Encoder::new(&mut output, &image);
+        let bits = encoder
+            .huffman_translators
+            .scan_bit_cost(&image.blockwise_image_data)
+            .unwrap();
+        encoder
+            .write_bands(iter::once(&image.blockwise_image_data))
+            .unwrap();
+        assert_eq!(bits, 30);
+        assert_eq!(output.len() as u64, bits.div_ceil(8));
+    }
+
+    #[test]
+    fn test_write_bands_like_whole_image() {
+        // DC values of the categories 1 and 3, whose symbols the test tables code
+        let blocks = |dc_values: &[i16]| -> Vec<CategorizedBlock> {
+            dc_values
+                .iter()
+                .map(|&dc| {
+                    CategorizedBlock::new(
+                        CategoryEncodedInteger::from(dc),
+                        vec![LeadingZerosToken::new(0, 1)],
+                    )
+                })
+                .collect()
+        };
+        let band = |dc_values: &[i16]| CombinedColorChannels {
+            luma: blocks(dc_values),
+            chroma_blue: blocks(dc_values),
+            chroma_red: blocks(dc_values),
+            key: Vec::new(),
+        };
+        let dc_values = [1, -5, 4, -1, 7, 1, -4];
+        let mut image = create_test_image();
+        image.restart_interval = Some(3);
+        image.blockwise_image_data = band(&dc_values);
+        let mut whole_output = Vec::new();
+        let mut encoder = Encoder::new(&mut whole_output, &image);
+        encoder
+            .write_bands(iter::once(&image.blockwise_image_data))
+            .unwrap();
+        let whole_offsets = encoder.take_restart_interval_offsets();
+
+        let mut banded_output = Vec::new();
+        let mut encoder = Encoder::new(&mut banded_output, &image);
+        let bands = [&dc_values[..2], &dc_values[2..6], &dc_values[6..]].map(band);
+        encoder.write_bands(bands.into_iter()).unwrap();
+        assert_eq!(encoder.take_restart_interval_offsets(), whole_offsets);
+        assert_eq!(banded_output, whole_output);
+        assert_eq!(whole_offsets.len(), 3);
+    }
+
+    #[test]
+    fn test_ratios_p444() {
+        let subsampling = ChromaSubsamplingPreset::P444;
+        assert_eq!(subsampling.sampling_layout().luma_factors.to_byte(), 0x11)
+    }
+
+    #[test]
+    fn test_ratios_p422() {
+        let subsampling = ChromaSubsamplingPreset::P422;
+        assert_eq!(subsampling.sampling_layout().luma_factors.to_byte(), 0x21)
+    }
+
+    #[test]
+    fn test_ratios_p420() {
+        let subsampling = ChromaSubsamplingPreset::P420;
+        assert_eq!(subsampling.sampling_layout().luma_factors.to_byte(), 0x22)
+    }
+}
@@ -12,28 +12,47 @@ pub struct BlockFoldIterator<'a> {
     luma_iterator: Box<dyn Iterator<Item = &'a CategorizedBlock> + 'a>,
     chroma_blue_iterator: Box<dyn Iterator<Item = &'a CategorizedBlock> + 'a>,
     chroma_red_iterator: Box<dyn Iterator<Item = &'a CategorizedBlock> + 'a>,
+    key_iterator: Box<dyn Iterator<Item = &'a CategorizedBlock> + 'a>,
     channel_selector: Box<dyn Iterator<Item = ColorChannelType>>,
 }
 
 impl<'a> BlockFoldIterator<'a> {
+    /// Iterates over the blocks of the interleaved MCUs. The blocks of the black ink follow
+    /// the chroma blocks of each MCU, if the channels hold any.
     pub fn new(
         channels: &'a CombinedColorChannels<Vec<CategorizedBlock>>,
         subsampling_preset: ChromaSubsamplingPreset,
     ) -> Self {
-        let channel_selector: Box<dyn Iterator<Item = ColorChannelType>> = match subsampling_preset
-        {
-            ChromaSubsamplingPreset::P444 => Box::new(P444ChannelSelector::new()),
-            ChromaSubsamplingPreset::P422 => Box::new(P422ChannelSelector::new()),
-            ChromaSubsamplingPreset::P420 => Box::new(P420ChannelSelector::new()),
+        let luma_blocks_per_mcu = subsampling_preset.luma_blocks_per_mcu();
+        let key_blocks_per_mcu = if channels.key.is_empty() {
+            0
+        } else {
+            luma_blocks_per_mcu
         };
+        let channel_selector = Box::new(ChannelSelector::new(
+            luma_blocks_per_mcu,
+            key_blocks_per_mcu,
+        ));
         Self {
             luma_iterator: Box::new(channels.luma.iter()),
             chroma_blue_iterator: Box::new(channels.chroma_blue.iter()),
             chroma_red_iterator: Box::new(channels.chroma_red.iter()),
+            key_iterator: Box::new(channels.key.iter()),
             channel_selector,
         }
     }
 
+    /// Iterates over the luma blocks only, as they are written into a grayscale scan.
+    pub fn luma_only(channels: &'a CombinedColorChannels<Vec<CategorizedBlock>>) -> Self {
+        Self {
+            luma_iterator: Box::new(channels.luma.iter()),
+            chroma_blue_iterator: Box::new(std::iter::empty()),
+            chroma_red_iterator: Box::new(std::iter::empty()),
+            key_iterator: Box::new(std::iter::empty()),
+            channel_selector: Box::new(std::iter::repeat_with(|| ColorChannelType::Luma)),
+        }
+    }
+
     fn take_next_luma_block(&mut self) -> Option<(ColorInformation, &'a CategorizedBlock)> {
         let block = self.luma_iterator.next()?;
         Some((ColorInformation::Luma, block))
@@ -48,6 +67,12 @@ impl<'a> BlockFoldIterator<'a> {
         let block = self.chroma_red_iterator.next()?;
         Some((ColorInformation::Chroma, block))
     }
+
+    /// Blocks of the black ink are coded with the tables of the luma.
+    fn take_next_key_block(&mut self) -> Option<(ColorInformation, &'a CategorizedBlock)> {
+        let block = self.key_iterator.next()?;
+        Some((ColorInformation::Luma, block))
+    }
 }
 
 impl<'a> Iterator for BlockFoldIterator<'a> {
@@ -62,6 +87,7 @@ impl<'a> Iterator for BlockFoldIterator<'a> {
             ColorChannelType::Luma => self.take_next_luma_block(),
             ColorChannelType::ChromaBlue => self.take_next_chroma_blue_block(),
             ColorChannelType::ChromaRed => self.take_next_chroma_red_block(),
+            ColorChannelType::Key => self.take_next_key_block(),
         }
     }
 }
@@ -70,79 +96,39 @@ enum ColorChannelType {
     Luma,
     ChromaBlue,
     ChromaRed,
+    Key,
 }
 
-struct P444ChannelSelector {
-    index: usize,
-}
-
-impl P444ChannelSelector {
-    fn new() -> Self {
-        Self { index: 0 }
-    }
-}
-
-impl Iterator for P444ChannelSelector {
-    type Item = ColorChannelType;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let return_value = match self.index {
-            0 => ColorChannelType::Luma,
-            1 => ColorChannelType::ChromaBlue,
-            2 => ColorChannelType::ChromaRed,
-            _ => panic!("Index to high"),
-        };
-        self.index = (self.index + 1) % 3;
-        Some(return_value)
-    }
-}
-
-struct P422ChannelSelector {
-    index: usize,
-}
-
-impl P422ChannelSelector {
-    fn new() -> Self {
-        Self { index: 0 }
-    }
-}
-
-impl Iterator for P422ChannelSelector {
-    type Item = ColorChannelType;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let return_value = match self.index {
-            0..=1 => ColorChannelType::Luma,
-            2 => ColorChannelType::ChromaBlue,
-            3 => ColorChannelType::ChromaRed,
-            _ => panic!("Index to high"),
-        };
-        self.index = (self.index + 1) % 4;
-        Some(return_value)
-    }
-}
-
-struct P420ChannelSelector {
+/// Selects the channel of each block of the interleaved MCUs: all luma blocks of the MCU,
+/// followed by one block of each chroma channel and the blocks of the black ink.
+struct ChannelSelector {
+    luma_blocks_per_mcu: usize,
+    key_blocks_per_mcu: usize,
     index: usize,
 }
 
-impl P420ChannelSelector {
-    fn new() -> Self {
-        Self { index: 0 }
+impl ChannelSelector {
+    fn new(luma_blocks_per_mcu: usize, key_blocks_per_mcu: usize) -> Self {
+        Self {
+            luma_blocks_per_mcu,
+            key_blocks_per_mcu,
+            index: 0,
+        }
     }
 }
 
-impl Iterator for P420ChannelSelector {
+impl Iterator for ChannelSelector {
     type Item = ColorChannelType;
 
     fn next(&mut self) -> Option<Self::Item> {
+        let luma_blocks = self.luma_blocks_per_mcu;
         let return_value = match self.index {
-            0..=3 => ColorChannelType::Luma,
-            4 => ColorChannelType::ChromaBlue,
-            5 => ColorChannelType::ChromaRed,
-            _ => panic!("Index too high"),
+            index if index < luma_blocks => ColorChannelType::Luma,
+            index if index == luma_blocks => ColorChannelType::ChromaBlue,
+            index if index == luma_blocks + 1 => ColorChannelType::ChromaRed,
+            _ => ColorChannelType::Key,
         };
-        self.index = (self.index + 1) % 6;
+        self.index = (self.index + 1) % (luma_blocks + 2 + self.key_blocks_per_mcu);
         Some(return_value)
     }
 }
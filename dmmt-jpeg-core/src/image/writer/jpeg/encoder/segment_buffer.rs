@@ -0,0 +1,55 @@
+/// Content of a marker segment with a known maximum length, which is built on the stack
+/// instead of a heap allocated vector.
+pub struct SegmentBuffer<const N: usize> {
+    content: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> SegmentBuffer<N> {
+    pub fn new() -> Self {
+        Self {
+            content: [0; N],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, byte: u8) {
+        assert!(
+            self.len < N,
+            "Segment content exceeds its maximum length of {} bytes",
+            N
+        );
+        self.content[self.len] = byte;
+        self.len += 1;
+    }
+
+    pub fn extend(&mut self, bytes: impl IntoIterator<Item = u8>) {
+        for byte in bytes {
+            self.push(byte);
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.content[..self.len]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SegmentBuffer;
+
+    #[test]
+    fn collect_bytes_in_order() {
+        let mut buffer = SegmentBuffer::<4>::new();
+        buffer.push(1);
+        buffer.extend([2, 3]);
+        assert_eq!(buffer.as_slice(), &[1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn exceed_maximum_length() {
+        let mut buffer = SegmentBuffer::<2>::new();
+        buffer.extend([1, 2, 3]);
+    }
+}
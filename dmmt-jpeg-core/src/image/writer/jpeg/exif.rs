@@ -0,0 +1,593 @@
+use std::env;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const EXIF_IDENTIFIER: &[u8; 6] = b"Exif\0\0";
+const TIFF_HEADER: [u8; 8] = [b'M', b'M', 0x00, 0x2A, 0x00, 0x00, 0x00, 0x08];
+const FIRST_IFD_OFFSET: usize = 8;
+
+const SOFTWARE_TAG: u16 = 0x0131;
+const DATE_TIME_TAG: u16 = 0x0132;
+const EXIF_IFD_POINTER_TAG: u16 = 0x8769;
+const GPS_IFD_POINTER_TAG: u16 = 0x8825;
+const USER_COMMENT_TAG: u16 = 0x9286;
+const GPS_VERSION_ID_TAG: u16 = 0x0000;
+const GPS_LATITUDE_REF_TAG: u16 = 0x0001;
+const GPS_LATITUDE_TAG: u16 = 0x0002;
+const GPS_LONGITUDE_REF_TAG: u16 = 0x0003;
+const GPS_LONGITUDE_TAG: u16 = 0x0004;
+const GPS_ALTITUDE_REF_TAG: u16 = 0x0005;
+const GPS_ALTITUDE_TAG: u16 = 0x0006;
+
+const SOURCE_DATE_EPOCH_VARIABLE: &str = "SOURCE_DATE_EPOCH";
+
+const ASCII_CHARACTER_CODE: &[u8; 8] = b"ASCII\0\0\0";
+const UNICODE_CHARACTER_CODE: &[u8; 8] = b"UNICODE\0";
+
+/// Denominator of the seconds and the altitude rationals, giving a resolution of 1/1000.
+const RATIONAL_DENOMINATOR: u32 = 1000;
+
+#[derive(Clone, Copy)]
+enum FieldType {
+    Byte = 1,
+    Ascii = 2,
+    Long = 4,
+    Rational = 5,
+    Undefined = 7,
+}
+
+struct IFDEntry {
+    tag: u16,
+    field_type: FieldType,
+    count: u32,
+    value: Vec<u8>,
+}
+
+impl IFDEntry {
+    fn bytes(tag: u16, values: &[u8]) -> Self {
+        Self {
+            tag,
+            field_type: FieldType::Byte,
+            count: values.len() as u32,
+            value: values.to_vec(),
+        }
+    }
+
+    fn ascii(tag: u16, text: &str) -> Self {
+        let mut value = text.as_bytes().to_vec();
+        value.push(0);
+        Self {
+            tag,
+            field_type: FieldType::Ascii,
+            count: value.len() as u32,
+            value,
+        }
+    }
+
+    fn long(tag: u16, value: u32) -> Self {
+        Self {
+            tag,
+            field_type: FieldType::Long,
+            count: 1,
+            value: value.to_be_bytes().to_vec(),
+        }
+    }
+
+    fn rationals(tag: u16, values: &[(u32, u32)]) -> Self {
+        Self {
+            tag,
+            field_type: FieldType::Rational,
+            count: values.len() as u32,
+            value: values
+                .iter()
+                .flat_map(|(numerator, denominator)| {
+                    numerator
+                        .to_be_bytes()
+                        .into_iter()
+                        .chain(denominator.to_be_bytes())
+                })
+                .collect(),
+        }
+    }
+
+    fn undefined(tag: u16, value: Vec<u8>) -> Self {
+        Self {
+            tag,
+            field_type: FieldType::Undefined,
+            count: value.len() as u32,
+            value,
+        }
+    }
+
+    fn is_inline(&self) -> bool {
+        self.value.len() <= 4
+    }
+
+    /// Length of the value outside of the entry, padded to an even offset.
+    fn external_length(&self) -> usize {
+        if self.is_inline() {
+            0
+        } else {
+            self.value.len() + self.value.len() % 2
+        }
+    }
+}
+
+fn ifd_length(entries: &[IFDEntry]) -> usize {
+    2 + entries.len() * 12 + 4 + entries.iter().map(IFDEntry::external_length).sum::<usize>()
+}
+
+/// Writes the IFD, followed by all values, which do not fit into their entry. The offset of the
+/// IFD is relative to the start of the TIFF header.
+fn write_ifd(output: &mut Vec<u8>, entries: &[IFDEntry], ifd_offset: usize) {
+    let mut external_values = Vec::new();
+    let external_values_offset = ifd_offset + 2 + entries.len() * 12 + 4;
+    output.extend((entries.len() as u16).to_be_bytes());
+    for entry in entries {
+        output.extend(entry.tag.to_be_bytes());
+        output.extend((entry.field_type as u16).to_be_bytes());
+        output.extend(entry.count.to_be_bytes());
+        if entry.is_inline() {
+            let mut value = [0; 4];
+            value[..entry.value.len()].copy_from_slice(&entry.value);
+            output.extend(value);
+        } else {
+            let offset = (external_values_offset + external_values.len()) as u32;
+            output.extend(offset.to_be_bytes());
+            external_values.extend(&entry.value);
+            if entry.value.len() % 2 == 1 {
+                external_values.push(0);
+            }
+        }
+    }
+    output.extend(0_u32.to_be_bytes());
+    output.extend(external_values);
+}
+
+/// Position in WGS-84 coordinates. Southern latitudes, western longitudes and altitudes below sea
+/// level are negative.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GpsPosition {
+    latitude: f64,
+    longitude: f64,
+    altitude: Option<f64>,
+}
+
+impl GpsPosition {
+    pub fn new(latitude: f64, longitude: f64, altitude: Option<f64>) -> Result<Self, String> {
+        if !(-90.0..=90.0).contains(&latitude) {
+            return Err(format!("latitude {} is not in range -90 to 90", latitude));
+        }
+        if !(-180.0..=180.0).contains(&longitude) {
+            return Err(format!(
+                "longitude {} is not in range -180 to 180",
+                longitude
+            ));
+        }
+        if altitude.is_some_and(|altitude| !altitude.is_finite()) {
+            return Err("altitude must be a finite number".to_string());
+        }
+        Ok(Self {
+            latitude,
+            longitude,
+            altitude,
+        })
+    }
+
+    /// Parses a sidecar file with one `key = value` pair per line. The keys are `latitude`,
+    /// `longitude` and the optional `altitude`. Empty lines and lines starting with `#` are
+    /// ignored.
+    pub fn from_sidecar(content: &str) -> Result<Self, String> {
+        let mut latitude = None;
+        let mut longitude = None;
+        let mut altitude = None;
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line '{}' is not of the form key = value", line))?;
+            let value = Some(parse_coordinate(value.trim())?);
+            match key.trim() {
+                "latitude" => latitude = value,
+                "longitude" => longitude = value,
+                "altitude" => altitude = value,
+                key => return Err(format!("unknown key '{}'", key)),
+            }
+        }
+        Self::new(
+            latitude.ok_or("latitude is missing")?,
+            longitude.ok_or("longitude is missing")?,
+            altitude,
+        )
+    }
+
+    fn to_degrees_minutes_seconds(value: f64) -> [(u32, u32); 3] {
+        let value = value.abs();
+        let degrees = value.trunc();
+        let minutes = ((value - degrees) * 60.0).trunc();
+        let seconds = ((value - degrees) * 60.0 - minutes) * 60.0;
+        [
+            (degrees as u32, 1),
+            (minutes as u32, 1),
+            (
+                (seconds * RATIONAL_DENOMINATOR as f64).round() as u32,
+                RATIONAL_DENOMINATOR,
+            ),
+        ]
+    }
+
+    fn ifd_entries(&self) -> Vec<IFDEntry> {
+        let latitude_ref = if self.latitude < 0.0 { "S" } else { "N" };
+        let longitude_ref = if self.longitude < 0.0 { "W" } else { "E" };
+        let mut entries = Vec::from([
+            IFDEntry::bytes(GPS_VERSION_ID_TAG, &[2, 3, 0, 0]),
+            IFDEntry::ascii(GPS_LATITUDE_REF_TAG, latitude_ref),
+            IFDEntry::rationals(
+                GPS_LATITUDE_TAG,
+                &Self::to_degrees_minutes_seconds(self.latitude),
+            ),
+            IFDEntry::ascii(GPS_LONGITUDE_REF_TAG, longitude_ref),
+            IFDEntry::rationals(
+                GPS_LONGITUDE_TAG,
+                &Self::to_degrees_minutes_seconds(self.longitude),
+            ),
+        ]);
+        if let Some(altitude) = self.altitude {
+            let below_sea_level = u8::from(altitude < 0.0);
+            let altitude = (altitude.abs() * RATIONAL_DENOMINATOR as f64).round() as u32;
+            entries.push(IFDEntry::bytes(GPS_ALTITUDE_REF_TAG, &[below_sea_level]));
+            entries.push(IFDEntry::rationals(
+                GPS_ALTITUDE_TAG,
+                &[(altitude, RATIONAL_DENOMINATOR)],
+            ));
+        }
+        entries
+    }
+}
+
+fn parse_coordinate(value: &str) -> Result<f64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))
+}
+
+impl FromStr for GpsPosition {
+    type Err = String;
+
+    /// Parses `LATITUDE,LONGITUDE[,ALTITUDE]` in decimal degrees and meters.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parts = value
+            .split(',')
+            .map(|part| parse_coordinate(part.trim()))
+            .collect::<Result<Vec<f64>, String>>()?;
+        match parts[..] {
+            [latitude, longitude] => Self::new(latitude, longitude, None),
+            [latitude, longitude, altitude] => Self::new(latitude, longitude, Some(altitude)),
+            _ => Err(format!(
+                "'{}' is not of the form LATITUDE,LONGITUDE[,ALTITUDE]",
+                value
+            )),
+        }
+    }
+}
+
+/// Date and time in seconds since the unix epoch (UTC), written as EXIF DateTime.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExifDateTime(u64);
+
+impl ExifDateTime {
+    pub fn from_unix_timestamp(seconds: u64) -> Self {
+        Self(seconds)
+    }
+
+    /// Uses `SOURCE_DATE_EPOCH`, if it is set, so reproducible builds get identical outputs.
+    /// Otherwise the current system time is used.
+    pub fn from_environment() -> Self {
+        if let Ok(value) = env::var(SOURCE_DATE_EPOCH_VARIABLE) {
+            match value.trim().parse() {
+                Ok(seconds) => return Self(seconds),
+                Err(_) => log::warn!(
+                    "Ignoring {} '{}', it is not a unix timestamp",
+                    SOURCE_DATE_EPOCH_VARIABLE,
+                    value
+                ),
+            }
+        }
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        Self(seconds)
+    }
+
+    /// Converts days since the unix epoch to a (year, month, day) civil date.
+    fn civil_date(days: i64) -> (i64, u32, u32) {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        } as u32;
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        (year, month, day)
+    }
+
+    /// Formats the timestamp as `YYYY:MM:DD HH:MM:SS`.
+    pub fn format(&self) -> String {
+        let seconds_of_day = self.0 % 86_400;
+        let (year, month, day) = Self::civil_date((self.0 / 86_400) as i64);
+        format!(
+            "{:04}:{:02}:{:02} {:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            seconds_of_day / 3600,
+            seconds_of_day / 60 % 60,
+            seconds_of_day % 60
+        )
+    }
+}
+
+/// Content of the EXIF APP1 segment. Nothing is written, if no field is set.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExifMetadata {
+    pub software: Option<String>,
+    pub date_time: Option<ExifDateTime>,
+    pub gps_position: Option<GpsPosition>,
+    pub user_comment: Option<String>,
+}
+
+impl ExifMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.software.is_none()
+            && self.date_time.is_none()
+            && self.gps_position.is_none()
+            && self.user_comment.is_none()
+    }
+
+    fn encode_user_comment(comment: &str) -> Vec<u8> {
+        if comment.is_ascii() {
+            ASCII_CHARACTER_CODE
+                .iter()
+                .copied()
+                .chain(comment.bytes())
+                .collect()
+        } else {
+            UNICODE_CHARACTER_CODE
+                .iter()
+                .copied()
+                .chain(comment.encode_utf16().flat_map(u16::to_be_bytes))
+                .collect()
+        }
+    }
+
+    fn first_ifd_entries(
+        &self,
+        exif_ifd_offset: Option<u32>,
+        gps_ifd_offset: Option<u32>,
+    ) -> Vec<IFDEntry> {
+        let mut entries = Vec::new();
+        if let Some(software) = &self.software {
+            entries.push(IFDEntry::ascii(SOFTWARE_TAG, software));
+        }
+        if let Some(date_time) = &self.date_time {
+            entries.push(IFDEntry::ascii(DATE_TIME_TAG, &date_time.format()));
+        }
+        if let Some(offset) = exif_ifd_offset {
+            entries.push(IFDEntry::long(EXIF_IFD_POINTER_TAG, offset));
+        }
+        if let Some(offset) = gps_ifd_offset {
+            entries.push(IFDEntry::long(GPS_IFD_POINTER_TAG, offset));
+        }
+        entries
+    }
+
+    /// Creates the content of the APP1 segment: the EXIF identifier followed by a big endian
+    /// TIFF structure with IFD0 and the referenced EXIF and GPS IFDs.
+    pub fn to_segment_content(&self) -> Vec<u8> {
+        let exif_entries: Vec<IFDEntry> = self
+            .user_comment
+            .iter()
+            .map(|comment| {
+                IFDEntry::undefined(USER_COMMENT_TAG, Self::encode_user_comment(comment))
+            })
+            .collect();
+        let gps_entries = self
+            .gps_position
+            .map(|position| position.ifd_entries())
+            .unwrap_or_default();
+        let has_exif_ifd = !exif_entries.is_empty();
+        let has_gps_ifd = !gps_entries.is_empty();
+
+        // The length of IFD0 does not depend on the pointer values, so it is measured first.
+        let first_ifd_length = ifd_length(
+            &self.first_ifd_entries(has_exif_ifd.then_some(0), has_gps_ifd.then_some(0)),
+        );
+        let exif_ifd_offset = FIRST_IFD_OFFSET + first_ifd_length;
+        let gps_ifd_offset = exif_ifd_offset
+            + if has_exif_ifd {
+                ifd_length(&exif_entries)
+            } else {
+                0
+            };
+        let first_ifd_entries = self.first_ifd_entries(
+            has_exif_ifd.then_some(exif_ifd_offset as u32),
+            has_gps_ifd.then_some(gps_ifd_offset as u32),
+        );
+
+        let mut tiff = Vec::from(TIFF_HEADER);
+        write_ifd(&mut tiff, &first_ifd_entries, FIRST_IFD_OFFSET);
+        if has_exif_ifd {
+            write_ifd(&mut tiff, &exif_entries, exif_ifd_offset);
+        }
+        if has_gps_ifd {
+            write_ifd(&mut tiff, &gps_entries, gps_ifd_offset);
+        }
+        EXIF_IDENTIFIER.iter().copied().chain(tiff).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ExifDateTime, ExifMetadata, GpsPosition};
+
+    fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+        u16::from_be_bytes([bytes[offset], bytes[offset + 1]])
+    }
+
+    fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_be_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ])
+    }
+
+    /// Returns the entries of the IFD at `offset` as (tag, type, count, value or offset).
+    fn read_ifd(tiff: &[u8], offset: usize) -> Vec<(u16, u16, u32, u32)> {
+        let number_of_entries = read_u16(tiff, offset) as usize;
+        (0..number_of_entries)
+            .map(|index| {
+                let entry = offset + 2 + index * 12;
+                (
+                    read_u16(tiff, entry),
+                    read_u16(tiff, entry + 2),
+                    read_u32(tiff, entry + 4),
+                    read_u32(tiff, entry + 8),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_gps_position() {
+        let position: GpsPosition = "48.1375, -11.575, 519.5".parse().unwrap();
+        assert_eq!(
+            position,
+            GpsPosition::new(48.1375, -11.575, Some(519.5)).unwrap()
+        );
+        assert!("91,0".parse::<GpsPosition>().is_err());
+        assert!("1".parse::<GpsPosition>().is_err());
+        assert!("a,b".parse::<GpsPosition>().is_err());
+    }
+
+    #[test]
+    fn parse_gps_sidecar() {
+        let content = "# drone frame 17\nlatitude = -33.5\nlongitude=151.25\n";
+        let position = GpsPosition::from_sidecar(content).unwrap();
+        assert_eq!(position, GpsPosition::new(-33.5, 151.25, None).unwrap());
+        assert!(GpsPosition::from_sidecar("latitude = 1").is_err());
+        assert!(GpsPosition::from_sidecar("height = 1").is_err());
+    }
+
+    #[test]
+    fn convert_to_degrees_minutes_seconds() {
+        let value = GpsPosition::to_degrees_minutes_seconds(-48.1375);
+        assert_eq!(value, [(48, 1), (8, 1), (15000, 1000)]);
+    }
+
+    #[test]
+    fn write_user_comment() {
+        let metadata = ExifMetadata {
+            user_comment: Some("frame".to_string()),
+            ..Default::default()
+        };
+        let content = metadata.to_segment_content();
+        assert_eq!(&content[..6], b"Exif\0\0");
+        let tiff = &content[6..];
+        let first_ifd = read_ifd(tiff, 8);
+        assert_eq!(first_ifd.len(), 1);
+        let (tag, _, _, exif_ifd_offset) = first_ifd[0];
+        assert_eq!(tag, 0x8769);
+        let exif_ifd = read_ifd(tiff, exif_ifd_offset as usize);
+        let (tag, field_type, count, offset) = exif_ifd[0];
+        assert_eq!((tag, field_type, count), (0x9286, 7, 13));
+        let offset = offset as usize;
+        assert_eq!(&tiff[offset..offset + 13], b"ASCII\0\0\0frame");
+    }
+
+    #[test]
+    fn write_gps_position() {
+        let metadata = ExifMetadata {
+            gps_position: Some(GpsPosition::new(-48.1375, 11.5, Some(-2.0)).unwrap()),
+            user_comment: Some("ü".to_string()),
+            ..Default::default()
+        };
+        let content = metadata.to_segment_content();
+        let tiff = &content[6..];
+        let first_ifd = read_ifd(tiff, 8);
+        assert_eq!(first_ifd.len(), 2);
+        let (tag, _, _, gps_ifd_offset) = first_ifd[1];
+        assert_eq!(tag, 0x8825);
+        let gps_ifd = read_ifd(tiff, gps_ifd_offset as usize);
+        let tags: Vec<u16> = gps_ifd.iter().map(|entry| entry.0).collect();
+        assert_eq!(tags, [0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(gps_ifd[1].3.to_be_bytes()[0], b'S');
+        assert_eq!(gps_ifd[3].3.to_be_bytes()[0], b'E');
+        assert_eq!(
+            gps_ifd[5].3.to_be_bytes()[0],
+            1,
+            "altitude is below sea level"
+        );
+        let latitude_offset = gps_ifd[2].3 as usize;
+        assert_eq!(read_u32(tiff, latitude_offset), 48);
+        assert_eq!(read_u32(tiff, latitude_offset + 8), 8);
+        let altitude_offset = gps_ifd[6].3 as usize;
+        assert_eq!(read_u32(tiff, altitude_offset), 2000);
+        assert_eq!(read_u32(tiff, altitude_offset + 4), 1000);
+    }
+
+    #[test]
+    fn format_date_time() {
+        assert_eq!(
+            ExifDateTime::from_unix_timestamp(0).format(),
+            "1970:01:01 00:00:00"
+        );
+        assert_eq!(
+            ExifDateTime::from_unix_timestamp(951_827_696).format(),
+            "2000:02:29 12:34:56"
+        );
+        assert_eq!(
+            ExifDateTime::from_unix_timestamp(1_767_225_599).format(),
+            "2025:12:31 23:59:59"
+        );
+    }
+
+    #[test]
+    fn write_software_and_date_time() {
+        let metadata = ExifMetadata {
+            software: Some("encoder".to_string()),
+            date_time: Some(ExifDateTime::from_unix_timestamp(0)),
+            user_comment: Some("frame".to_string()),
+            ..Default::default()
+        };
+        let content = metadata.to_segment_content();
+        let tiff = &content[6..];
+        let first_ifd = read_ifd(tiff, 8);
+        let tags: Vec<u16> = first_ifd.iter().map(|entry| entry.0).collect();
+        assert_eq!(tags, [0x0131, 0x0132, 0x8769]);
+        let software_offset = first_ifd[0].3 as usize;
+        assert_eq!(&tiff[software_offset..software_offset + 8], b"encoder\0");
+        let date_time_offset = first_ifd[1].3 as usize;
+        assert_eq!(
+            &tiff[date_time_offset..date_time_offset + 20],
+            b"1970:01:01 00:00:00\0"
+        );
+        let exif_ifd = read_ifd(tiff, first_ifd[2].3 as usize);
+        assert_eq!(exif_ifd[0].0, 0x9286);
+    }
+
+    #[test]
+    fn empty_metadata() {
+        assert!(ExifMetadata::default().is_empty());
+    }
+}
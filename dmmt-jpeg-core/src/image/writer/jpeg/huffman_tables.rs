@@ -0,0 +1,261 @@
+#[cfg(feature = "clap")]
+use clap::{builder::PossibleValue, ValueEnum};
+
+use crate::huffman::{code_length_counts, code_lengths_of_counts, SymbolCodeLength};
+
+/// Source of the Huffman tables, the entropy coded data is written with.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum HuffmanTableMode {
+    /// Tables generated from the symbol frequencies of the image, which needs a counting pass.
+    #[default]
+    Optimized,
+    /// Default tables of the specification. They cover the symbols of 8 bit samples only.
+    Standard,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for HuffmanTableMode {
+    fn value_variants<'a>() -> &'a [Self] {
+        Self::variants()
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()))
+    }
+}
+
+impl HuffmanTableMode {
+    pub fn variants() -> &'static [Self] {
+        &[Self::Optimized, Self::Standard]
+    }
+
+    /// Name of the mode, as accepted on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Optimized => "optimized",
+            Self::Standard => "standard",
+        }
+    }
+}
+
+/// Code lengths of the four Huffman tables of an image, sorted by descending length as the
+/// encoder expects them. The chroma tables of a grayscale image are empty.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HuffmanTables {
+    pub luma_ac: Vec<SymbolCodeLength>,
+    pub luma_dc: Vec<SymbolCodeLength>,
+    pub chroma_ac: Vec<SymbolCodeLength>,
+    pub chroma_dc: Vec<SymbolCodeLength>,
+}
+
+impl HuffmanTables {
+    /// Names of the tables in the JSON representation.
+    const NAMES: [&'static str; 4] = ["luma_ac", "luma_dc", "chroma_ac", "chroma_dc"];
+
+    fn tables(&self) -> [&Vec<SymbolCodeLength>; 4] {
+        [
+            &self.luma_ac,
+            &self.luma_dc,
+            &self.chroma_ac,
+            &self.chroma_dc,
+        ]
+    }
+
+    /// Describes the tables as JSON object with the code length counts and the symbols of each
+    /// table, in the same form as the DHT segment, which [`parse_huffman_tables`] reads back.
+    pub fn to_json(&self) -> String {
+        let list = |values: Vec<String>| values.join(",");
+        let tables: Vec<String> = Self::NAMES
+            .iter()
+            .zip(self.tables())
+            .map(|(name, code_lengths)| {
+                let counts = if code_lengths.is_empty() {
+                    [0; 16]
+                } else {
+                    code_length_counts(code_lengths)
+                };
+                let symbols = code_lengths
+                    .iter()
+                    .rev()
+                    .map(|code_length| code_length.symbol);
+                format!(
+                    "\"{}\":{{\"counts\":[{}],\"symbols\":[{}]}}",
+                    name,
+                    list(counts.iter().map(u8::to_string).collect()),
+                    list(symbols.map(|symbol| symbol.to_string()).collect())
+                )
+            })
+            .collect();
+        format!("{{{}}}\n", tables.join(","))
+    }
+}
+
+/// Parses the Huffman tables from the JSON object written by [`HuffmanTables::to_json`]. Each
+/// table must assign a code word to each of its symbols, without the code word of only ones,
+/// which the specification reserves. The luma tables must not be empty.
+pub fn parse_huffman_tables(content: &str) -> Result<HuffmanTables, String> {
+    let parse_table = |name: &str| {
+        let table = json_object(content, name)?;
+        let counts: [u8; 16] =
+            json_array(table, "counts", name)?
+                .try_into()
+                .map_err(|counts: Vec<u8>| {
+                    format!("table {} has {} instead of 16 counts", name, counts.len())
+                })?;
+        let symbols = json_array(table, "symbols", name)?;
+        validate_table(name, &counts, &symbols)?;
+        Ok::<_, String>(code_lengths_of_counts(&counts, &symbols))
+    };
+    let huffman_tables = HuffmanTables {
+        luma_ac: parse_table("luma_ac")?,
+        luma_dc: parse_table("luma_dc")?,
+        chroma_ac: parse_table("chroma_ac")?,
+        chroma_dc: parse_table("chroma_dc")?,
+    };
+    for (name, code_lengths) in [
+        ("luma_ac", &huffman_tables.luma_ac),
+        ("luma_dc", &huffman_tables.luma_dc),
+    ] {
+        if code_lengths.is_empty() {
+            return Err(format!("table {} has no symbols", name));
+        }
+    }
+    Ok(huffman_tables)
+}
+
+fn validate_table(name: &str, counts: &[u8; 16], symbols: &[u8]) -> Result<(), String> {
+    let number_of_codes: usize = counts.iter().map(|&count| count as usize).sum();
+    if number_of_codes != symbols.len() {
+        return Err(format!(
+            "table {} counts {} codes for {} symbols",
+            name,
+            number_of_codes,
+            symbols.len()
+        ));
+    }
+    let mut present = [false; 256];
+    for &symbol in symbols {
+        if std::mem::replace(&mut present[symbol as usize], true) {
+            return Err(format!("table {} lists symbol {} twice", name, symbol));
+        }
+    }
+    // each code of length l takes 2^(16 - l) of the 2^16 codes of 16 bits, one of which is
+    // the reserved code of only ones
+    let used_codes: u32 = counts
+        .iter()
+        .enumerate()
+        .map(|(index, &count)| (count as u32) << (15 - index))
+        .sum();
+    if used_codes >= 1 << 16 {
+        return Err(format!(
+            "table {} has more codes than fit its lengths",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Content of the JSON object of the key without the braces. The objects of the tables contain
+/// no further objects.
+fn json_object<'a>(content: &'a str, key: &str) -> Result<&'a str, String> {
+    let value = json_value(content, key)?;
+    value
+        .strip_prefix('{')
+        .and_then(|value| value.split_once('}'))
+        .map(|(object, _)| object)
+        .ok_or_else(|| format!("value of {} is not an object", key))
+}
+
+/// Numbers of the JSON array of the key.
+fn json_array(content: &str, key: &str, table_name: &str) -> Result<Vec<u8>, String> {
+    let array = json_value(content, key)?
+        .strip_prefix('[')
+        .and_then(|value| value.split_once(']'))
+        .map(|(array, _)| array)
+        .ok_or_else(|| format!("{} of table {} is not an array", key, table_name))?;
+    array
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            value.parse::<u8>().map_err(|_| {
+                format!(
+                    "{} of table {} contains '{}', which is not in range 0 to 255",
+                    key, table_name, value
+                )
+            })
+        })
+        .collect()
+}
+
+/// Rest of the content, which starts with the value of the key.
+fn json_value<'a>(content: &'a str, key: &str) -> Result<&'a str, String> {
+    let quoted_key = format!("\"{}\"", key);
+    let (_, rest) = content
+        .split_once(&quoted_key)
+        .ok_or_else(|| format!("{} is missing", key))?;
+    rest.trim_start()
+        .strip_prefix(':')
+        .map(str::trim_start)
+        .ok_or_else(|| format!("{} is not followed by a value", key))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_huffman_tables, HuffmanTables};
+    use crate::huffman::standard_tables;
+
+    fn standard_huffman_tables() -> HuffmanTables {
+        HuffmanTables {
+            luma_ac: standard_tables::luminance_ac(),
+            luma_dc: standard_tables::luminance_dc(),
+            chroma_ac: standard_tables::chrominance_ac(),
+            chroma_dc: standard_tables::chrominance_dc(),
+        }
+    }
+
+    #[test]
+    fn parse_tables_written_as_json() {
+        let tables = standard_huffman_tables();
+        let json = tables.to_json();
+        assert!(json.starts_with(
+            "{\"luma_ac\":{\"counts\":[0,2,1,3,3,2,4,3,5,5,4,4,0,0,1,125],\"symbols\":[1,2,3,0,4,17,"
+        ));
+        assert_eq!(parse_huffman_tables(&json), Ok(tables));
+    }
+
+    #[test]
+    fn parse_tables_of_grayscale_image() {
+        let tables = HuffmanTables {
+            chroma_ac: Vec::new(),
+            chroma_dc: Vec::new(),
+            ..standard_huffman_tables()
+        };
+        assert_eq!(parse_huffman_tables(&tables.to_json()), Ok(tables));
+    }
+
+    #[test]
+    fn reject_invalid_tables() {
+        let json = standard_huffman_tables().to_json();
+        assert_eq!(
+            parse_huffman_tables(&json.replace("\"chroma_dc\"", "\"chroma\"")),
+            Err("chroma_dc is missing".to_string())
+        );
+        let overfull = json.replacen("[0,2,1,3,", "[2,2,1,3,", 1);
+        assert_eq!(
+            parse_huffman_tables(&overfull),
+            Err("table luma_ac counts 164 codes for 162 symbols".to_string())
+        );
+        let duplicate = json.replacen("\"symbols\":[1,2,3,", "\"symbols\":[1,1,3,", 1);
+        assert_eq!(
+            parse_huffman_tables(&duplicate),
+            Err("table luma_ac lists symbol 1 twice".to_string())
+        );
+        // a code of length 1 takes the half of the codes, which the other codes need
+        let overflowing = json.replacen("[0,2,1,3,", "[1,1,1,3,", 1);
+        assert_eq!(
+            parse_huffman_tables(&overflowing),
+            Err("table luma_ac has more codes than fit its lengths".to_string())
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::ops::Range;
+
+use super::transformer::sample::SourceDot;
+use crate::{
+    color::{CMYKColorFormat, RGBColorFormat, YCbCrColorFormat},
+    image::{CMYKImage, Image, YCbCrImage},
+};
+
+/// Image, whose rows and columns are filled up to the next multiple by repeating the last column
+/// and row, like libjpeg does. Black padding would be averaged into the subsampled chroma of the
+/// edge. The dots of the source image are borrowed and padded, while the rows are read, so the
+/// padded image is never held as a whole.
+#[derive(Clone, Copy)]
+pub struct PaddedImage<'a, P = RGBColorFormat<f32>> {
+    pub width: u16,
+    pub height: u16,
+    pub padded_width: u16,
+    pub padded_height: u16,
+    dots: &'a [P],
+}
+
+impl<'a> PaddedImage<'a> {
+    pub fn new(image: &'a Image<f32>, pad_nearest_width: u16, pad_nearest_height: u16) -> Self {
+        Self::pad(
+            image.width,
+            image.height,
+            &image.dots,
+            pad_nearest_width,
+            pad_nearest_height,
+        )
+    }
+}
+
+impl<'a> PaddedImage<'a, YCbCrColorFormat<f32>> {
+    pub fn from_ycbcr_image(
+        image: &'a YCbCrImage,
+        pad_nearest_width: u16,
+        pad_nearest_height: u16,
+    ) -> Self {
+        Self::pad(
+            image.width,
+            image.height,
+            &image.dots,
+            pad_nearest_width,
+            pad_nearest_height,
+        )
+    }
+}
+
+impl<'a> PaddedImage<'a, CMYKColorFormat<f32>> {
+    pub fn from_cmyk_image(
+        image: &'a CMYKImage,
+        pad_nearest_width: u16,
+        pad_nearest_height: u16,
+    ) -> Self {
+        Self::pad(
+            image.width,
+            image.height,
+            &image.dots,
+            pad_nearest_width,
+            pad_nearest_height,
+        )
+    }
+}
+
+impl<'a, P: SourceDot> PaddedImage<'a, P> {
+    fn pad(
+        width: u16,
+        height: u16,
+        dots: &'a [P],
+        pad_nearest_width: u16,
+        pad_nearest_height: u16,
+    ) -> Self {
+        PaddedImage {
+            width,
+            height,
+            padded_width: width.div_ceil(pad_nearest_width) * pad_nearest_width,
+            padded_height: height.div_ceil(pad_nearest_height) * pad_nearest_height,
+            dots,
+        }
+    }
+
+    /// Dots of the source image without the padding.
+    pub fn source_dots(&self) -> &'a [P] {
+        self.dots
+    }
+
+    /// Appends the dots of the rows of the padded image to the buffer. The columns and rows
+    /// beyond the source image repeat its last column and row.
+    pub fn read_rows(&self, rows: Range<usize>, buffer: &mut Vec<P>) {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let padding = self.padded_width as usize - width;
+        buffer.reserve(rows.len() * self.padded_width as usize);
+        for row_index in rows {
+            let source_row_index = row_index.min(height.saturating_sub(1));
+            let row = &self.dots[source_row_index * width..(source_row_index + 1) * width];
+            buffer.extend_from_slice(row);
+            if let Some(&last_dot) = row.last() {
+                buffer.extend(std::iter::repeat_n(last_dot, padding));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::RGBColorFormat,
+        image::{writer::jpeg::padder::PaddedImage, Image},
+    };
+
+    /// Dots of all rows of the padded image.
+    fn padded_dots(padded: &PaddedImage) -> Vec<RGBColorFormat<f32>> {
+        let mut dots = Vec::new();
+        padded.read_rows(0..padded.padded_height as usize, &mut dots);
+        dots
+    }
+
+    #[test]
+    fn pad_one() {
+        let image: Image<f32> = Image {
+            width: 1,
+            height: 1,
+            dots: Vec::from([RGBColorFormat::red()]),
+        };
+        let padded: PaddedImage = PaddedImage::new(&image, 16, 8);
+        assert_eq!(padded_dots(&padded).len(), 16 * 8);
+        assert_eq!(padded.padded_height, 8);
+        assert_eq!(padded.padded_width, 16);
+        assert_eq!(padded.height, 1);
+        assert_eq!(padded.width, 1)
+    }
+
+    #[test]
+    fn pad_7_17() {
+        let image: Image<f32> = Image {
+            width: 17,
+            height: 7,
+            dots: Vec::from([RGBColorFormat::red(); 119]),
+        };
+        let padded: PaddedImage = PaddedImage::new(&image, 16, 16);
+        assert_eq!(padded_dots(&padded).len(), 32 * 16)
+    }
+
+    #[test]
+    fn pad_99_99() {
+        let image: Image<f32> = Image {
+            width: 99,
+            height: 99,
+            dots: Vec::from([RGBColorFormat::red(); 9801]),
+        };
+        let padded: PaddedImage = PaddedImage::new(&image, 10, 10);
+        assert_eq!(padded_dots(&padded).len(), 10000)
+    }
+
+    #[test]
+    fn read_rows_with_repeated_edge() {
+        let (red, white) = (RGBColorFormat::red(), RGBColorFormat::white());
+        let image: Image<f32> = Image {
+            width: 3,
+            height: 2,
+            dots: Vec::from([red, red, red, red, red, white]),
+        };
+        let padded: PaddedImage = PaddedImage::new(&image, 4, 4);
+        let mut dots = Vec::new();
+        padded.read_rows(1..3, &mut dots);
+        assert_eq!(dots, [red, red, white, white, red, red, white, white]);
+    }
+}
@@ -0,0 +1,474 @@
+use std::fmt::Display;
+
+#[cfg(feature = "clap")]
+use clap::{builder::PossibleValue, ValueEnum};
+
+use super::{consts, QuantizationTablePair};
+
+// Tables from JPEG Annex K (vips and libjpeg default)
+pub const SPECIFICATION_LUMINANCE_QUANTIZATION_TABLE: [u16; 64] =
+    consts::ANNEX_K_LUMINANCE_QUANTIZATION_TABLE;
+pub const SPECIFICATION_CHROMINANCE_QUANTIZATION_TABLE: [u16; 64] =
+    consts::ANNEX_K_CHROMINANCE_QUANTIZATION_TABLE;
+
+// Flat table
+#[rustfmt::skip]
+pub const FLAT_LUMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+    16,  16,  16,  16,  16,  16,  16,  16,
+    16,  16,  16,  16,  16,  16,  16,  16,
+    16,  16,  16,  16,  16,  16,  16,  16,
+    16,  16,  16,  16,  16,  16,  16,  16,
+    16,  16,  16,  16,  16,  16,  16,  16,
+    16,  16,  16,  16,  16,  16,  16,  16,
+    16,  16,  16,  16,  16,  16,  16,  16,
+    16,  16,  16,  16,  16,  16,  16,  16,
+];
+
+#[rustfmt::skip]
+pub const FLAT_CHROMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+    16,  16,  16,  16,  16,  16,  16,  16,
+    16,  16,  16,  16,  16,  16,  16,  16,
+    16,  16,  16,  16,  16,  16,  16,  16,
+    16,  16,  16,  16,  16,  16,  16,  16,
+    16,  16,  16,  16,  16,  16,  16,  16,
+    16,  16,  16,  16,  16,  16,  16,  16,
+    16,  16,  16,  16,  16,  16,  16,  16,
+    16,  16,  16,  16,  16,  16,  16,  16,
+];
+
+// Table tuned for MSSIM on Kodak image set
+#[rustfmt::skip]
+pub const MSSIM_KODAK_TUNED_LUMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+    12,  17,  20,  21,  30,  34,  56,  63,
+    18,  20,  20,  26,  28,  51,  61,  55,
+    19,  20,  21,  26,  33,  58,  69,  55,
+    26,  26,  26,  30,  46,  87,  86,  66,
+    31,  33,  36,  40,  46,  96, 100,  73,
+    40,  35,  46,  62,  81, 100, 111,  91,
+    46,  66,  76,  86, 102, 121, 120, 101,
+    68,  90,  90,  96, 113, 102, 105, 103,
+];
+
+#[rustfmt::skip]
+pub const MSSIM_KODAK_TUNED_CHROMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+     8,  12,  15,  15,  86,  96,  96,  98,
+    13,  13,  15,  26,  90,  96,  99,  98,
+    12,  15,  18,  96,  99,  99,  99,  99,
+    17,  16,  90,  96,  99,  99,  99,  99,
+    96,  96,  99,  99,  99,  99,  99,  99,
+    99,  99,  99,  99,  99,  99,  99,  99,
+    99,  99,  99,  99,  99,  99,  99,  99,
+    99,  99,  99,  99,  99,  99,  99,  99,
+];
+
+// Table from ImageMagick by N. Robidoux (current mozjpeg default)
+// From http://www.imagemagick.org/discourse-server/viewtopic.php?f=22&t=20333&p=98008#p98008
+#[rustfmt::skip]
+pub const IMAGE_MAGICK_LUMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+    16,  16,   16,  18,  25,  37,  56,  85,
+    16,  17,   20,  27,  34,  40,  53,  75,
+    16,  20,   24,  31,  43,  62,  91, 135,
+    18,  27,   31,  40,  53,  74, 106, 156,
+    25,  34,   43,  53,  69,  94, 131, 189,
+    37,  40,   62,  74,  94, 124, 169, 238,
+    56,  53,   91, 106, 131, 169, 226, 311,
+    85,  75,  135, 156, 189, 238, 311, 418,
+];
+
+#[rustfmt::skip]
+pub const IMAGE_MAGICK_CHROMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+    16,  16,   16,  18,  25,  37,  56,  85,
+    16,  17,   20,  27,  34,  40,  53,  75,
+    16,  20,   24,  31,  43,  62,  91, 135,
+    18,  27,   31,  40,  53,  74, 106, 156,
+    25,  34,   43,  53,  69,  94, 131, 189,
+    37,  40,   62,  74,  94, 124, 169, 238,
+    56,  53,   91, 106, 131, 169, 226, 311,
+    85,  75,  135, 156, 189, 238, 311, 418,
+];
+
+// Table tuned for PSNR-HVS-M on Kodak image set
+#[rustfmt::skip]
+pub const PSNRHVSNI_KODAK_TUNED_LUMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+     9,  10,  12,  14,  27,  32,  51,  62,
+    11,  12,  14,  19,  27,  44,  59,  73,
+    12,  14,  18,  25,  42,  59,  79,  78,
+    17,  18,  25,  42,  61,  92,  87,  92,
+    23,  28,  42,  75,  79, 112, 112,  99,
+    40,  42,  59,  84,  88, 124, 132, 111,
+    42,  64,  78,  95, 105, 126, 125,  99,
+    70,  75, 100, 102, 116, 100, 107,  98,
+];
+
+#[rustfmt::skip]
+pub const PSNRHVSNI_KODAK_TUNED_CHROMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+     9,  10,  17,  19,  62,  89,  91,  97,
+    12,  13,  18,  29,  84,  91,  88,  98,
+    14,  19,  29,  93,  95,  95,  98,  97,
+    20,  26,  84,  88,  95,  95,  98,  94,
+    26,  86,  91,  93,  97,  99,  98,  99,
+    99, 100,  98,  99,  99,  99,  99,  99,
+    99,  99,  99,  99,  99,  99,  99,  99,
+    97,  97,  99,  99,  99,  99,  97,  99,
+];
+
+// Table from Relevance of Human Vision to JPEG-DCT Compression (1992) Klein, Silverstein and Carney.
+#[rustfmt::skip]
+pub const RELEVANCE_OF_HUMAN_VISION_LUMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+    10,  12,  14,  19,  26,  38,  57,  86,
+    12,  18,  21,  28,  35,  41,  54,  76,
+    14,  21,  25,  32,  44,  63,  92, 136,
+    19,  28,  32,  41,  54,  75, 107, 157,
+    26,  35,  44,  54,  70,  95, 132, 190,
+    38,  41,  63,  75,  95, 125, 170, 239,
+    57,  54,  92, 107, 132, 170, 227, 312,
+    86,  76, 136, 157, 190, 239, 312, 419,
+];
+
+#[rustfmt::skip]
+pub const RELEVANCE_OF_HUMAN_VISION_CHROMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+    10,  12,  14,  19,  26,  38,  57,  86,
+    12,  18,  21,  28,  35,  41,  54,  76,
+    14,  21,  25,  32,  44,  63,  92, 136,
+    19,  28,  32,  41,  54,  75, 107, 157,
+    26,  35,  44,  54,  70,  95, 132, 190,
+    38,  41,  63,  75,  95, 125, 170, 239,
+    57,  54,  92, 107, 132, 170, 227, 312,
+    86,  76, 136, 157, 190, 239, 312, 419,
+];
+
+// Table from DCTune Perceptual Optimization of Compressed Dental X-Rays (1997) Watson, Taylor, Borthwick
+#[rustfmt::skip]
+pub const DC_TUNE_PERCEPTUAL_OPTIMIZATION_LUMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+      7,  8,   10,  14,  23,  44,  95, 241,
+      8,  8,   11,  15,  25,  47, 102, 255,
+     10,  11,  13,  19,  31,  58, 127, 255,
+     14,  15,  19,  27,  44,  83, 181, 255,
+     23,  25,  31,  44,  72, 136, 255, 255,
+     44,  47,  58,  83, 136, 255, 255, 255,
+     95, 102, 127, 181, 255, 255, 255, 255,
+    241, 255, 255, 255, 255, 255, 255, 255,
+];
+
+#[rustfmt::skip]
+pub const DC_TUNE_PERCEPTUAL_OPTIMIZATION_CHROMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+      7,   8,  10,  14,  23,  44,  95, 241,
+      8,   8,  11,  15,  25,  47, 102, 255,
+     10,  11,  13,  19,  31,  58, 127, 255,
+     14,  15,  19,  27,  44,  83, 181, 255,
+     23,  25,  31,  44,  72, 136, 255, 255,
+     44,  47,  58,  83, 136, 255, 255, 255,
+     95, 102, 127, 181, 255, 255, 255, 255,
+    241, 255, 255, 255, 255, 255, 255, 255,
+];
+
+// Table from A Visual Detection Model for DCT Coefficient Quantization (1993) Ahumada, Watson, Peterson
+#[rustfmt::skip]
+pub const A_VISUAL_DETECTION_MODEL_LUMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+    15, 11, 11, 12, 15, 19, 25, 32,
+    11, 13, 10, 10, 12, 15, 19, 24,
+    11, 10, 14, 14, 16, 18, 22, 27,
+    12, 10, 14, 18, 21, 24, 28, 33,
+    15, 12, 16, 21, 26, 31, 36, 42,
+    19, 15, 18, 24, 31, 38, 45, 53,
+    25, 19, 22, 28, 36, 45, 55, 65,
+    32, 24, 27, 33, 42, 53, 65, 77,
+];
+
+#[rustfmt::skip]
+pub const A_VISUAL_DETECTION_MODEL_CHROMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+    15, 11, 11, 12, 15, 19, 25, 32,
+    11, 13, 10, 10, 12, 15, 19, 24,
+    11, 10, 14, 14, 16, 18, 22, 27,
+    12, 10, 14, 18, 21, 24, 28, 33,
+    15, 12, 16, 21, 26, 31, 36, 42,
+    19, 15, 18, 24, 31, 38, 45, 53,
+    25, 19, 22, 28, 36, 45, 55, 65,
+    32, 24, 27, 33, 42, 53, 65, 77,
+];
+
+// Table from An Improved Detection Model for DCT Coefficient Quantization (1993) Peterson, Ahumada and Watson
+#[rustfmt::skip]
+pub const AN_IMPROVED_DETECTION_MODEL_LUMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+    14,  10,  11,  14,  19,  25,  34,  45,
+    10,  11,  11,  12,  15,  20,  26,  33,
+    11,  11,  15,  18,  21,  25,  31,  38,
+    14,  12,  18,  24,  28,  33,  39,  47,
+    19,  15,  21,  28,  36,  43,  51,  59,
+    25,  20,  25,  33,  43,  54,  64,  74,
+    34,  26,  31,  39,  51,  64,  77,  91,
+    45,  33,  38,  47,  59,  74,  91, 108,
+];
+
+#[rustfmt::skip]
+pub const AN_IMPROVED_DETECTION_MODEL_CHROMINANCE_QUANTIZATION_TABLE: [u16; 64] = [
+    14,  10,  11,  14,  19,  25,  34,  45,
+    10,  11,  11,  12,  15,  20,  26,  33,
+    11,  11,  15,  18,  21,  25,  31,  38,
+    14,  12,  18,  24,  28,  33,  39,  47,
+    19,  15,  21,  28,  36,  43,  51,  59,
+    25,  20,  25,  33,  43,  54,  64,  74,
+    34,  26,  31,  39,  51,  64,  77,  91,
+    45,  33,  38,  47,  59,  74,  91, 108
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QuantizationTablePreset {
+    Specification,
+    Flat,
+    MSSIMKodakTuned,
+    ImageMagick,
+    PSNRHVSNKodakTuned,
+    RelevanceOfHumanVision,
+    DCTunePerceptualOptimization,
+    AVisualDetectionModel,
+    AnImprovedDetectionModel,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for QuantizationTablePreset {
+    fn value_variants<'a>() -> &'a [Self] {
+        Self::variants()
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let return_value = match self {
+            Self::Specification => {
+                PossibleValue::new(self.name()).aliases(["Spec", "Default", "0"])
+            }
+            Self::Flat => PossibleValue::new(self.name()).alias("1"),
+            Self::MSSIMKodakTuned => PossibleValue::new(self.name()).alias("2"),
+            Self::ImageMagick => PossibleValue::new(self.name()).alias("3"),
+            Self::PSNRHVSNKodakTuned => PossibleValue::new(self.name()).alias("4"),
+            Self::RelevanceOfHumanVision => PossibleValue::new(self.name()).alias("5"),
+            Self::DCTunePerceptualOptimization => PossibleValue::new(self.name()).alias("6"),
+            Self::AVisualDetectionModel => PossibleValue::new(self.name()).alias("7"),
+            Self::AnImprovedDetectionModel => PossibleValue::new(self.name()).alias("8"),
+        };
+        Some(return_value)
+    }
+}
+
+impl QuantizationTablePreset {
+    pub fn variants() -> &'static [Self] {
+        &[
+            Self::Specification,
+            Self::Flat,
+            Self::MSSIMKodakTuned,
+            Self::ImageMagick,
+            Self::PSNRHVSNKodakTuned,
+            Self::RelevanceOfHumanVision,
+            Self::DCTunePerceptualOptimization,
+            Self::AVisualDetectionModel,
+            Self::AnImprovedDetectionModel,
+        ]
+    }
+
+    /// Name of the preset, as accepted on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Specification => "Specification",
+            Self::Flat => "Flat",
+            Self::MSSIMKodakTuned => "MSSIM-Kodak-Tuned",
+            Self::ImageMagick => "ImageMagick",
+            Self::PSNRHVSNKodakTuned => "PSNR-HVS-N-Kodak-Tuned",
+            Self::RelevanceOfHumanVision => "Relevance-of-human-vision",
+            Self::DCTunePerceptualOptimization => "DCTune-Perceptual-Optimization",
+            Self::AVisualDetectionModel => "A-visual-detection-model",
+            Self::AnImprovedDetectionModel => "An-improved-detection-model",
+        }
+    }
+
+    /// Chroma table of the preset, which can quantize the Cr component of another preset.
+    pub fn chroma_table(self) -> [u16; 64] {
+        self.to_pair().chroma_table
+    }
+
+    pub fn to_pair(self) -> QuantizationTablePair {
+        match self {
+            Self::Specification => QuantizationTablePair {
+                luma_table: SPECIFICATION_LUMINANCE_QUANTIZATION_TABLE,
+                chroma_table: SPECIFICATION_CHROMINANCE_QUANTIZATION_TABLE,
+                chroma_red_table: None,
+            },
+            Self::Flat => QuantizationTablePair {
+                luma_table: FLAT_LUMINANCE_QUANTIZATION_TABLE,
+                chroma_table: FLAT_CHROMINANCE_QUANTIZATION_TABLE,
+                chroma_red_table: None,
+            },
+            Self::MSSIMKodakTuned => QuantizationTablePair {
+                luma_table: MSSIM_KODAK_TUNED_LUMINANCE_QUANTIZATION_TABLE,
+                chroma_table: MSSIM_KODAK_TUNED_CHROMINANCE_QUANTIZATION_TABLE,
+                chroma_red_table: None,
+            },
+            Self::ImageMagick => QuantizationTablePair {
+                luma_table: IMAGE_MAGICK_LUMINANCE_QUANTIZATION_TABLE,
+                chroma_table: IMAGE_MAGICK_CHROMINANCE_QUANTIZATION_TABLE,
+                chroma_red_table: None,
+            },
+            Self::PSNRHVSNKodakTuned => QuantizationTablePair {
+                luma_table: PSNRHVSNI_KODAK_TUNED_LUMINANCE_QUANTIZATION_TABLE,
+                chroma_table: PSNRHVSNI_KODAK_TUNED_CHROMINANCE_QUANTIZATION_TABLE,
+                chroma_red_table: None,
+            },
+            Self::RelevanceOfHumanVision => QuantizationTablePair {
+                luma_table: RELEVANCE_OF_HUMAN_VISION_LUMINANCE_QUANTIZATION_TABLE,
+                chroma_table: RELEVANCE_OF_HUMAN_VISION_CHROMINANCE_QUANTIZATION_TABLE,
+                chroma_red_table: None,
+            },
+            Self::DCTunePerceptualOptimization => QuantizationTablePair {
+                luma_table: DC_TUNE_PERCEPTUAL_OPTIMIZATION_LUMINANCE_QUANTIZATION_TABLE,
+                chroma_table: DC_TUNE_PERCEPTUAL_OPTIMIZATION_CHROMINANCE_QUANTIZATION_TABLE,
+                chroma_red_table: None,
+            },
+            Self::AVisualDetectionModel => QuantizationTablePair {
+                luma_table: A_VISUAL_DETECTION_MODEL_LUMINANCE_QUANTIZATION_TABLE,
+                chroma_table: A_VISUAL_DETECTION_MODEL_CHROMINANCE_QUANTIZATION_TABLE,
+                chroma_red_table: None,
+            },
+            Self::AnImprovedDetectionModel => QuantizationTablePair {
+                luma_table: AN_IMPROVED_DETECTION_MODEL_LUMINANCE_QUANTIZATION_TABLE,
+                chroma_table: AN_IMPROVED_DETECTION_MODEL_CHROMINANCE_QUANTIZATION_TABLE,
+                chroma_red_table: None,
+            },
+        }
+    }
+}
+
+/// Weighted blend of the tables of two presets, which allows to experiment with tables between
+/// the presets without authoring custom tables.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuantizationTableMix {
+    presets: [(QuantizationTablePreset, f32); 2],
+}
+
+impl QuantizationTableMix {
+    /// The weights are relative to their sum, so they need not add up to 1.
+    pub fn new(
+        first: (QuantizationTablePreset, f32),
+        second: (QuantizationTablePreset, f32),
+    ) -> Self {
+        let weights_are_valid = [first.1, second.1]
+            .iter()
+            .all(|weight| weight.is_finite() && *weight >= 0.0);
+        if !weights_are_valid || first.1 + second.1 <= 0.0 {
+            panic!("Weights of the mix must be non negative and must not both be zero");
+        }
+        Self {
+            presets: [first, second],
+        }
+    }
+
+    pub fn to_pair(self) -> QuantizationTablePair {
+        let [(first, first_weight), (second, second_weight)] = self.presets;
+        let (first, second) = (first.to_pair(), second.to_pair());
+        let weight_sum = first_weight + second_weight;
+        let mix = |first: &[u16; 64], second: &[u16; 64]| -> [u16; 64] {
+            std::array::from_fn(|index| {
+                let step = (first[index] as f32 * first_weight
+                    + second[index] as f32 * second_weight)
+                    / weight_sum;
+                (step.round() as u16).max(1)
+            })
+        };
+        let chroma_red_table = match (first.chroma_red_table, second.chroma_red_table) {
+            (None, None) => None,
+            _ => Some(mix(first.chroma_red_table(), second.chroma_red_table())),
+        };
+        QuantizationTablePair {
+            luma_table: mix(&first.luma_table, &second.luma_table),
+            chroma_table: mix(&first.chroma_table, &second.chroma_table),
+            chroma_red_table,
+        }
+    }
+}
+
+/// Formats the mix as `PRESET:WEIGHT,PRESET:WEIGHT`, as accepted on the command line.
+impl Display for QuantizationTableMix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [(first, first_weight), (second, second_weight)] = self.presets;
+        write!(
+            f,
+            "{}:{},{}:{}",
+            first.name(),
+            first_weight,
+            second.name(),
+            second_weight
+        )
+    }
+}
+
+/// Parses a quantization table of 64 steps in row major order, separated by whitespace or
+/// commas. Lines starting with `#` are comments. Each step must fit into a baseline table.
+pub fn parse_quantization_table(content: &str) -> Result<[u16; 64], String> {
+    let steps = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .flat_map(|line| line.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter(|step| !step.is_empty())
+        .map(|step| match step.parse::<u8>() {
+            Ok(step) if step > 0 => Ok(step as u16),
+            _ => Err(format!("step '{}' is not in range 1 to 255", step)),
+        })
+        .collect::<Result<Vec<u16>, String>>()?;
+    let number_of_steps = steps.len();
+    steps
+        .try_into()
+        .map_err(|_| format!("table has {} instead of 64 steps", number_of_steps))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        parse_quantization_table, QuantizationTableMix, QuantizationTablePreset,
+        FLAT_CHROMINANCE_QUANTIZATION_TABLE, SPECIFICATION_LUMINANCE_QUANTIZATION_TABLE,
+    };
+
+    #[test]
+    fn parse_table_with_comments_and_separators() {
+        let content = format!("# flat table\n{}\n", ["16"; 64].join(", "));
+        assert_eq!(
+            parse_quantization_table(&content),
+            Ok(FLAT_CHROMINANCE_QUANTIZATION_TABLE)
+        );
+        let content = ["1 2 3 4 5 6 7 8"; 8].join("\n");
+        let table = parse_quantization_table(&content).unwrap();
+        assert_eq!(table[..9], [1, 2, 3, 4, 5, 6, 7, 8, 1]);
+    }
+
+    #[test]
+    fn mix_tables_of_presets() {
+        let mix = QuantizationTableMix::new(
+            (QuantizationTablePreset::Flat, 0.75),
+            (QuantizationTablePreset::Specification, 0.25),
+        );
+        let pair = mix.to_pair();
+        // the flat tables hold 16 in every step
+        for (&mixed, &specification) in pair
+            .luma_table
+            .iter()
+            .zip(&SPECIFICATION_LUMINANCE_QUANTIZATION_TABLE)
+        {
+            let expected = (16.0 * 0.75 + specification as f32 * 0.25).round() as u16;
+            assert_eq!(mixed, expected);
+        }
+        assert_eq!(mix.to_string(), "Flat:0.75,Specification:0.25");
+        let only_flat = QuantizationTableMix::new(
+            (QuantizationTablePreset::Flat, 3.0),
+            (QuantizationTablePreset::Specification, 0.0),
+        );
+        assert_eq!(
+            only_flat.to_pair().chroma_table,
+            FLAT_CHROMINANCE_QUANTIZATION_TABLE
+        );
+    }
+
+    #[test]
+    fn reject_invalid_tables() {
+        assert!(parse_quantization_table(&["16"; 63].join(" ")).is_err());
+        assert!(parse_quantization_table(&["16"; 65].join(" ")).is_err());
+        assert!(parse_quantization_table(&["0"; 64].join(" ")).is_err());
+        assert!(parse_quantization_table(&["256"; 64].join(" ")).is_err());
+    }
+}
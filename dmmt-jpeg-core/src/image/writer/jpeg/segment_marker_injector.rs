@@ -8,6 +8,12 @@ impl<'a, T: Write> SegmentMarkerInjector<'a, T> {
     pub fn new(writer: &'a mut T) -> Self {
         Self { writer }
     }
+
+    /// Gets a mutable reference to the underlying writer, which takes bytes without stuffing,
+    /// like markers.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.writer
+    }
 }
 
 impl<T: Write> Write for SegmentMarkerInjector<'_, T> {
@@ -0,0 +1,136 @@
+use crate::color::RGBColorFormat;
+use crate::Error;
+
+/// Length of the JFIF APP0 segment content without the thumbnail samples.
+const JFIF_HEADER_LENGTH: usize = 14;
+
+/// Largest number of thumbnail pixels, whose RGB samples fit into the JFIF APP0 segment.
+pub const MAX_THUMBNAIL_PIXELS: usize = (u16::MAX as usize - 2 - JFIF_HEADER_LENGTH) / 3;
+
+/// Size of the uncompressed RGB thumbnail, which is embedded into the JFIF APP0 segment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThumbnailSize {
+    width: u8,
+    height: u8,
+}
+
+impl ThumbnailSize {
+    pub fn new(width: u16, height: u16) -> crate::Result<Self> {
+        let fits = width > 0
+            && height > 0
+            && width <= u8::MAX as u16
+            && height <= u8::MAX as u16
+            && width as usize * height as usize <= MAX_THUMBNAIL_PIXELS;
+        if !fits {
+            return Err(Error::InvalidThumbnailSize(width, height));
+        }
+        Ok(Self {
+            width: width as u8,
+            height: height as u8,
+        })
+    }
+
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+}
+
+/// Thumbnail with interleaved 8 bit RGB samples in row major order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Thumbnail {
+    pub size: ThumbnailSize,
+    pub samples: Vec<u8>,
+}
+
+impl Thumbnail {
+    /// Downsamples an image of `width` x `height` dots with a box filter, which averages all
+    /// dots covered by a thumbnail pixel. Each thumbnail pixel covers at least one dot, so
+    /// images smaller than the thumbnail are enlarged by repeating their dots.
+    pub fn downsample(
+        width: u16,
+        height: u16,
+        dot: impl Fn(usize, usize) -> RGBColorFormat<f32>,
+        size: ThumbnailSize,
+    ) -> Self {
+        let covered_range = |index: usize, target_len: usize, source_len: usize| {
+            let start = index * source_len / target_len;
+            let end = ((index + 1) * source_len / target_len).max(start + 1);
+            start..end
+        };
+        let (target_width, target_height) = (size.width as usize, size.height as usize);
+        let samples = (0..target_height)
+            .flat_map(|y| (0..target_width).map(move |x| (x, y)))
+            .flat_map(|(x, y)| {
+                let columns = covered_range(x, target_width, width as usize);
+                let rows = covered_range(y, target_height, height as usize);
+                let count = (columns.len() * rows.len()) as f32;
+                let sum = rows
+                    .flat_map(|y| columns.clone().map(move |x| (x, y)))
+                    .fold([0_f32; 3], |mut sum, (x, y)| {
+                        for (total, sample) in sum.iter_mut().zip(dot(x, y).samples()) {
+                            *total += sample;
+                        }
+                        sum
+                    });
+                RGBColorFormat::clamped(sum[0] / count, sum[1] / count, sum[2] / count).to_rgb8()
+            })
+            .collect();
+        Self { size, samples }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Thumbnail, ThumbnailSize, MAX_THUMBNAIL_PIXELS};
+    use crate::color::RGBColorFormat;
+    use crate::Error;
+
+    #[test]
+    fn reject_sizes_exceeding_the_segment() {
+        assert!(ThumbnailSize::new(160, 120).is_ok());
+        assert!(matches!(
+            ThumbnailSize::new(0, 10),
+            Err(Error::InvalidThumbnailSize(0, 10))
+        ));
+        assert!(matches!(
+            ThumbnailSize::new(256, 1),
+            Err(Error::InvalidThumbnailSize(256, 1))
+        ));
+        assert!(matches!(
+            ThumbnailSize::new(255, 255),
+            Err(Error::InvalidThumbnailSize(255, 255))
+        ));
+        assert_eq!(MAX_THUMBNAIL_PIXELS, 21839);
+    }
+
+    #[test]
+    fn downsample_averages_covered_dots() {
+        // Left half black, right half white
+        let dot = |x: usize, _: usize| {
+            if x < 2 {
+                RGBColorFormat::default()
+            } else {
+                RGBColorFormat::default().inverted()
+            }
+        };
+        let size = ThumbnailSize::new(2, 1).unwrap();
+        let thumbnail = Thumbnail::downsample(4, 2, dot, size);
+        assert_eq!(thumbnail.samples, [0, 0, 0, 255, 255, 255]);
+
+        let size = ThumbnailSize::new(1, 1).unwrap();
+        let thumbnail = Thumbnail::downsample(4, 2, dot, size);
+        assert_eq!(thumbnail.samples, [128, 128, 128]);
+    }
+
+    #[test]
+    fn upsample_repeats_dots() {
+        let dot = |_: usize, _: usize| RGBColorFormat::red();
+        let size = ThumbnailSize::new(3, 2).unwrap();
+        let thumbnail = Thumbnail::downsample(1, 1, dot, size);
+        assert_eq!(thumbnail.samples, [255, 0, 0].repeat(6));
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+/// Stage of the conversion to JPEG, whose time and processed bytes are recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// Reading and decoding of the input file, which the caller of the writer records.
+    Read,
+    /// Conversion of the dots to YCbCr and splitting into the channels.
+    ColorConversion,
+    /// Subsampling of the channels and resorting of their samples into blocks.
+    Subsampling,
+    /// Level shift and DCT of the blocks.
+    Dct,
+    /// Quantization of the coefficients and categorization into Huffman symbols.
+    Quantization,
+    /// Stages from the color conversion up to the categorization of the passes, whose bands
+    /// are only counted, like the pass for the rate tables of the trellis quantization. Their
+    /// bytes are the bytes of the source.
+    RepeatedPasses,
+    /// Counting of the symbols and generation of the optimized Huffman tables.
+    HuffmanGeneration,
+    /// Writing of the segments and the entropy coded data.
+    EntropyWriting,
+}
+
+impl Stage {
+    pub fn variants() -> &'static [Self] {
+        &[
+            Self::Read,
+            Self::ColorConversion,
+            Self::Subsampling,
+            Self::Dct,
+            Self::Quantization,
+            Self::RepeatedPasses,
+            Self::HuffmanGeneration,
+            Self::EntropyWriting,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::ColorConversion => "color conversion",
+            Self::Subsampling => "subsampling",
+            Self::Dct => "dct",
+            Self::Quantization => "quantization",
+            Self::RepeatedPasses => "repeated passes",
+            Self::HuffmanGeneration => "huffman generation",
+            Self::EntropyWriting => "entropy writing",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+const NUMBER_OF_STAGES: usize = 8;
+
+/// CPU time and bytes processed by each stage of an encode. The stages, which run in the jobs
+/// of the bands of the image, record their time in each job, so their times are summed over the
+/// threads and may exceed the wall time of the encode. The other stages run on a single thread,
+/// whose time is recorded. The timings are shared with the jobs, so they are recorded without a
+/// lock.
+#[derive(Debug, Default)]
+pub struct StageTimings {
+    nanoseconds: [AtomicU64; NUMBER_OF_STAGES],
+    bytes: [AtomicU64; NUMBER_OF_STAGES],
+}
+
+impl StageTimings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the time and the bytes to the stage.
+    pub fn record(&self, stage: Stage, time: Duration, bytes: u64) {
+        let nanoseconds = u64::try_from(time.as_nanos()).unwrap_or(u64::MAX);
+        self.nanoseconds[stage.index()].fetch_add(nanoseconds, Ordering::Relaxed);
+        self.bytes[stage.index()].fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Runs the stage and records its time with the bytes, it processes.
+    pub fn measure<R>(&self, stage: Stage, bytes: u64, run_stage: impl FnOnce() -> R) -> R {
+        let start = Instant::now();
+        let result = run_stage();
+        self.record(stage, start.elapsed(), bytes);
+        result
+    }
+
+    /// Adds the timings of a pass stage by stage.
+    pub(super) fn add(&self, timings: &StageTimings) {
+        for &stage in Stage::variants() {
+            self.record(stage, timings.time(stage), timings.bytes(stage));
+        }
+    }
+
+    /// Adds the time of all stages of a pass, whose bands are only counted, to
+    /// [`Stage::RepeatedPasses`].
+    pub(super) fn add_repeated_pass(&self, timings: &StageTimings) {
+        let time = Stage::variants()
+            .iter()
+            .map(|&stage| timings.time(stage))
+            .sum();
+        self.record(
+            Stage::RepeatedPasses,
+            time,
+            timings.bytes(Stage::ColorConversion),
+        );
+    }
+
+    pub fn time(&self, stage: Stage) -> Duration {
+        Duration::from_nanos(self.nanoseconds[stage.index()].load(Ordering::Relaxed))
+    }
+
+    pub fn bytes(&self, stage: Stage) -> u64 {
+        self.bytes[stage.index()].load(Ordering::Relaxed)
+    }
+}
+
+/// Table of the stages with their time, processed bytes and throughput. Stages without
+/// recorded bytes are left out.
+impl Display for StageTimings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<18}  {:>13}  {:>12}  {:>9}",
+            "stage", "cpu time [ms]", "bytes", "MB/s"
+        )?;
+        for &stage in Stage::variants() {
+            let (time, bytes) = (self.time(stage), self.bytes(stage));
+            if bytes == 0 {
+                continue;
+            }
+            let throughput = bytes as f64 / 1_000_000.0 / time.as_secs_f64().max(f64::EPSILON);
+            writeln!(
+                f,
+                "{:<18}  {:>13.2}  {:>12}  {:>9.1}",
+                stage.name(),
+                time.as_secs_f64() * 1000.0,
+                bytes,
+                throughput
+            )?;
+        }
+        write!(
+            f,
+            "Times of the stages in the band jobs are summed over the threads, so they may exceed \
+             the wall time."
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{Stage, StageTimings};
+
+    #[test]
+    fn sum_recorded_stages() {
+        let timings = StageTimings::new();
+        timings.record(Stage::Dct, Duration::from_millis(3), 1024);
+        timings.record(Stage::Dct, Duration::from_millis(2), 1024);
+        let result = timings.measure(Stage::EntropyWriting, 10, || 42);
+        assert_eq!(result, 42);
+        assert_eq!(timings.time(Stage::Dct), Duration::from_millis(5));
+        assert_eq!(timings.bytes(Stage::Dct), 2048);
+        assert_eq!(timings.bytes(Stage::EntropyWriting), 10);
+        assert_eq!(timings.bytes(Stage::Read), 0);
+    }
+
+    #[test]
+    fn sum_stages_of_repeated_pass() {
+        let (timings, pass_timings) = (StageTimings::new(), StageTimings::new());
+        pass_timings.record(Stage::ColorConversion, Duration::from_millis(2), 300);
+        pass_timings.record(Stage::Dct, Duration::from_millis(3), 400);
+        timings.add_repeated_pass(&pass_timings);
+        assert_eq!(
+            timings.time(Stage::RepeatedPasses),
+            Duration::from_millis(5)
+        );
+        assert_eq!(timings.bytes(Stage::RepeatedPasses), 300);
+        assert_eq!(timings.bytes(Stage::Dct), 0);
+        timings.add(&pass_timings);
+        assert_eq!(timings.bytes(Stage::Dct), 400);
+    }
+
+    #[test]
+    fn list_stages_with_bytes_in_table() {
+        let timings = StageTimings::new();
+        timings.record(Stage::Read, Duration::from_millis(4), 2_000_000);
+        timings.record(Stage::Quantization, Duration::from_millis(1), 64);
+        let table = timings.to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("stage"));
+        assert!(lines[1].starts_with("read"));
+        assert!(lines[1].contains("4.00"));
+        assert!(lines[1].ends_with("500.0"));
+        assert!(lines[2].starts_with("quantization"));
+    }
+}
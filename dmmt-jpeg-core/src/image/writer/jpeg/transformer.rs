@@ -0,0 +1,1151 @@
+use std::sync::Arc;
+
+use band_pipeline::{concatenate_pooled_bands, BandStages};
+use block_entangler::entangle_channels;
+use categorize::{CategorizedBlock, DcPredictor};
+use frequency_block::FrequencyBlock;
+use fused_stage::transform_and_categorize_blocks;
+use quantizer::{BlockQuantizer, Quantizer};
+use sample::{Sample, SourceDot};
+use symbol_counting::{HuffmanCount, SymbolCounter, SYMBOL_STATS_CSV_HEADER};
+use threadpool::ThreadPool;
+use trellis::RateModel;
+
+use super::{
+    huffman_tables::{HuffmanTableMode, HuffmanTables},
+    padder::PaddedImage,
+    timings::{Stage, StageTimings},
+    Image, JpegTransformationOptions, OutputImage, QuantizationTablePair, MAX_COMMENT_LENGTH,
+    MAX_HUFFMAN_CODE_LENGTH, MIN_HUFFMAN_CODE_LENGTH_LIMIT,
+};
+use crate::{
+    color::{
+        CMYKColorFormat, RGBColorFormat, YCbCrColorFormat, BASELINE_SAMPLE_PRECISION,
+        EXTENDED_SAMPLE_PRECISION,
+    },
+    huffman::{standard_tables, SymbolCodeLength},
+    image::{CMYKImage, ColorChannel, YCbCrImage},
+    Error, Result,
+};
+
+pub mod adaptive_quantization;
+mod band_pipeline;
+mod block_entangler;
+mod buffer_pool;
+pub mod categorize;
+mod color_lookup;
+pub mod dead_zone;
+pub mod frequency_block;
+mod fused_stage;
+pub mod quantizer;
+pub mod sample;
+mod symbol_counting;
+pub mod trellis;
+mod vectorized_quantization;
+
+pub struct CombinedColorChannels<T> {
+    pub luma: T,
+    pub chroma_red: T,
+    pub chroma_blue: T,
+    /// Black ink of CMYK images, which is treated like the luma. It is empty for the other
+    /// images.
+    pub key: T,
+}
+
+type SeparateColorChannels<T> = CombinedColorChannels<ColorChannel<T>>;
+
+/// Blocks of a channel in the order of the interleaved scan.
+type EntangledBlocks<'a, B> = Box<dyn Iterator<Item = B> + 'a>;
+
+/// Categorized blocks of a band of the image in the order of the interleaved scan. The blocks of
+/// the whole image form a single band.
+pub type CategorizedBand = CombinedColorChannels<Vec<CategorizedBlock>>;
+
+/// Counted symbols of each channel.
+type SymbolCounters = CombinedColorChannels<SymbolCounter>;
+
+/// Bands of the scan in the order of the image, which are produced, while they are written.
+pub(super) type ScanBands<'a> = Box<dyn Iterator<Item = CategorizedBand> + 'a>;
+
+/// Bytes of the categorized blocks, up to which the bands of the counting pass are kept for the
+/// scan. A block of a photograph takes about a hundred bytes with its tokens, so the bands of
+/// photographs of about three megapixels are kept. Larger images run through all stages up to the
+/// categorization a second time, which costs about a third of the time of the encode and half
+/// of it with the trellis quantization, so the memory stays bounded by the bands in flight.
+const MAX_KEPT_BYTES: usize = 8 << 20;
+
+pub struct Transformer<'a, P = RGBColorFormat<f32>> {
+    options: &'a JpegTransformationOptions,
+    image: PaddedImage<'a, P>,
+    threadpool: &'a ThreadPool,
+    stage_timings: Arc<StageTimings>,
+}
+
+/// Output image of the search for a target size with the quality and size it was encoded with.
+pub(super) struct SizedOutputImage {
+    pub output_image: OutputImage,
+    pub quality: u8,
+    pub size: u64,
+}
+
+/// Output image, whose scan is written from the bands, if they are set. Otherwise the blocks of
+/// the output image form the scan.
+pub(super) struct StreamedOutputImage<'a> {
+    pub output_image: OutputImage,
+    pub scan_bands: Option<ScanBands<'a>>,
+}
+
+/// Images are padded to whole MCUs. Each MCU holds one block of each chroma channel, so even an
+/// image of a single pixel yields as many chroma blocks as MCUs under every preset and the
+/// interleaved scan never runs out of chroma blocks.
+fn pad_multiples(options: &JpegTransformationOptions) -> (u16, u16) {
+    let layout = options.frame_subsampling_preset().sampling_layout();
+    (layout.mcu_width(), layout.mcu_height())
+}
+
+/// Tables of Annex K, without the chroma tables for a grayscale image.
+fn standard_huffman_tables(grayscale: bool) -> HuffmanTables {
+    let chroma = |code_lengths: fn() -> Vec<SymbolCodeLength>| {
+        if grayscale {
+            Vec::new()
+        } else {
+            code_lengths()
+        }
+    };
+    HuffmanTables {
+        luma_ac: standard_tables::luminance_ac(),
+        luma_dc: standard_tables::luminance_dc(),
+        chroma_ac: chroma(standard_tables::chrominance_ac),
+        chroma_dc: chroma(standard_tables::chrominance_dc),
+    }
+}
+
+fn empty_band() -> CategorizedBand {
+    CombinedColorChannels {
+        luma: Vec::new(),
+        chroma_red: Vec::new(),
+        chroma_blue: Vec::new(),
+        key: Vec::new(),
+    }
+}
+
+fn number_of_blocks(band: &CategorizedBand) -> usize {
+    band.luma.len() + band.chroma_red.len() + band.chroma_blue.len() + band.key.len()
+}
+
+/// Bytes, the categorized blocks of the band occupy on the heap.
+fn band_heap_size(band: &CategorizedBand) -> usize {
+    [&band.luma, &band.chroma_red, &band.chroma_blue, &band.key]
+        .iter()
+        .map(|blocks| {
+            blocks.capacity() * size_of::<CategorizedBlock>()
+                + blocks
+                    .iter()
+                    .map(CategorizedBlock::heap_size)
+                    .sum::<usize>()
+        })
+        .sum()
+}
+
+/// Counters of the symbols, which are coded with the luma and with the chroma tables. The black
+/// ink of CMYK images shares the tables of the luma, the chroma channels share theirs.
+fn table_symbol_counters(symbol_counters: &SymbolCounters) -> (SymbolCounter, SymbolCounter) {
+    let mut luma_counter = symbol_counters.luma.clone();
+    luma_counter.merge(&symbol_counters.key);
+    let mut chroma_counter = symbol_counters.chroma_blue.clone();
+    chroma_counter.merge(&symbol_counters.chroma_red);
+    (luma_counter, chroma_counter)
+}
+
+/// Frees the buffers, which the pools of the samples keep between images.
+pub fn release_buffers() {
+    f32::buffer_pool().trim();
+    f64::buffer_pool().trim();
+}
+
+/// Puts the buffers of the channels back into the pool of their samples.
+fn put_back_channels<S: Sample>(channels: SeparateColorChannels<S>) {
+    let pool = S::buffer_pool();
+    for channel in [
+        channels.luma,
+        channels.chroma_red,
+        channels.chroma_blue,
+        channels.key,
+    ] {
+        pool.put_back(channel.dots);
+    }
+}
+
+impl<'a> Transformer<'a> {
+    pub fn new(
+        image: &'a Image<f32>,
+        options: &'a JpegTransformationOptions,
+        threadpool: &'a ThreadPool,
+    ) -> Self {
+        let (width_pad_multiple, height_pad_multiple) = pad_multiples(options);
+        let padded_image = PaddedImage::new(image, width_pad_multiple, height_pad_multiple);
+        Self::with_padded_image(padded_image, options, threadpool)
+    }
+}
+
+impl<'a> Transformer<'a, YCbCrColorFormat<f32>> {
+    /// Creates a transformer for an image, which is already in YCbCr. The color conversion is
+    /// skipped.
+    pub fn from_ycbcr_image(
+        image: &'a YCbCrImage,
+        options: &'a JpegTransformationOptions,
+        threadpool: &'a ThreadPool,
+    ) -> Self {
+        let (width_pad_multiple, height_pad_multiple) = pad_multiples(options);
+        let padded_image =
+            PaddedImage::from_ycbcr_image(image, width_pad_multiple, height_pad_multiple);
+        Self::with_padded_image(padded_image, options, threadpool)
+    }
+}
+
+impl<'a> Transformer<'a, CMYKColorFormat<f32>> {
+    /// Creates a transformer for a CMYK image, which is encoded as YCCK.
+    pub fn from_cmyk_image(
+        image: &'a CMYKImage,
+        options: &'a JpegTransformationOptions,
+        threadpool: &'a ThreadPool,
+    ) -> Self {
+        let (width_pad_multiple, height_pad_multiple) = pad_multiples(options);
+        let padded_image =
+            PaddedImage::from_cmyk_image(image, width_pad_multiple, height_pad_multiple);
+        Self::with_padded_image(padded_image, options, threadpool)
+    }
+}
+
+impl<'a, P: SourceDot> Transformer<'a, P> {
+    fn with_padded_image(
+        image: PaddedImage<'a, P>,
+        options: &'a JpegTransformationOptions,
+        threadpool: &'a ThreadPool,
+    ) -> Self {
+        Transformer {
+            options,
+            image,
+            threadpool,
+            stage_timings: Arc::new(StageTimings::new()),
+        }
+    }
+
+    /// Records the time of the stages into the timings, which the caller shares.
+    pub fn with_stage_timings(self, stage_timings: Arc<StageTimings>) -> Self {
+        Self {
+            stage_timings,
+            ..self
+        }
+    }
+
+    fn apply_cosine_transform_on_all_channels_in_place<S: Sample>(
+        &self,
+        channels: &mut SeparateColorChannels<S>,
+    ) {
+        let dct_algorithm = self.options.dct_algorithm;
+        let transform_channel = |channel: &mut ColorChannel<S>| {
+            let bytes = size_of_val(channel.dots.as_slice()) as u64;
+            self.stage_timings.measure(Stage::Dct, bytes, || {
+                S::transform_channel(channel, dct_algorithm, self.threadpool)
+            });
+        };
+        transform_channel(&mut channels.luma);
+        if !self.options.grayscale {
+            transform_channel(&mut channels.chroma_red);
+            transform_channel(&mut channels.chroma_blue);
+        }
+        if P::HAS_KEY {
+            transform_channel(&mut channels.key);
+        }
+    }
+
+    /// Quantizers of the blocks of each channel without the adaptive quantization, which depends
+    /// on the coefficients of the whole channel. The trellis search estimates the bits with the
+    /// AC tables of the rate tables.
+    fn block_quantizers(
+        &self,
+        quantization_table_pair: &QuantizationTablePair,
+        rate_tables: &HuffmanTables,
+    ) -> CombinedColorChannels<BlockQuantizer> {
+        let trellis_quantization = self.options.trellis_quantization;
+        let dead_zone = self.options.dead_zone;
+        let block_quantizer = |quantization_table, ac_code_lengths: &[SymbolCodeLength]| {
+            let mut block_quantizer = BlockQuantizer::new(quantization_table);
+            if trellis_quantization {
+                block_quantizer =
+                    block_quantizer.with_trellis(RateModel::from_code_lengths(ac_code_lengths));
+            }
+            if let Some(dead_zone) = dead_zone {
+                block_quantizer = block_quantizer.with_dead_zone(dead_zone);
+            }
+            block_quantizer
+        };
+        CombinedColorChannels {
+            luma: block_quantizer(quantization_table_pair.luma_table, &rate_tables.luma_ac),
+            chroma_red: block_quantizer(
+                *quantization_table_pair.chroma_red_table(),
+                &rate_tables.chroma_ac,
+            ),
+            chroma_blue: block_quantizer(
+                quantization_table_pair.chroma_table,
+                &rate_tables.chroma_ac,
+            ),
+            key: block_quantizer(quantization_table_pair.luma_table, &rate_tables.luma_ac),
+        }
+    }
+
+    /// Tables, with which the trellis search estimates the bits of the coefficients. They are
+    /// the tables, the image is written with. Standard and imported tables are known beforehand.
+    /// Optimized tables depend on the chosen coefficients, so `count_symbols` counts the symbols
+    /// of a first pass with the standard tables, and the pass, which is written, uses the tables
+    /// optimized for the first pass. The written tables are optimized for the second pass, so
+    /// its estimate is close, but not exact.
+    fn rate_tables(
+        &self,
+        count_symbols: impl FnOnce(&HuffmanTables) -> SymbolCounters,
+    ) -> HuffmanTables {
+        let standard_tables = standard_huffman_tables(self.options.grayscale);
+        match &self.options.huffman_tables {
+            Some(tables) => tables.clone(),
+            None if self.options.trellis_quantization
+                && self.options.used_huffman_table_mode() == HuffmanTableMode::Optimized =>
+            {
+                self.huffman_code_lengths(Some(&count_symbols(&standard_tables)))
+            }
+            None => standard_tables,
+        }
+    }
+
+    fn quantize_all_channels<'b, S: Sample>(
+        &self,
+        channels: &'b SeparateColorChannels<S>,
+        quantization_table_pair: &QuantizationTablePair,
+        rate_tables: &HuffmanTables,
+    ) -> CombinedColorChannels<impl Iterator<Item = FrequencyBlock<i16>> + use<'b, S, P>> {
+        let adaptive_quantization = self.options.adaptive_quantization;
+        let quantizer = |channel, block_quantizer| {
+            let mut quantizer = Quantizer::new(channel, block_quantizer);
+            if let Some(strength) = adaptive_quantization {
+                quantizer = quantizer.with_adaptive_quantization(strength);
+            }
+            quantizer
+        };
+        let block_quantizers = self.block_quantizers(quantization_table_pair, rate_tables);
+        CombinedColorChannels {
+            luma: quantizer(&channels.luma, block_quantizers.luma).quantize_channel(),
+            chroma_red: quantizer(&channels.chroma_red, block_quantizers.chroma_red)
+                .quantize_channel(),
+            chroma_blue: quantizer(&channels.chroma_blue, block_quantizers.chroma_blue)
+                .quantize_channel(),
+            key: quantizer(&channels.key, block_quantizers.key).quantize_channel(),
+        }
+    }
+
+    /// Streams the bands of the image through all stages up to the categorization, see
+    /// [`transform_and_categorize_blocks`]. The bands are yielded in the order of the image,
+    /// their blocks entangled into the order of the scan and their DC values predicted from the
+    /// previous band. The samples and coefficients of a band are dropped with its job, so only
+    /// the bands in flight and the bands, the caller holds, are kept. It can't be used with
+    /// adaptive quantization, which needs the coefficients of the whole channel first. The
+    /// stages record their timings into `stage_timings`.
+    fn categorized_bands<S: Sample>(
+        &self,
+        quantization_table_pair: &QuantizationTablePair,
+        rate_tables: &HuffmanTables,
+        stage_timings: Arc<StageTimings>,
+    ) -> impl Iterator<Item = CategorizedBand> + use<'a, S, P> {
+        let dct_algorithm = self.options.dct_algorithm;
+        let block_quantizers = self.block_quantizers(quantization_table_pair, rate_tables);
+        let stages = Arc::new(BandStages::new(
+            self.options,
+            &self.image,
+            Arc::clone(&stage_timings),
+        ));
+        let line_length = self.image.padded_width as usize / 8;
+        let subsampling_preset = self.options.frame_subsampling_preset();
+        let restart_intervals = self.blocks_per_restart_interval();
+        let mut dc_predictors = CombinedColorChannels {
+            luma: DcPredictor::new(restart_intervals.luma),
+            chroma_red: DcPredictor::new(restart_intervals.chroma_red),
+            chroma_blue: DcPredictor::new(restart_intervals.chroma_blue),
+            key: DcPredictor::new(restart_intervals.key),
+        };
+        stages
+            .run(
+                self.image,
+                self.threadpool,
+                move |channels: SeparateColorChannels<S>| {
+                    let stage = |mut channel: ColorChannel<S>, block_quantizer| {
+                        let blocks = transform_and_categorize_blocks(
+                            &mut channel.dots,
+                            dct_algorithm,
+                            block_quantizer,
+                            &stage_timings,
+                        );
+                        S::buffer_pool().put_back(channel.dots);
+                        blocks
+                    };
+                    CombinedColorChannels {
+                        luma: stage(channels.luma, &block_quantizers.luma),
+                        chroma_red: stage(channels.chroma_red, &block_quantizers.chroma_red),
+                        chroma_blue: stage(channels.chroma_blue, &block_quantizers.chroma_blue),
+                        key: stage(channels.key, &block_quantizers.key),
+                    }
+                },
+            )
+            .map(move |band| {
+                let partially_categorized_band = CombinedColorChannels {
+                    luma: band.luma.into_iter(),
+                    chroma_red: band.chroma_red.into_iter(),
+                    chroma_blue: band.chroma_blue.into_iter(),
+                    key: band.key.into_iter(),
+                };
+                let entangled_band =
+                    entangle_channels(partially_categorized_band, line_length, subsampling_preset);
+                CombinedColorChannels {
+                    luma: dc_predictors.luma.categorize_dc(entangled_band.luma),
+                    chroma_red: dc_predictors
+                        .chroma_red
+                        .categorize_dc(entangled_band.chroma_red),
+                    chroma_blue: dc_predictors
+                        .chroma_blue
+                        .categorize_dc(entangled_band.chroma_blue),
+                    key: dc_predictors.key.categorize_dc(entangled_band.key),
+                }
+            })
+    }
+
+    /// Number of blocks of each channel, after which the DC prediction restarts. The luma and
+    /// the black ink have several blocks in each MCU.
+    fn blocks_per_restart_interval(&self) -> CombinedColorChannels<Option<usize>> {
+        let restart_interval = self.options.restart_interval.map(usize::from);
+        let luma_blocks_per_mcu = self
+            .options
+            .frame_subsampling_preset()
+            .luma_blocks_per_mcu();
+        let luma_restart_interval = restart_interval.map(|mcus| mcus * luma_blocks_per_mcu);
+        CombinedColorChannels {
+            luma: luma_restart_interval,
+            chroma_red: restart_interval,
+            chroma_blue: restart_interval,
+            key: luma_restart_interval,
+        }
+    }
+
+    /// Categorizes the entangled blocks of each channel, whose DC prediction restarts with each
+    /// restart interval.
+    fn categorize_all_channels(
+        &self,
+        quantized_channels: CombinedColorChannels<EntangledBlocks<'_, FrequencyBlock<i16>>>,
+    ) -> CategorizedBand {
+        let restart_intervals = self.blocks_per_restart_interval();
+        CombinedColorChannels {
+            luma: categorize::categorize_channel(quantized_channels.luma, restart_intervals.luma),
+            chroma_red: categorize::categorize_channel(
+                quantized_channels.chroma_red,
+                restart_intervals.chroma_red,
+            ),
+            chroma_blue: categorize::categorize_channel(
+                quantized_channels.chroma_blue,
+                restart_intervals.chroma_blue,
+            ),
+            key: categorize::categorize_channel(quantized_channels.key, restart_intervals.key),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.image.width == 0 || self.image.height == 0 {
+            return Err(Error::EmptyImage(self.image.width, self.image.height));
+        }
+        let sample_precision = self.options.bits_per_channel;
+        if sample_precision != BASELINE_SAMPLE_PRECISION
+            && sample_precision != EXTENDED_SAMPLE_PRECISION
+        {
+            return Err(Error::UnsupportedSamplePrecision(sample_precision));
+        }
+        if P::HAS_KEY && self.options.grayscale {
+            return Err(Error::GrayscaleCMYKImage);
+        }
+        let max_huffman_code_length = self.options.max_huffman_code_length;
+        if !(MIN_HUFFMAN_CODE_LENGTH_LIMIT..=MAX_HUFFMAN_CODE_LENGTH)
+            .contains(&max_huffman_code_length)
+        {
+            return Err(Error::InvalidMaxHuffmanCodeLength(max_huffman_code_length));
+        }
+        if let Some(comment) = self
+            .options
+            .comments
+            .iter()
+            .find(|comment| comment.len() > MAX_COMMENT_LENGTH)
+        {
+            return Err(Error::CommentTooLong(comment.len()));
+        }
+        Ok(())
+    }
+
+    pub fn transform(self) -> Result<StreamedOutputImage<'a>> {
+        self.validate()?;
+        if self.options.high_precision {
+            self.transform_samples::<f64>()
+        } else {
+            self.transform_samples::<f32>()
+        }
+    }
+
+    /// Searches the highest quality, whose encoded image does not exceed the target size. The
+    /// expensive stages up to the DCT run once, only quantization and entropy coding are
+    /// repeated for each probed quality. The `measure` closure returns the size of the complete
+    /// encoded image. Quality 1 is returned, if even that exceeds the target size.
+    pub(super) fn transform_to_target_size(
+        self,
+        target_size: u64,
+        measure: impl FnMut(&mut OutputImage) -> Result<u64>,
+    ) -> Result<SizedOutputImage> {
+        self.validate()?;
+        if self.options.high_precision {
+            self.search_target_size::<f64>(target_size, measure)
+        } else {
+            self.search_target_size::<f32>(target_size, measure)
+        }
+    }
+
+    /// Encodes the image with the fused stage of DCT, quantization and categorization, whose
+    /// bands are written, while they are produced. If the Huffman tables or the symbol
+    /// statistics depend on the symbols, a first pass counts them. Its bands are kept for the
+    /// scan up to [`MAX_KEPT_BYTES`], larger images are streamed a second time. The
+    /// adaptive quantization needs the coefficients of whole channels, so its image is held as a
+    /// whole.
+    fn transform_samples<S: Sample>(self) -> Result<StreamedOutputImage<'a>> {
+        let quality = self.options.quality;
+        if self.options.adaptive_quantization.is_some() {
+            let color_channels = self.transform_coefficients::<S>();
+            let output_image = self.encode_coefficients(&color_channels, quality);
+            put_back_channels(color_channels);
+            return Ok(StreamedOutputImage {
+                output_image,
+                scan_bands: None,
+            });
+        }
+        let quantization_table_pair = self.quantization_table_pair_of(quality);
+        let count_symbols_of_bands = |rate_tables: &HuffmanTables, keep_bands| {
+            let pass_timings = Arc::new(StageTimings::new());
+            let bands = self.categorized_bands::<S>(
+                &quantization_table_pair,
+                rate_tables,
+                Arc::clone(&pass_timings),
+            );
+            let (symbol_counters, kept_bands) = self.count_symbols_of_bands(bands, keep_bands);
+            // the stages of a pass, whose bands are dropped, run once more for the scan
+            if kept_bands.is_some() {
+                self.stage_timings.add(&pass_timings);
+            } else {
+                self.stage_timings.add_repeated_pass(&pass_timings);
+            }
+            (symbol_counters, kept_bands)
+        };
+        let rate_tables =
+            self.rate_tables(|rate_tables| count_symbols_of_bands(rate_tables, false).0);
+        let (symbol_counters, kept_bands) = if self.counts_symbols() {
+            let (symbol_counters, kept_bands) = count_symbols_of_bands(&rate_tables, true);
+            (Some(symbol_counters), kept_bands)
+        } else {
+            (None, None)
+        };
+        let scan_bands: ScanBands<'a> = match kept_bands {
+            Some(bands) => Box::new(bands.into_iter()),
+            None => Box::new(self.categorized_bands::<S>(
+                &quantization_table_pair,
+                &rate_tables,
+                Arc::clone(&self.stage_timings),
+            )),
+        };
+        let output_image = self.output_image(
+            empty_band(),
+            symbol_counters.as_ref(),
+            quantization_table_pair,
+            quality,
+        );
+        Ok(StreamedOutputImage {
+            output_image,
+            scan_bands: Some(scan_bands),
+        })
+    }
+
+    fn search_target_size<S: Sample>(
+        self,
+        target_size: u64,
+        mut measure: impl FnMut(&mut OutputImage) -> Result<u64>,
+    ) -> Result<SizedOutputImage> {
+        let color_channels = self.transform_coefficients::<S>();
+        let mut encode = |quality| -> Result<SizedOutputImage> {
+            let mut output_image = self.encode_coefficients(&color_channels, Some(quality));
+            let size = measure(&mut output_image)?;
+            Ok(SizedOutputImage {
+                output_image,
+                quality,
+                size,
+            })
+        };
+        // the size grows with the quality, so a binary search finds the highest fitting quality
+        let (mut lowest, mut highest) = (1, 100);
+        let mut best = None;
+        let mut last_exceeding = None;
+        while lowest <= highest {
+            let quality = (lowest + highest) / 2;
+            let sized_output_image = encode(quality)?;
+            if sized_output_image.size <= target_size {
+                lowest = quality + 1;
+                best = Some(sized_output_image);
+            } else {
+                highest = quality - 1;
+                last_exceeding = Some(sized_output_image);
+            }
+        }
+        put_back_channels(color_channels);
+        // without a fitting quality the search ends with quality 1
+        Ok(best
+            .or(last_exceeding)
+            .expect("search probes at least one quality"))
+    }
+
+    /// Runs the stages up to the level shift, which prepare the blocks of samples for the DCT,
+    /// on the bands of the image and concatenates the blocks of the bands to whole channels.
+    fn level_shifted_channels<S: Sample>(&self) -> SeparateColorChannels<S> {
+        let stages = Arc::new(BandStages::new(
+            self.options,
+            &self.image,
+            Arc::clone(&self.stage_timings),
+        ));
+        let bands = stages
+            .run(
+                self.image,
+                self.threadpool,
+                |channels: SeparateColorChannels<S>| CombinedColorChannels {
+                    luma: channels.luma.dots,
+                    chroma_red: channels.chroma_red.dots,
+                    chroma_blue: channels.chroma_blue.dots,
+                    key: channels.key.dots,
+                },
+            )
+            .collect();
+        let channels = concatenate_pooled_bands(bands);
+        let width = self.image.padded_width;
+        let height = self.image.padded_height;
+        SeparateColorChannels {
+            luma: ColorChannel::new(width, height, channels.luma),
+            chroma_red: ColorChannel::new(width, height, channels.chroma_red),
+            chroma_blue: ColorChannel::new(width, height, channels.chroma_blue),
+            key: ColorChannel::new(width, height, channels.key),
+        }
+    }
+
+    /// Runs the stages up to the DCT, which don't depend on the quantization tables.
+    fn transform_coefficients<S: Sample>(&self) -> SeparateColorChannels<S> {
+        let mut color_channels = self.level_shifted_channels::<S>();
+        self.apply_cosine_transform_on_all_channels_in_place(&mut color_channels);
+        color_channels
+    }
+
+    /// Tables of the quality. The tables of the preset are used unchanged without a quality.
+    fn quantization_table_pair_of(&self, quality: Option<u8>) -> QuantizationTablePair {
+        match quality {
+            Some(quality) => self.options.quantization_table_pair_of_quality(quality),
+            None => self.options.quantization_table_pair(),
+        }
+    }
+
+    /// Quantizes the coefficients with the tables of the quality and codes them into the output
+    /// image. The tables of the preset are used unchanged without a quality.
+    fn encode_coefficients<S: Sample>(
+        &self,
+        color_channels: &SeparateColorChannels<S>,
+        quality: Option<u8>,
+    ) -> OutputImage {
+        let quantization_table_pair = self.quantization_table_pair_of(quality);
+        let bytes = [
+            &color_channels.luma,
+            &color_channels.chroma_red,
+            &color_channels.chroma_blue,
+            &color_channels.key,
+        ]
+        .iter()
+        .map(|channel| size_of_val(channel.dots.as_slice()) as u64)
+        .sum();
+        let categorize = |rate_tables: &HuffmanTables| {
+            self.stage_timings.measure(Stage::Quantization, bytes, || {
+                let quantized_channels = self.quantize_all_channels(
+                    color_channels,
+                    &quantization_table_pair,
+                    rate_tables,
+                );
+                let entangled_channels = entangle_channels(
+                    quantized_channels,
+                    self.image.padded_width as usize / 8,
+                    self.options.frame_subsampling_preset(),
+                );
+                self.categorize_all_channels(entangled_channels)
+            })
+        };
+        let rate_tables = self
+            .rate_tables(|rate_tables| self.count_symbols_of_channels(categorize(rate_tables)).1);
+        let categorized_channels = categorize(&rate_tables);
+        let (categorized_channels, symbol_counters) = if self.counts_symbols() {
+            let (categorized_channels, symbol_counters) =
+                self.count_symbols_of_channels(categorized_channels);
+            (categorized_channels, Some(symbol_counters))
+        } else {
+            (categorized_channels, None)
+        };
+        self.output_image(
+            categorized_channels,
+            symbol_counters.as_ref(),
+            quantization_table_pair,
+            quality,
+        )
+    }
+
+    /// Whether the Huffman tables or the symbol statistics depend on the symbols of the image.
+    /// Imported tables depend on them, as they are replaced, if they lack a symbol.
+    fn counts_symbols(&self) -> bool {
+        self.options.huffman_tables.is_some()
+            || self.options.used_huffman_table_mode() == HuffmanTableMode::Optimized
+            || self.options.collect_symbol_stats
+    }
+
+    /// Counts the symbols of each channel of the whole image on the threadpool.
+    fn count_symbols_of_channels(
+        &self,
+        categorized_channels: CategorizedBand,
+    ) -> (CategorizedBand, SymbolCounters) {
+        let bytes = (number_of_blocks(&categorized_channels) * 64 * size_of::<i16>()) as u64;
+        // the channels are shared with the counting jobs on the threadpool
+        let shared_channels = CombinedColorChannels {
+            luma: Arc::new(categorized_channels.luma),
+            chroma_red: Arc::new(categorized_channels.chroma_red),
+            chroma_blue: Arc::new(categorized_channels.chroma_blue),
+            key: Arc::new(categorized_channels.key),
+        };
+        let symbol_counters = self
+            .stage_timings
+            .measure(Stage::HuffmanGeneration, bytes, || {
+                let count = |blocks| SymbolCounter::count_on_threadpool(&[blocks], self.threadpool);
+                CombinedColorChannels {
+                    luma: count(&shared_channels.luma),
+                    chroma_red: count(&shared_channels.chroma_red),
+                    chroma_blue: count(&shared_channels.chroma_blue),
+                    key: count(&shared_channels.key),
+                }
+            });
+        let unshare = |blocks: Arc<Vec<CategorizedBlock>>| {
+            Arc::try_unwrap(blocks)
+                .unwrap_or_else(|_| unreachable!("Counting jobs release the channels"))
+        };
+        let categorized_channels = CombinedColorChannels {
+            luma: unshare(shared_channels.luma),
+            chroma_red: unshare(shared_channels.chroma_red),
+            chroma_blue: unshare(shared_channels.chroma_blue),
+            key: unshare(shared_channels.key),
+        };
+        (categorized_channels, symbol_counters)
+    }
+
+    /// Counts the symbols of the bands, one after another. If `keep_bands` is set, the counted
+    /// bands are returned for the scan, as long as they take at most [`MAX_KEPT_BYTES`].
+    /// Otherwise each band is dropped, as soon as it is counted.
+    fn count_symbols_of_bands(
+        &self,
+        bands: impl Iterator<Item = CategorizedBand>,
+        keep_bands: bool,
+    ) -> (SymbolCounters, Option<Vec<CategorizedBand>>) {
+        let mut symbol_counters = CombinedColorChannels {
+            luma: SymbolCounter::new(),
+            chroma_red: SymbolCounter::new(),
+            chroma_blue: SymbolCounter::new(),
+            key: SymbolCounter::new(),
+        };
+        let mut kept_bands = keep_bands.then(Vec::new);
+        let mut kept_bytes = 0;
+        for band in bands {
+            let band_blocks = number_of_blocks(&band);
+            let bytes = (band_blocks * 64 * size_of::<i16>()) as u64;
+            self.stage_timings
+                .measure(Stage::HuffmanGeneration, bytes, || {
+                    symbol_counters.luma.count_blocks(&band.luma);
+                    symbol_counters.chroma_red.count_blocks(&band.chroma_red);
+                    symbol_counters.chroma_blue.count_blocks(&band.chroma_blue);
+                    symbol_counters.key.count_blocks(&band.key);
+                });
+            if let Some(bands) = &mut kept_bands {
+                kept_bytes += band_heap_size(&band);
+                if kept_bytes <= MAX_KEPT_BYTES {
+                    bands.push(band);
+                } else {
+                    kept_bands = None;
+                }
+            }
+        }
+        (symbol_counters, kept_bands)
+    }
+
+    /// Codes the categorized blocks into the output image. The blocks are empty, if the scan is
+    /// written from bands. The Huffman tables and the symbol statistics are derived from the
+    /// symbol counters, which are set, if [`Self::counts_symbols`].
+    fn output_image(
+        &self,
+        categorized_channels: CategorizedBand,
+        symbol_counters: Option<&SymbolCounters>,
+        quantization_table_pair: QuantizationTablePair,
+        quality: Option<u8>,
+    ) -> OutputImage {
+        let counted_symbols = || symbol_counters.expect("Symbols of the image are counted");
+        let imported_huffman_tables = self.options.huffman_tables.as_ref();
+        let incomplete_huffman_tables = imported_huffman_tables
+            .is_some_and(|tables| !self.huffman_tables_cover(tables, counted_symbols()));
+        let huffman_tables = match imported_huffman_tables {
+            Some(tables) if !incomplete_huffman_tables => tables.clone(),
+            _ => self.huffman_code_lengths(symbol_counters),
+        };
+        let symbol_stats = self
+            .options
+            .collect_symbol_stats
+            .then(|| self.symbol_stats_csv(counted_symbols()));
+
+        OutputImage {
+            width: self.image.width,
+            height: self.image.height,
+            chroma_subsampling_preset: self.options.frame_subsampling_preset(),
+            grayscale: self.options.grayscale,
+            cmyk: P::HAS_KEY,
+            bits_per_channel: self.options.bits_per_channel,
+            huffman_tables,
+            incomplete_huffman_tables,
+            blockwise_image_data: categorized_channels,
+            quantization_table_pair,
+            exif_metadata: self.options.exif_metadata.clone(),
+            xmp_packet: self.options.xmp_packet.clone(),
+            thumbnail: None,
+            pixel_density: self.options.pixel_density,
+            adobe_marker: self.options.adobe_marker,
+            application_segments: Vec::new(),
+            define_number_of_lines: self.options.define_number_of_lines,
+            combine_table_segments: self.options.combine_table_segments,
+            comments: self.options.written_comments(),
+            restart_interval: self.options.restart_interval,
+            settings_comment: self
+                .options
+                .embed_settings
+                .then(|| self.options.settings_json_of_quality(quality)),
+            symbol_stats,
+        }
+    }
+
+    /// Whether the tables have a code word for each counted symbol of the components, which
+    /// they code, and no code word exceeds the maximum code length.
+    fn huffman_tables_cover(
+        &self,
+        huffman_tables: &HuffmanTables,
+        symbol_counters: &SymbolCounters,
+    ) -> bool {
+        let max_code_length = self.options.max_huffman_code_length as usize;
+        let within_limit = [
+            &huffman_tables.luma_ac,
+            &huffman_tables.luma_dc,
+            &huffman_tables.chroma_ac,
+            &huffman_tables.chroma_dc,
+        ]
+        .into_iter()
+        .flatten()
+        .all(|code_length| code_length.length <= max_code_length);
+        let (luma_counter, chroma_counter) = table_symbol_counters(symbol_counters);
+        within_limit
+            && luma_counter.covered_by(&huffman_tables.luma_ac, &huffman_tables.luma_dc)
+            && (self.options.grayscale
+                || chroma_counter.covered_by(&huffman_tables.chroma_ac, &huffman_tables.chroma_dc))
+    }
+
+    /// Code lengths of the luma AC, luma DC, chroma AC and chroma DC Huffman tables. Optimized
+    /// tables are generated from the counted symbols, standard tables need no counters.
+    fn huffman_code_lengths(&self, symbol_counters: Option<&SymbolCounters>) -> HuffmanTables {
+        if self.options.used_huffman_table_mode() == HuffmanTableMode::Standard {
+            return standard_huffman_tables(self.options.grayscale);
+        }
+        let symbol_counters = symbol_counters.expect("Symbols are counted for optimized tables");
+        self.stage_timings.measure(Stage::HuffmanGeneration, 0, || {
+            let max_code_length = self.options.max_huffman_code_length as usize;
+            let (luma_counter, chroma_counter) = table_symbol_counters(symbol_counters);
+            let luma_count = HuffmanCount::from(&luma_counter);
+            let (chroma_ac_huffman, chroma_dc_huffman) = if self.options.grayscale {
+                (Vec::new(), Vec::new())
+            } else {
+                let chroma_count = HuffmanCount::from(&chroma_counter);
+                (
+                    chroma_count.generate_ac_huffman_code(max_code_length),
+                    chroma_count.generate_dc_huffman_code(max_code_length),
+                )
+            };
+            HuffmanTables {
+                luma_ac: luma_count.generate_ac_huffman_code(max_code_length),
+                luma_dc: luma_count.generate_dc_huffman_code(max_code_length),
+                chroma_ac: chroma_ac_huffman,
+                chroma_dc: chroma_dc_huffman,
+            }
+        })
+    }
+
+    /// Symbol frequencies of each component as CSV. The chroma components share their Huffman
+    /// tables, but are counted separately to compare their distributions.
+    fn symbol_stats_csv(&self, symbol_counters: &SymbolCounters) -> String {
+        let mut csv = String::from(SYMBOL_STATS_CSV_HEADER);
+        let components = [
+            ("Y", &symbol_counters.luma),
+            ("Cb", &symbol_counters.chroma_blue),
+            ("Cr", &symbol_counters.chroma_red),
+            ("K", &symbol_counters.key),
+        ];
+        let number_of_components = if P::HAS_KEY { 4 } else { 3 };
+        for (component, counter) in components.into_iter().take(number_of_components) {
+            HuffmanCount::from(counter).append_csv_rows(component, &mut csv);
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, sync::Arc};
+
+    use threadpool::ThreadPool;
+
+    use super::{
+        block_entangler::entangle_channels, categorize::CategorizedBlock, standard_huffman_tables,
+        CategorizedBand, StageTimings, Transformer,
+    };
+    use crate::{
+        cosine_transform::DCTAlgorithm,
+        image::{
+            subsampling::ChromaSubsamplingPreset,
+            writer::jpeg::{HuffmanTableMode, JpegTransformationOptions},
+        },
+        image::{CMYKImage, Image},
+        BitPattern,
+    };
+
+    /// Colors with the YCbCr samples, libjpeg converts them to with its fixed point tables.
+    const LIBJPEG_SAMPLES: [([u8; 3], [i32; 3]); 5] = [
+        ([0, 0, 0], [0, 128, 128]),
+        ([255, 255, 255], [255, 128, 128]),
+        ([128, 128, 128], [128, 128, 128]),
+        ([255, 0, 0], [76, 85, 255]),
+        ([0, 0, 255], [29, 255, 107]),
+    ];
+
+    /// DC term of a block of a single sample, as libjpeg computes it without quantization.
+    fn reference_dc(sample: i32, sample_precision: u8) -> f64 {
+        8.0 * (sample - (1 << (sample_precision - 1))) as f64
+    }
+
+    /// DC terms of the luma, chroma blue and chroma red channel in the sample type of the
+    /// precision.
+    fn dc_terms(transformer: &Transformer, high_precision: bool) -> [f64; 3] {
+        if high_precision {
+            let channels = transformer.transform_coefficients::<f64>();
+            [&channels.luma, &channels.chroma_blue, &channels.chroma_red]
+                .map(|channel| channel.dots[0])
+        } else {
+            let channels = transformer.transform_coefficients::<f32>();
+            [&channels.luma, &channels.chroma_blue, &channels.chroma_red]
+                .map(|channel| channel.dots[0] as f64)
+        }
+    }
+
+    #[test]
+    fn level_shift_dc_terms_like_reference_encoder() {
+        let threadpool = ThreadPool::new(2);
+        for &dct_algorithm in DCTAlgorithm::variants() {
+            for (high_precision, bits_per_channel) in [(false, 8), (true, 8), (false, 12)] {
+                let options = JpegTransformationOptions {
+                    chroma_subsampling_preset: ChromaSubsamplingPreset::P444,
+                    dct_algorithm,
+                    high_precision,
+                    bits_per_channel,
+                    ..Default::default()
+                };
+                // 12 bit samples are the 8 bit samples of libjpeg scaled by 16
+                let scale = 1 << (bits_per_channel - 8);
+                for (rgb, samples) in LIBJPEG_SAMPLES {
+                    let image = Image::from_rgb8(8, 8, &rgb.repeat(64)).unwrap();
+                    let transformer = Transformer::new(&image, &options, &threadpool);
+                    let dc_terms = dc_terms(&transformer, high_precision);
+                    for (dc_term, sample) in dc_terms.into_iter().zip(samples) {
+                        let expected = reference_dc(sample * scale, bits_per_channel);
+                        // the conversion of libjpeg may round to the other neighbour
+                        let tolerance = 8.0 * scale as f64 + 0.5;
+                        assert!(
+                            (dc_term - expected).abs() <= tolerance,
+                            "DC {} of {:?} with {:?} at {} bits with high precision {} is not {}",
+                            dc_term,
+                            rgb,
+                            dct_algorithm,
+                            bits_per_channel,
+                            high_precision,
+                            expected
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn estimate_trellis_bits_with_tables_of_output() {
+        let threadpool = ThreadPool::new(2);
+        let samples: Vec<u8> = (0..32 * 32 * 3)
+            .map(|index| (index * 7 % 251) as u8)
+            .collect();
+        let image = Image::from_rgb8(32, 32, &samples).unwrap();
+        let imported_tables = standard_huffman_tables(false);
+        // rate tables with the tables of the counting passes
+        let rate_tables_of_passes = |options: &JpegTransformationOptions| {
+            let transformer = Transformer::new(&image, options, &threadpool);
+            let pair = options.quantization_table_pair();
+            let passes = RefCell::new(Vec::new());
+            let rate_tables = transformer.rate_tables(|rate_tables| {
+                passes.borrow_mut().push(rate_tables.clone());
+                let bands = transformer.categorized_bands::<f32>(
+                    &pair,
+                    rate_tables,
+                    Arc::new(StageTimings::new()),
+                );
+                transformer.count_symbols_of_bands(bands, false).0
+            });
+            (rate_tables, passes.into_inner())
+        };
+        let trellis_options = || JpegTransformationOptions {
+            trellis_quantization: true,
+            ..Default::default()
+        };
+        let (rate_tables, passes) = rate_tables_of_passes(&trellis_options());
+        assert_eq!(passes, [standard_huffman_tables(false)]);
+        assert_ne!(rate_tables, passes[0]);
+        for options in [
+            JpegTransformationOptions {
+                huffman_table_mode: HuffmanTableMode::Standard,
+                ..trellis_options()
+            },
+            JpegTransformationOptions {
+                huffman_tables: Some(imported_tables.clone()),
+                ..trellis_options()
+            },
+        ] {
+            let (rate_tables, passes) = rate_tables_of_passes(&options);
+            assert_eq!(rate_tables, imported_tables);
+            assert!(passes.is_empty());
+        }
+    }
+
+    #[test]
+    fn keep_counted_bands_up_to_memory_limit() {
+        let threadpool = ThreadPool::new(2);
+        let options = JpegTransformationOptions::default();
+        let pair = options.quantization_table_pair();
+        let rate_tables = standard_huffman_tables(false);
+        let kept_bands = |size: u16| {
+            let samples: Vec<u8> = (0..size as usize * size as usize * 3)
+                .map(|index: usize| (index.wrapping_mul(2_654_435_761) >> 13) as u8)
+                .collect();
+            let image = Image::from_rgb8(size, size, &samples).unwrap();
+            let transformer = Transformer::new(&image, &options, &threadpool);
+            let bands = transformer.categorized_bands::<f32>(
+                &pair,
+                &rate_tables,
+                Arc::new(StageTimings::new()),
+            );
+            transformer.count_symbols_of_bands(bands, true).1
+        };
+        assert!(kept_bands(64).is_some_and(|bands| !bands.is_empty()));
+        // the 98304 blocks of noise with their tokens exceed the limit
+        assert!(kept_bands(2048).is_none());
+    }
+
+    /// DC symbol, AC symbols and the additional bits of the DC and AC values of a block.
+    type CodedBlock = (u8, Vec<u8>, Vec<Box<[u8]>>);
+
+    fn coded_blocks(blocks: &[CategorizedBlock]) -> Vec<CodedBlock> {
+        blocks
+            .iter()
+            .map(|block| {
+                let categories = std::iter::once(block.dc_category())
+                    .chain(block.iter_ac_categories())
+                    .map(|category| category.to_bytes())
+                    .collect();
+                (
+                    block.dc_symbol(),
+                    block.iter_ac_symbols().collect(),
+                    categories,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stream_bands_like_whole_channels() {
+        let threadpool = ThreadPool::new(2);
+        let samples: Vec<u8> = (0..512 * 512 * 3)
+            .map(|index: usize| (index * 13 % 253) as u8)
+            .collect();
+        let image = Image::from_rgb8(512, 512, &samples).unwrap();
+        let options = JpegTransformationOptions {
+            chroma_subsampling_preset: ChromaSubsamplingPreset::P420,
+            restart_interval: Some(5),
+            ..Default::default()
+        };
+        let transformer = Transformer::new(&image, &options, &threadpool);
+        let pair = options.quantization_table_pair();
+        let rate_tables = standard_huffman_tables(false);
+        let bands: Vec<CategorizedBand> = transformer
+            .categorized_bands::<f32>(&pair, &rate_tables, Arc::new(StageTimings::new()))
+            .collect();
+        assert!(bands.len() > 1);
+
+        let color_channels = transformer.transform_coefficients::<f32>();
+        let quantized_channels =
+            transformer.quantize_all_channels(&color_channels, &pair, &rate_tables);
+        let entangled_channels = entangle_channels(
+            quantized_channels,
+            image.width as usize / 8,
+            options.frame_subsampling_preset(),
+        );
+        let whole_channels = transformer.categorize_all_channels(entangled_channels);
+        let banded_channel = |channel: fn(&CategorizedBand) -> &Vec<CategorizedBlock>| {
+            bands
+                .iter()
+                .flat_map(|band| coded_blocks(channel(band)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            banded_channel(|band| &band.luma),
+            coded_blocks(&whole_channels.luma)
+        );
+        assert_eq!(
+            banded_channel(|band| &band.chroma_blue),
+            coded_blocks(&whole_channels.chroma_blue)
+        );
+        assert_eq!(
+            banded_channel(|band| &band.chroma_red),
+            coded_blocks(&whole_channels.chroma_red)
+        );
+    }
+
+    #[test]
+    fn level_shift_key_channel() {
+        let threadpool = ThreadPool::new(1);
+        let options = JpegTransformationOptions::default();
+        for (black, expected) in [(0, 1016.0), (255, -1024.0)] {
+            let image = CMYKImage::from_cmyk8(16, 16, &[0, 0, 0, black].repeat(256)).unwrap();
+            let channels = Transformer::from_cmyk_image(&image, &options, &threadpool)
+                .transform_coefficients::<f32>();
+            assert!((channels.key.dots[0] - expected).abs() < 0.5);
+        }
+    }
+}
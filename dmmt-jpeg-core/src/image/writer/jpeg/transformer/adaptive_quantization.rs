@@ -0,0 +1,75 @@
+use super::sample::Sample;
+
+/// Largest factor, the quantization steps of a single block are coarsened by.
+pub const MAX_STEP_SCALE: f64 = 4.0;
+
+/// Doubling the activity of a block raises its step scale by 2^(strength / 6), like six steps of
+/// the quantization parameter double the step size in x264.
+const ACTIVITY_STEPS_PER_DOUBLING: f64 = 6.0;
+
+/// Activity of a block of DCT coefficients, the logarithm of the energy of its AC coefficients.
+/// The energy is proportional to the variance of the samples of the block, which is high for
+/// edges and texture and low for flat areas and gentle gradients.
+fn block_activity<T: Sample>(block: &[T]) -> f64 {
+    let energy: f64 = block[1..]
+        .iter()
+        .map(|&coefficient| Into::<f64>::into(coefficient).powi(2))
+        .sum();
+    (1.0 + energy / 63.0).log2()
+}
+
+/// Factors, the quantization steps of the AC coefficients of each block of the channel are
+/// multiplied with. Blocks, which are more active than the average block of the channel, are
+/// quantized coarser, as the texture masks the error. All other blocks keep the steps of the
+/// table, which are the finest steps a baseline decoder can reconstruct. The scales replace the
+/// values of the buffer, which holds the activities meanwhile.
+pub fn step_scales<T: Sample>(coefficients: &[T], strength: f32, scales: &mut Vec<f64>) {
+    scales.clear();
+    scales.extend(coefficients.chunks_exact(64).map(block_activity));
+    if scales.is_empty() {
+        return;
+    }
+    let mean_activity = scales.iter().sum::<f64>() / scales.len() as f64;
+    for scale in scales.iter_mut() {
+        let exponent = strength as f64 * (*scale - mean_activity) / ACTIVITY_STEPS_PER_DOUBLING;
+        *scale = exponent.exp2().clamp(1.0, MAX_STEP_SCALE);
+    }
+}
+
+/// Quantizes the coefficient to a multiple of the scaled step, given in units of the step of the
+/// table, so the decoder reconstructs it with the unchanged table.
+pub fn quantize_scaled<T: Sample>(coefficient: T, quantization_value: u16, step_scale: f64) -> i16 {
+    let steps = Into::<f64>::into(coefficient) / quantization_value as f64;
+    ((steps / step_scale).round() * step_scale).round() as i16
+}
+
+#[cfg(test)]
+mod test {
+    use super::{quantize_scaled, step_scales, MAX_STEP_SCALE};
+
+    #[test]
+    fn coarsen_active_blocks_only() {
+        let flat = [0.0f32; 64];
+        let mut gradient = [0.0f32; 64];
+        gradient[1] = 40.0;
+        let mut texture = [0.0f32; 64];
+        texture.iter_mut().skip(1).for_each(|value| *value = 200.0);
+        let coefficients: Vec<f32> = [flat, gradient, texture].concat();
+        let mut scales = Vec::new();
+        step_scales(&coefficients, 1.0, &mut scales);
+        assert_eq!(scales[..2], [1.0, 1.0]);
+        assert!(scales[2] > 1.0 && scales[2] <= MAX_STEP_SCALE);
+        step_scales(&coefficients, 0.0, &mut scales);
+        assert_eq!(scales.len(), 3);
+        assert!(scales.iter().all(|&scale| scale == 1.0));
+    }
+
+    #[test]
+    fn quantize_to_multiples_of_scaled_step() {
+        assert_eq!(quantize_scaled(57.0f32, 10, 1.0), 6);
+        assert_eq!(quantize_scaled(57.0f32, 10, 2.0), 6);
+        assert_eq!(quantize_scaled(37.0f32, 10, 2.0), 4);
+        assert_eq!(quantize_scaled(-37.0f32, 10, 2.0), -4);
+        assert_eq!(quantize_scaled(9.0f32, 10, 2.0), 0);
+    }
+}
@@ -0,0 +1,446 @@
+#[cfg(not(feature = "rayon"))]
+use std::{
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    sync::mpsc,
+};
+use std::{marker::PhantomData, ops::Range, sync::Arc};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use threadpool::ThreadPool;
+
+use super::{
+    color_lookup::ColorLookupTable,
+    sample::{Sample, SourceDot},
+    CombinedColorChannels, SeparateColorChannels,
+};
+use crate::{
+    color::{level_shift, ColorMatrix, ColorRange, YCbCrColorFormat, BASELINE_SAMPLE_PRECISION},
+    image::{
+        subsampling::{ChromaSiting, Subsampler, SubsamplingConfig, SubsamplingMethod},
+        writer::jpeg::{
+            padder::PaddedImage,
+            timings::{Stage, StageTimings},
+            JpegTransformationOptions,
+        },
+        ColorChannel,
+    },
+};
+
+/// Number of dots, a band holds at least, unless the image is smaller. Narrow images get bands
+/// of several MCU rows, so the jobs on the threadpool don't get too small.
+const MIN_BAND_DOTS: usize = 1 << 17;
+
+/// Band of whole MCU rows of the padded image. The rows around the band, which the subsampling
+/// filters reach, are read as well, so the band is subsampled like the same rows of the whole
+/// image.
+struct Band {
+    rows: Range<usize>,
+    read_rows: Range<usize>,
+}
+
+/// Stages from the color conversion to the level shift, which run on bands of MCU rows as jobs
+/// on the threadpool. Only the bands in flight hold dots and samples, so the memory of the stages
+/// is bounded independent of the image size. The stages hold no reference to the options, so they
+/// can be shared with the jobs.
+pub(super) struct BandStages<P> {
+    width: usize,
+    height: usize,
+    band_height: usize,
+    /// Number of rows read above and below a band.
+    halo: usize,
+    sample_precision: u8,
+    color_matrix: ColorMatrix,
+    color_range: ColorRange,
+    grayscale: bool,
+    /// Fixed point tables, if all dots are 8 bit RGB samples, which are encoded with baseline
+    /// precision in single precision.
+    lookup_table: Option<ColorLookupTable>,
+    chroma_config: SubsamplingConfig,
+    stage_timings: Arc<StageTimings>,
+    dots: PhantomData<P>,
+}
+
+impl<P: SourceDot> BandStages<P> {
+    pub fn new(
+        options: &JpegTransformationOptions,
+        image: &PaddedImage<'_, P>,
+        stage_timings: Arc<StageTimings>,
+    ) -> Self {
+        let width = image.padded_width as usize;
+        let mcu_height = options
+            .frame_subsampling_preset()
+            .sampling_layout()
+            .mcu_height() as usize;
+        let mcu_rows_per_band = (MIN_BAND_DOTS / (width * mcu_height).max(1)).max(1);
+        let chroma_config = SubsamplingConfig {
+            method: options.used_chroma_subsampling_method(),
+            siting: options.chroma_siting,
+            ..options.chroma_subsampling_preset.into()
+        };
+        let uses_lookup_table = options.bits_per_channel == BASELINE_SAMPLE_PRECISION
+            && !options.high_precision
+            && image
+                .source_dots()
+                .iter()
+                .all(|dot| dot.exact_rgb8().is_some());
+        Self {
+            width,
+            height: image.padded_height as usize,
+            band_height: mcu_rows_per_band * mcu_height,
+            // the kernels reach two areas above and one area below the subsampled area
+            halo: 2 * chroma_config.vertical_rate as usize,
+            sample_precision: options.bits_per_channel,
+            color_matrix: options.color_matrix,
+            color_range: options.color_range,
+            grayscale: options.grayscale,
+            lookup_table: uses_lookup_table
+                .then(|| ColorLookupTable::new(options.color_matrix, options.color_range)),
+            chroma_config,
+            stage_timings,
+            dots: PhantomData,
+        }
+    }
+
+    fn bands(&self) -> impl Iterator<Item = Band> + '_ {
+        (0..self.height)
+            .step_by(self.band_height)
+            .map(|first_row_index| {
+                let rows = first_row_index..(first_row_index + self.band_height).min(self.height);
+                let read_rows =
+                    rows.start.saturating_sub(self.halo)..(rows.end + self.halo).min(self.height);
+                Band { rows, read_rows }
+            })
+    }
+
+    fn convert_color_format<S: Sample>(&self, dot: &P) -> YCbCrColorFormat<S> {
+        let Some(table) = &self.lookup_table else {
+            return dot.to_ycbcr(self.sample_precision, self.color_matrix, self.color_range);
+        };
+        let converted = table.convert(
+            dot.exact_rgb8()
+                .expect("Lookup tables are only used for 8 bit dots"),
+        );
+        let sample = |value: i16| S::from(value as f32);
+        YCbCrColorFormat {
+            luma: sample(converted.luma),
+            chroma_blue: sample(converted.chroma_blue),
+            chroma_red: sample(converted.chroma_red),
+        }
+    }
+
+    /// Converts the dots into channels, whose buffers are taken from the pool.
+    fn split_into_color_channels<S: Sample>(&self, dots: &[P]) -> SeparateColorChannels<S> {
+        let pool = S::buffer_pool();
+        let capacity = dots.len();
+        let chroma_capacity = if self.grayscale { 0 } else { capacity };
+        let mut luma_dots = pool.take(capacity);
+        let mut chroma_red_dots = pool.take(chroma_capacity);
+        let mut chroma_blue_dots = pool.take(chroma_capacity);
+        let mut key_dots = pool.take(if P::HAS_KEY { capacity } else { 0 });
+        for dot in dots {
+            let dot = self.convert_color_format::<S>(dot);
+            luma_dots.push(dot.luma);
+            if !self.grayscale {
+                chroma_red_dots.push(dot.chroma_red);
+                chroma_blue_dots.push(dot.chroma_blue);
+            }
+        }
+        key_dots.extend(
+            dots.iter()
+                .filter_map(|dot| dot.to_key::<S>(self.sample_precision)),
+        );
+        let width = self.width as u16;
+        let height = (dots.len() / self.width) as u16;
+        SeparateColorChannels {
+            luma: ColorChannel::new(width, height, luma_dots),
+            chroma_red: ColorChannel::new(width, height, chroma_red_dots),
+            chroma_blue: ColorChannel::new(width, height, chroma_blue_dots),
+            key: ColorChannel::new(width, height, key_dots),
+        }
+    }
+
+    /// Subsamples the rows of the band from the read rows of the channel and resorts them into
+    /// blocks. The channel keeps the size of the band like the channels of the whole image. The
+    /// buffer of the read rows is put back into the pool.
+    fn subsample_channel<S: Sample>(
+        &self,
+        channel: ColorChannel<S>,
+        band: &Band,
+        config: &SubsamplingConfig,
+    ) -> ColorChannel<S> {
+        let (width, height) = (self.width as u16, band.rows.len() as u16);
+        if channel.dots.is_empty() {
+            return ColorChannel::new(width, height, Vec::new());
+        }
+        let rate = config.vertical_rate as usize;
+        let first_row_index = band.rows.start - band.read_rows.start;
+        let rows = first_row_index / rate..(first_row_index + band.rows.len()) / rate;
+        let pool = S::buffer_pool();
+        let mut dots = pool.take(rows.len() * self.width / config.horizontal_rate as usize);
+        Subsampler::new(&channel, config).subsample_rows_into_square_structure(rows, 8, &mut dots);
+        pool.put_back(channel.dots);
+        ColorChannel::new(width, height, dots)
+    }
+
+    /// The luma and the black ink keep the full resolution.
+    fn subsample_all_channels<S: Sample>(
+        &self,
+        channels: SeparateColorChannels<S>,
+        band: &Band,
+    ) -> SeparateColorChannels<S> {
+        let full_resolution = SubsamplingConfig {
+            horizontal_rate: 1,
+            vertical_rate: 1,
+            method: SubsamplingMethod::Skip,
+            siting: ChromaSiting::Centered,
+        };
+        SeparateColorChannels {
+            luma: self.subsample_channel(channels.luma, band, &full_resolution),
+            chroma_red: self.subsample_channel(channels.chroma_red, band, &self.chroma_config),
+            chroma_blue: self.subsample_channel(channels.chroma_blue, band, &self.chroma_config),
+            key: self.subsample_channel(channels.key, band, &full_resolution),
+        }
+    }
+
+    /// Centers the unsigned samples of every channel around zero, as the DCT of the JPEG
+    /// specification expects. The shift depends on the sample precision only, so the luma, the
+    /// chroma and the black ink are shifted alike.
+    fn level_shift_all_channels<S: Sample>(&self, channels: &mut SeparateColorChannels<S>) {
+        let level_shift = S::from(level_shift(self.sample_precision) as f32);
+        for channel in [
+            &mut channels.luma,
+            &mut channels.chroma_red,
+            &mut channels.chroma_blue,
+            &mut channels.key,
+        ] {
+            for sample in channel.dots.iter_mut() {
+                *sample -= level_shift;
+            }
+        }
+    }
+
+    /// Runs the stages on the dots of the read rows of the band. The level shift prepares the
+    /// samples for the DCT, so its time belongs to the DCT, whose transform counts the bytes.
+    fn level_shifted_band<S: Sample>(&self, dots: &[P], band: &Band) -> SeparateColorChannels<S> {
+        let timings = &self.stage_timings;
+        let channels = timings.measure(Stage::ColorConversion, size_of_val(dots) as u64, || {
+            self.split_into_color_channels(dots)
+        });
+        let bytes = channels_size(&channels);
+        let mut channels = timings.measure(Stage::Subsampling, bytes, || {
+            self.subsample_all_channels(channels, band)
+        });
+        timings.measure(Stage::Dct, 0, || {
+            self.level_shift_all_channels(&mut channels)
+        });
+        channels
+    }
+
+    /// Runs the stages on each band of the padded image and passes the level shifted blocks of
+    /// the band to `finish`, which runs in the same job. The results are yielded in the order of
+    /// the bands, while the following bands are processed. The rows of a band are read from the
+    /// source image, when its job is queued. At most one band per thread and one more are in
+    /// flight, counting the results, which wait for an earlier band, so the memory of the stages
+    /// stays bounded, as long as the caller releases the results it has taken.
+    #[cfg(not(feature = "rayon"))]
+    pub fn run<'a, S: Sample, R: Send + 'static>(
+        self: &Arc<Self>,
+        image: PaddedImage<'a, P>,
+        threadpool: &'a ThreadPool,
+        finish: impl Fn(SeparateColorChannels<S>) -> R + Send + Sync + 'static,
+    ) -> impl Iterator<Item = R> + 'a {
+        let stages = Arc::clone(self);
+        let finish = Arc::new(finish);
+        let max_bands_in_flight = threadpool.max_count() + 1;
+        let (sender, receiver) = mpsc::channel();
+        let mut bands = self.bands().collect::<Vec<_>>().into_iter().enumerate();
+        let mut finished_bands = HashMap::new();
+        let (mut queued_bands, mut yielded_bands) = (0, 0);
+        std::iter::from_fn(move || {
+            while queued_bands - yielded_bands < max_bands_in_flight {
+                let Some((band_index, band)) = bands.next() else {
+                    break;
+                };
+                let mut band_dots = Vec::new();
+                image.read_rows(band.read_rows.clone(), &mut band_dots);
+                let stages = Arc::clone(&stages);
+                let finish = Arc::clone(&finish);
+                let sender = sender.clone();
+                threadpool.execute(move || {
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                        let channels = stages.level_shifted_band(&band_dots, &band);
+                        drop(band_dots);
+                        finish(channels)
+                    }));
+                    // the results are dropped, if the caller stops before the last band
+                    let _ = sender.send((band_index, result));
+                });
+                queued_bands += 1;
+            }
+            if yielded_bands == queued_bands {
+                return None;
+            }
+            let result = loop {
+                if let Some(result) = finished_bands.remove(&yielded_bands) {
+                    break result;
+                }
+                let (band_index, result) =
+                    receiver.recv().expect("Jobs of the bands hold a sender");
+                finished_bands.insert(band_index, result);
+            };
+            yielded_bands += 1;
+            // a failed band fails the caller like a band of its own thread
+            Some(result.unwrap_or_else(|payload| panic::resume_unwind(payload)))
+        })
+    }
+
+    /// Runs the stages on each band like the threadpool variant, but as parallel iterator on the
+    /// current rayon pool. The bands are processed in rounds of one band per thread, whose
+    /// results are yielded, before the next round starts. The jobs read their rows themselves.
+    #[cfg(feature = "rayon")]
+    pub fn run<'a, S: Sample, R: Send + 'static>(
+        self: &Arc<Self>,
+        image: PaddedImage<'a, P>,
+        _threadpool: &'a ThreadPool,
+        finish: impl Fn(SeparateColorChannels<S>) -> R + Send + Sync + 'static,
+    ) -> impl Iterator<Item = R> + 'a {
+        let stages = Arc::clone(self);
+        let bands_per_round = rayon::current_num_threads();
+        let mut bands = self.bands().collect::<Vec<_>>().into_iter();
+        std::iter::from_fn(move || {
+            let round: Vec<Band> = bands.by_ref().take(bands_per_round).collect();
+            (!round.is_empty()).then(|| {
+                round
+                    .into_par_iter()
+                    .map(|band| {
+                        let mut band_dots = Vec::new();
+                        image.read_rows(band.read_rows.clone(), &mut band_dots);
+                        finish(stages.level_shifted_band(&band_dots, &band))
+                    })
+                    .collect::<Vec<R>>()
+            })
+        })
+        .flatten()
+    }
+}
+
+/// Number of bytes of the samples of all channels.
+fn channels_size<S>(channels: &SeparateColorChannels<S>) -> u64 {
+    [
+        &channels.luma,
+        &channels.chroma_red,
+        &channels.chroma_blue,
+        &channels.key,
+    ]
+    .iter()
+    .map(|channel| size_of_val(channel.dots.as_slice()) as u64)
+    .sum()
+}
+
+/// Concatenates the samples of each channel of the bands in the order of the bands into buffers
+/// of the pool and puts the buffers of the bands back.
+pub(super) fn concatenate_pooled_bands<S: Sample>(
+    bands: Vec<CombinedColorChannels<Vec<S>>>,
+) -> CombinedColorChannels<Vec<S>> {
+    let pool = S::buffer_pool();
+    let take = |length: fn(&CombinedColorChannels<Vec<S>>) -> usize| {
+        pool.take(bands.iter().map(length).sum())
+    };
+    let mut channels = CombinedColorChannels {
+        luma: take(|band| band.luma.len()),
+        chroma_red: take(|band| band.chroma_red.len()),
+        chroma_blue: take(|band| band.chroma_blue.len()),
+        key: take(|band| band.key.len()),
+    };
+    for band in bands {
+        for (channel, band_channel) in [
+            (&mut channels.luma, band.luma),
+            (&mut channels.chroma_red, band.chroma_red),
+            (&mut channels.chroma_blue, band.chroma_blue),
+            (&mut channels.key, band.key),
+        ] {
+            channel.extend_from_slice(&band_channel);
+            pool.put_back(band_channel);
+        }
+    }
+    channels
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use threadpool::ThreadPool;
+
+    use super::{concatenate_pooled_bands, BandStages, CombinedColorChannels};
+    use crate::{
+        color::RGBColorFormat,
+        image::{
+            subsampling::{ChromaSiting, ChromaSubsamplingPreset, SubsamplingMethod},
+            writer::jpeg::{
+                padder::PaddedImage, transformer::pad_multiples, JpegTransformationOptions,
+            },
+            Image,
+        },
+    };
+
+    /// Concatenated samples of the luma and the chroma channels.
+    fn level_shifted_samples(
+        stages: BandStages<RGBColorFormat<f32>>,
+        image: &PaddedImage,
+        threadpool: &ThreadPool,
+    ) -> Vec<f32> {
+        let bands = Arc::new(stages)
+            .run(*image, threadpool, |channels| CombinedColorChannels {
+                luma: channels.luma.dots,
+                chroma_red: channels.chroma_red.dots,
+                chroma_blue: channels.chroma_blue.dots,
+                key: channels.key.dots,
+            })
+            .collect();
+        let channels = concatenate_pooled_bands(bands);
+        [channels.luma, channels.chroma_red, channels.chroma_blue].concat()
+    }
+
+    #[test]
+    fn subsample_bands_like_whole_image() {
+        let threadpool = ThreadPool::new(3);
+        let (width, height) = (21, 75);
+        let samples: Vec<u8> = (0..width * height * 3)
+            .map(|index| (index * 37 % 251) as u8)
+            .collect();
+        let image = Image::from_rgb8(width as u16, height as u16, &samples).unwrap();
+        for &preset in ChromaSubsamplingPreset::variants() {
+            for &method in SubsamplingMethod::variants() {
+                for &chroma_siting in ChromaSiting::variants() {
+                    let options = JpegTransformationOptions {
+                        chroma_subsampling_preset: preset,
+                        chroma_subsampling_method: Some(method),
+                        chroma_siting,
+                        ..Default::default()
+                    };
+                    let (width_multiple, height_multiple) = pad_multiples(&options);
+                    let image = PaddedImage::new(&image, width_multiple, height_multiple);
+                    let whole_image = BandStages {
+                        band_height: image.padded_height as usize,
+                        ..BandStages::new(&options, &image, Default::default())
+                    };
+                    let bands = BandStages {
+                        band_height: height_multiple as usize,
+                        ..BandStages::new(&options, &image, Default::default())
+                    };
+                    assert_eq!(
+                        level_shifted_samples(bands, &image, &threadpool),
+                        level_shifted_samples(whole_image, &image, &threadpool),
+                        "{:?} with {:?} and {:?}",
+                        preset,
+                        method,
+                        chroma_siting
+                    );
+                }
+            }
+        }
+    }
+}
@@ -2,62 +2,91 @@ use crate::image::subsampling::ChromaSubsamplingPreset;
 
 use super::CombinedColorChannels;
 
-pub fn entangle_channels<'a, U: Copy + 'a, T: Iterator<Item = U> + 'a>(
+pub fn entangle_channels<'a, U: 'a, T: Iterator<Item = U> + 'a>(
     linear_blocks: CombinedColorChannels<T>,
     line_length: usize,
     subsampling_preset: ChromaSubsamplingPreset,
 ) -> CombinedColorChannels<Box<dyn Iterator<Item = U> + 'a>> {
-    match subsampling_preset {
-        ChromaSubsamplingPreset::P444 | ChromaSubsamplingPreset::P422 => CombinedColorChannels {
+    // Luma blocks of a single block row are already in MCU order, more rows have to be folded.
+    // The black ink of CMYK images is folded like the luma.
+    let luma_factors = subsampling_preset.sampling_layout().luma_factors;
+    match luma_factors.vertical {
+        1 => CombinedColorChannels {
             luma: Box::new(linear_blocks.luma),
             chroma_blue: Box::new(linear_blocks.chroma_blue),
             chroma_red: Box::new(linear_blocks.chroma_red),
+            key: Box::new(linear_blocks.key),
         },
-        ChromaSubsamplingPreset::P420 => CombinedColorChannels {
-            luma: Box::new(QuadFoldingIterator::new(linear_blocks.luma, line_length)),
+        vertical => CombinedColorChannels {
+            luma: Box::new(QuadFoldingIterator::new(
+                linear_blocks.luma,
+                line_length,
+                luma_factors.horizontal as usize,
+                vertical as usize,
+            )),
             chroma_blue: Box::new(linear_blocks.chroma_blue),
             chroma_red: Box::new(linear_blocks.chroma_red),
+            key: Box::new(QuadFoldingIterator::new(
+                linear_blocks.key,
+                line_length,
+                luma_factors.horizontal as usize,
+                vertical as usize,
+            )),
         },
     }
 }
 
 /**
- * Iterator to Fold two "lines" of an iterator in a 4x4 quad structure;
+ * Iterator to Fold two or more "lines" of an iterator in a quad structure;
  * Example Use: Reorder the blocks of a subsampled JPEG image for output
  */
 pub struct QuadFoldingIterator<U, T: Iterator<Item = U>> {
     linear_backlog: T,
-    two_line_buffer: Vec<U>,
-    two_line_buffer_index: usize,
+    line_buffer: Vec<Option<U>>,
+    line_buffer_index: usize,
     line_length: usize,
-    two_line_buffer_length: usize,
+    line_buffer_length: usize,
+    /// Number of blocks of a line, which belong to one MCU.
+    mcu_width: usize,
+    /// Number of lines, which are folded into each MCU.
+    number_of_lines: usize,
 }
 
-impl<U: Copy, T: Iterator<Item = U>> QuadFoldingIterator<U, T> {
-    pub fn new(linear_backlog: T, line_length: usize) -> Self {
+impl<U, T: Iterator<Item = U>> QuadFoldingIterator<U, T> {
+    /// Folds MCUs of `mcu_width` blocks in each of the `number_of_lines` lines, like one block
+    /// of 4:4:0 or two blocks of 4:2:0 in each of two lines.
+    pub fn new(
+        linear_backlog: T,
+        line_length: usize,
+        mcu_width: usize,
+        number_of_lines: usize,
+    ) -> Self {
+        let buffer_length = line_length * number_of_lines;
         Self {
             linear_backlog,
-            two_line_buffer: Vec::with_capacity(line_length * 2),
-            two_line_buffer_index: line_length * 2,
+            line_buffer: Vec::with_capacity(buffer_length),
+            line_buffer_index: buffer_length,
             line_length,
-            two_line_buffer_length: line_length * 2,
+            line_buffer_length: buffer_length,
+            mcu_width,
+            number_of_lines,
         }
     }
 
     fn is_buffer_consumed(&self) -> bool {
-        self.two_line_buffer_index >= self.two_line_buffer.len()
+        self.line_buffer_index >= self.line_buffer.len()
     }
 
     fn refill_buffer(&mut self) {
-        self.two_line_buffer_index = 0;
-        self.two_line_buffer.clear();
+        self.line_buffer_index = 0;
+        self.line_buffer.clear();
         let mut items_pushed = 0;
         // Ans: For loops move the iterator (implicit call to into_iter()), which is NOT what
         //      we want here, as only part of the iterator is consumed by early break
         for item in self.linear_backlog.by_ref() {
-            self.two_line_buffer.push(item);
+            self.line_buffer.push(Some(item));
             items_pushed += 1;
-            if items_pushed == self.two_line_buffer_length {
+            if items_pushed == self.line_buffer_length {
                 return;
             }
         }
@@ -67,23 +96,25 @@ impl<U: Copy, T: Iterator<Item = U>> QuadFoldingIterator<U, T> {
     }
 
     fn get_next_block(&mut self) -> U {
-        /*it's a kind of magic MAGIC magic ....*/
-        let on_quad = self.two_line_buffer_index / 4;
-        let line = (self.two_line_buffer_index % 4) / 2;
-        let actual_index =
-            self.two_line_buffer_index - (on_quad + line) * 2 + self.line_length * line;
-        self.two_line_buffer_index += 1;
-        self.two_line_buffer[actual_index]
+        let blocks_per_mcu = self.number_of_lines * self.mcu_width;
+        let mcu = self.line_buffer_index / blocks_per_mcu;
+        let line = self.line_buffer_index % blocks_per_mcu / self.mcu_width;
+        let column = self.line_buffer_index % self.mcu_width;
+        let actual_index = line * self.line_length + mcu * self.mcu_width + column;
+        self.line_buffer_index += 1;
+        self.line_buffer[actual_index]
+            .take()
+            .expect("Each block of the buffer is taken once")
     }
 }
 
-impl<U: Copy, T: Iterator<Item = U>> Iterator for QuadFoldingIterator<U, T> {
+impl<U, T: Iterator<Item = U>> Iterator for QuadFoldingIterator<U, T> {
     type Item = U;
     fn next(&mut self) -> Option<U> {
         if self.is_buffer_consumed() {
             self.refill_buffer();
         }
-        if self.two_line_buffer.is_empty() {
+        if self.line_buffer.is_empty() {
             return None;
         }
         Some(self.get_next_block())
@@ -106,6 +137,7 @@ mod tests {
             luma: luma_sequence.iter(),
             chroma_blue: chroma_blue_sequence.iter(),
             chroma_red: chroma_red_sequence.iter(),
+            key: luma_sequence.iter(),
         };
 
         let entangled_channels = entangle_channels(
@@ -133,13 +165,16 @@ mod tests {
         for (&expect, &got) in expect_cr_sequence.iter().zip(entangled_channels.chroma_red) {
             assert_eq!(expect, got);
         }
+
+        let key_sequence: Vec<u32> = entangled_channels.key.copied().collect();
+        assert_eq!(key_sequence, expect_luma_sequence);
     }
 
     #[test]
     fn entangle_test() {
         let test_sequence: Vec<u32> = vec![0, 1, 4, 5, 2, 3, 6, 7, 8, 9, 12, 13, 10, 11, 14, 15];
         let expect_sequence: Vec<u32> = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
-        let got_sequence = QuadFoldingIterator::new(test_sequence.iter(), 4);
+        let got_sequence = QuadFoldingIterator::new(test_sequence.iter(), 4, 2, 2);
         for (&expect, &got) in expect_sequence.iter().zip(got_sequence) {
             assert_eq!(expect, got);
         }
@@ -152,15 +187,34 @@ mod tests {
         let expect_sequence: Vec<u32> = vec![
             0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23,
         ];
-        let got_sequence = QuadFoldingIterator::new(test_sequence.iter(), 6);
+        let got_sequence = QuadFoldingIterator::new(test_sequence.iter(), 6, 2, 2);
         for (&expect, &got) in expect_sequence.iter().zip(got_sequence) {
             assert_eq!(expect, got);
         }
     }
+    #[test]
+    fn entangle_single_block_columns() {
+        // Two lines of four blocks, each MCU of 4:4:0 holds a block and the one below it
+        let test_sequence: Vec<u32> = vec![0, 2, 4, 6, 1, 3, 5, 7];
+        let got_sequence: Vec<u32> =
+            QuadFoldingIterator::new(test_sequence.into_iter(), 4, 1, 2).collect();
+        assert_eq!(got_sequence, [0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn entangle_three_lines() {
+        // Three lines of four blocks, each MCU of a vertical rate of 3 holds two blocks per line
+        let test_sequence: Vec<u32> = vec![0, 1, 6, 7, 2, 3, 8, 9, 4, 5, 10, 11];
+        let got_sequence: Vec<u32> =
+            QuadFoldingIterator::new(test_sequence.into_iter(), 4, 2, 3).collect();
+        assert_eq!(got_sequence, (0..12).collect::<Vec<u32>>());
+    }
+
     #[test]
     #[should_panic]
     fn panic_test() {
         let test_sequence: Vec<u32> = vec![0, 1, 4, 5, 2, 3, 6, 7, 8, 9, 12, 13];
-        let _got_sequence: Vec<&u32> = QuadFoldingIterator::new(test_sequence.iter(), 4).collect();
+        let _got_sequence: Vec<&u32> =
+            QuadFoldingIterator::new(test_sequence.iter(), 4, 2, 2).collect();
     }
 }
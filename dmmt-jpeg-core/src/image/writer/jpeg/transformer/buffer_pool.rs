@@ -0,0 +1,177 @@
+use std::{
+    mem,
+    ops::{Deref, DerefMut},
+    sync::Mutex,
+};
+
+/// Number of buffers, a pool keeps at most. It is enough for the channels of the bands in flight
+/// on common machines, surplus buffers are freed.
+const MAX_POOLED_BUFFERS: usize = 64;
+
+/// Bytes of all buffers, a pool keeps at most, so it holds the bands in flight, but not the
+/// memory of a huge image after it has been encoded.
+const MAX_POOLED_BYTES: usize = 32 << 20;
+
+/// Bytes of the largest buffer, a pool keeps. A channel of a band of 32 bit samples of the widest
+/// image with four times vertical subsampling fits, the channels of whole images are freed.
+const MAX_POOLED_BUFFER_BYTES: usize = 8 << 20;
+
+/// Pool of buffers, which keeps the memory of the channels between the stages of the
+/// transformer, between the channels and bands and between images, which are encoded one after
+/// another in the same process. The pools are shared by all threads, see
+/// [`super::sample::Sample::buffer_pool`], and [`super::release_buffers`] frees them.
+pub struct BufferPool<T> {
+    buffers: Mutex<Vec<Vec<T>>>,
+}
+
+impl<T> BufferPool<T> {
+    pub const fn new() -> Self {
+        Self {
+            buffers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Takes an empty buffer with at least the capacity. The smallest buffer, which fits, is
+    /// taken, otherwise the largest one grows, so the pool doesn't hold many large buffers.
+    pub fn take(&self, capacity: usize) -> Vec<T> {
+        if capacity == 0 {
+            return Vec::new();
+        }
+        let mut buffers = self.buffers.lock().expect("Buffer pool is poisoned");
+        let fitting = buffers
+            .iter()
+            .enumerate()
+            .filter(|(_, buffer)| buffer.capacity() >= capacity)
+            .min_by_key(|(_, buffer)| buffer.capacity())
+            .map(|(index, _)| index);
+        let largest = || (0..buffers.len()).max_by_key(|&index| buffers[index].capacity());
+        let mut buffer = match fitting.or_else(largest) {
+            Some(index) => buffers.swap_remove(index),
+            None => Vec::new(),
+        };
+        drop(buffers);
+        buffer.reserve(capacity);
+        buffer
+    }
+
+    /// Puts the buffer back into the pool, its values are dropped. The buffer is freed instead,
+    /// if it is larger than [`MAX_POOLED_BUFFER_BYTES`] or the pool is full.
+    pub fn put_back(&self, mut buffer: Vec<T>) {
+        let bytes = Self::bytes(&buffer);
+        if bytes == 0 || bytes > MAX_POOLED_BUFFER_BYTES {
+            return;
+        }
+        buffer.clear();
+        let mut buffers = self.buffers.lock().expect("Buffer pool is poisoned");
+        let pooled_bytes: usize = buffers.iter().map(Self::bytes).sum();
+        if buffers.len() < MAX_POOLED_BUFFERS && pooled_bytes + bytes <= MAX_POOLED_BYTES {
+            buffers.push(buffer);
+        }
+    }
+
+    /// Frees all buffers of the pool.
+    pub fn trim(&self) {
+        self.buffers
+            .lock()
+            .expect("Buffer pool is poisoned")
+            .clear();
+    }
+
+    fn bytes(buffer: &Vec<T>) -> usize {
+        buffer.capacity() * mem::size_of::<T>()
+    }
+
+    /// Takes a buffer, which is put back into the pool, when it is dropped.
+    pub fn take_guarded(&'static self, capacity: usize) -> PooledBuffer<T> {
+        PooledBuffer {
+            buffer: self.take(capacity),
+            pool: self,
+        }
+    }
+}
+
+/// Buffer of a pool for values, whose owner can't put them back explicitly, like the state of
+/// an iterator.
+pub struct PooledBuffer<T: 'static> {
+    buffer: Vec<T>,
+    pool: &'static BufferPool<T>,
+}
+
+impl<T> Deref for PooledBuffer<T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl<T> DerefMut for PooledBuffer<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buffer
+    }
+}
+
+impl<T> Drop for PooledBuffer<T> {
+    fn drop(&mut self) {
+        self.pool.put_back(mem::take(&mut self.buffer));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BufferPool, MAX_POOLED_BUFFERS, MAX_POOLED_BUFFER_BYTES, MAX_POOLED_BYTES};
+
+    #[test]
+    fn reuse_smallest_fitting_buffer() {
+        let pool = BufferPool::<f32>::new();
+        let small = Vec::with_capacity(100);
+        let large = Vec::with_capacity(1000);
+        let (small_pointer, large_pointer) = (small.as_ptr(), large.as_ptr());
+        pool.put_back(large);
+        pool.put_back(small);
+        let buffer = pool.take(50);
+        assert_eq!(buffer.as_ptr(), small_pointer);
+        assert!(buffer.is_empty());
+        let buffer = pool.take(200);
+        assert_eq!(buffer.as_ptr(), large_pointer);
+        assert!(pool.take(10).capacity() >= 10);
+    }
+
+    #[test]
+    fn free_buffers_of_full_pool() {
+        let pool = BufferPool::<u8>::new();
+        for capacity in 1..=MAX_POOLED_BUFFERS {
+            pool.put_back(Vec::with_capacity(capacity));
+        }
+        pool.put_back(Vec::with_capacity(10_000));
+        let buffers = pool.buffers.lock().unwrap();
+        assert_eq!(buffers.len(), MAX_POOLED_BUFFERS);
+        assert!(buffers.iter().all(|buffer| buffer.capacity() < 10_000));
+    }
+
+    #[test]
+    fn bound_bytes_of_pool() {
+        let pool = BufferPool::<u8>::new();
+        pool.put_back(Vec::with_capacity(MAX_POOLED_BUFFER_BYTES + 1));
+        assert!(pool.buffers.lock().unwrap().is_empty());
+        for _ in 0..MAX_POOLED_BYTES / MAX_POOLED_BUFFER_BYTES + 1 {
+            pool.put_back(Vec::with_capacity(MAX_POOLED_BUFFER_BYTES));
+        }
+        let pooled_bytes: usize = pool.buffers.lock().unwrap().iter().map(Vec::capacity).sum();
+        assert_eq!(pooled_bytes, MAX_POOLED_BYTES);
+        pool.trim();
+        assert!(pool.buffers.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn put_back_guarded_buffer_on_drop() {
+        static POOL: BufferPool<u16> = BufferPool::new();
+        let mut buffer = POOL.take_guarded(64);
+        buffer.extend([1, 2, 3]);
+        let pointer = buffer.as_ptr();
+        drop(buffer);
+        let buffer = POOL.take(64);
+        assert_eq!(buffer.as_ptr(), pointer);
+        assert!(buffer.is_empty());
+    }
+}
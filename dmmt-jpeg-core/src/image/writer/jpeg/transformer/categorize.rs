@@ -1,4 +1,4 @@
-use crate::BitPattern;
+use crate::{huffman::encoder::HuffmanTranslator, BitPattern, Error};
 
 use super::frequency_block::FrequencyBlock;
 
@@ -16,6 +16,10 @@ impl BitPattern for CategoryEncodedInteger {
     fn bit_len(&self) -> usize {
         self.pattern_length as usize
     }
+
+    fn to_u32(&self) -> Option<u32> {
+        Some(self.pattern as u32 >> (u16::BITS - self.pattern_length as u32))
+    }
 }
 
 impl CategoryEncodedInteger {
@@ -116,6 +120,11 @@ impl CategorizedBlock {
         self.ac_tokens.iter().map(|t| t.combined_symbol())
     }
 
+    /// Bytes, the tokens of the block occupy on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.ac_tokens.capacity() * size_of::<LeadingZerosToken>()
+    }
+
     pub fn dc_symbol(&self) -> u8 {
         self.dc_category.pattern_length
     }
@@ -127,6 +136,41 @@ impl CategorizedBlock {
     pub fn iter_ac_categories(&self) -> impl Iterator<Item = &CategoryEncodedInteger> + use<'_> {
         self.ac_tokens.iter().map(|t| t.category())
     }
+
+    /// Number of bits the block occupies in the scan, if it is encoded with the given
+    /// translators. Each symbol costs the length of its code word plus the length of the
+    /// category encoded value following it.
+    pub fn bit_cost(
+        &self,
+        dc_translator: &HuffmanTranslator,
+        ac_translator: &HuffmanTranslator,
+    ) -> crate::Result<u64> {
+        let symbol_cost = |translator: &HuffmanTranslator, symbol, name| {
+            translator
+                .code_word_length(symbol)
+                .map(|length| length as u64)
+                .ok_or(Error::HuffmanSymbolNotPresentInTranslator(symbol, name))
+        };
+        let mut cost =
+            symbol_cost(dc_translator, self.dc_symbol(), "dc")? + self.dc_category.bit_len() as u64;
+        for (symbol, category) in self.iter_ac_symbols().zip(self.iter_ac_categories()) {
+            cost += symbol_cost(ac_translator, symbol, "ac")? + category.bit_len() as u64;
+        }
+        Ok(cost)
+    }
+}
+
+/// Total number of bits of the categorized blocks of a channel, if they are encoded with the
+/// given translators. Neither padding nor restart markers are included.
+pub fn channel_bit_cost(
+    blocks: &[CategorizedBlock],
+    dc_translator: &HuffmanTranslator,
+    ac_translator: &HuffmanTranslator,
+) -> crate::Result<u64> {
+    blocks
+        .iter()
+        .map(|block| block.bit_cost(dc_translator, ac_translator))
+        .sum()
 }
 
 fn sum_zeros_before_values<'a, T: Iterator<Item = &'a i16>>(sequence: T) -> Vec<LeadingZerosToken> {
@@ -150,27 +194,89 @@ fn sum_zeros_before_values<'a, T: Iterator<Item = &'a i16>>(sequence: T) -> Vec<
     result
 }
 
+/// Block, whose AC coefficients are already categorized in zig-zag order, while the DC
+/// coefficient is kept, until the DC value of the previous block is known.
+pub struct PartiallyCategorizedBlock {
+    dc: i16,
+    ac_tokens: Vec<LeadingZerosToken>,
+}
+
+impl From<&FrequencyBlock<i16>> for PartiallyCategorizedBlock {
+    fn from(frequency_block: &FrequencyBlock<i16>) -> Self {
+        Self {
+            dc: *frequency_block.dc(),
+            ac_tokens: sum_zeros_before_values(frequency_block.iter_zig_zag().skip(1)),
+        }
+    }
+}
+
+/// Categorizes the blocks of a channel. The DC values are encoded as difference to the DC value
+/// of the previous block. The prediction starts over at zero with the first block of each restart
+/// interval, if `blocks_per_restart_interval` is set.
 pub fn categorize_channel<T: Iterator<Item = FrequencyBlock<i16>>>(
     frequency_blocks: T,
+    blocks_per_restart_interval: Option<usize>,
 ) -> Vec<CategorizedBlock> {
-    let mut categorized_blocks: Vec<CategorizedBlock> = Vec::new();
-    let mut last_dc = 0;
-    for frequency_block in frequency_blocks {
-        let current_dc = *frequency_block.dc();
-        let dc_category = CategoryEncodedInteger::from(current_dc - last_dc);
-        last_dc = current_dc;
-        let ac_components = sum_zeros_before_values(frequency_block.iter_zig_zag().skip(1));
-        categorized_blocks.push(CategorizedBlock {
-            ac_tokens: ac_components,
-            dc_category,
-        });
+    DcPredictor::new(blocks_per_restart_interval).categorize_dc(
+        frequency_blocks.map(|frequency_block| PartiallyCategorizedBlock::from(&frequency_block)),
+    )
+}
+
+/// Prediction of the DC values of a channel, see [`categorize_channel`]. It carries the DC value
+/// of the last block and the position in the restart interval over to the following blocks, so
+/// a channel can be categorized band by band.
+pub struct DcPredictor {
+    last_dc: i16,
+    number_of_blocks: usize,
+    blocks_per_restart_interval: Option<usize>,
+}
+
+impl DcPredictor {
+    pub fn new(blocks_per_restart_interval: Option<usize>) -> Self {
+        Self {
+            last_dc: 0,
+            number_of_blocks: 0,
+            blocks_per_restart_interval,
+        }
+    }
+
+    /// Completes the categorization of the partially categorized blocks, which follow the
+    /// blocks categorized before, by the DC differences.
+    pub fn categorize_dc<T: Iterator<Item = PartiallyCategorizedBlock>>(
+        &mut self,
+        blocks: T,
+    ) -> Vec<CategorizedBlock> {
+        let mut categorized_blocks: Vec<CategorizedBlock> = Vec::new();
+        for block in blocks {
+            if self
+                .blocks_per_restart_interval
+                .is_some_and(|blocks| self.number_of_blocks.is_multiple_of(blocks))
+            {
+                self.last_dc = 0;
+            }
+            let dc_category = CategoryEncodedInteger::from(block.dc - self.last_dc);
+            self.last_dc = block.dc;
+            self.number_of_blocks += 1;
+            categorized_blocks.push(CategorizedBlock {
+                ac_tokens: block.ac_tokens,
+                dc_category,
+            });
+        }
+        categorized_blocks
     }
-    categorized_blocks
 }
 
 #[cfg(test)]
 mod test {
-    use super::{sum_zeros_before_values, CategoryEncodedInteger, LeadingZerosToken};
+    use super::{
+        categorize_channel, channel_bit_cost, sum_zeros_before_values, CategorizedBlock,
+        CategoryEncodedInteger, DcPredictor, FrequencyBlock, LeadingZerosToken,
+        PartiallyCategorizedBlock,
+    };
+    use crate::{
+        huffman::{encoder::HuffmanTranslator, SymbolCodeLength},
+        BitPattern, Error,
+    };
 
     #[test]
     fn test_categorize_integer() {
@@ -287,4 +393,83 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_categorize_channel_restarts_dc_prediction() {
+        let blocks = [5, 7, 9, 4].map(|dc| {
+            let mut values = [0_i16; 64];
+            values[0] = dc;
+            FrequencyBlock::new(values)
+        });
+        let categorized = categorize_channel(blocks.into_iter(), Some(2));
+        let dc_symbols: Vec<u8> = categorized.iter().map(|b| b.dc_symbol()).collect();
+        let expected: Vec<u8> = [5, 2, 9, -5]
+            .map(|difference| CategoryEncodedInteger::from(difference).pattern_length)
+            .to_vec();
+        assert_eq!(dc_symbols, expected);
+    }
+
+    #[test]
+    fn carry_dc_prediction_over_bands() {
+        let blocks = || {
+            [5, 7, 9, 4, -3, 8, 8].map(|dc| {
+                let mut values = [0_i16; 64];
+                values[0] = dc;
+                PartiallyCategorizedBlock::from(&FrequencyBlock::new(values))
+            })
+        };
+        let whole_channel = DcPredictor::new(Some(3)).categorize_dc(blocks().into_iter());
+        let mut predictor = DcPredictor::new(Some(3));
+        let mut bands = blocks().into_iter();
+        let banded_channel: Vec<CategorizedBlock> = [2, 4, 1]
+            .into_iter()
+            .flat_map(|band_length| predictor.categorize_dc(bands.by_ref().take(band_length)))
+            .collect();
+        let dc_values = |channel: &[CategorizedBlock]| -> Vec<(u8, Box<[u8]>)> {
+            channel
+                .iter()
+                .map(|block| (block.dc_symbol(), block.dc_category().to_bytes()))
+                .collect()
+        };
+        assert_eq!(dc_values(&banded_channel), dc_values(&whole_channel));
+    }
+
+    #[test]
+    fn test_channel_bit_cost() {
+        // dc symbols 2 and 0 with 1 bit each, ac symbols 0x01 and 0x00 (end of block) with 2 bits
+        let dc_translator = HuffmanTranslator::from(&[(2, 1), (0, 1)].map(SymbolCodeLength::from));
+        let ac_translator =
+            HuffmanTranslator::from(&[(0x01, 2), (0x00, 2), (0x11, 1)].map(SymbolCodeLength::from));
+        let blocks = [
+            CategorizedBlock::new(
+                CategoryEncodedInteger::from(-3),
+                vec![LeadingZerosToken::new(0, 1), LeadingZerosToken::new(0, 0)],
+            ),
+            CategorizedBlock::new(
+                CategoryEncodedInteger::from(0),
+                vec![LeadingZerosToken::new(0, 0)],
+            ),
+        ];
+        assert_eq!(
+            blocks[0].bit_cost(&dc_translator, &ac_translator).unwrap(),
+            1 + 2 + 2 + 1 + 2
+        );
+        assert_eq!(
+            channel_bit_cost(&blocks, &dc_translator, &ac_translator).unwrap(),
+            8 + 1 + 2
+        );
+    }
+
+    #[test]
+    fn test_channel_bit_cost_missing_symbol() {
+        let translator = HuffmanTranslator::from(&[(0, 1), (1, 1)].map(SymbolCodeLength::from));
+        let blocks = [CategorizedBlock::new(
+            CategoryEncodedInteger::from(7),
+            vec![LeadingZerosToken::new(0, 0)],
+        )];
+        assert!(matches!(
+            channel_bit_cost(&blocks, &translator, &translator),
+            Err(Error::HuffmanSymbolNotPresentInTranslator(3, "dc"))
+        ));
+    }
 }
@@ -0,0 +1,139 @@
+use crate::color::{
+    level_shift, ColorMatrix, ColorRange, YCbCrColorFormat, BASELINE_SAMPLE_PRECISION,
+};
+
+/// Bits of the fraction of the fixed point values.
+const SCALE_BITS: u32 = 16;
+/// One half in fixed point, which rounds the sums to the nearest integer.
+const ONE_HALF: i32 = 1 << (SCALE_BITS - 1);
+
+/// Weighted 8 bit samples of one color in fixed point.
+type SampleTable = [i32; 256];
+
+/// Tables of one YCbCr component, which weight the red, green and blue sample.
+struct ComponentTables {
+    red: SampleTable,
+    green: SampleTable,
+    blue: SampleTable,
+}
+
+impl ComponentTables {
+    /// Tables of the weights. The offset and the rounding are folded into the red table, so
+    /// a component is three lookups, two additions and a shift.
+    fn new([red_weight, green_weight, blue_weight]: [f64; 3], offset: f64) -> Self {
+        let fixed = |value: f64| (value * (1 << SCALE_BITS) as f64).round() as i32;
+        let table = |weight: f64, offset: i32| {
+            std::array::from_fn(|sample| fixed(weight * sample as f64) + offset)
+        };
+        Self {
+            red: table(red_weight, fixed(offset) + ONE_HALF),
+            green: table(green_weight, 0),
+            blue: table(blue_weight, 0),
+        }
+    }
+
+    fn convert(&self, [red, green, blue]: [u8; 3]) -> i16 {
+        let sum = self.red[red as usize] + self.green[green as usize] + self.blue[blue as usize];
+        (sum >> SCALE_BITS) as i16
+    }
+}
+
+/// Lookup tables of libjpeg style, which convert 8 bit RGB samples in fixed point to unsigned
+/// YCbCr samples of baseline precision, rounded to integers. The conversion matches the
+/// floating point conversion of the color matrix and range up to the rounding.
+pub struct ColorLookupTable {
+    luma: ComponentTables,
+    chroma_blue: ComponentTables,
+    chroma_red: ComponentTables,
+}
+
+impl ColorLookupTable {
+    pub fn new(color_matrix: ColorMatrix, color_range: ColorRange) -> Self {
+        let [luma_row, chroma_blue_row, chroma_red_row] = color_matrix.coefficients();
+        let [(luma_scale, luma_offset), (chroma_scale, _)] =
+            color_range.scales_and_offsets(BASELINE_SAMPLE_PRECISION);
+        // the neutral chroma stays in the middle of the range in every range
+        let chroma_offset = level_shift(BASELINE_SAMPLE_PRECISION) as f64;
+        Self {
+            luma: ComponentTables::new(luma_row.map(|weight| weight * luma_scale), luma_offset),
+            chroma_blue: ComponentTables::new(
+                chroma_blue_row.map(|weight| weight * chroma_scale),
+                chroma_offset,
+            ),
+            chroma_red: ComponentTables::new(
+                chroma_red_row.map(|weight| weight * chroma_scale),
+                chroma_offset,
+            ),
+        }
+    }
+
+    pub fn convert(&self, samples: [u8; 3]) -> YCbCrColorFormat<i16> {
+        YCbCrColorFormat {
+            luma: self.luma.convert(samples),
+            chroma_blue: self.chroma_blue.convert(samples),
+            chroma_red: self.chroma_red.convert(samples),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ColorLookupTable;
+    use crate::color::{ColorMatrix, ColorRange, RGBColorFormat, YCbCrColorFormat};
+
+    #[test]
+    fn match_floating_point_conversion() {
+        for &color_matrix in ColorMatrix::variants() {
+            for &color_range in ColorRange::variants() {
+                let table = ColorLookupTable::new(color_matrix, color_range);
+                for index in 0..4096_u32 {
+                    let samples = [
+                        (index * 17 % 256) as u8,
+                        (index * 101 % 256) as u8,
+                        (index * 7 / 3 % 256) as u8,
+                    ];
+                    let rgb = RGBColorFormat::from_samples(
+                        samples.map(|sample| sample as f32 / u8::MAX as f32),
+                    );
+                    let expected =
+                        YCbCrColorFormat::unsigned_with_color_matrix_f64(&rgb, 8, color_matrix)
+                            .in_range(color_range, 8);
+                    let actual = table.convert(samples);
+                    for (actual, expected) in [
+                        (actual.luma, expected.luma),
+                        (actual.chroma_blue, expected.chroma_blue),
+                        (actual.chroma_red, expected.chroma_red),
+                    ] {
+                        assert!(
+                            (actual as f64 - expected).abs() <= 0.5 + 1e-3,
+                            "{} is different than {} for {:?} with {:?} and {:?}",
+                            actual,
+                            expected,
+                            samples,
+                            color_matrix,
+                            color_range
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn convert_black_and_white() {
+        let table = ColorLookupTable::new(ColorMatrix::Bt601, ColorRange::Full);
+        let black = table.convert([0; 3]);
+        assert_eq!(
+            (black.luma, black.chroma_blue, black.chroma_red),
+            (0, 128, 128)
+        );
+        let white = table.convert([u8::MAX; 3]);
+        assert_eq!(
+            (white.luma, white.chroma_blue, white.chroma_red),
+            (255, 128, 128)
+        );
+        let table = ColorLookupTable::new(ColorMatrix::Bt709, ColorRange::Limited);
+        assert_eq!(table.convert([0; 3]).luma, 16);
+        assert_eq!(table.convert([u8::MAX; 3]).luma, 235);
+    }
+}
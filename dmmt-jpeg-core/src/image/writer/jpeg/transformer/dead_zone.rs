@@ -0,0 +1,86 @@
+use std::fmt::Display;
+
+use super::frequency_block::FrequencyBlock;
+use crate::image::writer::jpeg::consts::ZIG_ZAG_ORDER;
+
+/// Zeroes the quantized AC coefficients, whose absolute value is below the threshold. Small
+/// isolated coefficients cost a symbol each and often end a run of zeros early, so dropping
+/// them saves noticeably more bits than they contribute to the quality. The dead zone applies
+/// to the coefficients from the spectral index in zig zag order on, so the low frequencies can
+/// be kept.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DeadZone {
+    threshold: u16,
+    start_index: u8,
+}
+
+impl DeadZone {
+    pub fn new(threshold: u16, start_index: u8) -> Self {
+        if !(1..64).contains(&start_index) {
+            panic!("Dead zone must start at an AC coefficient");
+        }
+        Self {
+            threshold,
+            start_index,
+        }
+    }
+
+    pub fn apply(&self, block: &mut FrequencyBlock<i16>) {
+        let data = block.data_mut();
+        for &index in &ZIG_ZAG_ORDER[self.start_index as usize..] {
+            if data[index].unsigned_abs() < self.threshold {
+                data[index] = 0;
+            }
+        }
+    }
+}
+
+impl Default for DeadZone {
+    /// Zeroes all AC coefficients of magnitude 1.
+    fn default() -> Self {
+        Self::new(2, 1)
+    }
+}
+
+/// Formats the dead zone as `THRESHOLD:INDEX`, as accepted on the command line.
+impl Display for DeadZone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.threshold, self.start_index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DeadZone;
+    use crate::image::writer::jpeg::{
+        consts::ZIG_ZAG_ORDER, transformer::frequency_block::FrequencyBlock,
+    };
+
+    #[test]
+    fn zero_small_coefficients_from_start_index() {
+        let mut data = [0i16; 64];
+        data[ZIG_ZAG_ORDER[0]] = 1;
+        data[ZIG_ZAG_ORDER[1]] = -1;
+        data[ZIG_ZAG_ORDER[5]] = 2;
+        data[ZIG_ZAG_ORDER[6]] = -2;
+        data[ZIG_ZAG_ORDER[7]] = 3;
+        let mut block = FrequencyBlock::new(data);
+        DeadZone::new(3, 5).apply(&mut block);
+        let coefficients: Vec<i16> = block.iter_zig_zag().copied().collect();
+        assert_eq!(coefficients[..8], [1, -1, 0, 0, 0, 0, 0, 3]);
+    }
+
+    #[test]
+    fn keep_dc_coefficient() {
+        let mut block = FrequencyBlock::new([1i16; 64]);
+        DeadZone::default().apply(&mut block);
+        assert_eq!(*block.dc(), 1);
+        assert!(block.iter_zig_zag().skip(1).all(|&value| value == 0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn reject_dead_zone_of_dc_coefficient() {
+        DeadZone::new(2, 0);
+    }
+}
@@ -1,8 +1,4 @@
-const ZIG_ZAG_ORDERED_BLOCK_INDEXES: [usize; 64] = [
-    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
-    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
-    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
-];
+use crate::image::writer::jpeg::consts::ZIG_ZAG_ORDER;
 
 #[derive(Clone, Copy)]
 pub struct FrequencyBlock<T> {
@@ -21,6 +17,11 @@ impl<T> FrequencyBlock<T> {
     pub fn dc(&self) -> &T {
         &self.data[0]
     }
+
+    /// Coefficients in row major order.
+    pub fn data_mut(&mut self) -> &mut [T; 64] {
+        &mut self.data
+    }
 }
 
 pub struct ZigZagIterator<'a, T> {
@@ -50,10 +51,10 @@ impl<'a, T> Iterator for ZigZagIterator<'a, T> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.next_index >= ZIG_ZAG_ORDERED_BLOCK_INDEXES.len() {
+        if self.next_index >= ZIG_ZAG_ORDER.len() {
             return None;
         }
-        let block_index = ZIG_ZAG_ORDERED_BLOCK_INDEXES[self.next_index];
+        let block_index = ZIG_ZAG_ORDER[self.next_index];
         let return_value = &self.data[block_index];
         self.next_index += 1;
         Some(return_value)
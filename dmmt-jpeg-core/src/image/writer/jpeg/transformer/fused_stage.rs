@@ -0,0 +1,120 @@
+use crate::{
+    cosine_transform::DCTAlgorithm,
+    image::writer::jpeg::timings::{Stage, StageTimings},
+};
+
+use super::{categorize::PartiallyCategorizedBlock, quantizer::BlockQuantizer, sample::Sample};
+
+/// Transforms and quantizes the blocks of level shifted samples and categorizes their AC
+/// coefficients in zig-zag order in a single pass, so the coefficients are never written back
+/// to a channel and read again. It runs in the job of a band of the image, see
+/// [`super::band_pipeline::BandStages::run`]. The blocks are returned in the order of the
+/// samples, which are transformed in place. The DCT and the quantization are timed apart.
+pub fn transform_and_categorize_blocks<S: Sample>(
+    samples: &mut [S],
+    dct_algorithm: DCTAlgorithm,
+    block_quantizer: &BlockQuantizer,
+    stage_timings: &StageTimings,
+) -> Vec<PartiallyCategorizedBlock> {
+    let bytes = size_of_val(samples) as u64;
+    stage_timings.measure(Stage::Dct, bytes, || {
+        S::transform_blocks(samples, dct_algorithm)
+    });
+    stage_timings.measure(Stage::Quantization, bytes, || {
+        samples
+            .chunks_exact(64)
+            .map(|coefficients| {
+                PartiallyCategorizedBlock::from(&block_quantizer.quantize(coefficients, 1.0))
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use threadpool::ThreadPool;
+
+    use super::transform_and_categorize_blocks;
+    use crate::{
+        cosine_transform::DCTAlgorithm,
+        huffman::standard_tables,
+        image::{
+            writer::jpeg::{
+                timings::StageTimings,
+                transformer::{
+                    categorize::{categorize_channel, DcPredictor},
+                    dead_zone::DeadZone,
+                    quantizer::{BlockQuantizer, Quantizer},
+                    sample::Sample,
+                    trellis::RateModel,
+                },
+                QuantizationTablePreset,
+            },
+            ColorChannel,
+        },
+        BitPattern,
+    };
+
+    #[test]
+    fn match_separate_passes() {
+        let number_of_blocks = 37;
+        let dots: Vec<f32> = (0..number_of_blocks * 64)
+            .map(|index| ((index * 37 + index / 8 * 11) % 255) as f32 - 128.0)
+            .collect();
+        let table_pair = QuantizationTablePreset::Specification.to_pair();
+        let block_quantizer = BlockQuantizer::new(table_pair.luma_table)
+            .with_trellis(RateModel::from_code_lengths(
+                &standard_tables::luminance_ac(),
+            ))
+            .with_dead_zone(DeadZone::default());
+        let threadpool = ThreadPool::new(4);
+
+        let fused = DcPredictor::new(None).categorize_dc(
+            transform_and_categorize_blocks(
+                &mut dots.clone(),
+                DCTAlgorithm::Arai,
+                &block_quantizer,
+                &StageTimings::new(),
+            )
+            .into_iter(),
+        );
+
+        let mut transformed_channel = ColorChannel::new(8, number_of_blocks as u16 * 8, dots);
+        f32::transform_channel(&mut transformed_channel, DCTAlgorithm::Arai, &threadpool);
+        let quantizer = Quantizer::new(&transformed_channel, block_quantizer);
+        let separate = categorize_channel(quantizer.quantize_channel(), None);
+
+        assert_eq!(fused.len(), separate.len());
+        for (index, (fused_block, separate_block)) in fused.iter().zip(&separate).enumerate() {
+            assert_eq!(
+                fused_block.dc_symbol(),
+                separate_block.dc_symbol(),
+                "DC symbol of block {} differs",
+                index
+            );
+            assert_eq!(
+                fused_block.dc_category().to_bytes(),
+                separate_block.dc_category().to_bytes(),
+                "DC value of block {} differs",
+                index
+            );
+            assert!(
+                fused_block
+                    .iter_ac_symbols()
+                    .eq(separate_block.iter_ac_symbols()),
+                "AC symbols of block {} differ",
+                index
+            );
+            assert!(
+                fused_block
+                    .iter_ac_categories()
+                    .map(|category| category.to_bytes())
+                    .eq(separate_block
+                        .iter_ac_categories()
+                        .map(|category| category.to_bytes())),
+                "AC values of block {} differ",
+                index
+            );
+        }
+    }
+}
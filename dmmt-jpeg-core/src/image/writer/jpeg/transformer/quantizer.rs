@@ -0,0 +1,132 @@
+use crate::image::ColorChannel;
+
+use super::{
+    adaptive_quantization,
+    buffer_pool::PooledBuffer,
+    dead_zone::DeadZone,
+    frequency_block::FrequencyBlock,
+    sample::Sample,
+    trellis::{self, RateModel},
+    vectorized_quantization,
+};
+
+/// Quantizes single blocks of coefficients. It holds no reference to a channel, so it can be
+/// moved to the jobs of a threadpool.
+#[derive(Clone)]
+pub struct BlockQuantizer {
+    quantization_table: [u16; 64],
+    reciprocals: [f32; 64],
+    rate_model: Option<RateModel>,
+    dead_zone: Option<DeadZone>,
+}
+
+impl BlockQuantizer {
+    pub fn new(quantization_table: [u16; 64]) -> Self {
+        Self {
+            quantization_table,
+            reciprocals: vectorized_quantization::reciprocals(&quantization_table),
+            rate_model: None,
+            dead_zone: None,
+        }
+    }
+
+    /// Chooses the AC coefficients by trellis quantization with the bits estimated by the rate
+    /// model instead of rounding them.
+    pub fn with_trellis(self, rate_model: RateModel) -> Self {
+        Self {
+            rate_model: Some(rate_model),
+            ..self
+        }
+    }
+
+    /// Zeroes the small AC coefficients after the quantization, see [`DeadZone`].
+    pub fn with_dead_zone(self, dead_zone: DeadZone) -> Self {
+        Self {
+            dead_zone: Some(dead_zone),
+            ..self
+        }
+    }
+
+    /// Quantizes the 64 coefficients of a block. The steps of the AC coefficients are multiplied
+    /// with the step scale, see [`adaptive_quantization::quantize_scaled`].
+    pub fn quantize<T: Sample>(&self, samples: &[T], step_scale: f64) -> FrequencyBlock<i16> {
+        let mut block = if let Some(rate_model) = &self.rate_model {
+            trellis::quantize_block(samples, &self.quantization_table, step_scale, rate_model)
+        } else {
+            self.round_block(samples, step_scale)
+        };
+        if let Some(dead_zone) = &self.dead_zone {
+            dead_zone.apply(&mut block);
+        }
+        block
+    }
+
+    fn round_block<T: Sample>(&self, samples: &[T], step_scale: f64) -> FrequencyBlock<i16> {
+        if step_scale == 1.0 {
+            return FrequencyBlock::new(T::quantize_block(
+                samples,
+                &self.quantization_table,
+                &self.reciprocals,
+            ));
+        }
+        FrequencyBlock::new(std::array::from_fn(|index| {
+            let quantization_value = self.quantization_table[index];
+            if index == 0 {
+                samples[index].quantize(quantization_value)
+            } else {
+                adaptive_quantization::quantize_scaled(
+                    samples[index],
+                    quantization_value,
+                    step_scale,
+                )
+            }
+        }))
+    }
+}
+
+pub struct Quantizer<'a, T> {
+    channel: &'a ColorChannel<T>,
+    block_quantizer: BlockQuantizer,
+    /// Scales of the blocks, whose buffer is put back into the pool with the quantizer or the
+    /// iterator of the quantized blocks.
+    step_scales: Option<PooledBuffer<f64>>,
+}
+
+impl<'a, T> Quantizer<'a, T> {
+    /// Quantizes the blocks of the channel with the block quantizer.
+    pub fn new(channel: &'a ColorChannel<T>, block_quantizer: BlockQuantizer) -> Self {
+        Self {
+            channel,
+            block_quantizer,
+            step_scales: None,
+        }
+    }
+}
+
+impl<'a, T: Sample> Quantizer<'a, T> {
+    /// Coarsens the steps of the AC coefficients of active blocks by the strength, see
+    /// [`adaptive_quantization::step_scales`].
+    pub fn with_adaptive_quantization(self, strength: f32) -> Self {
+        let mut step_scales = f64::buffer_pool().take_guarded(self.channel.dots.len() / 64);
+        adaptive_quantization::step_scales(&self.channel.dots, strength, &mut step_scales);
+        Self {
+            step_scales: Some(step_scales),
+            ..self
+        }
+    }
+
+    pub fn quantize_channel(self) -> impl Iterator<Item = FrequencyBlock<i16>> + use<'a, T> {
+        let block_quantizer = self.block_quantizer;
+        let step_scales = self.step_scales;
+        self.channel
+            .dots
+            .chunks_exact(64)
+            .enumerate()
+            .map(move |(block_index, samples)| {
+                let step_scale = step_scales
+                    .as_ref()
+                    .map_or(1.0, |step_scales| step_scales[block_index]);
+                block_quantizer.quantize(samples, step_scale)
+            })
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    fmt::Debug,
+    iter::Sum,
+    ops::{AddAssign, Div, DivAssign, Mul, SubAssign},
+};
+
+use threadpool::ThreadPool;
+
+use crate::{
+    color::{
+        level_shift, CMYKColorFormat, ColorMatrix, ColorRange, RGBColorFormat, YCbCrColorFormat,
+        BASELINE_SAMPLE_PRECISION,
+    },
+    cosine_transform::{transform_chunks_on_threadpool, DCTAlgorithm},
+    image::ColorChannel,
+};
+
+use super::{buffer_pool::BufferPool, vectorized_quantization};
+
+/// Number format of the samples between color conversion and quantization.
+pub trait Sample:
+    Copy
+    + Debug
+    + Default
+    + Send
+    + Sync
+    + 'static
+    + AddAssign
+    + SubAssign
+    + DivAssign
+    + Div<Output = Self>
+    + Mul<Output = Self>
+    + PartialOrd
+    + Sum
+    + From<u16>
+    + From<f32>
+    + Into<f64>
+{
+    /// Converts the dot with the matrix to unsigned YCbCr samples of `sample_precision` bits in
+    /// the range. They are level shifted by their own stage before the DCT.
+    fn convert_color(
+        dot: &RGBColorFormat<f32>,
+        sample_precision: u8,
+        color_matrix: ColorMatrix,
+        color_range: ColorRange,
+    ) -> YCbCrColorFormat<Self>;
+
+    /// Takes over a dot, which is already level shifted full range YCbCr of baseline sample
+    /// precision, scales it up to unsigned samples of `sample_precision` bits and maps it into the
+    /// range.
+    fn convert_ycbcr(
+        dot: &YCbCrColorFormat<f32>,
+        sample_precision: u8,
+        color_range: ColorRange,
+    ) -> YCbCrColorFormat<Self>;
+
+    /// Pool of the buffers of the channels of this number format.
+    fn buffer_pool() -> &'static BufferPool<Self>;
+
+    /// Applies the DCT on each 64-value-block of the contiguous samples.
+    fn transform_blocks(blocks: &mut [Self], dct_algorithm: DCTAlgorithm);
+
+    /// Applies the DCT on each 64-value-block of the channel on the threadpool. The function
+    /// returns, when all blocks have been transformed.
+    fn transform_channel(
+        channel: &mut ColorChannel<Self>,
+        dct_algorithm: DCTAlgorithm,
+        threadpool: &ThreadPool,
+    ) {
+        transform_chunks_on_threadpool(
+            threadpool,
+            &mut channel.dots,
+            JOBS_CHUNK_SIZE * 64,
+            move |chunk| Self::transform_blocks(chunk, dct_algorithm),
+        );
+    }
+
+    fn quantize(self, quantization_value: u16) -> i16;
+
+    /// Quantizes the 64 coefficients of a block with the quantization values of the table, whose
+    /// reciprocals are given as well.
+    fn quantize_block(
+        coefficients: &[Self],
+        quantization_table: &[u16; 64],
+        _reciprocals: &[f32; 64],
+    ) -> [i16; 64] {
+        std::array::from_fn(|index| coefficients[index].quantize(quantization_table[index]))
+    }
+}
+
+/// Dot of the image, which is passed to the transformer.
+pub trait SourceDot: Copy + Send + Sync + 'static {
+    /// Whether the dots have a fourth component, which is encoded as K of YCCK.
+    const HAS_KEY: bool = false;
+
+    /// 8 bit RGB samples of the dot, which the lookup tables convert, or `None`, if the dot
+    /// needs the floating point conversion.
+    fn exact_rgb8(&self) -> Option<[u8; 3]>;
+
+    fn to_ycbcr<S: Sample>(
+        &self,
+        sample_precision: u8,
+        color_matrix: ColorMatrix,
+        color_range: ColorRange,
+    ) -> YCbCrColorFormat<S>;
+
+    /// Unsigned sample of the fourth component of `sample_precision` bits, or `None`, if the dot
+    /// has only three components.
+    fn to_key<S: Sample>(&self, _sample_precision: u8) -> Option<S> {
+        None
+    }
+}
+
+/// Factor, which scales samples of baseline precision up to `sample_precision` bits.
+fn precision_scale(sample_precision: u8) -> f32 {
+    (1 << (sample_precision - BASELINE_SAMPLE_PRECISION)) as f32
+}
+
+impl SourceDot for RGBColorFormat<f32> {
+    fn exact_rgb8(&self) -> Option<[u8; 3]> {
+        RGBColorFormat::exact_rgb8(self)
+    }
+
+    fn to_ycbcr<S: Sample>(
+        &self,
+        sample_precision: u8,
+        color_matrix: ColorMatrix,
+        color_range: ColorRange,
+    ) -> YCbCrColorFormat<S> {
+        S::convert_color(self, sample_precision, color_matrix, color_range)
+    }
+}
+
+/// YCbCr dots skip the color conversion, so they keep the matrix of their source.
+impl SourceDot for YCbCrColorFormat<f32> {
+    fn exact_rgb8(&self) -> Option<[u8; 3]> {
+        None
+    }
+
+    fn to_ycbcr<S: Sample>(
+        &self,
+        sample_precision: u8,
+        _color_matrix: ColorMatrix,
+        color_range: ColorRange,
+    ) -> YCbCrColorFormat<S> {
+        S::convert_ycbcr(self, sample_precision, color_range)
+    }
+}
+
+/// CMYK dots are encoded as YCCK, which decoders convert back with the JFIF matrix in the full
+/// range, so they ignore the matrix and range of the options. The black ink is stored inverted
+/// like the other inks, see [`CMYKColorFormat::ycc_source`].
+impl SourceDot for CMYKColorFormat<f32> {
+    const HAS_KEY: bool = true;
+
+    fn exact_rgb8(&self) -> Option<[u8; 3]> {
+        None
+    }
+
+    fn to_ycbcr<S: Sample>(
+        &self,
+        sample_precision: u8,
+        _color_matrix: ColorMatrix,
+        _color_range: ColorRange,
+    ) -> YCbCrColorFormat<S> {
+        S::convert_color(
+            &self.ycc_source(),
+            sample_precision,
+            ColorMatrix::Bt601,
+            ColorRange::Full,
+        )
+    }
+
+    fn to_key<S: Sample>(&self, sample_precision: u8) -> Option<S> {
+        let max_value = ((1_u32 << sample_precision) - 1) as f32;
+        Some(S::from((1.0 - self.black) * max_value))
+    }
+}
+
+/// Number of blocks, a single job on the threadpool transforms in sequence.
+pub(super) const JOBS_CHUNK_SIZE: usize = 700;
+
+impl Sample for f32 {
+    fn convert_color(
+        dot: &RGBColorFormat<f32>,
+        sample_precision: u8,
+        color_matrix: ColorMatrix,
+        color_range: ColorRange,
+    ) -> YCbCrColorFormat<Self> {
+        YCbCrColorFormat::unsigned_with_color_matrix(dot, sample_precision, color_matrix)
+            .in_range(color_range, sample_precision)
+    }
+
+    fn convert_ycbcr(
+        dot: &YCbCrColorFormat<f32>,
+        sample_precision: u8,
+        color_range: ColorRange,
+    ) -> YCbCrColorFormat<Self> {
+        let scale = precision_scale(sample_precision);
+        let level_shift = level_shift(sample_precision) as f32;
+        let unsigned = |sample: f32| sample * scale + level_shift;
+        YCbCrColorFormat {
+            luma: unsigned(dot.luma),
+            chroma_blue: unsigned(dot.chroma_blue),
+            chroma_red: unsigned(dot.chroma_red),
+        }
+        .in_range(color_range, sample_precision)
+    }
+
+    fn buffer_pool() -> &'static BufferPool<Self> {
+        static BUFFER_POOL: BufferPool<f32> = BufferPool::new();
+        &BUFFER_POOL
+    }
+
+    fn transform_blocks(blocks: &mut [Self], dct_algorithm: DCTAlgorithm) {
+        dct_algorithm.transformer().transform_blocks(blocks);
+    }
+
+    fn quantize(self, quantization_value: u16) -> i16 {
+        (self / quantization_value as f32).round() as i16
+    }
+
+    /// Multiplies with the reciprocals instead of dividing, which is vectorized, see
+    /// [`vectorized_quantization::quantize_block`].
+    fn quantize_block(
+        coefficients: &[Self],
+        _quantization_table: &[u16; 64],
+        reciprocals: &[f32; 64],
+    ) -> [i16; 64] {
+        vectorized_quantization::quantize_block(coefficients, reciprocals)
+    }
+}
+
+/// Double precision samples are transformed by the double precision variant of the chosen DCT
+/// algorithm, see [`DCTAlgorithm::resolve_double_precision`].
+impl Sample for f64 {
+    fn convert_color(
+        dot: &RGBColorFormat<f32>,
+        sample_precision: u8,
+        color_matrix: ColorMatrix,
+        color_range: ColorRange,
+    ) -> YCbCrColorFormat<Self> {
+        YCbCrColorFormat::unsigned_with_color_matrix_f64(dot, sample_precision, color_matrix)
+            .in_range(color_range, sample_precision)
+    }
+
+    fn convert_ycbcr(
+        dot: &YCbCrColorFormat<f32>,
+        sample_precision: u8,
+        color_range: ColorRange,
+    ) -> YCbCrColorFormat<Self> {
+        let scale = precision_scale(sample_precision) as f64;
+        let level_shift = level_shift(sample_precision) as f64;
+        let unsigned = |sample: f32| sample as f64 * scale + level_shift;
+        YCbCrColorFormat {
+            luma: unsigned(dot.luma),
+            chroma_blue: unsigned(dot.chroma_blue),
+            chroma_red: unsigned(dot.chroma_red),
+        }
+        .in_range(color_range, sample_precision)
+    }
+
+    fn buffer_pool() -> &'static BufferPool<Self> {
+        static BUFFER_POOL: BufferPool<f64> = BufferPool::new();
+        &BUFFER_POOL
+    }
+
+    fn transform_blocks(blocks: &mut [Self], dct_algorithm: DCTAlgorithm) {
+        let transformer = dct_algorithm.double_precision_transformer();
+        for block in blocks.chunks_exact_mut(64) {
+            let block: &mut [f64; 64] = block
+                .try_into()
+                .expect("Chunks of exactly 64 values are blocks");
+            transformer.transform_double_precision(block);
+        }
+    }
+
+    fn quantize(self, quantization_value: u16) -> i16 {
+        (self / quantization_value as f64).round() as i16
+    }
+}
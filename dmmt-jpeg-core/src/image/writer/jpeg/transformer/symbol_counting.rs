@@ -0,0 +1,451 @@
+#[cfg(not(feature = "rayon"))]
+use std::sync::mpsc;
+use std::sync::Arc;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use threadpool::ThreadPool;
+
+use crate::huffman::{
+    code::HuffmanCodeGenerator, length_limited::LengthLimitedHuffmanCodeGenerator,
+    SymbolCodeLength, SymbolFrequency,
+};
+
+use super::categorize::CategorizedBlock;
+
+macro_rules! counter {
+    ($name:ident; $size:literal) => {
+        #[derive(Clone)]
+        struct $name {
+            symbol_frequencies: [usize; $size],
+        }
+
+        impl $name {
+            fn new() -> Self {
+                Self {
+                    symbol_frequencies: [usize::default(); $size],
+                }
+            }
+
+            fn increment_symbol(&mut self, symbol: u8) {
+                self.symbol_frequencies[symbol as usize] += 1;
+            }
+
+            fn merge(&mut self, other: &Self) {
+                for (frequency, other_frequency) in self
+                    .symbol_frequencies
+                    .iter_mut()
+                    .zip(other.symbol_frequencies)
+                {
+                    *frequency += other_frequency;
+                }
+            }
+
+            fn to_symbol_frequencies(&self) -> Vec<SymbolFrequency> {
+                (0..=u8::MAX)
+                    .into_iter()
+                    .zip(self.symbol_frequencies.iter().copied())
+                    .filter(|&(_, f)| f > 0)
+                    .map(SymbolFrequency::from)
+                    .collect()
+            }
+        }
+    };
+}
+
+counter!(DCCounter; 16);
+counter!(ACCounter; 256);
+
+/// Counters of the DC and AC symbols of a range of blocks.
+#[derive(Clone)]
+pub struct SymbolCounter {
+    dc_counter: DCCounter,
+    ac_counter: ACCounter,
+}
+
+impl SymbolCounter {
+    pub fn new() -> Self {
+        Self {
+            dc_counter: DCCounter::new(),
+            ac_counter: ACCounter::new(),
+        }
+    }
+
+    fn count_block(&mut self, block: &CategorizedBlock) {
+        self.dc_counter.increment_symbol(block.dc_symbol());
+        for ac_symbol in block.iter_ac_symbols() {
+            self.ac_counter.increment_symbol(ac_symbol);
+        }
+    }
+
+    pub fn count_blocks(&mut self, blocks: &[CategorizedBlock]) {
+        for block in blocks {
+            self.count_block(block);
+        }
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        self.dc_counter.merge(&other.dc_counter);
+        self.ac_counter.merge(&other.ac_counter);
+    }
+
+    /// Whether the tables have a code word for each counted symbol.
+    pub fn covered_by(&self, ac_table: &[SymbolCodeLength], dc_table: &[SymbolCodeLength]) -> bool {
+        fn cover(symbol_frequencies: &[usize], table: &[SymbolCodeLength]) -> bool {
+            let mut symbols = [false; 256];
+            for code_length in table {
+                symbols[code_length.symbol as usize] = true;
+            }
+            symbol_frequencies
+                .iter()
+                .zip(symbols)
+                .all(|(&frequency, covered)| frequency == 0 || covered)
+        }
+        cover(&self.dc_counter.symbol_frequencies, dc_table)
+            && cover(&self.ac_counter.symbol_frequencies, ac_table)
+    }
+
+    /// Counts the symbols of the blocks of all channels on the threadpool. Each channel is split
+    /// into one range of blocks per thread, which a job counts with its own counter, and the
+    /// counters are merged at the end. The jobs release the channels before they send their
+    /// counters, so the channels are no longer shared, when the count returns.
+    #[cfg(not(feature = "rayon"))]
+    pub fn count_on_threadpool(
+        channels: &[&Arc<Vec<CategorizedBlock>>],
+        threadpool: &ThreadPool,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        for &channel in channels {
+            let range_size = channel.len().div_ceil(threadpool.max_count()).max(1);
+            for start in (0..channel.len()).step_by(range_size) {
+                let end = (start + range_size).min(channel.len());
+                let blocks = Arc::clone(channel);
+                let sender = sender.clone();
+                threadpool.execute(move || {
+                    let mut counter = SymbolCounter::new();
+                    counter.count_blocks(&blocks[start..end]);
+                    drop(blocks);
+                    sender
+                        .send(counter)
+                        .expect("Receiver of symbol counters was dropped");
+                });
+            }
+        }
+        drop(sender);
+        let mut counter = SymbolCounter::new();
+        for job_counter in receiver.iter() {
+            counter.merge(&job_counter);
+        }
+        counter
+    }
+
+    /// Counts the symbols of the blocks of all channels like the threadpool variant, but folds
+    /// the blocks into counters with the parallel iterators of the current rayon pool.
+    #[cfg(feature = "rayon")]
+    pub fn count_on_threadpool(
+        channels: &[&Arc<Vec<CategorizedBlock>>],
+        _threadpool: &ThreadPool,
+    ) -> Self {
+        let merge = |mut counter: SymbolCounter, other: SymbolCounter| {
+            counter.merge(&other);
+            counter
+        };
+        channels
+            .par_iter()
+            .flat_map(|channel| channel.par_iter())
+            .fold(SymbolCounter::new, |mut counter, block| {
+                counter.count_block(block);
+                counter
+            })
+            .reduce(SymbolCounter::new, merge)
+    }
+}
+
+impl From<&SymbolCounter> for HuffmanCount {
+    fn from(counter: &SymbolCounter) -> Self {
+        let mut ac_count = counter.ac_counter.to_symbol_frequencies();
+        sort_by_frequency(&mut ac_count);
+        let mut dc_count = counter.dc_counter.to_symbol_frequencies();
+        sort_by_frequency(&mut dc_count);
+
+        Self { ac_count, dc_count }
+    }
+}
+
+pub struct HuffmanCount {
+    ac_count: Vec<SymbolFrequency>,
+    dc_count: Vec<SymbolFrequency>,
+}
+
+impl HuffmanCount {
+    /// Appends the frequencies of the DC and AC symbols as CSV rows with the columns
+    /// `component,class,symbol,run_length,size,frequency`, ordered by class and symbol. The
+    /// symbol of a DC value is its size category, so its run length is always zero.
+    pub fn append_csv_rows(&self, component: &str, csv: &mut String) {
+        for (class, count) in [("DC", &self.dc_count), ("AC", &self.ac_count)] {
+            let mut count: Vec<&SymbolFrequency> = count.iter().collect();
+            count.sort_by_key(|s| s.symbol);
+            for symbol_frequency in count {
+                let symbol = symbol_frequency.symbol;
+                csv.push_str(&format!(
+                    "{},{},{},{},{},{}\n",
+                    component,
+                    class,
+                    symbol,
+                    symbol >> 4,
+                    symbol & 0x0F,
+                    symbol_frequency.frequency
+                ));
+            }
+        }
+    }
+
+    pub fn generate_ac_huffman_code(&self, max_code_length: usize) -> Vec<SymbolCodeLength> {
+        generate_code_lengths(&self.ac_count, max_code_length)
+    }
+
+    pub fn generate_dc_huffman_code(&self, max_code_length: usize) -> Vec<SymbolCodeLength> {
+        generate_code_lengths(&self.dc_count, max_code_length)
+    }
+}
+
+impl<'a> FromIterator<&'a CategorizedBlock> for HuffmanCount {
+    fn from_iter<T: IntoIterator<Item = &'a CategorizedBlock>>(blocks: T) -> Self {
+        let mut counter = SymbolCounter::new();
+        for block in blocks {
+            counter.count_block(block);
+        }
+        Self::from(&counter)
+    }
+}
+
+impl<'a, T> From<T> for HuffmanCount
+where
+    T: IntoIterator<Item = &'a CategorizedBlock>,
+{
+    fn from(value: T) -> Self {
+        Self::from_iter(value)
+    }
+}
+
+/// Header of the CSV rows written by [`HuffmanCount::append_csv_rows`].
+pub const SYMBOL_STATS_CSV_HEADER: &str = "component,class,symbol,run_length,size,frequency\n";
+
+/// Generates the code lengths with a reserved dummy symbol of frequency 1 after section K.2 of
+/// the specification. The dummy is the least frequent symbol and takes one code point of the
+/// longest length, so no code word of the real symbols consists of ones only, and a single symbol
+/// still gets a code word of one bit. Without symbols the code is empty.
+fn generate_code_lengths(
+    symfreqs: &[SymbolFrequency],
+    max_code_length: usize,
+) -> Vec<SymbolCodeLength> {
+    let frequencies: Vec<usize> = std::iter::once(RESERVED_SYMBOL_FREQUENCY)
+        .chain(symfreqs.iter().map(|symfreq| symfreq.frequency))
+        .collect();
+    let mut generator = LengthLimitedHuffmanCodeGenerator::new(max_code_length);
+    let code = generator.generate(&frequencies);
+    code.into_iter()
+        .skip(1)
+        .zip(symfreqs)
+        .map(|(length, symfreq)| SymbolCodeLength::new(symfreq.symbol, length))
+        .collect()
+}
+
+/// Frequency of the dummy symbol, which reserves the code word of only ones. It is not above the
+/// frequency of any counted symbol, so the frequencies stay sorted.
+const RESERVED_SYMBOL_FREQUENCY: usize = 1;
+
+fn sort_by_frequency(symbol_frequencies: &mut [SymbolFrequency]) {
+    symbol_frequencies.sort_by_key(|s| s.frequency);
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use threadpool::ThreadPool;
+
+    use crate::huffman::SymbolFrequency;
+
+    use super::{
+        super::super::transformer::{
+            categorize::{CategoryEncodedInteger, LeadingZerosToken},
+            CategorizedBlock,
+        },
+        generate_code_lengths, HuffmanCount, SymbolCounter,
+    };
+
+    #[test]
+    fn test_append_csv_rows() {
+        let blocks = vec![
+            CategorizedBlock::new(
+                CategoryEncodedInteger::from(3), // DC symbol: 2
+                vec![LeadingZerosToken::new(2, -1), LeadingZerosToken::new(0, 0)],
+            ),
+            CategorizedBlock::new(
+                CategoryEncodedInteger::from(-2), // DC symbol: 2
+                vec![LeadingZerosToken::new(0, 0)],
+            ),
+        ];
+        let mut csv = String::new();
+        HuffmanCount::from(&blocks).append_csv_rows("Y", &mut csv);
+        assert_eq!(csv, "Y,DC,2,0,2,2\nY,AC,0,0,0,2\nY,AC,33,2,1,1\n");
+    }
+
+    #[test]
+    fn generate_code_of_degenerate_symbol_sets() {
+        assert!(generate_code_lengths(&[], 16).is_empty());
+        let code_lengths = generate_code_lengths(&[SymbolFrequency::new(0, 1000)], 16);
+        assert_eq!(code_lengths.len(), 1);
+        assert_eq!((code_lengths[0].symbol, code_lengths[0].length), (0, 1));
+        let code_lengths = generate_code_lengths(
+            &[SymbolFrequency::new(3, 5), SymbolFrequency::new(9, 5)],
+            16,
+        );
+        let lengths: Vec<usize> = code_lengths.iter().map(|code| code.length).collect();
+        assert_eq!(lengths, [2, 1]);
+    }
+
+    #[test]
+    fn reserve_code_word_of_only_ones() {
+        let symfreqs: Vec<SymbolFrequency> = (0..=255)
+            .map(|symbol| SymbolFrequency::new(symbol, 1 + symbol as usize * symbol as usize))
+            .collect();
+        for max_code_length in [16, 12, 9] {
+            let code_lengths = generate_code_lengths(&symfreqs, max_code_length);
+            assert!(code_lengths
+                .iter()
+                .all(|code| (1..=max_code_length).contains(&code.length)));
+            let kraft_sum: usize = code_lengths
+                .iter()
+                .map(|code| 1 << (16 - code.length))
+                .sum();
+            assert!(kraft_sum < 1 << 16);
+        }
+    }
+
+    #[test]
+    fn count_on_threadpool_like_serially() {
+        let channel = |offset: i16| -> Vec<CategorizedBlock> {
+            (0..37)
+                .map(|index: i16| {
+                    CategorizedBlock::new(
+                        CategoryEncodedInteger::from(index * 13 - offset),
+                        vec![
+                            LeadingZerosToken::new((index % 16) as u8, index * 7 - 100),
+                            LeadingZerosToken::new(0, 0),
+                        ],
+                    )
+                })
+                .collect()
+        };
+        let blue = Arc::new(channel(200));
+        let red = Arc::new(channel(-50));
+        let serial = HuffmanCount::from_iter(blue.iter().chain(red.iter()));
+        let parallel = HuffmanCount::from(&SymbolCounter::count_on_threadpool(
+            &[&blue, &red],
+            &ThreadPool::new(4),
+        ));
+
+        let mut serial_csv = String::new();
+        serial.append_csv_rows("C", &mut serial_csv);
+        let mut parallel_csv = String::new();
+        parallel.append_csv_rows("C", &mut parallel_csv);
+        assert_eq!(parallel_csv, serial_csv);
+        // the jobs have released the channels
+        assert_eq!(Arc::strong_count(&blue), 1);
+        assert_eq!(Arc::strong_count(&red), 1);
+    }
+
+    #[test]
+    fn test_count_symbols() {
+        let test_blocks_channel_1: Vec<CategorizedBlock> = vec![
+            CategorizedBlock::new(
+                CategoryEncodedInteger::from(30), // DC symbol: 5
+                vec![
+                    LeadingZerosToken::new(0, 300), // AC symbol: 0b00001001 x
+                    LeadingZerosToken::new(15, 0),  // AC symbol: 0b11110000 x
+                    LeadingZerosToken::new(4, 5),   // AC symbol: 0b01000011 x
+                    LeadingZerosToken::new(0, 0),   // AC symbol: 0b00000000 x
+                ],
+            ),
+            CategorizedBlock::new(
+                CategoryEncodedInteger::from(0), // DC symbol: 0
+                vec![
+                    LeadingZerosToken::new(0, 600), // AC symbol: 0b00001010 x
+                    LeadingZerosToken::new(15, 0),  // AC symbol: 0b11110000 x
+                    LeadingZerosToken::new(4, 15),  // AC symbol: 0b01000100 x
+                    LeadingZerosToken::new(0, 0),   // AC symbol: 0b00000000 x
+                ],
+            ),
+        ];
+        let test_blocks_channel_2: Vec<CategorizedBlock> = vec![
+            CategorizedBlock::new(
+                CategoryEncodedInteger::from(60), // DC symbol: 6
+                vec![
+                    LeadingZerosToken::new(0, 100), // AC symbol: 0b00000111 x
+                    LeadingZerosToken::new(15, 0),  // AC symbol: 0b11110000 x
+                    LeadingZerosToken::new(2, 7),   // AC symbol: 0b00100011 x
+                    LeadingZerosToken::new(0, 0),   // AC symbol: 0b00000000 x
+                ],
+            ),
+            CategorizedBlock::new(
+                CategoryEncodedInteger::from(1), // DC symbol: 1
+                vec![
+                    LeadingZerosToken::new(0, 900), // AC symbol: 0b00001010 x
+                    LeadingZerosToken::new(15, 0),  // AC symbol: 0b11110000 x
+                    LeadingZerosToken::new(0, 1),   // AC symbol: 0b00000001 x
+                    LeadingZerosToken::new(0, 0),   // AC symbol: 0b00000000 x
+                ],
+            ),
+        ];
+
+        let expected: HuffmanCount = HuffmanCount {
+            dc_count: vec![
+                SymbolFrequency::new(5, 1),
+                SymbolFrequency::new(0, 1),
+                SymbolFrequency::new(6, 1),
+                SymbolFrequency::new(1, 1),
+            ],
+            ac_count: vec![
+                SymbolFrequency::new(0b00001001, 1),
+                SymbolFrequency::new(0b11110000, 4),
+                SymbolFrequency::new(0b01000011, 1),
+                SymbolFrequency::new(0b00000000, 4),
+                SymbolFrequency::new(0b00001010, 2),
+                SymbolFrequency::new(0b01000100, 1),
+                SymbolFrequency::new(0b00000111, 1),
+                SymbolFrequency::new(0b00100011, 1),
+                SymbolFrequency::new(0b00000001, 1),
+            ],
+        };
+
+        let test_blocks_iterator = test_blocks_channel_1
+            .iter()
+            .chain(test_blocks_channel_2.iter());
+
+        let actual = HuffmanCount::from_iter(test_blocks_iterator);
+
+        for symfreq in actual.dc_count.iter() {
+            let mut found = false;
+            for comp in expected.dc_count.iter() {
+                if symfreq.symbol == comp.symbol {
+                    assert_eq!(symfreq.frequency, comp.frequency);
+                    found = true;
+                }
+            }
+            assert!(found);
+        }
+
+        for symfreq in actual.ac_count.iter() {
+            let mut found = false;
+            for comp in expected.ac_count.iter() {
+                if symfreq.symbol == comp.symbol {
+                    assert_eq!(symfreq.frequency, comp.frequency);
+                    found = true;
+                }
+            }
+            assert!(found);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{huffman::SymbolCodeLength, image::writer::jpeg::consts::ZIG_ZAG_ORDER};
+
+use super::{frequency_block::FrequencyBlock, sample::Sample};
+
+/// Bits of a symbol, which the table of the rate model lacks, like the categories of samples
+/// with extended precision. They are estimated with the longest code word.
+const MISSING_SYMBOL_COST: f64 = 16.0;
+const END_OF_BLOCK_SYMBOL: usize = 0x00;
+const ZERO_RUN_LENGTH_SYMBOL: usize = 0xF0;
+
+/// Weight of the distortion against the rate of a block, 2^SCALE1 / (2^SCALE2 + energy). The
+/// defaults of mozjpeg are adapted to the coefficients of this DCT, which are 8 times smaller.
+/// Errors in blocks with much energy in the AC coefficients are less visible, so they are
+/// weighted less.
+const LAMBDA_LOG_SCALE1: f64 = 14.75;
+const LAMBDA_LOG_SCALE2: f64 = 10.5;
+
+/// Bits of each AC symbol, which the trellis search estimates the size of a block with.
+#[derive(Clone)]
+pub struct RateModel {
+    ac_symbol_costs: [f64; 256],
+}
+
+impl RateModel {
+    /// Takes the code lengths of the AC Huffman table, the block is expected to be coded with.
+    pub fn from_code_lengths(code_lengths: &[SymbolCodeLength]) -> Self {
+        let mut ac_symbol_costs = [MISSING_SYMBOL_COST; 256];
+        for code_length in code_lengths {
+            ac_symbol_costs[code_length.symbol as usize] = code_length.length as f64;
+        }
+        Self { ac_symbol_costs }
+    }
+
+    /// Bits of a run of zeros followed by a value of the category. Runs of more than 15 zeros
+    /// need a ZRL symbol per 16 zeros. The category encoded value adds its category in bits.
+    fn run_cost(&self, run: usize, category: u8) -> f64 {
+        let zero_run_lengths = (run / 16) as f64;
+        let symbol = ((run % 16) << 4) | category as usize;
+        zero_run_lengths * self.ac_symbol_costs[ZERO_RUN_LENGTH_SYMBOL]
+            + self.ac_symbol_costs[symbol]
+            + category as f64
+    }
+
+    fn end_of_block_cost(&self) -> f64 {
+        self.ac_symbol_costs[END_OF_BLOCK_SYMBOL]
+    }
+}
+
+fn category_of(value: i16) -> u8 {
+    (i16::BITS - value.unsigned_abs().leading_zeros()) as u8
+}
+
+/// Quantizes a block of DCT coefficients in row major order. The DC coefficient is rounded, the
+/// AC coefficients are chosen by a search over the paths through the block in zig zag order,
+/// which minimizes the weighted sum of the squared quantization error and the estimated bits.
+/// Each nonzero coefficient is either rounded or rounded towards zero or dropped into a run of
+/// zeros, which often saves more bits than it costs in quality. The AC coefficients are chosen
+/// from multiples of the step scale, as adaptive quantization coarsens the steps of the block.
+pub fn quantize_block<T: Sample>(
+    samples: &[T],
+    quantization_table: &[u16; 64],
+    step_scale: f64,
+    rate_model: &RateModel,
+) -> FrequencyBlock<i16> {
+    let mut quantized = [0; 64];
+    quantized[0] = samples[0].quantize(quantization_table[0]);
+
+    // coefficients in zig zag order in units of their scaled quantization step
+    let coefficients: [f64; 64] = std::array::from_fn(|position| {
+        let index = ZIG_ZAG_ORDER[position];
+        Into::<f64>::into(samples[index]) / (quantization_table[index] as f64 * step_scale)
+    });
+    let energy = samples[1..]
+        .iter()
+        .map(|&sample| Into::<f64>::into(sample).powi(2))
+        .sum::<f64>()
+        / 63.0;
+    let distortion_weight = LAMBDA_LOG_SCALE1.exp2() / (LAMBDA_LOG_SCALE2.exp2() + energy);
+    // zeroed_distortion[position] holds the distortion of dropping the AC coefficients before
+    // the position, so a run of zeros costs the difference of two entries
+    let mut zeroed_distortion = [0.0; 65];
+    for position in 1..64 {
+        zeroed_distortion[position + 1] =
+            zeroed_distortion[position] + coefficients[position].powi(2) * distortion_weight;
+    }
+
+    // cheapest path, whose last nonzero coefficient is at the position, position 0 is the DC
+    let mut path_costs = [f64::INFINITY; 64];
+    let mut predecessors = [0; 64];
+    let mut values = [0; 64];
+    path_costs[0] = 0.0;
+    for position in 1..64 {
+        let coefficient = coefficients[position];
+        let rounded = coefficient.round() as i16;
+        for steps in [rounded, rounded - rounded.signum()] {
+            if steps == 0 {
+                continue;
+            }
+            let value = (steps as f64 * step_scale).round() as i16;
+            let category = category_of(value);
+            let value_distortion = (coefficient - steps as f64).powi(2) * distortion_weight;
+            for predecessor in 0..position {
+                if path_costs[predecessor].is_infinite() {
+                    continue;
+                }
+                let run = position - predecessor - 1;
+                let cost = path_costs[predecessor] + zeroed_distortion[position]
+                    - zeroed_distortion[predecessor + 1]
+                    + rate_model.run_cost(run, category)
+                    + value_distortion;
+                if cost < path_costs[position] {
+                    path_costs[position] = cost;
+                    predecessors[position] = predecessor;
+                    values[position] = value;
+                }
+            }
+        }
+    }
+
+    let mut last_position = 0;
+    let mut lowest_cost = f64::INFINITY;
+    for (position, &path_cost) in path_costs.iter().enumerate() {
+        if path_cost.is_infinite() {
+            continue;
+        }
+        let mut cost = path_cost + zeroed_distortion[64] - zeroed_distortion[position + 1];
+        if position < 63 {
+            cost += rate_model.end_of_block_cost();
+        }
+        if cost < lowest_cost {
+            lowest_cost = cost;
+            last_position = position;
+        }
+    }
+    let mut position = last_position;
+    while position > 0 {
+        quantized[ZIG_ZAG_ORDER[position]] = values[position];
+        position = predecessors[position];
+    }
+    FrequencyBlock::new(quantized)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{quantize_block, RateModel};
+    use crate::{huffman::standard_tables, image::writer::jpeg::consts::ZIG_ZAG_ORDER};
+
+    fn quantize(samples: &[f32; 64], step: u16) -> Vec<i16> {
+        let rate_model = RateModel::from_code_lengths(&standard_tables::luminance_ac());
+        let block = quantize_block(samples, &[step; 64], 1.0, &rate_model);
+        block.iter_zig_zag().copied().collect()
+    }
+
+    #[test]
+    fn rate_model_uses_code_lengths_of_table() {
+        let rate_model = RateModel::from_code_lengths(&standard_tables::luminance_ac());
+        // Table K.5: EOB is coded with 4 bits, 0/1 with 2 bits, ZRL with 11 bits
+        assert_eq!(rate_model.end_of_block_cost(), 4.0);
+        assert_eq!(rate_model.run_cost(0, 1), 2.0 + 1.0);
+        assert_eq!(rate_model.run_cost(16, 1), 11.0 + 2.0 + 1.0);
+    }
+
+    #[test]
+    fn keep_strong_coefficients_rounded() {
+        let mut samples = [0.0; 64];
+        samples[0] = 160.0;
+        samples[ZIG_ZAG_ORDER[1]] = 202.0;
+        samples[ZIG_ZAG_ORDER[2]] = -118.0;
+        let quantized = quantize(&samples, 10);
+        assert_eq!(quantized[..3], [16, 20, -12]);
+        assert!(quantized[3..].iter().all(|&value| value == 0));
+    }
+
+    #[test]
+    fn drop_isolated_small_coefficient() {
+        let mut samples = [0.0; 64];
+        samples[ZIG_ZAG_ORDER[1]] = 202.0;
+        samples[ZIG_ZAG_ORDER[60]] = 6.0;
+        let quantized = quantize(&samples, 10);
+        // rounding keeps 1 at position 60, which needs three ZRL symbols
+        assert_eq!(quantized[1], 20);
+        assert!(quantized[2..].iter().all(|&value| value == 0));
+    }
+
+    #[test]
+    fn choose_multiples_of_step_scale() {
+        let mut samples = [0.0f32; 64];
+        samples[ZIG_ZAG_ORDER[1]] = 202.0;
+        samples[ZIG_ZAG_ORDER[2]] = -118.0;
+        let rate_model = RateModel::from_code_lengths(&standard_tables::luminance_ac());
+        let block = quantize_block(&samples, &[10; 64], 2.0, &rate_model);
+        let quantized: Vec<i16> = block.iter_zig_zag().copied().collect();
+        assert_eq!(quantized[..3], [0, 20, -12]);
+    }
+}
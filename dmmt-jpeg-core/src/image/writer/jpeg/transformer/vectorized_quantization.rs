@@ -0,0 +1,114 @@
+//! Quantization of single precision blocks by multiplying the coefficients with the reciprocals of
+//! the quantization values. The vectorized variants round in the same way as [`f32::round`], half
+//! away from zero, so they yield the same coefficients as the scalar variant.
+
+/// Reciprocals of the quantization values of a table.
+pub fn reciprocals(quantization_table: &[u16; 64]) -> [f32; 64] {
+    quantization_table.map(|quantization_value| 1.0 / quantization_value as f32)
+}
+
+/// Quantizes the 64 coefficients with the reciprocals of the quantization values. The
+/// coefficients are processed with AVX or NEON, if the CPU supports it.
+pub fn quantize_block(coefficients: &[f32], reciprocals: &[f32; 64]) -> [i16; 64] {
+    let coefficients: &[f32; 64] = coefficients
+        .try_into()
+        .expect("Blocks consist of 64 coefficients");
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx") {
+        // SAFETY: the CPU supports AVX
+        return unsafe { avx::quantize_block(coefficients, reciprocals) };
+    }
+    #[cfg(target_arch = "aarch64")]
+    if std::arch::is_aarch64_feature_detected!("neon") {
+        // SAFETY: the CPU supports NEON
+        return unsafe { neon::quantize_block(coefficients, reciprocals) };
+    }
+    quantize_block_scalar(coefficients, reciprocals)
+}
+
+fn quantize_block_scalar(coefficients: &[f32; 64], reciprocals: &[f32; 64]) -> [i16; 64] {
+    std::array::from_fn(|index| (coefficients[index] * reciprocals[index]).round() as i16)
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx {
+    use std::arch::x86_64::{
+        _mm256_add_ps, _mm256_and_ps, _mm256_andnot_ps, _mm256_cmp_ps, _mm256_loadu_ps,
+        _mm256_mul_ps, _mm256_or_ps, _mm256_round_ps, _mm256_set1_ps, _mm256_storeu_ps,
+        _mm256_sub_ps, _CMP_GE_OQ, _MM_FROUND_NO_EXC, _MM_FROUND_TO_ZERO,
+    };
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX.
+    #[target_feature(enable = "avx")]
+    pub unsafe fn quantize_block(coefficients: &[f32; 64], reciprocals: &[f32; 64]) -> [i16; 64] {
+        let sign_mask = _mm256_set1_ps(-0.0);
+        let half = _mm256_set1_ps(0.5);
+        let one = _mm256_set1_ps(1.0);
+        let mut rounded = [0.0; 64];
+        for offset in (0..64).step_by(8) {
+            let steps = _mm256_mul_ps(
+                _mm256_loadu_ps(coefficients.as_ptr().add(offset)),
+                _mm256_loadu_ps(reciprocals.as_ptr().add(offset)),
+            );
+            // the fraction is split off exactly, so ties are detected like by f32::round
+            let truncated = _mm256_round_ps::<{ _MM_FROUND_TO_ZERO | _MM_FROUND_NO_EXC }>(steps);
+            let fraction = _mm256_andnot_ps(sign_mask, _mm256_sub_ps(steps, truncated));
+            let round_up = _mm256_cmp_ps::<_CMP_GE_OQ>(fraction, half);
+            let signed_one = _mm256_or_ps(_mm256_and_ps(steps, sign_mask), one);
+            let result = _mm256_add_ps(truncated, _mm256_and_ps(round_up, signed_one));
+            _mm256_storeu_ps(rounded.as_mut_ptr().add(offset), result);
+        }
+        rounded.map(|value: f32| value as i16)
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::{vld1q_f32, vmulq_f32, vrndaq_f32, vst1q_f32};
+
+    /// # Safety
+    ///
+    /// The CPU must support NEON.
+    #[target_feature(enable = "neon")]
+    pub unsafe fn quantize_block(coefficients: &[f32; 64], reciprocals: &[f32; 64]) -> [i16; 64] {
+        let mut rounded = [0.0; 64];
+        for offset in (0..64).step_by(4) {
+            let steps = vmulq_f32(
+                vld1q_f32(coefficients.as_ptr().add(offset)),
+                vld1q_f32(reciprocals.as_ptr().add(offset)),
+            );
+            // rounds to the nearest integer with ties away from zero like f32::round
+            vst1q_f32(rounded.as_mut_ptr().add(offset), vrndaq_f32(steps));
+        }
+        rounded.map(|value: f32| value as i16)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{quantize_block, quantize_block_scalar, reciprocals};
+
+    #[test]
+    fn match_scalar_quantization() {
+        let quantization_table: [u16; 64] =
+            std::array::from_fn(|index| (index * 7 % 99 + 1) as u16);
+        let reciprocals = reciprocals(&quantization_table);
+        // multiples of the half steps hit the ties of the rounding
+        let coefficients: [f32; 64] = std::array::from_fn(|index| {
+            let half_steps = (index as f32 - 32.0) * 3.0;
+            half_steps * quantization_table[index] as f32 / 2.0
+        });
+        assert_eq!(
+            quantize_block(&coefficients, &reciprocals),
+            quantize_block_scalar(&coefficients, &reciprocals)
+        );
+        let coefficients: [f32; 64] =
+            std::array::from_fn(|index| ((index * 337 + 11) % 2047) as f32 - 1023.5);
+        assert_eq!(
+            quantize_block(&coefficients, &reciprocals),
+            quantize_block_scalar(&coefficients, &reciprocals)
+        );
+    }
+}
@@ -0,0 +1,73 @@
+#[cfg(feature = "clap")]
+use clap::{builder::PossibleValue, ValueEnum};
+
+use super::{HuffmanTableMode, QuantizationTablePreset};
+use crate::{cosine_transform::DCTAlgorithm, image::subsampling::ChromaSubsamplingPreset};
+
+/// Meta preset, which selects the DCT algorithm, the Huffman table mode, the chroma subsampling
+/// and the quantization table preset at once, trading encoding speed against quality and size.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Tune {
+    /// Skips the counting pass of the symbols with the standard Huffman tables.
+    Fastest,
+    /// Defaults of the encoder.
+    #[default]
+    Balanced,
+    /// Keeps the full chroma resolution, transforms with the exact separated DCT and quantizes
+    /// with the tables of ImageMagick, which mozjpeg uses by default.
+    Best,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for Tune {
+    fn value_variants<'a>() -> &'a [Self] {
+        Self::variants()
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()))
+    }
+}
+
+impl Tune {
+    pub fn variants() -> &'static [Self] {
+        &[Self::Fastest, Self::Balanced, Self::Best]
+    }
+
+    /// Name of the tune, as accepted on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Fastest => "fastest",
+            Self::Balanced => "balanced",
+            Self::Best => "best",
+        }
+    }
+
+    pub fn dct_algorithm(&self) -> DCTAlgorithm {
+        match self {
+            Self::Fastest | Self::Balanced => DCTAlgorithm::Auto,
+            Self::Best => DCTAlgorithm::Separated,
+        }
+    }
+
+    pub fn huffman_table_mode(&self) -> HuffmanTableMode {
+        match self {
+            Self::Fastest => HuffmanTableMode::Standard,
+            Self::Balanced | Self::Best => HuffmanTableMode::Optimized,
+        }
+    }
+
+    pub fn chroma_subsampling_preset(&self) -> ChromaSubsamplingPreset {
+        match self {
+            Self::Fastest | Self::Balanced => ChromaSubsamplingPreset::P420,
+            Self::Best => ChromaSubsamplingPreset::P444,
+        }
+    }
+
+    pub fn quantization_table_preset(&self) -> QuantizationTablePreset {
+        match self {
+            Self::Fastest | Self::Balanced => QuantizationTablePreset::Specification,
+            Self::Best => QuantizationTablePreset::ImageMagick,
+        }
+    }
+}
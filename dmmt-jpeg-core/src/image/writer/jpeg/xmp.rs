@@ -0,0 +1,54 @@
+use crate::Error;
+
+const XMP_NAMESPACE: &[u8; 29] = b"http://ns.adobe.com/xap/1.0/\0";
+
+/// Largest packet, which fits into a single APP1 segment together with the namespace header.
+pub const MAX_XMP_PACKET_LENGTH: usize = u16::MAX as usize - 2 - XMP_NAMESPACE.len();
+
+/// XMP packet, embedded as APP1 segment with the standard XMP namespace header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct XmpPacket(String);
+
+impl XmpPacket {
+    pub fn new(packet: String) -> crate::Result<Self> {
+        if packet.len() > MAX_XMP_PACKET_LENGTH {
+            return Err(Error::XmpPacketTooLarge(packet.len()));
+        }
+        Ok(Self(packet))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn to_segment_content(&self) -> Vec<u8> {
+        XMP_NAMESPACE
+            .iter()
+            .chain(self.0.as_bytes())
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::Error;
+
+    use super::{XmpPacket, MAX_XMP_PACKET_LENGTH};
+
+    #[test]
+    fn create_segment_content() {
+        let packet = XmpPacket::new("<x:xmpmeta/>".to_string()).unwrap();
+        let content = packet.to_segment_content();
+        assert_eq!(&content[..29], b"http://ns.adobe.com/xap/1.0/\0");
+        assert_eq!(&content[29..], b"<x:xmpmeta/>");
+    }
+
+    #[test]
+    fn reject_too_large_packet() {
+        let packet = "a".repeat(MAX_XMP_PACKET_LENGTH + 1);
+        let result = XmpPacket::new(packet);
+        assert!(matches!(result, Err(Error::XmpPacketTooLarge(_))));
+        assert!(XmpPacket::new("a".repeat(MAX_XMP_PACKET_LENGTH)).is_ok());
+    }
+}
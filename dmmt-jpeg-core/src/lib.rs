@@ -0,0 +1,112 @@
+use std::{
+    io::{Read, Write},
+    thread,
+};
+
+pub use color::{ColorMatrix, ColorRange, RGBColorFormat};
+pub use error::{Error, TextPosition};
+use image::{
+    reader::y4m::Y4MStreamReader,
+    subsampling::ChromaSubsamplingPreset,
+    writer::jpeg::{JpegImageWriter, JpegTransformationOptions},
+    Image, ImageWriter,
+};
+use threadpool::ThreadPool;
+pub use warning::{Warning, Warnings};
+
+pub mod binary_stream;
+mod color;
+pub mod cosine_transform;
+mod error;
+pub mod huffman;
+pub mod image;
+mod logger;
+mod warning;
+
+/// Quality of [`encode_rgb8_default`].
+pub const DEFAULT_QUALITY: u8 = 85;
+
+pub type Result<T> = std::result::Result<T, error::Error>;
+
+pub trait BitPattern {
+    fn to_bytes(&self) -> Box<[u8]>;
+    fn bit_len(&self) -> usize;
+
+    /// The bits of the pattern right aligned in a word, which are written without allocating,
+    /// or `None`, if the pattern is longer than 32 bits.
+    fn to_u32(&self) -> Option<u32> {
+        let bit_len = self.bit_len();
+        if bit_len > u32::BITS as usize {
+            return None;
+        }
+        let bytes = self.to_bytes();
+        let value = bytes
+            .iter()
+            .take(bit_len.div_ceil(8))
+            .fold(0_u64, |value, &byte| (value << 8) | byte as u64);
+        Some((value >> ((8 - bit_len % 8) % 8)) as u32)
+    }
+}
+
+fn available_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Encodes interleaved 8 bit RGB samples in row major order as JPEG into the writer.
+///
+/// The buffer must hold exactly `width * height * 3` samples. The transformation runs on a
+/// threadpool with one thread per available CPU. Returns the warnings of the encode.
+pub fn encode_rgb8(
+    width: u16,
+    height: u16,
+    samples: &[u8],
+    options: &JpegTransformationOptions,
+    writer: &mut impl Write,
+) -> Result<Warnings> {
+    let image = Image::from_rgb8(width, height, samples)?;
+    let threadpool = ThreadPool::new(available_threads());
+    let mut image_writer = JpegImageWriter::new(writer, &image, options, &threadpool);
+    image_writer.write_image()?;
+    Ok(image_writer.warnings().clone())
+}
+
+/// Encodes interleaved 8 bit RGB samples in row major order as baseline JPEG with the defaults,
+/// which serve most use cases: quality 85, 4:2:0 chroma subsampling, Huffman tables optimized
+/// for the image and one thread per available CPU.
+///
+/// This is the fast path of the encoder. The output buffer is allocated once with an estimate of
+/// the file size, so it rarely grows while the image is written.
+pub fn encode_rgb8_default(width: u16, height: u16, samples: &[u8]) -> Result<Vec<u8>> {
+    let options = JpegTransformationOptions {
+        chroma_subsampling_preset: ChromaSubsamplingPreset::P420,
+        quality: Some(DEFAULT_QUALITY),
+        ..Default::default()
+    };
+    let mut output = Vec::with_capacity(estimate_default_file_size(width, height));
+    encode_rgb8(width, height, samples, &options, &mut output)?;
+    Ok(output)
+}
+
+/// Photographs take about two bits per pixel at the default quality, plus the headers.
+fn estimate_default_file_size(width: u16, height: u16) -> usize {
+    const HEADER_SIZE: usize = 1024;
+    width as usize * height as usize / 4 + HEADER_SIZE
+}
+
+/// Encodes each frame of a YUV4MPEG2 stream as JPEG into the writer, which is returned for the
+/// index of the frame. The frames skip the color conversion. Returns the number of frames.
+pub fn encode_y4m_frames<W: Write>(
+    reader: impl Read,
+    options: &JpegTransformationOptions,
+    mut writer_for_frame: impl FnMut(usize) -> Result<W>,
+) -> Result<usize> {
+    let threadpool = ThreadPool::new(available_threads());
+    let mut number_of_frames = 0;
+    for frame in Y4MStreamReader::new(reader) {
+        let frame = frame?;
+        let writer = writer_for_frame(number_of_frames)?;
+        JpegImageWriter::from_ycbcr_image(writer, &frame, options, &threadpool).write_image()?;
+        number_of_frames += 1;
+    }
+    Ok(number_of_frames)
+}
@@ -1,10 +1,7 @@
-#[ctor::ctor]
-fn init() {
-    use log4rs;
-    log4rs::init_file("log4rs.yaml", Default::default()).unwrap();
-}
-
 pub fn log_segment(marker: &[u8], content: &[u8], segment_length: &[u8]) {
+    if !log::log_enabled!(log::Level::Info) {
+        return;
+    }
     fn get_byte_array(bytes: &[u8]) -> Vec<String> {
         bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
     }
@@ -0,0 +1,141 @@
+use std::fmt::Display;
+
+use crate::cosine_transform::DCTAlgorithm;
+use crate::image::writer::jpeg::HuffmanTableMode;
+
+/// Non-fatal issue of an encode. The image is written anyway, but differs from what the caller
+/// might expect.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Warning {
+    PaddedDimensions {
+        width: u16,
+        height: u16,
+        padded_width: u32,
+        padded_height: u32,
+    },
+    DCTAlgorithmReplaced {
+        requested: DCTAlgorithm,
+        used: DCTAlgorithm,
+    },
+    QualityClamped {
+        requested: u8,
+        used: u8,
+    },
+    HuffmanTableModeReplaced {
+        requested: HuffmanTableMode,
+        used: HuffmanTableMode,
+    },
+    HuffmanTablesIncomplete,
+    TargetSizeExceeded {
+        target_size: u64,
+        size: u64,
+    },
+    /// The option needs the coefficients of the whole image, so the memory of the encode grows
+    /// with the image instead of being bounded by the bands in flight.
+    WholeImageTransformed {
+        pixels: u64,
+        option: &'static str,
+    },
+    ReplacedNaNSamples(usize),
+    ClampedSamples(usize),
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PaddedDimensions {
+                width,
+                height,
+                padded_width,
+                padded_height,
+            } => write!(
+                f,
+                "Image of {}x{} pixels is padded to {}x{} pixels of whole MCUs",
+                width, height, padded_width, padded_height
+            ),
+            Self::DCTAlgorithmReplaced { requested, used } => write!(
+                f,
+                "DCT algorithm {:?} is replaced by {:?}, which supports high precision",
+                requested, used
+            ),
+            Self::QualityClamped { requested, used } => write!(
+                f,
+                "Quality {} is out of range 1 to 100 and clamped to {}",
+                requested, used
+            ),
+            Self::HuffmanTableModeReplaced { requested, used } => write!(
+                f,
+                "{} Huffman tables are replaced by {} tables, which support the sample precision and the maximum code length",
+                requested.name(),
+                used.name()
+            ),
+            Self::HuffmanTablesIncomplete => write!(
+                f,
+                "Huffman tables lack symbols of the image or exceed the maximum code length and are replaced by optimized tables"
+            ),
+            Self::TargetSizeExceeded { target_size, size } => write!(
+                f,
+                "Image of {} bytes at quality 1 exceeds the target size of {} bytes",
+                size, target_size
+            ),
+            Self::WholeImageTransformed { pixels, option } => write!(
+                f,
+                "{} holds the coefficients of all {} pixels of the image in memory",
+                option, pixels
+            ),
+            Self::ReplacedNaNSamples(count) => {
+                write!(f, "{} samples are not a number and replaced by 0", count)
+            }
+            Self::ClampedSamples(count) => {
+                write!(f, "{} samples are out of range and clamped", count)
+            }
+        }
+    }
+}
+
+/// Warnings collected while encoding, in the order they occurred.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Warnings {
+    warnings: Vec<Warning>,
+}
+
+impl Warnings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, warning: Warning) {
+        log::warn!("{}", warning);
+        self.warnings.push(warning);
+    }
+
+    pub fn len(&self) -> usize {
+        self.warnings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Warning> {
+        self.warnings.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Warnings {
+    type Item = &'a Warning;
+    type IntoIter = std::slice::Iter<'a, Warning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.warnings.iter()
+    }
+}
+
+impl IntoIterator for Warnings {
+    type Item = Warning;
+    type IntoIter = std::vec::IntoIter<Warning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.warnings.into_iter()
+    }
+}
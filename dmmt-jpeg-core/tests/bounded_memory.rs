@@ -0,0 +1,64 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use dmmt_jpeg_core::image::writer::jpeg::{JpegImageWriter, JpegTransformationOptions};
+use dmmt_jpeg_core::image::{Image, ImageWriter};
+use dmmt_jpeg_core::RGBColorFormat;
+use threadpool::ThreadPool;
+
+/// Allocator, which tracks the allocated bytes and their peak.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = unsafe { System.alloc(layout) };
+        if !pointer.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::Relaxed);
+        }
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(pointer, layout) };
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Bytes, the memory of an encode may grow by, independent of the size of the image: the bands
+/// in flight, the kept bands of the counting pass and the pooled buffers.
+const MAX_PEAK_GROWTH: usize = 24 << 20;
+
+/// Only the writer is measured, the source image is decoded as a whole before.
+#[test]
+fn encode_with_memory_bounded_by_bands() {
+    let (width, height) = (4096_u16, 2048_u16);
+    let samples: Vec<u8> = (0..width as usize * height as usize * 3)
+        .map(|index| (index * 7 % 251) as u8)
+        .collect();
+    let image = Image::from_rgb8(width, height, &samples).unwrap();
+    drop(samples);
+    let source_size = width as usize * height as usize * size_of::<RGBColorFormat<f32>>();
+    let options = JpegTransformationOptions::default();
+    let threadpool = ThreadPool::new(2);
+    let mut writer = JpegImageWriter::new(io::sink(), &image, &options, &threadpool);
+
+    let baseline = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    writer.write_image().unwrap();
+    let peak_growth = PEAK.load(Ordering::Relaxed) - baseline;
+    // a copy of the source of 96 MiB or the blocks of the whole image exceed the bound
+    assert!(
+        peak_growth < MAX_PEAK_GROWTH,
+        "Peak memory grew by {} bytes while encoding {} bytes of samples",
+        peak_growth,
+        source_size
+    );
+}
@@ -0,0 +1,557 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use dmmt_jpeg_core::image::{
+    reader::jpeg::JPEGImageReader,
+    subsampling::ChromaSubsamplingPreset,
+    writer::jpeg::{JpegImageWriter, JpegTransformationOptions, QuantizationTablePreset},
+    Image, ImageReader, ImageWriter,
+};
+use dmmt_jpeg_core::{encode_rgb8, encode_y4m_frames};
+use threadpool::ThreadPool;
+
+const WIDTH: u16 = 37;
+const HEIGHT: u16 = 29;
+/// The checker pattern and the products of the coordinates in the blue channel are high
+/// frequency content, so the synthetic image compresses with a low PSNR even at P444.
+const MINIMUM_PSNR: f64 = 19.0;
+
+fn create_synthetic_image() -> Vec<u8> {
+    (0..HEIGHT as usize)
+        .flat_map(|y| {
+            (0..WIDTH as usize).flat_map(move |x| {
+                let checker = if (x / 4 + y / 4) % 2 == 0 { 0 } else { 64 };
+                [
+                    (x * 255 / WIDTH as usize) as u8,
+                    (y * 255 / HEIGHT as usize) as u8,
+                    (checker + (x * y) % 128) as u8,
+                ]
+            })
+        })
+        .collect()
+}
+
+struct Segment<'a> {
+    marker: u8,
+    content: &'a [u8],
+}
+
+/// Splits the file into the segments in front of the scan and the entropy coded data.
+fn split_segments(data: &[u8]) -> (Vec<Segment<'_>>, &[u8]) {
+    assert_eq!(data[..2], [0xFF, 0xD8], "start of image marker missing");
+    assert_eq!(
+        data[data.len() - 2..],
+        [0xFF, 0xD9],
+        "end of image marker missing"
+    );
+    let mut segments = Vec::new();
+    let mut position = 2;
+    loop {
+        assert_eq!(data[position], 0xFF, "marker expected at {}", position);
+        let marker = data[position + 1];
+        let length = u16::from_be_bytes([data[position + 2], data[position + 3]]) as usize;
+        let content = &data[position + 4..position + 2 + length];
+        segments.push(Segment { marker, content });
+        position += 2 + length;
+        if marker == 0xDA {
+            return (segments, &data[position..data.len() - 2]);
+        }
+    }
+}
+
+struct HuffmanTable {
+    codes: HashMap<(u8, u16), u8>,
+}
+
+impl HuffmanTable {
+    /// Assigns canonical codes and asserts, the code lengths form a valid prefix code.
+    fn new(content: &[u8]) -> Self {
+        let counts = &content[..16];
+        let symbols = &content[16..];
+        let number_of_symbols: usize = counts.iter().map(|&c| c as usize).sum();
+        assert_eq!(number_of_symbols, symbols.len(), "symbol count mismatch");
+        let mut codes = HashMap::new();
+        let mut code = 0_u32;
+        let mut symbol_index = 0;
+        for (length_index, &count) in counts.iter().enumerate() {
+            for _ in 0..count {
+                assert!(code < 1 << (length_index + 1), "code lengths overflow");
+                codes.insert(
+                    ((length_index + 1) as u8, code as u16),
+                    symbols[symbol_index],
+                );
+                symbol_index += 1;
+                code += 1;
+            }
+            code <<= 1;
+        }
+        Self { codes }
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bit: u8,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            bit: 0,
+        }
+    }
+
+    fn read_bit(&mut self) -> u16 {
+        let byte = self.data[self.position];
+        let value = (byte >> (7 - self.bit)) & 1;
+        self.bit += 1;
+        if self.bit == 8 {
+            self.bit = 0;
+            self.position += if byte == 0xFF { 2 } else { 1 };
+        }
+        value as u16
+    }
+
+    fn read_bits(&mut self, count: u8) -> u16 {
+        (0..count).fold(0, |value, _| value << 1 | self.read_bit())
+    }
+
+    fn decode(&mut self, table: &HuffmanTable) -> u8 {
+        let mut code = 0;
+        for length in 1..=16 {
+            code = code << 1 | self.read_bit();
+            if let Some(&symbol) = table.codes.get(&(length, code)) {
+                return symbol;
+            }
+        }
+        panic!("invalid huffman code at byte {}", self.position);
+    }
+
+    /// Skips the one bits, which fill up the last byte of the scan.
+    fn skip_padding(&mut self) {
+        while self.bit != 0 {
+            assert_eq!(self.read_bit(), 1, "padding bits must be ones");
+        }
+    }
+
+    fn skip_block(&mut self, dc_table: &HuffmanTable, ac_table: &HuffmanTable) {
+        let dc_size = self.decode(dc_table);
+        assert!(dc_size <= 11, "dc category {} out of range", dc_size);
+        self.read_bits(dc_size);
+        let mut index = 1;
+        while index < 64 {
+            let symbol = self.decode(ac_table);
+            let (run, size) = (symbol >> 4, symbol & 0x0F);
+            if size == 0 {
+                if run == 15 {
+                    index += 16;
+                    continue;
+                }
+                break;
+            }
+            index += run as usize;
+            self.read_bits(size);
+            index += 1;
+        }
+        assert!(index <= 64, "block overflows 64 coefficients");
+    }
+}
+
+fn verify_structure(
+    data: &[u8],
+    subsampling: ChromaSubsamplingPreset,
+    quantization: QuantizationTablePreset,
+    restart_interval: Option<u16>,
+) {
+    let (segments, scan_data) = split_segments(data);
+    let count = |marker: u8| segments.iter().filter(|s| s.marker == marker).count();
+    assert_eq!(count(0xDB), 2, "two quantization tables expected");
+    assert_eq!(count(0xC4), 4, "four huffman tables expected");
+    // Tables with steps beyond 8 bit need an extended sequential frame
+    let frame_marker = if quantization.to_pair().is_baseline() {
+        0xC0
+    } else {
+        0xC1
+    };
+    assert_eq!(count(frame_marker), 1, "one frame expected");
+    let restart_definition = segments.iter().find(|s| s.marker == 0xDD);
+    assert_eq!(
+        restart_definition.map(|s| u16::from_be_bytes([s.content[0], s.content[1]])),
+        restart_interval,
+        "restart interval definition"
+    );
+
+    let frame = segments
+        .iter()
+        .find(|s| s.marker == frame_marker)
+        .unwrap()
+        .content;
+    assert_eq!(u16::from_be_bytes([frame[1], frame[2]]), HEIGHT);
+    assert_eq!(u16::from_be_bytes([frame[3], frame[4]]), WIDTH);
+    let horizontal = subsampling.horizontal_rate();
+    let vertical = subsampling.vertical_rate();
+    assert_eq!(
+        frame[7],
+        horizontal << 4 | vertical,
+        "luma sampling factors"
+    );
+
+    let tables: HashMap<u8, HuffmanTable> = segments
+        .iter()
+        .filter(|s| s.marker == 0xC4)
+        .map(|s| (s.content[0], HuffmanTable::new(&s.content[1..])))
+        .collect();
+    let scan = segments.last().unwrap().content;
+    let component_tables: Vec<(&HuffmanTable, &HuffmanTable)> = (0..scan[0] as usize)
+        .map(|i| {
+            let selector = scan[2 + i * 2];
+            (
+                &tables[&(selector >> 4)],
+                &tables[&(0x10 | selector & 0x0F)],
+            )
+        })
+        .collect();
+
+    assert!(
+        scan_data.windows(2).all(|pair| pair[0] != 0xFF
+            || pair[1] == 0x00
+            || restart_interval.is_some() && (0xD0..=0xD7).contains(&pair[1])),
+        "unstuffed 0xFF in entropy coded data"
+    );
+
+    let mcu_width = horizontal as usize * 8;
+    let mcu_height = vertical as usize * 8;
+    let number_of_mcus =
+        (WIDTH as usize).div_ceil(mcu_width) * (HEIGHT as usize).div_ceil(mcu_height);
+    let luma_blocks_per_mcu = (horizontal * vertical) as usize;
+    let mcus_per_interval = restart_interval.map_or(number_of_mcus, usize::from);
+    let mut reader = BitReader::new(scan_data);
+    for mcu in 0..number_of_mcus {
+        if mcu > 0 && mcu % mcus_per_interval == 0 {
+            reader.skip_padding();
+            let restart_index = (mcu / mcus_per_interval - 1) % 8;
+            assert_eq!(
+                reader.data[reader.position..reader.position + 2],
+                [0xFF, 0xD0 + restart_index as u8],
+                "restart marker expected before mcu {}",
+                mcu
+            );
+            reader.position += 2;
+        }
+        let (dc, ac) = component_tables[0];
+        for _ in 0..luma_blocks_per_mcu {
+            reader.skip_block(dc, ac);
+        }
+        for &(dc, ac) in &component_tables[1..] {
+            reader.skip_block(dc, ac);
+        }
+    }
+    reader.skip_padding();
+    assert_eq!(
+        reader.position,
+        scan_data.len(),
+        "data left after the last MCU with {:?} and {:?}",
+        subsampling,
+        quantization
+    );
+}
+
+/// Peak signal to noise ratio between two buffers of 8 bit samples in dB.
+fn psnr(expected: &[u8], actual: &[u8]) -> f64 {
+    assert_eq!(expected.len(), actual.len(), "sample count mismatch");
+    let squared_error: f64 = expected
+        .iter()
+        .zip(actual)
+        .map(|(&e, &a)| (e as f64 - a as f64).powi(2))
+        .sum();
+    let mean_squared_error = squared_error / expected.len() as f64;
+    10.0 * (255.0_f64.powi(2) / mean_squared_error).log10()
+}
+
+/// Decodes the file and asserts, it reproduces the source samples with at least the PSNR.
+fn verify_round_trip(data: &[u8], samples: &[u8], minimum_psnr: f64, description: &str) {
+    let decoded = JPEGImageReader::new(data)
+        .read_image()
+        .unwrap_or_else(|e| panic!("Decoding {} failed: {}", description, e));
+    assert_eq!(decoded.width(), WIDTH);
+    assert_eq!(decoded.height(), HEIGHT);
+    let psnr = psnr(samples, &decoded.to_rgb8());
+    assert!(
+        psnr >= minimum_psnr,
+        "PSNR of {} is {:.2} dB, expected at least {} dB",
+        description,
+        psnr,
+        minimum_psnr
+    );
+}
+
+#[test]
+fn test_encode_all_preset_combinations() {
+    let samples = create_synthetic_image();
+    for &subsampling in ChromaSubsamplingPreset::variants() {
+        for &quantization in QuantizationTablePreset::variants() {
+            let options = JpegTransformationOptions {
+                chroma_subsampling_preset: subsampling,
+                quantization_table_preset: quantization,
+                ..Default::default()
+            };
+            let mut output = Vec::new();
+            encode_rgb8(WIDTH, HEIGHT, &samples, &options, &mut output).unwrap_or_else(|e| {
+                panic!(
+                    "Encoding with {:?} and {:?} failed: {}",
+                    subsampling, quantization, e
+                )
+            });
+            verify_structure(&output, subsampling, quantization, None);
+            verify_round_trip(
+                &output,
+                &samples,
+                MINIMUM_PSNR,
+                &format!("{:?} and {:?}", subsampling, quantization),
+            );
+        }
+    }
+}
+
+#[test]
+fn test_encode_high_precision_with_all_subsampling_presets() {
+    let samples = create_synthetic_image();
+    let quantization = QuantizationTablePreset::Specification;
+    for &subsampling in ChromaSubsamplingPreset::variants() {
+        let options = JpegTransformationOptions {
+            chroma_subsampling_preset: subsampling,
+            quantization_table_preset: quantization,
+            high_precision: true,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        encode_rgb8(WIDTH, HEIGHT, &samples, &options, &mut output).unwrap_or_else(|e| {
+            panic!(
+                "High precision encoding with {:?} failed: {}",
+                subsampling, e
+            )
+        });
+        verify_structure(&output, subsampling, quantization, None);
+        verify_round_trip(
+            &output,
+            &samples,
+            MINIMUM_PSNR,
+            &format!("high precision {:?}", subsampling),
+        );
+    }
+}
+
+#[test]
+fn test_encode_with_restart_interval() {
+    let samples = create_synthetic_image();
+    let quantization = QuantizationTablePreset::Specification;
+    let threadpool = ThreadPool::new(2);
+    for &subsampling in ChromaSubsamplingPreset::variants() {
+        for restart_interval in [1, 3] {
+            let options = JpegTransformationOptions {
+                chroma_subsampling_preset: subsampling,
+                restart_interval: Some(restart_interval),
+                ..Default::default()
+            };
+            let image = Image::from_rgb8(WIDTH, HEIGHT, &samples).unwrap();
+            let mut image_writer = JpegImageWriter::new(Vec::new(), &image, &options, &threadpool);
+            image_writer.write_image().unwrap();
+            let offsets = image_writer.restart_interval_offsets().to_vec();
+            let output = image_writer.into_inner();
+            verify_structure(&output, subsampling, quantization, Some(restart_interval));
+            verify_round_trip(
+                &output,
+                &samples,
+                MINIMUM_PSNR,
+                &format!(
+                    "{:?} with restart interval {}",
+                    subsampling, restart_interval
+                ),
+            );
+
+            let (segments, scan_data) = split_segments(&output);
+            let scan_offset = (output.len() - 2 - scan_data.len()) as u64;
+            assert_eq!(offsets[0], scan_offset, "first interval starts the scan");
+            for &offset in &offsets[1..] {
+                let offset = offset as usize;
+                assert_eq!(
+                    output[offset - 2],
+                    0xFF,
+                    "offset must follow a restart marker"
+                );
+                assert!((0xD0..=0xD7).contains(&output[offset - 1]));
+            }
+            let mcus = (WIDTH as usize).div_ceil(subsampling.horizontal_rate() as usize * 8)
+                * (HEIGHT as usize).div_ceil(subsampling.vertical_rate() as usize * 8);
+            assert_eq!(offsets.len(), mcus.div_ceil(restart_interval as usize));
+            assert!(segments.iter().any(|s| s.marker == 0xDD));
+        }
+    }
+}
+
+/// Writes into one of the shared frame buffers.
+struct FrameWriter {
+    frames: Rc<RefCell<Vec<Vec<u8>>>>,
+    index: usize,
+}
+
+impl Write for FrameWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.frames.borrow_mut()[self.index].write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_encode_y4m_frames() {
+    let mut stream = format!("YUV4MPEG2 W{} H{} F25:1 C420jpeg\n", WIDTH, HEIGHT).into_bytes();
+    let luma_length = WIDTH as usize * HEIGHT as usize;
+    let chroma_length = (WIDTH as usize).div_ceil(2) * (HEIGHT as usize).div_ceil(2);
+    for frame in 0..3 {
+        stream.extend(b"FRAME\n");
+        stream.extend((0..luma_length).map(|i| (16 + (i * 7 + frame * 40) % 220) as u8));
+        stream.extend((0..chroma_length * 2).map(|i| (16 + (i * 3) % 225) as u8));
+    }
+    let options = JpegTransformationOptions::default();
+    let frames = Rc::new(RefCell::new(Vec::new()));
+    let number_of_frames = encode_y4m_frames(&stream[..], &options, |index| {
+        frames.borrow_mut().push(Vec::new());
+        Ok(FrameWriter {
+            frames: Rc::clone(&frames),
+            index,
+        })
+    })
+    .unwrap();
+    assert_eq!(number_of_frames, 3);
+    let frames = frames.borrow();
+    for output in frames.iter() {
+        verify_structure(
+            output,
+            options.chroma_subsampling_preset,
+            options.quantization_table_preset,
+            None,
+        );
+    }
+    assert_ne!(frames[0], frames[1]);
+}
+
+#[test]
+fn test_symbol_stats_count_every_block() {
+    let samples = create_synthetic_image();
+    let image = Image::from_rgb8(WIDTH, HEIGHT, &samples).unwrap();
+    let threadpool = ThreadPool::new(2);
+    for &subsampling in ChromaSubsamplingPreset::variants() {
+        let options = JpegTransformationOptions {
+            chroma_subsampling_preset: subsampling,
+            collect_symbol_stats: true,
+            ..Default::default()
+        };
+        let mut image_writer = JpegImageWriter::new(Vec::new(), &image, &options, &threadpool);
+        image_writer.write_image().unwrap();
+        let stats = image_writer.symbol_stats().unwrap();
+        let mut lines = stats.lines();
+        assert_eq!(
+            lines.next(),
+            Some("component,class,symbol,run_length,size,frequency")
+        );
+
+        let layout = subsampling.sampling_layout();
+        let mcus = WIDTH.div_ceil(layout.mcu_width()) as usize
+            * HEIGHT.div_ceil(layout.mcu_height()) as usize;
+        let mut dc_symbols = HashMap::new();
+        for line in lines {
+            let columns: Vec<&str> = line.split(',').collect();
+            assert_eq!(columns.len(), 6, "{}", line);
+            if columns[1] == "DC" {
+                *dc_symbols.entry(columns[0]).or_insert(0) += columns[5].parse::<usize>().unwrap();
+            }
+        }
+        assert_eq!(dc_symbols["Y"], mcus * layout.luma_factors.blocks());
+        assert_eq!(dc_symbols["Cb"], mcus, "{:?}", subsampling);
+        assert_eq!(dc_symbols["Cr"], mcus, "{:?}", subsampling);
+    }
+}
+
+#[test]
+fn test_encode_grayscale() {
+    let samples = create_synthetic_image();
+    let image = Image::from_rgb8(WIDTH, HEIGHT, &samples).unwrap();
+    let gray_samples: Vec<u8> = samples
+        .chunks_exact(3)
+        .flat_map(|rgb| {
+            let luma = 0.299 * rgb[0] as f32 + 0.587 * rgb[1] as f32 + 0.114 * rgb[2] as f32;
+            [luma.round() as u8; 3]
+        })
+        .collect();
+    let threadpool = ThreadPool::new(2);
+    for &subsampling in ChromaSubsamplingPreset::variants() {
+        for restart_interval in [None, Some(5)] {
+            let options = JpegTransformationOptions {
+                chroma_subsampling_preset: subsampling,
+                grayscale: true,
+                restart_interval,
+                ..Default::default()
+            };
+            let mut image_writer = JpegImageWriter::new(Vec::new(), &image, &options, &threadpool);
+            image_writer.write_image().unwrap();
+            let output = image_writer.into_inner();
+
+            let (segments, _) = split_segments(&output);
+            let count = |marker| segments.iter().filter(|s| s.marker == marker).count();
+            assert_eq!(count(0xDB), 1, "only the luma quantization table");
+            assert_eq!(count(0xC4), 2, "only the luma Huffman tables");
+            let frame = segments.iter().find(|s| s.marker == 0xC0).unwrap();
+            assert_eq!(frame.content[5..], [0x01, 0x01, 0x11, 0x00]);
+            verify_round_trip(
+                &output,
+                &gray_samples,
+                MINIMUM_PSNR,
+                &format!("grayscale {:?} {:?}", subsampling, restart_interval),
+            );
+        }
+    }
+}
+
+/// Every preset pads tiny images to a whole MCU, so the subsampled chroma channels hold one
+/// block, even if the image is smaller than a single block.
+#[test]
+fn test_encode_tiny_images() {
+    for &subsampling in ChromaSubsamplingPreset::variants() {
+        for grayscale in [false, true] {
+            for width in 1..=8_u16 {
+                for height in 1..=8_u16 {
+                    let samples: Vec<u8> = (0..width as usize * height as usize * 3)
+                        .map(|i| (i * 37 % 256) as u8)
+                        .collect();
+                    let options = JpegTransformationOptions {
+                        chroma_subsampling_preset: subsampling,
+                        grayscale,
+                        ..Default::default()
+                    };
+                    let mut output = Vec::new();
+                    encode_rgb8(width, height, &samples, &options, &mut output).unwrap();
+                    let (_, scan_data) = split_segments(&output);
+                    assert!(!scan_data.is_empty());
+                    let decoded = JPEGImageReader::new(output.as_slice())
+                        .read_image()
+                        .unwrap();
+                    assert_eq!(
+                        (decoded.width(), decoded.height()),
+                        (width, height),
+                        "{:?} grayscale {}",
+                        subsampling,
+                        grayscale
+                    );
+                }
+            }
+        }
+    }
+}
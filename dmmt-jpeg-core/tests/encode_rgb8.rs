@@ -0,0 +1,460 @@
+use dmmt_jpeg_core::cosine_transform::DCTAlgorithm;
+use dmmt_jpeg_core::image::writer::jpeg::{
+    consts::ANNEX_K_LUMINANCE_DC_HUFFMAN_TABLE, exif::ExifMetadata, thumbnail::ThumbnailSize,
+    xmp::XmpPacket, HuffmanTableMode, JpegTransformationOptions,
+};
+use dmmt_jpeg_core::image::{reader::jpeg::JPEGImageReader, ImageReader};
+use dmmt_jpeg_core::{encode_rgb8, encode_rgb8_default, Error, Warning, DEFAULT_QUALITY};
+
+#[test]
+fn test_encode_rgb8() {
+    let width = 19;
+    let height = 11;
+    let samples: Vec<u8> = (0..width * height * 3).map(|i| (i % 256) as u8).collect();
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions::default();
+    encode_rgb8(width, height, &samples, &options, &mut output).expect("Encoding failed");
+    assert_eq!(output[..2], [0xFF, 0xD8], "Start of image marker missing");
+    assert_eq!(
+        output[output.len() - 2..],
+        [0xFF, 0xD9],
+        "End of image marker missing"
+    );
+}
+
+#[test]
+fn test_encode_rgb8_with_embedded_settings() {
+    let samples = [128; 8 * 8 * 3];
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions {
+        embed_settings: true,
+        ..Default::default()
+    };
+    encode_rgb8(8, 8, &samples, &options, &mut output).expect("Encoding failed");
+    let comment = options.settings_json();
+    let position = output
+        .windows(2)
+        .position(|marker| marker == [0xFF, 0xFE])
+        .expect("Comment segment missing");
+    let length = u16::from_be_bytes([output[position + 2], output[position + 3]]) as usize;
+    assert_eq!(length, comment.len() + 2);
+    assert_eq!(
+        &output[position + 4..position + 2 + length],
+        comment.as_bytes()
+    );
+}
+
+#[test]
+fn test_encode_rgb8_with_wrong_buffer_size() {
+    let samples = [0; 11];
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions::default();
+    let result = encode_rgb8(2, 2, &samples, &options, &mut output);
+    assert!(matches!(
+        result,
+        Err(Error::MismatchOfSizeBetweenDimensionsAndBuffer(12, 11))
+    ));
+    assert!(output.is_empty());
+}
+
+#[test]
+fn test_encode_rgb8_empty_image() {
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions::default();
+    for (width, height) in [(0, 0), (0, 4), (4, 0)] {
+        let result = encode_rgb8(width, height, &[], &options, &mut output);
+        assert!(matches!(result, Err(Error::EmptyImage(w, h)) if (w, h) == (width, height)));
+    }
+    assert!(output.is_empty());
+}
+
+#[test]
+fn test_encode_rgb8_without_warnings() {
+    let samples = [128; 16 * 16 * 3];
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions::default();
+    let warnings = encode_rgb8(16, 16, &samples, &options, &mut output).unwrap();
+    assert!(warnings.is_empty(), "Unexpected warnings {:?}", warnings);
+}
+
+#[test]
+fn test_encode_rgb8_warns_about_padding() {
+    let samples = [128; 19 * 11 * 3];
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions::default();
+    let warnings = encode_rgb8(19, 11, &samples, &options, &mut output).unwrap();
+    let expected = Warning::PaddedDimensions {
+        width: 19,
+        height: 11,
+        padded_width: 32,
+        padded_height: 16,
+    };
+    assert_eq!(warnings.iter().collect::<Vec<_>>(), [&expected]);
+}
+
+#[test]
+fn test_encode_rgb8_warns_about_adjusted_options() {
+    let samples = [128; 16 * 16 * 3];
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions {
+        quality: Some(150),
+        high_precision: true,
+        dct_algorithm: DCTAlgorithm::Simd,
+        ..Default::default()
+    };
+    let warnings = encode_rgb8(16, 16, &samples, &options, &mut output).unwrap();
+    let expected = [
+        Warning::QualityClamped {
+            requested: 150,
+            used: 100,
+        },
+        Warning::DCTAlgorithmReplaced {
+            requested: DCTAlgorithm::Simd,
+            used: DCTAlgorithm::Arai,
+        },
+    ];
+    assert_eq!(warnings.into_iter().collect::<Vec<_>>(), expected);
+}
+
+/// Returns the contents of the segments with the marker in the order of the file.
+fn segment_contents(output: &[u8], marker: u8) -> Vec<&[u8]> {
+    let mut contents = Vec::new();
+    let mut position = 2;
+    while output[position] == 0xFF && output[position + 1] != 0xDA {
+        let length = u16::from_be_bytes([output[position + 2], output[position + 3]]) as usize;
+        if output[position + 1] == marker {
+            contents.push(&output[position + 4..position + 2 + length]);
+        }
+        position += 2 + length;
+    }
+    contents
+}
+
+#[test]
+fn test_encode_rgb8_with_exif_and_xmp() {
+    let samples = [128; 8 * 8 * 3];
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions {
+        exif_metadata: ExifMetadata {
+            software: Some("dmmt".to_string()),
+            ..Default::default()
+        },
+        xmp_packet: Some(XmpPacket::new("<x:xmpmeta/>".to_string()).unwrap()),
+        ..Default::default()
+    };
+    encode_rgb8(8, 8, &samples, &options, &mut output).unwrap();
+    let application_segments = segment_contents(&output, 0xE1);
+    assert_eq!(application_segments.len(), 2);
+    assert!(application_segments[0].starts_with(b"Exif\0\0"));
+    assert_eq!(
+        application_segments[1],
+        b"http://ns.adobe.com/xap/1.0/\0<x:xmpmeta/>"
+    );
+}
+
+#[test]
+fn test_encode_rgb8_with_comments() {
+    let samples = [128; 8 * 8 * 3];
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions {
+        comments: vec!["first".to_string(), "second".to_string()],
+        embed_settings: true,
+        ..Default::default()
+    };
+    encode_rgb8(8, 8, &samples, &options, &mut output).expect("Encoding failed");
+    let settings = options.settings_json();
+    assert_eq!(
+        segment_contents(&output, 0xFE),
+        [&b"first"[..], b"second", settings.as_bytes()]
+    );
+}
+
+#[test]
+fn test_encode_rgb8_with_too_long_comment() {
+    let samples = [128; 8 * 8 * 3];
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions {
+        comments: vec!["x".repeat(u16::MAX as usize)],
+        ..Default::default()
+    };
+    let result = encode_rgb8(8, 8, &samples, &options, &mut output);
+    assert!(matches!(result, Err(Error::CommentTooLong(65535))));
+    assert!(output.is_empty());
+}
+
+#[test]
+fn test_encode_rgb8_default_matches_explicit_options() {
+    let samples: Vec<u8> = (0..19 * 11 * 3).map(|i| (i % 256) as u8).collect();
+    let mut expected = Vec::new();
+    let options = JpegTransformationOptions {
+        quality: Some(DEFAULT_QUALITY),
+        ..Default::default()
+    };
+    encode_rgb8(19, 11, &samples, &options, &mut expected).expect("Encoding failed");
+    let output = encode_rgb8_default(19, 11, &samples).expect("Encoding failed");
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_encode_rgb8_with_thumbnail() {
+    // Left half red, right half blue
+    let samples: Vec<u8> = (0..16 * 8)
+        .flat_map(|index| {
+            if index % 16 < 8 {
+                [255, 0, 0]
+            } else {
+                [0, 0, 255]
+            }
+        })
+        .collect();
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions {
+        thumbnail_size: Some(ThumbnailSize::new(2, 1).unwrap()),
+        ..Default::default()
+    };
+    encode_rgb8(16, 8, &samples, &options, &mut output).expect("Encoding failed");
+    let jfif = segment_contents(&output, 0xE0);
+    assert_eq!(jfif.len(), 1);
+    assert_eq!(jfif[0][12..], [2, 1, 255, 0, 0, 0, 0, 255]);
+}
+
+#[test]
+fn test_encode_rgb8_with_twelve_bit_precision() {
+    let samples: Vec<u8> = (0..19 * 11 * 3).map(|i| (i % 256) as u8).collect();
+    for high_precision in [false, true] {
+        let mut output = Vec::new();
+        let options = JpegTransformationOptions {
+            bits_per_channel: 12,
+            high_precision,
+            ..Default::default()
+        };
+        encode_rgb8(19, 11, &samples, &options, &mut output).expect("Encoding failed");
+        assert!(segment_contents(&output, 0xC0).is_empty());
+        let frame = segment_contents(&output, 0xC1);
+        assert_eq!(frame.len(), 1);
+        assert_eq!(frame[0][0], 12);
+        for table in segment_contents(&output, 0xDB) {
+            assert_eq!(table.len(), 1 + 128);
+            assert_eq!(table[0] >> 4, 1);
+        }
+    }
+}
+
+#[test]
+fn test_encode_rgb8_with_unsupported_precision() {
+    let samples = [128; 8 * 8 * 3];
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions {
+        bits_per_channel: 16,
+        ..Default::default()
+    };
+    let result = encode_rgb8(8, 8, &samples, &options, &mut output);
+    assert!(matches!(result, Err(Error::UnsupportedSamplePrecision(16))));
+}
+
+#[test]
+fn test_encode_rgb8_with_standard_huffman_tables() {
+    let samples: Vec<u8> = (0..19 * 11 * 3).map(|i| (i % 256) as u8).collect();
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions {
+        huffman_table_mode: HuffmanTableMode::Standard,
+        ..Default::default()
+    };
+    let warnings = encode_rgb8(19, 11, &samples, &options, &mut output).expect("Encoding failed");
+    assert!(!warnings
+        .iter()
+        .any(|warning| matches!(warning, Warning::HuffmanTableModeReplaced { .. })));
+    let tables = segment_contents(&output, 0xC4);
+    assert_eq!(tables.len(), 4);
+    let luma_dc_table = tables.iter().find(|table| table[0] == 0x00).unwrap();
+    let table = ANNEX_K_LUMINANCE_DC_HUFFMAN_TABLE;
+    assert_eq!(luma_dc_table[1..17], table.code_length_counts);
+    assert_eq!(luma_dc_table[17..], *table.symbols);
+}
+
+#[test]
+fn test_encode_rgb8_replaces_standard_huffman_tables_of_twelve_bit_samples() {
+    let samples = [128; 8 * 8 * 3];
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions {
+        bits_per_channel: 12,
+        huffman_table_mode: HuffmanTableMode::Standard,
+        ..Default::default()
+    };
+    let warnings = encode_rgb8(8, 8, &samples, &options, &mut output).expect("Encoding failed");
+    let expected = Warning::HuffmanTableModeReplaced {
+        requested: HuffmanTableMode::Standard,
+        used: HuffmanTableMode::Optimized,
+    };
+    assert!(warnings.iter().any(|warning| *warning == expected));
+}
+
+#[test]
+fn test_encode_rgb8_with_max_huffman_code_length() {
+    // noise yields many symbols of different frequencies
+    let samples: Vec<u8> = (0..64 * 64 * 3)
+        .map(|i: usize| (i * 7919 % 251 + i * i % 13) as u8)
+        .collect();
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions {
+        max_huffman_code_length: 9,
+        huffman_table_mode: HuffmanTableMode::Standard,
+        ..Default::default()
+    };
+    let warnings = encode_rgb8(64, 64, &samples, &options, &mut output).expect("Encoding failed");
+    let expected = Warning::HuffmanTableModeReplaced {
+        requested: HuffmanTableMode::Standard,
+        used: HuffmanTableMode::Optimized,
+    };
+    assert!(warnings.iter().any(|warning| *warning == expected));
+    for table in segment_contents(&output, 0xC4) {
+        assert!(
+            table[10..17].iter().all(|&count| count == 0),
+            "Code words longer than 9 bits in {:?}",
+            &table[1..17]
+        );
+    }
+    let decoded = JPEGImageReader::new(&output[..]).read_image().unwrap();
+    assert_eq!(decoded.to_rgb8().len(), samples.len());
+
+    for max_huffman_code_length in [7, 17] {
+        let options = JpegTransformationOptions {
+            max_huffman_code_length,
+            ..Default::default()
+        };
+        let result = encode_rgb8(8, 8, &[128; 8 * 8 * 3], &options, &mut Vec::new());
+        assert!(matches!(
+            result,
+            Err(Error::InvalidMaxHuffmanCodeLength(length)) if length == max_huffman_code_length
+        ));
+    }
+}
+
+#[test]
+fn test_encode_rgb8_with_separate_chroma_red_table() {
+    let samples: Vec<u8> = (0..19 * 11 * 3).map(|i| (i % 256) as u8).collect();
+    let mut output = Vec::new();
+    let options = JpegTransformationOptions {
+        chroma_red_quantization_table: Some([32; 64]),
+        ..Default::default()
+    };
+    encode_rgb8(19, 11, &samples, &options, &mut output).expect("Encoding failed");
+    let tables = segment_contents(&output, 0xDB);
+    let table_ids: Vec<u8> = tables.iter().map(|table| table[0]).collect();
+    assert_eq!(table_ids, [0, 1, 2]);
+    assert!(tables[2][1..].iter().all(|&step| step == 32));
+    let frame = &segment_contents(&output, 0xC0)[0];
+    assert_eq!(frame[frame.len() - 1], 2, "Cr must select the third table");
+    let decoded = JPEGImageReader::new(&output[..]).read_image().unwrap();
+    assert_eq!(decoded.to_rgb8().len(), samples.len());
+}
+
+#[test]
+fn test_encode_rgb8_with_combined_table_segments() {
+    let samples: Vec<u8> = (0..19 * 11 * 3).map(|i| (i % 256) as u8).collect();
+    let mut separate = Vec::new();
+    let options = JpegTransformationOptions::default();
+    encode_rgb8(19, 11, &samples, &options, &mut separate).expect("Encoding failed");
+    let mut combined = Vec::new();
+    let options = JpegTransformationOptions {
+        combine_table_segments: true,
+        ..Default::default()
+    };
+    encode_rgb8(19, 11, &samples, &options, &mut combined).expect("Encoding failed");
+    assert_eq!(segment_contents(&combined, 0xDB).len(), 1);
+    assert_eq!(segment_contents(&combined, 0xC4).len(), 1);
+    // One marker and length less for the second quantization and the last three Huffman tables
+    assert_eq!(separate.len() - combined.len(), 4 * 4);
+    let decoded = JPEGImageReader::new(&combined[..]).read_image().unwrap();
+    let expected = JPEGImageReader::new(&separate[..]).read_image().unwrap();
+    assert_eq!(decoded.to_rgb8(), expected.to_rgb8());
+}
+
+#[test]
+fn test_encode_rgb8_with_trellis_quantization() {
+    // gradient with a fine texture, which leaves many small AC coefficients
+    let samples: Vec<u8> = (0..64 * 64 * 3)
+        .map(|i: usize| {
+            let (x, y, channel) = (i / 3 % 64, i / 3 / 64, i % 3);
+            (x * 2 + y + channel * 40 + (x * y * 7 + channel) % 13) as u8
+        })
+        .collect();
+    let options = JpegTransformationOptions {
+        quality: Some(75),
+        ..Default::default()
+    };
+    let mut rounded = Vec::new();
+    encode_rgb8(64, 64, &samples, &options, &mut rounded).expect("Encoding failed");
+    let options = JpegTransformationOptions {
+        trellis_quantization: true,
+        ..options
+    };
+    let mut trellis = Vec::new();
+    encode_rgb8(64, 64, &samples, &options, &mut trellis).expect("Encoding failed");
+    let mean_error = |output: &[u8]| {
+        let decoded = JPEGImageReader::new(output).read_image().unwrap().to_rgb8();
+        let error: u32 = decoded
+            .iter()
+            .zip(&samples)
+            .map(|(&decoded, &sample)| decoded.abs_diff(sample) as u32)
+            .sum();
+        error as f64 / samples.len() as f64
+    };
+    assert!(trellis.len() < rounded.len());
+    assert!(mean_error(&trellis) < mean_error(&rounded) * 1.1);
+}
+
+#[test]
+fn test_encode_rgb8_with_adaptive_quantization() {
+    // gradient in the left half, noise in the right half
+    let samples: Vec<u8> = (0..64 * 64 * 3)
+        .map(|i: usize| {
+            let (x, y) = (i / 3 % 64, i / 3 / 64);
+            if x < 32 {
+                (x * 2 + y) as u8
+            } else {
+                (i * 37 % 251) as u8
+            }
+        })
+        .collect();
+    let options = JpegTransformationOptions {
+        quality: Some(90),
+        ..Default::default()
+    };
+    let mut uniform = Vec::new();
+    encode_rgb8(64, 64, &samples, &options, &mut uniform).expect("Encoding failed");
+    let options = JpegTransformationOptions {
+        quality: Some(90),
+        adaptive_quantization: Some(2.0),
+        ..Default::default()
+    };
+    let mut adaptive = Vec::new();
+    encode_rgb8(64, 64, &samples, &options, &mut adaptive).expect("Encoding failed");
+    assert!(adaptive.len() < uniform.len());
+    let gradient = |output: &[u8]| -> Vec<u8> {
+        let decoded = JPEGImageReader::new(output).read_image().unwrap().to_rgb8();
+        decoded
+            .chunks_exact(64 * 3)
+            .flat_map(|row| row[..24 * 3].to_vec())
+            .collect()
+    };
+    // only the noise is quantized coarser
+    assert_eq!(gradient(&adaptive), gradient(&uniform));
+}
+
+#[test]
+fn test_encode_rgb8_solid_and_tiny_images() {
+    for (width, height) in [(1, 1), (8, 8), (33, 17)] {
+        for grayscale in [false, true] {
+            let samples: Vec<u8> = [80, 120, 200].repeat(width as usize * height as usize);
+            let mut output = Vec::new();
+            let options = JpegTransformationOptions {
+                grayscale,
+                ..Default::default()
+            };
+            encode_rgb8(width, height, &samples, &options, &mut output).expect("Encoding failed");
+            let decoded = JPEGImageReader::new(&output[..]).read_image().unwrap();
+            assert_eq!(decoded.to_rgb8().len(), samples.len());
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use dmmt_jpeg_core::encode_rgb8;
+use dmmt_jpeg_core::image::{
+    reader::jpeg::JPEGImageReader,
+    subsampling::ChromaSubsamplingPreset,
+    writer::jpeg::{HuffmanTableMode, JpegTransformationOptions, QuantizationTablePreset},
+    ImageReader,
+};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::test_runner::{FailurePersistence, FileFailurePersistence, PersistedSeed};
+
+/// Cargo runs the tests in the package directory, but reports the source files relative to the
+/// workspace root, so the regression file is addressed directly instead of next to the source.
+const REGRESSION_FILE: &str = "tests/round_trip.proptest-regressions";
+
+/// File, new failures are saved to, so `cargo test` leaves the tracked regression file alone.
+/// Seeds worth keeping are copied into [`REGRESSION_FILE`] by hand.
+const NEW_REGRESSION_FILE: &str = concat!(
+    env!("CARGO_TARGET_TMPDIR"),
+    "/round_trip.proptest-regressions"
+);
+
+/// Persistence, which replays the tracked and the new regressions, but saves only to the new.
+#[derive(Debug, Clone, PartialEq)]
+struct TrackedRegressions;
+
+impl FailurePersistence for TrackedRegressions {
+    fn load_persisted_failures2(&self, source_file: Option<&'static str>) -> Vec<PersistedSeed> {
+        let mut seeds =
+            FileFailurePersistence::Direct(REGRESSION_FILE).load_persisted_failures2(source_file);
+        seeds.extend(
+            FileFailurePersistence::Direct(NEW_REGRESSION_FILE)
+                .load_persisted_failures2(source_file),
+        );
+        seeds
+    }
+
+    fn save_persisted_failure2(
+        &mut self,
+        source_file: Option<&'static str>,
+        seed: PersistedSeed,
+        shrunken_value: &dyn std::fmt::Debug,
+    ) {
+        FileFailurePersistence::Direct(NEW_REGRESSION_FILE).save_persisted_failure2(
+            source_file,
+            seed,
+            shrunken_value,
+        );
+    }
+
+    fn box_clone(&self) -> Box<dyn FailurePersistence> {
+        Box::new(self.clone())
+    }
+
+    fn eq(&self, other: &dyn FailurePersistence) -> bool {
+        other.as_any().is::<Self>()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Lower bound of the PSNR of random images. Uniform noise is the worst case for the
+/// quantization, so the bound is far below the values of photographs.
+const MINIMUM_PSNR: f64 = 10.0;
+
+struct RandomImage {
+    width: u16,
+    height: u16,
+    samples: Vec<u8>,
+}
+
+impl std::fmt::Debug for RandomImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RandomImage({}x{})", self.width, self.height)
+    }
+}
+
+/// Random images of up to three by three MCUs, which mix a horizontal gradient with noise.
+fn random_image() -> impl Strategy<Value = RandomImage> {
+    (1..=48_u16, 1..=48_u16, 0..=255_u8)
+        .prop_flat_map(|(width, height, noise)| {
+            let length = width as usize * height as usize * 3;
+            (
+                Just((width, height)),
+                vec(any::<[u8; 3]>(), 2),
+                vec(0..=noise, length),
+            )
+        })
+        .prop_map(|((width, height), corners, noise)| {
+            let samples = noise
+                .iter()
+                .enumerate()
+                .map(|(index, &noise)| {
+                    let channel = index % 3;
+                    let x = index / 3 % width as usize;
+                    let gradient = (corners[0][channel] as usize * (width as usize - x)
+                        + corners[1][channel] as usize * x)
+                        / width as usize;
+                    (gradient as u8).saturating_add(noise / 2)
+                })
+                .collect();
+            RandomImage {
+                width,
+                height,
+                samples,
+            }
+        })
+}
+
+fn encode(
+    image: &RandomImage,
+    subsampling: ChromaSubsamplingPreset,
+    quantization: QuantizationTablePreset,
+) -> Vec<u8> {
+    let options = JpegTransformationOptions {
+        chroma_subsampling_preset: subsampling,
+        quantization_table_preset: quantization,
+        ..Default::default()
+    };
+    encode_with_options(image, &options)
+}
+
+fn encode_with_options(image: &RandomImage, options: &JpegTransformationOptions) -> Vec<u8> {
+    let mut output = Vec::new();
+    encode_rgb8(
+        image.width,
+        image.height,
+        &image.samples,
+        options,
+        &mut output,
+    )
+    .unwrap();
+    output
+}
+
+fn psnr(expected: &[u8], data: &[u8]) -> f64 {
+    let decoded = JPEGImageReader::new(data).read_image().unwrap().to_rgb8();
+    assert_eq!(expected.len(), decoded.len(), "sample count mismatch");
+    let squared_error: f64 = expected
+        .iter()
+        .zip(&decoded)
+        .map(|(&e, &a)| (e as f64 - a as f64).powi(2))
+        .sum();
+    if squared_error == 0.0 {
+        return f64::INFINITY;
+    }
+    10.0 * (255.0_f64.powi(2) * expected.len() as f64 / squared_error).log10()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 32,
+        failure_persistence: Some(Box::new(TrackedRegressions)),
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn random_images_survive_round_trip(image in random_image()) {
+        for &subsampling in ChromaSubsamplingPreset::variants() {
+            for &quantization in QuantizationTablePreset::variants() {
+                let psnr = psnr(&image.samples, &encode(&image, subsampling, quantization));
+                prop_assert!(
+                    psnr >= MINIMUM_PSNR,
+                    "PSNR {:.2} with {:?} and {:?}",
+                    psnr,
+                    subsampling,
+                    quantization
+                );
+            }
+        }
+    }
+
+    /// A low quality scales every quantization step by a factor of ten compared to a high
+    /// quality, so the file must shrink and the error must grow. Close qualities are not
+    /// compared, because the optimized Huffman tables and the rounding make both properties
+    /// noisy for tiny images.
+    #[test]
+    fn higher_quality_grows_size_and_psnr(image in random_image()) {
+        let encode_quality = |quality| {
+            let options = JpegTransformationOptions {
+                chroma_subsampling_preset: ChromaSubsamplingPreset::P444,
+                quality: Some(quality),
+                ..Default::default()
+            };
+            encode_with_options(&image, &options)
+        };
+        let low = encode_quality(10);
+        let high = encode_quality(95);
+        prop_assert!(low.len() <= high.len(), "size {} > {}", low.len(), high.len());
+        let low_psnr = psnr(&image.samples, &low);
+        let high_psnr = psnr(&image.samples, &high);
+        prop_assert!(low_psnr <= high_psnr, "PSNR {:.2} > {:.2}", low_psnr, high_psnr);
+    }
+
+    /// The Huffman tables only change the entropy coding, so both modes decode to the same
+    /// samples.
+    #[test]
+    fn standard_huffman_tables_decode_like_optimized(image in random_image()) {
+        let decode = |huffman_table_mode| {
+            let options = JpegTransformationOptions {
+                huffman_table_mode,
+                ..Default::default()
+            };
+            let data = encode_with_options(&image, &options);
+            JPEGImageReader::new(&data[..]).read_image().unwrap().to_rgb8()
+        };
+        prop_assert_eq!(
+            decode(HuffmanTableMode::Standard),
+            decode(HuffmanTableMode::Optimized)
+        );
+    }
+}
+
+/// Custom rates need MCUs of three or four block rows and their own SOF factors, which the
+/// reader must decode back to the samples of a smooth image.
+#[test]
+fn custom_subsampling_rates_survive_round_trip() {
+    let (width, height) = (50_u16, 37_u16);
+    let samples: Vec<u8> = (0..height as usize)
+        .flat_map(|y| (0..width as usize).map(move |x| [(x * 5) as u8, (y * 6) as u8, 128]))
+        .flatten()
+        .collect();
+    let image = RandomImage {
+        width,
+        height,
+        samples,
+    };
+    for (horizontal_rate, vertical_rate) in [(3, 1), (1, 3), (2, 4), (4, 2), (1, 4)] {
+        let subsampling = ChromaSubsamplingPreset::custom(horizontal_rate, vertical_rate).unwrap();
+        let psnr = psnr(
+            &image.samples,
+            &encode(&image, subsampling, QuantizationTablePreset::Specification),
+        );
+        // 4:2:0 reaches about 30 dB, misplaced blocks would fall far below
+        assert!(psnr >= 25.0, "PSNR {:.2} with {:?}", psnr, subsampling);
+    }
+}
+
+/// Images narrower than a subsampled chroma sample average the padding into their chroma, so
+/// the padding repeats the edge instead of darkening it.
+#[test]
+fn narrow_images_keep_their_colors() {
+    for (width, height) in [(1_u16, 32_u16), (32, 1), (1, 1)] {
+        let samples = [200_u8, 30, 90].repeat(width as usize * height as usize);
+        let image = RandomImage {
+            width,
+            height,
+            samples,
+        };
+        for &subsampling in ChromaSubsamplingPreset::variants() {
+            let psnr = psnr(
+                &image.samples,
+                &encode(&image, subsampling, QuantizationTablePreset::Specification),
+            );
+            assert!(
+                psnr >= 30.0,
+                "PSNR {:.2} of {:?} with {:?}",
+                psnr,
+                image,
+                subsampling
+            );
+        }
+    }
+}
@@ -1,4 +0,0 @@
-#[derive(Debug)]
-pub enum CodingError {
-    DecoderError,
-}